@@ -1,10 +1,38 @@
-use serde::{Serialize, Serializer};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+use tauri::{Invoke, Runtime};
+
+pub mod artwork;
+pub mod backup;
 pub mod binaries;
+pub mod compatibility;
 pub mod config;
 pub mod game;
+pub mod history;
+pub mod hotfixes;
+pub mod journal;
+pub mod kiosk;
+pub mod libraries;
+pub mod local_builds;
 pub mod logging;
+pub mod migration;
+pub mod mod_scripts;
+pub mod news;
+pub mod permissions;
+pub mod post_install;
+pub mod releases;
+pub mod remote_api;
+pub mod reset;
+pub mod safe_mode;
+pub mod snapshots;
 pub mod support;
+pub mod tasks;
+pub mod textures;
+pub mod tools;
+pub mod version_switch;
 pub mod versions;
 pub mod window;
 
@@ -32,13 +60,167 @@ pub enum CommandError {
   BinaryExecution(String),
   #[error("{0}")]
   Support(String),
+  #[error("{0}")]
+  MeteredConnection(String),
+  #[error("{0}")]
+  Locked(String),
+  #[error("{0}")]
+  SafeMode(String),
+  #[error("{0}")]
+  RateLimited(String),
+  #[error("{0}")]
+  PermissionDenied(String),
+  #[error("{0}")]
+  UntrustedHost(String),
+  #[error("{0}")]
+  UnsignedBinaries(String),
+  #[error("{0}")]
+  RemoteApi(String),
 }
 
+impl CommandError {
+  /// A stable identifier for the kind of error, for the frontend to match on
+  pub fn code(&self) -> &'static str {
+    match self {
+      CommandError::IO(_) => "io",
+      CommandError::NetworkRequest(_) => "networkRequest",
+      CommandError::Configuration(_) => "configuration",
+      CommandError::TauriEvent(_) => "tauriEvent",
+      CommandError::Installation(_) => "installation",
+      CommandError::VersionManagement(_) => "versionManagement",
+      CommandError::GameManagement(_) => "gameManagement",
+      CommandError::OSOperation(_) => "osOperation",
+      CommandError::WindowManagement(_) => "windowManagement",
+      CommandError::BinaryExecution(_) => "binaryExecution",
+      CommandError::Support(_) => "support",
+      CommandError::MeteredConnection(_) => "meteredConnection",
+      CommandError::Locked(_) => "locked",
+      CommandError::SafeMode(_) => "safeMode",
+      CommandError::RateLimited(_) => "rateLimited",
+      CommandError::PermissionDenied(_) => "permissionDenied",
+      CommandError::UntrustedHost(_) => "untrustedHost",
+      CommandError::UnsignedBinaries(_) => "unsignedBinaries",
+      CommandError::RemoteApi(_) => "remoteApi",
+    }
+  }
+
+  pub fn message_key(&self) -> String {
+    format!("error_{}", self.code())
+  }
+}
+
+// Errors are sent to the frontend as `{ code, message, detail }`
+// - `message` is localized according to the configured locale
+// - `detail` is the specific (english) error, which is what ends up in the logs
 impl Serialize for CommandError {
   fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
   where
     S: Serializer,
   {
-    serializer.serialize_str(self.to_string().as_ref())
+    let detail = self.to_string();
+    let message = crate::i18n::message(&self.message_key()).unwrap_or(detail.clone());
+    let mut state = serializer.serialize_struct("CommandError", 3)?;
+    state.serialize_field("code", self.code())?;
+    state.serialize_field("message", &message)?;
+    state.serialize_field("detail", &detail)?;
+    state.end()
+  }
+}
+
+// Expensive commands (probing the GPU, collecting diagnostics), and how often they can be run
+// per minute
+const RATE_LIMITS: &[(&str, u32)] = &[
+  ("check_shared_library_requirements", 10),
+  ("generate_support_package", 3),
+  ("get_data_usage_breakdown", 5),
+  ("is_avx_requirement_met", 10),
+  ("is_opengl_requirement_met", 10),
+  ("run_self_test", 3),
+];
+// How the commands that read state are named
+pub const READ_ONLY_PREFIXES: &[&str] = &["get_", "has_", "is_", "list_"];
+// Getters are cheap, but one called from a frontend stuck in a render loop still keeps the
+// machine awake, so bursts beyond this are held back
+const GETTER_LIMIT: (u32, Duration) = (20, Duration::from_secs(1));
+
+struct CallWindow {
+  started: Instant,
+  calls: u32,
+  logged: bool,
+}
+
+fn limit_for(command: &str) -> Option<(u32, Duration)> {
+  if let Some((_, calls)) = RATE_LIMITS.iter().find(|(name, _)| *name == command) {
+    return Some((*calls, Duration::from_secs(60)));
+  }
+  READ_ONLY_PREFIXES
+    .iter()
+    .any(|prefix| command.starts_with(prefix))
+    .then(|| GETTER_LIMIT)
+}
+
+/// Wraps the invoke handler, holding calls to a command beyond its limit for the current window
+/// back until the next one.  Only once the next window is full as well are calls rejected, so a
+/// retry from the UI goes through (late) while a loop calling it still can't keep up
+pub fn rate_limit<R: Runtime>(
+  handler: impl Fn(Invoke<R>) + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) + Send + Sync + 'static {
+  let handler = Arc::new(handler);
+  let windows: std::sync::Mutex<HashMap<String, CallWindow>> = Default::default();
+  move |invoke| {
+    let command = invoke.message.command().to_string();
+    if let Some((max_calls, window)) = limit_for(&command) {
+      let mut windows = windows.lock().unwrap_or_else(|err| err.into_inner());
+      let entry = windows.entry(command.clone()).or_insert(CallWindow {
+        started: Instant::now(),
+        calls: 0,
+        logged: false,
+      });
+      if entry.started.elapsed() >= window * 2 {
+        *entry = CallWindow {
+          started: Instant::now(),
+          calls: 0,
+          logged: false,
+        };
+      } else if entry.started.elapsed() >= window {
+        // The calls held back for this window have already taken their share of it
+        *entry = CallWindow {
+          started: entry.started + window,
+          calls: entry.calls.saturating_sub(max_calls),
+          logged: false,
+        };
+      }
+      entry.calls += 1;
+      if entry.calls > max_calls && entry.calls <= max_calls * 2 {
+        let delay = (entry.started + window).saturating_duration_since(Instant::now());
+        drop(windows);
+        log::debug!("Holding '{}' back for {:?}", command, delay);
+        let handler = handler.clone();
+        tauri::async_runtime::spawn(async move {
+          tokio::time::sleep(delay).await;
+          handler(invoke)
+        });
+        return;
+      }
+      if entry.calls > max_calls {
+        // Once per window, a loop hitting the limit would otherwise fill the log just the same
+        if !entry.logged {
+          log::warn!(
+            "Rate limiting '{}', called more than {} times in {:?}",
+            command,
+            max_calls,
+            window
+          );
+          entry.logged = true;
+        }
+        drop(windows);
+        invoke.resolver.reject(CommandError::RateLimited(format!(
+          "'{}' was called too often, try again in a moment",
+          command
+        )));
+        return;
+      }
+    }
+    handler(invoke)
   }
 }
@@ -0,0 +1,245 @@
+// Artwork shown for each game (and installed mod) in the library
+//
+// Every game has a grid, hero and logo image.  For the games themselves these are downloaded once
+// and cached, mods ship theirs in an `artwork` folder next to their files.  Either can be
+// overridden by the user, custom artwork is kept in the install dir so that it survives the
+// launcher's cache being cleared:
+//
+// <install dir>/artwork/<game>/<kind>.<ext>
+// <install dir>/artwork/<game>/mods/<mod>/<kind>.<ext>
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  config::LauncherConfig,
+  util::file::{create_dir, delete_file},
+};
+
+use super::{game::validate_game_name, mod_scripts::mod_dir, CommandError};
+
+const ARTWORK_URL: &str = "https://raw.githubusercontent.com/open-goal/launcher/main/artwork";
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp"];
+// Not every game has every kind of artwork, a failed download isn't retried for this long
+const FAILED_LOOKUP_RETRY: std::time::Duration = std::time::Duration::from_secs(6 * 60 * 60);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ArtworkKind {
+  Grid,
+  Hero,
+  Logo,
+}
+
+impl ArtworkKind {
+  const ALL: [ArtworkKind; 3] = [ArtworkKind::Grid, ArtworkKind::Hero, ArtworkKind::Logo];
+
+  fn file_stem(&self) -> &'static str {
+    match self {
+      ArtworkKind::Grid => "grid",
+      ArtworkKind::Hero => "hero",
+      ArtworkKind::Logo => "logo",
+    }
+  }
+}
+
+#[derive(Debug, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ArtworkImage {
+  pub path: String,
+  pub custom: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct GameArtwork {
+  pub grid: Option<ArtworkImage>,
+  pub hero: Option<ArtworkImage>,
+  pub logo: Option<ArtworkImage>,
+}
+
+fn custom_artwork_dir(
+  config: &LauncherConfig,
+  game_name: &str,
+  mod_name: &Option<String>,
+) -> Result<PathBuf, CommandError> {
+  let game_data_path = config.game_data_path().ok_or_else(|| {
+    CommandError::GameManagement("No installation directory is configured".to_string())
+  })?;
+  let dir = game_data_path.join("artwork").join(game_name);
+  Ok(match mod_name {
+    Some(mod_name) => dir.join("mods").join(mod_name),
+    None => dir,
+  })
+}
+
+fn find_image(dir: &Path, kind: ArtworkKind) -> Option<PathBuf> {
+  IMAGE_EXTENSIONS
+    .iter()
+    .map(|ext| dir.join(format!("{}.{}", kind.file_stem(), ext)))
+    .find(|path| path.is_file())
+}
+
+async fn cached_game_image(
+  app_handle: &tauri::AppHandle,
+  game_name: &str,
+  kind: ArtworkKind,
+) -> Option<PathBuf> {
+  let cache_dir = app_handle
+    .path_resolver()
+    .app_cache_dir()?
+    .join("artwork")
+    .join(game_name);
+  if let Some(path) = find_image(&cache_dir, kind) {
+    return Some(path);
+  }
+  // An empty marker is left behind when the download fails
+  let failed_marker = cache_dir.join(format!("{}.failed", kind.file_stem()));
+  let failed_recently = std::fs::metadata(&failed_marker)
+    .and_then(|metadata| metadata.modified())
+    .ok()
+    .and_then(|failed_at| failed_at.elapsed().ok())
+    .map_or(false, |elapsed| elapsed < FAILED_LOOKUP_RETRY);
+  if failed_recently {
+    return None;
+  }
+  let file_name = format!("{}.png", kind.file_stem());
+  let url = format!("{}/{}/{}", ARTWORK_URL, game_name, file_name);
+  let download = async {
+    let bytes = reqwest::get(&url)
+      .await?
+      .error_for_status()?
+      .bytes()
+      .await?;
+    Ok::<_, CommandError>(bytes)
+  };
+  let bytes = match download.await {
+    Ok(bytes) => bytes,
+    Err(err) => {
+      log::warn!("Unable to download artwork {}: {}", url, err);
+      if let Err(err) = create_dir(&cache_dir).and_then(|_| std::fs::write(&failed_marker, "")) {
+        log::warn!("Unable to remember the failed download: {}", err);
+      }
+      return None;
+    }
+  };
+  let _ = delete_file(&failed_marker);
+  let path = cache_dir.join(file_name);
+  // Written aside first, so an interrupted write isn't mistaken for a cached image
+  let tmp_path = path.with_extension("png.tmp");
+  let written = create_dir(&cache_dir)
+    .and_then(|_| std::fs::write(&tmp_path, &bytes))
+    .and_then(|_| std::fs::rename(&tmp_path, &path));
+  match written {
+    Ok(()) => Some(path),
+    Err(err) => {
+      log::warn!("Unable to cache artwork {}: {}", path.display(), err);
+      None
+    }
+  }
+}
+
+/// The artwork for a game, or one of its mods, downloading anything that isn't cached yet
+#[tauri::command]
+#[specta::specta]
+pub async fn get_game_artwork(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  app_handle: tauri::AppHandle,
+  game_name: String,
+  mod_name: Option<String>,
+) -> Result<GameArtwork, CommandError> {
+  validate_game_name(&game_name)?;
+  let config_lock = config.lock().await;
+  let custom_dir = custom_artwork_dir(&config_lock, &game_name, &mod_name)?;
+  let bundled_dir = match &mod_name {
+    Some(mod_name) => Some(mod_dir(&config_lock, &game_name, mod_name)?.join("artwork")),
+    None => None,
+  };
+  drop(config_lock);
+
+  let mut images = vec![];
+  for kind in ArtworkKind::ALL {
+    let image = if let Some(path) = find_image(&custom_dir, kind) {
+      Some(ArtworkImage {
+        path: path.to_string_lossy().into_owned(),
+        custom: true,
+      })
+    } else {
+      let path = match &bundled_dir {
+        Some(bundled_dir) => find_image(bundled_dir, kind),
+        None => cached_game_image(&app_handle, &game_name, kind).await,
+      };
+      path.map(|path| ArtworkImage {
+        path: path.to_string_lossy().into_owned(),
+        custom: false,
+      })
+    };
+    images.push(image);
+  }
+  let mut images = images.into_iter();
+  Ok(GameArtwork {
+    grid: images.next().flatten(),
+    hero: images.next().flatten(),
+    logo: images.next().flatten(),
+  })
+}
+
+/// Copies `image_path` into the install dir as the custom artwork of the given kind
+#[tauri::command]
+#[specta::specta]
+pub async fn set_custom_artwork(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  game_name: String,
+  mod_name: Option<String>,
+  kind: ArtworkKind,
+  image_path: String,
+) -> Result<(), CommandError> {
+  validate_game_name(&game_name)?;
+  let config_lock = config.lock().await;
+  if let Some(mod_name) = &mod_name {
+    mod_dir(&config_lock, &game_name, mod_name)?;
+  }
+  let custom_dir = custom_artwork_dir(&config_lock, &game_name, &mod_name)?;
+  let image_path = PathBuf::from(image_path);
+  let ext = image_path
+    .extension()
+    .map(|ext| ext.to_string_lossy().to_lowercase())
+    .filter(|ext| IMAGE_EXTENSIONS.contains(&ext.as_str()))
+    .ok_or_else(|| {
+      CommandError::GameManagement(format!(
+        "Artwork has to be one of: {}",
+        IMAGE_EXTENSIONS.join(", ")
+      ))
+    })?;
+  create_dir(&custom_dir)?;
+  // Only one override per kind, or which one is used would depend on the extension
+  if let Some(previous) = find_image(&custom_dir, kind) {
+    delete_file(&previous)?;
+  }
+  std::fs::copy(
+    &image_path,
+    custom_dir.join(format!("{}.{}", kind.file_stem(), ext)),
+  )?;
+  Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn clear_custom_artwork(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  game_name: String,
+  mod_name: Option<String>,
+  kind: ArtworkKind,
+) -> Result<(), CommandError> {
+  validate_game_name(&game_name)?;
+  let config_lock = config.lock().await;
+  if let Some(mod_name) = &mod_name {
+    mod_dir(&config_lock, &game_name, mod_name)?;
+  }
+  let custom_dir = custom_artwork_dir(&config_lock, &game_name, &mod_name)?;
+  if let Some(path) = find_image(&custom_dir, kind) {
+    delete_file(&path)?;
+  }
+  Ok(())
+}
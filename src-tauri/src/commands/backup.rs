@@ -0,0 +1,474 @@
+// Backing up everything that's the user's own, for moving to a new machine or reinstalling the OS
+//
+// A backup is a zip of the launcher's settings and state, each game's settings and saves, and
+// the metadata of installed mods, with a `backup.json` manifest saying what wrote it.  What can
+// be downloaded again (tooling versions, game data, the mods themselves, caches) and logs are
+// left out, so a backup stays small.
+//
+// Secrets are redacted from the settings that are backed up, and restoring keeps this machine's
+// own secrets and locations (see `SECRET_SETTINGS` and `MACHINE_SETTINGS`) in place of whatever
+// the backup has for them.  A backup is extracted to a staging folder first and copied into place
+// from there, so one that can't be read doesn't leave a half-restored launcher behind.  The
+// staging folder is a temporary one, it can't be inside any of the folders it's copied to.
+//
+// Backups record the schema they were written with, one from a newer launcher isn't restored as
+// it may have things this one doesn't know how to put back.
+
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+use walkdir::WalkDir;
+use zip::write::FileOptions;
+
+use crate::{
+  config::{keep_settings, redact_settings, LauncherConfig, MACHINE_SETTINGS, SECRET_SETTINGS},
+  paths::{PathResolver, GAMES},
+  tasks::CancellationToken,
+  util::{
+    crypto::random_hex,
+    file::{copy_dir_with_progress, delete_dir, CopyVerification},
+  },
+};
+
+use super::{
+  remote_api::apply_remote_api_config, versions::update_active_version_link, CommandError,
+};
+
+const BACKUP_SCHEMA_VERSION: u32 = 1;
+const MANIFEST_NAME: &str = "backup.json";
+// The parts of a game's user directory that are worth keeping, screenshots can be large
+const GAME_USER_DIRS: &[&str] = &["settings", "saves", "misc"];
+// Fetched again on startup
+const EXCLUDED_LAUNCHER_FILES: &[&str] = &["remote-config.json", "remote-config.json.minisig"];
+const SETTINGS_NAME: &str = "settings.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupManifest {
+  pub schema_version: u32,
+  pub created_at: String,
+  pub launcher_version: String,
+  pub files: u32,
+  pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupRestore {
+  pub manifest: BackupManifest,
+  pub files_restored: u32,
+  /// Entries that weren't restored, ie. mod metadata with no installation directory to go to
+  pub skipped: Vec<String>,
+}
+
+// Where each part of a backup comes from, and is restored to
+struct BackupRoots {
+  launcher: PathBuf,
+  games: PathBuf,
+  mods: Option<PathBuf>,
+}
+
+fn backup_roots(
+  config: &LauncherConfig,
+  app_handle: &tauri::AppHandle,
+) -> Result<BackupRoots, CommandError> {
+  let paths = app_handle.state::<PathResolver>();
+  let launcher = paths.launcher_dir().ok_or_else(|| {
+    CommandError::Configuration("Couldn't determine launcher config directory".to_string())
+  })?;
+  let games = paths.games_user_dir().ok_or_else(|| {
+    CommandError::Configuration("Couldn't determine application config directory".to_string())
+  })?;
+  Ok(BackupRoots {
+    launcher,
+    games,
+    mods: paths.mods_dir(config),
+  })
+}
+
+// Every file to back up, along with its name in the backup
+fn backup_files(roots: &BackupRoots) -> Vec<(PathBuf, String)> {
+  let mut files = vec![];
+  // Only the launcher's own files, its folders are logs and caches
+  for entry in std::fs::read_dir(&roots.launcher)
+    .into_iter()
+    .flatten()
+    .filter_map(|entry| entry.ok())
+  {
+    let name = entry.file_name().to_string_lossy().into_owned();
+    if entry.path().is_file() && !EXCLUDED_LAUNCHER_FILES.contains(&name.as_str()) {
+      files.push((entry.path(), format!("launcher/{}", name)));
+    }
+  }
+  for game in GAMES {
+    for dir in GAME_USER_DIRS {
+      let root = roots.games.join(game).join(dir);
+      for entry in WalkDir::new(&root)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+      {
+        if let Ok(relative) = entry.path().strip_prefix(&roots.games) {
+          files.push((
+            entry.path().to_path_buf(),
+            format!("games/{}", backup_name(relative)),
+          ));
+        }
+      }
+    }
+  }
+  // A mod's metadata is what's at the top of its folder, the rest is its data
+  if let Some(mods) = &roots.mods {
+    for entry in WalkDir::new(mods)
+      .min_depth(3)
+      .max_depth(3)
+      .into_iter()
+      .filter_map(|entry| entry.ok())
+      .filter(|entry| entry.file_type().is_file())
+      .filter(|entry| entry.path().extension().map_or(false, |ext| ext == "json"))
+    {
+      if let Ok(relative) = entry.path().strip_prefix(mods) {
+        files.push((
+          entry.path().to_path_buf(),
+          format!("mods/{}", backup_name(relative)),
+        ));
+      }
+    }
+  }
+  files
+}
+
+// The settings without their secrets, settings that can't be parsed are backed up as they are
+fn redacted_settings(content: Vec<u8>) -> Vec<u8> {
+  match serde_json::from_slice::<serde_json::Value>(&content) {
+    Ok(mut settings) => {
+      redact_settings(&mut settings, SECRET_SETTINGS);
+      serde_json::to_vec_pretty(&settings).unwrap_or(content)
+    }
+    Err(_) => content,
+  }
+}
+
+// The restored settings, with this machine's secrets and locations kept
+fn merge_restored_settings(restored: &Path, current: &Path) -> Result<(), CommandError> {
+  let mut settings: serde_json::Value = serde_json::from_slice(&std::fs::read(restored)?)
+    .map_err(|_| CommandError::Support("The backup's settings are unreadable".to_string()))?;
+  let current: serde_json::Value = std::fs::read(current)
+    .ok()
+    .and_then(|content| serde_json::from_slice(&content).ok())
+    .unwrap_or_default();
+  keep_settings(&mut settings, &current, SECRET_SETTINGS);
+  keep_settings(&mut settings, &current, MACHINE_SETTINGS);
+  let content = serde_json::to_vec_pretty(&settings)
+    .map_err(|_| CommandError::Support("Unable to write the restored settings".to_string()))?;
+  std::fs::write(restored, content)?;
+  Ok(())
+}
+
+// Names in the backup always use `/`, whatever the platform
+fn backup_name(relative: &Path) -> String {
+  relative
+    .components()
+    .map(|component| component.as_os_str().to_string_lossy())
+    .collect::<Vec<_>>()
+    .join("/")
+}
+
+// Writes `files` to a zip at `dest`, counting them in `manifest` which is written last
+fn write_backup(
+  files: &[(PathBuf, String)],
+  dest: &Path,
+  manifest: &mut BackupManifest,
+) -> Result<(), CommandError> {
+  let mut zip_file = zip::ZipWriter::new(std::fs::File::create(dest)?);
+  let options = FileOptions::default().compression_method(zip::CompressionMethod::DEFLATE);
+  for (path, name) in files {
+    let content = match std::fs::read(path) {
+      Ok(content) if name == &format!("launcher/{}", SETTINGS_NAME) => redacted_settings(content),
+      Ok(content) => content,
+      Err(err) => {
+        log::warn!("Unable to back up {}: {}", path.display(), err);
+        continue;
+      }
+    };
+    zip_file
+      .start_file(name.as_str(), options)
+      .and_then(|_| Ok(zip_file.write_all(&content)?))
+      .map_err(|err| CommandError::Support(format!("Unable to write backup: {}", err)))?;
+    manifest.files += 1;
+    manifest.bytes += content.len() as u64;
+  }
+  // Written last so it has the totals, it's looked up by name when restoring
+  let manifest_content = serde_json::to_string_pretty(&manifest)
+    .map_err(|_| CommandError::Support("Unable to write backup manifest".to_string()))?;
+  zip_file
+    .start_file(MANIFEST_NAME, options)
+    .and_then(|_| Ok(zip_file.write_all(manifest_content.as_bytes())?))
+    .and_then(|_| zip_file.finish().map(|_| ()))
+    .map_err(|err| CommandError::Support(format!("Unable to write backup: {}", err)))
+}
+
+/// Writes a backup of the launcher's state to `dest`
+#[tauri::command]
+#[specta::specta]
+pub async fn create_full_backup(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  app_handle: tauri::AppHandle,
+  dest: String,
+) -> Result<BackupManifest, CommandError> {
+  let config_lock = config.lock().await;
+  let roots = backup_roots(&config_lock, &app_handle)?;
+  drop(config_lock);
+  let files = backup_files(&roots);
+  let mut manifest = BackupManifest {
+    schema_version: BACKUP_SCHEMA_VERSION,
+    created_at: chrono::Local::now().to_rfc3339(),
+    launcher_version: app_handle.package_info().version.to_string(),
+    files: 0,
+    bytes: 0,
+  };
+
+  let dest = PathBuf::from(dest);
+  if let Err(err) = write_backup(&files, &dest, &mut manifest) {
+    let _ = std::fs::remove_file(&dest);
+    return Err(err);
+  }
+  log::info!(
+    "Backed up {} files ({} bytes) to {}",
+    manifest.files,
+    manifest.bytes,
+    dest.display()
+  );
+  Ok(manifest)
+}
+
+// A backup entry name split into its parts, `None` if it could point outside where it's restored
+fn safe_components(name: &str) -> Option<Vec<&str>> {
+  let parts: Vec<&str> = name.split('/').collect();
+  let is_safe = parts.iter().all(|part| {
+    let mut components = Path::new(part).components();
+    matches!(
+      (components.next(), components.next()),
+      (Some(Component::Normal(_)), None)
+    )
+  });
+  is_safe.then(|| parts)
+}
+
+// Extracts what the backup holds into `staging_dir`, laid out as `launcher`, `games` and `mods`.
+// Returns how many files there were, and the entries that couldn't be restored
+fn extract_backup(
+  archive: &mut zip::ZipArchive<std::fs::File>,
+  staging_dir: &Path,
+  has_mods_dir: bool,
+) -> Result<(u32, Vec<String>), CommandError> {
+  let mut files = 0;
+  let mut skipped = vec![];
+  let names: Vec<String> = archive.file_names().map(|name| name.to_string()).collect();
+  for name in names {
+    if name == MANIFEST_NAME {
+      continue;
+    }
+    let target = match safe_components(&name).as_deref() {
+      Some([root @ ("launcher" | "games"), rest @ ..]) if !rest.is_empty() => Some((root, rest)),
+      Some([root @ "mods", rest @ ..]) if !rest.is_empty() && has_mods_dir => Some((root, rest)),
+      _ => None,
+    }
+    .map(|(root, rest)| rest.iter().fold(staging_dir.join(root), |p, c| p.join(c)));
+    let target = match target {
+      Some(target) => target,
+      None => {
+        log::warn!("Not restoring backup entry {}", name);
+        skipped.push(name);
+        continue;
+      }
+    };
+    let mut content = vec![];
+    archive
+      .by_name(&name)
+      .map_err(|err| CommandError::Support(format!("Unable to read backup: {}", err)))?
+      .read_to_end(&mut content)?;
+    if let Some(parent) = target.parent() {
+      std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&target, content)?;
+    files += 1;
+  }
+  Ok((files, skipped))
+}
+
+// Copies each part of an extracted backup to where it belongs
+fn restore_staged(
+  staging_dir: &Path,
+  roots: &BackupRoots,
+  token: &CancellationToken,
+) -> Result<(), CommandError> {
+  let launcher_settings = roots.launcher.join(SETTINGS_NAME);
+  let staged_settings = staging_dir.join("launcher").join(SETTINGS_NAME);
+  if staged_settings.is_file() {
+    merge_restored_settings(&staged_settings, &launcher_settings)?;
+  }
+  let parts = [
+    ("launcher", Some(&roots.launcher)),
+    ("games", Some(&roots.games)),
+    ("mods", roots.mods.as_ref()),
+  ];
+  for (part, root) in parts {
+    let (src, root) = match root {
+      Some(root) if staging_dir.join(part).is_dir() => (staging_dir.join(part), root),
+      _ => continue,
+    };
+    // Saves are worth reading back in full
+    copy_dir_with_progress(&src, root, CopyVerification::Hash, token, |_| {}).map_err(|err| {
+      CommandError::Support(format!("Unable to restore the backup's {}: {}", part, err))
+    })?;
+  }
+  Ok(())
+}
+
+// Extracts the backup to `staging_dir` and copies it into place from there, the staging folder is
+// removed again either way
+fn restore_backup(
+  archive: &mut zip::ZipArchive<std::fs::File>,
+  roots: &BackupRoots,
+  staging_dir: &Path,
+) -> Result<(u32, Vec<String>), CommandError> {
+  delete_dir(&staging_dir.to_path_buf())?;
+  let result = extract_backup(archive, staging_dir, roots.mods.is_some()).and_then(|extracted| {
+    restore_staged(staging_dir, roots, &CancellationToken::default())?;
+    Ok(extracted)
+  });
+  if let Err(err) = delete_dir(&staging_dir.to_path_buf()) {
+    log::warn!("Unable to remove {}: {}", staging_dir.display(), err);
+  }
+  result
+}
+
+/// Puts back what a backup from `create_full_backup` holds, replacing the current settings and
+/// any saves with the same name.  The settings are reloaded afterwards, keeping this machine's
+/// secrets and locations
+#[tauri::command]
+#[specta::specta]
+pub async fn restore_full_backup(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  app_handle: tauri::AppHandle,
+  path: String,
+) -> Result<BackupRestore, CommandError> {
+  let mut archive = std::fs::File::open(&path)
+    .map_err(CommandError::from)
+    .and_then(|file| {
+      zip::ZipArchive::new(file)
+        .map_err(|_| CommandError::Support(format!("'{}' is not a launcher backup", path)))
+    })?;
+  let manifest: BackupManifest = {
+    let mut content = String::new();
+    archive
+      .by_name(MANIFEST_NAME)
+      .map_err(|_| CommandError::Support(format!("'{}' is not a launcher backup", path)))?
+      .read_to_string(&mut content)?;
+    serde_json::from_str(&content)
+      .map_err(|_| CommandError::Support("The backup's manifest is unreadable".to_string()))?
+  };
+  if manifest.schema_version > BACKUP_SCHEMA_VERSION {
+    return Err(CommandError::Support(format!(
+      "This backup was made by a newer launcher ({}), update the launcher to restore it",
+      manifest.launcher_version
+    )));
+  }
+
+  // The locations are this machine's, so where everything goes doesn't change with the settings
+  let roots = backup_roots(&*config.lock().await, &app_handle)?;
+  let staging_dir =
+    std::env::temp_dir().join(format!("opengoal-launcher-restore-{}", random_hex(8)));
+  let (files_restored, skipped) =
+    tokio::task::spawn_blocking(move || restore_backup(&mut archive, &roots, &staging_dir))
+      .await
+      .map_err(|_| CommandError::Support("Unable to restore the backup".to_string()))??;
+  let restore = BackupRestore {
+    manifest,
+    files_restored,
+    skipped,
+  };
+
+  let mut config_lock = config.lock().await;
+  let launcher_dir = app_handle.state::<PathResolver>().launcher_dir();
+  *config_lock = LauncherConfig::load_config(launcher_dir);
+  update_active_version_link(&config_lock);
+  // The kiosk PIN is this machine's, so the lock stays as it is, but the remote control API's
+  // options come from the backup
+  apply_remote_api_config(app_handle.clone(), &config_lock).await?;
+  log::info!(
+    "Restored {} files from the backup made {}",
+    restore.files_restored,
+    restore.manifest.created_at
+  );
+  Ok(restore)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::util::test_dir::TestDir;
+
+  fn manifest() -> BackupManifest {
+    BackupManifest {
+      schema_version: BACKUP_SCHEMA_VERSION,
+      created_at: chrono::Local::now().to_rfc3339(),
+      launcher_version: "test".to_string(),
+      files: 0,
+      bytes: 0,
+    }
+  }
+
+  #[test]
+  fn restores_what_was_backed_up() {
+    let test_dir = TestDir::new("backup-round-trip");
+    let roots = BackupRoots {
+      launcher: test_dir.dir("launcher"),
+      games: test_dir.dir("games"),
+      mods: Some(test_dir.dir("mods")),
+    };
+    test_dir.file("launcher/settings.json", br#"{"locale":"en-US"}"#);
+    test_dir.file("launcher/remote-config.json", b"{}");
+    test_dir.file("launcher/logs/launcher.log", b"log");
+    let save = test_dir.file("games/jak1/saves/bank0.bin", b"save");
+    let mod_metadata = test_dir.file("mods/jak1/some-mod/mod.json", b"{}");
+    test_dir.file("mods/jak1/some-mod/data/iso.bin", b"data");
+    test_dir.file("games/jak1/screenshots/shot.png", b"png");
+
+    let backup = test_dir.path().join("backup.zip");
+    let mut written = manifest();
+    write_backup(&backup_files(&roots), &backup, &mut written).unwrap();
+    assert_eq!(written.files, 3);
+
+    // Lose what was backed up, and change what was left out of it
+    std::fs::remove_file(&save).unwrap();
+    std::fs::remove_file(&mod_metadata).unwrap();
+    std::fs::write(roots.launcher.join("settings.json"), b"{}").unwrap();
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(&backup).unwrap()).unwrap();
+    let staging_dir = test_dir.path().join("staging");
+    let (files_restored, skipped) = restore_backup(&mut archive, &roots, &staging_dir).unwrap();
+
+    assert_eq!(files_restored, 3);
+    assert!(skipped.is_empty());
+    assert_eq!(std::fs::read(&save).unwrap(), b"save");
+    assert_eq!(std::fs::read(&mod_metadata).unwrap(), b"{}");
+    let settings: serde_json::Value =
+      serde_json::from_slice(&std::fs::read(roots.launcher.join("settings.json")).unwrap())
+        .unwrap();
+    assert_eq!(settings["locale"], "en-US");
+    assert!(!staging_dir.exists());
+  }
+
+  #[test]
+  fn entries_outside_the_backup_roots_are_skipped() {
+    assert_eq!(
+      safe_components("games/jak1/saves/bank0.bin").map(|parts| parts.len()),
+      Some(4)
+    );
+    assert_eq!(safe_components("games/../../etc/passwd"), None);
+    assert_eq!(safe_components("/etc/passwd"), None);
+  }
+}
@@ -2,23 +2,47 @@
 use std::os::windows::process::CommandExt;
 use std::{
   collections::HashMap,
+  io::Write,
   path::{Path, PathBuf},
   process::Command,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+  },
+  time::Instant,
 };
 
 use log::{info, warn};
 use semver::Version;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use tauri::Manager;
 
 use crate::{
-  config::LauncherConfig,
-  util::file::{create_dir, overwrite_dir, read_last_lines_from_file},
+  config::{
+    check_shared_version, GameExitAction, InstallRetryPolicy, InstallStage, LaunchAction,
+    LauncherConfig, SessionCapture,
+  },
+  install_metrics::{self, StageMonitor, StagePerformance},
+  kiosk::KioskLock,
+  paths::{self, PathResolver},
+  setup_funnel::{SetupAttempt, SetupStep},
+  tasks::{InFlightTask, TaskHandle, TaskManager, TaskStatus},
+  throughput,
+  util::{
+    file::{create_dir, delete_dir, overwrite_dir, read_json, read_last_lines_from_file},
+    power::SleepInhibitor,
+    process::tool_command,
+  },
+  warnings::WarningCode,
 };
 
-use super::CommandError;
+use super::{
+  versions::{record_active_version_launch, rollback_if_active_version_invalid},
+  CommandError,
+};
 
-fn bin_ext(filename: &str) -> String {
+pub fn bin_ext(filename: &str) -> String {
   if cfg!(windows) {
     return format!("{}.exe", filename);
   }
@@ -26,21 +50,21 @@ fn bin_ext(filename: &str) -> String {
 }
 
 struct CommonConfigData {
-  install_path: std::path::PathBuf,
+  active_version_dir: std::path::PathBuf,
+  game_data_path: std::path::PathBuf,
   active_version: String,
-  active_version_folder: String,
 }
 
 fn common_prelude(
   config: &tokio::sync::MutexGuard<LauncherConfig>,
 ) -> Result<CommonConfigData, CommandError> {
-  let install_path = match &config.installation_dir {
+  let game_data_path = match config.game_data_path() {
     None => {
-      return Err(CommandError::BinaryExecution(format!(
-        "No installation directory set, can't perform operation"
-      )))
+      return Err(CommandError::BinaryExecution(
+        "No game data directory set, can't perform operation".to_string(),
+      ))
     }
-    Some(path) => Path::new(path),
+    Some(path) => path,
   };
 
   let active_version = config
@@ -58,10 +82,27 @@ fn common_prelude(
         "No active version folder set, can't perform operation"
       )))?;
 
+  let active_version_dir = match config.version_folder_path(active_version_folder) {
+    None => {
+      return Err(CommandError::BinaryExecution(
+        "No tooling directory set, can't perform operation".to_string(),
+      ))
+    }
+    Some(path) => path.join(active_version),
+  };
+  if config.is_version_folder_shared(active_version_folder) {
+    check_shared_version(&active_version_dir).map_err(|reason| {
+      CommandError::BinaryExecution(format!(
+        "{} is shared with the other users and can't be trusted to run: {}",
+        active_version, reason
+      ))
+    })?;
+  }
+
   Ok(CommonConfigData {
-    install_path: install_path.to_path_buf(),
+    active_version_dir,
+    game_data_path,
     active_version: active_version.clone(),
-    active_version_folder: active_version_folder.clone(),
   })
 }
 
@@ -70,15 +111,8 @@ struct LauncherErrorCode {
   msg: String,
 }
 
-fn get_error_codes(
-  config: &CommonConfigData,
-  game_name: &String,
-) -> HashMap<i32, LauncherErrorCode> {
-  let json_file = config
-    .install_path
-    .join("active")
-    .join(game_name)
-    .join("data")
+fn get_error_codes(config: &CommonConfigData, game_name: &str) -> HashMap<i32, LauncherErrorCode> {
+  let json_file = paths::game_data_dir(&config.game_data_path, game_name)
     .join("launcher")
     .join("error-code-metadata.json");
   if !json_file.exists() {
@@ -122,19 +156,10 @@ fn get_error_codes(
   }
 }
 
-fn copy_data_dir(config_info: &CommonConfigData, game_name: &String) -> Result<(), CommandError> {
-  let src_dir = config_info
-    .install_path
-    .join("versions")
-    .join(&config_info.active_version_folder)
-    .join(&config_info.active_version)
-    .join("data");
+fn copy_data_dir(config_info: &CommonConfigData, game_name: &str) -> Result<(), CommandError> {
+  let src_dir = config_info.active_version_dir.join("data");
 
-  let dst_dir = config_info
-    .install_path
-    .join("active")
-    .join(&game_name)
-    .join("data");
+  let dst_dir = paths::game_data_dir(&config_info.game_data_path, game_name);
 
   info!("Copying {} into {}", src_dir.display(), dst_dir.display());
 
@@ -152,11 +177,7 @@ fn get_data_dir(
   game_name: &String,
   copy_directory: bool,
 ) -> Result<PathBuf, CommandError> {
-  let data_folder = config_info
-    .install_path
-    .join("active")
-    .join(game_name)
-    .join("data");
+  let data_folder = paths::game_data_dir(&config_info.game_data_path, game_name);
   if !data_folder.exists() && !copy_directory {
     return Err(CommandError::BinaryExecution(format!(
       "Could not locate relevant data directory '{}', can't perform operation",
@@ -177,11 +198,7 @@ fn get_exec_location(
   config_info: &CommonConfigData,
   executable_name: &str,
 ) -> Result<ExecutableLocation, CommandError> {
-  let exec_dir = config_info
-    .install_path
-    .join("versions")
-    .join(&config_info.active_version_folder)
-    .join(&config_info.active_version);
+  let exec_dir = config_info.active_version_dir.clone();
   let exec_path = exec_dir.join(bin_ext(executable_name));
   if !exec_path.exists() {
     return Err(CommandError::BinaryExecution(format!(
@@ -195,12 +212,12 @@ fn get_exec_location(
   })
 }
 
-fn create_log_file(
+pub fn create_log_file(
   app_handle: &tauri::AppHandle,
   name: &str,
   append: bool,
 ) -> Result<std::fs::File, CommandError> {
-  let log_path = &match app_handle.path_resolver().app_log_dir() {
+  let log_path = &match app_handle.state::<PathResolver>().log_dir() {
     None => {
       return Err(CommandError::Installation(format!(
         "Could not determine path to save installation logs"
@@ -220,7 +237,7 @@ fn create_log_file(
   Ok(file)
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, specta::Type)]
 #[serde(rename_all = "camelCase")]
 pub struct InstallStepOutput {
   pub success: bool,
@@ -228,6 +245,7 @@ pub struct InstallStepOutput {
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn update_data_directory(
   config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
   game_name: String,
@@ -244,239 +262,600 @@ pub async fn update_data_directory(
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn get_end_of_logs(app_handle: tauri::AppHandle) -> Result<String, CommandError> {
   Ok(read_last_lines_from_file(
     &app_handle
-      .path_resolver()
-      .app_log_dir()
-      .unwrap()
-      .join("extractor.log"),
+      .state::<PathResolver>()
+      .log_file("extractor.log")
+      .unwrap(),
     250,
   )?)
 }
 
-#[tauri::command]
-pub async fn extract_and_validate_iso(
-  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
-  app_handle: tauri::AppHandle,
-  path_to_iso: String,
-  game_name: String,
-) -> Result<InstallStepOutput, CommandError> {
-  let config_lock = config.lock().await;
-  let config_info = common_prelude(&config_lock)?;
-
-  let data_folder = get_data_dir(&config_info, &game_name, true)?;
-  log::info!(
-    "extracting using data folder: {}",
-    data_folder.to_string_lossy()
-  );
-  let exec_info = match get_exec_location(&config_info, "extractor") {
-    Ok(exec_info) => exec_info,
-    Err(_) => {
-      return Ok(InstallStepOutput {
-        success: false,
-        msg: Some("Tooling appears to be missing critical files. This may be caused by antivirus software. You will need to redownload the version and try again.".to_string()),
-      })
-    }
-  };
-
-  let mut args = vec![
-    path_to_iso.clone(),
-    "--extract".to_string(),
-    "--validate".to_string(),
-    "--proj-path".to_string(),
-    data_folder.to_string_lossy().into_owned(),
-  ];
-  if Path::new(&path_to_iso.clone()).is_dir() {
-    args.push("--folder".to_string());
+fn cancelled_step_output() -> InstallStepOutput {
+  InstallStepOutput {
+    success: false,
+    msg: Some("Cancelled".to_owned()),
   }
+}
 
-  // This is the first install step, reset the file
-  let log_file = create_log_file(&app_handle, "extractor.log", false)?;
+// Install steps usually take about as long as they did the last time, that's what their ETA is
+// based on
+fn estimate_step(app_handle: &tauri::AppHandle, task: &TaskHandle, step_id: &str) -> Instant {
+  if let Some(estimate) = throughput::step_duration(app_handle, step_id) {
+    task.set_remaining(estimate);
+  }
+  Instant::now()
+}
 
-  let mut command = Command::new(exec_info.executable_path);
-  command
-    .args(args)
-    .current_dir(exec_info.executable_dir)
-    .stdout(log_file.try_clone()?)
-    .stderr(log_file.try_clone()?);
-  #[cfg(windows)]
-  {
-    command.creation_flags(0x08000000);
+// The outcome of an identical step that was already running when this one was requested
+async fn attached_step_output(in_flight: InFlightTask) -> InstallStepOutput {
+  let state = in_flight.finished().await;
+  InstallStepOutput {
+    success: state.status == TaskStatus::Succeeded,
+    msg: state.message,
   }
-  let output = command.output()?;
-  match output.status.code() {
+}
+
+fn install_step_output(
+  config_info: &CommonConfigData,
+  game_name: &str,
+  exit_code: Option<i32>,
+) -> InstallStepOutput {
+  match exit_code {
     Some(code) => {
       if code == 0 {
-        return Ok(InstallStepOutput {
+        return InstallStepOutput {
           success: true,
           msg: None,
-        });
+        };
       }
-      let error_code_map = get_error_codes(&config_info, &game_name);
+      let error_code_map = get_error_codes(config_info, game_name);
       let default_error = LauncherErrorCode {
         msg: format!("Unexpected error occured with code {}", code).to_owned(),
       };
       let message = error_code_map.get(&code).unwrap_or(&default_error);
-      Ok(InstallStepOutput {
+      InstallStepOutput {
         success: false,
         msg: Some(message.msg.clone()),
-      })
+      }
     }
-    None => Ok(InstallStepOutput {
+    None => InstallStepOutput {
       success: false,
       msg: Some("Unexpected error occurred".to_owned()),
-    }),
+    },
+  }
+}
+
+fn record_stage_performance(
+  config: &LauncherConfig,
+  game_name: &str,
+  performance: StagePerformance,
+) {
+  info!(
+    "{} step took {:.1}s, {:.2} cores used, {:.0}% system CPU, {} bytes read, {} written",
+    performance.stage,
+    performance.duration_seconds,
+    performance.average_cores_used,
+    performance.average_system_cpu_percent,
+    performance.bytes_read,
+    performance.bytes_written
+  );
+  if let Err(err) = install_metrics::record_stage(config, game_name, performance) {
+    warn!(
+      "unable to persist install performance for {}: {}",
+      game_name, err
+    );
+  }
+}
+
+// Runs an install step's extractor, and again as the retry policy allows if it fails for a reason
+// the extractor doesn't report.  Known error codes (ie. a bad ISO) won't go away by retrying.
+// `cleanup` is removed before every retry, so it starts from scratch.  `None` if it was cancelled
+#[allow(clippy::too_many_arguments)]
+async fn run_install_step(
+  app_handle: &tauri::AppHandle,
+  config_info: &CommonConfigData,
+  policy: InstallRetryPolicy,
+  task: &TaskHandle,
+  stage: &str,
+  game_name: &str,
+  cleanup: &[PathBuf],
+  mut make_command: impl FnMut() -> Result<Command, CommandError>,
+) -> Result<Option<(std::process::ExitStatus, StagePerformance)>, CommandError> {
+  let cancellation_token = task.cancellation_token();
+  let known_codes = get_error_codes(config_info, game_name);
+  let mut tries = 0;
+  loop {
+    tries += 1;
+    let mut child = make_command()?.spawn()?;
+    let monitor = StageMonitor::start(stage, child.id());
+    let status = match cancellation_token.wait_for_child(&mut child)? {
+      Some(status) => status,
+      None => return Ok(None),
+    };
+    let performance = monitor.finish(status.success());
+    let transient = status
+      .code()
+      .map_or(true, |code| !known_codes.contains_key(&code));
+    if status.success() || !transient || tries > policy.retries {
+      if status.success() && tries > 1 {
+        task.warn(
+          WarningCode::StepRetried,
+          format!("The {} step only succeeded after {} attempts", stage, tries),
+        );
+      }
+      return Ok(Some((status, performance)));
+    }
+    let line = format!(
+      "[{}] {}, attempt {} of {} failed: exited with {:?}",
+      chrono::Local::now().to_rfc3339(),
+      stage,
+      tries,
+      policy.retries + 1,
+      status.code()
+    );
+    warn!("{}", line);
+    match create_log_file(app_handle, "extractor.log", true) {
+      Ok(mut log_file) => {
+        if let Err(err) = writeln!(log_file, "{}", line) {
+          warn!("Unable to record install attempt: {}", err);
+        }
+      }
+      Err(err) => warn!("Unable to record install attempt: {}", err),
+    }
+    for dir in cleanup {
+      delete_dir(dir)?;
+    }
+    let backoff = u64::from(policy.backoff_secs) << (tries - 1).min(6);
+    tokio::select! {
+      _ = tokio::time::sleep(std::time::Duration::from_secs(backoff)) => {}
+      _ = cancellation_token.cancelled() => return Ok(None),
+    }
+  }
+}
+
+// Only a game that's being installed has checkpoints, recompiling an installed one doesn't
+fn record_checkpoint(config: &mut LauncherConfig, game_name: &str, stage: InstallStage) {
+  let game_name = game_name.to_owned();
+  if config.is_game_installed(&game_name) {
+    return;
+  }
+  if let Err(err) = config.set_game_install_checkpoint(&game_name, Some(stage)) {
+    warn!(
+      "unable to persist install checkpoint for {}: {}",
+      game_name, err
+    );
   }
 }
 
 #[tauri::command]
-pub async fn run_decompiler(
+#[specta::specta]
+pub async fn extract_and_validate_iso(
   config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
   app_handle: tauri::AppHandle,
+  task_manager: tauri::State<'_, TaskManager>,
   path_to_iso: String,
   game_name: String,
-  truncate_logs: bool,
 ) -> Result<InstallStepOutput, CommandError> {
-  let config_lock = config.lock().await;
-  let config_info = common_prelude(&config_lock)?;
-
-  let data_folder = get_data_dir(&config_info, &game_name, false)?;
-  let exec_info = match get_exec_location(&config_info, "extractor") {
-    Ok(exec_info) => exec_info,
-    Err(_) => {
-      return Ok(InstallStepOutput {
-        success: false,
-        msg: Some("Tooling appears to be missing critical files. This may be caused by antivirus software. You will need to redownload the version and try again.".to_string()),
-      })
-    }
+  // Started before taking the config lock, so a second click attaches instead of queueing up
+  let step_id = format!("extract-{}", game_name);
+  let mut task = match task_manager.start_or_attach(&app_handle, step_id.clone(), "Extracting game")
+  {
+    Ok(task) => task,
+    Err(in_flight) => return Ok(attached_step_output(in_flight).await),
   };
+  let result: Result<_, CommandError> = async {
+    let attempt = SetupAttempt::start(&app_handle, SetupStep::Iso);
+    let started = estimate_step(&app_handle, &task, &step_id);
+    let mut config_lock = config.lock().await;
+    let config_info = common_prelude(&config_lock)?;
 
-  let mut source_path = path_to_iso;
-  if source_path.is_empty() {
-    source_path = data_folder
-      .join("iso_data")
-      .join(&game_name)
-      .to_string_lossy()
-      .to_string();
-  }
+    // Extracting starts the installation over, whatever was done before doesn't count anymore
+    if config_lock.game_install_checkpoint(&game_name).is_some() {
+      if let Err(err) = config_lock.set_game_install_checkpoint(&game_name, None) {
+        warn!(
+          "unable to clear install checkpoint for {}: {}",
+          game_name, err
+        );
+      }
+    }
+    let data_folder = get_data_dir(&config_info, &game_name, true)?;
+    log::info!(
+      "extracting using data folder: {}",
+      data_folder.to_string_lossy()
+    );
+    let exec_info = match get_exec_location(&config_info, "extractor") {
+      Ok(exec_info) => exec_info,
+      Err(_) => {
+        attempt.failed("the extractor is missing");
+        return Ok(InstallStepOutput {
+          success: false,
+          msg: Some("Tooling appears to be missing critical files. This may be caused by antivirus software. You will need to redownload the version and try again.".to_string()),
+        });
+      }
+    };
 
-  let log_file = create_log_file(&app_handle, "extractor.log", !truncate_logs)?;
-  let mut command = Command::new(exec_info.executable_path);
-  command
-    .args([
-      source_path,
-      "--decompile".to_string(),
+    let mut args = vec![
+      path_to_iso.clone(),
+      "--extract".to_string(),
+      "--validate".to_string(),
       "--proj-path".to_string(),
       data_folder.to_string_lossy().into_owned(),
-    ])
-    .stdout(log_file.try_clone()?)
-    .stderr(log_file)
-    .current_dir(exec_info.executable_dir);
-  #[cfg(windows)]
-  {
-    command.creation_flags(0x08000000);
+    ];
+    if Path::new(&path_to_iso.clone()).is_dir() {
+      args.push("--folder".to_string());
+    }
+
+    // This is the first install step, reset the file
+    let _sleep_inhibitor = SleepInhibitor::acquire("Extracting game");
+    let log_file = create_log_file(&app_handle, "extractor.log", false)?;
+    let extractor_env = config_lock.tool_environment("extractor").cloned();
+    let staged_iso_data = data_folder.join("iso_data").join(&game_name);
+
+    let ran = run_install_step(
+      &app_handle,
+      &config_info,
+      config_lock.install_retry_policy.unwrap_or_default(),
+      &task,
+      "extract",
+      &game_name,
+      std::slice::from_ref(&staged_iso_data),
+      || {
+        let mut command = tool_command(
+          &exec_info.executable_path,
+          &exec_info.executable_dir,
+          extractor_env.as_ref(),
+        );
+        command
+          .args(&args)
+          .stdout(log_file.try_clone()?)
+          .stderr(log_file.try_clone()?);
+        #[cfg(windows)]
+        {
+          command.creation_flags(0x08000000);
+        }
+        Ok(command)
+      },
+    )
+    .await?;
+    let (status, performance) = match ran {
+      Some(ran) => ran,
+      None => {
+        // Don't leave a partial extraction behind, it would be mistaken for a valid one
+        if let Err(err) = delete_dir(&staged_iso_data) {
+          log::warn!(
+            "unable to clean up partially extracted data {}: {}",
+            staged_iso_data.display(),
+            err
+          );
+        }
+        attempt.abandoned();
+        return Ok(cancelled_step_output());
+      }
+    };
+    let step_output = install_step_output(&config_info, &game_name, status.code());
+    record_stage_performance(&config_lock, &game_name, performance);
+    if step_output.success {
+      throughput::record_step(&app_handle, &step_id, started.elapsed());
+      record_checkpoint(&mut config_lock, &game_name, InstallStage::Extracted);
+      attempt.succeeded();
+    } else {
+      attempt.failed(
+        step_output
+          .msg
+          .clone()
+          .unwrap_or_else(|| format!("exited with {:?}", status.code())),
+      );
+    }
+    task.finish(step_output.success, step_output.msg.clone());
+    Ok(step_output)
   }
-  let output = command.output()?;
-  match output.status.code() {
-    Some(code) => {
-      if code == 0 {
+  .await;
+  match &result {
+    Ok(output) if !output.success => task.finish(false, output.msg.clone()),
+    Err(err) => task.finish(false, Some(err.to_string())),
+    _ => {}
+  }
+  result
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn run_decompiler(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  app_handle: tauri::AppHandle,
+  task_manager: tauri::State<'_, TaskManager>,
+  path_to_iso: String,
+  game_name: String,
+  truncate_logs: bool,
+) -> Result<InstallStepOutput, CommandError> {
+  // Started before taking the config lock, so a second click attaches instead of queueing up
+  let step_id = format!("decompile-{}", game_name);
+  let mut task =
+    match task_manager.start_or_attach(&app_handle, step_id.clone(), "Decompiling game") {
+      Ok(task) => task,
+      Err(in_flight) => return Ok(attached_step_output(in_flight).await),
+    };
+  let result: Result<_, CommandError> = async {
+    let started = estimate_step(&app_handle, &task, &step_id);
+    let mut config_lock = config.lock().await;
+    let config_info = common_prelude(&config_lock)?;
+
+    let data_folder = get_data_dir(&config_info, &game_name, false)?;
+    let exec_info = match get_exec_location(&config_info, "extractor") {
+      Ok(exec_info) => exec_info,
+      Err(_) => {
         return Ok(InstallStepOutput {
-          success: true,
-          msg: None,
+          success: false,
+          msg: Some("Tooling appears to be missing critical files. This may be caused by antivirus software. You will need to redownload the version and try again.".to_string()),
+        })
+      }
+    };
+
+    let mut source_path = path_to_iso;
+    if source_path.is_empty() {
+      let iso_data = data_folder.join("iso_data").join(&game_name);
+      // Removed after installing to save space, the frontend asks for the ISO again
+      if !iso_data.is_dir() {
+        task.finish(false, Some("No ISO data to decompile from".to_string()));
+        return Ok(InstallStepOutput {
+          success: false,
+          msg: Some("The game's ISO data was removed after installing, select the ISO again to decompile the game.".to_string()),
         });
       }
-      let error_code_map = get_error_codes(&config_info, &game_name);
-      let default_error = LauncherErrorCode {
-        msg: format!("Unexpected error occured with code {}", code).to_owned(),
-      };
-      let message = error_code_map.get(&code).unwrap_or(&default_error);
-      Ok(InstallStepOutput {
-        success: false,
-        msg: Some(message.msg.clone()),
-      })
+      source_path = iso_data.to_string_lossy().to_string();
     }
-    None => Ok(InstallStepOutput {
-      success: false,
-      msg: Some("Unexpected error occurred".to_owned()),
-    }),
+
+    let _sleep_inhibitor = SleepInhibitor::acquire("Decompiling game");
+    let log_file = create_log_file(&app_handle, "extractor.log", !truncate_logs)?;
+    let extractor_env = config_lock.tool_environment("extractor").cloned();
+    let ran = run_install_step(
+      &app_handle,
+      &config_info,
+      config_lock.install_retry_policy.unwrap_or_default(),
+      &task,
+      "decompile",
+      &game_name,
+      &[],
+      || {
+        let mut command = tool_command(
+          &exec_info.executable_path,
+          &exec_info.executable_dir,
+          extractor_env.as_ref(),
+        );
+        command
+          .args([
+            source_path.clone(),
+            "--decompile".to_string(),
+            "--proj-path".to_string(),
+            data_folder.to_string_lossy().into_owned(),
+          ])
+          .stdout(log_file.try_clone()?)
+          .stderr(log_file.try_clone()?);
+        #[cfg(windows)]
+        {
+          command.creation_flags(0x08000000);
+        }
+        Ok(command)
+      },
+    )
+    .await?;
+    let (status, performance) = match ran {
+      Some(ran) => ran,
+      None => return Ok(cancelled_step_output()),
+    };
+    let step_output = install_step_output(&config_info, &game_name, status.code());
+    record_stage_performance(&config_lock, &game_name, performance);
+    if step_output.success {
+      throughput::record_step(&app_handle, &step_id, started.elapsed());
+      record_checkpoint(&mut config_lock, &game_name, InstallStage::Decompiled);
+    }
+    task.finish(step_output.success, step_output.msg.clone());
+    Ok(step_output)
+  }
+  .await;
+  match &result {
+    Ok(output) if !output.success => task.finish(false, output.msg.clone()),
+    Err(err) => task.finish(false, Some(err.to_string())),
+    _ => {}
   }
+  result
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn run_compiler(
   config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
   app_handle: tauri::AppHandle,
+  task_manager: tauri::State<'_, TaskManager>,
   path_to_iso: String,
   game_name: String,
   truncate_logs: bool,
 ) -> Result<InstallStepOutput, CommandError> {
-  let config_lock = config.lock().await;
-  let config_info = common_prelude(&config_lock)?;
+  // Started before taking the config lock, so a second click attaches instead of queueing up
+  let step_id = format!("compile-{}", game_name);
+  let mut task = match task_manager.start_or_attach(&app_handle, step_id.clone(), "Compiling game")
+  {
+    Ok(task) => task,
+    Err(in_flight) => return Ok(attached_step_output(in_flight).await),
+  };
+  let result: Result<_, CommandError> = async {
+    let started = estimate_step(&app_handle, &task, &step_id);
+    let mut config_lock = config.lock().await;
+    let config_info = common_prelude(&config_lock)?;
 
-  let data_folder = get_data_dir(&config_info, &game_name, false)?;
-  let exec_info = match get_exec_location(&config_info, "extractor") {
-    Ok(exec_info) => exec_info,
-    Err(_) => {
-      return Ok(InstallStepOutput {
-        success: false,
-        msg: Some("Tooling appears to be missing critical files. This may be caused by antivirus software. You will need to redownload the version and try again.".to_string()),
-      })
+    let data_folder = get_data_dir(&config_info, &game_name, false)?;
+    let exec_info = match get_exec_location(&config_info, "extractor") {
+      Ok(exec_info) => exec_info,
+      Err(_) => {
+        return Ok(InstallStepOutput {
+          success: false,
+          msg: Some("Tooling appears to be missing critical files. This may be caused by antivirus software. You will need to redownload the version and try again.".to_string()),
+        })
+      }
+    };
+
+    let mut source_path = path_to_iso;
+    if source_path.is_empty() {
+      let iso_data = data_folder.join("iso_data").join(&game_name);
+      if !iso_data.is_dir() {
+        task.finish(false, Some("No ISO data to compile from".to_string()));
+        return Ok(InstallStepOutput {
+          success: false,
+          msg: Some("The game's ISO data was removed after installing, select the ISO again to compile the game.".to_string()),
+        });
+      }
+      source_path = iso_data.to_string_lossy().to_string();
     }
-  };
 
-  let mut source_path = path_to_iso;
-  if source_path.is_empty() {
-    source_path = data_folder
-      .join("iso_data")
-      .join(&game_name)
-      .to_string_lossy()
-      .to_string();
+    let _sleep_inhibitor = SleepInhibitor::acquire("Compiling game");
+    let log_file = create_log_file(&app_handle, "extractor.log", !truncate_logs)?;
+    let extractor_env = config_lock.tool_environment("extractor").cloned();
+    let ran = run_install_step(
+      &app_handle,
+      &config_info,
+      config_lock.install_retry_policy.unwrap_or_default(),
+      &task,
+      "compile",
+      &game_name,
+      &[],
+      || {
+        let mut command = tool_command(
+          &exec_info.executable_path,
+          &exec_info.executable_dir,
+          extractor_env.as_ref(),
+        );
+        command
+          .args([
+            source_path.clone(),
+            "--compile".to_string(),
+            "--proj-path".to_string(),
+            data_folder.to_string_lossy().into_owned(),
+          ])
+          .stdout(log_file.try_clone()?)
+          .stderr(log_file.try_clone()?);
+        #[cfg(windows)]
+        {
+          command.creation_flags(0x08000000);
+        }
+        Ok(command)
+      },
+    )
+    .await?;
+    let (status, performance) = match ran {
+      Some(ran) => ran,
+      None => return Ok(cancelled_step_output()),
+    };
+    let step_output = install_step_output(&config_info, &game_name, status.code());
+    record_stage_performance(&config_lock, &game_name, performance);
+    if step_output.success {
+      throughput::record_step(&app_handle, &step_id, started.elapsed());
+      record_checkpoint(&mut config_lock, &game_name, InstallStage::Compiled);
+    }
+    task.finish(step_output.success, step_output.msg.clone());
+    Ok(step_output)
+  }
+  .await;
+  match &result {
+    Ok(output) if !output.success => task.finish(false, output.msg.clone()),
+    Err(err) => task.finish(false, Some(err.to_string())),
+    _ => {}
   }
+  result
+}
 
-  let log_file = create_log_file(&app_handle, "extractor.log", !truncate_logs)?;
-  let mut command = Command::new(exec_info.executable_path);
-  command
-    .args([
-      source_path,
-      "--compile".to_string(),
-      "--proj-path".to_string(),
-      data_folder.to_string_lossy().into_owned(),
-    ])
-    .stdout(log_file.try_clone().unwrap())
-    .stderr(log_file)
-    .current_dir(exec_info.executable_dir);
-  #[cfg(windows)]
+/// Continues an installation that was interrupted with the step after the last one that finished,
+/// rather than starting over from the ISO.  Finalizing it is still up to the caller
+#[tauri::command]
+#[specta::specta]
+pub async fn resume_install(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  app_handle: tauri::AppHandle,
+  task_manager: tauri::State<'_, TaskManager>,
+  game_name: String,
+) -> Result<InstallStepOutput, CommandError> {
+  let mut config_lock = config.lock().await;
+  let checkpoint = match config_lock.game_install_checkpoint(&game_name) {
+    Some(checkpoint) => checkpoint,
+    None => {
+      return Err(CommandError::Installation(format!(
+        "There is no interrupted installation of {} to resume",
+        game_name
+      )))
+    }
+  };
+  // What another version extracted or decompiled can't be built on
+  if checkpoint.version != config_lock.active_version
+    || checkpoint.version_folder != config_lock.active_version_folder
   {
-    command.creation_flags(0x08000000);
+    return Ok(InstallStepOutput {
+      success: false,
+      msg: Some("The installation was started with a different version, it has to be started over from the ISO.".to_string()),
+    });
   }
-  let output = command.output()?;
-  match output.status.code() {
-    Some(code) => {
-      if code == 0 {
-        return Ok(InstallStepOutput {
-          success: true,
-          msg: None,
-        });
-      }
-      let error_code_map = get_error_codes(&config_info, &game_name);
-      let default_error = LauncherErrorCode {
-        msg: format!("Unexpected error occured with code {}", code).to_owned(),
-      };
-      let message = error_code_map.get(&code).unwrap_or(&default_error);
-      Ok(InstallStepOutput {
-        success: false,
-        msg: Some(message.msg.clone()),
-      })
+  let config_info = common_prelude(&config_lock)?;
+  let iso_data = get_data_dir(&config_info, &game_name, false)?
+    .join("iso_data")
+    .join(&game_name);
+  if !iso_data.exists() {
+    if let Err(err) = config_lock.set_game_install_checkpoint(&game_name, None) {
+      warn!(
+        "unable to clear install checkpoint for {}: {}",
+        game_name, err
+      );
     }
-    None => Ok(InstallStepOutput {
+    return Ok(InstallStepOutput {
       success: false,
-      msg: Some("Unexpected error occurred".to_owned()),
-    }),
+      msg: Some(
+        "The extracted game files are gone, the installation has to be started over from the ISO."
+          .to_string(),
+      ),
+    });
+  }
+  // The steps take the lock themselves
+  drop(config_lock);
+  info!(
+    "resuming installation of {} after {:?}",
+    game_name, checkpoint.stage
+  );
+
+  let mut truncate_logs = true;
+  if checkpoint.stage < InstallStage::Decompiled {
+    let output = run_decompiler(
+      config.clone(),
+      app_handle.clone(),
+      task_manager.clone(),
+      String::new(),
+      game_name.clone(),
+      truncate_logs,
+    )
+    .await?;
+    if !output.success {
+      return Ok(output);
+    }
+    truncate_logs = false;
+  }
+  if checkpoint.stage < InstallStage::Compiled {
+    return run_compiler(
+      config,
+      app_handle,
+      task_manager,
+      String::new(),
+      game_name,
+      truncate_logs,
+    )
+    .await;
   }
+  Ok(InstallStepOutput {
+    success: true,
+    msg: None,
+  })
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn open_repl(
   config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
   game_name: String,
@@ -490,16 +869,18 @@ pub async fn open_repl(
 
   let data_folder = get_data_dir(&config_info, &game_name, false)?;
   let exec_info = get_exec_location(&config_info, "goalc")?;
-  let mut command = Command::new("cmd");
-  command
-    .args([
-      "/K",
-      "start",
-      &bin_ext("goalc"),
-      "--proj-path",
-      &data_folder.to_string_lossy().into_owned(),
-    ])
-    .current_dir(exec_info.executable_dir);
+  let mut command = tool_command(
+    "cmd",
+    &exec_info.executable_dir,
+    config_lock.tool_environment("goalc"),
+  );
+  command.args([
+    "/K",
+    "start",
+    &bin_ext("goalc"),
+    "--proj-path",
+    &data_folder.to_string_lossy(),
+  ]);
   #[cfg(windows)]
   {
     command.creation_flags(0x08000000);
@@ -508,15 +889,520 @@ pub async fn open_repl(
   Ok(())
 }
 
+#[derive(Debug, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingLibrary {
+  pub name: String,
+  pub package_hint: Option<String>,
+}
+
+#[cfg(target_os = "linux")]
+fn find_missing_libraries(executable_path: &Path) -> Vec<MissingLibrary> {
+  match crate::util::os::find_missing_shared_libraries(executable_path) {
+    Ok(missing) => missing
+      .into_iter()
+      .map(|name| MissingLibrary {
+        package_hint: crate::util::os::shared_library_package_hint(&name),
+        name,
+      })
+      .collect(),
+    Err(err) => {
+      warn!("Unable to check for missing shared libraries: {}", err);
+      Vec::new()
+    }
+  }
+}
+
+// Only linux distributions are known to ship without libraries the game needs, everywhere
+// else they are either bundled or part of the OS
+#[cfg(not(target_os = "linux"))]
+fn find_missing_libraries(_executable_path: &Path) -> Vec<MissingLibrary> {
+  Vec::new()
+}
+
 #[tauri::command]
+#[specta::specta]
+pub async fn check_shared_library_requirements(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<Vec<MissingLibrary>, CommandError> {
+  let config_lock = config.lock().await;
+  let config_info = common_prelude(&config_lock)?;
+  let exec_info = get_exec_location(&config_info, "gk")?;
+  Ok(find_missing_libraries(&exec_info.executable_path))
+}
+
+// If the game exits within this window, it never really started
+const EARLY_EXIT_WINDOW: std::time::Duration = std::time::Duration::from_secs(5);
+
+#[derive(Debug, Default, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct LaunchFailureReport {
+  pub timestamp: String,
+  pub command_line: Vec<String>,
+  pub working_directory: Option<String>,
+  pub environment: HashMap<String, String>,
+  pub spawn_error: Option<String>,
+  pub exit_code: Option<i32>,
+  pub seconds_until_exit: Option<f64>,
+  pub missing_libraries: Vec<String>,
+  pub loader_errors: Vec<String>,
+  pub game_log_tail: String,
+}
+
+impl LaunchFailureReport {
+  fn new(command: &Command) -> Self {
+    let mut command_line = vec![command.get_program().to_string_lossy().into_owned()];
+    command_line.extend(
+      command
+        .get_args()
+        .map(|arg| arg.to_string_lossy().into_owned()),
+    );
+    // What the game was given, which may include variables passed through to it, avoid leaking
+    // anything sensitive into the report
+    let environment = command
+      .get_envs()
+      .filter_map(|(key, value)| {
+        Some((
+          key.to_string_lossy().into_owned(),
+          value?.to_string_lossy().into_owned(),
+        ))
+      })
+      .map(|(key, value)| {
+        let upper_key = key.to_uppercase();
+        if ["TOKEN", "SECRET", "PASSWORD", "KEY"]
+          .iter()
+          .any(|sensitive| upper_key.contains(sensitive))
+        {
+          (key, "<redacted>".to_string())
+        } else {
+          (key, value)
+        }
+      })
+      .collect();
+    Self {
+      command_line,
+      working_directory: command
+        .get_current_dir()
+        .map(|dir| dir.to_string_lossy().into_owned()),
+      environment,
+      ..Self::default()
+    }
+  }
+
+  pub fn path(app_handle: &tauri::AppHandle) -> Option<PathBuf> {
+    app_handle
+      .state::<PathResolver>()
+      .log_file("launch-failure.json")
+  }
+
+  #[cfg_attr(not(target_os = "linux"), allow(unused_variables))]
+  fn finalize(mut self, app_handle: &tauri::AppHandle, executable_path: &Path) {
+    self.timestamp = chrono::Local::now().to_rfc3339();
+    #[cfg(target_os = "linux")]
+    match crate::util::os::find_missing_shared_libraries(executable_path) {
+      Ok(missing) => self.missing_libraries = missing,
+      Err(err) => warn!("Unable to check for missing shared libraries: {}", err),
+    }
+    #[cfg(target_os = "windows")]
+    match self.exit_code.map(|code| code as u32) {
+      Some(0xC0000135) => self
+        .loader_errors
+        .push("A required DLL could not be found (STATUS_DLL_NOT_FOUND)".to_string()),
+      Some(0xC0000139) => self.loader_errors.push(
+        "A required DLL entry point could not be found (STATUS_ENTRYPOINT_NOT_FOUND)".to_string(),
+      ),
+      Some(0xC000007B) => self.loader_errors.push(
+        "A DLL was built for the wrong architecture (STATUS_INVALID_IMAGE_FORMAT)".to_string(),
+      ),
+      _ => (),
+    }
+    if let Some(log_path) = app_handle.state::<PathResolver>().log_file("game.log") {
+      self.game_log_tail = read_last_lines_from_file(&log_path, 50).unwrap_or_default();
+    }
+    // The dynamic loader reports its errors on stderr, which ends up in the game log
+    for line in self.game_log_tail.lines() {
+      if line.contains("error while loading shared libraries") || line.contains("dyld") {
+        self.loader_errors.push(line.to_string());
+      }
+    }
+    log::error!(
+      "Game failed to launch: {:?}",
+      self.spawn_error.as_ref().or(self.loader_errors.first())
+    );
+    if let Some(report_path) = Self::path(app_handle) {
+      match std::fs::File::create(&report_path) {
+        Ok(file) => {
+          if let Err(err) = serde_json::to_writer_pretty(file, &self) {
+            warn!("Unable to write launch failure report: {}", err);
+          }
+        }
+        Err(err) => warn!("Unable to create launch failure report: {}", err),
+      }
+    }
+    if let Err(err) = app_handle.emit_all("gameLaunchFailed", &self) {
+      warn!("Unable to emit launch failure event: {}", err);
+    }
+  }
+}
+
+// Watches the freshly spawned game in the background, the launch command itself returns immediately
+// How long before the end of a limited session the player is warned, and how long the game gets
+// to shut down on its own once the limit is reached before it's killed
+const SESSION_WARNING_LEAD: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+const SESSION_SHUTDOWN_GRACE: std::time::Duration = std::time::Duration::from_secs(15);
+
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct GameSessionWarning {
+  pub game_name: String,
+  pub remaining_seconds: u64,
+}
+
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct GameSessionEnded {
+  pub game_name: String,
+  pub seconds_played: u64,
+  pub limit_reached: bool,
+  /// Whether the frontend should show a summary of the session
+  pub show_summary: bool,
+}
+
+/// The debug sessions that are running, so they can be restarted when the tooling they run on is
+/// rebuilt
+#[derive(Default)]
+pub struct DebugSessions {
+  stop_requests: std::sync::Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+impl DebugSessions {
+  fn track(&self, game_name: &str) -> Arc<AtomicBool> {
+    let stop_request = Arc::new(AtomicBool::new(false));
+    if let Ok(mut stop_requests) = self.stop_requests.lock() {
+      stop_requests.insert(game_name.to_string(), stop_request.clone());
+    }
+    stop_request
+  }
+
+  fn untrack(&self, game_name: &str) {
+    if let Ok(mut stop_requests) = self.stop_requests.lock() {
+      stop_requests.remove(game_name);
+    }
+  }
+
+  pub fn is_running(&self, game_name: &str) -> bool {
+    self
+      .stop_requests
+      .lock()
+      .map_or(false, |stop_requests| stop_requests.contains_key(game_name))
+  }
+
+  /// Asks every running debug session to exit, returning the games that were running
+  pub fn stop_all(&self) -> Vec<String> {
+    match self.stop_requests.lock() {
+      Ok(stop_requests) => stop_requests
+        .iter()
+        .map(|(game_name, stop_request)| {
+          stop_request.store(true, Ordering::SeqCst);
+          game_name.clone()
+        })
+        .collect(),
+      Err(_) => vec![],
+    }
+  }
+}
+
+/// The games the launcher started that are still running, the tooling they run on mustn't be
+/// switched from under them
+#[derive(Default)]
+pub struct RunningGames {
+  games: std::sync::Mutex<Vec<String>>,
+}
+
+impl RunningGames {
+  fn start(&self, game_name: &str) {
+    if let Ok(mut games) = self.games.lock() {
+      games.push(game_name.to_string());
+    }
+  }
+
+  fn end(&self, game_name: &str) {
+    if let Ok(mut games) = self.games.lock() {
+      if let Some(index) = games.iter().position(|game| game == game_name) {
+        games.remove(index);
+      }
+    }
+  }
+
+  pub fn list(&self) -> Vec<String> {
+    self
+      .games
+      .lock()
+      .map(|games| games.clone())
+      .unwrap_or_default()
+  }
+
+  /// Refuses to `what` (ie. "switch versions") while a game is running
+  pub fn ensure_none(&self, what: &str) -> Result<(), CommandError> {
+    let games = self.list();
+    if games.is_empty() {
+      return Ok(());
+    }
+    Err(CommandError::VersionManagement(format!(
+      "Unable to {} while {} is running, close the game first",
+      what,
+      games.join(", ")
+    )))
+  }
+}
+
+// Everything the game supervisor needs to know about the game it's following
+struct GameSupervision {
+  game_name: String,
+  session_limit: Option<std::time::Duration>,
+  /// Set for debug sessions, the game is closed (to be restarted) when it's raised
+  stop_request: Option<Arc<AtomicBool>>,
+  snapshot_hotkey: Option<String>,
+  launch_action: LaunchAction,
+  exit_action: GameExitAction,
+  session_capture: SessionCapture,
+}
+
+fn notify(app_handle: &tauri::AppHandle, body: &str) {
+  let result =
+    tauri::api::notification::Notification::new(&app_handle.config().tauri.bundle.identifier)
+      .title("OpenGOAL Launcher")
+      .body(body)
+      .show();
+  if let Err(err) = result {
+    warn!("Unable to show notification: {}", err);
+  }
+}
+
+// Follows the game until it exits: writes a failure report if it exits early, enforces the
+// session limit if there is one, and records the playtime (and a summary of the session, if
+// configured).  The snapshot hotkey is only registered while the game runs, and the launcher is
+// brought back afterwards if configured
+fn watch_game_process(
+  app_handle: tauri::AppHandle,
+  mut child: std::process::Child,
+  mut report: LaunchFailureReport,
+  executable_path: PathBuf,
+  supervision: GameSupervision,
+) {
+  let GameSupervision {
+    game_name,
+    session_limit,
+    stop_request,
+    snapshot_hotkey,
+    launch_action,
+    exit_action,
+    session_capture,
+  } = supervision;
+  app_handle.state::<RunningGames>().start(&game_name);
+  std::thread::spawn(move || {
+    let started = std::time::Instant::now();
+    let session = (session_capture != SessionCapture::Off)
+      .then(|| crate::sessions::SessionStart::new(&app_handle.state(), &game_name));
+    let mut warned = false;
+    let mut exit_requested_at: Option<std::time::Instant> = None;
+    let mut exit_status = None;
+    let mut crashed_early = false;
+    loop {
+      match child.try_wait() {
+        Ok(Some(status)) => {
+          exit_status = Some(status);
+          if !status.success() && started.elapsed() < EARLY_EXIT_WINDOW {
+            crashed_early = exit_requested_at.is_none();
+            report.exit_code = status.code();
+            report.seconds_until_exit = Some(started.elapsed().as_secs_f64());
+            report.finalize(&app_handle, &executable_path);
+          }
+          break;
+        }
+        Ok(None) => std::thread::sleep(std::time::Duration::from_millis(250)),
+        Err(err) => {
+          warn!("Unable to monitor game process: {}", err);
+          break;
+        }
+      }
+      let elapsed = started.elapsed();
+      let stop_requested = stop_request
+        .as_ref()
+        .map_or(false, |stop_request| stop_request.load(Ordering::SeqCst));
+      let limit_reached = session_limit.map_or(false, |limit| elapsed >= limit);
+      let warning_due = |limit: &std::time::Duration| elapsed + SESSION_WARNING_LEAD >= *limit;
+      if let Some(limit) = session_limit.filter(|limit| !warned && warning_due(limit)) {
+        warned = true;
+        let remaining_seconds = limit.saturating_sub(elapsed).as_secs();
+        notify(
+          &app_handle,
+          &format!(
+            "The game will close in {} minute(s)",
+            (remaining_seconds + 59) / 60
+          ),
+        );
+        app_handle
+          .emit_all(
+            "gameSessionWarning",
+            GameSessionWarning {
+              game_name: game_name.clone(),
+              remaining_seconds,
+            },
+          )
+          .ok();
+      }
+      match exit_requested_at {
+        None if limit_reached || stop_requested => {
+          if limit_reached {
+            info!("Session limit reached, closing {}", game_name);
+          } else {
+            info!("Closing {} so it can be restarted", game_name);
+          }
+          if let Err(err) = crate::util::os::request_process_exit(&child) {
+            warn!("Unable to ask the game to exit: {}", err);
+          }
+          exit_requested_at = Some(std::time::Instant::now());
+        }
+        Some(requested) if requested.elapsed() >= SESSION_SHUTDOWN_GRACE => {
+          warn!("Game did not exit when asked to, killing it");
+          child.kill().ok();
+        }
+        _ => {}
+      }
+    }
+    if let Some(accelerator) = snapshot_hotkey {
+      super::snapshots::unregister_hotkey(&app_handle, &accelerator);
+    }
+    let restarting = stop_request
+      .as_ref()
+      .map_or(false, |stop_request| stop_request.load(Ordering::SeqCst));
+    if stop_request.is_some() {
+      app_handle.state::<DebugSessions>().untrack(&game_name);
+    }
+    let seconds_played = started.elapsed().as_secs();
+    crate::playtime::record(&app_handle, &game_name, seconds_played);
+    app_handle.state::<RunningGames>().end(&game_name);
+    record_active_version_launch(&app_handle, crashed_early);
+    // A game that's about to be restarted doesn't end the session as far as the player can tell
+    if !restarting {
+      super::window::apply_exit_action(&app_handle, launch_action, exit_action);
+      if let Some(session) = session {
+        crate::sessions::record(
+          &app_handle,
+          session.summarize(session_capture, exit_status, exit_requested_at.is_some()),
+        );
+      }
+    }
+    app_handle
+      .emit_all(
+        "gameSessionEnded",
+        GameSessionEnded {
+          game_name,
+          seconds_played,
+          limit_reached: session_limit.map_or(false, |limit| started.elapsed() >= limit),
+          show_summary: exit_action == GameExitAction::ShowSummary,
+        },
+      )
+      .ok();
+    if !restarting {
+      super::window::exit_after_game(&app_handle, launch_action, crashed_early);
+    }
+  });
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_launch_failure_report(
+  app_handle: tauri::AppHandle,
+) -> Result<Option<LaunchFailureReport>, CommandError> {
+  let report_path = match LaunchFailureReport::path(&app_handle) {
+    Some(path) if path.exists() => path,
+    _ => return Ok(None),
+  };
+  // An unreadable report is as good as none
+  Ok(read_json(&report_path))
+}
+
+#[tauri::command]
+#[specta::specta]
 pub async fn launch_game(
   config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
   app_handle: tauri::AppHandle,
   game_name: String,
   in_debug: bool,
 ) -> Result<(), CommandError> {
-  let config_lock = config.lock().await;
-  let config_info = common_prelude(&config_lock)?;
+  let mut config_lock = config.lock().await;
+  rollback_if_active_version_invalid(&app_handle, &mut config_lock);
+  start_game(&config_lock, app_handle, game_name, in_debug, None)
+}
+
+/// Launches the game for at most `minutes`, the player is warned shortly before the limit and
+/// the game is closed once it's reached
+#[tauri::command]
+#[specta::specta]
+pub async fn launch_game_with_limit(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  app_handle: tauri::AppHandle,
+  game_name: String,
+  minutes: u32,
+) -> Result<(), CommandError> {
+  if minutes == 0 {
+    return Err(CommandError::BinaryExecution(
+      "Session limit must be at least a minute".to_string(),
+    ));
+  }
+  let mut config_lock = config.lock().await;
+  rollback_if_active_version_invalid(&app_handle, &mut config_lock);
+  start_game(
+    &config_lock,
+    app_handle,
+    game_name,
+    false,
+    Some(std::time::Duration::from_secs(u64::from(minutes) * 60)),
+  )
+}
+
+/// Launches the game in debug mode again, after its session was stopped to update its tooling
+pub fn restart_debug_game(
+  config_lock: &tokio::sync::MutexGuard<'_, LauncherConfig>,
+  app_handle: tauri::AppHandle,
+  game_name: String,
+) -> Result<(), CommandError> {
+  start_game(config_lock, app_handle, game_name, true, None)
+}
+
+// While locked, sessions can't run past what's left of the daily limit
+fn limit_to_remaining_playtime(
+  config_lock: &LauncherConfig,
+  app_handle: &tauri::AppHandle,
+  session_limit: Option<std::time::Duration>,
+) -> Result<Option<std::time::Duration>, CommandError> {
+  let daily_limit = match config_lock.kiosk_daily_limit_minutes {
+    Some(minutes) if app_handle.state::<KioskLock>().is_locked() => u64::from(minutes) * 60,
+    _ => return Ok(session_limit),
+  };
+  let remaining = daily_limit.saturating_sub(crate::playtime::today_total(app_handle));
+  if remaining == 0 {
+    return Err(CommandError::Locked(
+      "Today's playtime is used up".to_string(),
+    ));
+  }
+  let remaining = std::time::Duration::from_secs(remaining);
+  Ok(Some(
+    session_limit.map_or(remaining, |limit| limit.min(remaining)),
+  ))
+}
+
+fn start_game(
+  config_lock: &tokio::sync::MutexGuard<'_, LauncherConfig>,
+  app_handle: tauri::AppHandle,
+  game_name: String,
+  in_debug: bool,
+  session_limit: Option<std::time::Duration>,
+) -> Result<(), CommandError> {
+  let config_info = common_prelude(config_lock)?;
+  let session_limit = limit_to_remaining_playtime(config_lock, &app_handle, session_limit)?;
 
   let tooling_version = Version::parse(
     config_info
@@ -529,6 +1415,27 @@ pub async fn launch_game(
   let data_folder = get_data_dir(&config_info, &game_name, false)?;
   let exec_info = get_exec_location(&config_info, "gk")?;
 
+  if !config_lock
+    .requirements
+    .bypass_requirements
+    .unwrap_or(false)
+  {
+    let missing_libraries = find_missing_libraries(&exec_info.executable_path);
+    if !missing_libraries.is_empty() {
+      let details: Vec<String> = missing_libraries
+        .iter()
+        .map(|library| match &library.package_hint {
+          Some(hint) => format!("{} ({})", library.name, hint),
+          None => library.name.clone(),
+        })
+        .collect();
+      return Err(CommandError::BinaryExecution(format!(
+        "The game is missing required system libraries: {}",
+        details.join(", ")
+      )));
+    }
+  }
+
   let mut args;
   // NOTE - order unfortunately matters for gk args
   if tooling_version.major == 0 && tooling_version.minor <= 1 && tooling_version.patch < 35 {
@@ -546,7 +1453,7 @@ pub async fn launch_game(
     args = vec![
       "-v".to_string(),
       "--game".to_string(),
-      game_name,
+      game_name.clone(),
       "--proj-path".to_string(),
       data_folder.to_string_lossy().into_owned(),
       "--".to_string(),
@@ -558,6 +1465,12 @@ pub async fn launch_game(
     }
   }
 
+  if let Err(err) =
+    super::game::apply_display_settings(&app_handle.state(), config_lock, &game_name)
+  {
+    log::warn!("Unable to apply display settings to the game: {}", err);
+  }
+
   log::info!(
     "Launching game version {:?} -> {:?} with args: {:?}",
     &config_info.active_version,
@@ -566,16 +1479,73 @@ pub async fn launch_game(
   );
 
   let log_file = create_log_file(&app_handle, "game.log", false)?;
-  let mut command = Command::new(exec_info.executable_path);
+  let mut command = tool_command(
+    &exec_info.executable_path,
+    &exec_info.executable_dir,
+    config_lock.tool_environment("gk"),
+  );
   command
     .args(args)
     .stdout(log_file.try_clone().unwrap())
-    .stderr(log_file)
-    .current_dir(exec_info.executable_dir);
+    .stderr(log_file);
   #[cfg(windows)]
   {
-    command.creation_flags(0x08000000);
+    let mut creation_flags = 0x08000000;
+    if let Some(priority) = &config_lock.game_process_priority {
+      creation_flags |= crate::util::os::priority_creation_flags(priority);
+    }
+    command.creation_flags(creation_flags);
   }
-  command.spawn()?;
+  let report = LaunchFailureReport::new(&command);
+  let child = match command.spawn() {
+    Ok(child) => child,
+    Err(err) => {
+      let mut report = report;
+      report.spawn_error = Some(err.to_string());
+      report.finalize(&app_handle, &exec_info.executable_path);
+      return Err(err.into());
+    }
+  };
+
+  // Process options are best-effort, the game is already running at this point
+  #[cfg(unix)]
+  if let Some(priority) = &config_lock.game_process_priority {
+    if let Err(err) = crate::util::os::set_process_priority(&child, priority) {
+      log::warn!(
+        "Unable to set game process priority to {:?}: {}",
+        priority,
+        err
+      );
+    }
+  }
+  if let Some(mask) = config_lock.game_cpu_affinity {
+    if let Err(err) = crate::util::os::set_process_affinity(&child, mask) {
+      log::warn!("Unable to set game CPU affinity to {:#x}: {}", mask, err);
+    }
+  }
+  let snapshot_hotkey = super::snapshots::register_hotkey(&app_handle, config_lock, &game_name);
+  let (launch_action, exit_action) = config_lock.game_launch_actions(&game_name);
+  let stop_request = if in_debug {
+    Some(app_handle.state::<DebugSessions>().track(&game_name))
+  } else {
+    None
+  };
+  watch_game_process(
+    app_handle.clone(),
+    child,
+    report,
+    exec_info.executable_path,
+    GameSupervision {
+      game_name,
+      session_limit,
+      stop_request,
+      snapshot_hotkey,
+      launch_action,
+      exit_action,
+      session_capture: config_lock.session_capture(),
+    },
+  );
+  // Last, exiting the launcher is one of the options
+  super::window::apply_launch_action(&app_handle, launch_action);
   Ok(())
 }
@@ -0,0 +1,272 @@
+// Checks what a tooling release needs from the launcher before it's downloaded, so a version
+// that can't be launched by this launcher isn't installed in the first place
+//
+// A release declares the oldest launcher it works with, and any breaking changes, either in a
+// `launcher-manifest.json` asset or in a front-matter block at the top of its release notes:
+//
+// ---
+// minLauncherVersion: 2.2.0
+// breakingChanges:
+// - Game settings from older versions are reset
+// ---
+//
+// Only releases published on GitHub can be checked, and a release that can't be checked (ie.
+// while offline) is reported as such rather than blocking the download.
+
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  config::{release_platform, LauncherConfig, VersionFolder, VersionName},
+  util::github::GithubClient,
+};
+
+use super::CommandError;
+
+const MANIFEST_ASSET_NAME: &str = "launcher-manifest.json";
+
+#[derive(Debug, Deserialize)]
+struct GithubRelease {
+  body: Option<String>,
+  #[serde(default)]
+  assets: Vec<GithubAsset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubAsset {
+  name: String,
+  browser_download_url: String,
+  /// ie. `sha256:<hex>`, only reported for assets uploaded after GitHub started computing them
+  digest: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReleaseManifest {
+  min_launcher_version: Option<String>,
+  #[serde(default)]
+  breaking_changes: Vec<String>,
+}
+
+impl ReleaseManifest {
+  fn from_front_matter(body: &str) -> Option<Self> {
+    let block = body.trim_start().strip_prefix("---")?;
+    let block = &block[..block.find("\n---")?];
+    let mut manifest = Self::default();
+    let mut in_breaking_changes = false;
+    for line in block.lines().map(|line| line.trim()) {
+      if let Some(item) = line.strip_prefix("- ") {
+        if in_breaking_changes {
+          manifest.breaking_changes.push(item.trim().to_string());
+        }
+        continue;
+      }
+      let Some((key, value)) = line.split_once(':') else {
+        continue;
+      };
+      let value = value.trim();
+      in_breaking_changes = key.trim() == "breakingChanges";
+      match key.trim() {
+        "minLauncherVersion" if !value.is_empty() => {
+          manifest.min_launcher_version = Some(value.to_string())
+        }
+        "breakingChanges" if !value.is_empty() => manifest.breaking_changes.push(value.to_string()),
+        _ => {}
+      }
+    }
+    Some(manifest)
+  }
+}
+
+#[derive(Debug, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseCompatibility {
+  /// Whether the release's metadata could be retrieved at all
+  pub checked: bool,
+  pub compatible: bool,
+  pub launcher_version: String,
+  pub min_launcher_version: Option<String>,
+  pub breaking_changes: Vec<String>,
+}
+
+// `https://github.com/<owner>/<repo>` -> `(owner, repo)`
+pub fn github_repo(remote: &str) -> Option<(String, String)> {
+  let path = remote
+    .strip_prefix("https://github.com/")?
+    .trim_end_matches('/');
+  let (owner, repo) = path.split_once('/')?;
+  if owner.is_empty() || repo.is_empty() || repo.contains('/') {
+    return None;
+  }
+  Some((owner.to_string(), repo.trim_end_matches(".git").to_string()))
+}
+
+/// A client for GitHub's API, authenticated with the configured token if there is one
+pub fn github_client(config: &LauncherConfig) -> Result<GithubClient, String> {
+  GithubClient::new(config.github_token.clone()).map_err(|err| err.to_string())
+}
+
+/// The SHA-256 digest GitHub reports for a release asset, given the asset's download URL
+/// (`https://github.com/<owner>/<repo>/releases/download/<tag>/<asset>`).  `None` if the URL isn't
+/// a GitHub release asset, or GitHub has no digest for it
+pub async fn github_asset_sha256(
+  github: &GithubClient,
+  download_url: &str,
+) -> Result<Option<String>, String> {
+  let path = match download_url.strip_prefix("https://github.com/") {
+    Some(path) => path,
+    None => return Ok(None),
+  };
+  let parts: Vec<&str> = path.split('/').collect();
+  let (owner, repo, tag, asset_name) = match parts[..] {
+    [owner, repo, "releases", "download", tag, asset_name] => (owner, repo, tag, asset_name),
+    _ => return Ok(None),
+  };
+  let release: GithubRelease = github
+    .get_json(&format!(
+      "https://api.github.com/repos/{}/{}/releases/tags/{}",
+      owner, repo, tag
+    ))
+    .await
+    .map_err(|err| err.to_string())?;
+  Ok(
+    release
+      .assets
+      .into_iter()
+      .find(|asset| asset.name == asset_name)
+      .and_then(|asset| asset.digest)
+      .and_then(|digest| digest.strip_prefix("sha256:").map(|hex| hex.to_lowercase())),
+  )
+}
+
+/// The download URL of the asset for this platform in the `tag` release of a GitHub repository,
+/// for sources that don't have a download URL template.  `None` if the release has no asset that
+/// mentions the platform and has the archive format it's expected in
+pub async fn github_release_asset_url(
+  github: &GithubClient,
+  remote: &str,
+  tag: &str,
+) -> Result<Option<String>, String> {
+  let (owner, repo) =
+    github_repo(remote).ok_or(format!("'{}' is not a GitHub repository", remote))?;
+  let release: GithubRelease = github
+    .get_json(&format!(
+      "https://api.github.com/repos/{}/{}/releases/tags/{}",
+      owner, repo, tag
+    ))
+    .await
+    .map_err(|err| err.to_string())?;
+  let (platform, ext) = release_platform();
+  Ok(
+    release
+      .assets
+      .into_iter()
+      .find(|asset| {
+        let name = asset.name.to_lowercase();
+        name.contains(platform) && name.ends_with(&format!(".{}", ext))
+      })
+      .map(|asset| asset.browser_download_url),
+  )
+}
+
+pub fn parse_version(version: &str) -> Option<Version> {
+  Version::parse(version.trim().trim_start_matches('v')).ok()
+}
+
+async fn fetch_manifest(
+  github: &GithubClient,
+  remote: &str,
+  version: &VersionName,
+) -> Result<ReleaseManifest, String> {
+  let (owner, repo) =
+    github_repo(remote).ok_or(format!("'{}' is not a GitHub repository", remote))?;
+  let release: GithubRelease = github
+    .get_json(&format!(
+      "https://api.github.com/repos/{}/{}/releases/tags/{}",
+      owner, repo, version
+    ))
+    .await
+    .map_err(|err| err.to_string())?;
+
+  // An explicit manifest wins over whatever the release notes say
+  if let Some(asset) = release
+    .assets
+    .iter()
+    .find(|asset| asset.name == MANIFEST_ASSET_NAME)
+  {
+    return github
+      .http()
+      .get(&asset.browser_download_url)
+      .send()
+      .await
+      .and_then(|res| res.error_for_status())
+      .map_err(|err| err.to_string())?
+      .json()
+      .await
+      .map_err(|err| err.to_string());
+  }
+  Ok(
+    release
+      .body
+      .as_deref()
+      .and_then(ReleaseManifest::from_front_matter)
+      .unwrap_or_default(),
+  )
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn check_release_compatibility(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  app_handle: tauri::AppHandle,
+  version_folder: VersionFolder,
+  version: VersionName,
+) -> Result<ReleaseCompatibility, CommandError> {
+  let config_lock = config.lock().await;
+  let remote = config_lock
+    .version_sources()
+    .into_iter()
+    .find(|source| source.folder == version_folder)
+    .and_then(|source| source.remote);
+  let github = github_client(&config_lock).map_err(CommandError::VersionManagement)?;
+  drop(config_lock);
+  let launcher_version = app_handle.package_info().version.to_string();
+  let mut compatibility = ReleaseCompatibility {
+    checked: false,
+    compatible: true,
+    launcher_version: launcher_version.clone(),
+    min_launcher_version: None,
+    breaking_changes: vec![],
+  };
+  let Some(remote) = remote else {
+    return Ok(compatibility);
+  };
+  let manifest = match fetch_manifest(&github, &remote, &version).await {
+    Ok(manifest) => manifest,
+    Err(err) => {
+      log::warn!(
+        "Unable to check compatibility of {}:{}: {}",
+        version_folder,
+        version,
+        err
+      );
+      return Ok(compatibility);
+    }
+  };
+  compatibility.checked = true;
+  if let Some(min_version) = &manifest.min_launcher_version {
+    match (parse_version(min_version), parse_version(&launcher_version)) {
+      (Some(min_version), Some(launcher_version)) => {
+        compatibility.compatible = launcher_version >= min_version
+      }
+      _ => log::warn!(
+        "Unable to compare launcher version {} against {}",
+        launcher_version,
+        min_version
+      ),
+    }
+  }
+  compatibility.min_launcher_version = manifest.min_launcher_version;
+  compatibility.breaking_changes = manifest.breaking_changes;
+  Ok(compatibility)
+}
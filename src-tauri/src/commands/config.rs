@@ -1,26 +1,60 @@
-use crate::{config::LauncherConfig, util::file::delete_dir};
+use std::collections::HashMap;
+
+use crate::{
+  config::{
+    DisplayMode, GameExitAction, InstallCheckpoint, InstallRetryPolicy, LaunchAction,
+    LauncherConfig, MeteredBehavior, ProcessPriority, QuietHours, QuietHoursBehavior,
+    ReleaseChannel, SessionCapture, ToolEnvironment, VersionFolder, VersionName,
+    MAX_DOWNLOAD_CONNECTIONS,
+  },
+  config_sync::{self, ConfigSyncStatus},
+  gpu_probe::{self, ProbeOutcome},
+  install_metrics::{self, InstallPerformance},
+  paths::PathResolver,
+  remote_config::RemoteConfig,
+  schedule::{self, QuietHoursStatus},
+  setup_funnel::{SetupAttempt, SetupStep},
+  tasks::TaskManager,
+  util::{
+    file::{delete_dir, display_paths},
+    github::{self, GithubRateLimit},
+    os::missing_video_components,
+    process::TOOLS,
+  },
+  warnings::{Warning, WarningCode},
+};
+use serde::{Deserialize, Serialize};
+use sysinfo::{CpuExt, System, SystemExt};
 use tauri::Manager;
-use wgpu::InstanceDescriptor;
 
-use super::CommandError;
+use super::{
+  game::{has_video_settings, iso_data_path, purge_game_iso_data, validate_game_name},
+  history::{record_install, summarize_install, InstallSummary},
+  post_install::run_post_install_steps,
+  versions::{active_version_link_path, move_active_version_link, update_active_version_link},
+  CommandError,
+};
 
 #[tauri::command]
+#[specta::specta]
 pub async fn has_old_data_directory(app_handle: tauri::AppHandle) -> Result<bool, CommandError> {
-  match &app_handle.path_resolver().app_config_dir() {
+  match &app_handle.state::<PathResolver>().launcher_dir() {
     None => Ok(false),
     Some(dir) => Ok(dir.join("data").join("iso_data").exists()),
   }
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn delete_old_data_directory(app_handle: tauri::AppHandle) -> Result<(), CommandError> {
-  match &app_handle.path_resolver().app_config_dir() {
+  match &app_handle.state::<PathResolver>().launcher_dir() {
     None => Ok(()),
     Some(dir) => Ok(delete_dir(&dir.join("data"))?),
   }
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn reset_to_defaults(
   config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
 ) -> Result<(), CommandError> {
@@ -32,6 +66,7 @@ pub async fn reset_to_defaults(
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn get_install_directory(
   config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
 ) -> Result<Option<String>, CommandError> {
@@ -43,17 +78,155 @@ pub async fn get_install_directory(
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn set_install_directory(
   config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  app_handle: tauri::AppHandle,
   new_dir: String,
 ) -> Result<Option<String>, CommandError> {
+  let attempt = SetupAttempt::start(&app_handle, SetupStep::InstallDir);
   let mut config_lock = config.lock().await;
-  Ok(config_lock.set_install_directory(new_dir).map_err(|_| {
+  let old_link_path = active_version_link_path(&config_lock);
+  let invalid_reason = config_lock.set_install_directory(new_dir).map_err(|_| {
     CommandError::Configuration(format!("Unable to persist installation directory"))
-  })?)
+  })?;
+  match &invalid_reason {
+    Some(reason) => attempt.failed(reason.clone()),
+    None => {
+      move_active_version_link(&config_lock, old_link_path);
+      attempt.succeeded()
+    }
+  }
+  Ok(invalid_reason)
+}
+
+fn unavailable_directories(config: &LauncherConfig) -> Vec<String> {
+  display_paths(&config.unavailable_directories())
+}
+
+/// Logs the install directories that can't be reached at startup.  There's nothing listening for
+/// events yet, the frontend asks with `get_unavailable_install_directories` once it's up
+pub fn check_install_directories(config: &LauncherConfig) {
+  let paths = unavailable_directories(config);
+  if !paths.is_empty() {
+    log::warn!("Install directories are unavailable: {:?}", paths);
+  }
+}
+
+/// The install directories that can't be reached, most commands would fail in confusing ways
+/// while there are any
+#[tauri::command]
+#[specta::specta]
+pub async fn get_unavailable_install_directories(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<Vec<String>, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(unavailable_directories(&config_lock))
+}
+
+/// Waits for the install directories to come back (ie. the drive being plugged back in),
+/// returns whether they did before the timeout
+#[tauri::command]
+#[specta::specta]
+pub async fn wait_for_install_dir(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  app_handle: tauri::AppHandle,
+  timeout_seconds: Option<u32>,
+) -> Result<bool, CommandError> {
+  let started = std::time::Instant::now();
+  let timeout = timeout_seconds.map(|seconds| std::time::Duration::from_secs(u64::from(seconds)));
+  loop {
+    // Don't hold on to the config while waiting
+    if config.lock().await.unavailable_directories().is_empty() {
+      app_handle.emit_all("installDirAvailable", {})?;
+      return Ok(true);
+    }
+    if timeout.map_or(false, |timeout| started.elapsed() >= timeout) {
+      return Ok(false);
+    }
+    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+  }
+}
+
+/// Points the launcher at the new location of an install directory that went missing, keeping
+/// whatever is already installed there
+#[tauri::command]
+#[specta::specta]
+pub async fn choose_new_install_dir(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  app_handle: tauri::AppHandle,
+  new_dir: String,
+) -> Result<Option<String>, CommandError> {
+  let mut config_lock = config.lock().await;
+  let result = config_lock
+    .relocate_install_directory(new_dir)
+    .map_err(|_| {
+      CommandError::Configuration("Unable to persist installation directory".to_string())
+    })?;
+  if result.is_none() {
+    update_active_version_link(&config_lock);
+    app_handle.emit_all("installDirAvailable", {})?;
+  }
+  Ok(result)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_tooling_directory(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<Option<String>, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(
+    config_lock
+      .tooling_path()
+      .map(|path| path.to_string_lossy().into_owned()),
+  )
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_tooling_directory(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  new_dir: String,
+) -> Result<Option<String>, CommandError> {
+  let mut config_lock = config.lock().await;
+  let old_link_path = active_version_link_path(&config_lock);
+  let invalid_reason = config_lock
+    .set_tooling_directory(new_dir)
+    .map_err(|_| CommandError::Configuration("Unable to persist tooling directory".to_string()))?;
+  if invalid_reason.is_none() {
+    move_active_version_link(&config_lock, old_link_path);
+  }
+  Ok(invalid_reason)
 }
 
 #[tauri::command]
+#[specta::specta]
+pub async fn get_game_data_directory(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<Option<String>, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(
+    config_lock
+      .game_data_path()
+      .map(|path| path.to_string_lossy().into_owned()),
+  )
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_game_data_directory(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  new_dir: String,
+) -> Result<Option<String>, CommandError> {
+  let mut config_lock = config.lock().await;
+  config_lock
+    .set_game_data_directory(new_dir)
+    .map_err(|_| CommandError::Configuration("Unable to persist game data directory".to_string()))
+}
+
+#[tauri::command]
+#[specta::specta]
 pub async fn is_avx_requirement_met(
   config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
   force: bool,
@@ -73,11 +246,7 @@ pub async fn is_avx_requirement_met(
   }
   match config_lock.requirements.avx {
     None => {
-      if is_x86_feature_detected!("avx") || is_x86_feature_detected!("avx2") {
-        config_lock.requirements.avx = Some(true);
-      } else {
-        config_lock.requirements.avx = Some(false);
-      }
+      config_lock.requirements.avx = Some(CpuCapabilities::detect().meets_avx_requirement);
       config_lock.save_config().map_err(|err| {
         log::error!("Unable to persist avx requirement change {}", err);
         CommandError::Configuration(format!("Unable to persist avx requirement change"))
@@ -88,17 +257,67 @@ pub async fn is_avx_requirement_met(
   }
 }
 
-// NOTE - this is somewhat of a hack, instead of checking the actual specific version
-// of OpenGL, we just check if the system supports 3D textures and large uniform buffers
-// that match OpenGL 4.3's requirements.
-//
-// This is because OpenGL support requires libEGL -- which isn't always available on all
-// platforms, and GL support in general is waning.
-//
-// This should be good enough...hopefully.
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CpuCapabilities {
+  pub brand: String,
+  pub vendor: String,
+  pub sse: bool,
+  pub sse2: bool,
+  pub sse3: bool,
+  pub ssse3: bool,
+  pub sse41: bool,
+  pub sse42: bool,
+  pub avx: bool,
+  pub avx2: bool,
+  pub fma: bool,
+  pub meets_avx_requirement: bool,
+  /// The experimental builds without AVX still rely on SSE 4.1
+  pub can_run_avx_free_builds: bool,
+}
+
+impl CpuCapabilities {
+  fn detect() -> Self {
+    let mut system_info = System::new();
+    system_info.refresh_cpu();
+    let (brand, vendor) = match system_info.cpus().first() {
+      Some(cpu) => (cpu.brand().trim().to_string(), cpu.vendor_id().to_string()),
+      None => ("unknown".to_string(), "unknown".to_string()),
+    };
+    let avx = is_x86_feature_detected!("avx");
+    let avx2 = is_x86_feature_detected!("avx2");
+    let sse41 = is_x86_feature_detected!("sse4.1");
+    Self {
+      brand,
+      vendor,
+      sse: is_x86_feature_detected!("sse"),
+      sse2: is_x86_feature_detected!("sse2"),
+      sse3: is_x86_feature_detected!("sse3"),
+      ssse3: is_x86_feature_detected!("ssse3"),
+      sse41,
+      sse42: is_x86_feature_detected!("sse4.2"),
+      avx,
+      avx2,
+      fma: is_x86_feature_detected!("fma"),
+      meets_avx_requirement: avx || avx2,
+      can_run_avx_free_builds: sse41,
+    }
+  }
+}
+
+/// Everything about the CPU that matters for which builds it can run, unlike
+/// `is_avx_requirement_met` this isn't affected by bypassing the requirements
 #[tauri::command]
-pub async fn is_opengl_requirement_met(
-  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+#[specta::specta]
+pub async fn get_cpu_capabilities() -> Result<CpuCapabilities, CommandError> {
+  Ok(CpuCapabilities::detect())
+}
+
+// The probe itself runs in a helper process, see `gpu_probe`.  It can take a while, so the
+// config isn't held while it runs
+async fn opengl_requirement_met(
+  config: &tokio::sync::Mutex<LauncherConfig>,
+  app_handle: &tauri::AppHandle,
   force: bool,
 ) -> Result<Option<bool>, CommandError> {
   let mut config_lock = config.lock().await;
@@ -114,64 +333,14 @@ pub async fn is_opengl_requirement_met(
     }
     _ => (),
   }
-  match config_lock.requirements.opengl {
-    None => {
-      let instance = wgpu::Instance::new(InstanceDescriptor {
-        backends: wgpu::Backends::all(),
-        dx12_shader_compiler: wgpu::Dx12Compiler::default(),
-      });
-      let adapter = match instance
-        .request_adapter(&wgpu::RequestAdapterOptions {
-          power_preference: wgpu::PowerPreference::default(),
-          force_fallback_adapter: false,
-          compatible_surface: None,
-        })
-        .await
-      {
-        None => {
-          config_lock.set_opengl_requirement_met(None).map_err(|_| {
-            CommandError::Configuration(format!("Unable to persist opengl requirement change"))
-          })?;
-          return Err(CommandError::Configuration(format!(
-            "Unable to request GPU adapter to check for OpenGL support"
-          )));
-        }
-        Some(instance) => instance,
-      };
-
-      match adapter
-        .request_device(
-          &wgpu::DeviceDescriptor {
-            features: wgpu::Features::empty(),
-            limits: wgpu::Limits {
-              // These are OpenGL 4.3 minimums where these values
-              // were the maximum (not inclusive) for 4.2
-              max_texture_dimension_1d: 16384,
-              max_texture_dimension_2d: 16384,
-              max_texture_dimension_3d: 2048,
-              ..wgpu::Limits::default()
-            },
-            label: None,
-          },
-          None,
-        )
-        .await
-      {
-        Err(_err) => {
-          config_lock
-            .set_opengl_requirement_met(Some(false))
-            .map_err(|_| {
-              CommandError::Configuration(format!("Unable to persist opengl requirement change"))
-            })?;
-          return Err(CommandError::Configuration(format!(
-            "Unable to request GPU device with adequate OpenGL support - {:?}",
-            _err
-          )));
-        }
-        Ok(device) => device,
-      };
-
-      // If we didn't support the above limits, we would have returned an error already
+  if let Some(val) = config_lock.requirements.opengl {
+    return Ok(Some(val));
+  }
+  drop(config_lock);
+  let outcome = gpu_probe::probe_in_subprocess().await;
+  let mut config_lock = config.lock().await;
+  match outcome {
+    ProbeOutcome::Supported => {
       config_lock
         .set_opengl_requirement_met(Some(true))
         .map_err(|_| {
@@ -179,27 +348,153 @@ pub async fn is_opengl_requirement_met(
         })?;
       Ok(Some(true))
     }
-    Some(val) => Ok(Some(val)),
+    ProbeOutcome::Unsupported(reason) => {
+      config_lock
+        .set_opengl_requirement_met(Some(false))
+        .map_err(|_| {
+          CommandError::Configuration("Unable to persist opengl requirement change".to_string())
+        })?;
+      Err(CommandError::Configuration(format!(
+        "Unable to request GPU device with adequate OpenGL support - {}",
+        reason
+      )))
+    }
+    ProbeOutcome::NoAdapter => {
+      config_lock.set_opengl_requirement_met(None).map_err(|_| {
+        CommandError::Configuration("Unable to persist opengl requirement change".to_string())
+      })?;
+      Err(CommandError::Configuration(
+        "Unable to request GPU adapter to check for OpenGL support".to_string(),
+      ))
+    }
+    // Not remembered, the next check tries again
+    ProbeOutcome::Failed(reason) => {
+      log::error!("GPU probe failed: {}", reason);
+      let warning = Warning::new(
+        WarningCode::RequirementProbeFailed,
+        format!(
+          "Checking for OpenGL support failed ({}), it may be a graphics driver problem",
+          reason
+        ),
+      );
+      if let Err(err) = app_handle.emit_all("requirementProbeFailed", warning) {
+        log::warn!("Unable to emit requirementProbeFailed: {}", err);
+      }
+      Ok(None)
+    }
+  }
+}
+
+/// The setup checks AVX first and OpenGL last, so this is where the requirements step of the
+/// setup funnel is recorded, going by both
+#[tauri::command]
+#[specta::specta]
+pub async fn is_opengl_requirement_met(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  app_handle: tauri::AppHandle,
+  force: bool,
+) -> Result<Option<bool>, CommandError> {
+  let attempt = SetupAttempt::start(&app_handle, SetupStep::Requirements);
+  let result = opengl_requirement_met(&config, &app_handle, force).await;
+  let config_lock = config.lock().await;
+  let avx_met = config_lock.requirements.bypass_requirements == Some(true)
+    || config_lock.requirements.avx != Some(false);
+  match &result {
+    Ok(_) if !avx_met => attempt.failed("AVX isn't supported"),
+    Ok(Some(true)) => attempt.succeeded(),
+    Ok(Some(false)) => attempt.failed("OpenGL 4.3 isn't supported"),
+    Ok(None) => attempt.failed("OpenGL support couldn't be determined"),
+    Err(err) => attempt.failed(err.to_string()),
   }
+  result
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn finalize_installation(
   config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  task_manager: tauri::State<'_, TaskManager>,
   app_handle: tauri::AppHandle,
   game_name: String,
-) -> Result<(), CommandError> {
+) -> Result<InstallSummary, CommandError> {
   let mut config_lock = config.lock().await;
   config_lock
     .update_installed_game_version(&game_name, true)
     .map_err(|_| {
       CommandError::Configuration(format!("Unable to persist game installation status"))
     })?;
-  app_handle.emit_all("gameInstalled", {})?;
-  Ok(())
+  let purged_iso_data =
+    (!config_lock.game_keeps_iso_data(&game_name)).then(|| iso_data_path(&config_lock, &game_name));
+  // The post-install steps copy and check files, they only read from a copy of the config so it
+  // isn't held meanwhile
+  let config_snapshot = config_lock.clone();
+  drop(config_lock);
+  let mut summary = {
+    let (app_handle, game_name) = (app_handle.clone(), game_name.clone());
+    tokio::task::spawn_blocking(move || {
+      let step_failures = run_post_install_steps(&app_handle, &config_snapshot, &game_name);
+      let mut summary = summarize_install(
+        &app_handle,
+        &config_snapshot,
+        &app_handle.state::<TaskManager>(),
+        &game_name,
+      );
+      summary.warnings.extend(step_failures);
+      summary
+    })
+    .await
+    .map_err(|err| {
+      CommandError::GameManagement(format!("Unable to finish the installation: {}", err))
+    })?
+  };
+  // Only once the post-install steps are done with it, verifying looks at the ISO data
+  if let Some(iso_data) = purged_iso_data {
+    let purged = iso_data
+      .and_then(|iso_data| purge_game_iso_data(&app_handle, &task_manager, iso_data, &game_name));
+    if let Err(err) = purged {
+      log::warn!("Unable to remove ISO data for {}: {}", game_name, err);
+      summary.warnings.push(Warning::new(
+        WarningCode::IsoDataNotPurged,
+        format!("Unable to remove the ISO data: {}", err),
+      ));
+    }
+  }
+  log::info!("Installation finished: {:?}", summary);
+  record_install(&app_handle, &summary);
+  app_handle.emit_all("gameInstalled", &summary)?;
+  Ok(summary)
 }
 
+/// How long each step of the game's last install (or recompile) took and what held it up, `None`
+/// if none were measured
 #[tauri::command]
+#[specta::specta]
+pub async fn get_install_performance(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  game_name: String,
+) -> Result<Option<InstallPerformance>, CommandError> {
+  let config_lock = config.lock().await;
+  validate_game_name(&game_name)?;
+  let stages = install_metrics::stages(&config_lock, &game_name);
+  if stages.is_empty() {
+    return Ok(None);
+  }
+  Ok(Some(InstallPerformance::from_stages(stages)))
+}
+
+/// Where an interrupted installation of the game left off, if there is one to resume
+#[tauri::command]
+#[specta::specta]
+pub async fn get_install_checkpoint(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  game_name: String,
+) -> Result<Option<InstallCheckpoint>, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(config_lock.game_install_checkpoint(&game_name))
+}
+
+#[tauri::command]
+#[specta::specta]
 pub async fn is_game_installed(
   config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
   game_name: String,
@@ -233,6 +528,7 @@ pub async fn is_game_installed(
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn get_installed_version(
   config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
   game_name: String,
@@ -242,6 +538,7 @@ pub async fn get_installed_version(
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn get_installed_version_folder(
   config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
   game_name: String,
@@ -251,24 +548,22 @@ pub async fn get_installed_version_folder(
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn save_active_version_change(
   config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
-  version_folder: String,
-  new_active_version: String,
+  version_folder: VersionFolder,
+  new_active_version: VersionName,
 ) -> Result<(), CommandError> {
   let mut config_lock = config.lock().await;
   config_lock
-    .set_active_version_folder(version_folder)
-    .map_err(|_| {
-      CommandError::Configuration(format!("Unable to persist active version folder change"))
-    })?;
-  config_lock
-    .set_active_version(new_active_version)
+    .switch_active_version(version_folder.to_string(), new_active_version.to_string())
     .map_err(|_| CommandError::Configuration(format!("Unable to persist active version change")))?;
+  update_active_version_link(&config_lock);
   Ok(())
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn get_active_tooling_version(
   config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
 ) -> Result<Option<String>, CommandError> {
@@ -277,6 +572,7 @@ pub async fn get_active_tooling_version(
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn get_active_tooling_version_folder(
   config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
 ) -> Result<Option<String>, CommandError> {
@@ -285,6 +581,7 @@ pub async fn get_active_tooling_version_folder(
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn get_locale(
   config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
 ) -> Result<Option<String>, CommandError> {
@@ -293,18 +590,21 @@ pub async fn get_locale(
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn set_locale(
   config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
   locale: String,
 ) -> Result<(), CommandError> {
   let mut config_lock = config.lock().await;
   config_lock
-    .set_locale(locale)
+    .set_locale(locale.clone())
     .map_err(|_| CommandError::Configuration(format!("Unable to persist locale change")))?;
+  crate::i18n::set_locale(Some(locale));
   Ok(())
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn get_bypass_requirements(
   config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
 ) -> Result<bool, CommandError> {
@@ -316,6 +616,7 @@ pub async fn get_bypass_requirements(
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn set_bypass_requirements(
   config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
   bypass: bool,
@@ -326,3 +627,673 @@ pub async fn set_bypass_requirements(
   })?;
   Ok(())
 }
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_launch_on_startup(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<bool, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(config_lock.launch_on_startup.unwrap_or(false))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_launch_on_startup(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  enabled: bool,
+  minimized: bool,
+) -> Result<(), CommandError> {
+  let mut config_lock = config.lock().await;
+  crate::util::os::set_launch_on_startup(enabled, minimized).map_err(|err| {
+    log::error!("Unable to update OS auto-start registration: {}", err);
+    CommandError::OSOperation("Unable to register the launcher to start on login".to_string())
+  })?;
+  config_lock
+    .set_launch_on_startup(enabled, minimized)
+    .map_err(|_| CommandError::Configuration("Unable to persist auto-start change".to_string()))?;
+  Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct GameProcessOptions {
+  pub priority: Option<ProcessPriority>,
+  pub cpu_affinity: Option<u64>,
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_game_process_options(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<GameProcessOptions, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(GameProcessOptions {
+    priority: config_lock.game_process_priority,
+    cpu_affinity: config_lock.game_cpu_affinity,
+  })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_game_process_options(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  options: GameProcessOptions,
+) -> Result<(), CommandError> {
+  let mut config_lock = config.lock().await;
+  config_lock
+    .set_game_process_options(options.priority, options.cpu_affinity)
+    .map_err(|err| {
+      log::error!("Unable to persist game process options: {}", err);
+      CommandError::Configuration("Unable to persist game process options".to_string())
+    })?;
+  Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct GameDisplayOptions {
+  pub display_mode: Option<DisplayMode>,
+  pub monitor: Option<u32>,
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_game_display_options(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<GameDisplayOptions, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(GameDisplayOptions {
+    display_mode: config_lock.game_display_mode,
+    monitor: config_lock.game_monitor,
+  })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_game_display_options(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  options: GameDisplayOptions,
+) -> Result<(), CommandError> {
+  let mut config_lock = config.lock().await;
+  config_lock
+    .set_game_display_options(options.display_mode, options.monitor)
+    .map_err(|_| {
+      CommandError::Configuration("Unable to persist game display options".to_string())
+    })?;
+  Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct GameVideoOptions {
+  pub playback: Option<bool>,
+  pub hardware_decoding: Option<bool>,
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_game_video_options(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<GameVideoOptions, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(GameVideoOptions {
+    playback: config_lock.game_video_playback,
+    hardware_decoding: config_lock.game_video_hardware_decoding,
+  })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_game_video_options(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  options: GameVideoOptions,
+) -> Result<(), CommandError> {
+  let mut config_lock = config.lock().await;
+  config_lock
+    .set_game_video_options(options.playback, options.hardware_decoding)
+    .map_err(|_| CommandError::Configuration("Unable to persist game video options".to_string()))?;
+  Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct VideoPlaybackSupport {
+  /// Whether the installed version has video options, `None` until the game has been booted
+  pub has_video_settings: Option<bool>,
+  /// Codecs or drivers that are missing, which results in cutscenes being a black screen
+  pub missing_components: Vec<String>,
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_video_playback_support(
+  paths: tauri::State<'_, PathResolver>,
+  game_name: String,
+) -> Result<VideoPlaybackSupport, CommandError> {
+  Ok(VideoPlaybackSupport {
+    has_video_settings: has_video_settings(&paths, &game_name)?,
+    missing_components: missing_video_components(),
+  })
+}
+
+/// How many days launcher generated data is kept for, `None` keeps it indefinitely
+#[derive(Debug, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionPolicy {
+  pub launcher_log_days: Option<u32>,
+  pub install_log_days: Option<u32>,
+  pub crash_report_days: Option<u32>,
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_retention_policy(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<RetentionPolicy, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(RetentionPolicy {
+    launcher_log_days: config_lock.launcher_log_retention_days,
+    install_log_days: config_lock.install_log_retention_days,
+    crash_report_days: config_lock.crash_report_retention_days,
+  })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_retention_policy(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  policy: RetentionPolicy,
+) -> Result<(), CommandError> {
+  let mut config_lock = config.lock().await;
+  config_lock
+    .set_retention_policy(
+      policy.launcher_log_days,
+      policy.install_log_days,
+      policy.crash_report_days,
+    )
+    .map_err(|_| CommandError::Configuration("Unable to persist retention policy".to_string()))?;
+  Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_maintain_active_version_link(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<bool, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(config_lock.maintain_active_version_link.unwrap_or(false))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_maintain_active_version_link(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  enabled: bool,
+) -> Result<(), CommandError> {
+  let mut config_lock = config.lock().await;
+  config_lock
+    .set_maintain_active_version_link(enabled)
+    .map_err(|_| {
+      CommandError::Configuration("Unable to persist active version link option".to_string())
+    })?;
+  update_active_version_link(&config_lock);
+  Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_auto_update_tooling(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<bool, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(config_lock.auto_update_tooling.unwrap_or(false))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_auto_update_tooling(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  enabled: bool,
+) -> Result<(), CommandError> {
+  let mut config_lock = config.lock().await;
+  config_lock
+    .set_auto_update_tooling(enabled)
+    .map_err(|_| CommandError::Configuration("Unable to persist auto update option".to_string()))?;
+  Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_dedupe_versions(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<bool, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(config_lock.dedupe_versions.unwrap_or(false))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_dedupe_versions(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  enabled: bool,
+) -> Result<(), CommandError> {
+  let mut config_lock = config.lock().await;
+  config_lock.set_dedupe_versions(enabled).map_err(|_| {
+    CommandError::Configuration("Unable to persist deduplication option".to_string())
+  })?;
+  Ok(())
+}
+
+/// Whether a GitHub access token is set, the token itself is never sent back
+#[tauri::command]
+#[specta::specta]
+pub async fn has_github_token(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<bool, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(config_lock.github_token.is_some())
+}
+
+/// Sets the token GitHub's API is accessed with, `None` (or an empty one) to go back to anonymous
+/// requests
+#[tauri::command]
+#[specta::specta]
+pub async fn set_github_token(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  token: Option<String>,
+) -> Result<(), CommandError> {
+  let mut config_lock = config.lock().await;
+  config_lock
+    .set_github_token(token)
+    .map_err(|_| CommandError::Configuration("Unable to persist GitHub token".to_string()))?;
+  github::reset_rate_limit();
+  Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_github_rate_limit() -> Result<GithubRateLimit, CommandError> {
+  Ok(github::rate_limit())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_quiet_hours(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<Option<QuietHours>, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(config_lock.quiet_hours.clone())
+}
+
+/// `None` turns quiet hours off
+#[tauri::command]
+#[specta::specta]
+pub async fn set_quiet_hours(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  quiet_hours: Option<QuietHours>,
+) -> Result<(), CommandError> {
+  if let Some(quiet_hours) = &quiet_hours {
+    for time in [&quiet_hours.start, &quiet_hours.end] {
+      if schedule::parse_time(time).is_none() {
+        return Err(CommandError::Configuration(format!(
+          "'{}' is not a time of day, expected HH:MM",
+          time
+        )));
+      }
+    }
+    if quiet_hours.behavior == QuietHoursBehavior::CapBandwidth
+      && quiet_hours.bandwidth_cap_kbps.unwrap_or(0) == 0
+    {
+      return Err(CommandError::Configuration(
+        "Capping bandwidth during quiet hours needs a cap".to_string(),
+      ));
+    }
+  }
+  let mut config_lock = config.lock().await;
+  config_lock
+    .set_quiet_hours(quiet_hours)
+    .map_err(|_| CommandError::Configuration("Unable to persist quiet hours".to_string()))?;
+  Ok(())
+}
+
+/// Whether it's quiet hours right now
+#[tauri::command]
+#[specta::specta]
+pub async fn get_quiet_hours_status(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<QuietHoursStatus, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(schedule::status(&config_lock))
+}
+
+/// The hosts texture packs and mods can be downloaded from without asking
+#[tauri::command]
+#[specta::specta]
+pub async fn get_content_hosts(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<Vec<String>, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(config_lock.content_hosts())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_content_hosts(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  hosts: Vec<String>,
+) -> Result<(), CommandError> {
+  let hosts: Vec<String> = hosts
+    .iter()
+    .map(|host| host.trim().to_lowercase())
+    .filter(|host| !host.is_empty())
+    .collect();
+  // Only domains, a scheme or path would never match and silently allow nothing
+  if let Some(host) = hosts.iter().find(|host| {
+    !host
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || "-.*".contains(c))
+  }) {
+    return Err(CommandError::Configuration(format!(
+      "'{}' is not a domain name",
+      host
+    )));
+  }
+  let mut config_lock = config.lock().await;
+  config_lock
+    .set_content_hosts(hosts)
+    .map_err(|_| CommandError::Configuration("Unable to persist content hosts".to_string()))?;
+  Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_release_channel(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<ReleaseChannel, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(config_lock.release_channel())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_release_channel(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  channel: ReleaseChannel,
+) -> Result<(), CommandError> {
+  let mut config_lock = config.lock().await;
+  config_lock
+    .set_release_channel(channel)
+    .map_err(|_| CommandError::Configuration("Unable to persist release channel".to_string()))?;
+  Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_session_capture(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<SessionCapture, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(config_lock.session_capture())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_session_capture(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  capture: SessionCapture,
+) -> Result<(), CommandError> {
+  let mut config_lock = config.lock().await;
+  config_lock
+    .set_session_capture(capture)
+    .map_err(|_| CommandError::Configuration("Unable to persist session capture".to_string()))?;
+  Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_metered_behavior(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<MeteredBehavior, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(
+    config_lock
+      .metered_behavior
+      .unwrap_or(MeteredBehavior::Confirm),
+  )
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_metered_behavior(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  behavior: MeteredBehavior,
+) -> Result<(), CommandError> {
+  let mut config_lock = config.lock().await;
+  config_lock.set_metered_behavior(behavior).map_err(|_| {
+    CommandError::Configuration("Unable to persist metered connection option".to_string())
+  })?;
+  Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_download_connections(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<u32, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(config_lock.download_connections())
+}
+
+/// How many connections large downloads are split over, 1 turns chunked downloads off
+#[tauri::command]
+#[specta::specta]
+pub async fn set_download_connections(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  connections: u32,
+) -> Result<(), CommandError> {
+  if connections == 0 || connections > MAX_DOWNLOAD_CONNECTIONS {
+    return Err(CommandError::Configuration(format!(
+      "Downloads can use between 1 and {} connections",
+      MAX_DOWNLOAD_CONNECTIONS
+    )));
+  }
+  let mut config_lock = config.lock().await;
+  config_lock
+    .set_download_connections(connections)
+    .map_err(|_| {
+      CommandError::Configuration("Unable to persist download connections".to_string())
+    })?;
+  Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_versions_to_keep(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<u32, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(config_lock.versions_to_keep())
+}
+
+/// How many of each version folder's versions `prune_versions` keeps, the active one is always
+/// kept on top of that
+#[tauri::command]
+#[specta::specta]
+pub async fn set_versions_to_keep(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  count: u32,
+) -> Result<(), CommandError> {
+  let mut config_lock = config.lock().await;
+  config_lock
+    .set_versions_to_keep(count)
+    .map_err(|_| CommandError::Configuration("Unable to persist versions to keep".to_string()))?;
+  Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_tool_environments(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<HashMap<String, ToolEnvironment>, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(config_lock.tool_environments.clone().unwrap_or_default())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_tool_environment(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  tool: String,
+  environment: Option<ToolEnvironment>,
+) -> Result<(), CommandError> {
+  if !TOOLS.contains(&tool.as_str()) {
+    return Err(CommandError::Configuration(format!(
+      "'{}' isn't a tool the launcher runs, expected one of {}",
+      tool,
+      TOOLS.join(", ")
+    )));
+  }
+  let mut config_lock = config.lock().await;
+  config_lock
+    .set_tool_environment(tool, environment)
+    .map_err(|_| CommandError::Configuration("Unable to persist tool environment".to_string()))?;
+  Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_install_retry_policy(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<InstallRetryPolicy, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(config_lock.install_retry_policy.unwrap_or_default())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_install_retry_policy(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  policy: InstallRetryPolicy,
+) -> Result<(), CommandError> {
+  let mut config_lock = config.lock().await;
+  config_lock.set_install_retry_policy(policy).map_err(|_| {
+    CommandError::Configuration("Unable to persist install retry policy".to_string())
+  })?;
+  Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_config_sync_status(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<ConfigSyncStatus, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(config_sync::status(&config_lock))
+}
+
+/// Starts syncing the portable settings through `folder`, merging in what's already there, or
+/// stops syncing if `None`
+#[tauri::command]
+#[specta::specta]
+pub async fn set_config_sync_folder(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  folder: Option<String>,
+) -> Result<ConfigSyncStatus, CommandError> {
+  if let Some(folder) = &folder {
+    if !std::path::Path::new(folder).is_dir() {
+      return Err(CommandError::Configuration(format!(
+        "Sync folder '{}' does not exist",
+        folder
+      )));
+    }
+  }
+  let mut config_lock = config.lock().await;
+  config_lock
+    .set_sync_folder(folder)
+    .map_err(|_| CommandError::Configuration("Unable to persist sync folder".to_string()))?;
+  crate::i18n::set_locale(config_lock.locale.clone());
+  Ok(config_sync::status(&config_lock))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_remote_config(
+  remote_config: tauri::State<'_, tokio::sync::Mutex<RemoteConfig>>,
+) -> Result<RemoteConfig, CommandError> {
+  let remote_config_lock = remote_config.lock().await;
+  Ok(remote_config_lock.clone())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn is_feature_enabled(
+  remote_config: tauri::State<'_, tokio::sync::Mutex<RemoteConfig>>,
+  feature: String,
+  default: bool,
+) -> Result<bool, CommandError> {
+  let remote_config_lock = remote_config.lock().await;
+  Ok(remote_config_lock.is_feature_enabled(&feature, default))
+}
+
+#[derive(Debug, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct GameLaunchActions {
+  pub launch_action: LaunchAction,
+  pub exit_action: GameExitAction,
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_game_launch_actions(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  game_name: String,
+) -> Result<GameLaunchActions, CommandError> {
+  let config_lock = config.lock().await;
+  let (launch_action, exit_action) = config_lock.game_launch_actions(&game_name);
+  Ok(GameLaunchActions {
+    launch_action,
+    exit_action,
+  })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_game_launch_actions(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  game_name: String,
+  actions: GameLaunchActions,
+) -> Result<(), CommandError> {
+  let mut config_lock = config.lock().await;
+  config_lock
+    .set_game_launch_actions(&game_name, actions.launch_action, actions.exit_action)
+    .map_err(|err| {
+      log::error!("Unable to persist game launch actions: {}", err);
+      CommandError::Configuration("Unable to persist game launch actions".to_string())
+    })?;
+  Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct GameInstallInfo {
+  pub is_installed: bool,
+  pub version: Option<String>,
+  pub version_folder: Option<String>,
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_game_install_info(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  game_name: String,
+) -> Result<GameInstallInfo, CommandError> {
+  let config_lock = config.lock().await;
+  let is_installed = config_lock.is_game_installed(&game_name);
+  let non_empty = |value: String| if value.is_empty() { None } else { Some(value) };
+  Ok(GameInstallInfo {
+    is_installed,
+    version: non_empty(config_lock.game_install_version(&game_name)),
+    version_folder: non_empty(config_lock.game_install_version_folder(&game_name)),
+  })
+}
@@ -1,70 +1,369 @@
-use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
 
-use tauri::{api::path::config_dir, Manager};
+use tauri::Manager;
 
-use crate::config::LauncherConfig;
+use crate::{
+  config::{LauncherConfig, SupportedGame},
+  paths::{self, PathResolver},
+  tasks::TaskManager,
+  util::file::DeletionProgress,
+};
 
-use super::CommandError;
+use super::{
+  history::GameUninstalled, journal::GameDataTransaction, tasks::delete_dirs_for_task, CommandError,
+};
 
 #[tauri::command]
+#[specta::specta]
 pub async fn uninstall_game(
   config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  task_manager: tauri::State<'_, TaskManager>,
   app_handle: tauri::AppHandle,
   game_name: String,
-) -> Result<(), CommandError> {
-  let mut config_lock = config.lock().await;
+) -> Result<DeletionProgress, CommandError> {
+  let mut task = match task_manager.start_or_attach(
+    &app_handle,
+    format!("uninstall-{}", game_name),
+    "Uninstalling game",
+  ) {
+    Ok(task) => task,
+    Err(in_flight) => {
+      in_flight.finished().await;
+      return Err(CommandError::GameManagement(format!(
+        "{} was already being uninstalled",
+        game_name
+      )));
+    }
+  };
+  let result = async {
+    let mut config_lock = config.lock().await;
+
+    let game_data_path = match config_lock.game_data_path() {
+      None => {
+        return Err(CommandError::GameManagement(
+          "No game data directory set, can't perform uninstallation".to_string(),
+        ))
+      }
+      Some(path) => path,
+    };
+
+    let data_folder = paths::game_data_dir(&game_data_path, &game_name);
+
+    let removed = delete_dirs_for_task(
+      &app_handle,
+      &task,
+      &[
+        data_folder.join("decompiler_out"),
+        data_folder.join("iso_data"),
+        data_folder.join("out"),
+      ],
+    );
+    let removed = match removed {
+      Ok(removed) => removed,
+      // What's left can't be played, so it's uninstalled regardless
+      Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {
+        log::warn!("Uninstalling {} was cancelled part way through", game_name);
+        DeletionProgress::default()
+      }
+      Err(err) => {
+        task.finish(false, Some(err.to_string()));
+        return Err(err.into());
+      }
+    };
+
+    config_lock
+      .update_installed_game_version(&game_name, false)
+      .map_err(|_| {
+        CommandError::GameManagement("Unable to persist game installation status".to_string())
+      })?;
+    app_handle.emit_all("gameUninstalled", GameUninstalled { game_name })?;
+    task.finish(true, None);
+    Ok(removed)
+  }
+  .await;
+  if let Err(err) = &result {
+    task.finish(false, Some(err.to_string()));
+  }
+  result
+}
+
+// Where the extractor leaves the game's data from the ISO, which recompiling works from
+pub fn iso_data_path(config: &LauncherConfig, game_name: &str) -> Result<PathBuf, CommandError> {
+  match config.game_data_path() {
+    None => Err(CommandError::GameManagement(
+      "No game data directory set, can't locate ISO data".to_string(),
+    )),
+    Some(path) => Ok(
+      paths::game_data_dir(&path, game_name)
+        .join("iso_data")
+        .join(game_name),
+    ),
+  }
+}
 
-  let install_path = match &config_lock.installation_dir {
-    None => {
+pub fn has_game_iso_data(config: &LauncherConfig, game_name: &str) -> bool {
+  iso_data_path(config, game_name).map_or(false, |dir| dir.is_dir())
+}
+
+/// Why `what` can't be done without the game's ISO data, `None` if it's still there
+pub fn missing_iso_data(config: &LauncherConfig, game_name: &str, what: &str) -> Option<String> {
+  if has_game_iso_data(config, game_name) {
+    None
+  } else if config.is_game_installed(&game_name.to_string())
+    && !config.game_keeps_iso_data(game_name)
+  {
+    Some(format!(
+      "{}'s ISO data was removed after it was installed, select the ISO again to {}",
+      game_name, what
+    ))
+  } else {
+    Some(format!(
+      "{} has no extracted game data, select the ISO to {}",
+      game_name, what
+    ))
+  }
+}
+
+/// Removes the game's extracted ISO data at `iso_data`, recompiling will need the ISO again after
+/// this.  It takes a while, so it's best done without holding on to the config
+pub fn purge_game_iso_data(
+  app_handle: &tauri::AppHandle,
+  task_manager: &TaskManager,
+  iso_data: PathBuf,
+  game_name: &str,
+) -> Result<DeletionProgress, CommandError> {
+  let mut task = match task_manager.start_or_attach(
+    app_handle,
+    format!("purge-iso-data-{}", game_name),
+    "Removing ISO data",
+  ) {
+    Ok(task) => task,
+    Err(_) => {
       return Err(CommandError::GameManagement(format!(
-        "No installation directory set, can't perform uninstallation"
+        "{}'s ISO data is already being removed",
+        game_name
       )))
     }
-    Some(path) => Path::new(path),
   };
+  match delete_dirs_for_task(app_handle, &task, &[iso_data]) {
+    Ok(removed) => {
+      log::info!(
+        "Removed {} bytes of ISO data for {}",
+        removed.bytes_removed,
+        game_name
+      );
+      task.finish(true, None);
+      Ok(removed)
+    }
+    Err(err) => {
+      task.finish(false, Some(err.to_string()));
+      Err(err.into())
+    }
+  }
+}
 
-  let data_folder = Path::new(install_path)
-    .join("active")
-    .join(&game_name)
-    .join("data");
+/// Whether the game's extracted ISO data is still around to recompile from
+#[tauri::command]
+#[specta::specta]
+pub async fn has_iso_data(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  game_name: String,
+) -> Result<bool, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(has_game_iso_data(&config_lock, &game_name))
+}
 
-  std::fs::remove_dir_all(data_folder.join("decompiler_out"))?;
-  std::fs::remove_dir_all(data_folder.join("iso_data"))?;
-  std::fs::remove_dir_all(data_folder.join("out"))?;
+#[tauri::command]
+#[specta::specta]
+pub async fn purge_iso_data(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  task_manager: tauri::State<'_, TaskManager>,
+  app_handle: tauri::AppHandle,
+  game_name: String,
+) -> Result<DeletionProgress, CommandError> {
+  let iso_data = iso_data_path(&*config.lock().await, &game_name)?;
+  purge_game_iso_data(&app_handle, &task_manager, iso_data, &game_name)
+}
 
+/// Whether to keep the ISO data once the game is installed, the data that's already there is left
+/// alone either way
+#[tauri::command]
+#[specta::specta]
+pub async fn retain_iso_data(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  game_name: String,
+  retain: bool,
+) -> Result<(), CommandError> {
+  let mut config_lock = config.lock().await;
   config_lock
-    .update_installed_game_version(&game_name, false)
+    .set_game_keep_iso_data(&game_name, retain)
     .map_err(|_| {
-      CommandError::GameManagement(format!("Unable to persist game installation status"))
+      CommandError::Configuration("Unable to persist whether to keep ISO data".to_string())
     })?;
-  app_handle.emit_all("gameUninstalled", {})?;
   Ok(())
 }
 
-#[tauri::command]
-pub async fn reset_game_settings(game_name: String) -> Result<(), CommandError> {
-  let config_dir = match config_dir() {
-    None => {
-      return Err(CommandError::GameManagement(format!(
-        "Could not determine game config directory"
-      )))
+/// Game names end up in paths and URLs, so only the ones we know are accepted
+pub fn validate_game_name(game_name: &str) -> Result<SupportedGame, CommandError> {
+  SupportedGame::from_str(game_name)
+    .map_err(|_| CommandError::GameManagement(format!("Unknown game '{}'", game_name)))
+}
+
+fn game_settings_path(paths: &PathResolver, game_name: &str) -> Result<PathBuf, CommandError> {
+  paths.game_settings_file(game_name).ok_or_else(|| {
+    CommandError::GameManagement("Could not determine game config directory".to_string())
+  })
+}
+
+// Replaces the value of a `(key value)` form in the settings file, or adds it to the end
+// of the top-level settings form if the game hasn't written it yet
+fn set_game_setting(settings: &str, key: &str, value: &str) -> String {
+  let form_start = format!("({} ", key);
+  if let Some(start) = settings.find(&form_start) {
+    if let Some(len) = settings[start..].find(')') {
+      return format!(
+        "{}{}{}{}",
+        &settings[..start],
+        form_start,
+        value,
+        &settings[start + len..]
+      );
     }
-    Some(path) => path,
-  };
+  }
+  match settings.rfind(')') {
+    Some(end) => format!(
+      "{}  ({} {})\n{}",
+      &settings[..end],
+      key,
+      value,
+      &settings[end..]
+    ),
+    None => settings.to_string(),
+  }
+}
+
+// Video playback settings, only tooling versions that play videos write these
+const VIDEO_PLAYBACK_SETTING: &str = "video-playback";
+const VIDEO_HARDWARE_DECODING_SETTING: &str = "video-hw-decoding";
+
+fn has_game_setting(settings: &str, key: &str) -> bool {
+  settings.contains(&format!("({} ", key))
+}
+
+/// Whether the game's settings have video playback options, `None` if the game hasn't been
+/// booted yet to write its settings
+pub fn has_video_settings(
+  paths: &PathResolver,
+  game_name: &str,
+) -> Result<Option<bool>, CommandError> {
+  let path_to_settings = game_settings_path(paths, game_name)?;
+  if !path_to_settings.exists() {
+    return Ok(None);
+  }
+  let settings = std::fs::read_to_string(&path_to_settings)?;
+  Ok(Some(has_game_setting(&settings, VIDEO_PLAYBACK_SETTING)))
+}
 
-  let path_to_settings = config_dir
-    .join("OpenGOAL")
-    .join(game_name)
-    .join("settings")
-    .join("pc-settings.gc");
+/// Writes the launcher's display and video preferences into the game's settings before it boots
+pub fn apply_display_settings(
+  paths: &PathResolver,
+  config: &LauncherConfig,
+  game_name: &String,
+) -> Result<(), CommandError> {
+  let (display_mode, monitor) = (config.game_display_mode, config.game_monitor);
+  let (video_playback, video_hardware_decoding) = (
+    config.game_video_playback,
+    config.game_video_hardware_decoding,
+  );
+  if display_mode.is_none()
+    && monitor.is_none()
+    && video_playback.is_none()
+    && video_hardware_decoding.is_none()
+  {
+    return Ok(());
+  }
+  let path_to_settings = game_settings_path(paths, game_name)?;
+  // The game generates its settings on first boot, nothing to override until then
+  if !path_to_settings.exists() {
+    log::info!(
+      "No game settings at {}, not applying display settings",
+      path_to_settings.display()
+    );
+    return Ok(());
+  }
+  let original_settings = std::fs::read_to_string(&path_to_settings)?;
+  let mut settings = original_settings.clone();
+  if let Some(display_mode) = display_mode {
+    settings = set_game_setting(&settings, "display-mode", display_mode.game_setting_value());
+  }
+  if let Some(monitor) = monitor {
+    settings = set_game_setting(&settings, "monitor", &monitor.to_string());
+  }
+  // Unlike the display settings these aren't added if missing, older versions don't know them
+  let video_settings = [
+    (VIDEO_PLAYBACK_SETTING, video_playback),
+    (VIDEO_HARDWARE_DECODING_SETTING, video_hardware_decoding),
+  ];
+  for (key, value) in video_settings {
+    if let Some(value) = value {
+      if has_game_setting(&settings, key) {
+        settings = set_game_setting(&settings, key, if value { "#t" } else { "#f" });
+      }
+    }
+  }
+  if settings == original_settings {
+    return Ok(());
+  }
+  let mut transaction =
+    GameDataTransaction::begin_if_journaled(config, game_name, "Apply display settings")?;
+  if let Some(transaction) = &mut transaction {
+    transaction.stage_file(&path_to_settings)?;
+  }
+  std::fs::write(&path_to_settings, settings)?;
+  match transaction {
+    Some(transaction) => transaction.commit(),
+    None => Ok(()),
+  }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn reset_game_settings(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  paths: tauri::State<'_, PathResolver>,
+  game_name: String,
+) -> Result<(), CommandError> {
+  let path_to_settings = game_settings_path(&paths, &game_name)?;
   if path_to_settings.exists() {
     let mut backup_file = path_to_settings.clone();
     backup_file.set_file_name("pc-settings.old.gc");
+    let mut transaction = GameDataTransaction::begin_if_journaled(
+      &*config.lock().await,
+      &game_name,
+      "Reset game settings",
+    )?;
+    if let Some(transaction) = &mut transaction {
+      transaction.stage_replacement(&path_to_settings)?;
+      transaction.stage_replacement(&backup_file)?;
+    }
     std::fs::rename(path_to_settings, backup_file)?;
-    Ok(())
+    match transaction {
+      Some(transaction) => transaction.commit(),
+      None => Ok(()),
+    }
   } else {
     return Err(CommandError::GameManagement(format!(
       "Game config directory does not exist, cannot reset settings"
     )));
   }
 }
+
+/// Seconds played today, per game
+#[tauri::command]
+#[specta::specta]
+pub async fn get_playtime_today(
+  app_handle: tauri::AppHandle,
+) -> Result<std::collections::BTreeMap<String, u64>, CommandError> {
+  Ok(crate::playtime::today_by_game(&app_handle))
+}
@@ -0,0 +1,186 @@
+// Summaries of completed installs, emitted with `gameInstalled` for the completion screen and
+// kept in `install-history.json` in the config directory so there's a record of what was
+// installed when, and what went wrong along the way.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+use walkdir::WalkDir;
+
+use crate::{
+  config::LauncherConfig,
+  install_metrics::{self, InstallPerformance},
+  paths::{self, PathResolver},
+  sessions::{self, SessionSummary},
+  tasks::TaskManager,
+  util::file::{read_json, read_last_lines_from_file, update_json},
+  warnings::{Warning, WarningCode},
+};
+
+use super::CommandError;
+
+const MAX_HISTORY_ENTRIES: usize = 50;
+// Don't flood the completion screen if the tools are noisy
+const MAX_WARNINGS: usize = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallSummary {
+  pub game_name: String,
+  pub version: String,
+  pub version_folder: String,
+  pub finished_at: String,
+  /// From the start of extraction, `None` if that wasn't tracked (ie. the launcher restarted)
+  pub duration_seconds: Option<u64>,
+  pub disk_usage_bytes: u64,
+  /// Non-fatal problems with the install, including what the install tools logged as warnings
+  #[serde(default, deserialize_with = "deserialize_warnings")]
+  pub warnings: Vec<Warning>,
+  #[serde(default)]
+  pub performance: Option<InstallPerformance>,
+}
+
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct GameUninstalled {
+  pub game_name: String,
+}
+
+fn history_path(app_handle: &tauri::AppHandle) -> Option<PathBuf> {
+  app_handle
+    .state::<PathResolver>()
+    .launcher_file("install-history.json")
+}
+
+fn read_history(path: &Path) -> Vec<InstallSummary> {
+  read_json(path).unwrap_or_default()
+}
+
+fn install_duration(task_manager: &TaskManager, game_name: &String) -> Option<u64> {
+  let started_at = task_manager
+    .get(&format!("extract-{}", game_name))?
+    .started_at;
+  let started_at = chrono::DateTime::parse_from_rfc3339(&started_at).ok()?;
+  let elapsed = chrono::Local::now().signed_duration_since(started_at);
+  u64::try_from(elapsed.num_seconds()).ok()
+}
+
+fn disk_usage(config: &LauncherConfig, game_name: &str) -> u64 {
+  match config.game_data_path() {
+    Some(path) => WalkDir::new(paths::game_data_dir(&path, game_name))
+      .into_iter()
+      .filter_map(|entry| entry.ok())
+      .filter(|entry| entry.file_type().is_file())
+      .filter_map(|entry| entry.metadata().ok())
+      .map(|metadata| metadata.len())
+      .sum(),
+    None => 0,
+  }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RecordedWarning {
+  Warning(Warning),
+  // Installs recorded before warnings had codes only kept the tools' log lines
+  LogLine(String),
+}
+
+fn deserialize_warnings<'de, D: serde::Deserializer<'de>>(
+  deserializer: D,
+) -> Result<Vec<Warning>, D::Error> {
+  let warnings: Vec<RecordedWarning> = Deserialize::deserialize(deserializer)?;
+  Ok(
+    warnings
+      .into_iter()
+      .map(|warning| match warning {
+        RecordedWarning::Warning(warning) => warning,
+        RecordedWarning::LogLine(line) => Warning::new(WarningCode::ToolWarning, line),
+      })
+      .collect(),
+  )
+}
+
+fn install_warnings(app_handle: &tauri::AppHandle, config: &LauncherConfig) -> Vec<Warning> {
+  let mut warnings = vec![];
+  if config.requirements.bypass_requirements == Some(true) {
+    warnings.push(Warning::new(
+      WarningCode::RequirementsBypassed,
+      "Installed with the system requirements check bypassed",
+    ));
+  }
+  // All of the install steps log to the same file
+  if let Some(log_path) = app_handle.state::<PathResolver>().log_file("extractor.log") {
+    let log = read_last_lines_from_file(&log_path, 5000).unwrap_or_default();
+    warnings.extend(
+      log
+        .lines()
+        .filter(|line| line.to_lowercase().contains("[warn"))
+        .map(|line| Warning::new(WarningCode::ToolWarning, line.trim()))
+        .take(MAX_WARNINGS),
+    );
+  }
+  warnings
+}
+
+pub fn summarize_install(
+  app_handle: &tauri::AppHandle,
+  config: &LauncherConfig,
+  task_manager: &TaskManager,
+  game_name: &String,
+) -> InstallSummary {
+  InstallSummary {
+    game_name: game_name.clone(),
+    version: config.game_install_version(game_name),
+    version_folder: config.game_install_version_folder(game_name),
+    finished_at: chrono::Local::now().to_rfc3339(),
+    duration_seconds: install_duration(task_manager, game_name),
+    disk_usage_bytes: disk_usage(config, game_name),
+    warnings: install_warnings(app_handle, config),
+    performance: Some(InstallPerformance::from_stages(install_metrics::stages(
+      config, game_name,
+    )))
+    .filter(|performance| !performance.stages.is_empty()),
+  }
+}
+
+pub fn record_install(app_handle: &tauri::AppHandle, summary: &InstallSummary) {
+  let path = match history_path(app_handle) {
+    Some(path) => path,
+    None => return,
+  };
+  let result = update_json(&path, |history: &mut Vec<InstallSummary>| {
+    history.push(summary.clone());
+    if history.len() > MAX_HISTORY_ENTRIES {
+      history.drain(..history.len() - MAX_HISTORY_ENTRIES);
+    }
+  });
+  if let Err(err) = result {
+    log::warn!("Unable to record install history: {}", err);
+  }
+}
+
+/// Completed installs, most recent first
+#[tauri::command]
+#[specta::specta]
+pub async fn get_install_history(
+  app_handle: tauri::AppHandle,
+) -> Result<Vec<InstallSummary>, CommandError> {
+  let mut history = match history_path(&app_handle) {
+    Some(path) => read_history(&path),
+    None => vec![],
+  };
+  history.reverse();
+  Ok(history)
+}
+
+/// Summaries of past play sessions, most recent first, of `game_name` or every game
+#[tauri::command]
+#[specta::specta]
+pub async fn get_session_history(
+  app_handle: tauri::AppHandle,
+  game_name: Option<String>,
+) -> Result<Vec<SessionSummary>, CommandError> {
+  Ok(sessions::history(&app_handle, game_name.as_deref()))
+}
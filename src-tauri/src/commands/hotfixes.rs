@@ -0,0 +1,224 @@
+// Small fixes to installed game data that the project can ship without a new release, so a
+// broken file doesn't require a full recompile
+//
+// Each game has a manifest in the release feed, signed with the same key as the remote config.
+// A hotfix lists the tooling versions it targets and the files it replaces, along with their
+// expected hashes - both of the file it replaces (so it's only ever applied to the data it was
+// made for) and of the replacement.
+//
+// Every hotfix is applied in a `GameDataTransaction`, if anything about it fails all of its files
+// are rolled back.  Applied hotfixes are recorded next to the game data.
+
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  config::LauncherConfig,
+  paths,
+  remote_config::verify_signature,
+  util::{
+    crypto::sha256_hex,
+    file::{read_json, write_json},
+  },
+};
+
+use super::{game::validate_game_name, journal::GameDataTransaction, CommandError};
+
+const HOTFIX_FEED_URL: &str = "https://raw.githubusercontent.com/open-goal/launcher/main/hotfixes";
+const APPLIED_HOTFIXES_NAME: &str = ".launcher-hotfixes.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct HotfixFile {
+  /// Relative to the game's data folder
+  pub path: String,
+  pub url: String,
+  pub sha256: String,
+  /// The hash of the file being replaced, `None` if the hotfix adds a new file
+  pub previous_sha256: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DataHotfix {
+  pub id: String,
+  pub description: String,
+  /// Tooling versions the hotfix applies to
+  pub versions: Vec<String>,
+  pub files: Vec<HotfixFile>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct HotfixManifest {
+  #[serde(default)]
+  hotfixes: Vec<DataHotfix>,
+}
+
+fn data_dir(config: &LauncherConfig, game_name: &str) -> Result<PathBuf, CommandError> {
+  validate_game_name(game_name)?;
+  match config.game_data_path() {
+    Some(path) => Ok(paths::game_data_dir(&path, game_name)),
+    None => Err(CommandError::GameManagement(
+      "No game data directory set, can't apply hotfixes".to_string(),
+    )),
+  }
+}
+
+fn read_applied(data_dir: &Path) -> Vec<String> {
+  read_json(&data_dir.join(APPLIED_HOTFIXES_NAME)).unwrap_or_default()
+}
+
+fn target_path(data_dir: &Path, file: &HotfixFile) -> Result<PathBuf, CommandError> {
+  // The manifest is signed, but don't let it write outside of the game's data regardless
+  let relative = Path::new(&file.path);
+  if relative
+    .components()
+    .any(|component| !matches!(component, Component::Normal(_)))
+  {
+    return Err(CommandError::GameManagement(format!(
+      "Hotfix file '{}' is not inside the game data",
+      file.path
+    )));
+  }
+  Ok(data_dir.join(relative))
+}
+
+async fn fetch_manifest(game_name: &String) -> Result<HotfixManifest, CommandError> {
+  let url = format!("{}/{}.json", HOTFIX_FEED_URL, game_name);
+  let fetch =
+    |url: String| async move { reqwest::get(url).await?.error_for_status()?.text().await };
+  let content = fetch(url.clone()).await.map_err(|_| {
+    CommandError::GameManagement("Unable to retrieve the hotfix manifest".to_string())
+  })?;
+  let signature = fetch(format!("{}.minisig", url)).await.map_err(|_| {
+    CommandError::GameManagement("Unable to retrieve the hotfix manifest signature".to_string())
+  })?;
+  verify_signature(content.as_bytes(), &signature).map_err(|err| {
+    CommandError::GameManagement(format!("Hotfix manifest is not trusted: {}", err))
+  })?;
+  serde_json::from_str(&content)
+    .map_err(|_| CommandError::GameManagement("Hotfix manifest is invalid".to_string()))
+}
+
+/// Hotfixes that apply to the installed game and haven't been applied yet
+async fn pending_hotfixes(
+  config: &LauncherConfig,
+  game_name: &String,
+) -> Result<Vec<DataHotfix>, CommandError> {
+  if !config.is_game_installed(game_name) {
+    return Ok(vec![]);
+  }
+  let data_dir = data_dir(config, game_name)?;
+  let installed_version = config.game_install_version(game_name);
+  let applied = read_applied(&data_dir);
+  let manifest = fetch_manifest(game_name).await?;
+  Ok(
+    manifest
+      .hotfixes
+      .into_iter()
+      .filter(|hotfix| hotfix.versions.contains(&installed_version))
+      .filter(|hotfix| !applied.contains(&hotfix.id))
+      .collect(),
+  )
+}
+
+async fn apply_hotfix(
+  config: &LauncherConfig,
+  game_name: &String,
+  data_dir: &Path,
+  hotfix: &DataHotfix,
+) -> Result<(), CommandError> {
+  // Download and check everything up-front, so a failure leaves the game data untouched
+  let mut replacements = vec![];
+  for file in &hotfix.files {
+    let path = target_path(data_dir, file)?;
+    let current = std::fs::read(&path).ok().map(|bytes| sha256_hex(&bytes));
+    if current != file.previous_sha256 {
+      return Err(CommandError::GameManagement(format!(
+        "'{}' doesn't match what hotfix '{}' expects",
+        file.path, hotfix.id
+      )));
+    }
+    let bytes = reqwest::get(&file.url)
+      .await
+      .and_then(|res| res.error_for_status())
+      .map_err(|_| CommandError::GameManagement(format!("Unable to download '{}'", file.path)))?
+      .bytes()
+      .await
+      .map_err(|_| CommandError::GameManagement(format!("Unable to download '{}'", file.path)))?;
+    if sha256_hex(&bytes) != file.sha256.to_lowercase() {
+      return Err(CommandError::GameManagement(format!(
+        "Downloaded '{}' is corrupt",
+        file.path
+      )));
+    }
+    replacements.push((path, bytes));
+  }
+
+  let mut transaction = GameDataTransaction::begin(
+    config,
+    game_name,
+    &format!("Hotfix: {}", hotfix.description),
+  )?;
+  for (path, bytes) in &replacements {
+    transaction.stage_replacement(path)?;
+    if let Some(parent) = path.parent() {
+      std::fs::create_dir_all(parent)?;
+    }
+    // Write to a new file rather than over the old one, it might be hard-linked to by the
+    // journal backup
+    let tmp_path = path.with_extension("hotfix-tmp");
+    std::fs::write(&tmp_path, bytes)?;
+    std::fs::rename(&tmp_path, path)?;
+  }
+  let applied_path = data_dir.join(APPLIED_HOTFIXES_NAME);
+  transaction.stage_file(&applied_path)?;
+  let mut applied = read_applied(data_dir);
+  applied.push(hotfix.id.clone());
+  write_json(&applied_path, &applied)?;
+  transaction.commit()
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn check_data_hotfixes(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  game_name: String,
+) -> Result<Vec<DataHotfix>, CommandError> {
+  let config_lock = config.lock().await;
+  pending_hotfixes(&config_lock, &game_name).await
+}
+
+/// Applies every pending hotfix, returning the ids of the ones that were applied.  A hotfix that
+/// fails is rolled back and the rest are still attempted
+#[tauri::command]
+#[specta::specta]
+pub async fn apply_data_hotfixes(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  game_name: String,
+) -> Result<Vec<String>, CommandError> {
+  let config_lock = config.lock().await;
+  let data_dir = data_dir(&config_lock, &game_name)?;
+  let mut applied = vec![];
+  let mut failures = vec![];
+  for hotfix in pending_hotfixes(&config_lock, &game_name).await? {
+    match apply_hotfix(&config_lock, &game_name, &data_dir, &hotfix).await {
+      Ok(()) => {
+        log::info!("Applied hotfix '{}' to {}", hotfix.id, game_name);
+        applied.push(hotfix.id);
+      }
+      Err(err) => {
+        log::error!("Unable to apply hotfix '{}': {}", hotfix.id, err);
+        failures.push(hotfix.id);
+      }
+    }
+  }
+  if applied.is_empty() && !failures.is_empty() {
+    return Err(CommandError::GameManagement(format!(
+      "Unable to apply hotfixes: {}",
+      failures.join(", ")
+    )));
+  }
+  Ok(applied)
+}
@@ -0,0 +1,253 @@
+// Changes the launcher makes to a game's files (texture packs, settings) go through a
+// `GameDataTransaction`.  Every file is backed up before it's first modified and the change is
+// recorded in a per-game journal when committed, so if the game stops booting after a change
+// it can be undone with `revert_last_change`.
+//
+// A transaction that's dropped without being committed (ie. an error part way through) restores
+// its backups straight away.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  config::LauncherConfig,
+  util::file::{read_json, write_json},
+};
+
+use super::CommandError;
+
+// Only the most recent changes can be reverted, older backups are cleaned up
+const MAX_JOURNAL_ENTRIES: usize = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JournalFile {
+  path: PathBuf,
+  /// Name of the backup in the entry's directory, `None` if the file didn't exist before
+  backup: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JournalEntry {
+  id: String,
+  description: String,
+  timestamp: String,
+  files: Vec<JournalFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct GameDataChange {
+  pub id: String,
+  pub description: String,
+  pub timestamp: String,
+  pub file_count: u64,
+}
+
+fn journal_dir(config: &LauncherConfig, game_name: &String) -> Result<PathBuf, CommandError> {
+  match config.game_data_path() {
+    Some(path) => Ok(path.join("journal").join(game_name)),
+    None => Err(CommandError::GameManagement(
+      "No game data directory set, can't track changes to game data".to_string(),
+    )),
+  }
+}
+
+fn read_journal(dir: &Path) -> Vec<JournalEntry> {
+  read_json(&dir.join("journal.json")).unwrap_or_default()
+}
+
+fn write_journal(dir: &Path, entries: &Vec<JournalEntry>) -> std::io::Result<()> {
+  write_json(&dir.join("journal.json"), entries)
+}
+
+// Hard links make backups free when the file is about to be replaced rather than edited in
+// place, but an in place edit would change the backup too - so only link when asked to
+fn backup_file(src: &Path, dst: &Path, allow_link: bool) -> std::io::Result<()> {
+  if allow_link && std::fs::hard_link(src, dst).is_ok() {
+    return Ok(());
+  }
+  std::fs::copy(src, dst)?;
+  Ok(())
+}
+
+fn restore_files(entry_dir: &Path, files: &[JournalFile]) -> std::io::Result<()> {
+  for file in files {
+    if file.path.exists() {
+      std::fs::remove_file(&file.path)?;
+    }
+    if let Some(backup) = &file.backup {
+      if let Some(parent) = file.path.parent() {
+        std::fs::create_dir_all(parent)?;
+      }
+      // The backups are deleted once restored, so linking is safe here
+      backup_file(&entry_dir.join(backup), &file.path, true)?;
+    }
+  }
+  Ok(())
+}
+
+pub struct GameDataTransaction {
+  journal_dir: PathBuf,
+  entry: JournalEntry,
+  committed: bool,
+}
+
+impl GameDataTransaction {
+  pub fn begin(
+    config: &LauncherConfig,
+    game_name: &String,
+    description: &str,
+  ) -> Result<Self, CommandError> {
+    let journal_dir = journal_dir(config, game_name)?;
+    let entry = JournalEntry {
+      id: chrono::Local::now().format("%Y%m%d%H%M%S%3f").to_string(),
+      description: description.to_string(),
+      timestamp: chrono::Local::now().to_rfc3339(),
+      files: vec![],
+    };
+    std::fs::create_dir_all(journal_dir.join(&entry.id))?;
+    Ok(Self {
+      journal_dir,
+      entry,
+      committed: false,
+    })
+  }
+
+  /// The game's settings live outside the game data directory, so they can be changed without
+  /// one - the change just can't be journaled
+  pub fn begin_if_journaled(
+    config: &LauncherConfig,
+    game_name: &String,
+    description: &str,
+  ) -> Result<Option<Self>, CommandError> {
+    if config.game_data_path().is_none() {
+      log::info!(
+        "no game data directory set, '{}' can't be reverted",
+        description
+      );
+      return Ok(None);
+    }
+    Self::begin(config, game_name, description).map(Some)
+  }
+
+  fn entry_dir(&self) -> PathBuf {
+    self.journal_dir.join(&self.entry.id)
+  }
+
+  fn stage(&mut self, path: &Path, allow_link: bool) -> std::io::Result<()> {
+    if self.entry.files.iter().any(|file| file.path == path) {
+      return Ok(());
+    }
+    let backup = if path.is_file() {
+      let backup_name = self.entry.files.len().to_string();
+      backup_file(path, &self.entry_dir().join(&backup_name), allow_link)?;
+      Some(backup_name)
+    } else {
+      None
+    };
+    self.entry.files.push(JournalFile {
+      path: path.to_path_buf(),
+      backup,
+    });
+    Ok(())
+  }
+
+  /// Backs up `path` before it's modified in place
+  pub fn stage_file(&mut self, path: &Path) -> std::io::Result<()> {
+    self.stage(path, false)
+  }
+
+  /// Backs up `path` before it's deleted or replaced by a new file, this is cheaper than
+  /// `stage_file` but the file must not be written to afterwards
+  pub fn stage_replacement(&mut self, path: &Path) -> std::io::Result<()> {
+    self.stage(path, true)
+  }
+
+  pub fn commit(mut self) -> Result<(), CommandError> {
+    self.committed = true;
+    if self.entry.files.is_empty() {
+      let _ = std::fs::remove_dir_all(self.entry_dir());
+      return Ok(());
+    }
+    let mut entries = read_journal(&self.journal_dir);
+    entries.push(self.entry.clone());
+    while entries.len() > MAX_JOURNAL_ENTRIES {
+      let expired = entries.remove(0);
+      let _ = std::fs::remove_dir_all(self.journal_dir.join(&expired.id));
+    }
+    write_journal(&self.journal_dir, &entries)?;
+    log::info!(
+      "committed game data change '{}' ({} files)",
+      self.entry.description,
+      self.entry.files.len()
+    );
+    Ok(())
+  }
+}
+
+impl Drop for GameDataTransaction {
+  fn drop(&mut self) {
+    if self.committed {
+      return;
+    }
+    log::warn!(
+      "game data change '{}' was not completed, rolling back",
+      self.entry.description
+    );
+    if let Err(err) = restore_files(&self.entry_dir(), &self.entry.files) {
+      log::error!("unable to roll back game data change: {}", err);
+      return;
+    }
+    let _ = std::fs::remove_dir_all(self.entry_dir());
+  }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_game_data_changes(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  game_name: String,
+) -> Result<Vec<GameDataChange>, CommandError> {
+  let config_lock = config.lock().await;
+  let journal_dir = journal_dir(&config_lock, &game_name)?;
+  Ok(
+    read_journal(&journal_dir)
+      .into_iter()
+      .rev()
+      .map(|entry| GameDataChange {
+        id: entry.id,
+        description: entry.description,
+        timestamp: entry.timestamp,
+        file_count: entry.files.len() as u64,
+      })
+      .collect(),
+  )
+}
+
+/// Undoes the most recent change to the game's data, returning its description
+#[tauri::command]
+#[specta::specta]
+pub async fn revert_last_change(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  game_name: String,
+) -> Result<Option<String>, CommandError> {
+  let config_lock = config.lock().await;
+  let journal_dir = journal_dir(&config_lock, &game_name)?;
+  let mut entries = read_journal(&journal_dir);
+  let entry = match entries.pop() {
+    Some(entry) => entry,
+    None => return Ok(None),
+  };
+  let entry_dir = journal_dir.join(&entry.id);
+  restore_files(&entry_dir, &entry.files).map_err(|err| {
+    log::error!("unable to revert game data change: {}", err);
+    CommandError::GameManagement(format!("Unable to revert '{}'", entry.description))
+  })?;
+  write_journal(&journal_dir, &entries)?;
+  let _ = std::fs::remove_dir_all(&entry_dir);
+  log::info!("reverted game data change '{}'", entry.description);
+  Ok(Some(entry.description))
+}
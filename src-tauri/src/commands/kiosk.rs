@@ -0,0 +1,175 @@
+use serde::Serialize;
+
+use crate::{
+  config::LauncherConfig,
+  kiosk::{hash_pin, lockout, needs_rehash, verify_pin, KioskLock},
+};
+
+use super::CommandError;
+
+// Short PINs are fine for keeping kids out of the settings, but not empty ones
+const MIN_PIN_LENGTH: usize = 4;
+
+#[derive(Debug, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct KioskStatus {
+  pub enabled: bool,
+  pub locked: bool,
+  pub daily_limit_minutes: Option<u32>,
+  pub played_today_seconds: u64,
+}
+
+// Hashing is slow on purpose, so it's kept off the async runtime
+async fn hash_pin_blocking(pin: String) -> Result<String, CommandError> {
+  tokio::task::spawn_blocking(move || hash_pin(&pin))
+    .await
+    .map_err(|_| CommandError::Configuration("Unable to hash the PIN".to_string()))
+}
+
+fn now_seconds() -> u64 {
+  std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .map_or(0, |since| since.as_secs())
+}
+
+async fn check_pin(config: &mut LauncherConfig, pin: String) -> Result<(), CommandError> {
+  let now = now_seconds();
+  if let Some(retry_at) = config.kiosk_retry_at.filter(|retry_at| *retry_at > now) {
+    return Err(CommandError::Locked(format!(
+      "Too many incorrect PINs, try again in {} seconds",
+      retry_at - now
+    )));
+  }
+  let stored = match &config.kiosk_pin_hash {
+    Some(stored) => stored.clone(),
+    None => {
+      return Err(CommandError::Locked(
+        "Locked mode is not enabled".to_string(),
+      ))
+    }
+  };
+  let matches = tokio::task::spawn_blocking(move || verify_pin(&pin, &stored))
+    .await
+    .unwrap_or(false);
+  let failed_attempts = if matches {
+    0
+  } else {
+    config.kiosk_failed_attempts.unwrap_or(0) + 1
+  };
+  let retry_at = lockout(failed_attempts).map(|wait| now + wait.as_secs());
+  if let Err(err) = config.set_kiosk_failed_attempts(failed_attempts, retry_at) {
+    log::warn!("Unable to persist the incorrect PIN count: {}", err);
+  }
+  if !matches {
+    return Err(CommandError::Locked("Incorrect PIN".to_string()));
+  }
+  Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_kiosk_status(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  kiosk_lock: tauri::State<'_, KioskLock>,
+  app_handle: tauri::AppHandle,
+) -> Result<KioskStatus, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(KioskStatus {
+    enabled: config_lock.kiosk_pin_hash.is_some(),
+    locked: kiosk_lock.is_locked(),
+    daily_limit_minutes: config_lock.kiosk_daily_limit_minutes,
+    played_today_seconds: crate::playtime::today_total(&app_handle),
+  })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_kiosk_daily_limit(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  minutes: Option<u32>,
+) -> Result<(), CommandError> {
+  let mut config_lock = config.lock().await;
+  config_lock
+    .set_kiosk_daily_limit_minutes(minutes)
+    .map_err(|_| CommandError::Configuration("Unable to persist the daily limit".to_string()))?;
+  Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn enable_kiosk_mode(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  pin: String,
+) -> Result<(), CommandError> {
+  if pin.chars().count() < MIN_PIN_LENGTH {
+    return Err(CommandError::Configuration(format!(
+      "PIN must be at least {} characters long",
+      MIN_PIN_LENGTH
+    )));
+  }
+  let pin_hash = hash_pin_blocking(pin).await?;
+  let mut config_lock = config.lock().await;
+  config_lock
+    .set_kiosk_pin_hash(Some(pin_hash))
+    .map_err(|_| CommandError::Configuration("Unable to persist locked mode PIN".to_string()))?;
+  Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn disable_kiosk_mode(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  kiosk_lock: tauri::State<'_, KioskLock>,
+  pin: String,
+) -> Result<(), CommandError> {
+  let mut config_lock = config.lock().await;
+  check_pin(&mut config_lock, pin).await?;
+  config_lock
+    .set_kiosk_pin_hash(None)
+    .map_err(|_| CommandError::Configuration("Unable to persist locked mode PIN".to_string()))?;
+  kiosk_lock.set_locked(false);
+  Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn lock_kiosk(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  kiosk_lock: tauri::State<'_, KioskLock>,
+) -> Result<(), CommandError> {
+  let config_lock = config.lock().await;
+  if config_lock.kiosk_pin_hash.is_none() {
+    return Err(CommandError::Locked(
+      "Set a PIN before locking the launcher".to_string(),
+    ));
+  }
+  kiosk_lock.set_locked(true);
+  Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn unlock_kiosk(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  kiosk_lock: tauri::State<'_, KioskLock>,
+  pin: String,
+) -> Result<(), CommandError> {
+  let mut config_lock = config.lock().await;
+  let stretched = config_lock
+    .kiosk_pin_hash
+    .as_deref()
+    .map_or(true, |stored| !needs_rehash(stored));
+  if let Err(err) = check_pin(&mut config_lock, pin.clone()).await {
+    log::warn!("Failed attempt to unlock the launcher");
+    return Err(err);
+  }
+  kiosk_lock.set_locked(false);
+  // PINs set before they were stretched are upgraded now that it's known
+  if !stretched {
+    let pin_hash = hash_pin_blocking(pin).await?;
+    if let Err(err) = config_lock.set_kiosk_pin_hash(Some(pin_hash)) {
+      log::warn!("Unable to upgrade the stored PIN: {}", err);
+    }
+  }
+  Ok(())
+}
@@ -0,0 +1,146 @@
+// Finding install directories kept on other drives
+//
+// A drive is scanned for folders laid out the way the launcher lays out its install directory:
+// game data in `active/<game>/data` and tooling versions in `versions/<folder>/<version>`.  Only
+// a few levels deep are looked at, an install is normally near the root of a portable drive and
+// walking the whole drive would take far too long.
+//
+// One that's found can then be picked as the install directory, like any other folder.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  config::LauncherConfig,
+  paths::{self, GAMES},
+  util::file::is_dir_writable,
+};
+
+use super::{binaries::bin_ext, CommandError};
+
+// How far below the scanned folder an install directory can be
+const MAX_SCAN_DEPTH: usize = 3;
+// Folders on a drive that never hold an install, and can be slow to list
+const SKIPPED_DIRS: &[&str] = &[
+  "$RECYCLE.BIN",
+  "System Volume Information",
+  "lost+found",
+  "node_modules",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DetectedInstall {
+  pub path: String,
+  /// Games with installed data
+  pub games: Vec<String>,
+  /// Tooling versions, as `<folder>/<version>`
+  pub versions: Vec<String>,
+  /// Whether it's the install directory already
+  pub current: bool,
+  /// Whether the launcher can use it as its install directory, which it has to write to
+  pub writable: bool,
+}
+
+fn installed_games(dir: &Path) -> Vec<String> {
+  GAMES
+    .iter()
+    .filter(|game| paths::game_data_dir(dir, game).is_dir())
+    .map(|game| game.to_string())
+    .collect()
+}
+
+fn installed_versions(dir: &Path) -> Vec<String> {
+  let mut versions = vec![];
+  for folder in read_dirs(&paths::versions_dir(dir)) {
+    for version in read_dirs(&folder) {
+      if version.join(bin_ext("gk")).is_file() {
+        versions.push(format!(
+          "{}/{}",
+          folder.file_name().unwrap_or_default().to_string_lossy(),
+          version.file_name().unwrap_or_default().to_string_lossy()
+        ));
+      }
+    }
+  }
+  versions
+}
+
+fn read_dirs(dir: &Path) -> Vec<PathBuf> {
+  std::fs::read_dir(dir)
+    .into_iter()
+    .flatten()
+    .filter_map(|entry| entry.ok())
+    .map(|entry| entry.path())
+    .filter(|path| path.is_dir())
+    .collect()
+}
+
+fn find_installs(dir: &Path, depth: usize, found: &mut Vec<PathBuf>) {
+  let games = installed_games(dir);
+  if !games.is_empty() || !installed_versions(dir).is_empty() {
+    // Nothing below an install directory is another one
+    found.push(dir.to_path_buf());
+    return;
+  }
+  if depth >= MAX_SCAN_DEPTH {
+    return;
+  }
+  for child in read_dirs(dir) {
+    let name = child.file_name().unwrap_or_default().to_string_lossy();
+    if name.starts_with('.') || SKIPPED_DIRS.contains(&name.as_ref()) {
+      continue;
+    }
+    find_installs(&child, depth + 1, found);
+  }
+}
+
+fn same_path(a: &Path, b: &Path) -> bool {
+  match (a.canonicalize(), b.canonicalize()) {
+    (Ok(a), Ok(b)) => a == b,
+    _ => a == b,
+  }
+}
+
+fn is_current_install(config: &LauncherConfig, dir: &Path) -> bool {
+  config
+    .game_data_path()
+    .map_or(false, |main| same_path(&main, dir))
+}
+
+/// Looks for install directories on the drive (or in the folder) at `path`
+#[tauri::command]
+#[specta::specta]
+pub async fn scan_drive_for_installs(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  path: String,
+) -> Result<Vec<DetectedInstall>, CommandError> {
+  let root = PathBuf::from(&path);
+  if !root.is_dir() {
+    return Err(CommandError::GameManagement(format!(
+      "'{}' is not a folder that can be scanned",
+      path
+    )));
+  }
+  let found = tokio::task::spawn_blocking(move || {
+    let mut found = vec![];
+    find_installs(&root, 0, &mut found);
+    found
+  })
+  .await
+  .map_err(|_| CommandError::GameManagement(format!("Unable to scan '{}'", path)))?;
+  let config_lock = config.lock().await;
+  Ok(
+    found
+      .into_iter()
+      .map(|dir| DetectedInstall {
+        path: dir.to_string_lossy().into_owned(),
+        games: installed_games(&dir),
+        versions: installed_versions(&dir),
+        current: is_current_install(&config_lock, &dir),
+        writable: is_dir_writable(&dir),
+      })
+      .collect(),
+  )
+}
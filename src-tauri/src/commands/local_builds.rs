@@ -0,0 +1,424 @@
+// Lets developers building jak-project from source use their own build as a tooling version
+//
+// A local build is registered into the "local" version folder like any downloaded version, so it
+// can be made active (and installed with) through the usual version commands.  The binaries are
+// copied, they're small and it means a recompile never swaps them out from under a running
+// game.  The data the tooling needs is linked to the checkout by default, so edits to
+// `goal_src` and friends are picked up without registering the build again.
+//
+// A registered build can be watched, when the developer recompiles it the new binaries are
+// copied over and `localBuildUpdated` is emitted.  Running debug sessions of the build can be
+// restarted along with it.  Watching is polling the binaries' modification times, there's no need
+// to react to a rebuild any faster than that.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use serde::Serialize;
+use tauri::Manager;
+
+use crate::{
+  config::{LauncherConfig, VersionName},
+  supervisor::supervise,
+  tasks::CancellationToken,
+  util::file::{create_dir, delete_dir, overwrite_dir, replace_dir_link},
+};
+
+use super::{
+  binaries::{bin_ext, restart_debug_game, DebugSessions},
+  versions::{stamp_version_metadata, VersionMetadata},
+  CommandError,
+};
+
+pub const LOCAL_VERSION_FOLDER: &str = "local";
+const WATCH_POLL_INTERVAL: Duration = Duration::from_secs(2);
+// Linkers write the binary out in pieces, a change is only picked up once it has settled
+const WATCH_SETTLE_DELAY: Duration = Duration::from_secs(1);
+const RESTART_EXIT_TIMEOUT: Duration = Duration::from_secs(20);
+
+// Each binary and the directory it's built into within a jak-project checkout
+const LOCAL_BUILD_BINARIES: [(&str, &str); 3] = [
+  ("extractor", "decompiler"),
+  ("gk", "game"),
+  ("goalc", "goalc"),
+];
+
+// The parts of a checkout that make up a release's `data` folder
+const LOCAL_BUILD_DATA: [&str; 4] = [
+  "decompiler/config",
+  "game/assets",
+  "goal_src",
+  "custom_levels",
+];
+
+/// Where the binaries of a build are, checking the layouts jak-project's build presets produce as
+/// well as a folder that has them side by side (ie. an extracted release)
+pub fn find_local_build_binaries(root: &Path) -> Option<Vec<PathBuf>> {
+  let layouts = |bin: &str, subdir: &str| -> [PathBuf; 3] {
+    let bin = bin_ext(bin);
+    [
+      root.join(&bin),
+      root.join("build").join(subdir).join(&bin),
+      root
+        .join("out")
+        .join("build")
+        .join("Release")
+        .join("bin")
+        .join(&bin),
+    ]
+  };
+  (0..3)
+    .map(|layout| {
+      LOCAL_BUILD_BINARIES
+        .iter()
+        .map(|(bin, subdir)| layouts(bin, subdir)[layout].clone())
+        .collect::<Vec<PathBuf>>()
+    })
+    .find(|binaries| binaries.iter().all(|binary| binary.is_file()))
+}
+
+/// Copies the build's binaries into the version folder
+pub fn copy_local_build_binaries(root: &Path, version_dir: &Path) -> Result<(), CommandError> {
+  let binaries = find_local_build_binaries(root).ok_or_else(|| {
+    CommandError::VersionManagement(format!(
+      "'{}' doesn't contain a build of extractor, gk and goalc",
+      root.display()
+    ))
+  })?;
+  for binary in binaries {
+    if let Some(name) = binary.file_name() {
+      // Copy next to the destination and rename it into place, writing over an executable that
+      // is being run isn't allowed
+      let destination = version_dir.join(name);
+      let tmp_path = destination.with_extension("tmp");
+      std::fs::copy(&binary, &tmp_path)?;
+      std::fs::rename(&tmp_path, &destination)?;
+    }
+  }
+  Ok(())
+}
+
+fn add_data_dir(src: &PathBuf, dst: &PathBuf, copy: bool) -> Result<(), CommandError> {
+  if let Some(parent) = dst.parent() {
+    create_dir(&parent.to_path_buf())?;
+  }
+  if copy {
+    create_dir(dst)?;
+    overwrite_dir(src, dst).map_err(|err| {
+      CommandError::VersionManagement(format!(
+        "Unable to copy '{}' from the local build: {}",
+        src.display(),
+        err
+      ))
+    })?;
+  } else {
+    replace_dir_link(dst, src)?;
+  }
+  Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn register_local_build(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  path: String,
+  name: VersionName,
+  copy: Option<bool>,
+) -> Result<(), CommandError> {
+  let config_lock = config.lock().await;
+  let local_builds_path = match config_lock.version_folder_path(LOCAL_VERSION_FOLDER) {
+    None => {
+      return Err(CommandError::VersionManagement(
+        "Cannot register local build, no tooling directory set".to_string(),
+      ))
+    }
+    Some(path) => path,
+  };
+  let root = PathBuf::from(&path);
+  if !root.is_dir() {
+    return Err(CommandError::VersionManagement(format!(
+      "'{}' is not a directory",
+      root.display()
+    )));
+  }
+  let binaries_alongside = find_local_build_binaries(&root).map_or(false, |binaries| {
+    binaries.iter().all(|binary| binary.parent() == Some(&root))
+  });
+  // An extracted release has its data folder ready-made, a checkout has it spread out
+  let data_dirs: Vec<&str> = if binaries_alongside && root.join("data").is_dir() {
+    vec![]
+  } else if root.join("goal_src").is_dir() {
+    LOCAL_BUILD_DATA
+      .iter()
+      .copied()
+      .filter(|dir| root.join(dir).is_dir())
+      .collect()
+  } else {
+    return Err(CommandError::VersionManagement(format!(
+      "'{}' doesn't look like a jak-project checkout, goal_src is missing",
+      root.display()
+    )));
+  };
+
+  let version_dir = local_builds_path.join(&name);
+  log::info!(
+    "Registering local build {} as {}",
+    root.display(),
+    version_dir.display()
+  );
+  delete_dir(&version_dir)?;
+  create_dir(&version_dir)?;
+  let copy = copy.unwrap_or(false);
+  let result = copy_local_build_binaries(&root, &version_dir).and_then(|_| {
+    if data_dirs.is_empty() {
+      add_data_dir(&root.join("data"), &version_dir.join("data"), copy)
+    } else {
+      data_dirs.iter().try_for_each(|dir| {
+        add_data_dir(&root.join(dir), &version_dir.join("data").join(dir), copy)
+      })
+    }
+  });
+  if let Err(err) = result {
+    let _ = delete_dir(&version_dir);
+    return Err(err);
+  }
+
+  let metadata = VersionMetadata {
+    local_build: Some(root.to_string_lossy().into_owned()),
+    ..VersionMetadata::default()
+  };
+  metadata.save(&version_dir)?;
+  stamp_version_metadata(&version_dir);
+  Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalBuildUpdated {
+  pub name: String,
+  /// The debug sessions that were restarted on the new build
+  pub restarted_games: Vec<String>,
+}
+
+/// The local builds being watched, by version name
+#[derive(Default)]
+pub struct LocalBuildWatchers {
+  watchers: std::sync::Mutex<HashMap<String, CancellationToken>>,
+}
+
+impl LocalBuildWatchers {
+  fn start(&self, name: &str) -> CancellationToken {
+    let token = CancellationToken::default();
+    if let Ok(mut watchers) = self.watchers.lock() {
+      if let Some(previous) = watchers.insert(name.to_string(), token.clone()) {
+        previous.cancel();
+      }
+    }
+    token
+  }
+
+  fn stop(&self, name: &str) -> bool {
+    match self
+      .watchers
+      .lock()
+      .ok()
+      .and_then(|mut watchers| watchers.remove(name))
+    {
+      Some(token) => {
+        token.cancel();
+        true
+      }
+      None => false,
+    }
+  }
+
+  fn names(&self) -> Vec<String> {
+    match self.watchers.lock() {
+      Ok(watchers) => {
+        let mut names: Vec<String> = watchers.keys().cloned().collect();
+        names.sort();
+        names
+      }
+      Err(_) => vec![],
+    }
+  }
+}
+
+fn local_version_dir(config: &LauncherConfig, name: &VersionName) -> Result<PathBuf, CommandError> {
+  match config.version_folder_path(LOCAL_VERSION_FOLDER) {
+    Some(path) => Ok(path.join(name)),
+    None => Err(CommandError::VersionManagement(
+      "No tooling directory set, can't manage local builds".to_string(),
+    )),
+  }
+}
+
+fn modified_times(root: &Path) -> Option<Vec<SystemTime>> {
+  find_local_build_binaries(root)?
+    .iter()
+    .map(|binary| {
+      std::fs::metadata(binary)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+    })
+    .collect()
+}
+
+async fn update_local_build(
+  app_handle: &tauri::AppHandle,
+  name: &VersionName,
+  root: &Path,
+  auto_restart: bool,
+) -> Result<(), CommandError> {
+  let config = app_handle.state::<tokio::sync::Mutex<LauncherConfig>>();
+  let (version_dir, is_active) = {
+    let config_lock = config.lock().await;
+    let is_active = config_lock.active_version_folder.as_deref() == Some(LOCAL_VERSION_FOLDER)
+      && config_lock.active_version.as_deref() == Some(name.as_str());
+    (local_version_dir(&config_lock, name)?, is_active)
+  };
+
+  // Debug sessions run on the active version, so only those of this build are restarted
+  let debug_sessions = app_handle.state::<DebugSessions>();
+  let stopped_games = if auto_restart && is_active {
+    debug_sessions.stop_all()
+  } else {
+    vec![]
+  };
+  let stop_requested = std::time::Instant::now();
+  while stopped_games
+    .iter()
+    .any(|game_name| debug_sessions.is_running(game_name))
+  {
+    if stop_requested.elapsed() >= RESTART_EXIT_TIMEOUT {
+      return Err(CommandError::BinaryExecution(
+        "Debug session did not exit, unable to update local build".to_string(),
+      ));
+    }
+    tokio::time::sleep(Duration::from_millis(250)).await;
+  }
+
+  log::info!("Local build '{}' was rebuilt, updating it", name);
+  copy_local_build_binaries(root, &version_dir)?;
+  stamp_version_metadata(&version_dir);
+
+  let mut restarted_games = vec![];
+  if !stopped_games.is_empty() {
+    let config_lock = config.lock().await;
+    for game_name in stopped_games {
+      match restart_debug_game(&config_lock, app_handle.clone(), game_name.clone()) {
+        Ok(()) => restarted_games.push(game_name),
+        Err(err) => log::error!("Unable to restart {}: {}", game_name, err),
+      }
+    }
+  }
+  app_handle.emit_all(
+    "localBuildUpdated",
+    LocalBuildUpdated {
+      name: name.to_string(),
+      restarted_games,
+    },
+  )?;
+  Ok(())
+}
+
+// Fails when the checkout is gone or a rebuild couldn't be picked up, so the supervisor retries
+// it.  A `retry` picks up whatever is built right away, that's what the failed run was doing
+async fn watch(
+  app_handle: tauri::AppHandle,
+  name: VersionName,
+  root: PathBuf,
+  auto_restart: bool,
+  token: CancellationToken,
+  retry: bool,
+) -> Result<(), String> {
+  let mut known = if retry { None } else { modified_times(&root) };
+  loop {
+    tokio::select! {
+      _ = tokio::time::sleep(WATCH_POLL_INTERVAL) => {},
+      _ = token.cancelled() => break,
+    }
+    if !root.is_dir() {
+      return Err(format!("the checkout at {} is gone", root.display()));
+    }
+    let current = modified_times(&root);
+    // Binaries that are missing are most likely in the middle of being rebuilt
+    if current.is_none() || current == known {
+      continue;
+    }
+    tokio::time::sleep(WATCH_SETTLE_DELAY).await;
+    if modified_times(&root) != current {
+      continue;
+    }
+    known = current;
+    if let Err(err) = update_local_build(&app_handle, &name, &root, auto_restart).await {
+      log::error!("Unable to update local build '{}': {}", name, err);
+      return Err(format!("unable to update local build '{}': {}", name, err));
+    }
+  }
+  log::info!("Stopped watching local build '{}'", name);
+  Ok(())
+}
+
+/// Starts watching a registered local build for rebuilds, replacing an existing watch of it
+#[tauri::command]
+#[specta::specta]
+pub async fn watch_local_build(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  watchers: tauri::State<'_, LocalBuildWatchers>,
+  app_handle: tauri::AppHandle,
+  name: VersionName,
+  auto_restart: Option<bool>,
+) -> Result<(), CommandError> {
+  let config_lock = config.lock().await;
+  let version_dir = local_version_dir(&config_lock, &name)?;
+  let root = match VersionMetadata::load(&version_dir).local_build {
+    Some(root) => PathBuf::from(root),
+    None => {
+      return Err(CommandError::VersionManagement(format!(
+        "'{}' is not a registered local build",
+        name
+      )))
+    }
+  };
+  log::info!("Watching local build '{}' at {}", name, root.display());
+  let token = watchers.start(name.as_str());
+  let auto_restart = auto_restart.unwrap_or(false);
+  let watch_app_handle = app_handle.clone();
+  let started = Arc::new(AtomicBool::new(false));
+  supervise(
+    &app_handle,
+    format!("localBuildWatcher-{}", name),
+    move || {
+      watch(
+        watch_app_handle.clone(),
+        name.clone(),
+        root.clone(),
+        auto_restart,
+        token.clone(),
+        started.swap(true, Ordering::SeqCst),
+      )
+    },
+  );
+  Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn unwatch_local_build(
+  watchers: tauri::State<'_, LocalBuildWatchers>,
+  name: VersionName,
+) -> Result<bool, CommandError> {
+  Ok(watchers.stop(name.as_str()))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn list_watched_local_builds(
+  watchers: tauri::State<'_, LocalBuildWatchers>,
+) -> Result<Vec<String>, CommandError> {
+  Ok(watchers.names())
+}
@@ -1,4 +1,5 @@
 #[tauri::command]
+#[specta::specta]
 pub async fn frontend_log(level: String, log: String) -> Result<(), ()> {
   match level.as_str() {
     "debug" => log::debug!("{}", log),
@@ -0,0 +1,266 @@
+// Finding what a user switching from another setup already has, so they don't lose it
+//
+// Other OpenGOAL setups (the old launcher's data folder, a standalone release, a copy of someone
+// else's user folder) have saves and texture replacements laid out the same way ours are, those
+// can be imported as they are.  PCSX2 memory cards are detected too, but OpenGOAL has its own save
+// format and can't read them, they're only listed so the user knows they weren't overlooked.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use crate::{
+  config::LauncherConfig,
+  paths::{self, PathResolver, GAMES},
+  util::file::create_dir,
+};
+
+use super::{game::validate_game_name, textures::import_texture_dir, CommandError};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ForeignInstallKind {
+  Pcsx2,
+  OpenGoal,
+}
+
+#[derive(Debug, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ForeignInstall {
+  pub kind: ForeignInstallKind,
+  pub path: String,
+  /// Games that have saves which can be imported
+  pub save_games: Vec<String>,
+  /// `texture_replacements` folders, which can be imported as texture packs
+  pub texture_dirs: Vec<String>,
+  /// Memory card files, which can't be imported
+  pub memory_cards: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedSaves {
+  pub imported: u32,
+  /// Saves that were left alone, as there already is one with the same name
+  pub skipped: Vec<String>,
+}
+
+fn pcsx2_memcard_dirs() -> Vec<PathBuf> {
+  let mut dirs = vec![];
+  if let Some(user_dirs) = directories::UserDirs::new() {
+    let home = user_dirs.home_dir();
+    if let Some(documents) = user_dirs.document_dir() {
+      dirs.push(documents.join("PCSX2").join("memcards"));
+    }
+    dirs.push(home.join(".config").join("PCSX2").join("memcards"));
+    dirs.push(
+      home
+        .join(".var")
+        .join("app")
+        .join("net.pcsx2.PCSX2")
+        .join("config")
+        .join("PCSX2")
+        .join("memcards"),
+    );
+    dirs.push(
+      home
+        .join("Library")
+        .join("Application Support")
+        .join("PCSX2")
+        .join("memcards"),
+    );
+  }
+  dirs
+}
+
+fn our_saves_dir(paths: &PathResolver, game_name: &str) -> Result<PathBuf, CommandError> {
+  paths.saves_dir(game_name).ok_or_else(|| {
+    CommandError::GameManagement("Could not determine game config directory".to_string())
+  })
+}
+
+fn has_entries(dir: &Path) -> bool {
+  std::fs::read_dir(dir).map_or(false, |mut entries| entries.next().is_some())
+}
+
+fn detect_memory_cards(dir: &Path) -> Option<ForeignInstall> {
+  let memory_cards: Vec<String> = std::fs::read_dir(dir)
+    .ok()?
+    .filter_map(|entry| entry.ok())
+    .map(|entry| entry.path())
+    .filter(|path| {
+      path
+        .extension()
+        .map_or(false, |ext| ext.eq_ignore_ascii_case("ps2"))
+    })
+    .map(|path| path.to_string_lossy().into_owned())
+    .collect();
+  if memory_cards.is_empty() {
+    return None;
+  }
+  Some(ForeignInstall {
+    kind: ForeignInstallKind::Pcsx2,
+    path: dir.to_string_lossy().into_owned(),
+    save_games: vec![],
+    texture_dirs: vec![],
+    memory_cards,
+  })
+}
+
+// `dir` may be a user folder (`<game>/saves`), a launcher install dir
+// (`active/<game>/data/texture_replacements`), or a single data folder as releases have it
+fn detect_opengoal_install(dir: &Path) -> Option<ForeignInstall> {
+  let save_games: Vec<String> = GAMES
+    .iter()
+    .filter(|game| has_entries(&paths::saves_dir(dir, game)))
+    .map(|game| game.to_string())
+    .collect();
+  let mut texture_candidates: Vec<PathBuf> = GAMES
+    .iter()
+    .map(|game| paths::game_data_dir(dir, game).join("texture_replacements"))
+    .collect();
+  texture_candidates.push(dir.join("data").join("texture_replacements"));
+  texture_candidates.push(dir.join("texture_replacements"));
+  let texture_dirs: Vec<String> = texture_candidates
+    .into_iter()
+    .filter(|candidate| has_entries(candidate))
+    .map(|candidate| candidate.to_string_lossy().into_owned())
+    .collect();
+  if save_games.is_empty() && texture_dirs.is_empty() {
+    return None;
+  }
+  Some(ForeignInstall {
+    kind: ForeignInstallKind::OpenGoal,
+    path: dir.to_string_lossy().into_owned(),
+    save_games,
+    texture_dirs,
+    memory_cards: vec![],
+  })
+}
+
+/// Looks for saves and texture packs from other setups, in the usual places as well as in
+/// `extra_paths` (ie. a folder the user picked)
+#[tauri::command]
+#[specta::specta]
+pub async fn detect_foreign_installs(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  app_handle: tauri::AppHandle,
+  extra_paths: Option<Vec<String>>,
+) -> Result<Vec<ForeignInstall>, CommandError> {
+  let config_lock = config.lock().await;
+  // What's already ours isn't foreign
+  let paths = app_handle.state::<PathResolver>();
+  let own_dirs: Vec<PathBuf> = [paths.games_user_dir(), config_lock.game_data_path()]
+    .into_iter()
+    .flatten()
+    .filter_map(|dir| dir.canonicalize().ok())
+    .collect();
+  drop(config_lock);
+  let is_own = |dir: &Path| {
+    dir
+      .canonicalize()
+      .map_or(false, |dir| own_dirs.contains(&dir))
+  };
+
+  let mut installs = vec![];
+  for dir in pcsx2_memcard_dirs() {
+    if let Some(install) = detect_memory_cards(&dir) {
+      installs.push(install);
+    }
+  }
+  let mut candidates = vec![];
+  // The launcher used to keep a single data folder in its config directory
+  if let Some(config_dir) = paths.launcher_dir() {
+    candidates.push(config_dir.join("data"));
+  }
+  candidates.extend(
+    extra_paths
+      .unwrap_or_default()
+      .into_iter()
+      .map(PathBuf::from),
+  );
+  for dir in candidates {
+    if !dir.is_dir() || is_own(&dir) {
+      continue;
+    }
+    if let Some(install) = detect_opengoal_install(&dir) {
+      installs.push(install);
+    } else if let Some(install) = detect_memory_cards(&dir) {
+      installs.push(install);
+    }
+  }
+  Ok(installs)
+}
+
+/// Copies a game's saves from another setup's user folder, without replacing any existing saves
+#[tauri::command]
+#[specta::specta]
+pub async fn import_foreign_saves(
+  paths: tauri::State<'_, PathResolver>,
+  source_path: String,
+  game_name: String,
+) -> Result<ImportedSaves, CommandError> {
+  validate_game_name(&game_name)?;
+  let source_dir = paths::saves_dir(Path::new(&source_path), &game_name);
+  let dest_dir = our_saves_dir(&paths, &game_name)?;
+  if !source_dir.is_dir() {
+    return Err(CommandError::GameManagement(format!(
+      "There are no {} saves in {}",
+      game_name,
+      source_dir.display()
+    )));
+  }
+  if source_dir.canonicalize().ok() == dest_dir.canonicalize().ok() {
+    return Err(CommandError::GameManagement(
+      "These are already the launcher's saves".to_string(),
+    ));
+  }
+  create_dir(&dest_dir)?;
+  let mut imported = ImportedSaves {
+    imported: 0,
+    skipped: vec![],
+  };
+  for entry in std::fs::read_dir(&source_dir)?.filter_map(|entry| entry.ok()) {
+    if !entry.path().is_file() {
+      continue;
+    }
+    let dest = dest_dir.join(entry.file_name());
+    if dest.exists() {
+      imported
+        .skipped
+        .push(entry.file_name().to_string_lossy().into_owned());
+      continue;
+    }
+    std::fs::copy(entry.path(), dest)?;
+    imported.imported += 1;
+  }
+  log::info!(
+    "Imported {} save(s) for {} from {}, skipped {:?}",
+    imported.imported,
+    game_name,
+    source_dir.display(),
+    imported.skipped
+  );
+  Ok(imported)
+}
+
+/// Copies another setup's `texture_replacements` folder into the texture pack store as a pack
+#[tauri::command]
+#[specta::specta]
+pub async fn import_foreign_texture_pack(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  texture_dir: String,
+  game_name: String,
+  pack_name: String,
+) -> Result<String, CommandError> {
+  let texture_dir = PathBuf::from(texture_dir);
+  if !texture_dir.is_dir() {
+    return Err(CommandError::GameManagement(format!(
+      "{} is not a folder",
+      texture_dir.display()
+    )));
+  }
+  let config_lock = config.lock().await;
+  import_texture_dir(&config_lock, &game_name, &texture_dir, &pack_name)
+}
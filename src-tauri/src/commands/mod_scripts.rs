@@ -0,0 +1,302 @@
+// Running the install scripts that come with third-party mods
+//
+// A mod lives in `<install dir>/mods/<game>/<mod>` (where `install_mod_from_url` extracts one to)
+// and may ship an `install.sh` to set itself up.
+// These scripts aren't ours, so they're only ever run in a sandbox without network access that
+// can only write to the mod's own folder.  The user is first shown exactly what the script may
+// touch, and approves that specific script, if it changes afterwards it has to be approved again.
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  config::LauncherConfig,
+  paths,
+  tasks::TaskManager,
+  util::{
+    archive::extract_archive,
+    crypto::sha256_hex,
+    file::{create_dir, delete_dir, display_paths},
+    sandbox::{sandbox_name, sandboxed_command, SandboxPolicy},
+  },
+};
+
+use super::{
+  binaries::create_log_file, game::validate_game_name, textures::fetch_content, CommandError,
+};
+
+const INSTALL_SCRIPT_NAME: &str = "install.sh";
+
+#[derive(Debug, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ModScriptPlan {
+  pub script: String,
+  pub mod_dir: String,
+  pub read_only: Vec<String>,
+  pub writable: Vec<String>,
+  pub network: bool,
+  /// The sandbox the script would be run in, `None` if it can't be run on this system
+  pub sandbox: Option<String>,
+  /// What has to be passed back to run this script, so an edited script isn't run unseen
+  pub script_sha256: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ModScriptOutput {
+  pub success: bool,
+  pub exit_code: Option<i32>,
+  pub log_file: String,
+}
+
+// Where a mod goes, whether or not it's installed
+fn mod_path(
+  config: &LauncherConfig,
+  game_name: &str,
+  mod_name: &str,
+) -> Result<PathBuf, CommandError> {
+  validate_game_name(game_name)?;
+  if mod_name.is_empty()
+    || mod_name == "."
+    || mod_name == ".."
+    || mod_name.contains(&['/', '\\', ':'][..])
+  {
+    return Err(CommandError::GameManagement(format!(
+      "'{}' is not a valid mod name",
+      mod_name
+    )));
+  }
+  let game_data_path = config.game_data_path().ok_or_else(|| {
+    CommandError::GameManagement("No installation directory is configured".to_string())
+  })?;
+  Ok(
+    paths::mods_dir(&game_data_path)
+      .join(game_name)
+      .join(mod_name),
+  )
+}
+
+pub fn mod_dir(
+  config: &LauncherConfig,
+  game_name: &str,
+  mod_name: &str,
+) -> Result<PathBuf, CommandError> {
+  let mod_dir = mod_path(config, game_name, mod_name)?;
+  if !mod_dir.is_dir() {
+    return Err(CommandError::GameManagement(format!(
+      "Mod '{}' is not installed for {}",
+      mod_name, game_name
+    )));
+  }
+  // Resolved, so the sandbox is given the real folder even if the mod folder is a link
+  Ok(mod_dir.canonicalize()?)
+}
+
+fn read_script(mod_dir: &Path) -> Result<(PathBuf, String), CommandError> {
+  let script = mod_dir.join(INSTALL_SCRIPT_NAME);
+  if !script.is_file() {
+    return Err(CommandError::GameManagement(format!(
+      "Mod has no {} to run",
+      INSTALL_SCRIPT_NAME
+    )));
+  }
+  let hash = sha256_hex(&std::fs::read(&script)?);
+  Ok((script, hash))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn preview_mod_install_script(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  game_name: String,
+  mod_name: String,
+) -> Result<ModScriptPlan, CommandError> {
+  let config_lock = config.lock().await;
+  let mod_dir = mod_dir(&config_lock, &game_name, &mod_name)?;
+  let (script, script_sha256) = read_script(&mod_dir)?;
+  let policy = SandboxPolicy::for_scripts(vec![mod_dir.clone()]);
+  Ok(ModScriptPlan {
+    script: script.to_string_lossy().into_owned(),
+    mod_dir: mod_dir.to_string_lossy().into_owned(),
+    read_only: display_paths(&policy.read_only),
+    writable: display_paths(&policy.writable),
+    network: false,
+    sandbox: sandbox_name().map(|name| name.to_string()),
+    script_sha256,
+  })
+}
+
+/// Runs a mod's install script in the sandbox, `approved_sha256` being the hash of the script
+/// that was shown by `preview_mod_install_script`
+#[tauri::command]
+#[specta::specta]
+pub async fn run_mod_install_script(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  app_handle: tauri::AppHandle,
+  task_manager: tauri::State<'_, TaskManager>,
+  game_name: String,
+  mod_name: String,
+  approved_sha256: String,
+) -> Result<ModScriptOutput, CommandError> {
+  let config_lock = config.lock().await;
+  let mod_dir = mod_dir(&config_lock, &game_name, &mod_name)?;
+  drop(config_lock);
+  let (script, script_sha256) = read_script(&mod_dir)?;
+  if script_sha256 != approved_sha256 {
+    return Err(CommandError::GameManagement(
+      "The install script has changed since it was approved, review it again before running it"
+        .to_string(),
+    ));
+  }
+
+  let mut task = match task_manager.start_or_attach(
+    &app_handle,
+    format!("mod-script-{}-{}", game_name, mod_name),
+    "Running mod install script",
+  ) {
+    Ok(task) => task,
+    Err(_) => {
+      return Err(CommandError::GameManagement(format!(
+        "The install script for '{}' is already running",
+        mod_name
+      )))
+    }
+  };
+  let result = async {
+    let policy = SandboxPolicy::for_scripts(vec![mod_dir.clone()]);
+    let log_name = format!("mod-{}-{}.log", game_name, mod_name);
+    let log_file = create_log_file(&app_handle, &log_name, false)?;
+    let mut command = match sandboxed_command(
+      &policy,
+      "/bin/sh",
+      &[script.to_string_lossy().into_owned()],
+      &mod_dir,
+    ) {
+      Ok(command) => command,
+      Err(err) => {
+        task.finish(false, Some(err.to_string()));
+        return Err(CommandError::GameManagement(format!(
+          "Mod install scripts can't be run safely on this system: {}",
+          err
+        )));
+      }
+    };
+    // The sandbox only hands the curated environment on, and the script's home is its mod
+    command
+      .env("HOME", &mod_dir)
+      .env("MOD_DIR", &mod_dir)
+      .stdout(log_file.try_clone()?)
+      .stderr(log_file);
+    log::info!(
+      "Running install script of mod {} for {} in {:?}",
+      mod_name,
+      game_name,
+      sandbox_name()
+    );
+    let mut child = command.spawn()?;
+    let status = match task.cancellation_token().wait_for_child(&mut child)? {
+      Some(status) => status,
+      None => {
+        return Ok(ModScriptOutput {
+          success: false,
+          exit_code: None,
+          log_file: log_name,
+        })
+      }
+    };
+    task.finish(
+      status.success(),
+      (!status.success()).then(|| format!("Install script exited with {}", status)),
+    );
+    Ok(ModScriptOutput {
+      success: status.success(),
+      exit_code: status.code(),
+      log_file: log_name,
+    })
+  }
+  .await;
+  if let Err(err) = &result {
+    task.finish(false, Some(err.to_string()));
+  }
+  result
+}
+
+/// Downloads a mod's archive and extracts it as `mod_name`, replacing what was there.  Its
+/// install script, if it has one, still has to be reviewed and run separately
+#[tauri::command]
+#[specta::specta]
+pub async fn install_mod_from_url(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  app_handle: tauri::AppHandle,
+  task_manager: tauri::State<'_, TaskManager>,
+  game_name: String,
+  mod_name: String,
+  url: String,
+  allow_unknown_host: Option<bool>,
+) -> Result<String, CommandError> {
+  let mod_dir = mod_path(&*config.lock().await, &game_name, &mod_name)?;
+  let mods_dir = match mod_dir.parent() {
+    Some(dir) => dir.to_path_buf(),
+    None => {
+      return Err(CommandError::GameManagement(
+        "Unable to determine where to install mods".to_string(),
+      ))
+    }
+  };
+  let mut task = match task_manager.start_or_attach(
+    &app_handle,
+    format!("mod-download-{}-{}", game_name, mod_name),
+    "Downloading mod",
+  ) {
+    Ok(task) => task,
+    Err(_) => {
+      return Err(CommandError::GameManagement(format!(
+        "'{}' is already being downloaded",
+        mod_name
+      )))
+    }
+  };
+  let archive_path = mods_dir.join(format!(".{}.download", mod_name));
+  let staging_dir = mods_dir.join(format!(".{}.tmp", mod_name));
+  let result = async {
+    create_dir(&mods_dir)?;
+    fetch_content(
+      &config,
+      &task,
+      &url,
+      allow_unknown_host.unwrap_or(false),
+      &archive_path,
+    )
+    .await?;
+    // Extracted next to the mod first, so a bad archive doesn't cost the user the working mod
+    delete_dir(&staging_dir)?;
+    create_dir(&staging_dir)?;
+    if let Err(err) = extract_archive(&archive_path, &staging_dir) {
+      log::error!("unable to extract mod: {}", err);
+      return Err(CommandError::GameManagement(
+        "Unable to extract mod".to_string(),
+      ));
+    }
+    delete_dir(&mod_dir)?;
+    std::fs::rename(&staging_dir, &mod_dir)?;
+    Ok(mod_dir.to_string_lossy().into_owned())
+  }
+  .await;
+  for leftover in [&archive_path, &staging_dir] {
+    let cleanup = if leftover.is_dir() {
+      delete_dir(leftover)
+    } else if leftover.exists() {
+      std::fs::remove_file(leftover)
+    } else {
+      Ok(())
+    };
+    if let Err(err) = cleanup {
+      log::warn!("unable to clean up {}: {}", leftover.display(), err);
+    }
+  }
+  task.finish(
+    result.is_ok(),
+    result.as_ref().err().map(|err| err.to_string()),
+  );
+  result
+}
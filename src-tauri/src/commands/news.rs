@@ -0,0 +1,152 @@
+// Per-game news and mod highlights for each game's library page
+//
+// Every game has its own feed, so the Jak 2 channel can be filled independently of Jak 1's.  The
+// feeds are small JSON documents that are cached for a while, a library page being opened
+// repeatedly doesn't refetch them, and when offline the last cached feed is shown instead.
+//
+// Unlike hotfixes the feeds aren't signed, they only ever end up as links, so entries whose link
+// isn't https are dropped.
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::util::file::read_json;
+
+use super::{game::validate_game_name, CommandError};
+
+const NEWS_FEED_URL: &str = "https://raw.githubusercontent.com/open-goal/launcher/main/news";
+const FEED_CACHE_LIFETIME_SECS: i64 = 60 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum FeedEntryKind {
+  News,
+  Mod,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedEntry {
+  pub id: String,
+  pub kind: FeedEntryKind,
+  pub title: String,
+  pub summary: Option<String>,
+  pub url: String,
+  pub image_url: Option<String>,
+  pub published: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct GameFeed {
+  pub game_name: String,
+  pub entries: Vec<FeedEntry>,
+  /// When the feed was last retrieved, `None` if it never was
+  pub fetched_at: Option<String>,
+  /// Whether the feed couldn't be refreshed, and is older than it should be
+  pub stale: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct FeedDocument {
+  #[serde(default)]
+  entries: Vec<FeedEntry>,
+}
+
+fn cache_path(app_handle: &tauri::AppHandle, game_name: &str) -> Option<PathBuf> {
+  app_handle
+    .path_resolver()
+    .app_cache_dir()
+    .map(|dir| dir.join("news").join(format!("{}.json", game_name)))
+}
+
+fn read_cache(path: &Option<PathBuf>) -> Option<GameFeed> {
+  read_json(path.as_ref()?)
+}
+
+fn is_fresh(feed: &GameFeed) -> bool {
+  feed
+    .fetched_at
+    .as_ref()
+    .and_then(|fetched_at| chrono::DateTime::parse_from_rfc3339(fetched_at).ok())
+    .map_or(false, |fetched_at| {
+      chrono::Local::now()
+        .signed_duration_since(fetched_at)
+        .num_seconds()
+        < FEED_CACHE_LIFETIME_SECS
+    })
+}
+
+async fn fetch_feed(game_name: &str) -> Result<Vec<FeedEntry>, reqwest::Error> {
+  let document: FeedDocument = reqwest::get(format!("{}/{}.json", NEWS_FEED_URL, game_name))
+    .await?
+    .error_for_status()?
+    .json()
+    .await?;
+  let is_https = |url: &String| url.starts_with("https://");
+  Ok(
+    document
+      .entries
+      .into_iter()
+      .filter(|entry| is_https(&entry.url))
+      .map(|mut entry| {
+        entry.image_url = entry.image_url.filter(is_https);
+        entry
+      })
+      .collect(),
+  )
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_game_feed(
+  app_handle: tauri::AppHandle,
+  game_name: String,
+  force_refresh: Option<bool>,
+) -> Result<GameFeed, CommandError> {
+  validate_game_name(&game_name)?;
+  let cache_path = cache_path(&app_handle, &game_name);
+  let cached = read_cache(&cache_path);
+  if let Some(cached) = &cached {
+    if !force_refresh.unwrap_or(false) && is_fresh(cached) {
+      return Ok(cached.clone());
+    }
+  }
+
+  match fetch_feed(&game_name).await {
+    Ok(entries) => {
+      let feed = GameFeed {
+        game_name,
+        entries,
+        fetched_at: Some(chrono::Local::now().to_rfc3339()),
+        stale: false,
+      };
+      if let Some(cache_path) = &cache_path {
+        let written = cache_path
+          .parent()
+          .map_or(Ok(()), std::fs::create_dir_all)
+          .and_then(|_| std::fs::write(cache_path, serde_json::to_string(&feed)?));
+        if let Err(err) = written {
+          log::warn!("Unable to cache news feed: {}", err);
+        }
+      }
+      Ok(feed)
+    }
+    Err(err) => {
+      log::warn!("Unable to fetch news feed for {}: {}", game_name, err);
+      Ok(match cached {
+        Some(cached) => GameFeed {
+          stale: true,
+          ..cached
+        },
+        None => GameFeed {
+          game_name,
+          entries: vec![],
+          fetched_at: None,
+          stale: true,
+        },
+      })
+    }
+  }
+}
@@ -0,0 +1,56 @@
+use tauri::Manager;
+
+use crate::{
+  config::LauncherConfig,
+  permissions::{self, PermissionKind, PermissionStatus},
+  remote_api::RemoteApiServer,
+  remote_config::{self, RemoteConfig},
+};
+
+use super::CommandError;
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_permissions(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<Vec<PermissionStatus>, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(
+    PermissionKind::ALL
+      .iter()
+      .map(|kind| permissions::status(&config_lock, *kind))
+      .collect(),
+  )
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_permission(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  server: tauri::State<'_, RemoteApiServer>,
+  app_handle: tauri::AppHandle,
+  kind: PermissionKind,
+  granted: bool,
+) -> Result<PermissionStatus, CommandError> {
+  let mut config_lock = config.lock().await;
+  config_lock
+    .set_permission(kind, granted)
+    .map_err(|_| CommandError::Configuration("Unable to persist permission".to_string()))?;
+  let status = permissions::status(&config_lock, kind);
+  drop(config_lock);
+  // Revoking takes effect right away, not on the next restart
+  match (kind, granted) {
+    (PermissionKind::RemoteApi, false) => server.stop(),
+    (PermissionKind::RemoteConfig, true) => {
+      tauri::async_runtime::spawn(remote_config::refresh(app_handle));
+    }
+    (PermissionKind::RemoteConfig, false) => {
+      let remote_config = app_handle.state::<tokio::sync::Mutex<RemoteConfig>>();
+      let mut remote_config_lock = remote_config.lock().await;
+      *remote_config_lock = RemoteConfig::default();
+      app_handle.emit_all("remoteConfigUpdated", remote_config_lock.clone())?;
+    }
+    _ => {}
+  }
+  Ok(status)
+}
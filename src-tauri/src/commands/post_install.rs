@@ -0,0 +1,200 @@
+// Steps run after a game has finished installing
+//
+// Which steps are run, and in what order, is part of the config rather than the install flow, so
+// a new step only has to be added here to be available.  Every step reports its progress with a
+// `postInstallStepUpdated` event.  The game is already installed by the time they're run, so a
+// failing step doesn't fail the install, it's only reported and ends up in the install summary's
+// warnings.
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use crate::{
+  config::{LauncherConfig, PostInstallStep},
+  paths::{self, PathResolver},
+  util::{file::overwrite_dir, os::create_desktop_shortcut},
+  warnings::{Warning, WarningCode},
+};
+
+use super::{game::apply_display_settings, CommandError};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum PostInstallStepStatus {
+  Running,
+  Succeeded,
+  Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PostInstallStepUpdate {
+  pub game_name: String,
+  pub index: u32,
+  pub total: u32,
+  pub step: PostInstallStep,
+  pub status: PostInstallStepStatus,
+  pub message: Option<String>,
+}
+
+fn is_valid_pack_name(pack: &str) -> bool {
+  !pack.is_empty() && pack != "." && pack != ".." && !pack.contains(&['/', '\\', ':'][..])
+}
+
+fn install_subtitle_pack(
+  config: &LauncherConfig,
+  game_name: &String,
+  pack: &str,
+) -> Result<Option<String>, CommandError> {
+  let game_data_path = config.game_data_path().ok_or_else(|| {
+    CommandError::GameManagement("No installation directory is configured".to_string())
+  })?;
+  let pack_dir = game_data_path
+    .join("subtitle_packs")
+    .join(game_name)
+    .join(pack);
+  if !is_valid_pack_name(pack) || !pack_dir.is_dir() {
+    return Err(CommandError::GameManagement(format!(
+      "Subtitle pack '{}' is not available for {}",
+      pack, game_name
+    )));
+  }
+  // Packs are laid out like the game's data folder
+  let data_dir = paths::game_data_dir(&game_data_path, game_name);
+  overwrite_dir(&pack_dir, &data_dir).map_err(|err| {
+    CommandError::GameManagement(format!("Unable to install subtitle pack: {}", err))
+  })?;
+  Ok(None)
+}
+
+fn verify_game_data(
+  config: &LauncherConfig,
+  game_name: &String,
+) -> Result<Option<String>, CommandError> {
+  let game_data_path = config.game_data_path().ok_or_else(|| {
+    CommandError::GameManagement("No installation directory is configured".to_string())
+  })?;
+  let data_dir = paths::game_data_dir(&game_data_path, game_name);
+  let expected = [
+    ("extracted", data_dir.join("iso_data").join(game_name)),
+    ("compiled", data_dir.join("out").join(game_name)),
+  ];
+  for (kind, dir) in expected {
+    let has_files = std::fs::read_dir(&dir).map_or(false, |mut entries| entries.next().is_some());
+    if !has_files {
+      return Err(CommandError::GameManagement(format!(
+        "The {} game data is missing from {}",
+        kind,
+        dir.display()
+      )));
+    }
+  }
+  Ok(None)
+}
+
+fn run_step(
+  paths: &PathResolver,
+  config: &LauncherConfig,
+  game_name: &String,
+  step: &PostInstallStep,
+) -> Result<Option<String>, CommandError> {
+  match step {
+    PostInstallStep::ApplyDefaultSettings => {
+      apply_display_settings(paths, config, game_name)?;
+      Ok(None)
+    }
+    PostInstallStep::InstallSubtitlePack { pack } => install_subtitle_pack(config, game_name, pack),
+    PostInstallStep::CreateShortcut => {
+      let shortcut = create_desktop_shortcut()?;
+      Ok(Some(shortcut.to_string_lossy().into_owned()))
+    }
+    PostInstallStep::RunVerification => verify_game_data(config, game_name),
+  }
+}
+
+/// Runs the configured post-install steps for a game, returning why the ones that failed did
+pub fn run_post_install_steps(
+  app_handle: &tauri::AppHandle,
+  config: &LauncherConfig,
+  game_name: &String,
+) -> Vec<Warning> {
+  let steps = config
+    .post_install_steps
+    .clone()
+    .unwrap_or_else(PostInstallStep::defaults);
+  let total = steps.len() as u32;
+  let mut failures = vec![];
+  for (index, step) in steps.into_iter().enumerate() {
+    let emit = |status: PostInstallStepStatus, message: Option<String>| {
+      let update = PostInstallStepUpdate {
+        game_name: game_name.clone(),
+        index: index as u32,
+        total,
+        step: step.clone(),
+        status,
+        message,
+      };
+      if let Err(err) = app_handle.emit_all("postInstallStepUpdated", update) {
+        log::warn!("Unable to emit post-install step update: {}", err);
+      }
+    };
+    emit(PostInstallStepStatus::Running, None);
+    match run_step(&app_handle.state(), config, game_name, &step) {
+      Ok(message) => {
+        log::info!("Post-install step {:?} finished for {}", step, game_name);
+        emit(PostInstallStepStatus::Succeeded, message);
+      }
+      Err(err) => {
+        log::warn!(
+          "Post-install step {:?} failed for {}: {}",
+          step,
+          game_name,
+          err
+        );
+        failures.push(Warning::new(
+          WarningCode::PostInstallStepFailed,
+          format!("Post-install step failed: {}", err),
+        ));
+        emit(PostInstallStepStatus::Failed, Some(err.to_string()));
+      }
+    }
+  }
+  failures
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_post_install_steps(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<Vec<PostInstallStep>, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(
+    config_lock
+      .post_install_steps
+      .clone()
+      .unwrap_or_else(PostInstallStep::defaults),
+  )
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_post_install_steps(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  steps: Vec<PostInstallStep>,
+) -> Result<(), CommandError> {
+  for step in &steps {
+    if let PostInstallStep::InstallSubtitlePack { pack } = step {
+      if !is_valid_pack_name(pack) {
+        return Err(CommandError::Configuration(format!(
+          "'{}' is not a valid subtitle pack name",
+          pack
+        )));
+      }
+    }
+  }
+  let mut config_lock = config.lock().await;
+  config_lock
+    .set_post_install_steps(steps)
+    .map_err(|_| CommandError::Configuration("Unable to persist post-install steps".to_string()))?;
+  Ok(())
+}
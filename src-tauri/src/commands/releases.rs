@@ -0,0 +1,250 @@
+// Release listings kept on disk, so the version manager still lists what it's seen while offline
+//
+// Every listing of official releases that's fetched from GitHub is cached next to the tooling,
+// replacing the previous one.  Only what's needed to list and download a release is kept, not
+// GitHub's whole response.
+//
+// A release's changelog (the body of its GitHub release) is cached when it's looked at, one file
+// per release.  Release notes are sometimes edited after they're published, so a cached one is
+// only shown for `CHANGELOG_MAX_AGE` before it's fetched again, or when GitHub can't be reached.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  config::{LauncherConfig, ReleaseChannel, VersionFolder, VersionName},
+  paths,
+  util::file::{read_json, write_json},
+};
+
+use super::{
+  compatibility::{github_client, github_repo},
+  CommandError,
+};
+
+const CHANGELOG_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseAsset {
+  pub name: String,
+  pub download_url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CachedRelease {
+  pub tag: String,
+  pub published_at: Option<String>,
+  pub github_link: Option<String>,
+  pub assets: Vec<ReleaseAsset>,
+  /// Listings cached before channels were a thing only had stable releases
+  #[serde(default)]
+  pub channel: ReleaseChannel,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseCache {
+  pub releases: Vec<CachedRelease>,
+  pub cached_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseChangelog {
+  pub tag: String,
+  pub name: Option<String>,
+  /// Markdown, as written on GitHub
+  pub body: String,
+  pub published_at: Option<String>,
+  pub github_link: Option<String>,
+  /// When it was fetched from GitHub
+  pub cached_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubRelease {
+  tag_name: String,
+  name: Option<String>,
+  body: Option<String>,
+  published_at: Option<String>,
+  html_url: Option<String>,
+}
+
+fn cache_path(config: &LauncherConfig) -> Option<PathBuf> {
+  config
+    .tooling_path()
+    .map(|dir| paths::release_cache_file(&dir))
+}
+
+fn changelog_cache_path(
+  config: &LauncherConfig,
+  version_folder: &VersionFolder,
+  version: &VersionName,
+) -> Option<PathBuf> {
+  config
+    .tooling_path()
+    .map(|dir| paths::changelog_cache_file(&dir, version_folder.as_str(), version.as_str()))
+}
+
+fn changelog_is_fresh(changelog: &ReleaseChangelog) -> bool {
+  chrono::DateTime::parse_from_rfc3339(&changelog.cached_at)
+    .ok()
+    .and_then(|cached_at| {
+      chrono::Local::now()
+        .signed_duration_since(cached_at)
+        .to_std()
+        .ok()
+    })
+    .map_or(false, |age| age < CHANGELOG_MAX_AGE)
+}
+
+fn write_changelog_cache(path: &Path, changelog: &ReleaseChangelog) -> std::io::Result<()> {
+  if let Some(dir) = path.parent() {
+    std::fs::create_dir_all(dir)?;
+  }
+  write_json(path, changelog)
+}
+
+/// Replaces the cached listing with `releases`
+pub fn write_release_cache(
+  config: &LauncherConfig,
+  releases: Vec<CachedRelease>,
+) -> Result<(), CommandError> {
+  let cache_path = match cache_path(config) {
+    Some(path) => path,
+    None => {
+      return Err(CommandError::Configuration(
+        "No installation directory set, can't cache releases".to_string(),
+      ))
+    }
+  };
+  let cache = ReleaseCache {
+    releases,
+    cached_at: chrono::Local::now().to_rfc3339(),
+  };
+  if let Some(dir) = cache_path.parent() {
+    std::fs::create_dir_all(dir)?;
+  }
+  let content = serde_json::to_string(&cache).map_err(|err| {
+    CommandError::VersionManagement(format!("Unable to serialize releases: {}", err))
+  })?;
+  std::fs::write(&cache_path, content)?;
+  Ok(())
+}
+
+/// The last listing that was cached, `None` if there is none or it can't be read
+pub fn read_release_cache(config: &LauncherConfig) -> Option<ReleaseCache> {
+  let cache_path = cache_path(config)?;
+  if !cache_path.exists() {
+    return None;
+  }
+  let content = match std::fs::read_to_string(&cache_path) {
+    Ok(content) => content,
+    Err(err) => {
+      log::warn!("Ignoring unreadable release cache: {}", err);
+      return None;
+    }
+  };
+  match serde_json::from_str(&content) {
+    Ok(cache) => Some(cache),
+    Err(err) => {
+      log::warn!("Ignoring unreadable release cache: {}", err);
+      None
+    }
+  }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn cache_releases(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  releases: Vec<CachedRelease>,
+) -> Result<(), CommandError> {
+  let config_lock = config.lock().await;
+  write_release_cache(&config_lock, releases)
+}
+
+/// The last listing that was cached, `None` if there is none
+#[tauri::command]
+#[specta::specta]
+pub async fn get_cached_releases(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<Option<ReleaseCache>, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(read_release_cache(&config_lock))
+}
+
+/// The changelog of `version` from `version_folder`'s source, from the cache if it was looked at
+/// recently (or GitHub can't be reached), `refresh` fetches it again regardless.  `None` if the
+/// source isn't a GitHub repository
+#[tauri::command]
+#[specta::specta]
+pub async fn get_release_changelog(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  version_folder: VersionFolder,
+  version: VersionName,
+  refresh: Option<bool>,
+) -> Result<Option<ReleaseChangelog>, CommandError> {
+  let config_lock = config.lock().await;
+  let cache_path = changelog_cache_path(&config_lock, &version_folder, &version);
+  let cached: Option<ReleaseChangelog> = cache_path.as_deref().and_then(read_json);
+  if let Some(changelog) = cached
+    .as_ref()
+    .filter(|changelog| !refresh.unwrap_or(false) && changelog_is_fresh(changelog))
+  {
+    return Ok(Some(changelog.clone()));
+  }
+  let remote = config_lock
+    .version_sources()
+    .into_iter()
+    .find(|source| source.folder == version_folder)
+    .and_then(|source| source.remote);
+  let github = github_client(&config_lock).map_err(CommandError::VersionManagement)?;
+  drop(config_lock);
+  let (owner, repo) = match remote.as_deref().and_then(github_repo) {
+    Some(repo) => repo,
+    None => return Ok(None),
+  };
+  let fetched: Result<GithubRelease, _> = github
+    .get_json(&format!(
+      "https://api.github.com/repos/{}/{}/releases/tags/{}",
+      owner, repo, version
+    ))
+    .await;
+  let release = match (fetched, cached) {
+    (Ok(release), _) => release,
+    (Err(err), Some(cached)) => {
+      log::warn!(
+        "Unable to refresh the changelog of {}, showing the cached one: {}",
+        version,
+        err
+      );
+      return Ok(Some(cached));
+    }
+    (Err(err), None) => {
+      return Err(CommandError::VersionManagement(format!(
+        "Unable to retrieve the changelog of {}: {}",
+        version, err
+      )))
+    }
+  };
+  let changelog = ReleaseChangelog {
+    tag: release.tag_name,
+    name: release.name.filter(|name| !name.trim().is_empty()),
+    body: release.body.unwrap_or_default(),
+    published_at: release.published_at,
+    github_link: release.html_url,
+    cached_at: chrono::Local::now().to_rfc3339(),
+  };
+  // Not being able to cache it only means it's fetched again next time
+  if let Some(cache_path) = &cache_path {
+    if let Err(err) = write_changelog_cache(cache_path, &changelog) {
+      log::warn!("Unable to cache the changelog of {}: {}", version, err);
+    }
+  }
+  Ok(Some(changelog))
+}
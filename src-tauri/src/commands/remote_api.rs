@@ -0,0 +1,127 @@
+use serde::Serialize;
+use tauri::Manager;
+
+use crate::{
+  config::LauncherConfig,
+  kiosk::KioskLock,
+  permissions::{self, PermissionKind},
+  remote_api::{generate_token, RemoteApiServer, DEFAULT_PORT},
+};
+
+use super::CommandError;
+
+#[derive(Debug, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteApiSettings {
+  pub enabled: bool,
+  pub port: u16,
+  pub token: Option<String>,
+}
+
+fn settings(config: &LauncherConfig) -> RemoteApiSettings {
+  RemoteApiSettings {
+    enabled: config.remote_api_enabled.unwrap_or(false),
+    port: config.remote_api_port.unwrap_or(DEFAULT_PORT),
+    token: config.remote_api_token.clone(),
+  }
+}
+
+async fn apply(
+  app_handle: tauri::AppHandle,
+  server: &RemoteApiServer,
+  settings: &RemoteApiSettings,
+) -> Result<(), CommandError> {
+  match (settings.enabled, &settings.token) {
+    (true, Some(token)) => server
+      .start(app_handle, settings.port, token.clone())
+      .await
+      .map_err(|err| {
+        CommandError::OSOperation(format!(
+          "Unable to start remote control API on port {}: {}",
+          settings.port, err
+        ))
+      }),
+    _ => {
+      server.stop();
+      Ok(())
+    }
+  }
+}
+
+/// Starts or stops the remote control API to match `config`, for when the settings were replaced
+/// as a whole
+pub async fn apply_remote_api_config(
+  app_handle: tauri::AppHandle,
+  config: &LauncherConfig,
+) -> Result<(), CommandError> {
+  let mut settings = settings(config);
+  settings.enabled &= permissions::is_granted(config, PermissionKind::RemoteApi);
+  let server = app_handle.state::<RemoteApiServer>();
+  apply(app_handle.clone(), &server, &settings).await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_remote_api_settings(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  kiosk_lock: tauri::State<'_, KioskLock>,
+) -> Result<RemoteApiSettings, CommandError> {
+  let config_lock = config.lock().await;
+  let mut settings = settings(&config_lock);
+  // Anyone with the token can control the launcher remotely, so it's not shown while locked
+  if kiosk_lock.is_locked() {
+    settings.token = None;
+  }
+  Ok(settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_remote_api_enabled(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  server: tauri::State<'_, RemoteApiServer>,
+  app_handle: tauri::AppHandle,
+  enabled: bool,
+  port: Option<u16>,
+) -> Result<RemoteApiSettings, CommandError> {
+  let mut config_lock = config.lock().await;
+  if enabled {
+    permissions::require(&config_lock, PermissionKind::RemoteApi)?;
+  }
+  // A token is generated the first time the API is turned on
+  let token = match &config_lock.remote_api_token {
+    Some(token) => token.clone(),
+    None => generate_token(),
+  };
+  let port = port.or(config_lock.remote_api_port);
+  config_lock
+    .set_remote_api_options(enabled, port, Some(token))
+    .map_err(|_| {
+      CommandError::Configuration("Unable to persist remote control API options".to_string())
+    })?;
+  let settings = settings(&config_lock);
+  apply(app_handle, &server, &settings).await?;
+  Ok(settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn regenerate_remote_api_token(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  server: tauri::State<'_, RemoteApiServer>,
+  app_handle: tauri::AppHandle,
+) -> Result<RemoteApiSettings, CommandError> {
+  let mut config_lock = config.lock().await;
+  let enabled = config_lock.remote_api_enabled.unwrap_or(false)
+    && permissions::is_granted(&config_lock, PermissionKind::RemoteApi);
+  let port = config_lock.remote_api_port;
+  config_lock
+    .set_remote_api_options(enabled, port, Some(generate_token()))
+    .map_err(|_| {
+      CommandError::Configuration("Unable to persist remote control API options".to_string())
+    })?;
+  // Restart the server so the old token stops working
+  let settings = settings(&config_lock);
+  apply(app_handle, &server, &settings).await?;
+  Ok(settings)
+}
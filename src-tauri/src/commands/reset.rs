@@ -0,0 +1,273 @@
+// Factory reset, removing everything the launcher has written for users whose install is in a
+// state that can't be fixed from the settings
+//
+// Resetting is a two step process.  `prepare_factory_reset` lists what would be removed along
+// with a one-time token, and only `factory_reset` with that token removes anything.  The token
+// is short-lived, so a reset can't be triggered by a stale or scripted call.
+//
+// The install directories themselves are left alone, only the folders the launcher manages in
+// them are removed (and only when asked to), as they may have been pointed at a folder holding
+// other things.  For the same reason, version folders mapped to a directory of the user's
+// choosing are never removed, and an app folder an install directory was put in is only cleared
+// around it.
+
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::{
+  config::LauncherConfig,
+  paths::versions_dir,
+  remote_api::generate_token,
+  util::{
+    crypto::constant_time_eq,
+    file::{delete_dir, display_paths, is_within},
+  },
+};
+
+use super::CommandError;
+
+const CONFIRMATION_LIFETIME: Duration = Duration::from_secs(5 * 60);
+
+/// The token handed out by the last `prepare_factory_reset`
+#[derive(Default)]
+pub struct FactoryResetConfirmation {
+  pending: std::sync::Mutex<Option<(String, Instant)>>,
+}
+
+impl FactoryResetConfirmation {
+  fn issue(&self) -> String {
+    let token = generate_token();
+    if let Ok(mut pending) = self.pending.lock() {
+      *pending = Some((token.clone(), Instant::now()));
+    }
+    token
+  }
+
+  /// Tokens can only be used once, whether they match or not
+  fn redeem(&self, token: &str) -> bool {
+    match self
+      .pending
+      .lock()
+      .ok()
+      .and_then(|mut pending| pending.take())
+    {
+      Some((expected, issued)) => {
+        constant_time_eq(expected.as_bytes(), token.as_bytes())
+          && issued.elapsed() < CONFIRMATION_LIFETIME
+      }
+      None => false,
+    }
+  }
+}
+
+#[derive(Debug, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct FactoryResetPreview {
+  pub confirm_token: String,
+  /// The launcher's own settings, caches and logs
+  pub launcher_paths: Vec<String>,
+  /// Tooling versions and game data, only removed if asked to
+  pub install_paths: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct FactoryResetSummary {
+  pub removed: Vec<String>,
+  /// Paths that couldn't be removed, ie. because a file in them is in use
+  pub failed: Vec<String>,
+  pub freed_bytes: u64,
+}
+
+// Where installs live, which nothing around them is removed from under
+fn install_dirs(config: &LauncherConfig) -> Vec<PathBuf> {
+  let mut dirs: Vec<PathBuf> = [
+    config.installation_dir.as_ref().map(PathBuf::from),
+    config.tooling_path(),
+    config.game_data_path(),
+  ]
+  .into_iter()
+  .flatten()
+  .collect();
+  if let Some(mapped) = &config.version_folder_dirs {
+    dirs.extend(mapped.values().map(PathBuf::from));
+  }
+  dirs
+}
+
+// `paths` with the ones leading to any of `keep` replaced by what else is in them, so removing
+// them all leaves `keep` alone
+fn around(paths: Vec<PathBuf>, keep: &[PathBuf]) -> Vec<PathBuf> {
+  let mut removable = vec![];
+  for path in paths {
+    if keep.iter().any(|dir| is_within(&path, dir)) {
+      continue;
+    }
+    if keep.iter().any(|dir| is_within(dir, &path)) {
+      let entries = std::fs::read_dir(&path)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .collect();
+      removable.extend(around(entries, keep));
+    } else {
+      removable.push(path);
+    }
+  }
+  removable
+}
+
+fn launcher_paths(app_handle: &tauri::AppHandle, config: &LauncherConfig) -> Vec<PathBuf> {
+  let resolver = app_handle.path_resolver();
+  let mut paths: Vec<PathBuf> = vec![
+    resolver.app_config_dir(),
+    resolver.app_data_dir(),
+    resolver.app_local_data_dir(),
+    resolver.app_cache_dir(),
+    resolver.app_log_dir(),
+  ]
+  .into_iter()
+  .flatten()
+  .collect();
+  // Depending on the platform these can be the same folder, or nested in one another
+  paths.sort();
+  paths.dedup();
+  let outermost = paths
+    .iter()
+    .filter(|path| path.exists())
+    .filter(|path| {
+      !paths
+        .iter()
+        .any(|other| other != *path && path.starts_with(other))
+    })
+    .cloned()
+    .collect();
+  around(outermost, &install_dirs(config))
+}
+
+fn install_paths(config: &LauncherConfig) -> Vec<PathBuf> {
+  let mut paths = vec![];
+  if let Some(tooling_path) = config.tooling_path() {
+    paths.push(versions_dir(&tooling_path));
+  }
+  if let Some(game_data_path) = config.game_data_path() {
+    for dir in ["active", "snapshots", "journal"].iter() {
+      paths.push(game_data_path.join(dir));
+    }
+  }
+  paths.sort();
+  paths.dedup();
+  paths.into_iter().filter(|path| path.exists()).collect()
+}
+
+fn dir_size(path: &PathBuf) -> u64 {
+  WalkDir::new(path)
+    .into_iter()
+    .filter_map(|entry| entry.ok())
+    .filter_map(|entry| entry.metadata().ok())
+    .filter(|metadata| metadata.is_file())
+    .map(|metadata| metadata.len())
+    .sum()
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn prepare_factory_reset(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  confirmation: tauri::State<'_, FactoryResetConfirmation>,
+  app_handle: tauri::AppHandle,
+) -> Result<FactoryResetPreview, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(FactoryResetPreview {
+    confirm_token: confirmation.issue(),
+    launcher_paths: display_paths(&launcher_paths(&app_handle, &config_lock)),
+    install_paths: display_paths(&install_paths(&config_lock)),
+  })
+}
+
+/// Removes the launcher's data, the launcher should be restarted afterwards to start over from
+/// the first-run setup
+#[tauri::command]
+#[specta::specta]
+pub async fn factory_reset(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  confirmation: tauri::State<'_, FactoryResetConfirmation>,
+  app_handle: tauri::AppHandle,
+  confirm_token: String,
+  include_install_dir: bool,
+) -> Result<FactoryResetSummary, CommandError> {
+  if !confirmation.redeem(&confirm_token) {
+    return Err(CommandError::Configuration(
+      "Factory reset was not confirmed, or the confirmation has expired".to_string(),
+    ));
+  }
+  let mut config_lock = config.lock().await;
+  let mut paths = launcher_paths(&app_handle, &config_lock);
+  if include_install_dir {
+    paths.extend(install_paths(&config_lock));
+  }
+
+  if config_lock.launch_on_startup.unwrap_or(false) {
+    if let Err(err) = crate::util::os::set_launch_on_startup(false, false) {
+      log::warn!("Unable to remove OS auto-start registration: {}", err);
+    }
+  }
+
+  log::warn!("Performing a factory reset, removing {:?}", paths);
+  let mut summary = FactoryResetSummary {
+    removed: vec![],
+    failed: vec![],
+    freed_bytes: 0,
+  };
+  for path in paths {
+    let size = dir_size(&path);
+    // Clearing around an install directory leaves files of the app folder to remove as well
+    let removed = if path.is_file() {
+      std::fs::remove_file(&path)
+    } else {
+      delete_dir(&path)
+    };
+    match removed {
+      Ok(()) => {
+        summary.freed_bytes += size;
+        summary.removed.push(path.to_string_lossy().into_owned());
+      }
+      Err(err) => {
+        log::error!("Unable to remove {}: {}", path.display(), err);
+        summary.failed.push(path.to_string_lossy().into_owned());
+      }
+    }
+  }
+
+  // Forget the old settings without writing them back out, the next save starts a fresh file
+  config_lock.discard();
+  Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn install_dirs_inside_app_folders_are_kept() {
+    let app_dir =
+      std::env::temp_dir().join(format!("opengoal-launcher-reset-{}", std::process::id()));
+    let install_dir = app_dir.join("games").join("install");
+    std::fs::create_dir_all(&install_dir).unwrap();
+    std::fs::create_dir_all(app_dir.join("logs")).unwrap();
+    std::fs::write(app_dir.join("settings.json"), b"{}").unwrap();
+
+    let mut removable = around(vec![app_dir.clone()], std::slice::from_ref(&install_dir));
+    removable.sort();
+    assert_eq!(
+      removable,
+      vec![app_dir.join("logs"), app_dir.join("settings.json")]
+    );
+    assert!(around(vec![install_dir.join("data")], &[install_dir]).is_empty());
+    std::fs::remove_dir_all(&app_dir).unwrap();
+  }
+}
@@ -0,0 +1,81 @@
+use std::path::Path;
+
+use tauri::Manager;
+
+use crate::{
+  config::LauncherConfig,
+  paths::PathResolver,
+  safe_mode::{SafeMode, SAFE_MODE_ARG},
+  util::{file::read_json, os::launcher_executable, process::tool_command},
+};
+
+use super::CommandError;
+
+#[tauri::command]
+#[specta::specta]
+pub async fn is_safe_mode(safe_mode: tauri::State<'_, SafeMode>) -> Result<bool, CommandError> {
+  Ok(safe_mode.is_enabled())
+}
+
+/// Moves the settings file aside (to `settings.json.bak`), so the next normal start begins from
+/// the defaults.  Returns the path of the backup, `None` if there was no settings file
+#[tauri::command]
+#[specta::specta]
+pub async fn discard_settings_file(
+  app_handle: tauri::AppHandle,
+) -> Result<Option<String>, CommandError> {
+  let settings_path = match app_handle
+    .state::<PathResolver>()
+    .launcher_file("settings.json")
+  {
+    Some(path) => path,
+    None => return Ok(None),
+  };
+  if !settings_path.exists() {
+    return Ok(None);
+  }
+  // Safe mode doesn't load the settings, but it mustn't become a way around locked mode either
+  let has_pin = read_json::<serde_json::Value>(&settings_path)
+    .map_or(false, |settings| settings["kioskPinHash"].is_string());
+  if has_pin {
+    return Err(CommandError::Locked(
+      "Locked mode is enabled, the settings can only be reset after unlocking the launcher"
+        .to_string(),
+    ));
+  }
+  let backup_path = settings_path.with_extension("json.bak");
+  std::fs::rename(&settings_path, &backup_path)?;
+  log::warn!(
+    "Discarded settings file, a backup was kept at {}",
+    backup_path.display()
+  );
+  Ok(Some(backup_path.to_string_lossy().into_owned()))
+}
+
+/// Starts a new instance of the launcher, in or out of safe mode, and exits this one
+#[tauri::command]
+#[specta::specta]
+pub async fn restart_launcher(
+  app_handle: tauri::AppHandle,
+  safe_mode: bool,
+) -> Result<(), CommandError> {
+  // The AppImage, not the executable inside its temporary mount
+  let executable = launcher_executable()?;
+  let cwd = executable
+    .parent()
+    .map(Path::to_path_buf)
+    .unwrap_or_default();
+  // The curated environment also leaves out the AppImage's own variables and `SAFE_MODE_ENV`
+  let config = app_handle.state::<tokio::sync::Mutex<LauncherConfig>>();
+  let mut command = tool_command(
+    &executable,
+    &cwd,
+    config.lock().await.tool_environment("launcher"),
+  );
+  if safe_mode {
+    command.arg(SAFE_MODE_ARG);
+  }
+  command.spawn()?;
+  app_handle.exit(0);
+  Ok(())
+}
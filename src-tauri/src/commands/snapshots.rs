@@ -0,0 +1,288 @@
+// Snapshots of a game's save directory, as a safety net before risky sections
+//
+// While a game launched by the launcher is running, a global hotkey takes a snapshot without
+// having to leave the game.  Snapshots live in the game data directory, one folder each holding
+// a copy of the saves and a small metadata file.  Restoring a snapshot first takes another one
+// of the current saves, so a restore can always be undone.
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tauri::{GlobalShortcutManager, Manager};
+
+use crate::{
+  config::LauncherConfig,
+  paths::{self, PathResolver},
+  tasks::TaskManager,
+  util::file::{
+    create_dir, delete_dir, overwrite_dir, read_json, read_last_lines_from_file, write_json,
+    CopyVerification,
+  },
+};
+
+use super::{game::validate_game_name, tasks::copy_dir_for_task, CommandError};
+
+pub const DEFAULT_SNAPSHOT_HOTKEY: &str = "CmdOrCtrl+Shift+F5";
+const METADATA_NAME: &str = "snapshot.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveSnapshot {
+  pub id: String,
+  pub game_name: String,
+  pub label: String,
+  pub created_at: String,
+  /// The last checkpoint the game reported before the snapshot, if it could be found
+  pub checkpoint: Option<String>,
+}
+
+fn saves_dir(app_handle: &tauri::AppHandle, game_name: &str) -> Result<PathBuf, CommandError> {
+  match app_handle.state::<PathResolver>().saves_dir(game_name) {
+    Some(path) => Ok(path),
+    None => Err(CommandError::GameManagement(
+      "Could not determine game config directory".to_string(),
+    )),
+  }
+}
+
+fn snapshots_dir(config: &LauncherConfig, game_name: &str) -> Result<PathBuf, CommandError> {
+  validate_game_name(game_name)?;
+  match config.game_data_path() {
+    Some(path) => Ok(paths::snapshots_dir(&path, game_name)),
+    None => Err(CommandError::GameManagement(
+      "No game data directory set, can't manage save snapshots".to_string(),
+    )),
+  }
+}
+
+// The game doesn't expose where the player is, but it logs checkpoints as they're reached
+fn last_checkpoint(app_handle: &tauri::AppHandle) -> Option<String> {
+  let log_path = app_handle.state::<PathResolver>().log_file("game.log")?;
+  let tail = read_last_lines_from_file(&log_path, 500).ok()?;
+  tail
+    .lines()
+    .rev()
+    .find(|line| line.to_lowercase().contains("continue point"))
+    .and_then(|line| line.split_whitespace().last())
+    .map(|checkpoint| {
+      checkpoint
+        .trim_matches(|c| c == '"' || c == '\'')
+        .to_string()
+    })
+}
+
+fn read_snapshots(dir: &PathBuf) -> Vec<SaveSnapshot> {
+  let mut snapshots: Vec<SaveSnapshot> = match std::fs::read_dir(dir) {
+    Ok(entries) => entries
+      .filter_map(|entry| entry.ok())
+      .filter_map(|entry| read_json(&entry.path().join(METADATA_NAME)))
+      .collect(),
+    Err(_) => vec![],
+  };
+  snapshots.sort_by(|a, b| b.id.cmp(&a.id));
+  snapshots
+}
+
+fn take_snapshot(
+  app_handle: &tauri::AppHandle,
+  config: &LauncherConfig,
+  game_name: &str,
+  label: Option<String>,
+) -> Result<SaveSnapshot, CommandError> {
+  let saves_dir = saves_dir(app_handle, game_name)?;
+  if !saves_dir.exists() {
+    return Err(CommandError::GameManagement(
+      "There are no saves to take a snapshot of".to_string(),
+    ));
+  }
+  let now = chrono::Local::now();
+  let snapshot = SaveSnapshot {
+    id: now.format("%Y%m%d%H%M%S%3f").to_string(),
+    game_name: game_name.to_string(),
+    label: label.unwrap_or(now.format("%Y-%m-%d %H:%M:%S").to_string()),
+    created_at: now.to_rfc3339(),
+    checkpoint: last_checkpoint(app_handle),
+  };
+  let snapshot_dir = snapshots_dir(config, game_name)?.join(&snapshot.id);
+  let snapshot_saves_dir = paths::snapshot_saves_dir(&snapshot_dir);
+  create_dir(&snapshot_saves_dir)?;
+  let result = overwrite_dir(&saves_dir, &snapshot_saves_dir)
+    .map_err(|err| {
+      CommandError::GameManagement(format!("Unable to copy saves into the snapshot: {}", err))
+    })
+    .and_then(|_| Ok(write_json(&snapshot_dir.join(METADATA_NAME), &snapshot)?));
+  if let Err(err) = result {
+    let _ = delete_dir(&snapshot_dir);
+    return Err(err);
+  }
+  log::info!("Took save snapshot '{}' of {}", snapshot.id, game_name);
+  app_handle.emit_all("saveSnapshotCreated", &snapshot)?;
+  Ok(snapshot)
+}
+
+/// Registers the snapshot hotkey for the game that was just launched, returning the accelerator
+/// so it can be unregistered when the game exits
+pub fn register_hotkey(
+  app_handle: &tauri::AppHandle,
+  config: &LauncherConfig,
+  game_name: &str,
+) -> Option<String> {
+  let accelerator = config
+    .snapshot_hotkey
+    .clone()
+    .unwrap_or(DEFAULT_SNAPSHOT_HOTKEY.to_string());
+  if accelerator.is_empty() {
+    return None;
+  }
+  let handler_app_handle = app_handle.clone();
+  let handler_game_name = game_name.to_string();
+  let result = app_handle
+    .global_shortcut_manager()
+    .register(&accelerator, move || {
+      let app_handle = handler_app_handle.clone();
+      let game_name = handler_game_name.clone();
+      tauri::async_runtime::spawn(async move {
+        let config = app_handle.state::<tokio::sync::Mutex<LauncherConfig>>();
+        let config_lock = config.lock().await;
+        if let Err(err) = take_snapshot(&app_handle, &config_lock, &game_name, None) {
+          log::error!("Unable to take save snapshot: {}", err);
+        }
+      });
+    });
+  match result {
+    Ok(()) => Some(accelerator),
+    Err(err) => {
+      log::warn!(
+        "Unable to register snapshot hotkey '{}': {}",
+        accelerator,
+        err
+      );
+      None
+    }
+  }
+}
+
+pub fn unregister_hotkey(app_handle: &tauri::AppHandle, accelerator: &str) {
+  if let Err(err) = app_handle.global_shortcut_manager().unregister(accelerator) {
+    log::warn!(
+      "Unable to unregister snapshot hotkey '{}': {}",
+      accelerator,
+      err
+    );
+  }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn create_snapshot(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  app_handle: tauri::AppHandle,
+  game_name: String,
+  label: Option<String>,
+) -> Result<SaveSnapshot, CommandError> {
+  let config_lock = config.lock().await;
+  take_snapshot(&app_handle, &config_lock, &game_name, label)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn list_snapshots(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  game_name: String,
+) -> Result<Vec<SaveSnapshot>, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(read_snapshots(&snapshots_dir(&config_lock, &game_name)?))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn restore_snapshot(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  task_manager: tauri::State<'_, TaskManager>,
+  app_handle: tauri::AppHandle,
+  game_name: String,
+  id: String,
+) -> Result<(), CommandError> {
+  let config_lock = config.lock().await;
+  let snapshot_dir = snapshots_dir(&config_lock, &game_name)?;
+  let snapshot = read_snapshots(&snapshot_dir)
+    .into_iter()
+    .find(|snapshot| snapshot.id == id)
+    .ok_or_else(|| CommandError::GameManagement(format!("No snapshot with id '{}'", id)))?;
+  let saves_dir = saves_dir(&app_handle, &game_name)?;
+  if saves_dir.exists() {
+    take_snapshot(
+      &app_handle,
+      &config_lock,
+      &game_name,
+      Some(format!("Before restoring '{}'", snapshot.label)),
+    )?;
+  }
+  let mut task = match task_manager.start_or_attach(
+    &app_handle,
+    format!("restore-snapshot-{}", game_name),
+    "Restoring saves",
+  ) {
+    Ok(task) => task,
+    Err(_) => {
+      return Err(CommandError::GameManagement(format!(
+        "A snapshot of {} is already being restored",
+        game_name
+      )))
+    }
+  };
+  let result = async {
+    delete_dir(&saves_dir)?;
+    create_dir(&saves_dir)?;
+    // Saves are small, and a corrupted one is only found out about in-game
+    if let Err(err) = copy_dir_for_task(
+      &app_handle,
+      &task,
+      &paths::snapshot_saves_dir(&snapshot_dir.join(&snapshot.id)),
+      &saves_dir,
+      CopyVerification::Hash,
+    ) {
+      task.finish(false, Some(err.to_string()));
+      return Err(CommandError::GameManagement(format!(
+        "Unable to restore snapshot: {}",
+        err
+      )));
+    }
+    task.finish(true, None);
+    log::info!("Restored save snapshot '{}' of {}", snapshot.id, game_name);
+    Ok(())
+  }
+  .await;
+  if let Err(err) = &result {
+    task.finish(false, Some(err.to_string()));
+  }
+  result
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_snapshot_hotkey(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<String, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(
+    config_lock
+      .snapshot_hotkey
+      .clone()
+      .unwrap_or(DEFAULT_SNAPSHOT_HOTKEY.to_string()),
+  )
+}
+
+/// An empty hotkey disables it
+#[tauri::command]
+#[specta::specta]
+pub async fn set_snapshot_hotkey(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  hotkey: String,
+) -> Result<(), CommandError> {
+  let mut config_lock = config.lock().await;
+  config_lock
+    .set_snapshot_hotkey(hotkey)
+    .map_err(|_| CommandError::Configuration("Unable to persist snapshot hotkey".to_string()))?;
+  Ok(())
+}
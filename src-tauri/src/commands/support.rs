@@ -1,19 +1,39 @@
 use serde::{Deserialize, Serialize};
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
 use std::{
   io::{BufWriter, Write},
-  path::Path,
+  path::{Path, PathBuf},
+  time::Duration,
 };
-use sysinfo::{CpuExt, DiskExt, System, SystemExt};
+use sysinfo::{CpuExt, DiskExt, ProcessExt, System, SystemExt};
+use walkdir::WalkDir;
 use zip::write::FileOptions;
 
-use tauri::api::path::config_dir;
+use tauri::Manager;
 
 use crate::{
-  config::LauncherConfig,
-  util::zip::{append_dir_contents_to_zip, append_file_to_zip},
+  config::{redact_settings, LauncherConfig, SECRET_SETTINGS},
+  paths::{game_data_dir, installed_games_dir, PathResolver},
+  remote_config::RemoteConfig,
+  safe_mode::SafeMode,
+  setup_funnel::{self, SetupFunnelReport},
+  supervisor::{ServiceStatus, Supervisor},
+  tasks::TaskManager,
+  util::{
+    cloud_sync::{self, CloudSyncClient},
+    file::{delete_file, read_json},
+    github::GithubClient,
+    process::tool_command,
+    zip::{append_dir_contents_to_zip, append_file_to_zip},
+  },
 };
 
-use super::CommandError;
+use super::{
+  binaries::{bin_ext, LaunchFailureReport},
+  compatibility::github_client,
+  CommandError,
+};
 
 #[derive(Default, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -61,9 +81,12 @@ pub struct SupportPackage {
   pub gpu_info: Vec<GPUInfo>,
   pub game_info: PerGameInfo,
   pub launcher_version: String,
+  pub missing_video_components: Vec<String>,
+  pub setup_funnel: Option<SetupFunnelReport>,
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn generate_support_package(
   config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
   app_handle: tauri::AppHandle,
@@ -71,13 +94,13 @@ pub async fn generate_support_package(
 ) -> Result<(), CommandError> {
   let mut package = SupportPackage::default();
   let config_lock = config.lock().await;
-  let install_path = match &config_lock.installation_dir {
-    None => {
-      return Err(CommandError::Support(format!(
-        "No installation directory set, can't generate the support package"
-      )))
+  let game_data_path = match (config_lock.tooling_path(), config_lock.game_data_path()) {
+    (Some(_), Some(game_data_path)) => game_data_path,
+    (_, _) => {
+      return Err(CommandError::Support(
+        "No installation directory set, can't generate the support package".to_string(),
+      ))
     }
-    Some(path) => Path::new(path),
   };
 
   // System Information
@@ -95,6 +118,10 @@ pub async fn generate_support_package(
     .kernel_version()
     .unwrap_or("unknown".to_string());
   package.launcher_version = app_handle.package_info().version.to_string();
+  // It's a probe, which safe mode doesn't run
+  if !app_handle.state::<SafeMode>().is_enabled() {
+    package.missing_video_components = crate::util::os::missing_video_components();
+  }
 
   for disk in system_info.disks() {
     package.disk_info.push(format!(
@@ -124,17 +151,22 @@ pub async fn generate_support_package(
   let mut zip_file = zip::ZipWriter::new(save_file);
 
   // Save OpenGOAL config folder (this includes saves and settings)
-  let game_config_dir = match config_dir() {
-    None => {
+  let paths = app_handle.state::<PathResolver>();
+  let (settings_dir, misc_dir, saves_dir) = match (
+    paths.game_settings_dir("jak1"),
+    paths.game_misc_dir("jak1"),
+    paths.saves_dir("jak1"),
+  ) {
+    (Some(settings_dir), Some(misc_dir), Some(saves_dir)) => (settings_dir, misc_dir, saves_dir),
+    _ => {
       return Err(CommandError::Support(format!(
         "Couldn't determine application config directory"
       )))
     }
-    Some(path) => path.join("OpenGOAL"),
   };
   append_dir_contents_to_zip(
     &mut zip_file,
-    &game_config_dir.join("jak1").join("settings"),
+    &settings_dir,
     "Game Settings and Saves/jak1/settings",
     vec!["gc", "json"],
   )
@@ -145,7 +177,7 @@ pub async fn generate_support_package(
   })?;
   append_dir_contents_to_zip(
     &mut zip_file,
-    &game_config_dir.join("jak1").join("misc"),
+    &misc_dir,
     "Game Settings and Saves/jak1/misc",
     vec!["gc", "json"],
   )
@@ -156,7 +188,7 @@ pub async fn generate_support_package(
   })?;
   append_dir_contents_to_zip(
     &mut zip_file,
-    &game_config_dir.join("jak1").join("saves"),
+    &saves_dir,
     "Game Settings and Saves/jak1/saves",
     vec!["bin"],
   )
@@ -167,17 +199,17 @@ pub async fn generate_support_package(
   })?;
 
   // Save Launcher config folder
-  let launcher_config_dir = match app_handle.path_resolver().app_config_dir() {
-    None => {
+  let (launcher_config_dir, launcher_log_dir) = match (paths.launcher_dir(), paths.log_dir()) {
+    (Some(config_dir), Some(log_dir)) => (config_dir, log_dir),
+    _ => {
       return Err(CommandError::Support(format!(
         "Couldn't determine launcher config directory"
       )))
     }
-    Some(path) => path,
   };
   append_dir_contents_to_zip(
     &mut zip_file,
-    &launcher_config_dir.join("logs"),
+    &launcher_log_dir,
     "Launcher Settings and Logs/logs",
     vec!["log"],
   )
@@ -186,21 +218,15 @@ pub async fn generate_support_package(
       "Unable to append launcher logs to the support package"
     ))
   })?;
-  append_file_to_zip(
-    &mut zip_file,
-    &launcher_config_dir.join("settings.json"),
-    "Launcher Settings and Logs/settings.json",
-  )
-  .map_err(|_| {
-    CommandError::Support(format!(
-      "Unable to append launcher settings to the support package"
-    ))
-  })?;
+  append_settings_to_zip(&mut zip_file, &launcher_config_dir.join("settings.json")).map_err(
+    |_| {
+      CommandError::Support("Unable to append launcher settings to the support package".to_string())
+    },
+  )?;
 
   // Save Logs
-  let active_version_dir = install_path.join("active");
   // TODO - for all games
-  let jak1_log_dir = active_version_dir.join("jak1").join("data").join("log");
+  let jak1_log_dir = game_data_dir(&game_data_path, "jak1").join("log");
   append_dir_contents_to_zip(
     &mut zip_file,
     &jak1_log_dir,
@@ -210,15 +236,11 @@ pub async fn generate_support_package(
   .map_err(|_| CommandError::Support(format!("Unable to append game logs to support package")))?;
 
   // Per Game Info
-  let texture_repl_dir = active_version_dir
-    .join("jak1")
-    .join("data")
-    .join("texture_replacements");
+  let jak1_data_dir = game_data_dir(&game_data_path, "jak1");
+  let texture_repl_dir = jak1_data_dir.join("texture_replacements");
   package.game_info.jak1.has_texture_packs =
     texture_repl_dir.exists() && !texture_repl_dir.read_dir().unwrap().next().is_none();
-  let build_info_path = active_version_dir
-    .join("jak1")
-    .join("data")
+  let build_info_path = jak1_data_dir
     .join("iso_data")
     .join("jak1")
     .join("buildinfo.json");
@@ -231,13 +253,15 @@ pub async fn generate_support_package(
     CommandError::Support(format!("Unable to append iso metadata to support package"))
   })?;
 
-  if config_lock.active_version_folder.is_some() && config_lock.active_version_folder.is_some() {
-    let data_dir = active_version_dir.join("jak1").join("data");
-    let version_data_dir = install_path
-      .join("versions")
-      .join(config_lock.active_version_folder.as_ref().unwrap())
-      .join(config_lock.active_version.as_ref().unwrap())
-      .join("data");
+  let active_version_folder_path = config_lock
+    .active_version_folder
+    .as_ref()
+    .and_then(|folder| config_lock.version_folder_path(folder));
+  if let (Some(version_folder_path), Some(active_version)) =
+    (active_version_folder_path, &config_lock.active_version)
+  {
+    let data_dir = jak1_data_dir;
+    let version_data_dir = version_folder_path.join(active_version).join("data");
     package
       .game_info
       .jak1
@@ -258,6 +282,8 @@ pub async fn generate_support_package(
         .unwrap_or(true);
   }
 
+  package.setup_funnel = Some(setup_funnel::report(&app_handle));
+
   // Dump High Level Info
   let options = FileOptions::default()
     .compression_method(zip::CompressionMethod::DEFLATE)
@@ -287,3 +313,482 @@ pub async fn generate_support_package(
 
   Ok(())
 }
+
+#[derive(Debug, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfTestResult {
+  pub subsystem: String,
+  pub passed: bool,
+  pub msg: Option<String>,
+}
+
+impl SelfTestResult {
+  fn new(subsystem: &str, result: Result<(), String>) -> Self {
+    match result {
+      Ok(_) => {
+        log::info!("self-test [{}] passed", subsystem);
+        Self {
+          subsystem: subsystem.to_string(),
+          passed: true,
+          msg: None,
+        }
+      }
+      Err(msg) => {
+        log::error!("self-test [{}] failed - {}", subsystem, msg);
+        Self {
+          subsystem: subsystem.to_string(),
+          passed: false,
+          msg: Some(msg),
+        }
+      }
+    }
+  }
+}
+
+// Write, read back and delete a file, nothing is left behind on success or failure
+fn self_test_directory(dir: Option<PathBuf>) -> Result<(), String> {
+  let dir = dir.ok_or("Directory is not set".to_string())?;
+  if !dir.exists() {
+    return Err(format!("'{}' does not exist", dir.display()));
+  }
+  let test_file = dir.join(".self-test.tmp");
+  let expected_contents = "opengoal-launcher self-test";
+  let result = std::fs::write(&test_file, expected_contents)
+    .map_err(|err| format!("Unable to write to '{}' - {}", test_file.display(), err))
+    .and_then(|_| {
+      std::fs::read_to_string(&test_file)
+        .map_err(|err| format!("Unable to read from '{}' - {}", test_file.display(), err))
+    })
+    .and_then(|contents| {
+      if contents == expected_contents {
+        Ok(())
+      } else {
+        Err(format!(
+          "Contents of '{}' did not match what was written",
+          test_file.display()
+        ))
+      }
+    });
+  if test_file.exists() {
+    std::fs::remove_file(&test_file)
+      .map_err(|err| format!("Unable to delete '{}' - {}", test_file.display(), err))?;
+  }
+  result
+}
+
+// The settings with their secrets taken out, settings that can't be parsed are added as is
+fn append_settings_to_zip(
+  zip_file: &mut zip::ZipWriter<std::fs::File>,
+  settings_path: &Path,
+) -> zip::result::ZipResult<()> {
+  let path_in_zip = "Launcher Settings and Logs/settings.json";
+  let mut settings: serde_json::Value = match read_json(settings_path) {
+    Some(settings) => settings,
+    None => return append_file_to_zip(zip_file, settings_path, path_in_zip),
+  };
+  redact_settings(&mut settings, SECRET_SETTINGS);
+  let options = FileOptions::default().compression_method(zip::CompressionMethod::DEFLATE);
+  zip_file.start_file(path_in_zip, options)?;
+  zip_file.write_all(
+    serde_json::to_string_pretty(&settings)
+      .unwrap_or_default()
+      .as_bytes(),
+  )?;
+  Ok(())
+}
+
+async fn self_test_release_endpoint(github: Result<GithubClient, String>) -> Result<(), String> {
+  github?
+    .get_json::<serde_json::Value>(
+      "https://api.github.com/repos/open-goal/jak-project/releases?per_page=1",
+    )
+    .await
+    .map(|_| ())
+    .map_err(|err| format!("Unable to reach the release endpoint - {}", err))
+}
+
+fn self_test_extractor(config: &LauncherConfig) -> Result<(), String> {
+  let exec_dir = match (&config.active_version_folder, &config.active_version) {
+    (Some(version_folder), Some(version)) => match config.version_folder_path(version_folder) {
+      Some(path) => path.join(version),
+      None => return Err("No tooling directory set".to_string()),
+    },
+    (_, _) => return Err("No active version set".to_string()),
+  };
+  let exec_path = exec_dir.join(bin_ext("extractor"));
+  if !exec_path.exists() {
+    return Err(format!("'{}' does not exist", exec_path.display()));
+  }
+  let mut command = tool_command(&exec_path, &exec_dir, config.tool_environment("extractor"));
+  command.arg("--version");
+  #[cfg(windows)]
+  {
+    command.creation_flags(0x08000000);
+  }
+  let output = command
+    .output()
+    .map_err(|err| format!("Unable to spawn '{}' - {}", exec_path.display(), err))?;
+  if !output.status.success() {
+    return Err(format!(
+      "'{}' exited with {}",
+      exec_path.display(),
+      output.status
+    ));
+  }
+  Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CloudSyncedFolder {
+  /// Which of the launcher's folders it is, `toolingDirectory`, `gameDataDirectory` or `saves`
+  pub location: String,
+  pub path: String,
+  pub client: CloudSyncClient,
+  /// The root of what the client syncs, which the folder is somewhere in
+  pub sync_root: String,
+}
+
+fn cloud_synced_folders(config: &LauncherConfig, paths: &PathResolver) -> Vec<CloudSyncedFolder> {
+  let saves_dir = paths.games_user_dir();
+  [
+    ("toolingDirectory", config.tooling_path()),
+    ("gameDataDirectory", config.game_data_path()),
+    ("saves", saves_dir),
+  ]
+  .into_iter()
+  .filter_map(|(location, path)| {
+    let path = path?;
+    let (client, sync_root) = cloud_sync::detect(&path)?;
+    Some(CloudSyncedFolder {
+      location: location.to_string(),
+      path: path.to_string_lossy().into_owned(),
+      client,
+      sync_root: sync_root.to_string_lossy().into_owned(),
+    })
+  })
+  .collect()
+}
+
+fn self_test_cloud_sync(config: &LauncherConfig, paths: &PathResolver) -> Result<(), String> {
+  let synced = cloud_synced_folders(config, paths);
+  if synced.is_empty() {
+    return Ok(());
+  }
+  Err(format!(
+    "Synced by a cloud client, which can break compiling and saving - {}",
+    synced
+      .iter()
+      .map(|folder| format!("'{}' ({})", folder.path, folder.client.name()))
+      .collect::<Vec<String>>()
+      .join(", ")
+  ))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn run_self_test(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  paths: tauri::State<'_, PathResolver>,
+) -> Result<Vec<SelfTestResult>, CommandError> {
+  let config_lock = config.lock().await;
+  log::info!("Running self-test");
+  Ok(vec![
+    SelfTestResult::new(
+      "toolingDirectory",
+      self_test_directory(config_lock.tooling_path()),
+    ),
+    SelfTestResult::new(
+      "gameDataDirectory",
+      self_test_directory(config_lock.game_data_path()),
+    ),
+    SelfTestResult::new(
+      "releaseEndpoint",
+      self_test_release_endpoint(github_client(&config_lock)).await,
+    ),
+    SelfTestResult::new("extractor", self_test_extractor(&config_lock)),
+    SelfTestResult::new("cloudSync", self_test_cloud_sync(&config_lock, &paths)),
+  ])
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_cloud_synced_folders(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  paths: tauri::State<'_, PathResolver>,
+) -> Result<Vec<CloudSyncedFolder>, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(cloud_synced_folders(&config_lock, &paths))
+}
+
+/// Asks the sync client to leave one of the folders `get_cloud_synced_folders` found alone
+#[tauri::command]
+#[specta::specta]
+pub async fn exclude_from_cloud_sync(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  paths: tauri::State<'_, PathResolver>,
+  path: String,
+) -> Result<(), CommandError> {
+  let config_lock = config.lock().await;
+  let folder = match cloud_synced_folders(&config_lock, &paths)
+    .into_iter()
+    .find(|folder| folder.path == path)
+  {
+    Some(folder) => folder,
+    None => {
+      return Err(CommandError::Support(format!(
+        "'{}' isn't a launcher folder that's being synced",
+        path
+      )))
+    }
+  };
+  drop(config_lock);
+  cloud_sync::exclude_from_sync(folder.client, Path::new(&folder.path)).map_err(|err| {
+    CommandError::Support(format!(
+      "Unable to exclude '{}' from {}: {}",
+      folder.path,
+      folder.client.name(),
+      err
+    ))
+  })?;
+  log::info!("Excluded '{}' from {}", folder.path, folder.client.name());
+  Ok(())
+}
+
+fn files_in_dir(dir: &Path, extension: &str) -> Vec<PathBuf> {
+  match std::fs::read_dir(dir) {
+    Ok(entries) => entries
+      .filter_map(|entry| entry.ok())
+      .map(|entry| entry.path())
+      .filter(|path| path.is_file() && path.extension().map_or(false, |ext| ext == extension))
+      .collect(),
+    Err(_) => vec![],
+  }
+}
+
+// The daily rotated logs for the launcher itself
+fn launcher_log_files(app_handle: &tauri::AppHandle) -> Vec<PathBuf> {
+  match app_handle.state::<PathResolver>().log_dir() {
+    Some(log_dir) => files_in_dir(&log_dir.join("app"), "log"),
+    None => vec![],
+  }
+}
+
+// The output of the install steps and the last game launch (`extractor.log`, `game.log`)
+fn install_log_files(app_handle: &tauri::AppHandle) -> Vec<PathBuf> {
+  match app_handle.state::<PathResolver>().log_dir() {
+    Some(log_dir) => files_in_dir(&log_dir, "log"),
+    None => vec![],
+  }
+}
+
+fn crash_report_files(app_handle: &tauri::AppHandle) -> Vec<PathBuf> {
+  match LaunchFailureReport::path(app_handle) {
+    Some(path) if path.exists() => vec![path],
+    _ => vec![],
+  }
+}
+
+fn remove_files_older_than(files: Vec<PathBuf>, days: Option<u32>) {
+  let max_age = match days {
+    Some(days) => Duration::from_secs(u64::from(days) * 24 * 60 * 60),
+    None => return,
+  };
+  for file in files {
+    let age = std::fs::metadata(&file)
+      .and_then(|metadata| metadata.modified())
+      .ok()
+      .and_then(|modified| modified.elapsed().ok());
+    if age.map_or(false, |age| age > max_age) {
+      log::info!("retention policy - deleting {}", file.display());
+      if let Err(err) = delete_file(&file) {
+        log::warn!("unable to delete {}: {}", file.display(), err);
+      }
+    }
+  }
+}
+
+/// Deletes launcher generated data that is older than the configured retention policy,
+/// ran once at startup
+pub fn enforce_retention_policy(app_handle: &tauri::AppHandle, config: &LauncherConfig) {
+  remove_files_older_than(
+    launcher_log_files(app_handle),
+    config.launcher_log_retention_days,
+  );
+  remove_files_older_than(
+    install_log_files(app_handle),
+    config.install_log_retention_days,
+  );
+  remove_files_older_than(
+    crash_report_files(app_handle),
+    config.crash_report_retention_days,
+  );
+}
+
+#[derive(Debug, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DataUsageEntry {
+  pub category: String,
+  pub path: Option<String>,
+  pub size_bytes: u64,
+  pub file_count: u64,
+}
+
+impl DataUsageEntry {
+  fn new(category: &str, path: Option<PathBuf>, files: Vec<PathBuf>) -> Self {
+    let mut entry = Self {
+      category: category.to_string(),
+      path: path.map(|path| path.to_string_lossy().into_owned()),
+      size_bytes: 0,
+      file_count: 0,
+    };
+    for file in files {
+      if let Ok(metadata) = std::fs::metadata(&file) {
+        entry.size_bytes += metadata.len();
+        entry.file_count += 1;
+      }
+    }
+    entry
+  }
+
+  fn from_dir(category: &str, dir: Option<PathBuf>) -> Self {
+    let files = match &dir {
+      Some(dir) if dir.exists() => WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .collect(),
+      _ => vec![],
+    };
+    Self::new(category, dir, files)
+  }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_data_usage_breakdown(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  app_handle: tauri::AppHandle,
+) -> Result<Vec<DataUsageEntry>, CommandError> {
+  let config_lock = config.lock().await;
+  let paths = app_handle.state::<PathResolver>();
+  let log_dir = paths.log_dir();
+  Ok(vec![
+    DataUsageEntry::new(
+      "launcherLogs",
+      log_dir.as_ref().map(|dir| dir.join("app")),
+      launcher_log_files(&app_handle),
+    ),
+    DataUsageEntry::new(
+      "installLogs",
+      log_dir.clone(),
+      install_log_files(&app_handle),
+    ),
+    DataUsageEntry::new("crashReports", log_dir, crash_report_files(&app_handle)),
+    DataUsageEntry::from_dir("launcherSettings", paths.launcher_dir()),
+    DataUsageEntry::from_dir("toolingVersions", paths.versions_dir(&config_lock)),
+    DataUsageEntry::from_dir(
+      "gameData",
+      config_lock
+        .game_data_path()
+        .map(|path| installed_games_dir(&path)),
+    ),
+  ])
+}
+
+/// The backend's long running services, and how often they had to be restarted
+#[tauri::command]
+#[specta::specta]
+pub async fn get_service_status(
+  supervisor: tauri::State<'_, Supervisor>,
+) -> Result<Vec<ServiceStatus>, CommandError> {
+  Ok(supervisor.status())
+}
+
+/// Which setup steps were attempted on this machine and how they went, kept locally for users to
+/// share when asking for help
+#[tauri::command]
+#[specta::specta]
+pub async fn get_setup_funnel_report(
+  app_handle: tauri::AppHandle,
+) -> Result<SetupFunnelReport, CommandError> {
+  Ok(setup_funnel::report(&app_handle))
+}
+
+// Bumped whenever a command or event changes in a way the frontend has to be updated for, so a
+// frontend and backend from different releases (ie. after a partial update) can be detected
+pub const API_VERSION: u32 = 2;
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_api_version() -> Result<u32, CommandError> {
+  Ok(API_VERSION)
+}
+
+#[derive(Debug, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheUsage {
+  pub name: String,
+  pub entries: u64,
+  /// Approximated by the size of the cache serialized as JSON
+  pub approximate_bytes: u64,
+}
+
+impl CacheUsage {
+  fn new<T: Serialize>(name: &str, entries: usize, contents: &T) -> Self {
+    Self {
+      name: name.to_string(),
+      entries: entries as u64,
+      approximate_bytes: serde_json::to_vec(contents)
+        .map(|bytes| bytes.len() as u64)
+        .unwrap_or(0),
+    }
+  }
+}
+
+#[derive(Debug, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryUsage {
+  pub allocator: String,
+  pub resident_bytes: u64,
+  pub virtual_bytes: u64,
+  pub caches: Vec<CacheUsage>,
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_memory_usage(
+  remote_config: tauri::State<'_, tokio::sync::Mutex<RemoteConfig>>,
+  task_manager: tauri::State<'_, TaskManager>,
+) -> Result<MemoryUsage, CommandError> {
+  let mut system_info = System::new();
+  let pid = sysinfo::get_current_pid().map_err(|err| {
+    CommandError::Support(format!("Unable to determine launcher process: {}", err))
+  })?;
+  system_info.refresh_process(pid);
+  let (resident_bytes, virtual_bytes) = match system_info.process(pid) {
+    Some(process) => (process.memory(), process.virtual_memory()),
+    None => (0, 0),
+  };
+
+  let remote_config_lock = remote_config.lock().await;
+  let tasks = task_manager.all();
+  Ok(MemoryUsage {
+    allocator: if cfg!(feature = "mimalloc") {
+      "mimalloc".to_string()
+    } else {
+      "system".to_string()
+    },
+    resident_bytes,
+    virtual_bytes,
+    caches: vec![
+      CacheUsage::new(
+        "remoteConfig",
+        remote_config_lock.features.len() + remote_config_lock.disabled_download_hosts.len(),
+        &*remote_config_lock,
+      ),
+      CacheUsage::new("tasks", tasks.len(), &tasks),
+    ],
+  })
+}
@@ -0,0 +1,117 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use crate::{
+  tasks::{TaskHandle, TaskManager, TaskState},
+  util::file::{
+    copy_dir_with_progress, delete_dirs_with_progress, move_dir_with_progress, CopyProgress,
+    CopyVerification, DeletionProgress,
+  },
+};
+
+use super::CommandError;
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DeletionProgressUpdate {
+  pub task_id: String,
+  pub progress: DeletionProgress,
+}
+
+/// Deletes `paths` as part of `task` so it can be cancelled, emitting `deletionProgress` events
+/// along the way
+pub fn delete_dirs_for_task(
+  app_handle: &tauri::AppHandle,
+  task: &TaskHandle,
+  paths: &[PathBuf],
+) -> Result<DeletionProgress, std::io::Error> {
+  delete_dirs_with_progress(paths, &task.cancellation_token(), |progress| {
+    let update = DeletionProgressUpdate {
+      task_id: task.id().to_string(),
+      progress: *progress,
+    };
+    if let Err(err) = app_handle.emit_all("deletionProgress", update) {
+      log::warn!("Unable to emit deletion progress: {}", err);
+    }
+  })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyProgressUpdate {
+  pub task_id: String,
+  pub progress: CopyProgress,
+}
+
+fn emit_copy_progress(app_handle: &tauri::AppHandle, task: &TaskHandle, progress: &CopyProgress) {
+  let update = CopyProgressUpdate {
+    task_id: task.id().to_string(),
+    progress: *progress,
+  };
+  if let Err(err) = app_handle.emit_all("copyProgress", update) {
+    log::warn!("Unable to emit copy progress: {}", err);
+  }
+}
+
+/// Copies `src` into `dst` as part of `task` so it can be cancelled, emitting `copyProgress`
+/// events along the way
+pub fn copy_dir_for_task(
+  app_handle: &tauri::AppHandle,
+  task: &TaskHandle,
+  src: &Path,
+  dst: &Path,
+  verification: CopyVerification,
+) -> Result<CopyProgress, std::io::Error> {
+  copy_dir_with_progress(
+    src,
+    dst,
+    verification,
+    &task.cancellation_token(),
+    |progress| emit_copy_progress(app_handle, task, progress),
+  )
+}
+
+/// Moves `src` to `dst` as part of `task`, see `copy_dir_for_task`
+pub fn move_dir_for_task(
+  app_handle: &tauri::AppHandle,
+  task: &TaskHandle,
+  src: &Path,
+  dst: &Path,
+  verification: CopyVerification,
+) -> Result<CopyProgress, std::io::Error> {
+  move_dir_with_progress(
+    src,
+    dst,
+    verification,
+    &task.cancellation_token(),
+    |progress| emit_copy_progress(app_handle, task, progress),
+  )
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_task_state(
+  task_manager: tauri::State<'_, TaskManager>,
+  id: String,
+) -> Result<Option<TaskState>, CommandError> {
+  Ok(task_manager.get(&id))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_running_tasks(
+  task_manager: tauri::State<'_, TaskManager>,
+) -> Result<Vec<TaskState>, CommandError> {
+  Ok(task_manager.running())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn cancel_task(
+  task_manager: tauri::State<'_, TaskManager>,
+  id: String,
+) -> Result<bool, CommandError> {
+  Ok(task_manager.cancel(&id))
+}
@@ -0,0 +1,421 @@
+// Texture packs are extracted once into a per-game store, and enabling them links their files
+// into the game's `texture_replacements` folder instead of copying them.  Toggling packs only
+// re-creates links, so it's quick regardless of the pack's size and no data is duplicated.
+//
+// The links the launcher made are recorded in a manifest inside `texture_replacements`, so they
+// can be removed again without touching anything the user put there themselves.
+
+use std::{
+  collections::BTreeMap,
+  path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::{
+  config::LauncherConfig,
+  paths,
+  tasks::{TaskHandle, TaskManager},
+  util::{
+    archive::extract_archive,
+    content_download::{check_content_url, download_content, ContentDownloadError},
+    file::{create_dir, delete_dir, overwrite_dir, read_json, write_json},
+  },
+};
+
+use super::{game::validate_game_name, journal::GameDataTransaction, CommandError};
+
+const MANIFEST_NAME: &str = ".launcher-texture-manifest.json";
+const PACK_METADATA_NAME: &str = "about.json";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TextureManifest {
+  packs: Vec<String>,
+  /// Paths relative to `texture_replacements`
+  files: Vec<PathBuf>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TexturePackInfo {
+  #[serde(default)]
+  pub name: String,
+  #[serde(default)]
+  pub author: String,
+  #[serde(default)]
+  pub description: String,
+  #[serde(default)]
+  pub version: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AppliedTexturePacks {
+  pub linked_files: u64,
+  /// Files that were left alone because the user already has their own version of them
+  pub conflicts: Vec<String>,
+}
+
+struct TexturePaths {
+  store: PathBuf,
+  replacements: PathBuf,
+}
+
+fn texture_paths(config: &LauncherConfig, game_name: &str) -> Result<TexturePaths, CommandError> {
+  validate_game_name(game_name)?;
+  let game_data_path = match config.game_data_path() {
+    None => {
+      return Err(CommandError::GameManagement(
+        "No game data directory set, can't manage texture packs".to_string(),
+      ))
+    }
+    Some(path) => path,
+  };
+  Ok(TexturePaths {
+    store: game_data_path.join("texture_packs").join(game_name),
+    replacements: paths::game_data_dir(&game_data_path, game_name).join("texture_replacements"),
+  })
+}
+
+fn pack_dir(store: &Path, pack_name: &str) -> Result<PathBuf, CommandError> {
+  let is_plain_name = !pack_name.is_empty()
+    && !pack_name.starts_with('.')
+    && Path::new(pack_name).components().count() == 1;
+  let dir = store.join(pack_name);
+  if !is_plain_name || !dir.is_dir() {
+    return Err(CommandError::GameManagement(format!(
+      "Texture pack '{}' is not installed",
+      pack_name
+    )));
+  }
+  Ok(dir)
+}
+
+fn read_manifest(replacements: &Path) -> TextureManifest {
+  read_json(&replacements.join(MANIFEST_NAME)).unwrap_or_default()
+}
+
+fn link_or_copy(src: &Path, dst: &Path) -> std::io::Result<()> {
+  // Hard links need no special privileges on any platform, the store lives in the game data
+  // directory so it's normally on the same volume, fall back to copying if it isn't
+  if let Err(err) = std::fs::hard_link(src, dst) {
+    log::warn!(
+      "unable to link {}, copying it instead: {}",
+      dst.display(),
+      err
+    );
+    std::fs::copy(src, dst)?;
+  }
+  Ok(())
+}
+
+fn sanitize_pack_name(name: &str) -> Result<String, CommandError> {
+  let pack_name: String = name
+    .chars()
+    .map(|c| {
+      if c.is_ascii_alphanumeric() || "-_ ".contains(c) {
+        c
+      } else {
+        '_'
+      }
+    })
+    .collect();
+  if pack_name.trim().is_empty() {
+    return Err(CommandError::GameManagement(
+      "Unable to determine texture pack name".to_string(),
+    ));
+  }
+  Ok(pack_name)
+}
+
+/// Copies a `texture_replacements` folder from elsewhere into the store as a pack, returning the
+/// name it was stored under
+pub fn import_texture_dir(
+  config: &LauncherConfig,
+  game_name: &str,
+  source_dir: &Path,
+  pack_name: &str,
+) -> Result<String, CommandError> {
+  let paths = texture_paths(config, game_name)?;
+  let pack_name = sanitize_pack_name(pack_name)?;
+  let dest_dir = paths.store.join(&pack_name);
+  if dest_dir.exists() {
+    return Err(CommandError::GameManagement(format!(
+      "A texture pack named '{}' is already installed",
+      pack_name
+    )));
+  }
+  create_dir(&dest_dir)?;
+  if let Err(err) = overwrite_dir(&source_dir.to_path_buf(), &dest_dir) {
+    log::error!("unable to import texture pack: {}", err);
+    delete_dir(&dest_dir)?;
+    return Err(CommandError::GameManagement(
+      "Unable to import texture pack".to_string(),
+    ));
+  }
+  Ok(pack_name)
+}
+
+fn install_pack_archive(
+  paths: &TexturePaths,
+  archive_path: &Path,
+  pack_name: &str,
+) -> Result<String, CommandError> {
+  let pack_name = sanitize_pack_name(pack_name)?;
+  let dest_dir = paths.store.join(&pack_name);
+  delete_dir(&dest_dir)?;
+  create_dir(&dest_dir)?;
+  if let Err(err) = extract_archive(archive_path, &dest_dir) {
+    log::error!("unable to extract texture pack: {}", err);
+    delete_dir(&dest_dir)?;
+    return Err(CommandError::GameManagement(
+      "Unable to extract texture pack".to_string(),
+    ));
+  }
+  // Packs are expected to contain a `texture_replacements` folder, keep only what's inside it
+  let nested_dir = dest_dir.join("texture_replacements");
+  if nested_dir.is_dir() {
+    let staging_dir = paths.store.join(format!(".{}.tmp", pack_name));
+    delete_dir(&staging_dir)?;
+    std::fs::rename(&nested_dir, &staging_dir)?;
+    delete_dir(&dest_dir)?;
+    std::fs::rename(&staging_dir, &dest_dir)?;
+  }
+  Ok(pack_name)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn install_texture_pack(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  game_name: String,
+  archive_path: String,
+) -> Result<String, CommandError> {
+  let config_lock = config.lock().await;
+  let paths = texture_paths(&config_lock, &game_name)?;
+
+  let archive_path = PathBuf::from(archive_path);
+  let pack_name = archive_path
+    .file_stem()
+    .map(|stem| stem.to_string_lossy().into_owned())
+    .unwrap_or_default();
+  install_pack_archive(&paths, &archive_path, &pack_name)
+}
+
+/// Downloads community content from `url` to `destination`, see `util::content_download` for
+/// what's allowed.  A host that isn't on the allow-list is refused with `UntrustedHost`, until
+/// the user confirms it and `allow_unknown_host` is passed
+pub async fn fetch_content(
+  config: &tokio::sync::Mutex<LauncherConfig>,
+  task: &TaskHandle,
+  url: &str,
+  allow_unknown_host: bool,
+  destination: &Path,
+) -> Result<(), CommandError> {
+  let allowed_hosts = config.lock().await.content_hosts();
+  let result = match check_content_url(url, &allowed_hosts, allow_unknown_host) {
+    Ok(url) => download_content(
+      &url,
+      &allowed_hosts,
+      destination,
+      &task.cancellation_token(),
+    )
+    .await
+    .map(|bytes| log::info!("downloaded {} bytes of content from {}", bytes, url)),
+    Err(err) => Err(err),
+  };
+  result.map_err(|err| match err {
+    ContentDownloadError::UnknownHost(host) => {
+      CommandError::UntrustedHost(format!("{} is not a known community host", host))
+    }
+    err => CommandError::GameManagement(format!("Unable to download content: {}", err)),
+  })
+}
+
+// Named after the file the URL points at, ie. `.../hd-textures.zip` is `hd-textures`
+pub fn content_name_from_url(url: &str) -> String {
+  let path = url.split(&['?', '#'][..]).next().unwrap_or_default();
+  let file_name = path
+    .trim_end_matches('/')
+    .rsplit('/')
+    .next()
+    .unwrap_or_default();
+  Path::new(file_name)
+    .file_stem()
+    .map(|stem| stem.to_string_lossy().into_owned())
+    .unwrap_or_default()
+}
+
+/// Downloads a texture pack archive and installs it like `install_texture_pack`
+#[tauri::command]
+#[specta::specta]
+pub async fn install_texture_pack_from_url(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  task_manager: tauri::State<'_, TaskManager>,
+  app_handle: tauri::AppHandle,
+  game_name: String,
+  url: String,
+  allow_unknown_host: Option<bool>,
+) -> Result<String, CommandError> {
+  let paths = texture_paths(&*config.lock().await, &game_name)?;
+  let pack_name = sanitize_pack_name(&content_name_from_url(&url))?;
+  let mut task = match task_manager.start_or_attach(
+    &app_handle,
+    format!("texture-pack-download-{}-{}", game_name, pack_name),
+    "Downloading texture pack",
+  ) {
+    Ok(task) => task,
+    Err(_) => {
+      return Err(CommandError::GameManagement(format!(
+        "'{}' is already being downloaded",
+        pack_name
+      )))
+    }
+  };
+  let archive_path = paths.store.join(format!(".{}.download", pack_name));
+  let fetched = match create_dir(&paths.store) {
+    Ok(()) => {
+      fetch_content(
+        &config,
+        &task,
+        &url,
+        allow_unknown_host.unwrap_or(false),
+        &archive_path,
+      )
+      .await
+    }
+    Err(err) => Err(err.into()),
+  };
+  let result = match fetched {
+    Ok(()) => {
+      let installed = install_pack_archive(&paths, &archive_path, &pack_name);
+      if let Err(err) = std::fs::remove_file(&archive_path) {
+        log::warn!("unable to remove downloaded texture pack: {}", err);
+      }
+      installed
+    }
+    Err(err) => Err(err),
+  };
+  task.finish(
+    result.is_ok(),
+    result.as_ref().err().map(|err| err.to_string()),
+  );
+  result
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn list_texture_packs(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  game_name: String,
+) -> Result<Vec<TexturePackInfo>, CommandError> {
+  let config_lock = config.lock().await;
+  let paths = texture_paths(&config_lock, &game_name)?;
+  if !paths.store.exists() {
+    return Ok(vec![]);
+  }
+  let mut packs = vec![];
+  for entry in std::fs::read_dir(&paths.store)? {
+    let entry = entry?;
+    let name = entry.file_name().to_string_lossy().into_owned();
+    if !entry.path().is_dir() || name.starts_with('.') {
+      continue;
+    }
+    let mut info: TexturePackInfo =
+      read_json(&entry.path().join(PACK_METADATA_NAME)).unwrap_or_default();
+    info.name = name;
+    packs.push(info);
+  }
+  packs.sort_by(|a, b| a.name.cmp(&b.name));
+  Ok(packs)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_applied_texture_packs(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  game_name: String,
+) -> Result<Vec<String>, CommandError> {
+  let config_lock = config.lock().await;
+  let paths = texture_paths(&config_lock, &game_name)?;
+  Ok(read_manifest(&paths.replacements).packs)
+}
+
+/// Makes `packs` the enabled texture packs, in order - files in later packs take priority.
+/// Passing no packs removes everything that was previously applied
+#[tauri::command]
+#[specta::specta]
+pub async fn apply_texture_packs(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  game_name: String,
+  packs: Vec<String>,
+) -> Result<AppliedTexturePacks, CommandError> {
+  let config_lock = config.lock().await;
+  let paths = texture_paths(&config_lock, &game_name)?;
+
+  // Work out the final set of files before touching anything
+  let mut files: BTreeMap<PathBuf, PathBuf> = BTreeMap::new();
+  for pack in &packs {
+    let dir = pack_dir(&paths.store, pack)?;
+    for entry in WalkDir::new(&dir).into_iter().filter_map(|e| e.ok()) {
+      if !entry.file_type().is_file() {
+        continue;
+      }
+      let relative_path = entry.path().strip_prefix(&dir).unwrap().to_path_buf();
+      if relative_path == Path::new(PACK_METADATA_NAME) {
+        continue;
+      }
+      files.insert(relative_path, entry.into_path());
+    }
+  }
+
+  let mut transaction =
+    GameDataTransaction::begin(&config_lock, &game_name, "Apply texture packs")?;
+
+  // Remove the previously applied links
+  let previous_manifest = read_manifest(&paths.replacements);
+  for relative_path in &previous_manifest.files {
+    let path = paths.replacements.join(relative_path);
+    if path.is_file() {
+      transaction.stage_replacement(&path)?;
+      std::fs::remove_file(&path)?;
+    }
+  }
+
+  create_dir(&paths.replacements)?;
+  let mut manifest = TextureManifest {
+    packs: packs.clone(),
+    files: vec![],
+  };
+  let mut conflicts = vec![];
+  for (relative_path, src) in files {
+    let dst = paths.replacements.join(&relative_path);
+    if dst.exists() {
+      conflicts.push(relative_path.to_string_lossy().into_owned());
+      continue;
+    }
+    if let Some(parent) = dst.parent() {
+      create_dir(&parent.to_path_buf())?;
+    }
+    transaction.stage_replacement(&dst)?;
+    link_or_copy(&src, &dst)?;
+    manifest.files.push(relative_path);
+  }
+
+  let manifest_path = paths.replacements.join(MANIFEST_NAME);
+  transaction.stage_file(&manifest_path)?;
+  write_json(&manifest_path, &manifest)?;
+  transaction.commit()?;
+  log::info!(
+    "applied texture packs {:?}, {} files linked, {} conflicts",
+    packs,
+    manifest.files.len(),
+    conflicts.len()
+  );
+  Ok(AppliedTexturePacks {
+    linked_files: manifest.files.len() as u64,
+    conflicts,
+  })
+}
@@ -0,0 +1,229 @@
+// Running the standalone utilities that ship with a version (the decompiler, formatter, ...)
+//
+// Only tools on the allow-list are run, and only from the active version's folder, so this can't
+// be used to start arbitrary programs.  The arguments are passed through as given, quoting and
+// all is up to the frontend.  Output is streamed back a line at a time as `toolOutput` events,
+// and the run is a task so it can be cancelled.
+use std::io::{BufRead, BufReader, Read};
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use log::info;
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use crate::{config::LauncherConfig, tasks::TaskManager, util::process::tool_command};
+
+use super::{binaries::bin_ext, CommandError};
+
+/// The tools that can be run, along with what they're for
+const ALLOWED_TOOLS: &[(&str, &str)] = &[
+  (
+    "decompiler",
+    "Decompiles the game's code and data from the extracted ISO",
+  ),
+  (
+    "extractor",
+    "Extracts, validates, decompiles and compiles the game",
+  ),
+  ("formatter", "Formats OpenGOAL source files"),
+  ("dgo_unpacker", "Unpacks DGO archives"),
+  (
+    "type_searcher",
+    "Searches the game's types by size and fields",
+  ),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AvailableTool {
+  pub name: String,
+  pub description: String,
+  pub path: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ToolOutputStream {
+  Stdout,
+  Stderr,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolOutputLine {
+  pub task_id: String,
+  pub stream: ToolOutputStream,
+  pub line: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolRunOutput {
+  /// `None` if the tool was cancelled, or killed by a signal
+  pub exit_code: Option<i32>,
+  pub success: bool,
+  pub cancelled: bool,
+}
+
+fn active_version_dir(config: &LauncherConfig) -> Result<PathBuf, CommandError> {
+  match (&config.active_version_folder, &config.active_version) {
+    (Some(folder), Some(version)) => config
+      .version_folder_path(folder)
+      .map(|path| path.join(version))
+      .ok_or_else(|| {
+        CommandError::BinaryExecution("No tooling directory set, can't run tools".to_string())
+      }),
+    _ => Err(CommandError::BinaryExecution(
+      "No active version set, can't run tools".to_string(),
+    )),
+  }
+}
+
+fn available_tools(version_dir: &Path) -> Vec<AvailableTool> {
+  ALLOWED_TOOLS
+    .iter()
+    .filter_map(|(name, description)| {
+      let path = version_dir.join(bin_ext(name));
+      path.is_file().then(|| AvailableTool {
+        name: name.to_string(),
+        description: description.to_string(),
+        path: path.to_string_lossy().into_owned(),
+      })
+    })
+    .collect()
+}
+
+/// The allow-listed tools the active version comes with
+#[tauri::command]
+#[specta::specta]
+pub async fn list_available_tools(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<Vec<AvailableTool>, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(available_tools(&active_version_dir(&config_lock)?))
+}
+
+fn stream_lines(
+  app_handle: tauri::AppHandle,
+  task_id: String,
+  stream: ToolOutputStream,
+  output: impl Read + Send + 'static,
+) -> std::thread::JoinHandle<()> {
+  std::thread::spawn(move || {
+    for line in BufReader::new(output).lines() {
+      let line = match line {
+        Ok(line) => line,
+        Err(_) => break,
+      };
+      let output_line = ToolOutputLine {
+        task_id: task_id.clone(),
+        stream,
+        line,
+      };
+      if let Err(err) = app_handle.emit_all("toolOutput", output_line) {
+        log::warn!("Unable to emit tool output: {}", err);
+      }
+    }
+  })
+}
+
+/// Runs one of the tools from `list_available_tools` with `args`, in the active version's folder
+#[tauri::command]
+#[specta::specta]
+pub async fn run_tool(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  task_manager: tauri::State<'_, TaskManager>,
+  app_handle: tauri::AppHandle,
+  name: String,
+  args: Vec<String>,
+) -> Result<ToolRunOutput, CommandError> {
+  let config_lock = config.lock().await;
+  let version_dir = active_version_dir(&config_lock)?;
+  let tool = available_tools(&version_dir)
+    .into_iter()
+    .find(|tool| tool.name == name)
+    .ok_or_else(|| {
+      CommandError::BinaryExecution(format!(
+        "'{}' isn't a tool the active version comes with",
+        name
+      ))
+    })?;
+  let mut command = tool_command(
+    &tool.path,
+    &version_dir,
+    config_lock.tool_environment(&tool.name),
+  );
+  drop(config_lock);
+
+  let mut task = match task_manager.start_or_attach(
+    &app_handle,
+    format!("tool-{}", tool.name),
+    "Running tool",
+  ) {
+    Ok(task) => task,
+    Err(_) => {
+      return Err(CommandError::BinaryExecution(format!(
+        "{} is already running",
+        tool.name
+      )))
+    }
+  };
+  let result: Result<_, CommandError> = async {
+    info!("Running tool {} with {:?}", tool.name, args);
+    command
+      .args(&args)
+      .stdin(Stdio::null())
+      .stdout(Stdio::piped())
+      .stderr(Stdio::piped());
+    #[cfg(windows)]
+    {
+      command.creation_flags(0x08000000);
+    }
+    let mut child = command.spawn()?;
+    let readers = [
+      child.stdout.take().map(|stdout| {
+        stream_lines(
+          app_handle.clone(),
+          task.id().to_string(),
+          ToolOutputStream::Stdout,
+          stdout,
+        )
+      }),
+      child.stderr.take().map(|stderr| {
+        stream_lines(
+          app_handle.clone(),
+          task.id().to_string(),
+          ToolOutputStream::Stderr,
+          stderr,
+        )
+      }),
+    ];
+    let status = task.cancellation_token().wait_for_child(&mut child)?;
+    // The pipes close with the process, so this only waits for the last lines to be sent
+    for reader in readers.into_iter().flatten() {
+      let _ = reader.join();
+    }
+    let output = ToolRunOutput {
+      exit_code: status.and_then(|status| status.code()),
+      success: status.map_or(false, |status| status.success()),
+      cancelled: status.is_none(),
+    };
+    info!("Tool {} finished: {:?}", tool.name, output);
+    task.finish(
+      output.success,
+      (!output.success).then(|| match output.exit_code {
+        Some(code) => format!("{} exited with {}", tool.name, code),
+        None => format!("{} was stopped", tool.name),
+      }),
+    );
+    Ok(output)
+  }
+  .await;
+  if let Err(err) = &result {
+    task.finish(false, Some(err.to_string()));
+  }
+  result
+}
@@ -0,0 +1,481 @@
+// Switching the active version in two steps, so a switch that needs the games recompiled doesn't
+// leave them unplayable until that's done
+//
+// `prepare_version_switch` checks the version's binaries and, for every installed game, compiles
+// the version's data into a staging folder next to the game's data (`data-next`), with the
+// extracted ISO data linked in rather than copied.  Nothing the current version uses is touched
+// while doing so.  Once prepared, `commit_version_switch` only has to swap the folders around and
+// change the config, which is instant.  Committing is all or nothing, if any game can't be swapped
+// (or the config can't be saved) the games already swapped are put back the way they were.
+//
+// Only one switch can be prepared at a time, preparing another discards the previous one.  The
+// automatic tooling update goes through the same two steps.
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use crate::{
+  config::{LauncherConfig, ToolEnvironment, VersionFolder, VersionName},
+  paths::game_install_dir,
+  tasks::TaskManager,
+  util::{
+    file::{delete_dir, overwrite_dir, remove_dir_link, replace_dir_link},
+    process::tool_command,
+  },
+};
+
+use super::{
+  binaries::{bin_ext, create_log_file, RunningGames},
+  game::missing_iso_data,
+  versions::update_active_version_link,
+  CommandError,
+};
+
+const STAGING_DIR_NAME: &str = "data-next";
+const PREVIOUS_DIR_NAME: &str = "data-previous";
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PreparedVersionSwitch {
+  pub version_folder: VersionFolder,
+  pub version: VersionName,
+  /// Games that were recompiled for the version, and are swapped over when committing
+  pub games: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct VersionSwitch {
+  prepared: std::sync::Mutex<Option<PreparedVersionSwitch>>,
+}
+
+impl VersionSwitch {
+  fn take(&self) -> Option<PreparedVersionSwitch> {
+    self
+      .prepared
+      .lock()
+      .ok()
+      .and_then(|mut prepared| prepared.take())
+  }
+
+  fn set(&self, switch: Option<PreparedVersionSwitch>) {
+    if let Ok(mut prepared) = self.prepared.lock() {
+      *prepared = switch;
+    }
+  }
+}
+
+fn game_dir(game_data_path: &Path, game_name: &str) -> PathBuf {
+  game_install_dir(game_data_path, game_name)
+}
+
+fn discard_staging(game_data_path: &Path, games: &[String]) {
+  for game_name in games {
+    let staging_dir = game_dir(game_data_path, game_name).join(STAGING_DIR_NAME);
+    // The ISO data is linked in, don't follow the link when removing the rest
+    let _ = remove_dir_link(&staging_dir.join("iso_data"));
+    if let Err(err) = delete_dir(&staging_dir) {
+      log::warn!("Unable to remove {}: {}", staging_dir.display(), err);
+    }
+  }
+}
+
+fn run_extractor(
+  app_handle: &tauri::AppHandle,
+  executable: &Path,
+  args: Vec<String>,
+  overrides: Option<&ToolEnvironment>,
+  cancellation_token: &crate::tasks::CancellationToken,
+) -> Result<bool, CommandError> {
+  let log_file = create_log_file(app_handle, "extractor.log", true)?;
+  let mut command = tool_command(
+    executable,
+    executable.parent().unwrap_or_else(|| Path::new(".")),
+    overrides,
+  );
+  command
+    .args(args)
+    .stdout(log_file.try_clone()?)
+    .stderr(log_file);
+  #[cfg(windows)]
+  {
+    command.creation_flags(0x08000000);
+  }
+  let mut child = command.spawn()?;
+  match cancellation_token.wait_for_child(&mut child)? {
+    Some(status) => Ok(status.success()),
+    None => Err(CommandError::VersionManagement(
+      "Preparing the version switch was cancelled".to_string(),
+    )),
+  }
+}
+
+// Builds the version's data for a game in its staging folder
+fn stage_game(
+  app_handle: &tauri::AppHandle,
+  version_dir: &Path,
+  game_data_path: &Path,
+  game_name: &String,
+  extractor_env: Option<&ToolEnvironment>,
+  cancellation_token: &crate::tasks::CancellationToken,
+) -> Result<(), CommandError> {
+  let game_dir = game_dir(game_data_path, game_name);
+  let iso_data = game_dir.join("data").join("iso_data");
+  let staging_dir = game_dir.join(STAGING_DIR_NAME);
+  discard_staging(game_data_path, std::slice::from_ref(game_name));
+  overwrite_dir(&version_dir.join("data"), &staging_dir).map_err(|err| {
+    CommandError::VersionManagement(format!("Unable to stage version data: {}", err))
+  })?;
+  replace_dir_link(&staging_dir.join("iso_data"), &iso_data)?;
+
+  let extractor = version_dir.join(bin_ext("extractor"));
+  let source = staging_dir
+    .join("iso_data")
+    .join(game_name)
+    .to_string_lossy()
+    .into_owned();
+  let proj_path = staging_dir.to_string_lossy().into_owned();
+  for step in ["--decompile", "--compile"] {
+    let args = vec![
+      source.clone(),
+      step.to_string(),
+      "--proj-path".to_string(),
+      proj_path.clone(),
+    ];
+    if !run_extractor(
+      app_handle,
+      &extractor,
+      args,
+      extractor_env,
+      cancellation_token,
+    )? {
+      return Err(CommandError::VersionManagement(format!(
+        "Unable to recompile {} with the new version, check extractor.log for details",
+        game_name
+      )));
+    }
+  }
+  Ok(())
+}
+
+// Swaps a game's staged data in, returning what was carried over from the old data so it can be
+// put back by `swap_out`
+fn swap_in(game_dir: &Path) -> Result<Vec<OsString>, CommandError> {
+  let (data_dir, staging_dir, previous_dir) = (
+    game_dir.join("data"),
+    game_dir.join(STAGING_DIR_NAME),
+    game_dir.join(PREVIOUS_DIR_NAME),
+  );
+  // Everything the version doesn't provide (the ISO data, applied texture packs, logs) moves
+  // over to the new data
+  remove_dir_link(&staging_dir.join("iso_data"))?;
+  let mut carried_over = vec![];
+  let result = (|| -> Result<(), std::io::Error> {
+    for entry in std::fs::read_dir(&data_dir)?.filter_map(|entry| entry.ok()) {
+      let target = staging_dir.join(entry.file_name());
+      if !target.exists() {
+        std::fs::rename(entry.path(), target)?;
+        carried_over.push(entry.file_name());
+      }
+    }
+    delete_dir(&previous_dir)?;
+    std::fs::rename(&data_dir, &previous_dir)?;
+    if let Err(err) = std::fs::rename(&staging_dir, &data_dir) {
+      std::fs::rename(&previous_dir, &data_dir)?;
+      return Err(err);
+    }
+    Ok(())
+  })();
+  if let Err(err) = result {
+    restore_carried_over(&data_dir, &staging_dir, &carried_over);
+    return Err(err.into());
+  }
+  Ok(carried_over)
+}
+
+fn restore_carried_over(data_dir: &Path, staging_dir: &Path, carried_over: &[OsString]) {
+  for name in carried_over {
+    if let Err(err) = std::fs::rename(staging_dir.join(name), data_dir.join(name)) {
+      log::error!(
+        "Unable to move {} back into {}: {}",
+        name.to_string_lossy(),
+        data_dir.display(),
+        err
+      );
+    }
+  }
+  let iso_data = data_dir.join("iso_data");
+  if let Err(err) = replace_dir_link(&staging_dir.join("iso_data"), &iso_data) {
+    log::warn!("Unable to relink {}: {}", iso_data.display(), err);
+  }
+}
+
+// Undoes `swap_in`, leaving the staged data where it was prepared
+fn swap_out(game_dir: &Path, carried_over: &[OsString]) {
+  let (data_dir, staging_dir, previous_dir) = (
+    game_dir.join("data"),
+    game_dir.join(STAGING_DIR_NAME),
+    game_dir.join(PREVIOUS_DIR_NAME),
+  );
+  let result = std::fs::rename(&data_dir, &staging_dir)
+    .and_then(|_| std::fs::rename(&previous_dir, &data_dir));
+  match result {
+    Ok(()) => restore_carried_over(&data_dir, &staging_dir, carried_over),
+    Err(err) => log::error!(
+      "Unable to restore the previous data of {}: {}",
+      game_dir.display(),
+      err
+    ),
+  }
+}
+
+/// Validates `version` and recompiles the installed games with it, without changing anything
+/// the active version uses
+#[tauri::command]
+#[specta::specta]
+pub async fn prepare_version_switch(
+  app_handle: tauri::AppHandle,
+  version_folder: VersionFolder,
+  version: VersionName,
+) -> Result<PreparedVersionSwitch, CommandError> {
+  prepare(&app_handle, version_folder, version).await
+}
+
+pub async fn prepare(
+  app_handle: &tauri::AppHandle,
+  version_folder: VersionFolder,
+  version: VersionName,
+) -> Result<PreparedVersionSwitch, CommandError> {
+  let config = app_handle.state::<tokio::sync::Mutex<LauncherConfig>>();
+  let version_switch = app_handle.state::<VersionSwitch>();
+  let task_manager = app_handle.state::<TaskManager>();
+  let mut task = match task_manager.start_or_attach(
+    app_handle,
+    format!("prepare-switch-{}-{}", version_folder, version),
+    "Preparing version switch",
+  ) {
+    Ok(task) => task,
+    Err(_) => {
+      return Err(CommandError::VersionManagement(
+        "This version switch is already being prepared".to_string(),
+      ))
+    }
+  };
+  let result = async {
+    let config_lock = config.lock().await;
+    let (version_folder_path, game_data_path) = match (
+      config_lock.version_folder_path(version_folder.as_str()),
+      config_lock.game_data_path(),
+    ) {
+      (Some(version_folder_path), Some(game_data_path)) => (version_folder_path, game_data_path),
+      _ => {
+        return Err(CommandError::VersionManagement(
+          "No installation directory set, can't switch versions".to_string(),
+        ))
+      }
+    };
+    let version_dir = version_folder_path.join(&version);
+    for binary in ["extractor", "gk", "goalc"] {
+      if !version_dir.join(bin_ext(binary)).exists() {
+        return Err(CommandError::VersionManagement(format!(
+          "Version {} is missing '{}', it has to be redownloaded",
+          version, binary
+        )));
+      }
+    }
+    // Games already on this version are fine as they are
+    let games: Vec<String> = config_lock
+      .games
+      .keys()
+      .map(|game| game.internal_str().to_string())
+      .filter(|game_name| config_lock.is_game_installed(game_name))
+      .filter(|game_name| {
+        config_lock.game_install_version(game_name) != version.to_string()
+          || config_lock.game_install_version_folder(game_name) != version_folder.to_string()
+      })
+      .collect();
+    // Checked for every game up front, rather than after recompiling the ones before it
+    if let Some(missing) = games
+      .iter()
+      .find_map(|game_name| missing_iso_data(&config_lock, game_name, "update it"))
+    {
+      task.finish(false, Some(missing.clone()));
+      return Err(CommandError::VersionManagement(missing));
+    }
+    let extractor_env = config_lock.tool_environment("extractor").cloned();
+    drop(config_lock);
+
+    if let Some(previous) = version_switch.take() {
+      discard_staging(&game_data_path, &previous.games);
+    }
+    let cancellation_token = task.cancellation_token();
+    for (index, game_name) in games.iter().enumerate() {
+      if let Err(err) = stage_game(
+        app_handle,
+        &version_dir,
+        &game_data_path,
+        game_name,
+        extractor_env.as_ref(),
+        &cancellation_token,
+      ) {
+        discard_staging(&game_data_path, &games[..=index]);
+        task.finish(false, Some(err.to_string()));
+        return Err(err);
+      }
+    }
+
+    let prepared = PreparedVersionSwitch {
+      version_folder,
+      version,
+      games,
+    };
+    version_switch.set(Some(prepared.clone()));
+    task.finish(true, None);
+    app_handle.emit_all("versionSwitchPrepared", &prepared)?;
+    Ok(prepared)
+  }
+  .await;
+  if let Err(err) = &result {
+    task.finish(false, Some(err.to_string()));
+  }
+  result
+}
+
+/// Makes the prepared version the active one, swapping in the games' recompiled data
+#[tauri::command]
+#[specta::specta]
+pub async fn commit_version_switch(app_handle: tauri::AppHandle) -> Result<(), CommandError> {
+  commit(&app_handle).await
+}
+
+pub async fn commit(app_handle: &tauri::AppHandle) -> Result<(), CommandError> {
+  let config = app_handle.state::<tokio::sync::Mutex<LauncherConfig>>();
+  let version_switch = app_handle.state::<VersionSwitch>();
+  // The game's data is swapped underneath it otherwise, the switch stays prepared for later
+  app_handle
+    .state::<RunningGames>()
+    .ensure_none("switch versions")?;
+  let prepared = version_switch.take().ok_or_else(|| {
+    CommandError::VersionManagement("No version switch has been prepared".to_string())
+  })?;
+  let mut config_lock = config.lock().await;
+  let game_data_path = config_lock.game_data_path().ok_or_else(|| {
+    CommandError::VersionManagement(
+      "No installation directory set, can't switch versions".to_string(),
+    )
+  })?;
+
+  if let Some(game_name) = prepared.games.iter().find(|game_name| {
+    !game_dir(&game_data_path, game_name)
+      .join(STAGING_DIR_NAME)
+      .exists()
+  }) {
+    return Err(CommandError::VersionManagement(format!(
+      "The prepared data for {} is gone, prepare the switch again",
+      game_name
+    )));
+  }
+
+  let before = serde_json::to_value(&*config_lock)
+    .map_err(|err| CommandError::Configuration(err.to_string()))?;
+  let mut swapped: Vec<(PathBuf, Vec<OsString>)> = vec![];
+  let mut result = Ok(());
+  for game_name in &prepared.games {
+    let game_dir = game_dir(&game_data_path, game_name);
+    match swap_in(&game_dir) {
+      Ok(carried_over) => swapped.push((game_dir, carried_over)),
+      Err(err) => {
+        result = Err(err);
+        break;
+      }
+    }
+  }
+  if result.is_ok() {
+    result = persist_switch(&mut config_lock, &prepared);
+    if result.is_err() {
+      // Put back whatever was already changed, the games' data is restored below
+      if config_lock.update_from_json(before).is_ok() {
+        let _ = config_lock.save_config();
+      }
+    }
+  }
+  if let Err(err) = result {
+    for (game_dir, carried_over) in swapped.iter().rev() {
+      swap_out(game_dir, carried_over);
+    }
+    // Still staged, the switch can be committed again once whatever failed is sorted out
+    version_switch.set(Some(prepared));
+    return Err(err);
+  }
+  for (game_dir, _) in &swapped {
+    let previous_dir = game_dir.join(PREVIOUS_DIR_NAME);
+    if let Err(err) = delete_dir(&previous_dir) {
+      log::warn!("Unable to remove {}: {}", previous_dir.display(), err);
+    }
+  }
+  update_active_version_link(&config_lock);
+  log::info!(
+    "Switched to {}:{}, recompiled {:?}",
+    prepared.version_folder,
+    prepared.version,
+    prepared.games
+  );
+  Ok(())
+}
+
+fn persist_switch(
+  config: &mut LauncherConfig,
+  prepared: &PreparedVersionSwitch,
+) -> Result<(), CommandError> {
+  config
+    .switch_active_version(
+      prepared.version_folder.to_string(),
+      prepared.version.to_string(),
+    )
+    .map_err(|_| {
+      CommandError::Configuration("Unable to persist active version change".to_string())
+    })?;
+  for game_name in &prepared.games {
+    config
+      .update_installed_game_version(game_name, true)
+      .map_err(|_| {
+        CommandError::Configuration(format!(
+          "Unable to persist installed version of {}",
+          game_name
+        ))
+      })?;
+  }
+  Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn cancel_version_switch(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  version_switch: tauri::State<'_, VersionSwitch>,
+) -> Result<(), CommandError> {
+  if let Some(prepared) = version_switch.take() {
+    if let Some(game_data_path) = config.lock().await.game_data_path() {
+      discard_staging(&game_data_path, &prepared.games);
+    }
+  }
+  Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_prepared_version_switch(
+  version_switch: tauri::State<'_, VersionSwitch>,
+) -> Result<Option<PreparedVersionSwitch>, CommandError> {
+  let prepared = version_switch
+    .prepared
+    .lock()
+    .ok()
+    .and_then(|prepared| prepared.clone());
+  Ok(prepared)
+}
@@ -1,34 +1,272 @@
-use std::path::Path;
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+use std::{
+  collections::HashMap,
+  io::Write,
+  path::{Path, PathBuf},
+  str::FromStr,
+  time::{Duration, Instant},
+};
 
 use log::info;
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
 
 use crate::{
-  config::LauncherConfig,
+  config::{
+    check_shared_version, release_platform, shared_versions_path, InstallRetryPolicy,
+    LauncherConfig, MeteredBehavior, ReleaseChannel, TrustLevel, VersionFolder, VersionName,
+    VersionSource, SHAREABLE_VERSION_FOLDERS,
+  },
+  downloads::{DownloadJob, DownloadQueue},
+  paths,
+  remote_config::RemoteConfig,
+  schedule,
+  setup_funnel::{SetupAttempt, SetupStep},
+  tasks::{TaskHandle, TaskManager, TaskStatus},
+  throughput,
   util::{
-    file::{create_dir, delete_dir},
-    network::download_file,
-    os::open_dir_in_os,
-    tar::extract_and_delete_tar_ball,
-    zip::extract_and_delete_zip_file,
+    archive::{extract_and_delete_archive, ArchiveError, ArchiveStream, ExtractionProgress},
+    dedup::{deduplicate_version, measure_savings, DedupSavings},
+    file::{
+      create_dir, delete_dir, delete_file, is_dir_writable, paths_overlap, remove_dir_link,
+      replace_dir_link, sha256_file, CopyVerification, DeletionProgress,
+    },
+    github::{GithubClient, GithubError},
+    network::{
+      content_length, download_file_chunked, download_to_channel, remove_partial_download,
+      NetworkError, PauseFlag,
+    },
+    os::{is_connection_metered, open_dir_in_os},
+    power::SleepInhibitor,
+    process::tool_command,
+    signing::verify_binary,
+  },
+  warnings::WarningCode,
+};
+
+use super::{
+  binaries::{bin_ext, create_log_file, RunningGames},
+  compatibility::{
+    github_asset_sha256, github_client, github_release_asset_url, github_repo, parse_version,
   },
+  releases::{read_release_cache, write_release_cache, CachedRelease, ReleaseAsset},
+  tasks::{delete_dirs_for_task, move_dir_for_task},
+  version_switch, CommandError,
 };
 
-use super::CommandError;
+#[derive(Debug, Default, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct BinaryBuildInfo {
+  pub raw_output: String,
+  pub reported_version: Option<String>,
+  pub build_hash: Option<String>,
+  pub build_date: Option<String>,
+}
+
+impl BinaryBuildInfo {
+  // The binaries print something along the lines of `extractor v0.1.35 (abc1234, 2023-05-12)`
+  // but the exact format is not guaranteed, so pick out anything that looks relevant
+  fn parse(output: &str) -> Self {
+    let mut info = Self {
+      raw_output: output.trim().to_string(),
+      ..Self::default()
+    };
+    let tokens = output
+      .split(|c: char| c.is_whitespace() || c == ',' || c == '(' || c == ')' || c == ':')
+      .filter(|token| !token.is_empty());
+    for token in tokens {
+      let is_version = token.starts_with('v')
+        && token[1..].starts_with(|c: char| c.is_ascii_digit())
+        && token.contains('.');
+      let is_date = token.len() == 10
+        && token.chars().enumerate().all(|(i, c)| match i {
+          4 | 7 => c == '-',
+          _ => c.is_ascii_digit(),
+        });
+      let is_hash = (7..=40).contains(&token.len())
+        && token.chars().all(|c| c.is_ascii_hexdigit())
+        && token.chars().any(|c| c.is_ascii_alphabetic());
+      if is_version && info.reported_version.is_none() {
+        info.reported_version = Some(token.to_string());
+      } else if is_date && info.build_date.is_none() {
+        info.build_date = Some(token.to_string());
+      } else if is_hash && info.build_hash.is_none() {
+        info.build_hash = Some(token.to_string());
+      }
+    }
+    info
+  }
+}
+
+/// Information about a downloaded version, stored alongside the binaries
+#[derive(Debug, Default, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionMetadata {
+  pub extractor: Option<BinaryBuildInfo>,
+  pub gk: Option<BinaryBuildInfo>,
+  /// The jak-project checkout a local build was registered from
+  pub local_build: Option<String>,
+  /// The archive a downloaded version was extracted from
+  pub archive: Option<ArchiveVerification>,
+  /// The channel an official release was listed on when it was downloaded
+  pub channel: Option<ReleaseChannel>,
+  /// Every file the version came with, to tell when some have gone missing since
+  pub files: Option<Vec<VersionFile>>,
+  /// Whether the binaries' signatures checked out when it was downloaded, `false` if its release
+  /// has none or the user chose to keep it regardless
+  pub signed: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionFile {
+  /// Relative to the version's folder, with `/` separators
+  pub path: String,
+  pub bytes: u64,
+  pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveVerification {
+  pub sha256: String,
+  /// Whether it matched a known digest, see `expected_sha256`.  Releases hosted elsewhere, and
+  /// older ones, may have none to check against
+  pub verified: bool,
+}
+
+const VERSION_METADATA_FILE: &str = ".launcher-metadata.json";
+
+impl VersionMetadata {
+  fn path(version_dir: &Path) -> PathBuf {
+    version_dir.join(VERSION_METADATA_FILE)
+  }
+
+  pub fn exists(version_dir: &Path) -> bool {
+    Self::path(version_dir).exists()
+  }
+
+  pub fn load(version_dir: &Path) -> Self {
+    let metadata_path = Self::path(version_dir);
+    let content = match std::fs::read_to_string(&metadata_path) {
+      Ok(content) => content,
+      Err(_) => return Self::default(),
+    };
+    match serde_json::from_str(&content) {
+      Ok(metadata) => metadata,
+      Err(err) => {
+        log::warn!(
+          "Could not parse version metadata at {}: {}",
+          metadata_path.display(),
+          err
+        );
+        Self::default()
+      }
+    }
+  }
+
+  pub fn save(&self, version_dir: &Path) -> Result<(), CommandError> {
+    let file = std::fs::File::create(Self::path(version_dir))?;
+    serde_json::to_writer_pretty(file, &self).map_err(|_| {
+      CommandError::VersionManagement("Unable to write version metadata".to_string())
+    })?;
+    Ok(())
+  }
+}
+
+fn read_binary_build_info(version_dir: &Path, executable_name: &str) -> Option<BinaryBuildInfo> {
+  let exec_path = version_dir.join(bin_ext(executable_name));
+  if !exec_path.exists() {
+    return None;
+  }
+  // Asking for the version doesn't depend on anything the user could override
+  let mut command = tool_command(&exec_path, version_dir, None);
+  command.arg("--version");
+  #[cfg(windows)]
+  {
+    command.creation_flags(0x08000000);
+  }
+  match command.output() {
+    Ok(output) => {
+      let stdout = String::from_utf8_lossy(&output.stdout);
+      let stderr = String::from_utf8_lossy(&output.stderr);
+      let text = if stdout.trim().is_empty() {
+        stderr
+      } else {
+        stdout
+      };
+      Some(BinaryBuildInfo::parse(&text))
+    }
+    Err(err) => {
+      log::warn!(
+        "Unable to determine build info of {}: {}",
+        exec_path.display(),
+        err
+      );
+      None
+    }
+  }
+}
+
+/// Ask the binaries what they actually are, so that a version folder containing binaries
+/// from a different version can be detected
+pub fn stamp_version_metadata(version_dir: &Path) -> VersionMetadata {
+  let mut metadata = VersionMetadata::load(version_dir);
+  metadata.extractor = read_binary_build_info(version_dir, "extractor");
+  metadata.gk = read_binary_build_info(version_dir, "gk");
+  if let Err(err) = metadata.save(version_dir) {
+    log::warn!(
+      "Unable to save version metadata for {}: {}",
+      version_dir.display(),
+      err
+    );
+  }
+  metadata
+}
+
+/// A version's number split up, so versions can be ordered by it rather than by name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionOrderingKey {
+  pub major: u64,
+  pub minor: u64,
+  pub patch: u64,
+  /// ie. `rc.1`, which comes before the release it's for
+  pub pre_release: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadedVersion {
+  pub version: String,
+  /// `None` if the folder isn't named after a version number
+  pub ordering_key: Option<VersionOrderingKey>,
+  /// When the version's folder was last written to, which is when it was downloaded
+  pub downloaded_at: Option<String>,
+  pub bytes: u64,
+  pub is_active: bool,
+  pub channel: Option<ReleaseChannel>,
+}
 
+/// The versions in `version_folder`, newest first
 #[tauri::command]
+#[specta::specta]
 pub async fn list_downloaded_versions(
   config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
-  version_folder: String,
-) -> Result<Vec<String>, CommandError> {
+  disk_usage: tauri::State<'_, VersionDiskUsageCache>,
+  version_folder: VersionFolder,
+) -> Result<Vec<DownloadedVersion>, CommandError> {
   let config_lock = config.lock().await;
-  let install_path = match &config_lock.installation_dir {
+  let expected_path = match config_lock.version_folder_path(version_folder.as_str()) {
     None => return Ok(Vec::new()),
-    Some(path) => Path::new(path),
+    Some(path) => path,
   };
-
-  let expected_path = Path::new(install_path)
-    .join("versions")
-    .join(version_folder);
+  let active_version = match config_lock.active_version_folder.as_deref() {
+    Some(folder) if folder == version_folder.as_str() => config_lock.active_version.clone(),
+    _ => None,
+  };
+  drop(config_lock);
   if !expected_path.exists() || !expected_path.is_dir() {
     log::info!(
       "No {} folder found, returning no releases",
@@ -43,250 +281,3358 @@ pub async fn list_downloaded_versions(
       expected_path.display()
     ))
   })?;
-  Ok(
-    entries
-      .filter_map(|e| {
-        e.ok().and_then(|d| {
-          let p = d.path();
-          if p.is_dir() {
-            Some(
-              p.file_name()
-                .map(|name| name.to_string_lossy().into_owned())
-                .unwrap_or("".into()),
-            )
-          } else {
-            None
-          }
-        })
-      })
-      .collect(),
-  )
+  let mut versions: Vec<(DownloadedVersion, std::time::SystemTime)> = entries
+    .filter_map(|entry| entry.ok())
+    .filter(is_version_dir)
+    .map(|entry| {
+      let dir = entry.path();
+      let version = entry.file_name().to_string_lossy().into_owned();
+      let modified = entry
+        .metadata()
+        .and_then(|metadata| metadata.modified())
+        .ok();
+      let usage = disk_usage.get_or_measure(&version_folder, version.clone(), &dir, false);
+      let downloaded = DownloadedVersion {
+        ordering_key: parse_version(&version).map(|parsed| VersionOrderingKey {
+          major: parsed.major,
+          minor: parsed.minor,
+          patch: parsed.patch,
+          pre_release: Some(parsed.pre.to_string()).filter(|pre| !pre.is_empty()),
+        }),
+        downloaded_at: modified
+          .map(|modified| chrono::DateTime::<chrono::Local>::from(modified).to_rfc3339()),
+        bytes: usage.bytes,
+        is_active: active_version.as_deref() == Some(version.as_str()),
+        channel: VersionMetadata::load(&dir).channel,
+        version,
+      };
+      (downloaded, modified.unwrap_or(std::time::UNIX_EPOCH))
+    })
+    .collect();
+  // Numbered versions by number, the rest (ie. local builds, mods) after them by date
+  versions.sort_by(|(a, a_modified), (b, b_modified)| {
+    match (parse_version(&a.version), parse_version(&b.version)) {
+      (Some(a), Some(b)) => b.cmp(&a),
+      (Some(_), None) => std::cmp::Ordering::Less,
+      (None, Some(_)) => std::cmp::Ordering::Greater,
+      (None, None) => b_modified.cmp(a_modified),
+    }
+  });
+  Ok(versions.into_iter().map(|(version, _)| version).collect())
 }
 
-#[tauri::command]
-pub async fn download_version(
-  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
-  version: String,
-  version_folder: String,
-  url: String,
-) -> Result<(), CommandError> {
-  let config_lock = config.lock().await;
-  let install_path = match &config_lock.installation_dir {
-    None => {
-      return Err(CommandError::VersionManagement(format!(
-        "Cannot install version, no installation directory set"
-      )))
+/// Tracks everything that a download / extraction creates on disk so that no failure path
+/// leaves half-written folders or stray archives behind in the versions folder.
+///
+/// Tracked files are always removed when this goes out of scope, tracked directories are
+/// removed unless the operation was marked as successful via `succeeded`.  Tracked downloads are
+/// the other way around, they're kept for the next attempt to resume unless it succeeded
+struct DownloadArtifacts {
+  files: Vec<PathBuf>,
+  downloads: Vec<PathBuf>,
+  dirs: Vec<PathBuf>,
+  success: bool,
+}
+
+impl DownloadArtifacts {
+  fn new() -> Self {
+    Self {
+      files: Vec::new(),
+      downloads: Vec::new(),
+      dirs: Vec::new(),
+      success: false,
     }
-    Some(path) => Path::new(path),
-  };
+  }
 
-  let dest_dir = install_path
-    .join("versions")
-    .join(&version_folder)
-    .join(&version);
+  fn track_file(&mut self, path: &Path) {
+    self.files.push(path.to_path_buf());
+  }
 
-  // Delete the directory if it exists, and create it from scratch
-  delete_dir(&dest_dir).map_err(|_| {
-    CommandError::VersionManagement(format!(
-      "Unable to prepare destination folder '{}' for download",
-      dest_dir.display()
-    ))
-  })?;
-  create_dir(&dest_dir).map_err(|_| {
-    CommandError::VersionManagement(format!(
-      "Unable to prepare destination folder '{}' for download",
-      dest_dir.display()
-    ))
-  })?;
+  fn track_download(&mut self, path: &Path) {
+    self.downloads.push(path.to_path_buf());
+  }
 
-  if cfg!(windows) {
-    let download_path = install_path
-      .join("versions")
-      .join(&version_folder)
-      .join(format!("{}.zip", version));
+  fn track_dir(&mut self, path: &Path) {
+    self.dirs.push(path.to_path_buf());
+  }
 
-    // Download the file
-    download_file(&url, &download_path).await.map_err(|_| {
-      CommandError::VersionManagement(format!("Unable to successfully download version"))
-    })?;
+  fn succeeded(&mut self) {
+    self.success = true;
+  }
+}
 
-    // Extract the zip file
-    extract_and_delete_zip_file(&download_path, &dest_dir).map_err(|_| {
-      CommandError::VersionManagement(format!("Unable to successfully extract downloaded version"))
-    })?;
+impl Drop for DownloadArtifacts {
+  fn drop(&mut self) {
+    for file in &self.files {
+      if let Err(err) = delete_file(file) {
+        log::error!("Unable to cleanup '{}': {}", file.display(), err);
+      }
+    }
+    if self.success {
+      for download in &self.downloads {
+        if let Err(err) = remove_partial_download(download) {
+          log::error!("Unable to cleanup '{}': {}", download.display(), err);
+        }
+      }
+      return;
+    }
+    for dir in &self.dirs {
+      log::info!("Cleaning up after failed download - {}", dir.display());
+      if let Err(err) = delete_dir(dir) {
+        log::error!("Unable to cleanup '{}': {}", dir.display(), err);
+      }
+    }
+  }
+}
 
-    // Verify that the extracted files seem correct (look for extractor.exe)
-    let expected_extractor_path = dest_dir.join("extractor.exe");
-    if !expected_extractor_path.exists() {
-      log::info!(
-        "Version did not extract properly, {} is missing!",
-        expected_extractor_path.display()
-      );
-      delete_dir(&dest_dir).map_err(|_| {
-        CommandError::VersionManagement(format!(
-          "Unable to prepare destination folder '{}' for download",
-          dest_dir.display()
-        ))
-      })?;
-      return Err(CommandError::VersionManagement(format!(
-        "Version did not extract properly, critical files are missing. An antivirus may have deleted the files!"
-      )));
+pub fn active_version_link_path(config: &LauncherConfig) -> Option<PathBuf> {
+  config
+    .tooling_path()
+    .map(|path| paths::active_version_link(&path))
+}
+
+/// Updates the active version link after the tooling directory changed, the link in the old
+/// one would be left pointing at a version that isn't used anymore
+pub fn move_active_version_link(config: &LauncherConfig, old_link_path: Option<PathBuf>) {
+  if let Some(old_link_path) = old_link_path {
+    if Some(&old_link_path) != active_version_link_path(config).as_ref() {
+      if let Err(err) = remove_dir_link(&old_link_path) {
+        log::warn!(
+          "Unable to remove old active version link {}: {}",
+          old_link_path.display(),
+          err
+        );
+      }
     }
-    return Ok(());
-  } else if cfg!(unix) {
-    let download_path = install_path
-      .join("versions")
-      .join(&version_folder)
-      .join(format!("{}.tar.gz", version));
-
-    // Download the file
-    download_file(&url, &download_path).await.map_err(|_| {
-      CommandError::VersionManagement(format!("Unable to successfully download version"))
-    })?;
+  }
+  update_active_version_link(config);
+}
+
+/// Keeps `versions/active` pointing at the active tooling version, so external tools have a
+/// stable path to it.  The link is removed if the option is off or there is no active version
+pub fn update_active_version_link(config: &LauncherConfig) {
+  let link_path = match active_version_link_path(config) {
+    Some(path) => path,
+    None => return,
+  };
+  let target = match (
+    config.maintain_active_version_link,
+    &config.active_version_folder,
+    &config.active_version,
+  ) {
+    (Some(true), Some(version_folder), Some(version)) => config
+      .version_folder_path(version_folder)
+      .map(|path| path.join(version)),
+    (_, _, _) => None,
+  };
+  let result = match target {
+    Some(target) => replace_dir_link(&link_path, &target),
+    None => remove_dir_link(&link_path),
+  };
+  if let Err(err) = result {
+    log::warn!(
+      "Unable to update active version link {}: {}",
+      link_path.display(),
+      err
+    );
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionRollback {
+  pub version_folder: String,
+  pub version: String,
+  /// The version that was active until the rollback
+  pub rolled_back_from: Option<String>,
+  /// Why the launcher rolled back by itself, `None` if it was asked to
+  pub reason: Option<String>,
+}
 
-    // Extract the zip file
-    extract_and_delete_tar_ball(&download_path, &dest_dir).map_err(|err| {
-      log::error!("unable to extract and delete version tar.gz file {}", err);
-      CommandError::VersionManagement(format!("Unable to successfully extract downloaded version"))
+/// Switches back to the version that was active before the current one, if it's still installed.
+/// Only the version changes, so games whose data was compiled with the current one have to be
+/// switched back through the version switch instead
+fn rollback_to_previous_version(
+  app_handle: &tauri::AppHandle,
+  config: &mut LauncherConfig,
+  reason: Option<String>,
+) -> Result<VersionRollback, CommandError> {
+  let (version_folder, version) = match config.previous_active_version() {
+    Some((folder, version)) => (folder.to_string(), version.to_string()),
+    None => {
+      return Err(CommandError::VersionManagement(
+        "There is no previous version to go back to".to_string(),
+      ))
+    }
+  };
+  app_handle
+    .state::<RunningGames>()
+    .ensure_none("roll back the active version")?;
+  let compiled_with_active: Vec<String> = config
+    .games
+    .keys()
+    .map(|game| game.internal_str().to_string())
+    .filter(|game_name| config.is_game_installed(game_name))
+    .filter(|game_name| {
+      config.active_version_folder.as_deref()
+        == Some(config.game_install_version_folder(game_name).as_str())
+        && config.active_version.as_deref() == Some(config.game_install_version(game_name).as_str())
+    })
+    .collect();
+  if !compiled_with_active.is_empty() {
+    return Err(CommandError::VersionManagement(format!(
+      "{} was compiled with {}, switch to {} from the version manager to recompile it",
+      compiled_with_active.join(", "),
+      config.active_version.clone().unwrap_or_default(),
+      version
+    )));
+  }
+  let still_installed = config
+    .version_folder_path(&version_folder)
+    .map_or(false, |path| {
+      CRITICAL_BINARIES
+        .iter()
+        .all(|binary| path.join(&version).join(bin_ext(binary)).exists())
+    });
+  if !still_installed {
+    return Err(CommandError::VersionManagement(format!(
+      "The previous version {} is no longer installed",
+      version
+    )));
+  }
+  let rolled_back_from = config.active_version.clone();
+  config
+    .switch_active_version(version_folder.clone(), version.clone())
+    .and_then(|_| config.set_active_version_proven())
+    .map_err(|_| {
+      CommandError::Configuration("Unable to persist active version change".to_string())
     })?;
+  update_active_version_link(config);
+  info!(
+    "Rolled back from {:?} to {}:{}",
+    rolled_back_from, version_folder, version
+  );
+  Ok(VersionRollback {
+    version_folder,
+    version,
+    rolled_back_from,
+    reason,
+  })
+}
 
-    // Verify that the extracted files seem correct (look for extractor.exe)
-    let expected_extractor_path = dest_dir.join("extractor");
-    if !expected_extractor_path.exists() {
-      log::info!(
-        "Version did not extract properly, {} is missing!",
-        expected_extractor_path.display()
-      );
-      delete_dir(&dest_dir).map_err(|_| {
-        CommandError::VersionManagement(format!(
-          "Unable to prepare destination folder '{}' for download",
-          dest_dir.display()
-        ))
-      })?;
-      return Err(CommandError::VersionManagement(format!(
-        "Version did not extract properly, critical files are missing. An antivirus may have deleted the files!"
-      )));
+// Rollbacks the launcher does by itself are announced with `activeVersionRolledBack`, so the
+// frontend can say what happened
+fn rollback_automatically(
+  app_handle: &tauri::AppHandle,
+  config: &mut LauncherConfig,
+  reason: String,
+) {
+  if config.previous_active_version().is_none() {
+    log::warn!("{}, but there is no previous version to go back to", reason);
+    return;
+  }
+  match rollback_to_previous_version(app_handle, config, Some(reason)) {
+    Ok(rollback) => {
+      if let Err(err) = app_handle.emit_all("activeVersionRolledBack", rollback) {
+        log::warn!("Unable to emit version rollback: {}", err);
+      }
     }
-    return Ok(());
+    Err(err) => log::warn!("Unable to roll back the active version: {}", err),
+  }
+}
+
+/// Goes back to the previous version if the active one is missing any of its binaries (ie. a
+/// reinstall of it failed part way, or an antivirus removed them), rather than leaving nothing
+/// the game can be launched with
+pub fn rollback_if_active_version_invalid(
+  app_handle: &tauri::AppHandle,
+  config: &mut LauncherConfig,
+) {
+  let active_dir = match (&config.active_version_folder, &config.active_version) {
+    (Some(folder), Some(version)) => config
+      .version_folder_path(folder)
+      .map(|path| path.join(version)),
+    _ => None,
+  };
+  let active_dir = match active_dir {
+    Some(dir) => dir,
+    None => return,
+  };
+  let missing: Vec<&str> = CRITICAL_BINARIES
+    .iter()
+    .copied()
+    .filter(|binary| !active_dir.join(bin_ext(binary)).exists())
+    .collect();
+  if !missing.is_empty() {
+    let reason = format!(
+      "{} is missing {}",
+      config.active_version.clone().unwrap_or_default(),
+      missing.join(", ")
+    );
+    rollback_automatically(app_handle, config, reason);
+  }
+}
+
+/// Called once the game exits.  A version the game ran fine with has proven itself, a new one
+/// that made it crash straight away is rolled back
+pub fn record_active_version_launch(app_handle: &tauri::AppHandle, crashed_early: bool) {
+  let config = app_handle.state::<tokio::sync::Mutex<LauncherConfig>>();
+  let mut config_lock = config.blocking_lock();
+  if config_lock.active_version_proven != Some(false) {
+    return;
+  }
+  if !crashed_early {
+    if let Err(err) = config_lock.set_active_version_proven() {
+      log::warn!("Unable to persist that the active version works: {}", err);
+    }
+    return;
+  }
+  let reason = format!(
+    "The game crashed right after starting with {}",
+    config_lock.active_version.clone().unwrap_or_default()
+  );
+  rollback_automatically(app_handle, &mut config_lock, reason);
+}
+
+// A download of the active version that fails validation is about to be removed, which would
+// leave nothing to launch the games with
+async fn rollback_failed_active_download(
+  app_handle: &tauri::AppHandle,
+  config: &tokio::sync::Mutex<LauncherConfig>,
+  version_folder: &VersionFolder,
+  version: &VersionName,
+  err: &CommandError,
+) {
+  let mut config_lock = config.lock().await;
+  if config_lock.active_version_folder.as_deref() == Some(version_folder.as_str())
+    && config_lock.active_version.as_deref() == Some(version.as_str())
+  {
+    let reason = format!("The download of {} failed validation: {}", version, err);
+    rollback_automatically(app_handle, &mut config_lock, reason);
   }
-  Err(CommandError::VersionManagement(format!(
-    "Unknown operating system, unable to download and extract correct release"
-  )))
 }
 
+/// Switches back to the previously active version
 #[tauri::command]
-pub async fn remove_version(
+#[specta::specta]
+pub async fn rollback_active_version(
   config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
-  version: String,
-  version_folder: String,
-) -> Result<(), CommandError> {
+  app_handle: tauri::AppHandle,
+) -> Result<VersionRollback, CommandError> {
   let mut config_lock = config.lock().await;
-  let install_path = match &config_lock.installation_dir {
-    None => {
-      return Err(CommandError::VersionManagement(format!(
-        "Cannot install version, no installation directory set"
-      )))
-    }
-    Some(path) => Path::new(path),
-  };
+  rollback_to_previous_version(&app_handle, &mut config_lock, None)
+}
 
-  info!("Deleting Version {}:{}", version_folder, version);
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum VersionDownloadStage {
+  Downloading,
+  /// Tarballs are extracted as they arrive, the bytes count towards both
+  DownloadingAndExtracting,
+  Extracting,
+  Extracted,
+}
 
-  let version_dir = install_path
-    .join("versions")
-    .join(&version_folder)
-    .join(&version);
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionDownloadProgress {
+  pub job_id: String,
+  pub version_folder: VersionFolder,
+  pub version: VersionName,
+  pub stage: VersionDownloadStage,
+  /// Only counted while downloading, the extraction stages just mark its start and end
+  pub downloaded_bytes: u64,
+  /// `None` if the server didn't say how large the download is
+  pub total_bytes: Option<u64>,
+  pub percent: Option<f64>,
+}
 
-  delete_dir(&version_dir)?;
+// Chunks arrive far more often than a progress bar needs updating
+const PROGRESS_EMIT_INTERVAL: Duration = Duration::from_millis(250);
 
-  // If it's the active version, we should clean that up in the settings file
-  match (
-    &config_lock.active_version_folder,
-    &config_lock.active_version,
-  ) {
-    (Some(config_version_folder), Some(config_version)) => {
-      if (version_folder == *config_version_folder) && (version == *config_version) {
-        config_lock.clear_active_version().map_err(|_| {
-          CommandError::VersionManagement(format!(
-            "Unable to clear active version after it was removed"
-          ))
-        })?;
-      }
+// The speed of the download itself only says much once it has been going for a bit, until then
+// the speed of previous downloads is the better guess
+const LIVE_SPEED_AFTER: Duration = Duration::from_secs(3);
+const ETA_UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+
+// Reports a download's progress, throttled, and keeps the task's remaining time up to date
+struct DownloadProgressTracker<'a> {
+  task: &'a TaskHandle,
+  stage: VersionDownloadStage,
+  emit_progress: &'a (dyn Fn(VersionDownloadStage, u64, Option<u64>) + Sync),
+  started: Instant,
+  previous_speed: Option<f64>,
+  last_estimate: Option<Instant>,
+  last_emit: Option<Instant>,
+  downloaded_bytes: u64,
+  total_bytes: Option<u64>,
+}
+
+impl<'a> DownloadProgressTracker<'a> {
+  fn new(
+    app_handle: &tauri::AppHandle,
+    task: &'a TaskHandle,
+    stage: VersionDownloadStage,
+    emit_progress: &'a (dyn Fn(VersionDownloadStage, u64, Option<u64>) + Sync),
+  ) -> Self {
+    Self {
+      task,
+      stage,
+      emit_progress,
+      started: Instant::now(),
+      previous_speed: throughput::download_bytes_per_sec(app_handle),
+      last_estimate: None,
+      last_emit: None,
+      downloaded_bytes: 0,
+      total_bytes: None,
     }
-    (_, _) => (),
   }
 
-  Ok(())
+  fn update(&mut self, downloaded: u64, total: Option<u64>) {
+    self.downloaded_bytes = downloaded;
+    self.total_bytes = total;
+    if !self
+      .last_emit
+      .map_or(false, |at| at.elapsed() < PROGRESS_EMIT_INTERVAL)
+    {
+      self.last_emit = Some(Instant::now());
+      (self.emit_progress)(self.stage, downloaded, total);
+    }
+    if self
+      .last_estimate
+      .map_or(false, |at| at.elapsed() < ETA_UPDATE_INTERVAL)
+    {
+      return;
+    }
+    self.last_estimate = Some(Instant::now());
+    let elapsed = self.started.elapsed();
+    let speed = if elapsed >= LIVE_SPEED_AFTER {
+      Some(downloaded as f64 / elapsed.as_secs_f64())
+    } else {
+      self.previous_speed
+    };
+    if let (Some(total), Some(speed)) = (total, speed.filter(|speed| *speed > 0.0)) {
+      let remaining = total.saturating_sub(downloaded) as f64 / speed;
+      self
+        .task
+        .set_remaining(Duration::from_secs_f64(remaining.min(1e9)));
+    }
+  }
+
+  fn finished(&self, app_handle: &tauri::AppHandle) {
+    // The last chunk was likely throttled away, so the bar would never reach the end
+    (self.emit_progress)(
+      self.stage,
+      self.downloaded_bytes,
+      self.total_bytes.or(Some(self.downloaded_bytes)),
+    );
+    throughput::record_download(app_handle, self.downloaded_bytes, self.started.elapsed());
+  }
 }
 
-#[tauri::command]
-pub async fn go_to_version_folder(
-  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
-  version_folder: String,
-) -> Result<(), CommandError> {
-  let config_lock = config.lock().await;
-  let install_path = match &config_lock.installation_dir {
-    None => {
-      return Err(CommandError::VersionManagement(format!(
-        "Cannot go to version folder, no installation directory set"
-      )))
+fn download_error(err: NetworkError) -> CommandError {
+  match err {
+    NetworkError::Cancelled => {
+      CommandError::VersionManagement("Download was cancelled".to_string())
     }
-    Some(path) => Path::new(path),
+    _ => CommandError::VersionManagement("Unable to successfully download version".to_string()),
+  }
+}
+
+async fn download_cancellable(
+  app_handle: &tauri::AppHandle,
+  url: &String,
+  download_path: &PathBuf,
+  task: &TaskHandle,
+  connections: u32,
+  pause: &PauseFlag,
+  emit_progress: &(dyn Fn(VersionDownloadStage, u64, Option<u64>) + Sync),
+) -> Result<(), CommandError> {
+  let cancellation_token = task.cancellation_token();
+  let mut progress = DownloadProgressTracker::new(
+    app_handle,
+    task,
+    VersionDownloadStage::Downloading,
+    emit_progress,
+  );
+  // The download checks for cancellation between chunks, but a stalled connection may never
+  // deliver the next one.  Dropping the download future aborts it, whatever was written is kept
+  // for the next attempt to resume
+  let download = download_file_chunked(
+    url,
+    download_path,
+    connections,
+    &cancellation_token,
+    pause,
+    |downloaded, total| progress.update(downloaded, total),
+  );
+  let result = tokio::select! {
+    result = download => result.map_err(download_error),
+    _ = cancellation_token.cancelled() => Err(CommandError::VersionManagement("Download was cancelled".to_string())),
   };
+  if result.is_ok() {
+    progress.finished(app_handle);
+  }
+  result
+}
 
-  let folder_path = Path::new(install_path)
-    .join("versions")
-    .join(version_folder);
-  create_dir(&folder_path).map_err(|_| {
-    CommandError::VersionManagement(format!(
-      "Unable to go to create version folder '{}' in order to open it",
-      folder_path.display()
-    ))
+/// Downloads the archive at `url` straight into `dest_dir`, extracting it as it arrives if its
+/// format allows.  Returns the archive's SHA-256, hashed in order over a single connection.  The
+/// archive is kept in `download_path` for an interrupted download to resume
+async fn download_and_extract_cancellable(
+  app_handle: &tauri::AppHandle,
+  url: &String,
+  download_path: &PathBuf,
+  dest_dir: &Path,
+  task: &TaskHandle,
+  pause: &PauseFlag,
+  emit_progress: &(dyn Fn(VersionDownloadStage, u64, Option<u64>) + Sync),
+) -> Result<String, CommandError> {
+  let cancellation_token = task.cancellation_token();
+  let mut progress = DownloadProgressTracker::new(
+    app_handle,
+    task,
+    VersionDownloadStage::DownloadingAndExtracting,
+    emit_progress,
+  );
+  let stream = ArchiveStream::start(dest_dir, Some(download_path)).map_err(|err| {
+    log::error!("unable to start extracting downloaded version: {}", err);
+    CommandError::VersionManagement("Unable to successfully extract downloaded version".to_string())
   })?;
+  let download = download_to_channel(
+    url,
+    download_path,
+    stream.sender(),
+    &cancellation_token,
+    pause,
+    |downloaded, total| progress.update(downloaded, total),
+  );
+  let result = tokio::select! {
+    result = download => result,
+    _ = cancellation_token.cancelled() => Err(NetworkError::Cancelled),
+  };
+  // The download is over one way or another, the extraction only has what's buffered left
+  let extraction = tokio::task::spawn_blocking(move || stream.finish())
+    .await
+    .map_err(|_| ArchiveError::Interrupted)
+    .and_then(|result| result);
+  match (result, extraction) {
+    (Ok(()), Ok(sha256)) => {
+      progress.finished(app_handle);
+      Ok(sha256)
+    }
+    // The extraction failing is what makes the download stop, so it's the one to report
+    (Err(NetworkError::SinkClosed), Err(err)) | (Ok(()), Err(err)) => {
+      log::error!("unable to extract downloaded version: {}", err);
+      discard_partial_download(download_path, dest_dir)?;
+      Err(CommandError::VersionManagement(
+        "Unable to successfully extract downloaded version".to_string(),
+      ))
+    }
+    (Err(err), _) => Err(download_error(err)),
+  }
+}
 
-  open_dir_in_os(folder_path.to_string_lossy().into_owned())
-    .map_err(|_| CommandError::VersionManagement(format!("Unable to open folder in OS")))?;
-  Ok(())
+fn is_sha256_hex(value: &str) -> bool {
+  value.len() == 64 && value.chars().all(|c| c.is_ascii_hexdigit())
 }
 
-#[tauri::command]
-pub async fn ensure_active_version_still_exists(
-  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
-) -> Result<bool, CommandError> {
-  let mut config_lock = config.lock().await;
-  let install_path = match &config_lock.installation_dir {
-    None => {
-      return Err(CommandError::VersionManagement(format!(
-        "Cannot install version, no installation directory set"
+// Releases may publish `<asset>.sha256` next to the asset, in `sha256sum`'s format
+async fn published_checksum(url: &str) -> Result<Option<String>, reqwest::Error> {
+  let res = reqwest::get(format!("{}.sha256", url)).await?;
+  if res.status() == reqwest::StatusCode::NOT_FOUND {
+    return Ok(None);
+  }
+  let content = res.error_for_status()?.text().await?;
+  Ok(
+    content
+      .split_whitespace()
+      .next()
+      .filter(|hash| is_sha256_hex(hash))
+      .map(|hash| hash.to_lowercase()),
+  )
+}
+
+/// The digest the archive at `url` should have: the one the caller passed, otherwise the one
+/// GitHub reports for the asset, otherwise a published checksum file
+async fn expected_sha256(
+  github: &GithubClient,
+  url: &str,
+  given: &Option<String>,
+) -> Option<String> {
+  if let Some(given) = given {
+    return Some(given.trim().to_lowercase());
+  }
+  // Not being able to ask shouldn't stop the download, the archive is then only unverified
+  match github_asset_sha256(github, url).await {
+    Ok(Some(digest)) => return Some(digest),
+    Ok(None) => {}
+    Err(err) => log::warn!("Unable to retrieve the digest of {}: {}", url, err),
+  }
+  published_checksum(url).await.unwrap_or_else(|err| {
+    log::warn!("Unable to retrieve the checksum of {}: {}", url, err);
+    None
+  })
+}
+
+async fn verify_archive(
+  github: &GithubClient,
+  url: &str,
+  archive_path: &PathBuf,
+  given_sha256: &Option<String>,
+) -> Result<ArchiveVerification, CommandError> {
+  let expected = expected_sha256(github, url, given_sha256).await;
+  let sha256 = sha256_file(archive_path)?;
+  match expected {
+    Some(expected) if expected != sha256 => {
+      log::error!(
+        "Downloaded archive has digest {}, expected {}",
+        sha256,
+        expected
+      );
+      // Likely corrupted along the way, a retry has to start over rather than resume from it
+      delete_file(archive_path)?;
+      Err(CommandError::VersionManagement(format!(
+        "Downloaded version failed the checksum check (expected SHA-256 {}, got {}), it may have \
+         been corrupted or tampered with",
+        expected, sha256
       )))
     }
-    Some(path) => Path::new(path),
-  };
-
-  info!(
-    "Checking if active version still exists {:?}:{:?}",
-    config_lock.active_version_folder, config_lock.active_version
-  );
+    expected => Ok(ArchiveVerification {
+      sha256,
+      verified: expected.is_some(),
+    }),
+  }
+}
 
-  match (
-    &config_lock.active_version_folder,
-    &config_lock.active_version,
-  ) {
-    (Some(config_version_folder), Some(config_version)) => {
-      let version_dir = install_path
-        .join("versions")
-        .join(&config_version_folder)
-        .join(&config_version);
-      if !version_dir.exists() {
-        // Clear active version if it's no longer available
-        config_lock.clear_active_version().map_err(|_| {
-          CommandError::VersionManagement(format!(
-            "Unable to clear active version after it was found to be missing"
-          ))
-        })?;
-      }
-      Ok(version_dir.exists())
+// Like `verify_archive` for an archive that was extracted as it was downloaded, in which case
+// it's what was extracted that has to go if the digest doesn't match
+async fn verify_streamed_archive(
+  github: &GithubClient,
+  url: &str,
+  sha256: String,
+  dest_dir: &Path,
+  given_sha256: &Option<String>,
+) -> Result<ArchiveVerification, CommandError> {
+  let expected = expected_sha256(github, url, given_sha256).await;
+  match expected {
+    Some(expected) if expected != sha256 => {
+      log::error!(
+        "Downloaded archive has digest {}, expected {}",
+        sha256,
+        expected
+      );
+      clear_dir(dest_dir)?;
+      Err(CommandError::VersionManagement(format!(
+        "Downloaded version failed the checksum check (expected SHA-256 {}, got {}), it may have \
+         been corrupted or tampered with",
+        expected, sha256
+      )))
     }
-    (_, _) => Ok(false),
+    expected => Ok(ArchiveVerification {
+      sha256,
+      verified: expected.is_some(),
+    }),
+  }
+}
+
+// Where a download of `version` is kept until it's done, and the folder it's extracted to before
+// being moved into place.  Both are kept when a download fails, for the next attempt at it to
+// pick up where it stopped
+fn partial_download_paths(version_folder_path: &Path, version: &str) -> (PathBuf, PathBuf) {
+  let extension = if cfg!(windows) { "zip" } else { "tar.gz" };
+  (
+    version_folder_path.join(format!("{}.{}", version, extension)),
+    version_folder_path.join(format!(".{}.partial", version)),
+  )
+}
+
+// For when what was downloaded turned out to be no good, the next attempt then starts over
+fn discard_partial_download(download_path: &Path, extract_dir: &Path) -> Result<(), CommandError> {
+  remove_partial_download(download_path)?;
+  ExtractionProgress::remove(download_path);
+  clear_dir(extract_dir)
+}
+
+// Empties a version's folder so a failed attempt doesn't leave files behind for the next one
+fn clear_dir(dir: &Path) -> Result<(), CommandError> {
+  let dir = dir.to_path_buf();
+  delete_dir(&dir)
+    .and_then(|_| create_dir(&dir))
+    .map_err(|_| {
+      CommandError::VersionManagement(format!(
+        "Unable to prepare destination folder '{}' for download",
+        dir.display()
+      ))
+    })
+}
+
+// Only official releases are listed with a channel, going by the last listing that was fetched
+fn listed_release_channel(
+  config: &LauncherConfig,
+  version_folder: &VersionFolder,
+  version: &VersionName,
+) -> Option<ReleaseChannel> {
+  if version_folder.as_str() != "official" {
+    return None;
+  }
+  read_release_cache(config)?
+    .releases
+    .into_iter()
+    .find(|release| release.tag == version.as_str())
+    .map(|release| release.channel)
+}
+
+// Hashing every file of the version takes a while, so it's kept off the async runtime
+async fn record_download_metadata(
+  version_dir: &Path,
+  verification: ArchiveVerification,
+  channel: Option<ReleaseChannel>,
+  signed: bool,
+  task: &TaskHandle,
+) {
+  if !verification.verified {
+    task.warn(
+      WarningCode::UnverifiedDownload,
+      "The downloaded version has no published digest to be checked against".to_string(),
+    );
+  }
+  let version_dir = version_dir.to_path_buf();
+  let recorded = tokio::task::spawn_blocking(move || {
+    let mut metadata = VersionMetadata::load(&version_dir);
+    metadata.archive = Some(verification);
+    metadata.channel = channel;
+    metadata.files = Some(version_files(&version_dir));
+    metadata.signed = Some(signed);
+    metadata
+      .save(&version_dir)
+      .map_err(|err| format!("{}: {}", version_dir.display(), err))
+  })
+  .await;
+  match recorded {
+    Ok(Ok(())) => {}
+    Ok(Err(err)) => log::warn!("Unable to save version metadata for {}", err),
+    Err(err) => log::warn!("Unable to record version metadata: {}", err),
+  }
+}
+
+/// Some archivers drop the executable bit, put it back on the binaries that lost it.  Returns
+/// the binaries that had to be fixed
+#[cfg(unix)]
+fn fix_binary_permissions(version_dir: &Path) -> Vec<String> {
+  use std::os::unix::fs::PermissionsExt;
+  let mut fixed = vec![];
+  for binary in ["extractor", "gk", "goalc"] {
+    let path = version_dir.join(binary);
+    let mode = match std::fs::metadata(&path) {
+      Ok(metadata) => metadata.permissions().mode(),
+      Err(_) => continue,
+    };
+    if mode & 0o111 != 0 {
+      continue;
+    }
+    match std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode | 0o755)) {
+      Ok(()) => fixed.push(binary.to_string()),
+      Err(err) => log::warn!("Unable to make {} executable: {}", path.display(), err),
+    }
+  }
+  fixed
+}
+
+/// Checks the signatures of the binaries in a freshly extracted version, if its release publishes
+/// them.  One that isn't signed, or whose signature isn't valid, is refused unless
+/// `allow_unsigned`.  Returns whether they all were signed
+// Versions from a source that isn't vouched for are only expected to be signed when they say so
+fn source_trust_level(config: &LauncherConfig, version_folder: &VersionFolder) -> TrustLevel {
+  config
+    .version_sources()
+    .into_iter()
+    .find(|source| &source.folder == version_folder)
+    .map_or(TrustLevel::Untrusted, |source| source.trust_level)
+}
+
+fn verify_binary_signatures(
+  version_dir: &Path,
+  trust_level: TrustLevel,
+  allow_unsigned: bool,
+  task: &TaskHandle,
+) -> Result<bool, CommandError> {
+  let results: Vec<(String, Result<bool, String>)> = ["extractor", "gk", "goalc"]
+    .iter()
+    .map(|binary| version_dir.join(bin_ext(binary)))
+    .filter(|path| path.is_file())
+    .map(|path| {
+      (
+        path
+          .file_name()
+          .unwrap_or_default()
+          .to_string_lossy()
+          .to_string(),
+        verify_binary(&path),
+      )
+    })
+    .collect();
+  // Official and trusted sources are held to their signatures, an unsigned binary is only
+  // kept when asked to, others are kept with a warning
+  let mut refused = vec![];
+  let mut unverified = vec![];
+  for (name, result) in results {
+    match result {
+      Ok(true) => {}
+      Ok(false) if trust_level == TrustLevel::Untrusted => {
+        unverified.push(format!("{} (it isn't signed)", name))
+      }
+      Ok(false) => refused.push(format!("{} (it isn't signed)", name)),
+      Err(reason) => refused.push(format!("{} ({})", name, reason)),
+    }
+  }
+  if refused.is_empty() && unverified.is_empty() {
+    return Ok(true);
+  }
+  log::warn!(
+    "Binaries in {} failed signature verification: {}",
+    version_dir.display(),
+    refused
+      .iter()
+      .chain(&unverified)
+      .cloned()
+      .collect::<Vec<_>>()
+      .join(", ")
+  );
+  if !refused.is_empty() && !allow_unsigned {
+    return Err(CommandError::UnsignedBinaries(format!(
+      "The downloaded binaries couldn't be verified: {}",
+      refused.join(", ")
+    )));
+  }
+  if !refused.is_empty() {
+    task.warn(
+      WarningCode::UnsignedBinaries,
+      format!(
+        "{} couldn't be verified, and were kept as asked",
+        refused.join(", ")
+      ),
+    );
+  }
+  if !unverified.is_empty() {
+    task.warn(
+      WarningCode::UnsignedBinaries,
+      format!(
+        "{} couldn't be verified, the version's source isn't trusted",
+        unverified.join(", ")
+      ),
+    );
+  }
+  Ok(false)
+}
+
+fn extract_archive_measured(
+  app_handle: &tauri::AppHandle,
+  task: &TaskHandle,
+  archive_path: &Path,
+  dest_dir: &Path,
+) -> Result<(), ArchiveError> {
+  let archive_bytes = std::fs::metadata(archive_path).map_or(0, |metadata| metadata.len());
+  if let Some(estimate) = throughput::extraction_duration(app_handle, archive_bytes) {
+    task.set_remaining(estimate);
+  }
+  let started = Instant::now();
+  extract_and_delete_archive(archive_path, dest_dir)?;
+  throughput::record_extraction(app_handle, archive_bytes, started.elapsed());
+  Ok(())
+}
+
+/// Runs `attempt` until it succeeds or the policy's retries are used up, waiting longer after
+/// every failure.  Each failed attempt is recorded in `download.log`, only the last error is
+/// returned
+async fn with_retries<T, Fut>(
+  app_handle: &tauri::AppHandle,
+  policy: InstallRetryPolicy,
+  step: &str,
+  task: &TaskHandle,
+  mut attempt: impl FnMut() -> Fut,
+) -> Result<T, CommandError>
+where
+  Fut: std::future::Future<Output = Result<T, CommandError>>,
+{
+  let cancellation_token = task.cancellation_token();
+  let mut tries = 0;
+  loop {
+    tries += 1;
+    let err = match attempt().await {
+      Ok(value) => {
+        if tries > 1 {
+          task.warn(
+            WarningCode::StepRetried,
+            format!("{} only succeeded after {} attempts", step, tries),
+          );
+        }
+        return Ok(value);
+      }
+      Err(err) => err,
+    };
+    let line = format!(
+      "[{}] {}, attempt {} of {} failed: {}",
+      chrono::Local::now().to_rfc3339(),
+      step,
+      tries,
+      policy.retries + 1,
+      err
+    );
+    log::warn!("{}", line);
+    match create_log_file(app_handle, "download.log", true) {
+      Ok(mut log_file) => {
+        if let Err(err) = writeln!(log_file, "{}", line) {
+          log::warn!("Unable to record install attempt: {}", err);
+        }
+      }
+      Err(err) => log::warn!("Unable to record install attempt: {}", err),
+    }
+    if tries > policy.retries || cancellation_token.is_cancelled() {
+      return Err(err);
+    }
+    let backoff = u64::from(policy.backoff_secs) << (tries - 1).min(6);
+    tokio::select! {
+      _ = tokio::time::sleep(Duration::from_secs(backoff)) => {}
+      _ = cancellation_token.cancelled() => return Err(err),
+    }
+  }
+}
+
+// Anything smaller than this is downloaded regardless of the connection
+const LARGE_DOWNLOAD_BYTES: u64 = 50 * 1024 * 1024;
+const METERED_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Asking the OS runs a process, so it's kept off the async runtime
+async fn connection_is_metered() -> bool {
+  tokio::task::spawn_blocking(is_connection_metered)
+    .await
+    .ok()
+    .flatten()
+    == Some(true)
+}
+
+/// Whether a large download has to wait for an unmetered connection, according to the user's
+/// preference, or an error if it needs their confirmation.  Downloads of an unknown size are
+/// treated as large.  Checked before the download's task is started, so a refused download
+/// doesn't leave a failed one behind
+async fn check_metered_connection(
+  behavior: MeteredBehavior,
+  allow_metered: bool,
+  url: &String,
+) -> Result<bool, CommandError> {
+  if behavior == MeteredBehavior::Ignore || allow_metered {
+    return Ok(false);
+  }
+  if !connection_is_metered().await {
+    return Ok(false);
+  }
+  let size = content_length(url).await.ok().flatten();
+  if size.map_or(false, |size| size < LARGE_DOWNLOAD_BYTES) {
+    return Ok(false);
+  }
+  match behavior {
+    MeteredBehavior::Confirm => Err(CommandError::MeteredConnection(format!(
+      "Connection is metered, download of {} requires confirmation",
+      size.map_or("unknown size".to_string(), |size| format!("{} bytes", size))
+    ))),
+    _ => Ok(true),
+  }
+}
+
+/// Holds off the download until the connection is no longer metered, or it's cancelled
+async fn wait_for_unmetered_connection(task: &TaskHandle) -> Result<(), CommandError> {
+  info!("Connection is metered, pausing download until it isn't");
+  let cancellation_token = task.cancellation_token();
+  while connection_is_metered().await {
+    tokio::select! {
+      _ = tokio::time::sleep(METERED_POLL_INTERVAL) => {},
+      _ = cancellation_token.cancelled() => return Err(CommandError::VersionManagement(
+        "Download was cancelled".to_string()
+      )),
+    }
+  }
+  info!("Connection is no longer metered, resuming download");
+  Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+// Managed state and the app handle are injected by tauri, they count towards the limit
+#[allow(clippy::too_many_arguments)]
+pub async fn download_version(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  remote_config: tauri::State<'_, tokio::sync::Mutex<RemoteConfig>>,
+  task_manager: tauri::State<'_, TaskManager>,
+  app_handle: tauri::AppHandle,
+  version: VersionName,
+  version_folder: VersionFolder,
+  url: Option<String>,
+  allow_metered: Option<bool>,
+  expected_sha256: Option<String>,
+  allow_unsigned: Option<bool>,
+) -> Result<(), CommandError> {
+  // Without a URL it's looked up from the version's source
+  let url = match url {
+    Some(url) => url,
+    None => resolve_download_url(&config, &remote_config, &version_folder, &version)
+      .await?
+      .ok_or_else(|| {
+        CommandError::VersionManagement(format!(
+          "Don't know where to download {} from, its source has no download URL template or GitHub repository",
+          version
+        ))
+      })?,
+  };
+  if let Some(hash) = &expected_sha256 {
+    if !is_sha256_hex(hash.trim()) {
+      return Err(CommandError::VersionManagement(format!(
+        "'{}' is not a SHA-256 checksum",
+        hash
+      )));
+    }
+  }
+  let migration_id = format!("migrate-versions-{}", version_folder);
+  if task_manager
+    .running()
+    .iter()
+    .any(|task| task.id == migration_id)
+  {
+    return Err(CommandError::VersionManagement(format!(
+      "'{}' versions are being moved, wait for it to finish before downloading",
+      version_folder
+    )));
+  }
+  if remote_config.lock().await.is_download_host_disabled(&url) {
+    return Err(CommandError::VersionManagement(
+      "Downloads from this location have been temporarily disabled, please try again later"
+        .to_string(),
+    ));
+  }
+  let config_lock = config.lock().await;
+  let metered_behavior = config_lock
+    .metered_behavior
+    .unwrap_or(MeteredBehavior::Confirm);
+  let github = github_client(&config_lock).map_err(CommandError::VersionManagement)?;
+  let trust_level = source_trust_level(&config_lock, &version_folder);
+  drop(config_lock);
+  let metered =
+    check_metered_connection(metered_behavior, allow_metered.unwrap_or(false), &url).await?;
+  let mut task = match task_manager.start_or_attach(
+    &app_handle,
+    format!("download-{}-{}", version_folder, version),
+    "Downloading tooling version",
+  ) {
+    Ok(task) => task,
+    Err(in_flight) => {
+      // Someone already asked for this exact download, share its outcome rather than racing it
+      let state = in_flight.finished().await;
+      return match state.status {
+        TaskStatus::Succeeded => Ok(()),
+        _ => {
+          Err(CommandError::VersionManagement(state.message.unwrap_or(
+            format!("Download of {} did not complete", version),
+          )))
+        }
+      };
+    }
+  };
+  let result = async {
+    let attempt = SetupAttempt::start(&app_handle, SetupStep::Download);
+    let download_queue = app_handle.state::<DownloadQueue>();
+    if metered {
+      if let Err(err) = wait_for_unmetered_connection(&task).await {
+        attempt.abandoned();
+        return Err(err);
+      }
+    }
+    let job = match download_queue
+      .enqueue(
+        &app_handle,
+        task.id().to_string(),
+        version_folder.clone(),
+        version.clone(),
+        &task.cancellation_token(),
+      )
+      .await
+    {
+      Some(job) => job,
+      None => {
+        attempt.abandoned();
+        return Err(CommandError::VersionManagement(
+          "Download was cancelled".to_string(),
+        ));
+      }
+    };
+    let config_lock = config.lock().await;
+    ensure_version_folder_writable(&config_lock, version_folder.as_str())?;
+    let retry_policy = config_lock.install_retry_policy.unwrap_or_default();
+    let connections = config_lock.download_connections();
+    let channel = listed_release_channel(&config_lock, &version_folder, &version);
+    let version_folder_path = match config_lock.version_folder_path(version_folder.as_str()) {
+      None => {
+        return Err(CommandError::VersionManagement(
+          "Cannot install version, no tooling directory set".to_string(),
+        ))
+      }
+      Some(path) => path,
+    };
+    // Not held for the download, and deduplicating the new version takes it again
+    drop(config_lock);
+
+    let dest_dir = version_folder_path.join(&version);
+    let (download_path, extract_dir) = partial_download_paths(&version_folder_path, version.as_str());
+
+    // Delete the directory if it exists, it's extracted from scratch unless an earlier attempt
+    // left part of it extracted
+    delete_dir(&dest_dir).map_err(|_| {
+      CommandError::VersionManagement(format!(
+        "Unable to prepare destination folder '{}' for download",
+        dest_dir.display()
+      ))
+    })?;
+    if !ExtractionProgress::is_started(&download_path) {
+      clear_dir(&extract_dir)?;
+    }
+    // Only moved into place once it's all there
+    let move_into_place = || {
+      std::fs::rename(&extract_dir, &dest_dir).map_err(|err| {
+        CommandError::VersionManagement(format!(
+          "Unable to move the downloaded version into '{}': {}",
+          dest_dir.display(),
+          err
+        ))
+      })
+    };
+    let emit_progress = |stage: VersionDownloadStage, downloaded: u64, total: Option<u64>| {
+      if matches!(
+        stage,
+        VersionDownloadStage::Downloading | VersionDownloadStage::DownloadingAndExtracting
+      ) {
+        job.set_progress(downloaded, total);
+      }
+      let progress = VersionDownloadProgress {
+        job_id: job.id().to_string(),
+        version_folder: version_folder.clone(),
+        version: version.clone(),
+        stage,
+        downloaded_bytes: downloaded,
+        total_bytes: total,
+        percent: total
+          .filter(|total| *total > 0)
+          .map(|total| (downloaded as f64 / total as f64 * 100.0).min(100.0)),
+      };
+      if let Err(err) = app_handle.emit_all("versionDownloadProgress", progress) {
+        log::warn!("Unable to emit download progress: {}", err);
+      }
+    };
+    let _sleep_inhibitor = SleepInhibitor::acquire("Downloading tooling version");
+    let mut artifacts = DownloadArtifacts::new();
+    artifacts.track_dir(&dest_dir);
+    artifacts.track_download(&download_path);
+
+    if cfg!(windows) {
+      let mut redownloaded = false;
+      let verification = loop {
+        // Download the file
+        let verification = with_retries(&app_handle, retry_policy, "Download", &task, || async {
+          download_cancellable(
+            &app_handle,
+            &url,
+            &download_path,
+            &task,
+            connections,
+            job.pause_flag(),
+            &emit_progress,
+          )
+          .await?;
+          let verification = verify_archive(&github, &url, &download_path, &expected_sha256).await;
+          if verification.is_err() {
+            // Not what was expected, it's downloaded again from scratch rather than resumed
+            discard_partial_download(&download_path, &extract_dir)?;
+          }
+          verification
+        })
+        .await?;
+
+        // Extract the zip file
+        emit_progress(VersionDownloadStage::Extracting, 0, None);
+        let extracted = with_retries(&app_handle, retry_policy, "Extraction", &task, || async {
+          // Only what a failed attempt recorded as done is kept
+          if !ExtractionProgress::is_started(&download_path) {
+            clear_dir(&extract_dir)?;
+          }
+          extract_archive_measured(&app_handle, &task, &download_path, &extract_dir).map_err(|_| {
+            CommandError::VersionManagement(
+              "Unable to successfully extract downloaded version".to_string(),
+            )
+          })
+        })
+        .await;
+        match extracted {
+          Ok(()) => break verification,
+          // Without a digest to check it against, a corrupt archive is only found out about now
+          Err(err) if !redownloaded && !task.cancellation_token().is_cancelled() => {
+            log::warn!("Downloading {} again from scratch: {}", version, err);
+            discard_partial_download(&download_path, &extract_dir)?;
+            redownloaded = true;
+          }
+          Err(err) => return Err(err),
+        }
+      };
+      move_into_place()?;
+
+      // Verify that the extracted files seem correct (look for extractor.exe)
+      let expected_extractor_path = dest_dir.join("extractor.exe");
+      if !expected_extractor_path.exists() {
+        log::info!(
+          "Version did not extract properly, {} is missing!",
+          expected_extractor_path.display()
+        );
+        let err = CommandError::VersionManagement("Version did not extract properly, critical files are missing. An antivirus may have deleted the files!".to_string());
+        rollback_failed_active_download(&app_handle, &config, &version_folder, &version, &err).await;
+        return Err(err);
+      }
+      let signed = match verify_binary_signatures(
+        &dest_dir,
+        trust_level,
+        allow_unsigned.unwrap_or(false),
+        &task,
+      ) {
+        Ok(signed) => signed,
+        Err(err) => {
+          rollback_failed_active_download(&app_handle, &config, &version_folder, &version, &err)
+            .await;
+          return Err(err);
+        }
+      };
+      record_download_metadata(&dest_dir, verification, channel, signed, &task).await;
+      stamp_version_metadata(&dest_dir);
+      dedupe_new_version(&config, &dest_dir).await;
+      artifacts.succeeded();
+      emit_progress(VersionDownloadStage::Extracted, 0, None);
+      task.finish(true, None);
+      attempt.succeeded();
+      return Ok(());
+    } else if cfg!(unix) {
+      // Download and extract the tarball at once.  A failed attempt resumes both, unless it was the
+      // archive that was no good
+      let mut redownloaded = false;
+      let verification = loop {
+        let verification = with_retries(&app_handle, retry_policy, "Download", &task, || async {
+          let sha256 = download_and_extract_cancellable(
+            &app_handle,
+            &url,
+            &download_path,
+            &extract_dir,
+            &task,
+            job.pause_flag(),
+            &emit_progress,
+          )
+          .await?;
+          let verification =
+            verify_streamed_archive(&github, &url, sha256, &extract_dir, &expected_sha256).await;
+          if verification.is_err() {
+            discard_partial_download(&download_path, &extract_dir)?;
+          }
+          verification
+        })
+        .await;
+        match verification {
+          Ok(verification) => break verification,
+          // Resuming a corrupt archive fails the same way every time, it's started over once
+          Err(err) if !redownloaded && !task.cancellation_token().is_cancelled() => {
+            log::warn!("Downloading {} again from scratch: {}", version, err);
+            discard_partial_download(&download_path, &extract_dir)?;
+            redownloaded = true;
+          }
+          Err(err) => return Err(err),
+        }
+      };
+      move_into_place()?;
+
+      // Verify that the extracted files seem correct (look for extractor.exe)
+      let expected_extractor_path = dest_dir.join("extractor");
+      if !expected_extractor_path.exists() {
+        log::info!(
+          "Version did not extract properly, {} is missing!",
+          expected_extractor_path.display()
+        );
+        let err = CommandError::VersionManagement("Version did not extract properly, critical files are missing. An antivirus may have deleted the files!".to_string());
+        rollback_failed_active_download(&app_handle, &config, &version_folder, &version, &err).await;
+        return Err(err);
+      }
+      #[cfg(unix)]
+      {
+        let fixed = fix_binary_permissions(&dest_dir);
+        if !fixed.is_empty() {
+          task.warn(
+            WarningCode::PermissionsFixed,
+            format!(
+              "{} weren't executable and had to be fixed",
+              fixed.join(", ")
+            ),
+          );
+        }
+      }
+      let signed = match verify_binary_signatures(
+        &dest_dir,
+        trust_level,
+        allow_unsigned.unwrap_or(false),
+        &task,
+      ) {
+        Ok(signed) => signed,
+        Err(err) => {
+          rollback_failed_active_download(&app_handle, &config, &version_folder, &version, &err)
+            .await;
+          return Err(err);
+        }
+      };
+      record_download_metadata(&dest_dir, verification, channel, signed, &task).await;
+      stamp_version_metadata(&dest_dir);
+      dedupe_new_version(&config, &dest_dir).await;
+      artifacts.succeeded();
+      emit_progress(VersionDownloadStage::Extracted, 0, None);
+      task.finish(true, None);
+      attempt.succeeded();
+      return Ok(());
+    }
+    Err(CommandError::VersionManagement(
+      "Unknown operating system, unable to download and extract correct release".to_string(),
+    ))
+  }
+  .await;
+  if let Err(err) = &result {
+    task.finish(false, Some(err.to_string()));
+  }
+  result
+}
+
+/// Downloads that are queued or running
+#[tauri::command]
+#[specta::specta]
+pub async fn list_downloads(
+  download_queue: tauri::State<'_, DownloadQueue>,
+) -> Result<Vec<DownloadJob>, CommandError> {
+  Ok(download_queue.list())
+}
+
+/// Holds a download (or keeps a queued one from starting) until it's resumed, returns whether
+/// there was one to pause
+#[tauri::command]
+#[specta::specta]
+pub async fn pause_download(
+  download_queue: tauri::State<'_, DownloadQueue>,
+  app_handle: tauri::AppHandle,
+  job_id: String,
+) -> Result<bool, CommandError> {
+  Ok(download_queue.pause(&app_handle, &job_id))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn resume_download(
+  download_queue: tauri::State<'_, DownloadQueue>,
+  app_handle: tauri::AppHandle,
+  job_id: String,
+) -> Result<bool, CommandError> {
+  Ok(download_queue.resume(&app_handle, &job_id))
+}
+
+/// Stops a download started with `download_version`, whether it's running or still queued.
+/// Returns whether there was one to stop
+#[tauri::command]
+#[specta::specta]
+pub async fn cancel_download(
+  task_manager: tauri::State<'_, TaskManager>,
+  job_id: String,
+) -> Result<bool, CommandError> {
+  if !job_id.starts_with("download-") {
+    return Ok(false);
+  }
+  Ok(task_manager.cancel(&job_id))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn remove_version(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  task_manager: tauri::State<'_, TaskManager>,
+  app_handle: tauri::AppHandle,
+  version: VersionName,
+  version_folder: VersionFolder,
+) -> Result<DeletionProgress, CommandError> {
+  let mut task = match task_manager.start_or_attach(
+    &app_handle,
+    format!("remove-{}-{}", version_folder, version),
+    "Removing tooling version",
+  ) {
+    Ok(task) => task,
+    Err(in_flight) => {
+      in_flight.finished().await;
+      return Err(CommandError::VersionManagement(format!(
+        "Version {} was already being removed",
+        version
+      )));
+    }
+  };
+  let mut config_lock = config.lock().await;
+  let prepared = (|| {
+    ensure_version_folder_writable(&config_lock, version_folder.as_str())?;
+    let version_folder_path = match config_lock.version_folder_path(version_folder.as_str()) {
+      None => {
+        return Err(CommandError::VersionManagement(
+          "Cannot install version, no tooling directory set".to_string(),
+        ))
+      }
+      Some(path) => path,
+    };
+    // A partly removed version is as good as gone, so the settings stop pointing at it up front
+    if let (Some(config_version_folder), Some(config_version)) = (
+      &config_lock.active_version_folder,
+      &config_lock.active_version,
+    ) {
+      if (version_folder.as_str() == config_version_folder) && (version.as_str() == config_version)
+      {
+        config_lock.clear_active_version().map_err(|_| {
+          CommandError::VersionManagement(
+            "Unable to clear active version after it was removed".to_string(),
+          )
+        })?;
+        update_active_version_link(&config_lock);
+      }
+    }
+    Ok(version_folder_path)
+  })();
+  // The version can take a while to delete, the settings aren't needed for it
+  drop(config_lock);
+  let version_folder_path = match prepared {
+    Ok(version_folder_path) => version_folder_path,
+    Err(err) => {
+      task.finish(false, Some(err.to_string()));
+      return Err(err);
+    }
+  };
+
+  info!("Deleting Version {}:{}", version_folder, version);
+
+  let version_dir = version_folder_path.join(&version);
+
+  let (mut task, removed) = tokio::task::spawn_blocking({
+    let app_handle = app_handle.clone();
+    let version = version.clone();
+    move || {
+      let removed = delete_dirs_for_task(&app_handle, &task, &[version_dir]);
+      // Along with whatever is left of a download of it that never finished
+      let (download_path, extract_dir) =
+        partial_download_paths(&version_folder_path, version.as_str());
+      ExtractionProgress::remove(&download_path);
+      if let Err(err) =
+        remove_partial_download(&download_path).and_then(|_| delete_dir(&extract_dir))
+      {
+        log::warn!(
+          "Unable to remove the partial download of {}: {}",
+          version,
+          err
+        );
+      }
+      (task, removed)
+    }
+  })
+  .await
+  .map_err(|_| CommandError::VersionManagement(format!("Unable to remove version {}", version)))?;
+
+  match removed {
+    Ok(removed) => {
+      info!(
+        "Removed {} files ({} bytes) of version {}:{}",
+        removed.files_removed, removed.bytes_removed, version_folder, version
+      );
+      task.finish(true, None);
+      Ok(removed)
+    }
+    Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {
+      Err(CommandError::VersionManagement(format!(
+        "Removal of {} was cancelled, it has been partly removed",
+        version
+      )))
+    }
+    Err(err) => {
+      task.finish(false, Some(err.to_string()));
+      Err(err.into())
+    }
+  }
+}
+
+// The files in a version's folder, leaving out what the launcher puts there itself
+fn version_files(version_dir: &Path) -> Vec<VersionFile> {
+  walkdir::WalkDir::new(version_dir)
+    .into_iter()
+    .filter_map(|entry| entry.ok())
+    .filter(|entry| entry.file_type().is_file())
+    .filter(|entry| entry.path() != VersionMetadata::path(version_dir))
+    .filter_map(|entry| {
+      let relative = entry.path().strip_prefix(version_dir).ok()?;
+      let path = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+      let bytes = entry.metadata().ok()?.len();
+      let sha256 = match sha256_file(entry.path()) {
+        Ok(sha256) => sha256,
+        Err(err) => {
+          log::warn!("Unable to hash {}: {}", entry.path().display(), err);
+          return None;
+        }
+      };
+      Some(VersionFile {
+        path,
+        bytes,
+        sha256,
+      })
+    })
+    .collect()
+}
+
+// What every version has to have, all that can be checked for versions downloaded without a
+// list of their files
+const CRITICAL_BINARIES: [&str; 3] = ["extractor", "gk", "goalc"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionVerification {
+  pub version_folder: VersionFolder,
+  pub version: VersionName,
+  pub intact: bool,
+  /// Whether the version's files were recorded when it was downloaded, otherwise only the
+  /// critical binaries are checked
+  pub has_manifest: bool,
+  pub hashes_checked: bool,
+  pub checked_files: u64,
+  pub missing: Vec<String>,
+  /// Files that are there, but aren't what was downloaded
+  pub modified: Vec<String>,
+}
+
+fn verify_version_dir(
+  version_dir: &Path,
+  version_folder: &VersionFolder,
+  version: &VersionName,
+  check_hashes: bool,
+) -> VersionVerification {
+  let metadata = VersionMetadata::load(version_dir);
+  let mut missing = vec![];
+  let mut modified = vec![];
+  let checked_files = match &metadata.files {
+    Some(files) => {
+      for file in files {
+        let path = version_dir.join(&file.path);
+        let bytes = match std::fs::metadata(&path) {
+          Ok(metadata) => metadata.len(),
+          Err(_) => {
+            missing.push(file.path.clone());
+            continue;
+          }
+        };
+        let changed = bytes != file.bytes
+          || (check_hashes && sha256_file(&path).map_or(true, |sha256| sha256 != file.sha256));
+        if changed {
+          modified.push(file.path.clone());
+        }
+      }
+      files.len()
+    }
+    None => {
+      for binary in CRITICAL_BINARIES {
+        let name = bin_ext(binary);
+        if !version_dir.join(&name).is_file() {
+          missing.push(name);
+        }
+      }
+      CRITICAL_BINARIES.len()
+    }
+  };
+  VersionVerification {
+    version_folder: version_folder.clone(),
+    version: version.clone(),
+    intact: missing.is_empty() && modified.is_empty(),
+    has_manifest: metadata.files.is_some(),
+    hashes_checked: check_hashes && metadata.files.is_some(),
+    checked_files: checked_files as u64,
+    missing,
+    modified,
+  }
+}
+
+fn existing_version_dir(
+  config: &LauncherConfig,
+  version_folder: &VersionFolder,
+  version: &VersionName,
+) -> Result<PathBuf, CommandError> {
+  let version_dir = config
+    .version_folder_path(version_folder.as_str())
+    .map(|path| path.join(version));
+  match version_dir {
+    Some(dir) if dir.is_dir() => Ok(dir),
+    _ => Err(CommandError::VersionManagement(format!(
+      "{} is not downloaded",
+      version
+    ))),
+  }
+}
+
+/// Hashing a version's files takes a while, so it's kept off the async runtime
+async fn verify_version_dir_in_background(
+  version_dir: PathBuf,
+  version_folder: &VersionFolder,
+  version: &VersionName,
+  check_hashes: bool,
+) -> Result<VersionVerification, CommandError> {
+  let (version_folder, version) = (version_folder.clone(), version.clone());
+  tokio::task::spawn_blocking(move || {
+    verify_version_dir(&version_dir, &version_folder, &version, check_hashes)
+  })
+  .await
+  .map_err(|err| CommandError::VersionManagement(format!("Unable to verify version: {}", err)))
+}
+
+/// Checks that a downloaded version still has every file it came with, comparing their sizes
+/// (and contents, if `check_hashes` is set) against what was recorded when it was downloaded
+#[tauri::command]
+#[specta::specta]
+pub async fn verify_version(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  version_folder: VersionFolder,
+  version: VersionName,
+  check_hashes: Option<bool>,
+) -> Result<VersionVerification, CommandError> {
+  let version_dir = existing_version_dir(&*config.lock().await, &version_folder, &version)?;
+  let verification = verify_version_dir_in_background(
+    version_dir,
+    &version_folder,
+    &version,
+    check_hashes.unwrap_or(false),
+  )
+  .await?;
+  if !verification.intact {
+    log::warn!(
+      "{} is damaged, missing {:?}, modified {:?}",
+      version,
+      verification.missing,
+      verification.modified
+    );
+  }
+  Ok(verification)
+}
+
+/// Puts back the files `verify_version` found missing or modified, from a fresh download of the
+/// version.  Everything else in the version's folder is left as it is
+#[tauri::command]
+#[specta::specta]
+// Managed state and the app handle are injected by tauri, they count towards the limit
+#[allow(clippy::too_many_arguments)]
+pub async fn repair_version(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  remote_config: tauri::State<'_, tokio::sync::Mutex<RemoteConfig>>,
+  task_manager: tauri::State<'_, TaskManager>,
+  app_handle: tauri::AppHandle,
+  version_folder: VersionFolder,
+  version: VersionName,
+  check_hashes: Option<bool>,
+  allow_metered: Option<bool>,
+  allow_unsigned: Option<bool>,
+) -> Result<VersionVerification, CommandError> {
+  let check_hashes = check_hashes.unwrap_or(false);
+  let config_lock = config.lock().await;
+  let version_dir = existing_version_dir(&config_lock, &version_folder, &version)?;
+  let trust_level = source_trust_level(&config_lock, &version_folder);
+  drop(config_lock);
+  let verification =
+    verify_version_dir_in_background(version_dir.clone(), &version_folder, &version, check_hashes)
+      .await?;
+  if verification.intact {
+    return Ok(verification);
+  }
+  let url = resolve_download_url(&config, &remote_config, &version_folder, &version)
+    .await?
+    .ok_or_else(|| {
+      CommandError::VersionManagement(format!(
+        "Don't know where to download {} from, it has to be replaced by hand",
+        version
+      ))
+    })?;
+  let config_lock = config.lock().await;
+  let metered_behavior = config_lock
+    .metered_behavior
+    .unwrap_or(MeteredBehavior::Confirm);
+  drop(config_lock);
+  let metered =
+    check_metered_connection(metered_behavior, allow_metered.unwrap_or(false), &url).await?;
+  let mut task = match task_manager.start_or_attach(
+    &app_handle,
+    format!("repair-{}-{}", version_folder, version),
+    "Repairing tooling version",
+  ) {
+    Ok(task) => task,
+    Err(_) => {
+      return Err(CommandError::VersionManagement(format!(
+        "{} is already being repaired",
+        version
+      )))
+    }
+  };
+  // The whole archive is needed to get at any of its files, it's extracted next to the version
+  let staging_dir = version_dir.with_file_name(format!(".{}.repair", version));
+  let download_path = version_dir.with_file_name(format!(
+    ".{}.repair.{}",
+    version,
+    if cfg!(windows) { "zip" } else { "tar.gz" }
+  ));
+  let mut artifacts = DownloadArtifacts::new();
+  artifacts.track_download(&download_path);
+  artifacts.track_file(&ExtractionProgress::path(&download_path));
+  artifacts.track_dir(&staging_dir);
+  let emit_progress = |stage: VersionDownloadStage, downloaded: u64, total: Option<u64>| {
+    let progress = VersionDownloadProgress {
+      job_id: task.id().to_string(),
+      version_folder: version_folder.clone(),
+      version: version.clone(),
+      stage,
+      downloaded_bytes: downloaded,
+      total_bytes: total,
+      percent: total
+        .filter(|total| *total > 0)
+        .map(|total| (downloaded as f64 / total as f64 * 100.0).min(100.0)),
+    };
+    if let Err(err) = app_handle.emit_all("versionDownloadProgress", progress) {
+      log::warn!("Unable to emit download progress: {}", err);
+    }
+  };
+  let result = async {
+    let config_lock = config.lock().await;
+    ensure_version_folder_writable(&config_lock, version_folder.as_str())?;
+    let connections = config_lock.download_connections();
+    let github = github_client(&config_lock).map_err(CommandError::VersionManagement)?;
+    drop(config_lock);
+    if metered {
+      wait_for_unmetered_connection(&task).await?;
+    }
+    download_cancellable(
+      &app_handle,
+      &url,
+      &download_path,
+      &task,
+      connections,
+      &PauseFlag::default(),
+      &emit_progress,
+    )
+    .await?;
+    // A version that was verified when it was downloaded has to come back the same
+    let known_sha256 = VersionMetadata::load(&version_dir)
+      .archive
+      .filter(|archive| archive.verified)
+      .map(|archive| archive.sha256);
+    if let Err(err) = verify_archive(&github, &url, &download_path, &known_sha256).await {
+      remove_partial_download(&download_path)?;
+      return Err(err);
+    }
+    emit_progress(VersionDownloadStage::Extracting, 0, None);
+    delete_dir(&staging_dir)?;
+    create_dir(&staging_dir)?;
+    extract_archive_measured(&app_handle, &task, &download_path, &staging_dir).map_err(|err| {
+      log::error!("unable to extract version for repair {}", err);
+      CommandError::VersionManagement("Unable to extract the downloaded version".to_string())
+    })?;
+    // Restored binaries are held to the same signatures as a fresh download
+    verify_binary_signatures(
+      &staging_dir,
+      trust_level,
+      allow_unsigned.unwrap_or(false),
+      &task,
+    )?;
+    for path in verification.missing.iter().chain(&verification.modified) {
+      let source = staging_dir.join(path);
+      if !source.is_file() {
+        return Err(CommandError::VersionManagement(format!(
+          "The downloaded version has no {}, it can't be repaired",
+          path
+        )));
+      }
+      let dest = version_dir.join(path);
+      if let Some(parent) = dest.parent() {
+        create_dir(&parent.to_path_buf())?;
+      }
+      // It may be a hardlink shared with other versions, which mustn't be written through
+      let _ = std::fs::remove_file(&dest);
+      std::fs::copy(&source, &dest)?;
+      log::info!("Restored {} of {}", path, version);
+    }
+    // Versions downloaded before files were recorded can be checked fully from now on
+    let mut metadata = VersionMetadata::load(&version_dir);
+    if metadata.files.is_none() {
+      let hashed_dir = staging_dir.clone();
+      let files = tokio::task::spawn_blocking(move || version_files(&hashed_dir))
+        .await
+        .map_err(|_| {
+          CommandError::VersionManagement("Unable to record the version's files".to_string())
+        })?;
+      metadata.files = Some(files);
+      metadata.save(&version_dir)?;
+    }
+    #[cfg(unix)]
+    fix_binary_permissions(&version_dir);
+    emit_progress(VersionDownloadStage::Extracted, 0, None);
+    Ok(())
+  }
+  .await;
+  // The staging folder always goes, the archive only once it's no longer needed to resume
+  if result.is_ok() {
+    artifacts.succeeded();
+    artifacts.track_dir(&staging_dir);
+  }
+  drop(artifacts);
+  if let Err(err) = result {
+    task.finish(false, Some(err.to_string()));
+    return Err(err);
+  }
+  task.finish(true, None);
+  Ok(verify_version_dir(
+    &version_dir,
+    &version_folder,
+    &version,
+    check_hashes,
+  ))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PrunedVersion {
+  pub version_folder: VersionFolder,
+  pub version: String,
+  pub bytes_removed: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PruneResult {
+  pub removed: Vec<PrunedVersion>,
+  pub bytes_reclaimed: u64,
+}
+
+// Newest first.  Releases are ordered by their version, a folder with anything else in it (ie.
+// builds named after a branch) by when its versions were downloaded
+fn versions_newest_first(folder_path: &Path) -> Vec<String> {
+  let entries = match std::fs::read_dir(folder_path) {
+    Ok(entries) => entries,
+    Err(_) => return vec![],
+  };
+  let mut versions: Vec<(String, std::time::SystemTime)> = entries
+    .filter_map(|entry| entry.ok())
+    .filter(is_version_dir)
+    .map(|entry| {
+      let modified = entry
+        .metadata()
+        .and_then(|metadata| metadata.modified())
+        .unwrap_or(std::time::UNIX_EPOCH);
+      (entry.file_name().to_string_lossy().into_owned(), modified)
+    })
+    .collect();
+  if versions
+    .iter()
+    .all(|(name, _)| parse_version(name).is_some())
+  {
+    versions.sort_by_key(|(name, _)| std::cmp::Reverse(parse_version(name)));
+  } else {
+    versions.sort_by_key(|(_, modified)| std::cmp::Reverse(*modified));
+  }
+  versions.into_iter().map(|(name, _)| name).collect()
+}
+
+/// Removes all but the `keep` most recent versions (`versions_to_keep` from the config if not
+/// given) of every version folder.  The active version, the previous one (to be able to roll
+/// back), and versions that are still being downloaded, are never removed, and neither are
+/// shared versions as other users may rely on them
+#[tauri::command]
+#[specta::specta]
+pub async fn prune_versions(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  task_manager: tauri::State<'_, TaskManager>,
+  download_queue: tauri::State<'_, DownloadQueue>,
+  app_handle: tauri::AppHandle,
+  keep: Option<u32>,
+) -> Result<PruneResult, CommandError> {
+  let mut task = match task_manager.start_or_attach(
+    &app_handle,
+    "prune-versions".to_string(),
+    "Removing old tooling versions",
+  ) {
+    Ok(task) => task,
+    Err(in_flight) => {
+      in_flight.finished().await;
+      return Err(CommandError::VersionManagement(
+        "Old versions were already being removed".to_string(),
+      ));
+    }
+  };
+  let config_lock = config.lock().await;
+  let keep = keep.unwrap_or_else(|| config_lock.versions_to_keep()) as usize;
+  let downloading = download_queue.list();
+  let mut result = PruneResult {
+    removed: vec![],
+    bytes_reclaimed: 0,
+  };
+  // Picked while holding the config, but it isn't held while they're deleted
+  let mut to_prune = vec![];
+  for source in config_lock.version_sources() {
+    let folder = source.folder;
+    if config_lock.is_version_folder_shared(folder.as_str()) {
+      continue;
+    }
+    let folder_path = match config_lock.version_folder_path(folder.as_str()) {
+      Some(path) => path,
+      None => continue,
+    };
+    let is_active = |version: &str| {
+      (config_lock.active_version_folder.as_deref() == Some(folder.as_str())
+        && config_lock.active_version.as_deref() == Some(version))
+        || config_lock.previous_active_version() == Some((folder.as_str(), version))
+    };
+    let is_downloading = |version: &str| {
+      downloading
+        .iter()
+        .any(|job| job.version_folder == folder && job.version.as_str() == version)
+    };
+    let pruned: Vec<String> = versions_newest_first(&folder_path)
+      .into_iter()
+      .filter(|version| !is_active(version))
+      .skip(keep)
+      .filter(|version| !is_downloading(version))
+      .collect();
+    to_prune.push((folder, folder_path, pruned));
+  }
+  drop(config_lock);
+  for (folder, folder_path, pruned) in to_prune {
+    for version in pruned {
+      info!("Pruning version {}:{}", folder, version);
+      match delete_dirs_for_task(&app_handle, &task, &[folder_path.join(&version)]) {
+        Ok(removed) => {
+          result.bytes_reclaimed += removed.bytes_removed;
+          result.removed.push(PrunedVersion {
+            version_folder: folder.clone(),
+            version,
+            bytes_removed: removed.bytes_removed,
+          });
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {
+          return Err(CommandError::VersionManagement(format!(
+            "Pruning was cancelled after removing {} version(s), {} is partly removed",
+            result.removed.len(),
+            version
+          )));
+        }
+        Err(err) => {
+          task.finish(false, Some(err.to_string()));
+          return Err(err.into());
+        }
+      }
+    }
+  }
+  info!(
+    "Pruned {} versions, reclaiming {} bytes",
+    result.removed.len(),
+    result.bytes_reclaimed
+  );
+  task.finish(true, None);
+  Ok(result)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionDiskUsage {
+  pub version_folder: VersionFolder,
+  pub version: String,
+  pub bytes: u64,
+  pub files: u64,
+  pub measured_at: String,
+}
+
+/// Disk usage already measured, by version directory.  A version's directory is only ever
+/// replaced or written to at the top (ie. its metadata), so it's measured again once its
+/// modification time changes
+#[derive(Default)]
+pub struct VersionDiskUsageCache {
+  entries: std::sync::Mutex<HashMap<PathBuf, (std::time::SystemTime, VersionDiskUsage)>>,
+}
+
+fn measure_version_dir(dir: &Path) -> (u64, u64) {
+  walkdir::WalkDir::new(dir)
+    .into_iter()
+    .filter_map(|entry| entry.ok())
+    .filter(|entry| entry.file_type().is_file())
+    .filter_map(|entry| entry.metadata().ok())
+    .fold((0, 0), |(bytes, files), metadata| {
+      (bytes + metadata.len(), files + 1)
+    })
+}
+
+impl VersionDiskUsageCache {
+  fn get_or_measure(
+    &self,
+    version_folder: &VersionFolder,
+    version: String,
+    dir: &Path,
+    refresh: bool,
+  ) -> VersionDiskUsage {
+    let modified = std::fs::metadata(dir)
+      .and_then(|metadata| metadata.modified())
+      .unwrap_or(std::time::UNIX_EPOCH);
+    if !refresh {
+      if let Ok(entries) = self.entries.lock() {
+        if let Some((measured_modified, usage)) = entries.get(dir) {
+          if *measured_modified == modified {
+            return usage.clone();
+          }
+        }
+      }
+    }
+    let (bytes, files) = measure_version_dir(dir);
+    let usage = VersionDiskUsage {
+      version_folder: version_folder.clone(),
+      version,
+      bytes,
+      files,
+      measured_at: chrono::Local::now().to_rfc3339(),
+    };
+    if let Ok(mut entries) = self.entries.lock() {
+      entries.insert(dir.to_path_buf(), (modified, usage.clone()));
+    }
+    usage
+  }
+}
+
+/// How much space every downloaded version takes up, or just those of `version_folder`.  Sizes
+/// are remembered between calls, `refresh` measures everything again
+#[tauri::command]
+#[specta::specta]
+pub async fn get_version_disk_usage(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  cache: tauri::State<'_, VersionDiskUsageCache>,
+  version_folder: Option<VersionFolder>,
+  refresh: Option<bool>,
+) -> Result<Vec<VersionDiskUsage>, CommandError> {
+  let config_lock = config.lock().await;
+  let folders: Vec<(VersionFolder, PathBuf)> = config_lock
+    .version_sources()
+    .into_iter()
+    .map(|source| source.folder)
+    .filter(|folder| {
+      version_folder
+        .as_ref()
+        .map_or(true, |wanted| wanted == folder)
+    })
+    .filter_map(|folder| {
+      let path = config_lock.version_folder_path(folder.as_str())?;
+      Some((folder, path))
+    })
+    .collect();
+  drop(config_lock);
+
+  let refresh = refresh.unwrap_or(false);
+  let mut usage = vec![];
+  for (folder, folder_path) in folders {
+    let entries = match std::fs::read_dir(&folder_path) {
+      Ok(entries) => entries,
+      Err(_) => continue,
+    };
+    for entry in entries
+      .filter_map(|entry| entry.ok())
+      .filter(is_version_dir)
+    {
+      let version = entry.file_name().to_string_lossy().into_owned();
+      usage.push(cache.get_or_measure(&folder, version, &entry.path(), refresh));
+    }
+  }
+  usage.sort_by_key(|b| std::cmp::Reverse(b.bytes));
+  Ok(usage)
+}
+
+// Every downloaded version's folder, in all version folders or only those that can be written to
+// Staging folders of repairs and partial downloads sit next to the versions, starting with a `.`
+fn is_version_dir(entry: &std::fs::DirEntry) -> bool {
+  !entry.file_name().to_string_lossy().starts_with('.') && entry.path().is_dir()
+}
+
+fn downloaded_version_dirs(config: &LauncherConfig, writable_only: bool) -> Vec<PathBuf> {
+  config
+    .version_sources()
+    .into_iter()
+    .filter(|source| {
+      !writable_only || ensure_version_folder_writable(config, source.folder.as_str()).is_ok()
+    })
+    .filter_map(|source| config.version_folder_path(source.folder.as_str()))
+    .flat_map(|folder_path| {
+      std::fs::read_dir(folder_path)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(is_version_dir)
+        .map(|entry| entry.path())
+        .collect::<Vec<_>>()
+    })
+    .collect()
+}
+
+async fn deduplicate_in_background(
+  version_dirs: Vec<PathBuf>,
+  other_dirs: Vec<PathBuf>,
+) -> Result<DedupSavings, CommandError> {
+  tokio::task::spawn_blocking(move || {
+    let mut savings = DedupSavings::default();
+    for version_dir in version_dirs {
+      let others: Vec<PathBuf> = other_dirs
+        .iter()
+        .filter(|dir| **dir != version_dir)
+        .cloned()
+        .collect();
+      let saved = deduplicate_version(&version_dir, &others, &[VERSION_METADATA_FILE]);
+      savings.files += saved.files;
+      savings.bytes += saved.bytes;
+    }
+    savings
+  })
+  .await
+  .map_err(|_| CommandError::VersionManagement("Unable to deduplicate versions".to_string()))
+}
+
+// Runs after a version is extracted if `dedupe_versions` is on, not being able to is no reason
+// for the download to fail
+async fn dedupe_new_version(config: &tokio::sync::Mutex<LauncherConfig>, version_dir: &Path) {
+  let config_lock = config.lock().await;
+  if !config_lock.dedupe_versions.unwrap_or(false) {
+    return;
+  }
+  let other_dirs = downloaded_version_dirs(&config_lock, false);
+  drop(config_lock);
+  match deduplicate_in_background(vec![version_dir.to_path_buf()], other_dirs).await {
+    Ok(savings) => log::info!(
+      "Linked {} files ({} bytes) of {} to other versions",
+      savings.files,
+      savings.bytes,
+      version_dir.display()
+    ),
+    Err(err) => log::warn!("Unable to deduplicate {}: {}", version_dir.display(), err),
+  }
+}
+
+/// Replaces the files every downloaded version shares with another one by hardlinks, whether or
+/// not `dedupe_versions` is on.  Returns what this pass saved, on top of what already was
+#[tauri::command]
+#[specta::specta]
+pub async fn deduplicate_versions(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<DedupSavings, CommandError> {
+  let config_lock = config.lock().await;
+  // Read-only shared versions can still be linked to
+  let version_dirs = downloaded_version_dirs(&config_lock, true);
+  let other_dirs = downloaded_version_dirs(&config_lock, false);
+  drop(config_lock);
+  let savings = deduplicate_in_background(version_dirs, other_dirs).await?;
+  log::info!(
+    "Deduplicated versions, linked {} files ({} bytes)",
+    savings.files,
+    savings.bytes
+  );
+  Ok(savings)
+}
+
+/// How much space is currently saved by downloaded versions sharing files
+#[tauri::command]
+#[specta::specta]
+pub async fn get_version_dedup_savings(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<DedupSavings, CommandError> {
+  let version_dirs = downloaded_version_dirs(&*config.lock().await, false);
+  tokio::task::spawn_blocking(move || measure_savings(&version_dirs))
+    .await
+    .map_err(|_| CommandError::VersionManagement("Unable to measure deduplication".to_string()))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn go_to_version_folder(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  version_folder: VersionFolder,
+) -> Result<(), CommandError> {
+  let config_lock = config.lock().await;
+  let folder_path = match config_lock.version_folder_path(version_folder.as_str()) {
+    None => {
+      return Err(CommandError::VersionManagement(
+        "Cannot go to version folder, no tooling directory set".to_string(),
+      ))
+    }
+    Some(path) => path,
+  };
+  create_dir(&folder_path).map_err(|_| {
+    CommandError::VersionManagement(format!(
+      "Unable to go to create version folder '{}' in order to open it",
+      folder_path.display()
+    ))
+  })?;
+
+  open_dir_in_os(folder_path.to_string_lossy().into_owned())
+    .map_err(|_| CommandError::VersionManagement(format!("Unable to open folder in OS")))?;
+  Ok(())
+}
+
+// Versions in the machine-wide location belong to every user, only someone allowed to write there
+// can add or remove them
+fn ensure_version_folder_writable(
+  config: &LauncherConfig,
+  version_folder: &str,
+) -> Result<(), CommandError> {
+  if !config.is_version_folder_shared(version_folder) {
+    return Ok(());
+  }
+  let shared_path = shared_versions_path().ok_or_else(|| {
+    CommandError::VersionManagement("There is no shared versions location".to_string())
+  })?;
+  if is_dir_writable(&shared_path) {
+    return Ok(());
+  }
+  Err(CommandError::VersionManagement(format!(
+    "Shared versions in '{}' are read-only for this user, ask whoever manages this PC to add or remove them",
+    shared_path.display()
+  )))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SharedVersionsStatus {
+  pub enabled: bool,
+  pub path: Option<String>,
+  pub exists: bool,
+  /// Whether this user can add and remove shared versions, or only use them
+  pub writable: bool,
+}
+
+fn shared_versions_status(config: &LauncherConfig) -> SharedVersionsStatus {
+  let path = shared_versions_path();
+  SharedVersionsStatus {
+    enabled: config.shared_versions_enabled.unwrap_or(false),
+    exists: path.as_ref().map_or(false, |path| path.is_dir()),
+    writable: path.as_ref().map_or(false, |path| is_dir_writable(path)),
+    path: path.map(|path| path.to_string_lossy().into_owned()),
+  }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_shared_versions_status(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<SharedVersionsStatus, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(shared_versions_status(&config_lock))
+}
+
+// Moves this user's versions into the machine-wide location, skipping the ones it already has
+fn adopt_versions_into_shared(
+  app_handle: &tauri::AppHandle,
+  task: &TaskHandle,
+  tooling_path: &Path,
+  shared_path: &Path,
+) -> Result<(), CommandError> {
+  for version_folder in SHAREABLE_VERSION_FOLDERS {
+    let entries = match std::fs::read_dir(paths::version_folder_dir(tooling_path, version_folder)) {
+      Ok(entries) => entries,
+      Err(_) => continue,
+    };
+    for entry in entries.filter_map(|entry| entry.ok()) {
+      // Only version directories, not the links or leftovers of an interrupted download
+      let is_version = entry
+        .file_type()
+        .map_or(false, |file_type| file_type.is_dir())
+        && !entry.file_name().to_string_lossy().starts_with('.');
+      let shared_dir = shared_path.join(version_folder).join(entry.file_name());
+      if !is_version || shared_dir.exists() {
+        continue;
+      }
+      info!(
+        "Adopting {} into the shared versions",
+        entry.path().display()
+      );
+      move_dir_for_task(
+        app_handle,
+        task,
+        &entry.path(),
+        &shared_dir,
+        CopyVerification::Hash,
+      )
+      .map_err(|err| {
+        CommandError::VersionManagement(format!(
+          "Unable to move {} into the shared versions: {}",
+          entry.file_name().to_string_lossy(),
+          err
+        ))
+      })?;
+    }
+  }
+  Ok(())
+}
+
+/// Uses the machine-wide location for official, unofficial and development versions instead of
+/// this user's tooling directory.  Versions already downloaded stay where they are, unless
+/// `adopt_existing` is set, then they're moved over for every user to have
+#[tauri::command]
+#[specta::specta]
+pub async fn set_shared_versions_enabled(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  task_manager: tauri::State<'_, TaskManager>,
+  app_handle: tauri::AppHandle,
+  enabled: bool,
+  adopt_existing: Option<bool>,
+) -> Result<SharedVersionsStatus, CommandError> {
+  let mut config_lock = config.lock().await;
+  if enabled {
+    let shared_path = shared_versions_path().ok_or_else(|| {
+      CommandError::VersionManagement(
+        "There is no shared versions location on this system".to_string(),
+      )
+    })?;
+    // Creating it usually takes an administrator, but it may be allowed
+    if !shared_path.is_dir() && std::fs::create_dir_all(&shared_path).is_err() {
+      return Err(CommandError::VersionManagement(format!(
+        "'{}' doesn't exist and can't be created by this user, an administrator has to create it first",
+        shared_path.display()
+      )));
+    }
+  }
+  config_lock
+    .set_shared_versions_enabled(enabled)
+    .map_err(|_| {
+      CommandError::Configuration("Unable to persist shared versions setting".to_string())
+    })?;
+  let shared_path = shared_versions_path().filter(|_| enabled && adopt_existing.unwrap_or(false));
+  if let (Some(shared_path), Some(tooling_path)) = (shared_path, config_lock.tooling_path()) {
+    if !is_dir_writable(&shared_path) {
+      return Err(CommandError::VersionManagement(format!(
+        "'{}' can't be written to by this user, versions can only be used from there",
+        shared_path.display()
+      )));
+    }
+    let task = match task_manager.start_or_attach(
+      &app_handle,
+      "adopt-shared-versions".to_string(),
+      "Moving versions",
+    ) {
+      Ok(task) => task,
+      Err(_) => {
+        return Err(CommandError::VersionManagement(
+          "Versions are already being moved into the shared location".to_string(),
+        ))
+      }
+    };
+    // The versions are moved by the task, the settings aren't needed until it's done
+    drop(config_lock);
+    let (mut task, result) = tokio::task::spawn_blocking({
+      let app_handle = app_handle.clone();
+      move || {
+        let result = adopt_versions_into_shared(&app_handle, &task, &tooling_path, &shared_path);
+        (task, result)
+      }
+    })
+    .await
+    .map_err(|_| {
+      CommandError::VersionManagement(
+        "Unable to move versions into the shared location".to_string(),
+      )
+    })?;
+    task.finish(
+      result.is_ok(),
+      result.as_ref().err().map(|err| err.to_string()),
+    );
+    result?;
+    config_lock = config.lock().await;
+  }
+  update_active_version_link(&config_lock);
+  Ok(shared_versions_status(&config_lock))
+}
+
+/// Version folders that are kept somewhere other than the tooling directory
+#[tauri::command]
+#[specta::specta]
+pub async fn get_version_folder_dirs(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<HashMap<VersionFolder, String>, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(config_lock.version_folder_dirs.clone().unwrap_or_default())
+}
+
+fn validate_version_folder_dir(dir: &Option<String>) -> Result<(), CommandError> {
+  if let Some(dir) = dir {
+    if !Path::new(dir).is_absolute() {
+      return Err(CommandError::VersionManagement(format!(
+        "'{}' is not an absolute path",
+        dir
+      )));
+    }
+  }
+  Ok(())
+}
+
+/// Points a version folder at another directory (or back at the tooling directory with `None`),
+/// without moving anything, ie. for a directory that already holds its versions
+#[tauri::command]
+#[specta::specta]
+pub async fn set_version_folder_dir(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  version_folder: VersionFolder,
+  dir: Option<String>,
+) -> Result<(), CommandError> {
+  validate_version_folder_dir(&dir)?;
+  let mut config_lock = config.lock().await;
+  config_lock
+    .set_version_folder_dir(version_folder, dir)
+    .map_err(|_| {
+      CommandError::Configuration("Unable to persist version folder location".to_string())
+    })?;
+  update_active_version_link(&config_lock);
+  Ok(())
+}
+
+/// Moves a version folder's versions to `dir` (or back into the tooling directory with `None`),
+/// and keeps it there from then on
+#[tauri::command]
+#[specta::specta]
+pub async fn migrate_version_folder(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  task_manager: tauri::State<'_, TaskManager>,
+  app_handle: tauri::AppHandle,
+  version_folder: VersionFolder,
+  dir: Option<String>,
+) -> Result<(), CommandError> {
+  validate_version_folder_dir(&dir)?;
+  let download_prefix = format!("download-{}-", version_folder);
+  if task_manager
+    .running()
+    .iter()
+    .any(|task| task.id.starts_with(&download_prefix))
+  {
+    return Err(CommandError::VersionManagement(format!(
+      "A version is being downloaded into '{}', wait for it to finish before moving it",
+      version_folder
+    )));
+  }
+  let mut config_lock = config.lock().await;
+  // Moving them would take them away from every other user
+  if config_lock.is_version_folder_shared(version_folder.as_str()) {
+    return Err(CommandError::VersionManagement(format!(
+      "'{}' versions are shared with the other users, they can't be moved",
+      version_folder
+    )));
+  }
+  let default_path = config_lock
+    .tooling_path()
+    .map(|path| paths::version_folder_dir(&path, version_folder.as_str()));
+  let (current_path, new_path) = match (
+    config_lock.version_folder_path(version_folder.as_str()),
+    dir.as_ref().map(PathBuf::from).or(default_path),
+  ) {
+    (Some(current_path), Some(new_path)) => (current_path, new_path),
+    (_, _) => {
+      return Err(CommandError::VersionManagement(
+        "Cannot move version folder, no tooling directory set".to_string(),
+      ))
+    }
+  };
+  if current_path != new_path && current_path.is_dir() {
+    // Moving a folder into itself ends with removing what was just copied
+    if paths_overlap(&current_path, &new_path) {
+      return Err(CommandError::VersionManagement(format!(
+        "'{}' and '{}' are inside one another, pick a folder outside of the current one",
+        new_path.display(),
+        current_path.display()
+      )));
+    }
+    let new_path_in_use =
+      std::fs::read_dir(&new_path).map_or(false, |mut entries| entries.next().is_some());
+    if new_path_in_use {
+      return Err(CommandError::VersionManagement(format!(
+        "'{}' is not empty, pick an empty or new folder",
+        new_path.display()
+      )));
+    }
+    info!(
+      "Moving version folder {} from {} to {}",
+      version_folder,
+      current_path.display(),
+      new_path.display()
+    );
+    let task = match task_manager.start_or_attach(
+      &app_handle,
+      format!("migrate-versions-{}", version_folder),
+      "Moving versions",
+    ) {
+      Ok(task) => task,
+      Err(_) => {
+        return Err(CommandError::VersionManagement(format!(
+          "'{}' versions are already being moved",
+          version_folder
+        )))
+      }
+    };
+    // Downloads into the folder are refused while the task runs, the settings aren't needed
+    drop(config_lock);
+    // A broken binary only shows up at launch, so copies across drives are checked in full
+    let (mut task, result) = tokio::task::spawn_blocking({
+      let app_handle = app_handle.clone();
+      let new_path = new_path.clone();
+      move || {
+        let result = move_dir_for_task(
+          &app_handle,
+          &task,
+          &current_path,
+          &new_path,
+          CopyVerification::Hash,
+        );
+        (task, result)
+      }
+    })
+    .await
+    .map_err(|_| {
+      CommandError::VersionManagement(format!(
+        "Unable to move versions to '{}'",
+        new_path.display()
+      ))
+    })?;
+    config_lock = config.lock().await;
+    if let Err(err) = result {
+      task.finish(false, Some(err.to_string()));
+      return Err(CommandError::VersionManagement(format!(
+        "Unable to move versions to '{}': {}",
+        new_path.display(),
+        err
+      )));
+    }
+    task.finish(true, None);
+  }
+  config_lock
+    .set_version_folder_dir(version_folder, dir)
+    .map_err(|_| {
+      CommandError::Configuration("Unable to persist version folder location".to_string())
+    })?;
+  update_active_version_link(&config_lock);
+  Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn ensure_active_version_still_exists(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<bool, CommandError> {
+  let mut config_lock = config.lock().await;
+  if config_lock.tooling_path().is_none() {
+    return Err(CommandError::VersionManagement(
+      "Cannot install version, no tooling directory set".to_string(),
+    ));
+  }
+
+  info!(
+    "Checking if active version still exists {:?}:{:?}",
+    config_lock.active_version_folder, config_lock.active_version
+  );
+
+  match (
+    &config_lock.active_version_folder,
+    &config_lock.active_version,
+  ) {
+    (Some(config_version_folder), Some(config_version)) => {
+      let version_dir = config_lock
+        .version_folder_path(config_version_folder)
+        .map(|path| path.join(config_version))
+        .unwrap_or_default();
+      if !version_dir.exists() {
+        // Clear active version if it's no longer available
+        config_lock.clear_active_version().map_err(|_| {
+          CommandError::VersionManagement(format!(
+            "Unable to clear active version after it was found to be missing"
+          ))
+        })?;
+        update_active_version_link(&config_lock);
+      }
+      Ok(version_dir.exists())
+    }
+    (_, _) => Ok(false),
+  }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_active_tooling_build_info(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<Option<VersionMetadata>, CommandError> {
+  let config_lock = config.lock().await;
+  let version_dir = match (
+    &config_lock.active_version_folder,
+    &config_lock.active_version,
+  ) {
+    (Some(config_version_folder), Some(config_version)) => config_lock
+      .version_folder_path(config_version_folder)
+      .map(|path| path.join(config_version)),
+    (_, _) => None,
+  };
+  match version_dir {
+    Some(version_dir) => {
+      if !version_dir.exists() {
+        return Ok(None);
+      }
+      // Versions downloaded before metadata was recorded get stamped on demand
+      if !VersionMetadata::exists(&version_dir) {
+        return Ok(Some(stamp_version_metadata(&version_dir)));
+      }
+      Ok(Some(VersionMetadata::load(&version_dir)))
+    }
+    None => Ok(None),
+  }
+}
+
+fn validate_version_source(source: &VersionSource) -> Result<(), CommandError> {
+  if source.display_name.trim().is_empty() {
+    return Err(CommandError::VersionManagement(
+      "Version source needs a display name".to_string(),
+    ));
+  }
+  // Only the project's own releases are official, whatever a source claims
+  if source.trust_level == TrustLevel::Official && source.folder.as_str() != "official" {
+    return Err(CommandError::VersionManagement(format!(
+      "Only the built-in official source can have the official trust level, '{}' can be trusted instead",
+      source.folder
+    )));
+  }
+  if let Some(template) = &source.download_url_template {
+    if !template.contains("{version}") {
+      return Err(CommandError::VersionManagement(
+        "The download URL template has to contain {version}".to_string(),
+      ));
+    }
+  }
+  // Binaries are downloaded from these, so don't accept anything that isn't https
+  let urls = [&source.remote, &source.download_url_template];
+  for url in urls.iter().filter_map(|url| url.as_ref()) {
+    if !url.starts_with("https://") {
+      return Err(CommandError::VersionManagement(format!(
+        "'{}' is not an https URL",
+        url
+      )));
+    }
+  }
+  Ok(())
+}
+
+fn save_version_sources(
+  config: &mut LauncherConfig,
+  sources: Vec<VersionSource>,
+) -> Result<(), CommandError> {
+  config
+    .set_version_sources(sources)
+    .map_err(|_| CommandError::Configuration("Unable to persist version sources".to_string()))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn list_version_sources(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<Vec<VersionSource>, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(config_lock.version_sources())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn add_version_source(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  source: VersionSource,
+) -> Result<(), CommandError> {
+  validate_version_source(&source)?;
+  let mut config_lock = config.lock().await;
+  let mut sources = config_lock.version_sources();
+  if sources
+    .iter()
+    .any(|existing| existing.folder == source.folder)
+  {
+    return Err(CommandError::VersionManagement(format!(
+      "A version source for the '{}' folder already exists",
+      source.folder
+    )));
+  }
+  sources.push(source);
+  save_version_sources(&mut config_lock, sources)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn update_version_source(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  source: VersionSource,
+) -> Result<(), CommandError> {
+  validate_version_source(&source)?;
+  let mut config_lock = config.lock().await;
+  let mut sources = config_lock.version_sources();
+  match sources
+    .iter_mut()
+    .find(|existing| existing.folder == source.folder)
+  {
+    Some(existing) => *existing = source,
+    None => {
+      return Err(CommandError::VersionManagement(format!(
+        "There is no version source for the '{}' folder",
+        source.folder
+      )))
+    }
+  }
+  save_version_sources(&mut config_lock, sources)
+}
+
+/// Removes the source, versions already downloaded into its folder are left alone
+#[tauri::command]
+#[specta::specta]
+pub async fn remove_version_source(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  version_folder: VersionFolder,
+) -> Result<(), CommandError> {
+  let mut config_lock = config.lock().await;
+  let mut sources = config_lock.version_sources();
+  let source = match sources
+    .iter()
+    .find(|source| source.folder == version_folder)
+  {
+    Some(source) => source,
+    None => return Ok(()),
+  };
+  if source.is_builtin() {
+    return Err(CommandError::VersionManagement(format!(
+      "The '{}' version source can't be removed",
+      version_folder
+    )));
+  }
+  sources.retain(|source| source.folder != version_folder);
+  save_version_sources(&mut config_lock, sources)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_version_download_url(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  remote_config: tauri::State<'_, tokio::sync::Mutex<RemoteConfig>>,
+  version_folder: VersionFolder,
+  version: VersionName,
+) -> Result<Option<String>, CommandError> {
+  resolve_download_url(&config, &remote_config, &version_folder, &version).await
+}
+
+/// Where to download `version` of a source from: its download URL template if it has one,
+/// otherwise the asset for this platform in its GitHub repository's release tagged `version`
+async fn resolve_download_url(
+  config: &tokio::sync::Mutex<LauncherConfig>,
+  remote_config: &tokio::sync::Mutex<RemoteConfig>,
+  version_folder: &VersionFolder,
+  version: &VersionName,
+) -> Result<Option<String>, CommandError> {
+  let remote_templates = remote_config.lock().await.download_url_templates.clone();
+  let config_lock = config.lock().await;
+  let source = config_lock
+    .version_sources()
+    .into_iter()
+    .find(|source| &source.folder == version_folder);
+  let github = github_client(&config_lock).map_err(CommandError::VersionManagement)?;
+  drop(config_lock);
+  let source = match source {
+    Some(source) => source,
+    None => return Ok(None),
+  };
+  if let Some(url) = source.download_url(version, &remote_templates) {
+    return Ok(Some(url));
+  }
+  match &source.remote {
+    Some(remote) if github_repo(remote).is_some() => {
+      github_release_asset_url(&github, remote, version.as_str())
+        .await
+        .map_err(|err| {
+          CommandError::VersionManagement(format!(
+            "Unable to look up {} in {}: {}",
+            version, source.display_name, err
+          ))
+        })
+    }
+    _ => Ok(None),
+  }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct PublishedRelease {
+  tag_name: String,
+  html_url: String,
+  published_at: Option<String>,
+  #[serde(default)]
+  prerelease: bool,
+  #[serde(default)]
+  assets: Vec<PublishedReleaseAsset>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct PublishedReleaseAsset {
+  name: String,
+  browser_download_url: String,
+}
+
+impl PublishedRelease {
+  fn channel(&self) -> ReleaseChannel {
+    ReleaseChannel::of_release(&self.tag_name, self.prerelease)
+  }
+
+  // By version, falling back to when they were published for tags that aren't one (ie. nightlies)
+  fn cmp_newness(&self, other: &PublishedRelease) -> std::cmp::Ordering {
+    match (
+      parse_version(&self.tag_name),
+      parse_version(&other.tag_name),
+    ) {
+      (Some(a), Some(b)) => a.cmp(&b),
+      _ => self.published_at.cmp(&other.published_at),
+    }
+  }
+}
+
+// The official source, and the GitHub repository its releases are published in
+fn official_github_source(
+  config: &LauncherConfig,
+) -> Result<(VersionSource, (String, String)), CommandError> {
+  github_source(config, "official")
+}
+
+fn github_source(
+  config: &LauncherConfig,
+  version_folder: &str,
+) -> Result<(VersionSource, (String, String)), CommandError> {
+  let source = match config
+    .version_sources()
+    .into_iter()
+    .find(|source| source.folder.as_str() == version_folder)
+  {
+    Some(source) => source,
+    None => {
+      return Err(CommandError::VersionManagement(format!(
+        "There is no '{}' version source to check",
+        version_folder
+      )))
+    }
+  };
+  match source.remote.as_deref().and_then(github_repo) {
+    Some(repo) => Ok((source, repo)),
+    None => Err(CommandError::VersionManagement(format!(
+      "{} versions aren't published on GitHub, they can't be checked",
+      source.display_name
+    ))),
+  }
+}
+
+fn github_error(err: GithubError) -> CommandError {
+  match err {
+    GithubError::NetworkRequest(err) => CommandError::NetworkRequest(err),
+    GithubError::RateLimited(_) => CommandError::RateLimited(err.to_string()),
+    GithubError::InvalidToken => CommandError::Configuration(err.to_string()),
+  }
+}
+
+const RELEASES_PER_PAGE: usize = 100;
+// Every page is a request against the rate limit, releases older than this are left out
+const MAX_RELEASE_PAGES: usize = 10;
+
+// GitHub lists pre-releases (and nightlies) alongside stable releases, newest first
+async fn fetch_published_releases(
+  github: &GithubClient,
+  owner: &str,
+  repo: &str,
+) -> Result<Vec<PublishedRelease>, CommandError> {
+  let mut releases = vec![];
+  for page in 1..=MAX_RELEASE_PAGES {
+    let listed: Vec<PublishedRelease> = github
+      .get_json(&format!(
+        "https://api.github.com/repos/{}/{}/releases?per_page={}&page={}",
+        owner, repo, RELEASES_PER_PAGE, page
+      ))
+      .await
+      .map_err(github_error)?;
+    let last_page = listed.len() < RELEASES_PER_PAGE;
+    releases.extend(listed);
+    if last_page {
+      break;
+    }
+  }
+  Ok(releases)
+}
+
+/// Official releases on `channel` (the configured one if not given) and the channels before it,
+/// labeled with the channel they're on.  Every listing is cached, and the last one is used when
+/// GitHub can't be reached
+#[tauri::command]
+#[specta::specta]
+pub async fn list_channel_releases(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  channel: Option<ReleaseChannel>,
+) -> Result<Vec<CachedRelease>, CommandError> {
+  let config_lock = config.lock().await;
+  let channel = channel.unwrap_or_else(|| config_lock.release_channel());
+  let (_, (owner, repo)) = official_github_source(&config_lock)?;
+  let github = github_client(&config_lock).map_err(CommandError::VersionManagement)?;
+  drop(config_lock);
+
+  let releases = match fetch_published_releases(&github, &owner, &repo).await {
+    Ok(published) => {
+      let releases: Vec<CachedRelease> = published
+        .into_iter()
+        .map(|release| CachedRelease {
+          channel: release.channel(),
+          tag: release.tag_name,
+          published_at: release.published_at,
+          github_link: Some(release.html_url),
+          assets: release
+            .assets
+            .into_iter()
+            .map(|asset| ReleaseAsset {
+              name: asset.name,
+              download_url: asset.browser_download_url,
+            })
+            .collect(),
+        })
+        .collect();
+      if let Err(err) = write_release_cache(&*config.lock().await, releases.clone()) {
+        log::warn!("Unable to cache releases: {}", err);
+      }
+      releases
+    }
+    Err(err) => {
+      log::warn!("Unable to list releases, using the cached listing: {}", err);
+      match read_release_cache(&*config.lock().await) {
+        Some(cache) => cache.releases,
+        None => return Err(err),
+      }
+    }
+  };
+  Ok(
+    releases
+      .into_iter()
+      .filter(|release| release.channel <= channel)
+      .collect(),
+  )
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct NewToolingVersion {
+  /// The version folder the release is published for, and downloaded into
+  pub version_folder: String,
+  pub tag: String,
+  pub published_at: Option<String>,
+  pub changelog_url: String,
+  /// The release's archive for this platform, `None` if it has none
+  pub asset_url: Option<String>,
+}
+
+// The latest release from where the active version came from (official releases if nothing is
+// active yet), if it's newer than the active version.  Someone on a fork stays on that fork.  An
+// active version that isn't a release (ie. a local build) is never considered outdated
+async fn newer_release(
+  config: &tokio::sync::Mutex<LauncherConfig>,
+  remote_config: &tokio::sync::Mutex<RemoteConfig>,
+) -> Result<Option<NewToolingVersion>, CommandError> {
+  let remote_config = remote_config.lock().await.clone();
+  let mut config_lock = config.lock().await;
+  let active_folder = match &config_lock.active_version_folder {
+    Some(folder) => folder.clone(),
+    None => "official".to_string(),
+  };
+  let (source, (owner, repo)) = github_source(&config_lock, &active_folder)?;
+  let channel = config_lock.release_channel();
+  let active_version = config_lock.active_version.clone();
+  let rollout_id = config_lock
+    .rollout_id()
+    .map_err(|_| CommandError::Configuration("Unable to persist rollout id".to_string()))?;
+  let github = github_client(&config_lock).map_err(CommandError::VersionManagement)?;
+  drop(config_lock);
+
+  // GitHub's latest release is never a pre-release, other channels have to look through them all
+  let (release, listed): (PublishedRelease, Vec<PublishedRelease>) =
+    if channel == ReleaseChannel::Stable {
+      let latest = github
+        .get_json(&format!(
+          "https://api.github.com/repos/{}/{}/releases/latest",
+          owner, repo
+        ))
+        .await
+        .map_err(github_error)?;
+      (latest, vec![])
+    } else {
+      let listed = fetch_published_releases(&github, &owner, &repo).await?;
+      let newest = listed
+        .iter()
+        .filter(|release| release.channel() <= channel)
+        .filter(|release| remote_config.is_release_rolled_out(&release.tag_name, &rollout_id))
+        .max_by(|a, b| a.cmp_newness(b))
+        .cloned();
+      match newest {
+        Some(release) => (release, listed),
+        None => return Ok(None),
+      }
+    };
+
+  let is_newer = match active_version.as_deref() {
+    // Nothing is active yet, anything is new
+    None => true,
+    Some(active) => match (parse_version(&release.tag_name), parse_version(active)) {
+      (Some(latest), Some(active)) => latest > active,
+      // Without versions to compare, it's newer if it was published after the active one
+      _ => {
+        release.tag_name != active
+          && listed
+            .iter()
+            .find(|listed| listed.tag_name == active)
+            .map_or(false, |active| release.cmp_newness(active).is_gt())
+      }
+    },
+  };
+  if !is_newer {
+    return Ok(None);
+  }
+  if !remote_config.is_release_rolled_out(&release.tag_name, &rollout_id) {
+    info!(
+      "{} is being rolled out in stages and isn't offered to this install yet",
+      release.tag_name
+    );
+    return Ok(None);
+  }
+  // Tags end up in paths once downloaded, one that isn't a valid version name has no asset to offer
+  let version = match VersionName::from_str(&release.tag_name) {
+    Ok(version) => version,
+    Err(_) => return Ok(None),
+  };
+  // Sources without a download URL template (ie. most forks) are matched by the asset's name
+  let (platform, ext) = release_platform();
+  let asset_url = match source.download_url(&version, &remote_config.download_url_templates) {
+    Some(url) => release
+      .assets
+      .iter()
+      .find(|asset| asset.browser_download_url == url),
+    None => release.assets.iter().find(|asset| {
+      let name = asset.name.to_lowercase();
+      name.contains(platform) && name.ends_with(&format!(".{}", ext))
+    }),
+  }
+  .map(|asset| asset.browser_download_url.clone());
+  Ok(Some(NewToolingVersion {
+    version_folder: source.folder.to_string(),
+    tag: release.tag_name,
+    published_at: release.published_at,
+    changelog_url: release.html_url,
+    asset_url,
+  }))
+}
+
+/// Asks GitHub for the latest release on the configured channel from the active version's
+/// source, and returns it (emitting `newVersionAvailable`) if it's newer than the active version
+#[tauri::command]
+#[specta::specta]
+pub async fn check_for_new_tooling_version(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  remote_config: tauri::State<'_, tokio::sync::Mutex<RemoteConfig>>,
+  app_handle: tauri::AppHandle,
+) -> Result<Option<NewToolingVersion>, CommandError> {
+  let new_version = match newer_release(&config, &remote_config).await? {
+    Some(new_version) => new_version,
+    None => return Ok(None),
+  };
+  info!("New tooling version available: {}", new_version.tag);
+  app_handle.emit_all("newVersionAvailable", &new_version)?;
+  Ok(Some(new_version))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ToolingUpdateStage {
+  Checking,
+  /// The download's own progress is in the `versionDownloadProgress` events
+  Downloading,
+  Verifying,
+  Switched,
+  UpToDate,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolingUpdateProgress {
+  pub stage: ToolingUpdateStage,
+  pub version: Option<String>,
+}
+
+fn emit_update_progress(
+  app_handle: &tauri::AppHandle,
+  stage: ToolingUpdateStage,
+  version: Option<&str>,
+) {
+  let progress = ToolingUpdateProgress {
+    stage,
+    version: version.map(str::to_string),
+  };
+  if let Err(err) = app_handle.emit_all("toolingUpdateProgress", progress) {
+    log::warn!("Unable to emit tooling update progress: {}", err);
+  }
+}
+
+/// Downloads the latest release if it's newer than the active version, and makes it the active
+/// version once its binaries are confirmed to run.  Returns the version that was switched
+/// to, `None` if the active version is already the latest
+#[tauri::command]
+#[specta::specta]
+pub async fn update_tooling_to_latest(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  remote_config: tauri::State<'_, tokio::sync::Mutex<RemoteConfig>>,
+  task_manager: tauri::State<'_, TaskManager>,
+  app_handle: tauri::AppHandle,
+) -> Result<Option<NewToolingVersion>, CommandError> {
+  update_tooling(config, remote_config, task_manager, app_handle, false).await
+}
+
+// `background` is whether the launcher is updating on its own, in which case the download keeps
+// to quiet hours
+async fn update_tooling(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  remote_config: tauri::State<'_, tokio::sync::Mutex<RemoteConfig>>,
+  task_manager: tauri::State<'_, TaskManager>,
+  app_handle: tauri::AppHandle,
+  background: bool,
+) -> Result<Option<NewToolingVersion>, CommandError> {
+  emit_update_progress(&app_handle, ToolingUpdateStage::Checking, None);
+  let new_version = match newer_release(&config, &remote_config).await? {
+    Some(new_version) => new_version,
+    None => {
+      emit_update_progress(&app_handle, ToolingUpdateStage::UpToDate, None);
+      return Ok(None);
+    }
+  };
+  let version = VersionName::from_str(&new_version.tag).map_err(CommandError::VersionManagement)?;
+  // Updates stay within the folder the release came from, a fork is never swapped for official
+  let version_folder = VersionFolder::from_str(&new_version.version_folder)
+    .map_err(CommandError::VersionManagement)?;
+  let url = match &new_version.asset_url {
+    Some(url) => url.clone(),
+    None => {
+      return Err(CommandError::VersionManagement(format!(
+        "Version {} has no download for this platform",
+        new_version.tag
+      )))
+    }
+  };
+
+  // It may have been downloaded already without being switched to
+  let version_dir = config
+    .lock()
+    .await
+    .version_folder_path(version_folder.as_str())
+    .map(|path| path.join(&version));
+  let already_downloaded = version_dir
+    .as_ref()
+    .map_or(false, |dir| dir.join(bin_ext("extractor")).exists());
+  if !already_downloaded {
+    emit_update_progress(
+      &app_handle,
+      ToolingUpdateStage::Downloading,
+      Some(&new_version.tag),
+    );
+    let download = download_version(
+      config.clone(),
+      remote_config,
+      task_manager,
+      app_handle.clone(),
+      version.clone(),
+      version_folder.clone(),
+      Some(url),
+      None,
+      None,
+      None,
+    );
+    if background {
+      let job_id = format!("download-{}-{}", version_folder, version);
+      schedule::run_background_download(&app_handle, &job_id, download).await?;
+    } else {
+      download.await?;
+    }
+  }
+
+  emit_update_progress(
+    &app_handle,
+    ToolingUpdateStage::Verifying,
+    Some(&new_version.tag),
+  );
+  let version_dir = match version_dir {
+    Some(dir) => dir,
+    None => {
+      return Err(CommandError::VersionManagement(
+        "Cannot install version, no tooling directory set".to_string(),
+      ))
+    }
+  };
+  if config
+    .lock()
+    .await
+    .is_version_folder_shared(version_folder.as_str())
+  {
+    check_shared_version(&version_dir).map_err(|reason| {
+      CommandError::VersionManagement(format!(
+        "Version {} is shared with the other users and can't be trusted to run: {}",
+        new_version.tag, reason
+      ))
+    })?;
+  }
+  for binary in ["extractor", "gk", "goalc"] {
+    if read_binary_build_info(&version_dir, binary).is_none() {
+      return Err(CommandError::VersionManagement(format!(
+        "Version {} was downloaded, but its {} doesn't run, staying on the current version",
+        new_version.tag, binary
+      )));
+    }
+  }
+
+  // Through the same prepare and commit as a manual switch, so the installed games are recompiled
+  // and nothing is swapped while one is running
+  version_switch::prepare(&app_handle, version_folder, version).await?;
+  version_switch::commit(&app_handle).await?;
+  info!("Updated tooling to {}", new_version.tag);
+  emit_update_progress(
+    &app_handle,
+    ToolingUpdateStage::Switched,
+    Some(&new_version.tag),
+  );
+  Ok(Some(new_version))
+}
+
+/// Runs `update_tooling_to_latest` on startup, for those who opted into it
+pub async fn auto_update_tooling(app_handle: tauri::AppHandle) {
+  schedule::wait_for_quiet_hours(&app_handle, "the automatic tooling update").await;
+  let result = update_tooling(
+    app_handle.state(),
+    app_handle.state(),
+    app_handle.state(),
+    app_handle.clone(),
+    true,
+  )
+  .await;
+  if let Err(err) = result {
+    log::warn!("Unable to update tooling automatically: {}", err);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::util::test_dir::TestDir;
+
+  fn partial_file(test_dir: &TestDir, name: &str) -> PathBuf {
+    test_dir.file(name, b"partial")
+  }
+
+  fn version_dir(test_dir: &TestDir, name: &str) -> PathBuf {
+    test_dir.file(&format!("{}/nested/extractor", name), b"");
+    test_dir.path().join(name)
+  }
+
+  #[test]
+  fn failed_download_removes_everything_but_the_download() {
+    let test_dir = TestDir::new("failed-download");
+    let (file, download, dir) = (
+      partial_file(&test_dir, "v1.0.0.zip.progress"),
+      partial_file(&test_dir, "v1.0.0.zip"),
+      version_dir(&test_dir, "v1.0.0"),
+    );
+    let mut artifacts = DownloadArtifacts::new();
+    artifacts.track_file(&file);
+    artifacts.track_download(&download);
+    artifacts.track_dir(&dir);
+    drop(artifacts);
+    assert!(!file.exists());
+    assert!(!dir.exists());
+    // Kept for the next attempt to resume
+    assert!(download.exists());
+  }
+
+  #[test]
+  fn successful_download_keeps_only_the_dirs() {
+    let test_dir = TestDir::new("successful-download");
+    let (file, download, dir) = (
+      partial_file(&test_dir, "v1.0.0.zip.progress"),
+      partial_file(&test_dir, "v1.0.0.zip"),
+      version_dir(&test_dir, "v1.0.0"),
+    );
+    let validator = partial_file(&test_dir, "v1.0.0.zip.validator");
+    let mut artifacts = DownloadArtifacts::new();
+    artifacts.track_file(&file);
+    artifacts.track_download(&download);
+    artifacts.track_dir(&dir);
+    artifacts.succeeded();
+    drop(artifacts);
+    assert!(!file.exists());
+    assert!(!download.exists());
+    assert!(!validator.exists());
+    assert!(dir.join("nested").join("extractor").exists());
+  }
+
+  #[test]
+  fn missing_artifacts_are_not_an_error() {
+    let test_dir = TestDir::new("missing-artifacts");
+    let mut artifacts = DownloadArtifacts::new();
+    artifacts.track_file(&test_dir.path().join("never-created.zip"));
+    artifacts.track_download(&test_dir.path().join("never-downloaded.zip"));
+    artifacts.track_dir(&test_dir.path().join("never-extracted"));
+    drop(artifacts);
+    assert!(test_dir.path().exists());
+  }
+
+  #[test]
+  fn partial_downloads_are_kept_next_to_the_version() {
+    let folder = Path::new("versions").join("official");
+    let (download_path, extract_dir) = partial_download_paths(&folder, "v1.0.0");
+    assert_eq!(download_path.parent(), Some(folder.as_path()));
+    assert_eq!(extract_dir, folder.join(".v1.0.0.partial"));
+  }
+
+  #[test]
+  fn staging_dirs_are_not_versions() {
+    let test_dir = TestDir::new("staging-dirs");
+    version_dir(&test_dir, "v1.0.0");
+    version_dir(&test_dir, ".v1.0.0.repair");
+    version_dir(&test_dir, ".v1.1.0.partial");
+    partial_file(&test_dir, "v1.1.0.zip");
+    assert_eq!(
+      versions_newest_first(test_dir.path()),
+      vec!["v1.0.0".to_string()]
+    );
+  }
+
+  fn release(tag: &str, published_at: &str) -> PublishedRelease {
+    PublishedRelease {
+      tag_name: tag.to_string(),
+      html_url: String::new(),
+      published_at: Some(published_at.to_string()),
+      prerelease: false,
+      assets: vec![],
+    }
+  }
+
+  #[test]
+  fn releases_without_a_version_are_ordered_by_when_they_were_published() {
+    let backport = release("v0.1.40", "2026-03-01T00:00:00Z");
+    let stable = release("v0.2.0", "2026-02-01T00:00:00Z");
+    let nightly = release("nightly-20260401", "2026-04-01T00:00:00Z");
+    assert!(stable.cmp_newness(&backport).is_gt());
+    assert!(nightly.cmp_newness(&stable).is_gt());
+    assert!(nightly.cmp_newness(&backport).is_gt());
   }
 }
@@ -1,10 +1,14 @@
 use std::path::Path;
 
+use serde::{Deserialize, Serialize};
 use tauri::Manager;
 
-use super::CommandError;
+use crate::config::{GameExitAction, LaunchAction};
+
+use super::{binaries::RunningGames, CommandError};
 
 #[tauri::command]
+#[specta::specta]
 pub async fn open_main_window(handle: tauri::AppHandle) -> Result<(), CommandError> {
   // NOTE:
   // When you create multiple static windows (inside the conf file)
@@ -28,7 +32,7 @@ pub async fn open_main_window(handle: tauri::AppHandle) -> Result<(), CommandErr
   //   "decorations": false
   // },
   log::info!("Creating main window");
-  tauri::WindowBuilder::new(
+  let main_window = tauri::WindowBuilder::new(
     &handle,
     "main", /* the unique window label */
     tauri::WindowUrl::App("index.html".parse().unwrap()),
@@ -43,6 +47,13 @@ pub async fn open_main_window(handle: tauri::AppHandle) -> Result<(), CommandErr
   .focused(true)
   .build()
   .map_err(|_| CommandError::WindowManagement(format!("Unable to create main launcher window")))?;
+  // When started by the OS on login, stay out of the way
+  if std::env::args().any(|arg| arg == crate::util::os::START_MINIMIZED_ARG) {
+    log::info!("Started minimized, minimizing main window");
+    main_window
+      .minimize()
+      .map_err(|_| CommandError::WindowManagement("Unable to minimize main window".to_string()))?;
+  }
   log::info!("Closing splash window");
   // Close splashscreen
   if let Some(splashscreen) = handle.app_handle().get_window("splashscreen") {
@@ -54,6 +65,7 @@ pub async fn open_main_window(handle: tauri::AppHandle) -> Result<(), CommandErr
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn open_dir_in_os(directory: String) -> Result<(), CommandError> {
   let folder_path = Path::new(&directory);
 
@@ -68,3 +80,113 @@ pub async fn open_dir_in_os(directory: String) -> Result<(), CommandError> {
     .map_err(|_| CommandError::OSOperation(format!("Unable to go to open folder in OS")))?;
   Ok(())
 }
+
+#[derive(Debug, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct MonitorInfo {
+  pub index: usize,
+  pub name: Option<String>,
+  pub width: u32,
+  pub height: u32,
+  pub x: i32,
+  pub y: i32,
+  pub scale_factor: f64,
+  pub is_primary: bool,
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_monitors(window: tauri::Window) -> Result<Vec<MonitorInfo>, CommandError> {
+  let primary_name = window
+    .primary_monitor()
+    .ok()
+    .flatten()
+    .and_then(|monitor| monitor.name().cloned());
+  let monitors = window
+    .available_monitors()
+    .map_err(|_| CommandError::WindowManagement("Unable to list monitors".to_string()))?;
+  Ok(
+    monitors
+      .iter()
+      .enumerate()
+      .map(|(index, monitor)| MonitorInfo {
+        index,
+        name: monitor.name().cloned(),
+        width: monitor.size().width,
+        height: monitor.size().height,
+        x: monitor.position().x,
+        y: monitor.position().y,
+        scale_factor: monitor.scale_factor(),
+        is_primary: primary_name.is_some() && monitor.name().cloned() == primary_name,
+      })
+      .collect(),
+  )
+}
+
+/// Gets the launcher out of the way once a game has been launched
+pub fn apply_launch_action(app_handle: &tauri::AppHandle, action: LaunchAction) {
+  let main_window = match app_handle.get_window("main") {
+    Some(window) => window,
+    None => return,
+  };
+  let result = match action {
+    LaunchAction::KeepOpen => Ok(()),
+    LaunchAction::Minimize => main_window.minimize(),
+    // Exiting now would take the game's supervision with it, see `exit_after_game`
+    LaunchAction::Exit => {
+      log::info!("Game launched, the launcher exits once the game does");
+      main_window.hide()
+    }
+  };
+  if let Err(err) = result {
+    log::warn!("Unable to apply launch action {:?}: {}", action, err);
+  }
+}
+
+/// Brings the launcher back after the game exits, unless it's about to exit
+pub fn apply_exit_action(
+  app_handle: &tauri::AppHandle,
+  launch_action: LaunchAction,
+  exit_action: GameExitAction,
+) {
+  let main_window = match app_handle.get_window("main") {
+    Some(window) => window,
+    None => return,
+  };
+  if exit_action == GameExitAction::Nothing || launch_action == LaunchAction::Exit {
+    return;
+  }
+  let result = main_window
+    .show()
+    .and_then(|_| main_window.unminimize())
+    .and_then(|_| main_window.set_focus());
+  if let Err(err) = result {
+    log::warn!("Unable to reopen the launcher: {}", err);
+  }
+}
+
+/// Exits the launcher once the game it was closed for is done, and the session is recorded.  If
+/// another game is still running it's kept around for that one, and if the game crashed right
+/// away it's brought back to show why
+pub fn exit_after_game(
+  app_handle: &tauri::AppHandle,
+  launch_action: LaunchAction,
+  crashed_early: bool,
+) {
+  if launch_action != LaunchAction::Exit {
+    return;
+  }
+  if crashed_early {
+    apply_exit_action(
+      app_handle,
+      LaunchAction::KeepOpen,
+      GameExitAction::ReopenLauncher,
+    );
+    return;
+  }
+  if !app_handle.state::<RunningGames>().list().is_empty() {
+    return;
+  }
+  log::info!("Game exited, exiting the launcher");
+  app_handle.exit(0);
+}
@@ -16,7 +16,10 @@ use std::str::FromStr;
 
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-use crate::util::file::touch_file;
+use crate::paths;
+use crate::permissions::{PermissionGrant, PermissionKind};
+use crate::util::crypto::random_hex;
+use crate::util::file::{delete_file, touch_file};
 
 #[derive(Debug, thiserror::Error)]
 pub enum ConfigError {
@@ -28,7 +31,7 @@ pub enum ConfigError {
   Configuration(String),
 }
 
-#[derive(Debug, Eq, PartialEq, Hash)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum SupportedGame {
   Jak1,
   Jak2,
@@ -37,7 +40,7 @@ pub enum SupportedGame {
 }
 
 impl SupportedGame {
-  fn internal_str(&self) -> &'static str {
+  pub fn internal_str(&self) -> &'static str {
     match self {
       SupportedGame::Jak1 => "jak1",
       SupportedGame::Jak2 => "jak2",
@@ -94,12 +97,507 @@ impl Serialize for SupportedGame {
   }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+// Version folders (ie. "official") and version names (ie. "v0.1.35") come from the frontend
+// and are joined into paths, so they are validated as soon as they are deserialized to
+// ensure they can only ever refer to a single directory inside the versions folder
+fn validate_version_path_component(kind: &str, value: &str) -> Result<(), String> {
+  if value.is_empty() {
+    return Err(format!("{} cannot be empty", kind));
+  }
+  if value.len() > 128 {
+    return Err(format!("{} '{}' is too long", kind, value));
+  }
+  if value.contains("..") {
+    return Err(format!("{} '{}' cannot contain '..'", kind, value));
+  }
+  // `.` is the directory itself, and anything else starting with one is hidden and skipped as a
+  // leftover when listing versions
+  if value.starts_with('.') {
+    return Err(format!("{} '{}' cannot start with '.'", kind, value));
+  }
+  // Windows drops trailing dots from names, so `v0.1.` would be the same folder as `v0.1`
+  if value.ends_with('.') {
+    return Err(format!("{} '{}' cannot end with '.'", kind, value));
+  }
+  // These are devices rather than files on Windows, with any extension
+  let stem = value
+    .split('.')
+    .next()
+    .unwrap_or(value)
+    .to_ascii_uppercase();
+  let is_reserved = ["CON", "PRN", "AUX", "NUL"].contains(&stem.as_str())
+    || ((stem.starts_with("COM") || stem.starts_with("LPT"))
+      && stem.len() == 4
+      && matches!(stem.as_bytes()[3], b'1'..=b'9'));
+  if is_reserved {
+    return Err(format!("{} '{}' is a reserved name", kind, value));
+  }
+  if !value
+    .chars()
+    .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_')
+  {
+    return Err(format!(
+      "{} '{}' contains invalid characters, only letters, digits, '.', '-' and '_' are allowed",
+      kind, value
+    ));
+  }
+  Ok(())
+}
+
+macro_rules! version_path_component {
+  ($name:ident, $kind:literal) => {
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    pub struct $name(String);
+
+    impl $name {
+      pub fn as_str(&self) -> &str {
+        &self.0
+      }
+    }
+
+    impl FromStr for $name {
+      type Err = String;
+
+      fn from_str(s: &str) -> Result<Self, Self::Err> {
+        validate_version_path_component($kind, s)?;
+        Ok(Self(s.to_string()))
+      }
+    }
+
+    impl std::fmt::Display for $name {
+      fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+      }
+    }
+
+    impl AsRef<Path> for $name {
+      fn as_ref(&self) -> &Path {
+        Path::new(&self.0)
+      }
+    }
+
+    impl<'de> Deserialize<'de> for $name {
+      fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+      where
+        D: Deserializer<'de>,
+      {
+        let s = String::deserialize(deserializer)?;
+        $name::from_str(&s).map_err(serde::de::Error::custom)
+      }
+    }
+
+    impl Serialize for $name {
+      fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+      where
+        S: Serializer,
+      {
+        serializer.serialize_str(&self.0)
+      }
+    }
+
+    // Sent to and from the frontend as a plain string
+    impl specta::Type for $name {
+      fn inline(
+        opts: specta::DefOpts,
+        generics: &[specta::DataType],
+      ) -> Result<specta::DataType, specta::ExportError> {
+        <String as specta::Type>::inline(opts, generics)
+      }
+    }
+  };
+}
+
+version_path_component!(VersionFolder, "Version folder");
+version_path_component!(VersionName, "Version");
+
+/// How much a version source's binaries are trusted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum TrustLevel {
+  /// Released by the project
+  Official,
+  /// Added by the user and vouched for, ie. a fork they follow
+  Trusted,
+  Untrusted,
+}
+
+/// How releases name this platform and the archive format they use for it
+pub fn release_platform() -> (&'static str, &'static str) {
+  if cfg!(windows) {
+    ("windows", "zip")
+  } else if cfg!(target_os = "macos") {
+    ("macos", "tar.gz")
+  } else {
+    ("linux", "tar.gz")
+  }
+}
+
+/// A folder of tooling versions, and where its versions come from
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionSource {
+  pub folder: VersionFolder,
+  pub display_name: String,
+  /// The repository (or other page) versions are published on, if any
+  pub remote: Option<String>,
+  pub trust_level: TrustLevel,
+  /// Where to download a version from, `{version}`, `{platform}` and `{ext}` are substituted
+  pub download_url_template: Option<String>,
+}
+
+impl VersionSource {
+  fn builtin(
+    folder: &str,
+    display_name: &str,
+    remote: Option<&str>,
+    trust_level: TrustLevel,
+    download_url_template: Option<&str>,
+  ) -> Self {
+    Self {
+      folder: VersionFolder(folder.to_string()),
+      display_name: display_name.to_string(),
+      remote: remote.map(|remote| remote.to_string()),
+      trust_level,
+      download_url_template: download_url_template.map(|template| template.to_string()),
+    }
+  }
+
+  /// The sources every launcher has, these can be edited but not removed
+  pub fn defaults() -> Vec<Self> {
+    vec![
+      Self::builtin(
+        "official",
+        "Official",
+        Some("https://github.com/open-goal/jak-project"),
+        TrustLevel::Official,
+        Some("https://github.com/open-goal/jak-project/releases/download/{version}/opengoal-{platform}-{version}.{ext}"),
+      ),
+      Self::builtin("unofficial", "Unofficial", None, TrustLevel::Untrusted, None),
+      Self::builtin("devel", "Development", None, TrustLevel::Trusted, None),
+      Self::builtin("local", "Local builds", None, TrustLevel::Trusted, None),
+    ]
+  }
+
+  pub fn is_builtin(&self) -> bool {
+    Self::defaults()
+      .iter()
+      .any(|source| source.folder == self.folder)
+  }
+
+  /// The template downloads go through.  Remote config can replace a built-in source's template
+  /// (ie. when upstream renames its assets), unless the template was changed by the user
+  fn effective_download_url_template<'a>(
+    &'a self,
+    remote_templates: &'a HashMap<String, String>,
+  ) -> Option<&'a str> {
+    let is_default_template = Self::defaults().iter().any(|source| {
+      source.folder == self.folder && source.download_url_template == self.download_url_template
+    });
+    match remote_templates.get(self.folder.as_str()) {
+      Some(template) if is_default_template => Some(template),
+      _ => self.download_url_template.as_deref(),
+    }
+  }
+
+  pub fn download_url(
+    &self,
+    version: &VersionName,
+    remote_templates: &HashMap<String, String>,
+  ) -> Option<String> {
+    let (platform, ext) = release_platform();
+    self
+      .effective_download_url_template(remote_templates)
+      .map(|template| {
+        template
+          .replace("{version}", version.as_str())
+          .replace("{platform}", platform)
+          .replace("{ext}", ext)
+      })
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum DisplayMode {
+  Windowed,
+  Fullscreen,
+  Borderless,
+}
+
+impl DisplayMode {
+  /// The symbol the game uses for this mode in its `pc-settings.gc`
+  pub fn game_setting_value(&self) -> &'static str {
+    match self {
+      DisplayMode::Windowed => "windowed",
+      DisplayMode::Fullscreen => "fullscreen",
+      DisplayMode::Borderless => "borderless",
+    }
+  }
+}
+
+/// What to do about large downloads when the connection is metered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum MeteredBehavior {
+  /// Download as usual
+  Ignore,
+  /// Ask the user before downloading
+  Confirm,
+  /// Wait until the connection is no longer metered
+  Pause,
+}
+
+/// What's kept of a play session once the game exits, see `sessions`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum SessionCapture {
+  Off,
+  Summary,
+  /// The summary, along with the last screenshot taken during the session
+  SummaryWithScreenshot,
+}
+
+impl Default for SessionCapture {
+  fn default() -> Self {
+    SessionCapture::Off
+  }
+}
+
+/// Which releases to offer, each channel includes the ones before it
+#[derive(
+  Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, specta::Type,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum ReleaseChannel {
+  Stable,
+  /// Releases marked as pre-releases on GitHub
+  PreRelease,
+  /// Automated builds, tagged as nightlies
+  Nightly,
+}
+
+impl Default for ReleaseChannel {
+  fn default() -> Self {
+    ReleaseChannel::Stable
+  }
+}
+
+impl ReleaseChannel {
+  pub fn of_release(tag: &str, prerelease: bool) -> Self {
+    if tag.to_lowercase().contains("nightly") {
+      ReleaseChannel::Nightly
+    } else if prerelease {
+      ReleaseChannel::PreRelease
+    } else {
+      ReleaseChannel::Stable
+    }
+  }
+}
+
+/// What background downloads do during quiet hours
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum QuietHoursBehavior {
+  /// Hold them until quiet hours are over
+  Pause,
+  /// Keep them under `bandwidth_cap_kbps`
+  CapBandwidth,
+}
+
+/// A time of day during which the launcher keeps its background network activity (update checks,
+/// automatic updates, remote config) down, see `schedule`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct QuietHours {
+  /// `HH:MM` in local time, quiet hours that end earlier than they start go past midnight
+  pub start: String,
+  pub end: String,
+  pub behavior: QuietHoursBehavior,
+  /// Kilobytes per second, only used to cap bandwidth
+  pub bandwidth_cap_kbps: Option<u32>,
+}
+
+/// How often a failed install step that may have failed for a transient reason (ie. a dropped
+/// connection, a file briefly locked by an antivirus) is retried before giving up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallRetryPolicy {
+  /// Retries after the first attempt, `0` disables retrying
+  pub retries: u32,
+  /// The wait before the first retry, doubled for every retry after it
+  pub backoff_secs: u32,
+}
+
+impl Default for InstallRetryPolicy {
+  fn default() -> Self {
+    Self {
+      retries: 2,
+      backoff_secs: 2,
+    }
+  }
+}
+
+/// A step run once a game has finished installing, in the order they're configured in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum PostInstallStep {
+  /// Writes the launcher's display and video preferences into the game's settings
+  ApplyDefaultSettings,
+  /// Copies `<install dir>/subtitle_packs/<game>/<pack>` over the game's data
+  InstallSubtitlePack { pack: String },
+  /// Puts a shortcut to the launcher on the desktop
+  CreateShortcut,
+  /// Checks that the extracted and compiled game data is all there
+  RunVerification,
+}
+
+impl PostInstallStep {
+  /// The steps run when none have been configured
+  pub fn defaults() -> Vec<PostInstallStep> {
+    vec![
+      PostInstallStep::ApplyDefaultSettings,
+      PostInstallStep::RunVerification,
+    ]
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ProcessPriority {
+  Normal,
+  AboveNormal,
+  High,
+}
+
+/// What the launcher window does once a game has been launched
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum LaunchAction {
+  KeepOpen,
+  // There's no tray icon to bring a hidden window back from, earlier settings minimize instead
+  #[serde(alias = "minimizeToTray")]
+  Minimize,
+  /// Close the launcher's window, it exits once the game does so the session is still recorded
+  Exit,
+}
+
+/// What the launcher does once the game exits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum GameExitAction {
+  Nothing,
+  ReopenLauncher,
+  /// Reopen the launcher and show a summary of the session
+  ShowSummary,
+}
+
+/// How many connections large downloads are split over, unless configured otherwise
+pub const DEFAULT_DOWNLOAD_CONNECTIONS: u32 = 4;
+pub const MAX_DOWNLOAD_CONNECTIONS: u32 = 16;
+/// How many versions of each version folder pruning leaves behind, unless configured otherwise
+pub const DEFAULT_VERSIONS_TO_KEEP: u32 = 3;
+/// Where the community hosts texture packs and mods, content from anywhere else has to be
+/// confirmed by the user.  Subdomains of these are allowed as well
+pub const DEFAULT_CONTENT_HOSTS: &[&str] = &[
+  "github.com",
+  "githubusercontent.com",
+  "gitlab.com",
+  "codeberg.org",
+  "gamebanana.com",
+];
+
+/// The version folders that can be kept in the machine-wide location, local builds stay per-user
+pub const SHAREABLE_VERSION_FOLDERS: &[&str] = &["official", "unofficial", "devel"];
+
+/// Where versions shared by every user on the machine are kept
+pub fn shared_versions_path() -> Option<PathBuf> {
+  if cfg!(windows) {
+    std::env::var_os("ProgramData").map(|dir| {
+      PathBuf::from(dir)
+        .join("OpenGOAL-Launcher")
+        .join("versions")
+    })
+  } else if cfg!(target_os = "macos") {
+    Some(PathBuf::from("/Users/Shared/OpenGOAL-Launcher/versions"))
+  } else {
+    Some(PathBuf::from("/usr/local/share/opengoal-launcher/versions"))
+  }
+}
+
+/// Whether a version in the shared location can be trusted to run.  Other users can write to the
+/// shared location, so everything in the version has to belong to an administrator or this user,
+/// and mustn't be writable by anyone else.
+///
+/// On windows `ProgramData` only lets users change what they created themselves, which isn't
+/// checked here
+#[cfg(unix)]
+pub fn check_shared_version(version_dir: &Path) -> Result<(), String> {
+  use std::os::unix::fs::MetadataExt;
+
+  // SAFETY: getuid can't fail and has no side effects
+  let uid = unsafe { libc::getuid() };
+  for entry in walkdir::WalkDir::new(version_dir) {
+    let entry = entry.map_err(|err| err.to_string())?;
+    let metadata = entry
+      .path()
+      .symlink_metadata()
+      .map_err(|err| err.to_string())?;
+    if metadata.uid() != 0 && metadata.uid() != uid {
+      return Err(format!(
+        "'{}' belongs to another user",
+        entry.path().display()
+      ));
+    }
+    if !metadata.file_type().is_symlink() && metadata.mode() & 0o022 != 0 {
+      return Err(format!(
+        "'{}' can be changed by other users",
+        entry.path().display()
+      ));
+    }
+  }
+  Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn check_shared_version(_version_dir: &Path) -> Result<(), String> {
+  Ok(())
+}
+
+/// The install steps, in the order they run
+#[derive(
+  Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, specta::Type,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum InstallStage {
+  Extracted,
+  Decompiled,
+  Compiled,
+}
+
+/// The last install step that finished for a game that isn't installed yet, so an installation
+/// that was interrupted can pick up from there
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallCheckpoint {
+  pub stage: InstallStage,
+  /// The tooling the step was run with, later steps have to use the same one
+  pub version: Option<String>,
+  pub version_folder: Option<String>,
+  pub reached_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GameConfig {
   pub is_installed: bool,
   pub version: Option<String>,
   pub version_folder: Option<String>,
+  pub launch_action: Option<LaunchAction>,
+  pub exit_action: Option<GameExitAction>,
+  pub install_checkpoint: Option<InstallCheckpoint>,
+  /// Whether the extracted ISO data is kept after installing, for faster recompiles.  Kept
+  /// unless set otherwise
+  pub keep_iso_data: Option<bool>,
 }
 
 impl GameConfig {
@@ -108,11 +606,15 @@ impl GameConfig {
       is_installed: false,
       version: None,
       version_folder: None,
+      launch_action: None,
+      exit_action: None,
+      install_checkpoint: None,
+      keep_iso_data: None,
     }
   }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Requirements {
   pub bypass_requirements: Option<bool>,
@@ -131,7 +633,60 @@ impl Requirements {
   }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// What a tool gets on top of the environment the launcher curates for it
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolEnvironment {
+  /// Set as given, replacing the curated value if there is one
+  pub vars: HashMap<String, String>,
+  /// Put in front of the curated PATH
+  pub path: Vec<String>,
+  /// Names of the launcher's own variables to pass through as they are
+  pub inherit: Vec<String>,
+}
+
+/// Settings (as they're named in `settings.json`) that are secrets, and left out of anything the
+/// launcher writes for the user to keep or share
+pub const SECRET_SETTINGS: &[&str] = &["githubToken", "kioskPinHash", "remoteApiToken"];
+/// Settings that only mean something on this machine: where things are, the install's own id and
+/// locked mode's state (how often the PIN was guessed wrong and the daily limit)
+pub const MACHINE_SETTINGS: &[&str] = &[
+  "gameDataDir",
+  "installationDir",
+  "kioskDailyLimitMinutes",
+  "kioskFailedAttempts",
+  "kioskRetryAt",
+  "rolloutId",
+  "toolingDir",
+  "versionFolderDirs",
+];
+
+/// Replaces each of `keys` that's set in `settings` (the contents of `settings.json`) with
+/// `<redacted>`
+pub fn redact_settings(settings: &mut serde_json::Value, keys: &[&str]) {
+  for key in keys {
+    if let Some(value) = settings.get_mut(*key).filter(|value| !value.is_null()) {
+      *value = serde_json::Value::from("<redacted>");
+    }
+  }
+}
+
+/// Sets each of `keys` in `settings` to what it is in `current`, it's removed if it isn't set
+/// there
+pub fn keep_settings(settings: &mut serde_json::Value, current: &serde_json::Value, keys: &[&str]) {
+  let settings = match settings.as_object_mut() {
+    Some(settings) => settings,
+    None => return,
+  };
+  for key in keys {
+    match current.get(*key) {
+      Some(value) => settings.insert(key.to_string(), value.clone()),
+      None => settings.remove(*key),
+    };
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LauncherConfig {
   #[serde(skip_serializing)]
@@ -144,15 +699,73 @@ pub struct LauncherConfig {
   pub games: HashMap<SupportedGame, GameConfig>,
   pub last_active_game: Option<SupportedGame>,
   pub installation_dir: Option<String>,
+  pub tooling_dir: Option<String>,
+  pub game_data_dir: Option<String>,
   pub active_version: Option<String>,
   pub active_version_folder: Option<String>,
   pub locale: Option<String>,
+  pub launch_on_startup: Option<bool>,
+  pub launch_minimized: Option<bool>,
+  pub game_process_priority: Option<ProcessPriority>,
+  pub game_cpu_affinity: Option<u64>,
+  pub game_display_mode: Option<DisplayMode>,
+  pub game_monitor: Option<u32>,
+  pub game_video_playback: Option<bool>,
+  pub game_video_hardware_decoding: Option<bool>,
+  pub launcher_log_retention_days: Option<u32>,
+  pub install_log_retention_days: Option<u32>,
+  pub crash_report_retention_days: Option<u32>,
+  pub maintain_active_version_link: Option<bool>,
+  pub metered_behavior: Option<MeteredBehavior>,
+  pub release_channel: Option<ReleaseChannel>,
+  pub session_capture: Option<SessionCapture>,
+  pub content_hosts: Option<Vec<String>>,
+  pub install_retry_policy: Option<InstallRetryPolicy>,
+  pub post_install_steps: Option<Vec<PostInstallStep>>,
+  pub kiosk_pin_hash: Option<String>,
+  /// Wrong PINs in a row, kept so restarting the launcher doesn't allow more guesses
+  pub kiosk_failed_attempts: Option<u32>,
+  /// When the PIN can be tried again, in seconds since the epoch
+  pub kiosk_retry_at: Option<u64>,
+  /// How long games can be played each day while the launcher is locked, no limit if unset
+  pub kiosk_daily_limit_minutes: Option<u32>,
+  pub remote_api_enabled: Option<bool>,
+  pub remote_api_port: Option<u16>,
+  pub remote_api_token: Option<String>,
+  pub snapshot_hotkey: Option<String>,
+  pub version_sources: Option<Vec<VersionSource>>,
+  pub sync_folder: Option<String>,
+  pub permissions: Option<HashMap<PermissionKind, PermissionGrant>>,
+  pub version_folder_dirs: Option<HashMap<VersionFolder, String>>,
+  pub download_connections: Option<u32>,
+  pub tool_environments: Option<HashMap<String, ToolEnvironment>>,
+  pub shared_versions_enabled: Option<bool>,
+  pub auto_update_tooling: Option<bool>,
+  pub versions_to_keep: Option<u32>,
+  pub rollout_id: Option<String>,
+  /// What was active before the current version, for going back to it
+  pub previous_active_version_folder: Option<String>,
+  pub previous_active_version: Option<String>,
+  /// Whether the game has been launched with the active version without crashing right away,
+  /// `None` for versions switched to before this was tracked
+  pub active_version_proven: Option<bool>,
+  /// Whether downloaded versions have the files they share with other versions replaced by
+  /// hardlinks, off unless set
+  pub dedupe_versions: Option<bool>,
+  /// A personal access token for GitHub's API, for a higher rate limit than anonymous requests
+  /// get.  Left out of support packages
+  pub github_token: Option<String>,
+  pub quiet_hours: Option<QuietHours>,
 }
 
 fn default_version() -> Option<String> {
   Some("1.0".to_string())
 }
 
+fn current_version() -> Option<String> {
+  Some("1.1".to_string())
+}
+
 impl LauncherConfig {
   fn default(_settings_path: Option<PathBuf>) -> Self {
     let mut default_games = HashMap::new();
@@ -162,14 +775,58 @@ impl LauncherConfig {
     default_games.insert(SupportedGame::JakX, GameConfig::default());
     Self {
       settings_path: _settings_path,
-      version: default_version(),
+      version: current_version(),
       requirements: Requirements::default(),
       games: default_games,
       last_active_game: None,
       installation_dir: None,
+      tooling_dir: None,
+      game_data_dir: None,
       active_version: None,
       active_version_folder: Some("official".to_string()),
       locale: None,
+      launch_on_startup: None,
+      launch_minimized: None,
+      game_process_priority: None,
+      game_cpu_affinity: None,
+      game_display_mode: None,
+      game_monitor: None,
+      game_video_playback: None,
+      game_video_hardware_decoding: None,
+      launcher_log_retention_days: None,
+      install_log_retention_days: None,
+      crash_report_retention_days: None,
+      maintain_active_version_link: None,
+      metered_behavior: None,
+      release_channel: None,
+      session_capture: None,
+      content_hosts: None,
+      install_retry_policy: None,
+      post_install_steps: None,
+      kiosk_pin_hash: None,
+      kiosk_failed_attempts: None,
+      kiosk_retry_at: None,
+      kiosk_daily_limit_minutes: None,
+      remote_api_enabled: None,
+      remote_api_port: None,
+      remote_api_token: None,
+      snapshot_hotkey: None,
+      version_sources: None,
+      sync_folder: None,
+      permissions: None,
+      version_folder_dirs: None,
+      download_connections: None,
+      tool_environments: None,
+      shared_versions_enabled: None,
+      auto_update_tooling: None,
+      versions_to_keep: None,
+      rollout_id: None,
+      previous_active_version_folder: None,
+      previous_active_version: None,
+      active_version_proven: None,
+      dedupe_versions: None,
+      github_token: None,
+      quiet_hours: None,
     }
   }
 
@@ -199,6 +856,7 @@ impl LauncherConfig {
               config.version.as_ref().unwrap()
             );
             config.settings_path = Some(settings_path.to_path_buf());
+            config.migrate();
             return config;
           }
           Err(err) => {
@@ -217,6 +875,75 @@ impl LauncherConfig {
     }
   }
 
+  // Settings files prior to 1.1 only had a single `installation_dir` that held both
+  // the tooling versions and the game data, carry that over to the split directories
+  fn migrate(&mut self) {
+    if self.version == default_version() {
+      log::info!("Migrating settings file from version 1.0 to 1.1");
+      if self.tooling_dir.is_none() {
+        self.tooling_dir = self.installation_dir.clone();
+      }
+      if self.game_data_dir.is_none() {
+        self.game_data_dir = self.installation_dir.clone();
+      }
+      self.version = current_version();
+      if let Err(err) = self.save_config() {
+        log::error!("Unable to persist migrated settings file: {}", err);
+      }
+    }
+  }
+
+  /// The directory that holds the downloaded tooling versions (`<dir>/versions`)
+  pub fn tooling_path(&self) -> Option<PathBuf> {
+    self
+      .tooling_dir
+      .as_ref()
+      .or(self.installation_dir.as_ref())
+      .map(PathBuf::from)
+  }
+
+  /// The directory that holds the per-game data (`<dir>/active`)
+  pub fn game_data_path(&self) -> Option<PathBuf> {
+    self
+      .game_data_dir
+      .as_ref()
+      .or(self.installation_dir.as_ref())
+      .map(PathBuf::from)
+  }
+
+  /// Where a version folder's versions are kept, `<tooling>/versions/<folder>` unless it was
+  /// mapped to a directory of its own, or is kept in the machine-wide location
+  pub fn version_folder_path(&self, version_folder: &str) -> Option<PathBuf> {
+    let mapped = self.version_folder_dirs.as_ref().and_then(|dirs| {
+      dirs
+        .iter()
+        .find(|(folder, _)| folder.as_str() == version_folder)
+        .map(|(_, dir)| PathBuf::from(dir))
+    });
+    mapped
+      .or_else(|| {
+        self
+          .is_version_folder_shared(version_folder)
+          .then(shared_versions_path)
+          .flatten()
+          .map(|path| path.join(version_folder))
+      })
+      .or_else(|| {
+        self
+          .tooling_path()
+          .map(|path| paths::version_folder_dir(&path, version_folder))
+      })
+  }
+
+  /// Whether the folder's versions come from the machine-wide location, a mapped folder isn't
+  pub fn is_version_folder_shared(&self, version_folder: &str) -> bool {
+    self.shared_versions_enabled.unwrap_or(false)
+      && SHAREABLE_VERSION_FOLDERS.contains(&version_folder)
+      && !self.version_folder_dirs.as_ref().map_or(false, |dirs| {
+        dirs.keys().any(|folder| folder.as_str() == version_folder)
+      })
+  }
+
   pub fn save_config(&self) -> Result<(), ConfigError> {
     let settings_path = match &self.settings_path {
       None => {
@@ -229,71 +956,223 @@ impl LauncherConfig {
     };
     let file = fs::File::create(settings_path)?;
     serde_json::to_writer_pretty(file, &self)?;
+    // The settings are saved either way, the sync folder catches up the next time
+    if let Err(err) = crate::config_sync::push(self) {
+      log::warn!("Unable to sync settings: {}", err);
+    }
+    Ok(())
+  }
+
+  /// Where what was last synced with the sync folder is kept, see `config_sync`
+  pub fn sync_state_path(&self) -> Option<PathBuf> {
+    Some(
+      self
+        .settings_path
+        .as_ref()?
+        .with_file_name("sync-state.json"),
+    )
+  }
+
+  /// Replaces the settings with the ones in `json`, which is in the settings file's format
+  pub fn update_from_json(&mut self, json: serde_json::Value) -> Result<(), ConfigError> {
+    let mut config: LauncherConfig = serde_json::from_value(json)?;
+    config.settings_path = self.settings_path.clone();
+    *self = config;
     Ok(())
   }
 
   pub fn reset_to_defaults(&mut self) -> Result<(), ConfigError> {
     let original_installation_dir = self.installation_dir.clone();
+    let original_tooling_dir = self.tooling_dir.clone();
+    let original_game_data_dir = self.game_data_dir.clone();
     *self = Self::default(self.settings_path.clone());
     self.installation_dir = original_installation_dir;
+    self.tooling_dir = original_tooling_dir;
+    self.game_data_dir = original_game_data_dir;
     Self::save_config(self)?;
     Ok(())
   }
 
-  pub fn set_install_directory(&mut self, new_dir: String) -> Result<Option<String>, ConfigError> {
-    // Do some tests on this folder, if they fail, return a decent error
-    let path = Path::new(&new_dir);
+  /// Goes back to the defaults without saving, for when the settings file has been removed
+  pub fn discard(&mut self) {
+    *self = Self::default(self.settings_path.clone());
+  }
+
+  // Do some tests on this folder, if they fail, return a decent error
+  fn validate_directory(new_dir: &String) -> Option<String> {
+    let path = Path::new(new_dir);
     if !path.exists() {
-      return Ok(Some("Provided folder does not exist".to_owned()));
+      return Some("Provided folder does not exist".to_owned());
     }
 
     if !path.is_dir() {
-      return Ok(Some("Provided folder is not a folder".to_owned()));
+      return Some("Provided folder is not a folder".to_owned());
     }
 
     // Check our permissions on the folder by touching a file (and deleting it)
     let test_file = path.join(".perm-test.tmp");
     match touch_file(&test_file) {
       Err(e) => {
-        log::error!(
-          "Provided installation folder could not be written to: {}",
-          e
-        );
-        return Ok(Some("Provided folder cannot be written to".to_owned()));
+        log::error!("Provided folder could not be written to: {}", e);
+        return Some("Provided folder cannot be written to".to_owned());
+      }
+      _ => {
+        let _ = std::fs::remove_file(&test_file);
       }
-      _ => (),
+    }
+    None
+  }
+
+  fn uninstall_all_games(&mut self) -> Result<(), ConfigError> {
+    self.update_installed_game_version(&SupportedGame::Jak1.internal_str().to_string(), false)?;
+    self.update_installed_game_version(&SupportedGame::Jak2.internal_str().to_string(), false)?;
+    self.update_installed_game_version(&SupportedGame::Jak3.internal_str().to_string(), false)?;
+    self.update_installed_game_version(&SupportedGame::JakX.internal_str().to_string(), false)?;
+    Ok(())
+  }
+
+  pub fn set_install_directory(&mut self, new_dir: String) -> Result<Option<String>, ConfigError> {
+    if let Some(err) = Self::validate_directory(&new_dir) {
+      return Ok(Some(err));
     }
 
     // If the directory changes (it's not a no-op), we need to:
     // - wipe any installed games (make them reinstall)
     // - wipe the active version/version types
-    match &self.installation_dir {
-      Some(old_dir) => {
-        if *old_dir != new_dir {
-          self.active_version = None;
-          self.active_version_folder = None;
-          self.update_installed_game_version(
-            &SupportedGame::Jak1.internal_str().to_string(),
-            false,
-          )?;
-          self.update_installed_game_version(
-            &SupportedGame::Jak2.internal_str().to_string(),
-            false,
-          )?;
-          self.update_installed_game_version(
-            &SupportedGame::Jak3.internal_str().to_string(),
-            false,
-          )?;
-          self.update_installed_game_version(
-            &SupportedGame::JakX.internal_str().to_string(),
-            false,
-          )?;
-        }
+    if (self.tooling_path() != Some(PathBuf::from(&new_dir))
+      || self.game_data_path() != Some(PathBuf::from(&new_dir)))
+      && self.installation_dir.is_some()
+    {
+      self.active_version = None;
+      self.active_version_folder = None;
+      self.uninstall_all_games()?;
+    }
+
+    // Setting the install directory moves both the tooling and the game data there
+    self.installation_dir = Some(new_dir.clone());
+    self.tooling_dir = Some(new_dir.clone());
+    self.game_data_dir = Some(new_dir);
+    self.save_config()?;
+    Ok(None)
+  }
+
+  /// Configured directories that can't be reached, ie. they're on a drive that was unplugged
+  pub fn unavailable_directories(&self) -> Vec<PathBuf> {
+    let mapped_dirs = self
+      .version_folder_dirs
+      .iter()
+      .flat_map(|dirs| dirs.values().map(PathBuf::from));
+    let shared_dir = self
+      .shared_versions_enabled
+      .unwrap_or(false)
+      .then(shared_versions_path)
+      .flatten();
+    let mut missing: Vec<PathBuf> = [self.tooling_path(), self.game_data_path(), shared_dir]
+      .into_iter()
+      .flatten()
+      .chain(mapped_dirs)
+      .filter(|path| !path.is_dir())
+      .collect();
+    missing.dedup();
+    missing
+  }
+
+  /// Points the launcher at a new location for an install directory that went missing.  Unlike
+  /// `set_install_directory` nothing is reset up-front, the active version and installed games
+  /// are kept as long as they're actually present in the new location.
+  pub fn relocate_install_directory(
+    &mut self,
+    new_dir: String,
+  ) -> Result<Option<String>, ConfigError> {
+    if let Some(err) = Self::validate_directory(&new_dir) {
+      return Ok(Some(err));
+    }
+    let new_path = PathBuf::from(&new_dir);
+    // Only move the directories that are missing, a split install might still have one of them
+    if self.tooling_path().map_or(true, |path| !path.is_dir()) {
+      self.tooling_dir = Some(new_dir.clone());
+    }
+    if self.game_data_path().map_or(true, |path| !path.is_dir()) {
+      self.game_data_dir = Some(new_dir.clone());
+    }
+    if self
+      .installation_dir
+      .as_ref()
+      .map_or(true, |dir| !Path::new(dir).is_dir())
+    {
+      self.installation_dir = Some(new_dir);
+    }
+
+    let active_version_present = match (&self.active_version_folder, &self.active_version) {
+      (Some(folder), Some(version)) => self
+        .version_folder_path(folder)
+        .map_or(true, |path| path.join(version).is_dir()),
+      _ => true,
+    };
+    if !active_version_present {
+      log::warn!("Active version is not in the new location, clearing it");
+      self.active_version = None;
+      self.active_version_folder = None;
+    }
+    let game_data = self.game_data_path().unwrap_or(new_path);
+    for (game, game_config) in self.games.iter_mut() {
+      let data_present = paths::game_data_dir(&game_data, game.internal_str()).is_dir();
+      if game_config.is_installed && (!data_present || !active_version_present) {
+        log::warn!(
+          "{} is not installed in the new location, it will have to be reinstalled",
+          game.internal_str()
+        );
+        game_config.is_installed = false;
+        game_config.version = None;
+        game_config.version_folder = None;
+      }
+    }
+    self.save_config()?;
+    Ok(None)
+  }
+
+  pub fn set_tooling_directory(&mut self, new_dir: String) -> Result<Option<String>, ConfigError> {
+    if let Some(err) = Self::validate_directory(&new_dir) {
+      return Ok(Some(err));
+    }
+
+    // The versions live in the tooling directory, so the active version (and the games
+    // that were installed with it) are no longer valid if it moves
+    if let Some(old_dir) = self.tooling_path() {
+      if old_dir != Path::new(&new_dir) {
+        self.active_version = None;
+        self.active_version_folder = None;
+        self.uninstall_all_games()?;
+      }
+    }
+
+    if self.installation_dir.is_none() {
+      self.installation_dir = Some(new_dir.clone());
+    }
+    self.tooling_dir = Some(new_dir);
+    self.save_config()?;
+    Ok(None)
+  }
+
+  pub fn set_game_data_directory(
+    &mut self,
+    new_dir: String,
+  ) -> Result<Option<String>, ConfigError> {
+    if let Some(err) = Self::validate_directory(&new_dir) {
+      return Ok(Some(err));
+    }
+
+    // Games have to be reinstalled into the new location, but the tooling is unaffected
+    if let Some(old_dir) = self.game_data_path() {
+      if old_dir != Path::new(&new_dir) {
+        self.uninstall_all_games()?;
       }
-      _ => (),
     }
 
-    self.installation_dir = Some(new_dir);
+    if self.installation_dir.is_none() {
+      self.installation_dir = Some(new_dir.clone());
+    }
+    self.game_data_dir = Some(new_dir);
     self.save_config()?;
     Ok(None)
   }
@@ -309,17 +1188,40 @@ impl LauncherConfig {
     Ok(())
   }
 
-  pub fn set_active_version(&mut self, new_version: String) -> Result<(), ConfigError> {
+  /// Switches both at once, so the settings file never has the new version in the old folder.
+  /// The version that was active is remembered, and the new one has yet to prove itself
+  pub fn switch_active_version(
+    &mut self,
+    new_version_folder: String,
+    new_version: String,
+  ) -> Result<(), ConfigError> {
+    let changed = self.active_version_folder.as_deref() != Some(new_version_folder.as_str())
+      || self.active_version.as_deref() != Some(new_version.as_str());
+    if changed {
+      if let (Some(folder), Some(version)) = (&self.active_version_folder, &self.active_version) {
+        self.previous_active_version_folder = Some(folder.clone());
+        self.previous_active_version = Some(version.clone());
+      }
+      self.active_version_proven = Some(false);
+    }
+    self.active_version_folder = Some(new_version_folder);
     self.active_version = Some(new_version);
     self.save_config()?;
     Ok(())
   }
 
-  pub fn set_active_version_folder(
-    &mut self,
-    new_version_folder: String,
-  ) -> Result<(), ConfigError> {
-    self.active_version_folder = Some(new_version_folder);
+  pub fn previous_active_version(&self) -> Option<(&str, &str)> {
+    match (
+      &self.previous_active_version_folder,
+      &self.previous_active_version,
+    ) {
+      (Some(folder), Some(version)) => Some((folder.as_str(), version.as_str())),
+      _ => None,
+    }
+  }
+
+  pub fn set_active_version_proven(&mut self) -> Result<(), ConfigError> {
+    self.active_version_proven = Some(true);
     self.save_config()?;
     Ok(())
   }
@@ -337,6 +1239,326 @@ impl LauncherConfig {
     Ok(())
   }
 
+  pub fn set_launch_on_startup(
+    &mut self,
+    enabled: bool,
+    minimized: bool,
+  ) -> Result<(), ConfigError> {
+    self.launch_on_startup = Some(enabled);
+    self.launch_minimized = Some(minimized);
+    self.save_config()?;
+    Ok(())
+  }
+
+  pub fn set_game_process_options(
+    &mut self,
+    priority: Option<ProcessPriority>,
+    cpu_affinity: Option<u64>,
+  ) -> Result<(), ConfigError> {
+    // An empty mask would leave the game with no cores to run on
+    if cpu_affinity == Some(0) {
+      return Err(ConfigError::Configuration(
+        "CPU affinity mask must include at least one core".to_string(),
+      ));
+    }
+    self.game_process_priority = priority;
+    self.game_cpu_affinity = cpu_affinity;
+    self.save_config()?;
+    Ok(())
+  }
+
+  pub fn set_game_display_options(
+    &mut self,
+    display_mode: Option<DisplayMode>,
+    monitor: Option<u32>,
+  ) -> Result<(), ConfigError> {
+    self.game_display_mode = display_mode;
+    self.game_monitor = monitor;
+    self.save_config()?;
+    Ok(())
+  }
+
+  pub fn set_game_video_options(
+    &mut self,
+    playback: Option<bool>,
+    hardware_decoding: Option<bool>,
+  ) -> Result<(), ConfigError> {
+    self.game_video_playback = playback;
+    self.game_video_hardware_decoding = hardware_decoding;
+    self.save_config()?;
+    Ok(())
+  }
+
+  pub fn set_retention_policy(
+    &mut self,
+    launcher_log_days: Option<u32>,
+    install_log_days: Option<u32>,
+    crash_report_days: Option<u32>,
+  ) -> Result<(), ConfigError> {
+    self.launcher_log_retention_days = launcher_log_days;
+    self.install_log_retention_days = install_log_days;
+    self.crash_report_retention_days = crash_report_days;
+    self.save_config()?;
+    Ok(())
+  }
+
+  pub fn set_maintain_active_version_link(&mut self, enabled: bool) -> Result<(), ConfigError> {
+    self.maintain_active_version_link = Some(enabled);
+    self.save_config()?;
+    Ok(())
+  }
+
+  pub fn set_kiosk_pin_hash(&mut self, pin_hash: Option<String>) -> Result<(), ConfigError> {
+    self.kiosk_pin_hash = pin_hash;
+    self.kiosk_failed_attempts = None;
+    self.kiosk_retry_at = None;
+    self.save_config()?;
+    Ok(())
+  }
+
+  pub fn set_kiosk_failed_attempts(
+    &mut self,
+    failed_attempts: u32,
+    retry_at: Option<u64>,
+  ) -> Result<(), ConfigError> {
+    self.kiosk_failed_attempts = Some(failed_attempts).filter(|count| *count > 0);
+    self.kiosk_retry_at = retry_at;
+    self.save_config()?;
+    Ok(())
+  }
+
+  pub fn set_kiosk_daily_limit_minutes(&mut self, minutes: Option<u32>) -> Result<(), ConfigError> {
+    self.kiosk_daily_limit_minutes = minutes.filter(|minutes| *minutes > 0);
+    self.save_config()?;
+    Ok(())
+  }
+
+  pub fn set_remote_api_options(
+    &mut self,
+    enabled: bool,
+    port: Option<u16>,
+    token: Option<String>,
+  ) -> Result<(), ConfigError> {
+    self.remote_api_enabled = Some(enabled);
+    self.remote_api_port = port;
+    self.remote_api_token = token;
+    self.save_config()?;
+    Ok(())
+  }
+
+  /// A random id that tells this install apart from others without saying anything about it,
+  /// made the first time it's needed.  Staged rollouts use it to pick the same installs every time
+  pub fn rollout_id(&mut self) -> Result<String, ConfigError> {
+    if let Some(id) = &self.rollout_id {
+      return Ok(id.clone());
+    }
+    let id = random_hex(16);
+    self.rollout_id = Some(id.clone());
+    self.save_config()?;
+    Ok(id)
+  }
+
+  pub fn set_snapshot_hotkey(&mut self, hotkey: String) -> Result<(), ConfigError> {
+    self.snapshot_hotkey = Some(hotkey);
+    self.save_config()?;
+    Ok(())
+  }
+
+  pub fn version_sources(&self) -> Vec<VersionSource> {
+    let mut sources = self
+      .version_sources
+      .clone()
+      .unwrap_or_else(VersionSource::defaults);
+    // A hand edited config can't make a source official either
+    for source in sources.iter_mut() {
+      if source.trust_level == TrustLevel::Official && source.folder.as_str() != "official" {
+        source.trust_level = TrustLevel::Trusted;
+      }
+    }
+    sources
+  }
+
+  pub fn set_version_sources(&mut self, sources: Vec<VersionSource>) -> Result<(), ConfigError> {
+    self.version_sources = Some(sources);
+    self.save_config()?;
+    Ok(())
+  }
+
+  pub fn release_channel(&self) -> ReleaseChannel {
+    self.release_channel.unwrap_or_default()
+  }
+
+  pub fn set_release_channel(&mut self, channel: ReleaseChannel) -> Result<(), ConfigError> {
+    self.release_channel = Some(channel);
+    self.save_config()?;
+    Ok(())
+  }
+
+  pub fn session_capture(&self) -> SessionCapture {
+    self.session_capture.unwrap_or_default()
+  }
+
+  pub fn set_session_capture(&mut self, capture: SessionCapture) -> Result<(), ConfigError> {
+    self.session_capture = Some(capture);
+    self.save_config()?;
+    Ok(())
+  }
+
+  pub fn content_hosts(&self) -> Vec<String> {
+    self.content_hosts.clone().unwrap_or_else(|| {
+      DEFAULT_CONTENT_HOSTS
+        .iter()
+        .map(|host| host.to_string())
+        .collect()
+    })
+  }
+
+  pub fn set_content_hosts(&mut self, hosts: Vec<String>) -> Result<(), ConfigError> {
+    self.content_hosts = Some(hosts);
+    self.save_config()?;
+    Ok(())
+  }
+
+  pub fn set_metered_behavior(&mut self, behavior: MeteredBehavior) -> Result<(), ConfigError> {
+    self.metered_behavior = Some(behavior);
+    self.save_config()?;
+    Ok(())
+  }
+
+  pub fn download_connections(&self) -> u32 {
+    self
+      .download_connections
+      .unwrap_or(DEFAULT_DOWNLOAD_CONNECTIONS)
+      .clamp(1, MAX_DOWNLOAD_CONNECTIONS)
+  }
+
+  pub fn set_download_connections(&mut self, connections: u32) -> Result<(), ConfigError> {
+    self.download_connections = Some(connections);
+    self.save_config()?;
+    Ok(())
+  }
+
+  pub fn versions_to_keep(&self) -> u32 {
+    self.versions_to_keep.unwrap_or(DEFAULT_VERSIONS_TO_KEEP)
+  }
+
+  pub fn set_versions_to_keep(&mut self, count: u32) -> Result<(), ConfigError> {
+    self.versions_to_keep = Some(count);
+    self.save_config()?;
+    Ok(())
+  }
+
+  pub fn tool_environment(&self, tool: &str) -> Option<&ToolEnvironment> {
+    self
+      .tool_environments
+      .as_ref()
+      .and_then(|environments| environments.get(tool))
+  }
+
+  /// `None` goes back to the curated environment alone
+  pub fn set_tool_environment(
+    &mut self,
+    tool: String,
+    environment: Option<ToolEnvironment>,
+  ) -> Result<(), ConfigError> {
+    let environments = self.tool_environments.get_or_insert_with(HashMap::new);
+    match environment {
+      Some(environment) => environments.insert(tool, environment),
+      None => environments.remove(&tool),
+    };
+    self.save_config()?;
+    Ok(())
+  }
+
+  pub fn set_quiet_hours(&mut self, quiet_hours: Option<QuietHours>) -> Result<(), ConfigError> {
+    self.quiet_hours = quiet_hours;
+    self.save_config()?;
+    Ok(())
+  }
+
+  pub fn set_install_retry_policy(
+    &mut self,
+    policy: InstallRetryPolicy,
+  ) -> Result<(), ConfigError> {
+    self.install_retry_policy = Some(policy);
+    self.save_config()?;
+    Ok(())
+  }
+
+  pub fn set_post_install_steps(&mut self, steps: Vec<PostInstallStep>) -> Result<(), ConfigError> {
+    self.post_install_steps = Some(steps);
+    self.save_config()?;
+    Ok(())
+  }
+
+  pub fn set_sync_folder(&mut self, folder: Option<String>) -> Result<(), ConfigError> {
+    // What was synced with another folder says nothing about this one
+    if let Some(state_path) = self.sync_state_path() {
+      delete_file(&state_path)?;
+    }
+    self.sync_folder = folder;
+    crate::config_sync::pull(self)?;
+    self.save_config()?;
+    Ok(())
+  }
+
+  pub fn set_permission(&mut self, kind: PermissionKind, granted: bool) -> Result<(), ConfigError> {
+    log::info!(
+      "Permission {:?} {}",
+      kind,
+      if granted { "granted" } else { "revoked" }
+    );
+    self.permissions.get_or_insert_with(HashMap::new).insert(
+      kind,
+      PermissionGrant {
+        granted,
+        changed_at: chrono::Local::now().to_rfc3339(),
+      },
+    );
+    self.save_config()?;
+    Ok(())
+  }
+
+  pub fn set_version_folder_dir(
+    &mut self,
+    version_folder: VersionFolder,
+    dir: Option<String>,
+  ) -> Result<(), ConfigError> {
+    let dirs = self.version_folder_dirs.get_or_insert_with(HashMap::new);
+    match dir {
+      Some(dir) => dirs.insert(version_folder, dir),
+      None => dirs.remove(&version_folder),
+    };
+    self.save_config()?;
+    Ok(())
+  }
+
+  pub fn set_shared_versions_enabled(&mut self, enabled: bool) -> Result<(), ConfigError> {
+    self.shared_versions_enabled = Some(enabled);
+    self.save_config()?;
+    Ok(())
+  }
+
+  pub fn set_auto_update_tooling(&mut self, enabled: bool) -> Result<(), ConfigError> {
+    self.auto_update_tooling = Some(enabled);
+    self.save_config()?;
+    Ok(())
+  }
+
+  pub fn set_dedupe_versions(&mut self, enabled: bool) -> Result<(), ConfigError> {
+    self.dedupe_versions = Some(enabled);
+    self.save_config()?;
+    Ok(())
+  }
+
+  pub fn set_github_token(&mut self, token: Option<String>) -> Result<(), ConfigError> {
+    self.github_token = token
+      .map(|token| token.trim().to_string())
+      .filter(|token| !token.is_empty());
+    self.save_config()?;
+    Ok(())
+  }
+
   pub fn set_bypass_requirements(&mut self, bypass: bool) -> Result<(), ConfigError> {
     self.requirements.bypass_requirements = Some(bypass);
     self.save_config()?;
@@ -354,6 +1576,7 @@ impl LauncherConfig {
         match self.games.get_mut(&game) {
           Some(game) => {
             game.is_installed = installed;
+            game.install_checkpoint = None;
             if installed {
               game.version = self.active_version.clone();
               game.version_folder = self.active_version_folder.clone();
@@ -381,6 +1604,105 @@ impl LauncherConfig {
     Ok(())
   }
 
+  pub fn game_launch_actions(&self, game_name: &str) -> (LaunchAction, GameExitAction) {
+    let game = SupportedGame::from_str(game_name)
+      .ok()
+      .and_then(|game| self.games.get(&game));
+    (
+      game
+        .and_then(|game| game.launch_action)
+        .unwrap_or(LaunchAction::KeepOpen),
+      game
+        .and_then(|game| game.exit_action)
+        .unwrap_or(GameExitAction::Nothing),
+    )
+  }
+
+  pub fn set_game_launch_actions(
+    &mut self,
+    game_name: &String,
+    launch_action: LaunchAction,
+    exit_action: GameExitAction,
+  ) -> Result<(), ConfigError> {
+    let game = SupportedGame::from_str(game_name)
+      .ok()
+      .and_then(|game| self.games.get_mut(&game));
+    match game {
+      Some(game) => {
+        game.launch_action = Some(launch_action);
+        game.exit_action = Some(exit_action);
+      }
+      None => {
+        return Err(ConfigError::Configuration(format!(
+          "Invalid game name - {}, can't update launch actions!",
+          game_name
+        )));
+      }
+    }
+    self.save_config()?;
+    Ok(())
+  }
+
+  pub fn game_keeps_iso_data(&self, game_name: &str) -> bool {
+    SupportedGame::from_str(game_name)
+      .ok()
+      .and_then(|game| self.games.get(&game))
+      .and_then(|game| game.keep_iso_data)
+      .unwrap_or(true)
+  }
+
+  pub fn set_game_keep_iso_data(&mut self, game_name: &str, keep: bool) -> Result<(), ConfigError> {
+    let game = SupportedGame::from_str(game_name)
+      .ok()
+      .and_then(|game| self.games.get_mut(&game));
+    match game {
+      Some(game) => game.keep_iso_data = Some(keep),
+      None => {
+        return Err(ConfigError::Configuration(format!(
+          "Invalid game name - {}, can't update whether to keep ISO data!",
+          game_name
+        )));
+      }
+    }
+    self.save_config()?;
+    Ok(())
+  }
+
+  pub fn game_install_checkpoint(&self, game_name: &str) -> Option<InstallCheckpoint> {
+    SupportedGame::from_str(game_name)
+      .ok()
+      .and_then(|game| self.games.get(&game))
+      .and_then(|game| game.install_checkpoint.clone())
+  }
+
+  /// Records `stage` as reached with the active version, `None` clears the checkpoint
+  pub fn set_game_install_checkpoint(
+    &mut self,
+    game_name: &String,
+    stage: Option<InstallStage>,
+  ) -> Result<(), ConfigError> {
+    let checkpoint = stage.map(|stage| InstallCheckpoint {
+      stage,
+      version: self.active_version.clone(),
+      version_folder: self.active_version_folder.clone(),
+      reached_at: chrono::Local::now().to_rfc3339(),
+    });
+    let game = SupportedGame::from_str(game_name)
+      .ok()
+      .and_then(|game| self.games.get_mut(&game));
+    match game {
+      Some(game) => game.install_checkpoint = checkpoint,
+      None => {
+        return Err(ConfigError::Configuration(format!(
+          "Invalid game name - {}, can't update install checkpoint!",
+          game_name
+        )));
+      }
+    }
+    self.save_config()?;
+    Ok(())
+  }
+
   pub fn is_game_installed(&self, game_name: &String) -> bool {
     match SupportedGame::from_str(game_name) {
       Ok(game) => {
@@ -462,3 +1784,73 @@ impl LauncherConfig {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn version_names_are_accepted() {
+    for name in ["v0.1.35", "v0.2.0-beta_1", "official", "a"] {
+      assert_eq!(VersionName::from_str(name).unwrap().as_str(), name);
+    }
+    assert!(VersionName::from_str(&"v".repeat(128)).is_ok());
+  }
+
+  #[test]
+  fn names_that_leave_their_folder_are_rejected() {
+    for name in [
+      "",
+      "..",
+      "../v0.1.35",
+      "v0..1",
+      ".",
+      ".hidden",
+      "a/b",
+      "/v0.1.35",
+      "a\\b",
+    ] {
+      assert!(
+        VersionName::from_str(name).is_err(),
+        "{:?} was accepted",
+        name
+      );
+    }
+    assert!(VersionName::from_str(&"v".repeat(129)).is_err());
+  }
+
+  #[test]
+  fn names_windows_treats_specially_are_rejected() {
+    for name in [
+      "con", "CON", "Nul", "prn", "aux", "com1", "COM9", "lpt3", "nul.txt", "con.v0.1", "v0.1.",
+    ] {
+      assert!(
+        VersionName::from_str(name).is_err(),
+        "{:?} was accepted",
+        name
+      );
+    }
+    for name in ["com0", "com10", "console", "lpt", "nullable"] {
+      assert!(
+        VersionName::from_str(name).is_ok(),
+        "{:?} was rejected",
+        name
+      );
+    }
+  }
+
+  #[test]
+  fn invalid_names_are_not_deserialized() {
+    assert_eq!(
+      serde_json::from_str::<VersionFolder>("\"official\"").unwrap(),
+      VersionFolder::from_str("official").unwrap()
+    );
+    for name in ["\"..\"", "\"a/b\"", "\"\"", "\"nul\""] {
+      assert!(
+        serde_json::from_str::<VersionName>(name).is_err(),
+        "{} was accepted",
+        name
+      );
+    }
+  }
+}
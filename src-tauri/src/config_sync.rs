@@ -0,0 +1,240 @@
+// Keeping preferences in sync between devices through a folder the user syncs themselves (ie.
+// Dropbox, Syncthing)
+//
+// Only the settings that mean the same thing on every machine are synced, paths, installed
+// versions and anything hardware specific (display mode, priority, video decoding) stay local.
+// Version sources aren't synced either, a file in a shared folder shouldn't be able to add
+// somewhere the launcher downloads and runs binaries from.  They're written to `SYNC_FILE_NAME` in
+// the sync folder whenever the settings are saved, and merged back in when the launcher starts.
+//
+// To tell which side changed what, the values as of the last sync are kept in `sync-state.json`
+// next to the settings file.  A value only changed on one side is taken from that side, one that
+// was changed on both is a conflict, which the sync folder wins.  Conflicts are kept in the sync
+// state so the user can see what was replaced.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+  config::{ConfigError, LauncherConfig},
+  util::file::read_json,
+};
+
+pub const SYNC_FILE_NAME: &str = "opengoal-launcher-sync.json";
+
+const SYNCED_KEYS: &[&str] = &[
+  "locale",
+  "gameVideoPlayback",
+  "launcherLogRetentionDays",
+  "installLogRetentionDays",
+  "crashReportRetentionDays",
+  "meteredBehavior",
+  "installRetryPolicy",
+  "postInstallSteps",
+  "snapshotHotkey",
+];
+// Synced for every game, as `games.<game>.<key>`
+const SYNCED_GAME_KEYS: &[&str] = &["launchAction", "exitAction"];
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncDocument {
+  updated_at: Option<String>,
+  #[serde(default)]
+  values: BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncConflict {
+  pub key: String,
+  /// The value this device had, as JSON
+  pub local_value: String,
+  /// The value from the sync folder that replaced it, as JSON
+  pub synced_value: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncState {
+  #[serde(default)]
+  base: BTreeMap<String, Value>,
+  last_synced_at: Option<String>,
+  #[serde(default)]
+  conflicts: Vec<SyncConflict>,
+}
+
+#[derive(Debug, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigSyncStatus {
+  pub folder: Option<String>,
+  pub last_synced_at: Option<String>,
+  /// From the last time changes were merged in from the sync folder
+  pub conflicts: Vec<SyncConflict>,
+}
+
+// Written aside first, so a sync tool never picks up a half written file
+fn write_synced_json<T: Serialize>(path: &Path, value: &T) -> Result<(), ConfigError> {
+  let tmp_path = path.with_extension("json.tmp");
+  std::fs::write(&tmp_path, serde_json::to_string_pretty(value)?)?;
+  std::fs::rename(&tmp_path, path)?;
+  Ok(())
+}
+
+fn sync_paths(config: &LauncherConfig) -> Option<(PathBuf, PathBuf)> {
+  let folder = PathBuf::from(config.sync_folder.as_ref()?);
+  let state_path = config.sync_state_path()?;
+  // It may be a network drive or removable disk that isn't there right now
+  if !folder.is_dir() {
+    log::warn!(
+      "Sync folder {} is unavailable, not syncing settings",
+      folder.display()
+    );
+    return None;
+  }
+  Some((folder.join(SYNC_FILE_NAME), state_path))
+}
+
+fn portable_values(config: &LauncherConfig) -> Result<BTreeMap<String, Value>, ConfigError> {
+  let json = serde_json::to_value(config)?;
+  let mut values = BTreeMap::new();
+  for key in SYNCED_KEYS {
+    let value = json.get(key).cloned().unwrap_or(Value::Null);
+    values.insert(key.to_string(), value);
+  }
+  if let Some(games) = json.get("games").and_then(Value::as_object) {
+    for (game, game_config) in games {
+      for key in SYNCED_GAME_KEYS {
+        let value = game_config.get(key).cloned().unwrap_or(Value::Null);
+        values.insert(format!("games.{}.{}", game, key), value);
+      }
+    }
+  }
+  Ok(values)
+}
+
+fn apply_values(
+  config: &mut LauncherConfig,
+  values: &BTreeMap<String, Value>,
+) -> Result<(), ConfigError> {
+  let mut json = serde_json::to_value(&*config)?;
+  for (key, value) in values {
+    let mut path: Vec<&str> = key.split('.').collect();
+    let field = match path.pop() {
+      Some(field) => field,
+      None => continue,
+    };
+    let parent = path
+      .into_iter()
+      .try_fold(&mut json, |json, part| json.get_mut(part));
+    if let Some(Value::Object(parent)) = parent {
+      parent.insert(field.to_string(), value.clone());
+    }
+  }
+  config.update_from_json(json)
+}
+
+/// Merges in what was changed in the sync folder since this device last synced, and then writes
+/// back what was changed here
+pub fn pull(config: &mut LauncherConfig) -> Result<(), ConfigError> {
+  let (sync_file, state_path) = match sync_paths(config) {
+    Some(paths) => paths,
+    None => return Ok(()),
+  };
+  let document: SyncDocument = read_json(&sync_file).unwrap_or_default();
+  let mut state: SyncState = read_json(&state_path).unwrap_or_default();
+  // Joining a sync folder that's already in use adopts what's in it, without calling everything
+  // that differs a conflict
+  let first_sync = state.base.is_empty();
+  let mut merged = BTreeMap::new();
+  let mut conflicts = vec![];
+  // Only what this launcher syncs itself is taken, whatever else is in the file is ignored
+  for (key, local_value) in portable_values(config)? {
+    let synced_value = match document.values.get(&key) {
+      Some(value) => value,
+      None => continue,
+    };
+    let base_value = state.base.get(&key);
+    if *synced_value == local_value {
+      state.base.insert(key, local_value);
+      continue;
+    }
+    if base_value == Some(synced_value) {
+      // Only changed here, that's for `push` to handle
+      continue;
+    }
+    if !first_sync && base_value != Some(&local_value) {
+      conflicts.push(SyncConflict {
+        key: key.clone(),
+        local_value: local_value.to_string(),
+        synced_value: synced_value.to_string(),
+      });
+    }
+    state.base.insert(key.clone(), synced_value.clone());
+    merged.insert(key, synced_value.clone());
+  }
+  if !conflicts.is_empty() {
+    log::warn!(
+      "Settings changed both here and in the sync folder, using the synced ones for: {:?}",
+      conflicts
+        .iter()
+        .map(|conflict| &conflict.key)
+        .collect::<Vec<_>>()
+    );
+    state.conflicts = conflicts;
+  }
+  state.last_synced_at = Some(chrono::Local::now().to_rfc3339());
+  write_synced_json(&state_path, &state)?;
+
+  if merged.is_empty() {
+    return push(config);
+  }
+  log::info!("Merged {} setting(s) from the sync folder", merged.len());
+  apply_values(config, &merged)?;
+  // Saving pushes anything that was only changed here
+  config.save_config()
+}
+
+/// Writes the settings changed on this device since the last sync to the sync folder
+pub fn push(config: &LauncherConfig) -> Result<(), ConfigError> {
+  let (sync_file, state_path) = match sync_paths(config) {
+    Some(paths) => paths,
+    None => return Ok(()),
+  };
+  let mut document: SyncDocument = read_json(&sync_file).unwrap_or_default();
+  let mut state: SyncState = read_json(&state_path).unwrap_or_default();
+  let mut changed = false;
+  for (key, value) in portable_values(config)? {
+    // Values this device didn't change are left alone, they may have been changed elsewhere
+    if state.base.get(&key) == Some(&value) && document.values.contains_key(&key) {
+      continue;
+    }
+    if document.values.get(&key) != Some(&value) {
+      document.values.insert(key.clone(), value.clone());
+      changed = true;
+    }
+    state.base.insert(key, value);
+  }
+  let now = chrono::Local::now().to_rfc3339();
+  if changed {
+    document.updated_at = Some(now.clone());
+    write_synced_json(&sync_file, &document)?;
+  }
+  state.last_synced_at = Some(now);
+  write_synced_json(&state_path, &state)
+}
+
+pub fn status(config: &LauncherConfig) -> ConfigSyncStatus {
+  let state: SyncState = config
+    .sync_state_path()
+    .and_then(|path| read_json(&path))
+    .unwrap_or_default();
+  ConfigSyncStatus {
+    folder: config.sync_folder.clone(),
+    last_synced_at: state.last_synced_at,
+    conflicts: state.conflicts,
+  }
+}
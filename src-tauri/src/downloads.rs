@@ -0,0 +1,252 @@
+// Queue for tooling version downloads
+//
+// Downloads run one at a time in the order they were asked for, a download requested while
+// another one is running waits as `queued` until it's its turn.  Every download is a job whose id
+// is that of the task tracking it (`download-<folder>-<version>`), so it can be cancelled like any
+// task.  A job's state is emitted as `downloadJobUpdated` whenever it changes, its progress is
+// part of the `versionDownloadProgress` events.
+//
+// Pausing holds the transfer between chunks, the paused job keeps its turn so the ones queued
+// behind it wait until it's resumed or cancelled.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::{
+  config::{VersionFolder, VersionName},
+  tasks::CancellationToken,
+  util::network::PauseFlag,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum DownloadJobState {
+  Queued,
+  Downloading,
+  Paused,
+  /// Done, one way or another, the task has the outcome
+  Finished,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadJob {
+  pub id: String,
+  pub version_folder: VersionFolder,
+  pub version: VersionName,
+  pub state: DownloadJobState,
+  pub queued_at: String,
+  pub downloaded_bytes: u64,
+  pub total_bytes: Option<u64>,
+}
+
+struct Job {
+  job: DownloadJob,
+  pause: PauseFlag,
+  // Whether it got its turn, to know what it goes back to when resumed
+  started: bool,
+}
+
+type Jobs = Arc<Mutex<Vec<Job>>>;
+
+pub struct DownloadQueue {
+  jobs: Jobs,
+  turn: Arc<Semaphore>,
+  // Caps for jobs that weren't queued yet when they were capped, applied once they are
+  pending_caps: Mutex<HashMap<String, u64>>,
+}
+
+impl Default for DownloadQueue {
+  fn default() -> Self {
+    Self {
+      jobs: Arc::default(),
+      turn: Arc::new(Semaphore::new(1)),
+      pending_caps: Mutex::default(),
+    }
+  }
+}
+
+fn update_job(
+  app_handle: &tauri::AppHandle,
+  jobs: &Jobs,
+  id: &str,
+  apply: impl FnOnce(&mut Job),
+) -> bool {
+  let job = match jobs.lock() {
+    Ok(mut jobs) => match jobs.iter_mut().find(|job| job.job.id == id) {
+      Some(job) => {
+        apply(job);
+        job.job.clone()
+      }
+      None => return false,
+    },
+    Err(_) => return false,
+  };
+  if let Err(err) = app_handle.emit_all("downloadJobUpdated", job) {
+    log::warn!("Unable to emit download job update: {}", err);
+  }
+  true
+}
+
+impl DownloadQueue {
+  pub fn list(&self) -> Vec<DownloadJob> {
+    match self.jobs.lock() {
+      Ok(jobs) => jobs.iter().map(|job| job.job.clone()).collect(),
+      Err(_) => vec![],
+    }
+  }
+
+  /// Adds a download to the queue and waits for its turn, or returns `None` if it's cancelled
+  /// before then
+  pub async fn enqueue(
+    &self,
+    app_handle: &tauri::AppHandle,
+    id: String,
+    version_folder: VersionFolder,
+    version: VersionName,
+    cancellation_token: &CancellationToken,
+  ) -> Option<QueuedDownload> {
+    let pause = PauseFlag::default();
+    if let Some(cap) = self
+      .pending_caps
+      .lock()
+      .ok()
+      .and_then(|mut caps| caps.remove(&id))
+    {
+      pause.set_bandwidth_cap(Some(cap));
+    }
+    if let Ok(mut jobs) = self.jobs.lock() {
+      jobs.push(Job {
+        job: DownloadJob {
+          id: id.clone(),
+          version_folder,
+          version,
+          state: DownloadJobState::Queued,
+          queued_at: chrono::Local::now().to_rfc3339(),
+          downloaded_bytes: 0,
+          total_bytes: None,
+        },
+        pause: pause.clone(),
+        started: false,
+      });
+    }
+    let mut download = QueuedDownload {
+      id,
+      jobs: self.jobs.clone(),
+      app_handle: app_handle.clone(),
+      pause,
+      _turn: None,
+    };
+    download.set_state(DownloadJobState::Queued);
+    // A job that's paused while queued is skipped until it's resumed
+    let turn = loop {
+      download.pause.wait_while_paused(cancellation_token).await;
+      let turn = tokio::select! {
+        turn = self.turn.clone().acquire_owned() => turn.ok()?,
+        _ = cancellation_token.cancelled() => return None,
+      };
+      if !download.pause.is_paused() {
+        break turn;
+      }
+    };
+    download._turn = Some(turn);
+    update_job(app_handle, &self.jobs, &download.id, |job| {
+      job.started = true;
+      job.job.state = DownloadJobState::Downloading;
+    });
+    Some(download)
+  }
+
+  /// Returns whether there was a queued or running job to pause
+  pub fn pause(&self, app_handle: &tauri::AppHandle, id: &str) -> bool {
+    update_job(app_handle, &self.jobs, id, |job| {
+      job.pause.set(true);
+      job.job.state = DownloadJobState::Paused;
+    })
+  }
+
+  pub fn resume(&self, app_handle: &tauri::AppHandle, id: &str) -> bool {
+    update_job(app_handle, &self.jobs, id, |job| {
+      job.pause.set(false);
+      job.job.state = if job.started {
+        DownloadJobState::Downloading
+      } else {
+        DownloadJobState::Queued
+      };
+    })
+  }
+
+  /// Caps a job's bandwidth, `None` lifts the cap.  A job that isn't queued yet is capped from
+  /// the moment it is.  Returns whether the job was already queued
+  pub fn set_bandwidth_cap(&self, id: &str, bytes_per_sec: Option<u64>) -> bool {
+    let queued = match self.jobs.lock() {
+      Ok(jobs) => match jobs.iter().find(|job| job.job.id == id) {
+        Some(job) => {
+          job.pause.set_bandwidth_cap(bytes_per_sec);
+          true
+        }
+        None => false,
+      },
+      Err(_) => false,
+    };
+    if let Ok(mut caps) = self.pending_caps.lock() {
+      match bytes_per_sec {
+        Some(cap) if !queued => {
+          caps.insert(id.to_string(), cap);
+        }
+        _ => {
+          caps.remove(id);
+        }
+      }
+    }
+    queued
+  }
+}
+
+/// A download that has its turn, the next one in the queue starts once this is dropped
+pub struct QueuedDownload {
+  id: String,
+  jobs: Jobs,
+  app_handle: tauri::AppHandle,
+  pause: PauseFlag,
+  _turn: Option<OwnedSemaphorePermit>,
+}
+
+impl QueuedDownload {
+  pub fn id(&self) -> &str {
+    &self.id
+  }
+
+  pub fn pause_flag(&self) -> &PauseFlag {
+    &self.pause
+  }
+
+  fn set_state(&self, state: DownloadJobState) {
+    update_job(&self.app_handle, &self.jobs, &self.id, |job| {
+      job.job.state = state
+    });
+  }
+
+  /// Records how far along the download is.  Not emitted, it's part of the progress events
+  pub fn set_progress(&self, downloaded_bytes: u64, total_bytes: Option<u64>) {
+    if let Ok(mut jobs) = self.jobs.lock() {
+      if let Some(job) = jobs.iter_mut().find(|job| job.job.id == self.id) {
+        job.job.downloaded_bytes = downloaded_bytes;
+        job.job.total_bytes = total_bytes;
+      }
+    }
+  }
+}
+
+impl Drop for QueuedDownload {
+  fn drop(&mut self) {
+    self.set_state(DownloadJobState::Finished);
+    if let Ok(mut jobs) = self.jobs.lock() {
+      jobs.retain(|job| job.job.id != self.id);
+    }
+  }
+}
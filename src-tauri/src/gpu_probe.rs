@@ -0,0 +1,148 @@
+// Checking for OpenGL 4.3 level GPU support, in a helper process
+//
+// Asking wgpu for a device is known to crash on some broken drivers, which used to take the whole
+// launcher down.  Instead the launcher runs itself again with `--probe-gpu`, that process does the
+// probe, prints the outcome and exits before anything else starts.  If the helper crashes (or
+// hangs) the outcome is unknown, and the user is warned rather than the launcher dying.
+//
+// NOTE - this is somewhat of a hack, instead of checking the actual specific version of OpenGL,
+// we just check if the system supports 3D textures and large uniform buffers that match OpenGL
+// 4.3's requirements.
+//
+// This is because OpenGL support requires libEGL -- which isn't always available on all
+// platforms, and GL support in general is waning.
+//
+// This should be good enough...hopefully.
+use std::path::Path;
+use std::time::Duration;
+
+use wgpu::InstanceDescriptor;
+
+use crate::util::process::tool_command;
+
+pub const GPU_PROBE_ARG: &str = "--probe-gpu";
+
+// Creating a device takes a moment, but never this long unless the driver is stuck
+const PROBE_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProbeOutcome {
+  Supported,
+  Unsupported(String),
+  /// There's no GPU adapter to ask at all
+  NoAdapter,
+  /// The helper crashed, hung or printed something that makes no sense
+  Failed(String),
+}
+
+impl ProbeOutcome {
+  fn to_line(&self) -> String {
+    match self {
+      ProbeOutcome::Supported => "supported".to_string(),
+      ProbeOutcome::Unsupported(reason) => format!("unsupported: {}", reason),
+      ProbeOutcome::NoAdapter => "no-adapter".to_string(),
+      ProbeOutcome::Failed(reason) => format!("failed: {}", reason),
+    }
+  }
+
+  fn from_line(line: &str) -> Self {
+    let line = line.trim();
+    match line.split_once(": ") {
+      Some(("unsupported", reason)) => ProbeOutcome::Unsupported(reason.to_string()),
+      Some(("failed", reason)) => ProbeOutcome::Failed(reason.to_string()),
+      _ => match line {
+        "supported" => ProbeOutcome::Supported,
+        "no-adapter" => ProbeOutcome::NoAdapter,
+        _ => ProbeOutcome::Failed(format!("unexpected probe output '{}'", line)),
+      },
+    }
+  }
+}
+
+async fn probe_device() -> ProbeOutcome {
+  let instance = wgpu::Instance::new(InstanceDescriptor {
+    backends: wgpu::Backends::all(),
+    dx12_shader_compiler: wgpu::Dx12Compiler::default(),
+  });
+  let adapter = match instance
+    .request_adapter(&wgpu::RequestAdapterOptions {
+      power_preference: wgpu::PowerPreference::default(),
+      force_fallback_adapter: false,
+      compatible_surface: None,
+    })
+    .await
+  {
+    None => return ProbeOutcome::NoAdapter,
+    Some(instance) => instance,
+  };
+
+  let device = adapter
+    .request_device(
+      &wgpu::DeviceDescriptor {
+        features: wgpu::Features::empty(),
+        limits: wgpu::Limits {
+          // These are OpenGL 4.3 minimums where these values
+          // were the maximum (not inclusive) for 4.2
+          max_texture_dimension_1d: 16384,
+          max_texture_dimension_2d: 16384,
+          max_texture_dimension_3d: 2048,
+          ..wgpu::Limits::default()
+        },
+        label: None,
+      },
+      None,
+    )
+    .await;
+  match device {
+    Err(err) => ProbeOutcome::Unsupported(format!("{:?}", err)),
+    // If we didn't support the above limits, we would have returned an error already
+    Ok(_) => ProbeOutcome::Supported,
+  }
+}
+
+/// Does the probe and exits if this process was started as the helper, otherwise does nothing.
+/// This has to come first in `main`, before anything that could get in the way of exiting
+pub fn run_if_requested() {
+  if !std::env::args().any(|arg| arg == GPU_PROBE_ARG) {
+    return;
+  }
+  let outcome = tauri::async_runtime::block_on(probe_device());
+  println!("{}", outcome.to_line());
+  std::process::exit(0);
+}
+
+/// Runs the probe in a helper process
+pub async fn probe_in_subprocess() -> ProbeOutcome {
+  let exe = match std::env::current_exe() {
+    Ok(exe) => exe,
+    Err(err) => return ProbeOutcome::Failed(format!("unable to find the launcher: {}", err)),
+  };
+  let cwd = exe.parent().map(Path::to_path_buf).unwrap_or_default();
+  let mut command = tokio::process::Command::from(tool_command(&exe, &cwd, None));
+  command
+    .arg(GPU_PROBE_ARG)
+    .stdin(std::process::Stdio::null())
+    .stderr(std::process::Stdio::null())
+    .kill_on_drop(true);
+  #[cfg(windows)]
+  {
+    command.creation_flags(0x08000000);
+  }
+  let output = match tokio::time::timeout(PROBE_TIMEOUT, command.output()).await {
+    Err(_) => {
+      return ProbeOutcome::Failed(format!(
+        "the probe didn't finish within {}s",
+        PROBE_TIMEOUT.as_secs()
+      ))
+    }
+    Ok(Err(err)) => return ProbeOutcome::Failed(format!("unable to start the probe: {}", err)),
+    Ok(Ok(output)) => output,
+  };
+  if !output.status.success() {
+    return ProbeOutcome::Failed(format!("the probe crashed ({})", output.status));
+  }
+  match String::from_utf8_lossy(&output.stdout).lines().last() {
+    Some(line) => ProbeOutcome::from_line(line),
+    None => ProbeOutcome::Failed("the probe exited without a result".to_string()),
+  }
+}
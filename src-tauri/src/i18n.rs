@@ -0,0 +1,105 @@
+// Message catalog for text produced by the backend, currently the messages attached to errors
+// returned from commands
+//
+// Catalogs are JSON files in `src-tauri/locales` named after the same locales the frontend uses,
+// they're embedded in the binary so they are always available. Messages missing from a catalog
+// fall back to `en-US`.
+//
+// Errors are serialized without access to any managed state, so unlike the rest of the launcher
+// the active catalog is kept in a global that's updated whenever the locale changes.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const DEFAULT_LOCALE: &str = "en-US";
+
+const CATALOGS: &[(&str, &str)] = &[
+  ("de-DE", include_str!("../locales/de-DE.json")),
+  ("en-US", include_str!("../locales/en-US.json")),
+  ("es-ES", include_str!("../locales/es-ES.json")),
+  ("fr-FR", include_str!("../locales/fr-FR.json")),
+  ("it-IT", include_str!("../locales/it-IT.json")),
+  ("pt-BR", include_str!("../locales/pt-BR.json")),
+];
+
+// Index into `CATALOGS`, `NO_CATALOG` when there's no catalog for the locale
+const NO_CATALOG: usize = usize::MAX;
+static ACTIVE_CATALOG: AtomicUsize = AtomicUsize::new(NO_CATALOG);
+
+fn find_catalog(locale: &str) -> Option<usize> {
+  // Prefer an exact match, but any catalog for the same language is better than none
+  let language = locale.split('-').next().unwrap_or(locale);
+  CATALOGS
+    .iter()
+    .position(|(name, _)| name.eq_ignore_ascii_case(locale))
+    .or_else(|| {
+      CATALOGS.iter().position(|(name, _)| {
+        name.split('-').next().map_or(false, |name_language| {
+          name_language.eq_ignore_ascii_case(language)
+        })
+      })
+    })
+}
+
+fn lookup_in(catalog: &str, key: &str) -> Option<String> {
+  let messages: HashMap<String, String> = match serde_json::from_str(catalog) {
+    Ok(messages) => messages,
+    Err(err) => {
+      log::error!("Backend message catalog is invalid: {}", err);
+      return None;
+    }
+  };
+  messages.get(key).cloned()
+}
+
+pub fn set_locale(locale: Option<String>) {
+  let catalog = find_catalog(locale.as_deref().unwrap_or(DEFAULT_LOCALE));
+  ACTIVE_CATALOG.store(catalog.unwrap_or(NO_CATALOG), Ordering::SeqCst);
+}
+
+/// Returns the message for `key` in the active locale, or `None` if no catalog has it
+pub fn message(key: &str) -> Option<String> {
+  let lookup = |index: usize| {
+    CATALOGS
+      .get(index)
+      .and_then(|(_, catalog)| lookup_in(catalog, key))
+  };
+  let active = match ACTIVE_CATALOG.load(Ordering::SeqCst) {
+    NO_CATALOG => find_catalog(DEFAULT_LOCALE),
+    index => Some(index),
+  };
+  active
+    .and_then(lookup)
+    .or_else(|| find_catalog(DEFAULT_LOCALE).and_then(lookup))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn catalogs_only_translate_default_messages() {
+    let parse = |catalog: &str| -> HashMap<String, String> {
+      serde_json::from_str(catalog).expect("catalog should be valid JSON")
+    };
+    let default = parse(CATALOGS[find_catalog(DEFAULT_LOCALE).unwrap()].1);
+    for (name, catalog) in CATALOGS {
+      for key in parse(catalog).keys() {
+        assert!(
+          default.contains_key(key),
+          "{} has {} which {} doesn't",
+          name,
+          key,
+          DEFAULT_LOCALE
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn regional_locales_fall_back_to_their_language() {
+    assert_eq!(find_catalog("pt-PT"), find_catalog("pt-BR"));
+    assert_eq!(find_catalog("de-AT"), find_catalog("de-DE"));
+    assert_eq!(find_catalog("ja-JP"), None);
+  }
+}
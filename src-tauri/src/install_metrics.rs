@@ -0,0 +1,255 @@
+// How the install steps used the machine, for telling from a user's report why an install was slow
+//
+// While an install tool runs, a thread samples its process (and the system as a whole) once a
+// second for CPU use and how much it read and wrote.  Each step's figures are kept in a file in
+// the game's install folder, so they go along with the install, the extraction starts a new
+// install over so it clears what was there.
+//
+// The bottleneck is a guess from those figures: a tool that keeps a core busy is CPU bound, one
+// that barely uses the CPU while the rest of the system is busy is competing with another process
+// (most often antivirus scanning everything it writes), and one that's idle along with the
+// system is waiting on the disk.
+use std::path::PathBuf;
+use std::sync::{
+  atomic::{AtomicBool, Ordering},
+  Arc,
+};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use sysinfo::{CpuExt, PidExt, ProcessExt, System, SystemExt};
+
+use crate::{
+  config::LauncherConfig,
+  paths,
+  util::file::{read_json, update_json},
+};
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+// A tool keeping at least this much of one core busy is limited by the CPU
+const CPU_BOUND_CORES: f64 = 0.85;
+// Other processes using this much of the whole CPU are getting in the way
+const CONTENDED_SYSTEM_PERCENT: f64 = 50.0;
+
+const METADATA_NAME: &str = ".launcher-install-performance.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum Bottleneck {
+  Cpu,
+  Disk,
+  /// Other processes were busy while the tool wasn't, usually antivirus
+  OtherProcesses,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct StagePerformance {
+  /// The install step, ie. `compile`
+  pub stage: String,
+  pub started_at: String,
+  pub duration_seconds: f64,
+  pub succeeded: bool,
+  /// How many cores the tool kept busy on average, 1.0 is one core fully used
+  pub average_cores_used: f64,
+  pub peak_cores_used: f64,
+  /// How busy the whole CPU was, including the tool
+  pub average_system_cpu_percent: f64,
+  pub bytes_read: u64,
+  pub bytes_written: u64,
+  pub io_bytes_per_sec: f64,
+  pub bottleneck: Option<Bottleneck>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallPerformance {
+  pub stages: Vec<StagePerformance>,
+  pub total_seconds: f64,
+  /// What held up the slowest step, that's where most of the time went
+  pub bottleneck: Option<Bottleneck>,
+}
+
+impl InstallPerformance {
+  pub fn from_stages(stages: Vec<StagePerformance>) -> Self {
+    let bottleneck = stages
+      .iter()
+      .max_by(|a, b| {
+        a.duration_seconds
+          .partial_cmp(&b.duration_seconds)
+          .unwrap_or(std::cmp::Ordering::Equal)
+      })
+      .and_then(|stage| stage.bottleneck);
+    Self {
+      total_seconds: stages.iter().map(|stage| stage.duration_seconds).sum(),
+      stages,
+      bottleneck,
+    }
+  }
+}
+
+fn metadata_path(config: &LauncherConfig, game_name: &str) -> Option<PathBuf> {
+  config
+    .game_data_path()
+    .map(|dir| paths::game_install_dir(&dir, game_name).join(METADATA_NAME))
+}
+
+/// How each step of the game's last install (or recompile) went
+pub fn stages(config: &LauncherConfig, game_name: &str) -> Vec<StagePerformance> {
+  metadata_path(config, game_name)
+    .and_then(|path| read_json(&path))
+    .unwrap_or_default()
+}
+
+/// Keeps how a step went, replacing the last run of the same step.  Extracting starts an install
+/// over, so it clears the other steps too
+pub fn record_stage(
+  config: &LauncherConfig,
+  game_name: &str,
+  performance: StagePerformance,
+) -> std::io::Result<()> {
+  let path = match metadata_path(config, game_name) {
+    Some(path) => path,
+    None => {
+      return Err(std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        "no game data directory set",
+      ))
+    }
+  };
+  if let Some(dir) = path.parent() {
+    std::fs::create_dir_all(dir)?;
+  }
+  update_json(&path, |stages: &mut Vec<StagePerformance>| {
+    if performance.stage == "extract" {
+      stages.clear();
+    }
+    stages.retain(|stage| stage.stage != performance.stage);
+    stages.push(performance);
+  })
+}
+
+#[derive(Debug, Default)]
+struct Samples {
+  count: u32,
+  process_cpu_total: f64,
+  process_cpu_peak: f64,
+  system_cpu_total: f64,
+  bytes_read: u64,
+  bytes_written: u64,
+}
+
+fn sample_process(pid: u32, stop: &AtomicBool) -> Samples {
+  let pid = sysinfo::Pid::from_u32(pid);
+  let mut system = System::new();
+  let mut samples = Samples::default();
+  // CPU use is measured between refreshes, so the first one only sets the baseline
+  system.refresh_cpu();
+  system.refresh_process(pid);
+  while !stop.load(Ordering::Relaxed) {
+    std::thread::sleep(SAMPLE_INTERVAL);
+    system.refresh_cpu();
+    if !system.refresh_process(pid) {
+      break;
+    }
+    let process = match system.process(pid) {
+      Some(process) => process,
+      None => break,
+    };
+    let cores_used = f64::from(process.cpu_usage()) / 100.0;
+    samples.count += 1;
+    samples.process_cpu_total += cores_used;
+    samples.process_cpu_peak = samples.process_cpu_peak.max(cores_used);
+    samples.system_cpu_total += f64::from(system.global_cpu_info().cpu_usage());
+    let disk_usage = process.disk_usage();
+    samples.bytes_read = disk_usage.total_read_bytes;
+    samples.bytes_written = disk_usage.total_written_bytes;
+  }
+  samples
+}
+
+fn bottleneck(average_cores_used: f64, average_system_cpu_percent: f64) -> Bottleneck {
+  let cores = std::thread::available_parallelism().map_or(1, |cores| cores.get()) as f64;
+  let tool_system_percent = average_cores_used / cores * 100.0;
+  if average_cores_used >= CPU_BOUND_CORES {
+    Bottleneck::Cpu
+  } else if average_system_cpu_percent - tool_system_percent >= CONTENDED_SYSTEM_PERCENT {
+    Bottleneck::OtherProcesses
+  } else {
+    Bottleneck::Disk
+  }
+}
+
+/// Samples an install tool's process from when it's started until `finish`
+pub struct StageMonitor {
+  stage: String,
+  started: Instant,
+  started_at: String,
+  stop: Arc<AtomicBool>,
+  sampler: Option<JoinHandle<Samples>>,
+}
+
+impl StageMonitor {
+  pub fn start(stage: &str, pid: u32) -> Self {
+    let stop = Arc::new(AtomicBool::new(false));
+    let sampler_stop = stop.clone();
+    let sampler = std::thread::Builder::new()
+      .name(format!("{}-metrics", stage))
+      .spawn(move || sample_process(pid, &sampler_stop));
+    if let Err(err) = &sampler {
+      log::warn!("Unable to measure the {} step: {}", stage, err);
+    }
+    Self {
+      stage: stage.to_string(),
+      started: Instant::now(),
+      started_at: chrono::Local::now().to_rfc3339(),
+      stop,
+      sampler: sampler.ok(),
+    }
+  }
+
+  pub fn finish(mut self, succeeded: bool) -> StagePerformance {
+    let duration = self.started.elapsed();
+    self.stop.store(true, Ordering::Relaxed);
+    let samples = self
+      .sampler
+      .take()
+      .and_then(|sampler| sampler.join().ok())
+      .unwrap_or_default();
+    let seconds = duration.as_secs_f64();
+    let (average_cores_used, average_system_cpu_percent) = match samples.count {
+      0 => (0.0, 0.0),
+      count => (
+        samples.process_cpu_total / f64::from(count),
+        samples.system_cpu_total / f64::from(count),
+      ),
+    };
+    let io_bytes = samples.bytes_read + samples.bytes_written;
+    StagePerformance {
+      stage: self.stage.clone(),
+      started_at: self.started_at.clone(),
+      duration_seconds: seconds,
+      succeeded,
+      average_cores_used,
+      peak_cores_used: samples.process_cpu_peak,
+      average_system_cpu_percent,
+      bytes_read: samples.bytes_read,
+      bytes_written: samples.bytes_written,
+      io_bytes_per_sec: if seconds > 0.0 {
+        io_bytes as f64 / seconds
+      } else {
+        0.0
+      },
+      // A step that's over within a sample or two says nothing about what held it up
+      bottleneck: (samples.count >= 3)
+        .then(|| bottleneck(average_cores_used, average_system_cpu_percent)),
+    }
+  }
+}
+
+impl Drop for StageMonitor {
+  fn drop(&mut self) {
+    self.stop.store(true, Ordering::Relaxed);
+  }
+}
@@ -0,0 +1,299 @@
+// Locked ("kiosk") mode, for shared family PCs and exhibition setups
+//
+// Once a PIN is set the launcher starts out locked, and while locked the backend only accepts
+// commands that read state or launch an already installed game.  Everything else (installs,
+// version changes, settings) is rejected until the launcher is unlocked with the PIN.
+//
+// This is enforced by wrapping the invoke handler rather than checking in each command, so new
+// commands are blocked by default until they are added to the allowlists below.  Getters are
+// listed one by one, some of them fetch from the network or write to disk as they go.
+//
+// PINs are short, so they're stored salted and stretched with PBKDF2, and after a few wrong
+// guesses unlocking is refused for a while, longer with every further wrong guess.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use hmac::Hmac;
+use sha2::{Digest, Sha256};
+use tauri::{Invoke, Manager, Runtime};
+
+use crate::{
+  commands::CommandError,
+  util::crypto::{constant_time_eq, hex, random_hex},
+};
+
+// Commands that are always allowed, on top of the ones that only read state
+const ALLOWED_WHILE_LOCKED: &[&str] = &[
+  "check_shared_library_requirements",
+  "ensure_active_version_still_exists",
+  "frontend_log",
+  // Only ever marks a half installed game as not installed, which couldn't be launched anyway
+  "is_game_installed",
+  "launch_game",
+  "launch_game_with_limit",
+  "lock_kiosk",
+  "open_main_window",
+  "unlock_kiosk",
+  "wait_for_install_dir",
+];
+// Commands that only read state, without fetching anything or writing to disk
+const READ_ONLY_WHILE_LOCKED: &[&str] = &[
+  "get_active_tooling_version",
+  "get_active_tooling_version_folder",
+  "get_api_version",
+  "get_applied_texture_packs",
+  "get_auto_update_tooling",
+  "get_bypass_requirements",
+  "get_cached_releases",
+  "get_cloud_synced_folders",
+  "get_config_sync_status",
+  "get_content_hosts",
+  "get_cpu_capabilities",
+  "get_data_usage_breakdown",
+  "get_dedupe_versions",
+  "get_download_connections",
+  "get_end_of_logs",
+  "get_game_data_changes",
+  "get_game_data_directory",
+  "get_game_display_options",
+  "get_game_install_info",
+  "get_game_launch_actions",
+  "get_game_process_options",
+  "get_game_video_options",
+  "get_github_rate_limit",
+  "get_install_checkpoint",
+  "get_install_directory",
+  "get_install_history",
+  "get_install_performance",
+  "get_install_retry_policy",
+  "get_installed_version",
+  "get_installed_version_folder",
+  "get_kiosk_status",
+  "get_launch_failure_report",
+  "get_launch_on_startup",
+  "get_locale",
+  "get_maintain_active_version_link",
+  "get_memory_usage",
+  "get_metered_behavior",
+  "get_monitors",
+  "get_permissions",
+  "get_playtime_today",
+  "get_post_install_steps",
+  "get_prepared_version_switch",
+  "get_quiet_hours",
+  "get_quiet_hours_status",
+  "get_release_channel",
+  "get_remote_api_settings",
+  "get_remote_config",
+  "get_retention_policy",
+  "get_running_tasks",
+  "get_service_status",
+  "get_session_capture",
+  "get_session_history",
+  "get_setup_funnel_report",
+  "get_shared_versions_status",
+  "get_snapshot_hotkey",
+  "get_task_state",
+  "get_tool_environments",
+  "get_tooling_directory",
+  "get_unavailable_install_directories",
+  "get_version_dedup_savings",
+  "get_version_disk_usage",
+  "get_version_folder_dirs",
+  "get_versions_to_keep",
+  "get_video_playback_support",
+  "has_github_token",
+  "has_iso_data",
+  "has_old_data_directory",
+  "is_feature_enabled",
+  "is_safe_mode",
+  "list_available_tools",
+  "list_downloaded_versions",
+  "list_downloads",
+  "list_snapshots",
+  "list_texture_packs",
+  "list_version_sources",
+  "list_watched_local_builds",
+];
+
+const PIN_HASH_SCHEME: &str = "pbkdf2-sha256";
+const PIN_HASH_ROUNDS: u32 = 600_000;
+// Wrong guesses allowed before unlocking is refused for a while
+const FREE_ATTEMPTS: u32 = 5;
+const LOCKOUT_BASE: Duration = Duration::from_secs(30);
+const LOCKOUT_MAX: Duration = Duration::from_secs(60 * 60);
+
+/// How long the PIN can't be tried after `failed_attempts` wrong ones in a row, every one past
+/// the free attempts doubles the wait
+pub fn lockout(failed_attempts: u32) -> Option<Duration> {
+  if failed_attempts < FREE_ATTEMPTS {
+    return None;
+  }
+  let doublings = (failed_attempts - FREE_ATTEMPTS).min(16);
+  Some((LOCKOUT_BASE * 2u32.pow(doublings)).min(LOCKOUT_MAX))
+}
+
+pub struct KioskLock {
+  locked: AtomicBool,
+}
+
+impl KioskLock {
+  pub fn new(locked: bool) -> Self {
+    Self {
+      locked: AtomicBool::new(locked),
+    }
+  }
+
+  pub fn is_locked(&self) -> bool {
+    self.locked.load(Ordering::SeqCst)
+  }
+
+  pub fn set_locked(&self, locked: bool) {
+    self.locked.store(locked, Ordering::SeqCst);
+  }
+}
+
+fn is_allowed_while_locked(command: &str) -> bool {
+  ALLOWED_WHILE_LOCKED.contains(&command) || READ_ONLY_WHILE_LOCKED.contains(&command)
+}
+
+/// Wraps the invoke handler, rejecting anything that isn't allowed while the launcher is locked
+pub fn guard<R: Runtime>(
+  handler: impl Fn(Invoke<R>) + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) + Send + Sync + 'static {
+  move |invoke| {
+    let locked = invoke.message.window_ref().state::<KioskLock>().is_locked();
+    if locked && !is_allowed_while_locked(invoke.message.command()) {
+      log::warn!(
+        "Rejected '{}', the launcher is locked",
+        invoke.message.command()
+      );
+      invoke.resolver.reject(CommandError::Locked(
+        "The launcher is locked, unlock it to make changes".to_string(),
+      ));
+      return;
+    }
+    handler(invoke)
+  }
+}
+
+// How PINs were hashed before they were stretched, only used to check (and replace) those
+fn legacy_digest(salt: &str, pin: &str) -> String {
+  let mut hasher = Sha256::new();
+  hasher.update(salt.as_bytes());
+  hasher.update(pin.as_bytes());
+  hex(&hasher.finalize())
+}
+
+fn stretched_digest(salt: &str, pin: &str, rounds: u32) -> String {
+  let mut hash = [0u8; 32];
+  pbkdf2::pbkdf2::<Hmac<Sha256>>(pin.as_bytes(), salt.as_bytes(), rounds, &mut hash);
+  hex(&hash)
+}
+
+/// Hashes `pin` with a fresh salt, stored as `pbkdf2-sha256$<rounds>$<salt>$<hash>`
+pub fn hash_pin(pin: &str) -> String {
+  let salt = random_hex(16);
+  format!(
+    "{}${}${}${}",
+    PIN_HASH_SCHEME,
+    PIN_HASH_ROUNDS,
+    salt,
+    stretched_digest(&salt, pin, PIN_HASH_ROUNDS)
+  )
+}
+
+pub fn verify_pin(pin: &str, stored: &str) -> bool {
+  let parts: Vec<&str> = stored.split('$').collect();
+  let (digest, hash) = match parts.as_slice() {
+    [PIN_HASH_SCHEME, rounds, salt, hash] => match rounds.parse() {
+      Ok(rounds) => (stretched_digest(salt, pin, rounds), *hash),
+      Err(_) => return false,
+    },
+    [salt, hash] => (legacy_digest(salt, pin), *hash),
+    _ => return false,
+  };
+  constant_time_eq(digest.as_bytes(), hash.as_bytes())
+}
+
+/// Whether `stored` should be replaced with a fresh `hash_pin` the next time the PIN is entered
+pub fn needs_rehash(stored: &str) -> bool {
+  !stored.starts_with(&format!("{}${}$", PIN_HASH_SCHEME, PIN_HASH_ROUNDS))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Few rounds, the real number of them is slow outside of release builds
+  fn quick_hash(pin: &str) -> String {
+    let salt = random_hex(16);
+    format!(
+      "{}${}${}${}",
+      PIN_HASH_SCHEME,
+      1000,
+      salt,
+      stretched_digest(&salt, pin, 1000)
+    )
+  }
+
+  #[test]
+  fn the_right_pin_is_accepted() {
+    assert!(verify_pin("1234", &quick_hash("1234")));
+  }
+
+  #[test]
+  fn a_wrong_pin_is_refused() {
+    let stored = quick_hash("1234");
+    for pin in ["4321", "12345", "123", ""] {
+      assert!(!verify_pin(pin, &stored), "{} was accepted", pin);
+    }
+    assert!(!verify_pin("1234", "not a hash"));
+    assert!(!verify_pin("1234", &stored.replace("$1000$", "$many$")));
+  }
+
+  #[test]
+  fn legacy_hashes_are_upgraded_to_pbkdf2() {
+    let legacy = format!("abcd${}", legacy_digest("abcd", "1234"));
+    assert!(verify_pin("1234", &legacy));
+    assert!(!verify_pin("4321", &legacy));
+    assert!(needs_rehash(&legacy));
+    // So are stretched ones with fewer rounds than PINs are hashed with now
+    assert!(needs_rehash(&quick_hash("1234")));
+
+    let upgraded = hash_pin("1234");
+    assert!(upgraded.starts_with(&format!("{}${}$", PIN_HASH_SCHEME, PIN_HASH_ROUNDS)));
+    assert!(!needs_rehash(&upgraded));
+    assert!(verify_pin("1234", &upgraded));
+    assert!(!verify_pin("4321", &upgraded));
+  }
+
+  #[test]
+  fn lockouts_double_up_to_an_hour() {
+    for failed_attempts in 0..FREE_ATTEMPTS {
+      assert_eq!(lockout(failed_attempts), None);
+    }
+    assert_eq!(lockout(FREE_ATTEMPTS), Some(Duration::from_secs(30)));
+    assert_eq!(lockout(FREE_ATTEMPTS + 1), Some(Duration::from_secs(60)));
+    assert_eq!(lockout(FREE_ATTEMPTS + 2), Some(Duration::from_secs(120)));
+    assert_eq!(lockout(FREE_ATTEMPTS + 6), Some(Duration::from_secs(1920)));
+    assert_eq!(lockout(FREE_ATTEMPTS + 7), Some(LOCKOUT_MAX));
+    assert_eq!(lockout(u32::MAX), Some(LOCKOUT_MAX));
+  }
+
+  #[test]
+  fn getters_with_side_effects_are_refused_while_locked() {
+    for command in [
+      "get_game_artwork",
+      "get_game_feed",
+      "get_release_changelog",
+      "is_avx_requirement_met",
+      "is_opengl_requirement_met",
+    ] {
+      assert!(!is_allowed_while_locked(command), "{} is allowed", command);
+    }
+    assert!(is_allowed_while_locked("get_kiosk_status"));
+    assert!(is_allowed_while_locked("launch_game"));
+    assert!(!is_allowed_while_locked("get_something_new"));
+  }
+}
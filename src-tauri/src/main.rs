@@ -13,8 +13,31 @@ use std::io::Write;
 
 mod commands;
 mod config;
+mod config_sync;
+mod downloads;
+mod gpu_probe;
+mod i18n;
+mod install_metrics;
+mod kiosk;
+mod paths;
+mod permissions;
+mod playtime;
+mod remote_api;
+mod remote_config;
+mod safe_mode;
+mod schedule;
+mod sessions;
+mod setup_funnel;
+mod supervisor;
+mod tasks;
 mod textures;
+mod throughput;
 mod util;
+mod warnings;
+
+#[cfg(feature = "mimalloc")]
+#[global_allocator]
+static GLOBAL_ALLOCATOR: mimalloc::MiMalloc = mimalloc::MiMalloc;
 
 fn log_crash(panic_info: Option<&std::panic::PanicInfo>, error: Option<tauri::Error>) {
   let backtrace = Backtrace::new();
@@ -49,16 +72,231 @@ fn panic_hook(info: &std::panic::PanicInfo) {
 }
 
 fn main() {
+  // When started as the GPU probe helper, this is all the process does
+  gpu_probe::run_if_requested();
+
   // In the event that some catastrophic happens, atleast log it out
   // the panic_hook will log to a file in the folder of the executable
   std::panic::set_hook(Box::new(panic_hook));
 
+  // Keep the typescript definitions of the commands (and everything they take and return) up to
+  // date, this happens on every debug run so commands added during development are picked up
+  #[cfg(debug_assertions)]
+  tauri_specta::ts::export_with_cfg(
+    specta::collect_types![
+      commands::artwork::clear_custom_artwork,
+      commands::artwork::get_game_artwork,
+      commands::artwork::set_custom_artwork,
+      commands::backup::create_full_backup,
+      commands::backup::restore_full_backup,
+      commands::binaries::check_shared_library_requirements,
+      commands::binaries::extract_and_validate_iso,
+      commands::binaries::get_end_of_logs,
+      commands::binaries::get_launch_failure_report,
+      commands::binaries::launch_game_with_limit,
+      commands::binaries::launch_game,
+      commands::binaries::open_repl,
+      commands::binaries::resume_install,
+      commands::binaries::run_compiler,
+      commands::binaries::run_decompiler,
+      commands::binaries::update_data_directory,
+      commands::compatibility::check_release_compatibility,
+      commands::config::choose_new_install_dir,
+      commands::config::delete_old_data_directory,
+      commands::config::finalize_installation,
+      commands::config::get_active_tooling_version_folder,
+      commands::config::get_active_tooling_version,
+      commands::config::get_auto_update_tooling,
+      commands::config::get_bypass_requirements,
+      commands::config::get_config_sync_status,
+      commands::config::get_content_hosts,
+      commands::config::get_cpu_capabilities,
+      commands::config::get_dedupe_versions,
+      commands::config::get_download_connections,
+      commands::config::get_game_data_directory,
+      commands::config::get_game_display_options,
+      commands::config::get_game_install_info,
+      commands::config::get_game_launch_actions,
+      commands::config::get_game_process_options,
+      commands::config::get_game_video_options,
+      commands::config::get_github_rate_limit,
+      commands::config::get_install_checkpoint,
+      commands::config::get_install_directory,
+      commands::config::get_install_performance,
+      commands::config::get_install_retry_policy,
+      commands::config::get_installed_version_folder,
+      commands::config::get_installed_version,
+      commands::config::get_launch_on_startup,
+      commands::config::get_locale,
+      commands::config::get_maintain_active_version_link,
+      commands::config::get_metered_behavior,
+      commands::config::get_quiet_hours_status,
+      commands::config::get_quiet_hours,
+      commands::config::get_release_channel,
+      commands::config::get_remote_config,
+      commands::config::get_retention_policy,
+      commands::config::get_session_capture,
+      commands::config::get_tool_environments,
+      commands::config::get_tooling_directory,
+      commands::config::get_unavailable_install_directories,
+      commands::config::get_versions_to_keep,
+      commands::config::get_video_playback_support,
+      commands::config::has_github_token,
+      commands::config::has_old_data_directory,
+      commands::config::is_avx_requirement_met,
+      commands::config::is_feature_enabled,
+      commands::config::is_game_installed,
+      commands::config::is_opengl_requirement_met,
+      commands::config::reset_to_defaults,
+      commands::config::save_active_version_change,
+      commands::config::set_auto_update_tooling,
+      commands::config::set_bypass_requirements,
+      commands::config::set_config_sync_folder,
+      commands::config::set_content_hosts,
+      commands::config::set_dedupe_versions,
+      commands::config::set_download_connections,
+      commands::config::set_game_data_directory,
+      commands::config::set_game_display_options,
+      commands::config::set_game_launch_actions,
+      commands::config::set_game_process_options,
+      commands::config::set_game_video_options,
+      commands::config::set_github_token,
+      commands::config::set_install_directory,
+      commands::config::set_install_retry_policy,
+      commands::config::set_launch_on_startup,
+      commands::config::set_locale,
+      commands::config::set_maintain_active_version_link,
+      commands::config::set_metered_behavior,
+      commands::config::set_quiet_hours,
+      commands::config::set_release_channel,
+      commands::config::set_retention_policy,
+      commands::config::set_session_capture,
+      commands::config::set_tool_environment,
+      commands::config::set_tooling_directory,
+      commands::config::set_versions_to_keep,
+      commands::config::wait_for_install_dir,
+      commands::game::get_playtime_today,
+      commands::game::has_iso_data,
+      commands::game::purge_iso_data,
+      commands::game::reset_game_settings,
+      commands::game::retain_iso_data,
+      commands::game::uninstall_game,
+      commands::history::get_install_history,
+      commands::history::get_session_history,
+      commands::hotfixes::apply_data_hotfixes,
+      commands::hotfixes::check_data_hotfixes,
+      commands::journal::get_game_data_changes,
+      commands::journal::revert_last_change,
+      commands::kiosk::disable_kiosk_mode,
+      commands::kiosk::enable_kiosk_mode,
+      commands::kiosk::get_kiosk_status,
+      commands::kiosk::lock_kiosk,
+      commands::kiosk::set_kiosk_daily_limit,
+      commands::kiosk::unlock_kiosk,
+      commands::libraries::scan_drive_for_installs,
+      commands::local_builds::list_watched_local_builds,
+      commands::local_builds::register_local_build,
+      commands::local_builds::unwatch_local_build,
+      commands::local_builds::watch_local_build,
+      commands::logging::frontend_log,
+      commands::migration::detect_foreign_installs,
+      commands::migration::import_foreign_saves,
+      commands::migration::import_foreign_texture_pack,
+      commands::mod_scripts::install_mod_from_url,
+      commands::mod_scripts::preview_mod_install_script,
+      commands::mod_scripts::run_mod_install_script,
+      commands::news::get_game_feed,
+      commands::permissions::get_permissions,
+      commands::permissions::set_permission,
+      commands::post_install::get_post_install_steps,
+      commands::post_install::set_post_install_steps,
+      commands::releases::cache_releases,
+      commands::releases::get_cached_releases,
+      commands::releases::get_release_changelog,
+      commands::remote_api::get_remote_api_settings,
+      commands::remote_api::regenerate_remote_api_token,
+      commands::remote_api::set_remote_api_enabled,
+      commands::reset::factory_reset,
+      commands::reset::prepare_factory_reset,
+      commands::safe_mode::discard_settings_file,
+      commands::safe_mode::is_safe_mode,
+      commands::safe_mode::restart_launcher,
+      commands::snapshots::create_snapshot,
+      commands::snapshots::get_snapshot_hotkey,
+      commands::snapshots::list_snapshots,
+      commands::snapshots::restore_snapshot,
+      commands::snapshots::set_snapshot_hotkey,
+      commands::support::exclude_from_cloud_sync,
+      commands::support::generate_support_package,
+      commands::support::get_api_version,
+      commands::support::get_cloud_synced_folders,
+      commands::support::get_data_usage_breakdown,
+      commands::support::get_memory_usage,
+      commands::support::get_service_status,
+      commands::support::get_setup_funnel_report,
+      commands::support::run_self_test,
+      commands::tasks::cancel_task,
+      commands::tasks::get_running_tasks,
+      commands::tasks::get_task_state,
+      commands::textures::apply_texture_packs,
+      commands::textures::get_applied_texture_packs,
+      commands::textures::install_texture_pack_from_url,
+      commands::textures::install_texture_pack,
+      commands::textures::list_texture_packs,
+      commands::tools::list_available_tools,
+      commands::tools::run_tool,
+      commands::version_switch::cancel_version_switch,
+      commands::version_switch::commit_version_switch,
+      commands::version_switch::get_prepared_version_switch,
+      commands::version_switch::prepare_version_switch,
+      commands::versions::add_version_source,
+      commands::versions::cancel_download,
+      commands::versions::check_for_new_tooling_version,
+      commands::versions::deduplicate_versions,
+      commands::versions::download_version,
+      commands::versions::ensure_active_version_still_exists,
+      commands::versions::get_active_tooling_build_info,
+      commands::versions::get_shared_versions_status,
+      commands::versions::get_version_dedup_savings,
+      commands::versions::get_version_disk_usage,
+      commands::versions::get_version_download_url,
+      commands::versions::get_version_folder_dirs,
+      commands::versions::go_to_version_folder,
+      commands::versions::list_channel_releases,
+      commands::versions::list_downloaded_versions,
+      commands::versions::list_downloads,
+      commands::versions::list_version_sources,
+      commands::versions::migrate_version_folder,
+      commands::versions::pause_download,
+      commands::versions::prune_versions,
+      commands::versions::remove_version_source,
+      commands::versions::remove_version,
+      commands::versions::repair_version,
+      commands::versions::resume_download,
+      commands::versions::rollback_active_version,
+      commands::versions::set_shared_versions_enabled,
+      commands::versions::set_version_folder_dir,
+      commands::versions::update_tooling_to_latest,
+      commands::versions::update_version_source,
+      commands::versions::verify_version,
+      commands::window::get_monitors,
+      commands::window::open_dir_in_os,
+      commands::window::open_main_window
+    ]
+    .expect("Could not collect command types"),
+    specta::ts::ExportConfiguration::default().bigint(specta::ts::BigIntExportBehavior::Number),
+    "../src/lib/rpc/bindings.ts",
+  )
+  .expect("Could not export command typescript definitions");
+
   let tauri_setup = tauri::Builder::default()
     .setup(|app| {
+      let paths = paths::PathResolver::new(&app.path_resolver());
+      let launcher_dir = paths.launcher_dir();
+
       // Setup Logging
-      let log_path = app
-        .path_resolver()
-        .app_log_dir()
+      let log_path = paths
+        .log_dir()
         .expect("Could not determine log path")
         .join("app");
       create_dir(&log_path)?;
@@ -126,49 +364,286 @@ fn main() {
       //
       // This allows us to avoid hacky globals, and pass around information (in this case, the config)
       // to the relevant places
-      app.manage(tokio::sync::Mutex::new(
-        config::LauncherConfig::load_config(app.path_resolver().app_config_dir()),
-      ));
+      //
+      // In safe mode the settings file is left alone, without a path the defaults are never saved
+      let safe_mode = safe_mode::requested();
+      let mut config = if safe_mode {
+        log::warn!("Starting in safe mode");
+        let mut config = config::LauncherConfig::load_config(None);
+        if let Some(settings_path) = paths.launcher_file("settings.json") {
+          safe_mode::carry_over_locked_mode(&mut config, &settings_path);
+        }
+        config
+      } else {
+        config::LauncherConfig::load_config(launcher_dir.clone())
+      };
+      if let Err(err) = config_sync::pull(&mut config) {
+        log::warn!("Unable to merge settings from the sync folder: {}", err);
+      }
+      app.manage(paths);
+      app.manage(safe_mode::SafeMode::new(safe_mode));
+      i18n::set_locale(config.locale.clone());
+      if !safe_mode {
+        commands::support::enforce_retention_policy(&app.handle(), &config);
+        commands::config::check_install_directories(&config);
+      }
+      // Locked mode always starts out locked, so restarting the launcher isn't a way around it
+      app.manage(kiosk::KioskLock::new(config.kiosk_pin_hash.is_some()));
+      let remote_api_allowed =
+        permissions::is_granted(&config, permissions::PermissionKind::RemoteApi);
+      let remote_api_options = match (config.remote_api_enabled, &config.remote_api_token) {
+        (Some(true), Some(token)) if remote_api_allowed => Some((
+          config.remote_api_port.unwrap_or(remote_api::DEFAULT_PORT),
+          token.clone(),
+        )),
+        _ => None,
+      };
+      let remote_config_allowed =
+        permissions::is_granted(&config, permissions::PermissionKind::RemoteConfig);
+      let auto_update_tooling = config.auto_update_tooling.unwrap_or(false);
+      app.manage(tokio::sync::Mutex::new(config));
+      app.manage(supervisor::Supervisor::default());
+      app.manage(downloads::DownloadQueue::default());
+      app.manage(remote_api::RemoteApiServer::default());
+      app.manage(commands::binaries::DebugSessions::default());
+      app.manage(commands::binaries::RunningGames::default());
+      app.manage(commands::local_builds::LocalBuildWatchers::default());
+      app.manage(commands::reset::FactoryResetConfirmation::default());
+      app.manage(commands::version_switch::VersionSwitch::default());
+      app.manage(commands::versions::VersionDiskUsageCache::default());
+      if let Some((port, token)) = remote_api_options {
+        let app_handle = app.handle();
+        tauri::async_runtime::spawn(async move {
+          let server = app_handle.state::<remote_api::RemoteApiServer>();
+          if let Err(err) = server.start(app_handle.clone(), port, token).await {
+            log::error!("Unable to start remote control API: {}", err);
+          }
+        });
+      }
+      // Remote config starts out as whatever was cached, and is refreshed in the background
+      app.manage(tokio::sync::Mutex::new(if remote_config_allowed {
+        remote_config::RemoteConfig::load_cached(launcher_dir.clone())
+      } else {
+        remote_config::RemoteConfig::default()
+      }));
+      if !safe_mode {
+        let app_handle = app.handle();
+        supervisor::supervise(&app.handle(), "remoteConfig".to_string(), move || {
+          let app_handle = app_handle.clone();
+          async move {
+            schedule::wait_for_quiet_hours(&app_handle, "fetching the remote config").await;
+            remote_config::refresh(app_handle).await
+          }
+        });
+      }
+      app.manage(tasks::TaskManager::load(launcher_dir));
+      if auto_update_tooling && !safe_mode {
+        tauri::async_runtime::spawn(commands::versions::auto_update_tooling(app.handle()));
+      }
       Ok(())
     })
-    .invoke_handler(tauri::generate_handler![
-      commands::binaries::extract_and_validate_iso,
-      commands::binaries::get_end_of_logs,
-      commands::binaries::launch_game,
-      commands::binaries::open_repl,
-      commands::binaries::run_compiler,
-      commands::binaries::run_decompiler,
-      commands::binaries::update_data_directory,
-      commands::config::delete_old_data_directory,
-      commands::config::finalize_installation,
-      commands::config::get_active_tooling_version_folder,
-      commands::config::get_active_tooling_version,
-      commands::config::get_bypass_requirements,
-      commands::config::get_install_directory,
-      commands::config::get_installed_version_folder,
-      commands::config::get_installed_version,
-      commands::config::get_locale,
-      commands::config::has_old_data_directory,
-      commands::config::is_avx_requirement_met,
-      commands::config::is_game_installed,
-      commands::config::is_opengl_requirement_met,
-      commands::config::reset_to_defaults,
-      commands::config::save_active_version_change,
-      commands::config::set_bypass_requirements,
-      commands::config::set_install_directory,
-      commands::config::set_locale,
-      commands::game::reset_game_settings,
-      commands::game::uninstall_game,
-      commands::logging::frontend_log,
-      commands::support::generate_support_package,
-      commands::versions::download_version,
-      commands::versions::ensure_active_version_still_exists,
-      commands::versions::go_to_version_folder,
-      commands::versions::list_downloaded_versions,
-      commands::versions::remove_version,
-      commands::window::open_dir_in_os,
-      commands::window::open_main_window
-    ])
+    .invoke_handler(commands::rate_limit(safe_mode::guard(kiosk::guard(
+      tauri::generate_handler![
+        commands::artwork::clear_custom_artwork,
+        commands::artwork::get_game_artwork,
+        commands::artwork::set_custom_artwork,
+        commands::backup::create_full_backup,
+        commands::backup::restore_full_backup,
+        commands::binaries::check_shared_library_requirements,
+        commands::binaries::extract_and_validate_iso,
+        commands::binaries::get_end_of_logs,
+        commands::binaries::get_launch_failure_report,
+        commands::binaries::launch_game_with_limit,
+        commands::binaries::launch_game,
+        commands::binaries::open_repl,
+        commands::binaries::resume_install,
+        commands::binaries::run_compiler,
+        commands::binaries::run_decompiler,
+        commands::binaries::update_data_directory,
+        commands::compatibility::check_release_compatibility,
+        commands::config::choose_new_install_dir,
+        commands::config::delete_old_data_directory,
+        commands::config::finalize_installation,
+        commands::config::get_active_tooling_version_folder,
+        commands::config::get_active_tooling_version,
+        commands::config::get_auto_update_tooling,
+        commands::config::get_bypass_requirements,
+        commands::config::get_config_sync_status,
+        commands::config::get_content_hosts,
+        commands::config::get_cpu_capabilities,
+        commands::config::get_dedupe_versions,
+        commands::config::get_download_connections,
+        commands::config::get_game_data_directory,
+        commands::config::get_game_display_options,
+        commands::config::get_game_install_info,
+        commands::config::get_game_launch_actions,
+        commands::config::get_game_process_options,
+        commands::config::get_game_video_options,
+        commands::config::get_github_rate_limit,
+        commands::config::get_install_checkpoint,
+        commands::config::get_install_directory,
+        commands::config::get_install_performance,
+        commands::config::get_install_retry_policy,
+        commands::config::get_installed_version_folder,
+        commands::config::get_installed_version,
+        commands::config::get_launch_on_startup,
+        commands::config::get_locale,
+        commands::config::get_maintain_active_version_link,
+        commands::config::get_metered_behavior,
+        commands::config::get_quiet_hours_status,
+        commands::config::get_quiet_hours,
+        commands::config::get_release_channel,
+        commands::config::get_remote_config,
+        commands::config::get_retention_policy,
+        commands::config::get_session_capture,
+        commands::config::get_tool_environments,
+        commands::config::get_tooling_directory,
+        commands::config::get_unavailable_install_directories,
+        commands::config::get_versions_to_keep,
+        commands::config::get_video_playback_support,
+        commands::config::has_github_token,
+        commands::config::has_old_data_directory,
+        commands::config::is_avx_requirement_met,
+        commands::config::is_feature_enabled,
+        commands::config::is_game_installed,
+        commands::config::is_opengl_requirement_met,
+        commands::config::reset_to_defaults,
+        commands::config::save_active_version_change,
+        commands::config::set_auto_update_tooling,
+        commands::config::set_bypass_requirements,
+        commands::config::set_config_sync_folder,
+        commands::config::set_content_hosts,
+        commands::config::set_dedupe_versions,
+        commands::config::set_download_connections,
+        commands::config::set_game_data_directory,
+        commands::config::set_game_display_options,
+        commands::config::set_game_launch_actions,
+        commands::config::set_game_process_options,
+        commands::config::set_game_video_options,
+        commands::config::set_github_token,
+        commands::config::set_install_directory,
+        commands::config::set_install_retry_policy,
+        commands::config::set_launch_on_startup,
+        commands::config::set_locale,
+        commands::config::set_maintain_active_version_link,
+        commands::config::set_metered_behavior,
+        commands::config::set_quiet_hours,
+        commands::config::set_release_channel,
+        commands::config::set_retention_policy,
+        commands::config::set_session_capture,
+        commands::config::set_tool_environment,
+        commands::config::set_tooling_directory,
+        commands::config::set_versions_to_keep,
+        commands::config::wait_for_install_dir,
+        commands::game::get_playtime_today,
+        commands::game::has_iso_data,
+        commands::game::purge_iso_data,
+        commands::game::reset_game_settings,
+        commands::game::retain_iso_data,
+        commands::game::uninstall_game,
+        commands::history::get_install_history,
+        commands::history::get_session_history,
+        commands::hotfixes::apply_data_hotfixes,
+        commands::hotfixes::check_data_hotfixes,
+        commands::journal::get_game_data_changes,
+        commands::journal::revert_last_change,
+        commands::kiosk::disable_kiosk_mode,
+        commands::kiosk::enable_kiosk_mode,
+        commands::kiosk::get_kiosk_status,
+        commands::kiosk::lock_kiosk,
+        commands::kiosk::set_kiosk_daily_limit,
+        commands::kiosk::unlock_kiosk,
+        commands::libraries::scan_drive_for_installs,
+        commands::local_builds::list_watched_local_builds,
+        commands::local_builds::register_local_build,
+        commands::local_builds::unwatch_local_build,
+        commands::local_builds::watch_local_build,
+        commands::logging::frontend_log,
+        commands::migration::detect_foreign_installs,
+        commands::migration::import_foreign_saves,
+        commands::migration::import_foreign_texture_pack,
+        commands::mod_scripts::install_mod_from_url,
+        commands::mod_scripts::preview_mod_install_script,
+        commands::mod_scripts::run_mod_install_script,
+        commands::news::get_game_feed,
+        commands::permissions::get_permissions,
+        commands::permissions::set_permission,
+        commands::post_install::get_post_install_steps,
+        commands::post_install::set_post_install_steps,
+        commands::releases::cache_releases,
+        commands::releases::get_cached_releases,
+        commands::releases::get_release_changelog,
+        commands::remote_api::get_remote_api_settings,
+        commands::remote_api::regenerate_remote_api_token,
+        commands::remote_api::set_remote_api_enabled,
+        commands::reset::factory_reset,
+        commands::reset::prepare_factory_reset,
+        commands::safe_mode::discard_settings_file,
+        commands::safe_mode::is_safe_mode,
+        commands::safe_mode::restart_launcher,
+        commands::snapshots::create_snapshot,
+        commands::snapshots::get_snapshot_hotkey,
+        commands::snapshots::list_snapshots,
+        commands::snapshots::restore_snapshot,
+        commands::snapshots::set_snapshot_hotkey,
+        commands::support::exclude_from_cloud_sync,
+        commands::support::generate_support_package,
+        commands::support::get_api_version,
+        commands::support::get_cloud_synced_folders,
+        commands::support::get_data_usage_breakdown,
+        commands::support::get_memory_usage,
+        commands::support::get_service_status,
+        commands::support::get_setup_funnel_report,
+        commands::support::run_self_test,
+        commands::tasks::cancel_task,
+        commands::tasks::get_running_tasks,
+        commands::tasks::get_task_state,
+        commands::textures::apply_texture_packs,
+        commands::textures::get_applied_texture_packs,
+        commands::textures::install_texture_pack_from_url,
+        commands::textures::install_texture_pack,
+        commands::textures::list_texture_packs,
+        commands::tools::list_available_tools,
+        commands::tools::run_tool,
+        commands::version_switch::cancel_version_switch,
+        commands::version_switch::commit_version_switch,
+        commands::version_switch::get_prepared_version_switch,
+        commands::version_switch::prepare_version_switch,
+        commands::versions::add_version_source,
+        commands::versions::cancel_download,
+        commands::versions::check_for_new_tooling_version,
+        commands::versions::deduplicate_versions,
+        commands::versions::download_version,
+        commands::versions::ensure_active_version_still_exists,
+        commands::versions::get_active_tooling_build_info,
+        commands::versions::get_shared_versions_status,
+        commands::versions::get_version_dedup_savings,
+        commands::versions::get_version_disk_usage,
+        commands::versions::get_version_download_url,
+        commands::versions::get_version_folder_dirs,
+        commands::versions::go_to_version_folder,
+        commands::versions::list_channel_releases,
+        commands::versions::list_downloaded_versions,
+        commands::versions::list_downloads,
+        commands::versions::list_version_sources,
+        commands::versions::migrate_version_folder,
+        commands::versions::pause_download,
+        commands::versions::prune_versions,
+        commands::versions::remove_version_source,
+        commands::versions::remove_version,
+        commands::versions::repair_version,
+        commands::versions::resume_download,
+        commands::versions::rollback_active_version,
+        commands::versions::set_shared_versions_enabled,
+        commands::versions::set_version_folder_dir,
+        commands::versions::update_tooling_to_latest,
+        commands::versions::update_version_source,
+        commands::versions::verify_version,
+        commands::window::get_monitors,
+        commands::window::open_dir_in_os,
+        commands::window::open_main_window
+      ],
+    ))))
     .build(tauri::generate_context!())
     .map_err(|err| {
       log_crash(None, Some(err));
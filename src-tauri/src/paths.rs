@@ -0,0 +1,299 @@
+// Where everything the launcher manages is kept
+//
+// There are two kinds of locations.  The OS's per-user directories (the launcher's own settings
+// and logs, and the games' settings and saves) are fixed once the app has started, they're looked
+// up once into the `PathResolver` kept in managed state.  Everything else is under the tooling
+// and game data directories the user picks.  Both follow the layout of the functions below:
+//
+//   <tooling>/versions/<folder>/<version>     tooling versions
+//   <tooling>/versions/active                 link to the active version
+//   <tooling>/cache/releases.json             the last listing of official releases
+//   <tooling>/cache/changelogs/<folder>/<version>.json
+//                                             a release's cached changelog
+//   <game data>/active/<game>/data            a game's installed data
+//   <game data>/mods/<game>/<mod>             installed mods
+//   <game data>/snapshots/<game>/<id>/saves   save snapshots
+//   <games user>/<game>/saves                 a game's saves
+//   <games user>/<game>/settings              its settings, ie. `pc-settings.gc`
+//   <games user>/<game>/misc                  and whatever else it keeps
+//   <games user>/<game>/screenshots           screenshots taken in game
+//
+// Attached libraries, drives being scanned and other setups being imported from are laid out the
+// same way, which is why the layout takes the directory it's under rather than reading it from
+// the config.
+use std::path::{Path, PathBuf};
+
+use tauri::api::path::config_dir;
+
+use crate::config::LauncherConfig;
+
+/// The games the launcher can install, which each have their own folders
+pub const GAMES: &[&str] = &["jak1", "jak2", "jak3", "jakx"];
+
+/// `<tooling>/versions`
+pub fn versions_dir(tooling_dir: &Path) -> PathBuf {
+  tooling_dir.join("versions")
+}
+
+/// `<tooling>/versions/<folder>`
+pub fn version_folder_dir(tooling_dir: &Path, version_folder: &str) -> PathBuf {
+  versions_dir(tooling_dir).join(version_folder)
+}
+
+/// `<tooling>/versions/active`
+pub fn active_version_link(tooling_dir: &Path) -> PathBuf {
+  versions_dir(tooling_dir).join("active")
+}
+
+/// `<tooling>/cache`
+pub fn tooling_cache_dir(tooling_dir: &Path) -> PathBuf {
+  tooling_dir.join("cache")
+}
+
+/// `<tooling>/cache/releases.json`
+pub fn release_cache_file(tooling_dir: &Path) -> PathBuf {
+  tooling_cache_dir(tooling_dir).join("releases.json")
+}
+
+/// `<tooling>/cache/changelogs/<folder>/<version>.json`
+pub fn changelog_cache_file(tooling_dir: &Path, version_folder: &str, version: &str) -> PathBuf {
+  tooling_cache_dir(tooling_dir)
+    .join("changelogs")
+    .join(version_folder)
+    .join(format!("{}.json", version))
+}
+
+/// `<game data>/active`
+pub fn installed_games_dir(game_data_dir: &Path) -> PathBuf {
+  game_data_dir.join("active")
+}
+
+/// `<game data>/active/<game>`
+pub fn game_install_dir(game_data_dir: &Path, game_name: &str) -> PathBuf {
+  installed_games_dir(game_data_dir).join(game_name)
+}
+
+/// `<game data>/active/<game>/data`
+pub fn game_data_dir(game_data_dir: &Path, game_name: &str) -> PathBuf {
+  game_install_dir(game_data_dir, game_name).join("data")
+}
+
+/// `<game data>/mods`
+pub fn mods_dir(game_data_dir: &Path) -> PathBuf {
+  game_data_dir.join("mods")
+}
+
+/// `<game data>/snapshots/<game>`
+pub fn snapshots_dir(game_data_dir: &Path, game_name: &str) -> PathBuf {
+  game_data_dir.join("snapshots").join(game_name)
+}
+
+/// `<game data>/snapshots/<game>/<id>/saves`, given the snapshot's folder
+pub fn snapshot_saves_dir(snapshot_dir: &Path) -> PathBuf {
+  snapshot_dir.join("saves")
+}
+
+/// `<games user>/<game>`
+pub fn game_user_dir(games_user_dir: &Path, game_name: &str) -> PathBuf {
+  games_user_dir.join(game_name)
+}
+
+/// `<games user>/<game>/saves`
+pub fn saves_dir(games_user_dir: &Path, game_name: &str) -> PathBuf {
+  game_user_dir(games_user_dir, game_name).join("saves")
+}
+
+/// `<games user>/<game>/settings`
+pub fn game_settings_dir(games_user_dir: &Path, game_name: &str) -> PathBuf {
+  game_user_dir(games_user_dir, game_name).join("settings")
+}
+
+/// `<games user>/<game>/misc`
+pub fn game_misc_dir(games_user_dir: &Path, game_name: &str) -> PathBuf {
+  game_user_dir(games_user_dir, game_name).join("misc")
+}
+
+/// `<games user>/<game>/screenshots`
+pub fn screenshots_dir(games_user_dir: &Path, game_name: &str) -> PathBuf {
+  game_user_dir(games_user_dir, game_name).join("screenshots")
+}
+
+/// Every launcher-managed location, see the module documentation for the layout
+pub struct PathResolver {
+  launcher_dir: Option<PathBuf>,
+  log_dir: Option<PathBuf>,
+  games_user_dir: Option<PathBuf>,
+}
+
+impl PathResolver {
+  pub fn new(resolver: &tauri::PathResolver) -> Self {
+    Self {
+      launcher_dir: resolver.app_config_dir(),
+      log_dir: resolver.app_log_dir(),
+      games_user_dir: config_dir().map(|dir| dir.join("OpenGOAL")),
+    }
+  }
+
+  /// The launcher's own settings and state
+  pub fn launcher_dir(&self) -> Option<PathBuf> {
+    self.launcher_dir.clone()
+  }
+
+  /// A file the launcher keeps its state in, ie. `settings.json`
+  pub fn launcher_file(&self, name: &str) -> Option<PathBuf> {
+    self.launcher_dir.as_ref().map(|dir| dir.join(name))
+  }
+
+  pub fn log_dir(&self) -> Option<PathBuf> {
+    self.log_dir.clone()
+  }
+
+  /// A log the tooling or the launcher writes, ie. `game.log`
+  pub fn log_file(&self, name: &str) -> Option<PathBuf> {
+    self.log_dir.as_ref().map(|dir| dir.join(name))
+  }
+
+  /// Where the games keep their settings and saves, one folder per game
+  pub fn games_user_dir(&self) -> Option<PathBuf> {
+    self.games_user_dir.clone()
+  }
+
+  fn in_games_user_dir(&self, path: impl FnOnce(&Path) -> PathBuf) -> Option<PathBuf> {
+    self.games_user_dir.as_deref().map(path)
+  }
+
+  pub fn saves_dir(&self, game_name: &str) -> Option<PathBuf> {
+    self.in_games_user_dir(|dir| saves_dir(dir, game_name))
+  }
+
+  pub fn game_settings_dir(&self, game_name: &str) -> Option<PathBuf> {
+    self.in_games_user_dir(|dir| game_settings_dir(dir, game_name))
+  }
+
+  pub fn game_misc_dir(&self, game_name: &str) -> Option<PathBuf> {
+    self.in_games_user_dir(|dir| game_misc_dir(dir, game_name))
+  }
+
+  pub fn screenshots_dir(&self, game_name: &str) -> Option<PathBuf> {
+    self.in_games_user_dir(|dir| screenshots_dir(dir, game_name))
+  }
+
+  /// The game's `pc-settings.gc`
+  pub fn game_settings_file(&self, game_name: &str) -> Option<PathBuf> {
+    self
+      .game_settings_dir(game_name)
+      .map(|dir| dir.join("pc-settings.gc"))
+  }
+
+  pub fn versions_dir(&self, config: &LauncherConfig) -> Option<PathBuf> {
+    config.tooling_path().map(|dir| versions_dir(&dir))
+  }
+
+  pub fn mods_dir(&self, config: &LauncherConfig) -> Option<PathBuf> {
+    config.game_data_path().map(|dir| mods_dir(&dir))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Other setups, and older launchers, expect these exact locations
+  #[test]
+  fn tooling_layout() {
+    let tooling = Path::new("tooling");
+    assert_eq!(versions_dir(tooling), Path::new("tooling/versions"));
+    assert_eq!(
+      version_folder_dir(tooling, "official"),
+      Path::new("tooling/versions/official")
+    );
+    assert_eq!(
+      active_version_link(tooling),
+      Path::new("tooling/versions/active")
+    );
+    assert_eq!(
+      release_cache_file(tooling),
+      Path::new("tooling/cache/releases.json")
+    );
+    assert_eq!(
+      changelog_cache_file(tooling, "official", "v0.1.38"),
+      Path::new("tooling/cache/changelogs/official/v0.1.38.json")
+    );
+  }
+
+  #[test]
+  fn game_data_layout() {
+    let game_data = Path::new("data");
+    assert_eq!(installed_games_dir(game_data), Path::new("data/active"));
+    assert_eq!(
+      game_install_dir(game_data, "jak1"),
+      Path::new("data/active/jak1")
+    );
+    assert_eq!(
+      game_data_dir(game_data, "jak1"),
+      Path::new("data/active/jak1/data")
+    );
+    assert_eq!(mods_dir(game_data), Path::new("data/mods"));
+    assert_eq!(
+      snapshots_dir(game_data, "jak2"),
+      Path::new("data/snapshots/jak2")
+    );
+    assert_eq!(
+      snapshot_saves_dir(&snapshots_dir(game_data, "jak2").join("1")),
+      Path::new("data/snapshots/jak2/1/saves")
+    );
+  }
+
+  #[test]
+  fn games_user_layout() {
+    let user = Path::new("OpenGOAL");
+    assert_eq!(game_user_dir(user, "jak1"), Path::new("OpenGOAL/jak1"));
+    assert_eq!(saves_dir(user, "jak1"), Path::new("OpenGOAL/jak1/saves"));
+    assert_eq!(
+      game_settings_dir(user, "jak1"),
+      Path::new("OpenGOAL/jak1/settings")
+    );
+    assert_eq!(game_misc_dir(user, "jak1"), Path::new("OpenGOAL/jak1/misc"));
+    assert_eq!(
+      screenshots_dir(user, "jak1"),
+      Path::new("OpenGOAL/jak1/screenshots")
+    );
+  }
+
+  #[test]
+  fn resolver_follows_layout() {
+    let resolver = PathResolver {
+      launcher_dir: Some(PathBuf::from("launcher")),
+      log_dir: Some(PathBuf::from("logs")),
+      games_user_dir: Some(PathBuf::from("OpenGOAL")),
+    };
+    assert_eq!(
+      resolver.launcher_file("settings.json"),
+      Some(PathBuf::from("launcher/settings.json"))
+    );
+    assert_eq!(
+      resolver.log_file("game.log"),
+      Some(PathBuf::from("logs/game.log"))
+    );
+    assert_eq!(
+      resolver.saves_dir("jak1"),
+      Some(saves_dir(Path::new("OpenGOAL"), "jak1"))
+    );
+    assert_eq!(
+      resolver.game_settings_file("jak1"),
+      Some(PathBuf::from("OpenGOAL/jak1/settings/pc-settings.gc"))
+    );
+  }
+
+  #[test]
+  fn resolver_without_user_dirs() {
+    let resolver = PathResolver {
+      launcher_dir: None,
+      log_dir: None,
+      games_user_dir: None,
+    };
+    assert_eq!(resolver.launcher_file("settings.json"), None);
+    assert_eq!(resolver.saves_dir("jak1"), None);
+    assert_eq!(resolver.game_settings_file("jak1"), None);
+  }
+}
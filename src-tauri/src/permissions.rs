@@ -0,0 +1,96 @@
+// What the user has agreed to the launcher doing with their data, or on their behalf
+//
+// Every subsystem that sends something off the machine, or lets something else drive the
+// launcher, checks its permission here before doing so, rather than each one keeping its own
+// toggle.  Grants are kept in the config along with when they last changed, and every change is
+// logged, so it's always possible to tell what was allowed when.
+//
+// Nothing sends telemetry or uploads support bundles yet, those kinds exist so consent can be
+// asked for (and is enforced) from the start once something does.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{commands::CommandError, config::LauncherConfig};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum PermissionKind {
+  /// Sending anonymous usage statistics
+  Telemetry,
+  /// Uploading support bundles, which contain logs and system details
+  BundleUploads,
+  /// Fetching the remote config published by the maintainers
+  RemoteConfig,
+  /// Letting other local tools drive the launcher through the remote control API
+  RemoteApi,
+}
+
+impl PermissionKind {
+  pub const ALL: &'static [PermissionKind] = &[
+    PermissionKind::Telemetry,
+    PermissionKind::BundleUploads,
+    PermissionKind::RemoteConfig,
+    PermissionKind::RemoteApi,
+  ];
+
+  // Only what the launcher already did before asking is allowed without asking
+  fn granted_by_default(&self, config: &LauncherConfig) -> bool {
+    match self {
+      PermissionKind::Telemetry | PermissionKind::BundleUploads => false,
+      PermissionKind::RemoteConfig => true,
+      PermissionKind::RemoteApi => config.remote_api_enabled.unwrap_or(false),
+    }
+  }
+
+  fn description(&self) -> &'static str {
+    match self {
+      PermissionKind::Telemetry => "send usage statistics",
+      PermissionKind::BundleUploads => "upload support bundles",
+      PermissionKind::RemoteConfig => "fetch the remote config",
+      PermissionKind::RemoteApi => "let other tools control the launcher",
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionGrant {
+  pub granted: bool,
+  pub changed_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionStatus {
+  pub kind: PermissionKind,
+  pub granted: bool,
+  /// When the user last granted or revoked it, `None` if they never did and the default applies
+  pub changed_at: Option<String>,
+}
+
+pub fn status(config: &LauncherConfig, kind: PermissionKind) -> PermissionStatus {
+  let grant = config
+    .permissions
+    .as_ref()
+    .and_then(|permissions| permissions.get(&kind));
+  PermissionStatus {
+    kind,
+    granted: grant.map_or_else(|| kind.granted_by_default(config), |grant| grant.granted),
+    changed_at: grant.map(|grant| grant.changed_at.clone()),
+  }
+}
+
+pub fn is_granted(config: &LauncherConfig, kind: PermissionKind) -> bool {
+  status(config, kind).granted
+}
+
+/// Fails unless the user allowed `kind`, for subsystems to call before doing anything it covers
+pub fn require(config: &LauncherConfig, kind: PermissionKind) -> Result<(), CommandError> {
+  if is_granted(config, kind) {
+    return Ok(());
+  }
+  Err(CommandError::PermissionDenied(format!(
+    "The launcher isn't allowed to {}, this can be changed in the privacy settings",
+    kind.description()
+  )))
+}
@@ -0,0 +1,72 @@
+// Cumulative playtime per day and game, recorded whenever a launched game exits
+//
+// Kept in `playtime.json` in the config directory, only the last `RETAINED_DAYS` days are kept
+// around.  This is what locked mode's daily limit is checked against.
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use crate::{
+  paths::PathResolver,
+  util::file::{read_json, update_json},
+};
+
+const RETAINED_DAYS: usize = 30;
+
+// date (YYYY-MM-DD) -> game -> seconds
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PlaytimeLog(BTreeMap<String, BTreeMap<String, u64>>);
+
+fn log_path(app_handle: &tauri::AppHandle) -> Option<PathBuf> {
+  app_handle
+    .state::<PathResolver>()
+    .launcher_file("playtime.json")
+}
+
+fn load(path: &Path) -> PlaytimeLog {
+  read_json(path).unwrap_or_default()
+}
+
+fn today() -> String {
+  chrono::Local::now().format("%Y-%m-%d").to_string()
+}
+
+pub fn record(app_handle: &tauri::AppHandle, game_name: &str, seconds: u64) {
+  let path = match log_path(app_handle) {
+    Some(path) => path,
+    None => return,
+  };
+  let result = update_json(&path, |log: &mut PlaytimeLog| {
+    *log
+      .0
+      .entry(today())
+      .or_default()
+      .entry(game_name.to_string())
+      .or_default() += seconds;
+    // Dates sort chronologically, so the oldest days come first
+    while log.0.len() > RETAINED_DAYS {
+      match log.0.keys().next().cloned() {
+        Some(oldest) => log.0.remove(&oldest),
+        None => break,
+      };
+    }
+  });
+  if let Err(err) = result {
+    log::warn!("Unable to record playtime: {}", err);
+  }
+}
+
+/// Seconds played today, across all games
+pub fn today_total(app_handle: &tauri::AppHandle) -> u64 {
+  today_by_game(app_handle).values().sum()
+}
+
+/// Seconds played today, per game
+pub fn today_by_game(app_handle: &tauri::AppHandle) -> BTreeMap<String, u64> {
+  match log_path(app_handle) {
+    Some(path) => load(&path).0.remove(&today()).unwrap_or_default(),
+    None => BTreeMap::new(),
+  }
+}
@@ -0,0 +1,223 @@
+// Optional HTTP API for driving the launcher from other local tools (Stream Deck plugins,
+// stream overlays and the like)
+//
+// It's off by default, only ever listens on the loopback interface, and every request has to
+// carry the token from the config as `Authorization: Bearer <token>`.  The API is deliberately
+// small:
+//
+// - `GET /status` - launcher version, active tooling version, installed games and running tasks
+// - `GET /tasks/<id>` - the state of a tracked task, ie. to follow an install's progress
+// - `POST /games/<game>/launch` - launch an installed game
+use std::convert::Infallible;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+
+use hyper::{
+  header::{AUTHORIZATION, CONTENT_TYPE},
+  server::{conn::AddrIncoming, Builder},
+  service::{make_service_fn, service_fn},
+  Body, Method, Request, Response, Server, StatusCode,
+};
+use serde::Serialize;
+use tauri::Manager;
+
+use crate::{
+  commands::CommandError,
+  config::{LauncherConfig, SupportedGame},
+  supervisor::supervise,
+  tasks::{CancellationToken, TaskManager, TaskState},
+  util::crypto::{constant_time_eq, random_hex},
+};
+
+pub const DEFAULT_PORT: u16 = 47820;
+
+#[derive(Default)]
+pub struct RemoteApiServer {
+  shutdown: std::sync::Mutex<Option<CancellationToken>>,
+}
+
+impl RemoteApiServer {
+  /// Starts listening on `port`, stopping the previous server if there is one
+  pub async fn start(
+    &self,
+    app_handle: tauri::AppHandle,
+    port: u16,
+    token: String,
+  ) -> Result<(), hyper::Error> {
+    self.stop();
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    // Bound here so the caller finds out about a port that's taken, restarts bind it again
+    let bound = Arc::new(std::sync::Mutex::new(Some(Server::try_bind(&addr)?)));
+    let shutdown = CancellationToken::default();
+    if let Ok(mut current) = self.shutdown.lock() {
+      *current = Some(shutdown.clone());
+    }
+    log::info!("Remote control API listening on {}", addr);
+    let service_app_handle = app_handle.clone();
+    supervise(&app_handle, "remoteApi".to_string(), move || {
+      let builder = bound.lock().ok().and_then(|mut bound| bound.take());
+      serve(
+        service_app_handle.clone(),
+        addr,
+        token.clone(),
+        builder,
+        shutdown.clone(),
+      )
+    });
+    Ok(())
+  }
+
+  pub fn stop(&self) {
+    if let Some(shutdown) = self.shutdown.lock().ok().and_then(|mut s| s.take()) {
+      log::info!("Stopping remote control API");
+      shutdown.cancel();
+    }
+  }
+}
+
+async fn serve(
+  app_handle: tauri::AppHandle,
+  addr: SocketAddr,
+  token: String,
+  builder: Option<Builder<AddrIncoming>>,
+  shutdown: CancellationToken,
+) -> Result<(), String> {
+  let builder = match builder {
+    Some(builder) => builder,
+    None => Server::try_bind(&addr).map_err(|err| err.to_string())?,
+  };
+  let make_service = make_service_fn(move |_| {
+    let app_handle = app_handle.clone();
+    let token = token.clone();
+    async move {
+      Ok::<_, Infallible>(service_fn(move |req| {
+        handle(app_handle.clone(), token.clone(), req)
+      }))
+    }
+  });
+  builder
+    .serve(make_service)
+    .with_graceful_shutdown(async move { shutdown.cancelled().await })
+    .await
+    .map_err(|err| err.to_string())
+}
+
+pub fn generate_token() -> String {
+  random_hex(24)
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Status {
+  launcher_version: String,
+  active_version: Option<String>,
+  installed_games: Vec<String>,
+  running_tasks: Vec<TaskState>,
+}
+
+fn json_response(status: StatusCode, body: &impl Serialize) -> Response<Body> {
+  let body = serde_json::to_string(body).unwrap_or_default();
+  Response::builder()
+    .status(status)
+    .header(CONTENT_TYPE, "application/json")
+    .body(Body::from(body))
+    .unwrap_or_default()
+}
+
+fn error_response(status: StatusCode, error: CommandError) -> Response<Body> {
+  json_response(status, &error)
+}
+
+async fn handle(
+  app_handle: tauri::AppHandle,
+  token: String,
+  req: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+  let authorized = req
+    .headers()
+    .get(AUTHORIZATION)
+    .and_then(|value| value.to_str().ok())
+    .and_then(|value| value.strip_prefix("Bearer "))
+    .map_or(false, |provided| {
+      constant_time_eq(provided.as_bytes(), token.as_bytes())
+    });
+  if !authorized {
+    return Ok(error_response(
+      StatusCode::UNAUTHORIZED,
+      CommandError::PermissionDenied("Missing or invalid API token".to_string()),
+    ));
+  }
+
+  let segments: Vec<&str> = req.uri().path().trim_matches('/').split('/').collect();
+  let response = match (req.method(), segments.as_slice()) {
+    (&Method::GET, ["status"]) => json_response(StatusCode::OK, &status(&app_handle).await),
+    (&Method::GET, ["tasks", id]) => match app_handle.state::<TaskManager>().get(id) {
+      Some(task) => json_response(StatusCode::OK, &task),
+      None => error_response(
+        StatusCode::NOT_FOUND,
+        CommandError::RemoteApi(format!("No task with id '{}'", id)),
+      ),
+    },
+    (&Method::POST, ["games", game_name, "launch"]) => {
+      launch(&app_handle, game_name.to_string()).await
+    }
+    _ => error_response(
+      StatusCode::NOT_FOUND,
+      CommandError::RemoteApi("Unknown endpoint".to_string()),
+    ),
+  };
+  Ok(response)
+}
+
+async fn status(app_handle: &tauri::AppHandle) -> Status {
+  let config = app_handle.state::<tokio::sync::Mutex<LauncherConfig>>();
+  let config_lock = config.lock().await;
+  let mut installed_games: Vec<String> = config_lock
+    .games
+    .iter()
+    .filter(|(_, game)| game.is_installed)
+    .map(|(game, _)| game.internal_str().to_string())
+    .collect();
+  installed_games.sort();
+  Status {
+    launcher_version: app_handle.package_info().version.to_string(),
+    active_version: config_lock.active_version.clone(),
+    installed_games,
+    running_tasks: app_handle.state::<TaskManager>().running(),
+  }
+}
+
+async fn launch(app_handle: &tauri::AppHandle, game_name: String) -> Response<Body> {
+  if game_name.parse::<SupportedGame>().is_err() {
+    return error_response(
+      StatusCode::NOT_FOUND,
+      CommandError::GameManagement(format!("Unknown game '{}'", game_name)),
+    );
+  }
+  let installed = app_handle
+    .state::<tokio::sync::Mutex<LauncherConfig>>()
+    .lock()
+    .await
+    .is_game_installed(&game_name);
+  if !installed {
+    return error_response(
+      StatusCode::CONFLICT,
+      CommandError::GameManagement(format!("'{}' is not installed", game_name)),
+    );
+  }
+  log::info!("Launching '{}' through the remote control API", game_name);
+  match crate::commands::binaries::launch_game(
+    app_handle.state(),
+    app_handle.clone(),
+    game_name,
+    false,
+  )
+  .await
+  {
+    Ok(()) => Response::builder()
+      .status(StatusCode::ACCEPTED)
+      .body(Body::empty())
+      .unwrap_or_default(),
+    Err(err) => error_response(StatusCode::INTERNAL_SERVER_ERROR, err),
+  }
+}
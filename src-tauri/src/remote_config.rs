@@ -0,0 +1,266 @@
+// Remote configuration that lets the maintainers toggle launcher features (disable a broken
+// download host, gate a new pipeline) without shipping a release
+//
+// The configuration is a JSON document signed with minisign, with the key launcher releases are
+// signed with. It is fetched at startup and cached locally alongside its signature - the
+// signature is checked every time the config is read, so a tampered cache is treated the same
+// way as no config at all.
+//
+// If nothing valid is available (offline on first run, bad signature), the defaults are used
+// which leave every feature in its normal state.
+//
+// New tooling releases can be rolled out in stages, by only offering them to a percentage of
+// installs at first.  Which installs is decided by hashing the release's tag with an anonymous
+// id each install makes for itself, so the same ones keep getting it and nothing identifying
+// has to be sent anywhere.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use minisign_verify::{PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::Manager;
+
+use crate::{
+  config::LauncherConfig,
+  paths::PathResolver,
+  permissions::{self, PermissionKind},
+};
+
+const REMOTE_CONFIG_URL: &str =
+  "https://raw.githubusercontent.com/open-goal/launcher/main/remote-config.json";
+
+// The key the project signs launcher releases with, the updater's `pubkey` in
+// `.tauri/release-config.json` (minisign key id 39CDB032FF0A4192)
+const PROJECT_PUBLIC_KEY: &str = "RWSSQQr/MrDNOQDE+4R8S74lLu4r6Fu6s7QSXaxjWXtaeLnzyg9RJ8c9";
+
+#[derive(Debug, thiserror::Error)]
+pub enum RemoteConfigError {
+  #[error(transparent)]
+  IO(#[from] std::io::Error),
+  #[error(transparent)]
+  NetworkRequest(#[from] reqwest::Error),
+  #[error(transparent)]
+  JSONError(#[from] serde_json::Error),
+  #[error("signature verification failed: {0}")]
+  Signature(String),
+}
+
+// Either a plain minisign signature, or one made with `tauri signer sign` which wraps it in base64
+fn decode_signature(signature: &str) -> Result<Signature, RemoteConfigError> {
+  let signature = match base64::engine::general_purpose::STANDARD.decode(signature.trim()) {
+    Ok(decoded) => {
+      String::from_utf8(decoded).map_err(|err| RemoteConfigError::Signature(err.to_string()))?
+    }
+    Err(_) => signature.to_string(),
+  };
+  Signature::decode(&signature).map_err(|err| RemoteConfigError::Signature(err.to_string()))
+}
+
+/// Checks `content` against its minisign `signature` made with the project's key, this is used
+/// for anything else the project publishes for the launcher to apply as well
+pub fn verify_signature(content: &[u8], signature: &str) -> Result<(), RemoteConfigError> {
+  let public_key = PublicKey::from_base64(PROJECT_PUBLIC_KEY)
+    .map_err(|err| RemoteConfigError::Signature(err.to_string()))?;
+  let signature = decode_signature(signature)?;
+  public_key
+    .verify(content, &signature, false)
+    .map_err(|err| RemoteConfigError::Signature(err.to_string()))
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteConfig {
+  #[serde(default)]
+  pub features: HashMap<String, bool>,
+  /// Hosts that downloads must not be attempted from
+  #[serde(default)]
+  pub disabled_download_hosts: Vec<String>,
+  /// Version folder -> download URL template, replacing the built-in source's default
+  #[serde(default)]
+  pub download_url_templates: HashMap<String, String>,
+  /// Release tag -> percentage of installs it's offered to, for tooling releases that are still
+  /// being rolled out.  Releases that aren't listed are offered to everyone
+  #[serde(default)]
+  pub release_rollouts: HashMap<String, u8>,
+}
+
+// Where an install falls for a release, from 0 to 99
+fn rollout_bucket(tag: &str, rollout_id: &str) -> u32 {
+  let digest = Sha256::digest(format!("{}:{}", tag, rollout_id).as_bytes());
+  u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]) % 100
+}
+
+impl RemoteConfig {
+  pub fn is_feature_enabled(&self, feature: &str, default: bool) -> bool {
+    *self.features.get(feature).unwrap_or(&default)
+  }
+
+  /// Whether the release `tag` is offered to the install with `rollout_id` yet.  Each release
+  /// picks different installs so it's not the same ones trying everything first, and raising
+  /// the percentage only ever adds installs
+  pub fn is_release_rolled_out(&self, tag: &str, rollout_id: &str) -> bool {
+    match self.release_rollouts.get(tag) {
+      Some(percentage) => rollout_bucket(tag, rollout_id) < u32::from(*percentage),
+      None => true,
+    }
+  }
+
+  pub fn is_download_host_disabled(&self, url: &str) -> bool {
+    let host = match reqwest::Url::parse(url) {
+      Ok(url) => url.host_str().map(|host| host.to_lowercase()),
+      Err(_) => None,
+    };
+    match host {
+      Some(host) => self
+        .disabled_download_hosts
+        .iter()
+        .any(|disabled| disabled.to_lowercase() == host),
+      None => false,
+    }
+  }
+
+  fn cache_paths(config_dir: &Path) -> (PathBuf, PathBuf) {
+    (
+      config_dir.join("remote-config.json"),
+      config_dir.join("remote-config.json.minisig"),
+    )
+  }
+
+  fn verify_and_parse(content: &str, signature: &str) -> Result<Self, RemoteConfigError> {
+    verify_signature(content.as_bytes(), signature)?;
+    Ok(serde_json::from_str(content)?)
+  }
+
+  /// Loads the last successfully fetched config, falling back to the defaults
+  pub fn load_cached(config_dir: Option<PathBuf>) -> Self {
+    let config_dir = match config_dir {
+      Some(dir) => dir,
+      None => return Self::default(),
+    };
+    let (content_path, signature_path) = Self::cache_paths(&config_dir);
+    if !content_path.exists() || !signature_path.exists() {
+      return Self::default();
+    }
+    let result = std::fs::read_to_string(&content_path)
+      .and_then(|content| Ok((content, std::fs::read_to_string(&signature_path)?)))
+      .map_err(RemoteConfigError::from)
+      .and_then(|(content, signature)| Self::verify_and_parse(&content, &signature));
+    match result {
+      Ok(config) => {
+        log::info!("Loaded cached remote config");
+        config
+      }
+      Err(err) => {
+        log::warn!("Ignoring cached remote config: {}", err);
+        Self::default()
+      }
+    }
+  }
+
+  async fn fetch(config_dir: Option<PathBuf>) -> Result<Self, RemoteConfigError> {
+    let client = reqwest::Client::new();
+    let content = client
+      .get(REMOTE_CONFIG_URL)
+      .send()
+      .await?
+      .error_for_status()?
+      .text()
+      .await?;
+    let signature = client
+      .get(format!("{}.minisig", REMOTE_CONFIG_URL))
+      .send()
+      .await?
+      .error_for_status()?
+      .text()
+      .await?;
+    let config = Self::verify_and_parse(&content, &signature)?;
+    if let Some(config_dir) = config_dir {
+      let (content_path, signature_path) = Self::cache_paths(&config_dir);
+      std::fs::write(content_path, &content)?;
+      std::fs::write(signature_path, &signature)?;
+    }
+    Ok(config)
+  }
+}
+
+/// Fetches the latest remote config in the background, replacing the managed one on success.
+/// Fails if it couldn't be fetched, the cached one stays in use meanwhile
+pub async fn refresh(app_handle: tauri::AppHandle) -> Result<(), String> {
+  let config = app_handle.state::<tokio::sync::Mutex<LauncherConfig>>();
+  if !permissions::is_granted(&*config.lock().await, PermissionKind::RemoteConfig) {
+    log::info!("Not fetching remote config, it isn't allowed");
+    return Ok(());
+  }
+  match RemoteConfig::fetch(app_handle.state::<PathResolver>().launcher_dir()).await {
+    Ok(new_config) => {
+      log::info!("Fetched remote config");
+      let remote_config = app_handle.state::<tokio::sync::Mutex<RemoteConfig>>();
+      let mut remote_config_lock = remote_config.lock().await;
+      *remote_config_lock = new_config;
+      if let Err(err) = app_handle.emit_all("remoteConfigUpdated", remote_config_lock.clone()) {
+        log::warn!("Unable to emit remote config update: {}", err);
+      }
+      Ok(())
+    }
+    Err(err) => {
+      log::warn!(
+        "Unable to fetch remote config, using cached config: {}",
+        err
+      );
+      Err(format!("unable to fetch remote config: {}", err))
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Installs have to stay in the bucket they're in, or raising a rollout would take it back
+  #[test]
+  fn rollout_buckets_are_stable() {
+    for (tag, rollout_id, bucket) in [
+      ("v0.2.0", "0123456789abcdef", 67),
+      ("v0.2.1", "0123456789abcdef", 46),
+      ("v0.2.0", "fedcba9876543210", 91),
+    ] {
+      assert_eq!(
+        rollout_bucket(tag, rollout_id),
+        bucket,
+        "{} {}",
+        tag,
+        rollout_id
+      );
+    }
+  }
+
+  #[test]
+  fn releases_are_rolled_out_below_their_percentage() {
+    let remote_config = RemoteConfig {
+      release_rollouts: HashMap::from([
+        ("v0.2.0".to_string(), 67),
+        ("v0.2.1".to_string(), 47),
+        ("v0.2.2".to_string(), 0),
+        ("v0.2.3".to_string(), 100),
+      ]),
+      ..Default::default()
+    };
+    for (tag, rolled_out) in [
+      ("v0.2.0", false),
+      ("v0.2.1", true),
+      ("v0.2.2", false),
+      ("v0.2.3", true),
+      ("v0.1.38", true),
+    ] {
+      assert_eq!(
+        remote_config.is_release_rolled_out(tag, "0123456789abcdef"),
+        rolled_out,
+        "{}",
+        tag
+      );
+    }
+  }
+}
@@ -0,0 +1,127 @@
+// Safe mode, for when a corrupted settings file or a crashing GPU probe keeps the launcher from
+// getting as far as showing its window
+//
+// Started with `--safe-mode` (or with `OPENGOAL_LAUNCHER_SAFE_MODE` set, or by holding Shift on
+// the splash screen, which restarts the launcher with the flag), the settings file isn't loaded
+// and nothing is written back to it, the requirement probes and background work are skipped, and
+// only commands that read state or help getting out of the broken state (exporting logs,
+// discarding the settings file) are accepted.  Locked mode is the exception, its PIN is still
+// read from the settings file so safe mode isn't a way around it.
+//
+// Like locked mode this is enforced by wrapping the invoke handler, new commands are unavailable
+// in safe mode until they're added to the allowlist below.
+use std::path::Path;
+
+use tauri::{Invoke, Manager, Runtime};
+
+use crate::{
+  commands::{CommandError, READ_ONLY_PREFIXES},
+  config::LauncherConfig,
+  util::file::read_json,
+};
+
+pub const SAFE_MODE_ARG: &str = "--safe-mode";
+pub const SAFE_MODE_ENV: &str = "OPENGOAL_LAUNCHER_SAFE_MODE";
+
+// Commands that are allowed in safe mode, on top of anything that only reads state
+const ALLOWED_IN_SAFE_MODE: &[&str] = &[
+  "discard_settings_file",
+  "frontend_log",
+  "generate_support_package",
+  "open_dir_in_os",
+  "open_main_window",
+  "restart_launcher",
+];
+// These read state but probe the system to do so, which is what safe mode is trying to avoid.
+// Anything else that probes already isn't allowed, it doesn't only read state
+const PROBES: &[&str] = &[
+  "get_cpu_capabilities",
+  "get_video_playback_support",
+  "is_avx_requirement_met",
+  "is_opengl_requirement_met",
+];
+
+pub struct SafeMode {
+  enabled: bool,
+}
+
+impl SafeMode {
+  pub fn new(enabled: bool) -> Self {
+    Self { enabled }
+  }
+
+  pub fn is_enabled(&self) -> bool {
+    self.enabled
+  }
+}
+
+/// Whether the launcher was asked to start in safe mode
+pub fn requested() -> bool {
+  std::env::args().any(|arg| arg == SAFE_MODE_ARG)
+    || std::env::var_os(SAFE_MODE_ENV).map_or(false, |value| !value.is_empty())
+}
+
+/// Takes locked mode's PIN (and how often it was guessed wrong) and daily limit from the settings
+/// file into the defaults safe mode starts with
+pub fn carry_over_locked_mode(config: &mut LauncherConfig, settings_path: &Path) {
+  // The settings file as plain JSON, without trusting it to be a valid config
+  let settings: serde_json::Value = match read_json(settings_path) {
+    Some(settings) => settings,
+    None => return,
+  };
+  config.kiosk_pin_hash = settings["kioskPinHash"].as_str().map(str::to_string);
+  config.kiosk_failed_attempts = settings["kioskFailedAttempts"]
+    .as_u64()
+    .and_then(|count| u32::try_from(count).ok());
+  config.kiosk_retry_at = settings["kioskRetryAt"].as_u64();
+  config.kiosk_daily_limit_minutes = settings["kioskDailyLimitMinutes"]
+    .as_u64()
+    .and_then(|minutes| u32::try_from(minutes).ok());
+}
+
+fn is_allowed_in_safe_mode(command: &str) -> bool {
+  if PROBES.contains(&command) {
+    return false;
+  }
+  ALLOWED_IN_SAFE_MODE.contains(&command)
+    || READ_ONLY_PREFIXES
+      .iter()
+      .any(|prefix| command.starts_with(prefix))
+}
+
+/// Wraps the invoke handler, rejecting anything that isn't allowed in safe mode
+pub fn guard<R: Runtime>(
+  handler: impl Fn(Invoke<R>) + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) + Send + Sync + 'static {
+  move |invoke| {
+    let enabled = invoke.message.window_ref().state::<SafeMode>().is_enabled();
+    if enabled && !is_allowed_in_safe_mode(invoke.message.command()) {
+      log::warn!("Rejected '{}' in safe mode", invoke.message.command());
+      invoke.resolver.reject(CommandError::SafeMode(
+        "The launcher is running in safe mode, restart it normally to do this".to_string(),
+      ));
+      return;
+    }
+    handler(invoke)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn probes_are_commands_safe_mode_would_otherwise_allow() {
+    for probe in PROBES {
+      assert!(
+        READ_ONLY_PREFIXES
+          .iter()
+          .any(|prefix| probe.starts_with(prefix)),
+        "{} isn't allowed in safe mode anyway",
+        probe
+      );
+      assert!(!ALLOWED_IN_SAFE_MODE.contains(probe));
+      assert!(!is_allowed_in_safe_mode(probe));
+    }
+  }
+}
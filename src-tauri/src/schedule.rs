@@ -0,0 +1,181 @@
+// Quiet hours, during which the launcher keeps its background network activity down
+//
+// Background work is what the launcher does on its own at startup: fetching the remote config,
+// and checking for (and downloading) tooling updates.  It waits on `wait_for_quiet_hours` before
+// starting, which holds it until quiet hours are over unless they only cap bandwidth.  A
+// background download that's still going when quiet hours start is paused or capped through the
+// download queue, the same way the user would, and let go again once they end.  A cap is applied
+// as soon as the download is queued, so it doesn't get a head start at full speed.
+//
+// What the user asks for directly is never held back.
+use std::future::Future;
+use std::time::Duration;
+
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use crate::{
+  config::{LauncherConfig, QuietHours, QuietHoursBehavior},
+  downloads::{DownloadJobState, DownloadQueue},
+};
+
+// How often a running background download checks whether quiet hours started or ended
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+// Held work reads the config again this often, in case quiet hours were changed or turned off
+const MAX_HOLD: Duration = Duration::from_secs(5 * 60);
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct QuietHoursStatus {
+  pub active: bool,
+  /// When the current quiet hours end, if it's quiet hours
+  pub ends_at: Option<String>,
+  pub behavior: Option<QuietHoursBehavior>,
+}
+
+/// `HH:MM`, the format quiet hours are configured in
+pub fn parse_time(value: &str) -> Option<NaiveTime> {
+  NaiveTime::parse_from_str(value.trim(), "%H:%M").ok()
+}
+
+// How long until quiet hours are over, `None` if it isn't quiet hours at `now`
+fn remaining(quiet_hours: &QuietHours, now: NaiveTime) -> Option<Duration> {
+  let start = parse_time(&quiet_hours.start)?;
+  let end = parse_time(&quiet_hours.end)?;
+  let quiet = if start <= end {
+    now >= start && now < end
+  } else {
+    now >= start || now < end
+  };
+  if !quiet {
+    return None;
+  }
+  let mut seconds = end.signed_duration_since(now).num_seconds();
+  if seconds <= 0 {
+    seconds += SECONDS_PER_DAY;
+  }
+  Some(Duration::from_secs(seconds as u64))
+}
+
+// The quiet hours that are going on, and how long they have left
+fn current(config: &LauncherConfig) -> Option<(QuietHours, Duration)> {
+  let quiet_hours = config.quiet_hours.clone()?;
+  let remaining = remaining(&quiet_hours, chrono::Local::now().time())?;
+  Some((quiet_hours, remaining))
+}
+
+pub fn status(config: &LauncherConfig) -> QuietHoursStatus {
+  match current(config) {
+    Some((quiet_hours, remaining)) => QuietHoursStatus {
+      active: true,
+      ends_at: chrono::Duration::from_std(remaining)
+        .ok()
+        .map(|remaining| (chrono::Local::now() + remaining).to_rfc3339()),
+      behavior: Some(quiet_hours.behavior),
+    },
+    None => QuietHoursStatus {
+      active: false,
+      ends_at: None,
+      behavior: None,
+    },
+  }
+}
+
+/// Holds background work (`what`, for the logs) until quiet hours are over.  Quiet hours that
+/// only cap bandwidth don't hold anything
+pub async fn wait_for_quiet_hours(app_handle: &tauri::AppHandle, what: &str) {
+  let config = app_handle.state::<tokio::sync::Mutex<LauncherConfig>>();
+  let mut logged = false;
+  loop {
+    let remaining = match current(&*config.lock().await) {
+      Some((quiet_hours, remaining)) if quiet_hours.behavior == QuietHoursBehavior::Pause => {
+        remaining
+      }
+      _ => return,
+    };
+    if !logged {
+      log::info!("Holding {} until quiet hours are over", what);
+      logged = true;
+    }
+    tokio::time::sleep(remaining.min(MAX_HOLD)).await;
+  }
+}
+
+// What was done to a background download for the quiet hours that are going on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Applied {
+  Nothing,
+  Paused,
+  Capped,
+  // It was already paused by the user, so it's left to them
+  LeftPaused,
+}
+
+/// Runs `download`, the background download with the job id `job_id`, pausing or capping it for
+/// as long as quiet hours go on while it runs
+pub async fn run_background_download<T>(
+  app_handle: &tauri::AppHandle,
+  job_id: &str,
+  download: impl Future<Output = T>,
+) -> T {
+  let config = app_handle.state::<tokio::sync::Mutex<LauncherConfig>>();
+  let queue = app_handle.state::<DownloadQueue>();
+  tokio::pin!(download);
+  let mut interval = tokio::time::interval(CHECK_INTERVAL);
+  let mut applied = Applied::Nothing;
+  loop {
+    tokio::select! {
+      result = &mut download => {
+        // In case it never got as far as being queued
+        if applied == Applied::Capped {
+          queue.set_bandwidth_cap(job_id, None);
+        }
+        return result;
+      }
+      _ = interval.tick() => {}
+    }
+    let quiet_hours = current(&*config.lock().await).map(|(quiet_hours, _)| quiet_hours);
+    // `None` if the job isn't queued yet, the queue caps it once it is but pausing is tried again
+    // on the next check
+    let state = queue
+      .list()
+      .into_iter()
+      .find(|job| job.id == job_id)
+      .map(|job| job.state);
+    applied = match (quiet_hours, applied) {
+      (Some(quiet_hours), Applied::Nothing) => match quiet_hours.behavior {
+        QuietHoursBehavior::Pause if state.is_none() => Applied::Nothing,
+        QuietHoursBehavior::Pause if state == Some(DownloadJobState::Paused) => Applied::LeftPaused,
+        QuietHoursBehavior::Pause => {
+          log::info!("Pausing {} for quiet hours", job_id);
+          queue.pause(app_handle, job_id);
+          Applied::Paused
+        }
+        QuietHoursBehavior::CapBandwidth => {
+          let cap = quiet_hours
+            .bandwidth_cap_kbps
+            .map(|kbps| u64::from(kbps) * 1024);
+          log::info!("Capping {} to {:?} bytes/s for quiet hours", job_id, cap);
+          queue.set_bandwidth_cap(job_id, cap);
+          Applied::Capped
+        }
+      },
+      (None, Applied::Paused) => {
+        // Unless the user resumed it in the meantime
+        if state == Some(DownloadJobState::Paused) {
+          log::info!("Resuming {} after quiet hours", job_id);
+          queue.resume(app_handle, job_id);
+        }
+        Applied::Nothing
+      }
+      (None, Applied::Capped) => {
+        queue.set_bandwidth_cap(job_id, None);
+        Applied::Nothing
+      }
+      (None, _) => Applied::Nothing,
+      (Some(_), applied) => applied,
+    };
+  }
+}
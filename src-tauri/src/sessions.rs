@@ -0,0 +1,336 @@
+// Summaries of play sessions, made when a launched game exits if `session_capture` asks for them
+//
+// Kept in `session-history.json` in the config directory, only the last `MAX_SESSIONS` are kept.
+// Progress is what the game saved during the session: the save files in the game's user
+// directory are compared before and after, and for the games whose save format is known (only
+// Jak 1 so far) what they record as collected is read from them to tell how far the player got.
+// The screenshot is the newest one the game wrote to its screenshots directory while it ran, if
+// there is one.
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use std::time::{Instant, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use crate::{
+  config::SessionCapture,
+  paths::PathResolver,
+  util::file::{read_json, update_json},
+};
+
+const MAX_SESSIONS: usize = 100;
+const SCREENSHOT_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "bmp"];
+
+// Jak 1's memory card files start with a header holding a copy of the save's info block, which
+// the save menu shows without loading the whole save.  After the save count, checksum and magic
+// comes the level index, then the power cells, precursor orbs and scout flies collected and the
+// completion percentage, as floats
+const JAK1_HEADER_SIZE: usize = 32;
+const JAK1_POWER_CELLS_OFFSET: usize = 16;
+const JAK1_ORBS_OFFSET: usize = 20;
+const JAK1_SCOUT_FLIES_OFFSET: usize = 24;
+const JAK1_COMPLETION_OFFSET: usize = 28;
+// There's no more than this to collect, anything over it isn't a save being read right
+const JAK1_MAX_POWER_CELLS: f32 = 101.0;
+const JAK1_MAX_ORBS: f32 = 2000.0;
+const JAK1_MAX_SCOUT_FLIES: f32 = 112.0;
+
+/// What a save records as collected
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveProgress {
+  pub power_cells: i32,
+  pub precursor_orbs: i32,
+  pub scout_flies: i32,
+  pub completion_percent: f32,
+}
+
+impl SaveProgress {
+  fn since(&self, before: &SaveProgress) -> SaveProgress {
+    SaveProgress {
+      power_cells: self.power_cells - before.power_cells,
+      precursor_orbs: self.precursor_orbs - before.precursor_orbs,
+      scout_flies: self.scout_flies - before.scout_flies,
+      completion_percent: self.completion_percent - before.completion_percent,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveChange {
+  pub file: String,
+  /// Whether the save didn't exist before the session, rather than being overwritten
+  pub created: bool,
+  pub bytes: u64,
+  /// What was collected since the save was last written, `None` if the save can't be read
+  pub progress: Option<SaveProgress>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSummary {
+  pub game_name: String,
+  pub started_at: String,
+  pub ended_at: String,
+  pub seconds_played: u64,
+  /// The game exited with an error without being asked to
+  pub crashed: bool,
+  pub exit_code: Option<i32>,
+  pub saves_written: Vec<SaveChange>,
+  pub screenshot: Option<String>,
+}
+
+fn modified_files(dir: &Path) -> BTreeMap<String, (SystemTime, u64)> {
+  std::fs::read_dir(dir)
+    .into_iter()
+    .flatten()
+    .filter_map(|entry| entry.ok())
+    .filter_map(|entry| {
+      let metadata = entry
+        .metadata()
+        .ok()
+        .filter(|metadata| metadata.is_file())?;
+      Some((
+        entry.file_name().to_string_lossy().into_owned(),
+        (metadata.modified().ok()?, metadata.len()),
+      ))
+    })
+    .collect()
+}
+
+fn parse_jak1_progress(header: &[u8]) -> Option<SaveProgress> {
+  let float_at = |offset: usize| {
+    header
+      .get(offset..offset + 4)
+      .and_then(|bytes| bytes.try_into().ok())
+      .map(f32::from_le_bytes)
+  };
+  let count = |offset: usize, max: f32| {
+    float_at(offset)
+      .filter(|value| (0.0..=max).contains(value))
+      .map(|value| value as i32)
+  };
+  Some(SaveProgress {
+    power_cells: count(JAK1_POWER_CELLS_OFFSET, JAK1_MAX_POWER_CELLS)?,
+    precursor_orbs: count(JAK1_ORBS_OFFSET, JAK1_MAX_ORBS)?,
+    scout_flies: count(JAK1_SCOUT_FLIES_OFFSET, JAK1_MAX_SCOUT_FLIES)?,
+    completion_percent: float_at(JAK1_COMPLETION_OFFSET)
+      .filter(|value| (0.0..=100.0).contains(value))?,
+  })
+}
+
+fn read_progress(game_name: &str, path: &Path) -> Option<SaveProgress> {
+  if game_name != "jak1" {
+    return None;
+  }
+  let mut header = [0u8; JAK1_HEADER_SIZE];
+  std::fs::File::open(path)
+    .and_then(|mut file| file.read_exact(&mut header))
+    .ok()?;
+  parse_jak1_progress(&header)
+}
+
+/// What the game's saves looked like when it was started
+pub struct SessionStart {
+  game_name: String,
+  saves_dir: Option<PathBuf>,
+  screenshots_dir: Option<PathBuf>,
+  started: Instant,
+  started_at: chrono::DateTime<chrono::Local>,
+  started_system: SystemTime,
+  saves: BTreeMap<String, (SystemTime, u64)>,
+  progress: BTreeMap<String, SaveProgress>,
+}
+
+impl SessionStart {
+  pub fn new(paths: &PathResolver, game_name: &str) -> Self {
+    let saves_dir = paths.saves_dir(game_name);
+    let saves = saves_dir
+      .as_ref()
+      .map(|dir| modified_files(dir))
+      .unwrap_or_default();
+    let progress = saves_dir
+      .as_ref()
+      .map(|dir| {
+        saves
+          .keys()
+          .filter_map(|file| Some((file.clone(), read_progress(game_name, &dir.join(file))?)))
+          .collect()
+      })
+      .unwrap_or_default();
+    Self {
+      game_name: game_name.to_string(),
+      started: Instant::now(),
+      started_at: chrono::Local::now(),
+      started_system: SystemTime::now(),
+      saves,
+      progress,
+      saves_dir,
+      screenshots_dir: paths.screenshots_dir(game_name),
+    }
+  }
+
+  fn saves_written(&self, saves_dir: &Path) -> Vec<SaveChange> {
+    modified_files(saves_dir)
+      .into_iter()
+      .filter_map(|(file, (modified, bytes))| {
+        let previous = self.saves.get(&file);
+        let changed = previous.map_or(true, |(previous_modified, previous_bytes)| {
+          *previous_modified != modified || *previous_bytes != bytes
+        });
+        if !changed {
+          return None;
+        }
+        // A new save is progress from nothing, an overwritten one is compared with what it had
+        let before = match previous {
+          Some(_) => self.progress.get(&file).copied(),
+          None => Some(SaveProgress::default()),
+        };
+        let progress = read_progress(&self.game_name, &saves_dir.join(&file))
+          .zip(before)
+          .map(|(after, before)| after.since(&before));
+        Some(SaveChange {
+          file,
+          created: previous.is_none(),
+          bytes,
+          progress,
+        })
+      })
+      .collect()
+  }
+
+  fn last_screenshot(&self, screenshots_dir: &Path) -> Option<String> {
+    modified_files(screenshots_dir)
+      .into_iter()
+      .filter(|(file, (modified, _))| {
+        *modified >= self.started_system
+          && Path::new(file)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map_or(false, |ext| {
+              SCREENSHOT_EXTENSIONS.contains(&ext.to_lowercase().as_str())
+            })
+      })
+      .max_by_key(|(_, (modified, _))| *modified)
+      .map(|(file, _)| screenshots_dir.join(file).to_string_lossy().into_owned())
+  }
+
+  /// `exit_requested` is whether the launcher closed the game (ie. the session limit), which
+  /// doesn't count as a crash whatever it exited with
+  pub fn summarize(
+    &self,
+    capture: SessionCapture,
+    exit_status: Option<ExitStatus>,
+    exit_requested: bool,
+  ) -> SessionSummary {
+    SessionSummary {
+      game_name: self.game_name.clone(),
+      started_at: self.started_at.to_rfc3339(),
+      ended_at: chrono::Local::now().to_rfc3339(),
+      seconds_played: self.started.elapsed().as_secs(),
+      crashed: !exit_requested && exit_status.map_or(false, |status| !status.success()),
+      exit_code: exit_status.and_then(|status| status.code()),
+      saves_written: self
+        .saves_dir
+        .as_ref()
+        .map(|dir| self.saves_written(dir))
+        .unwrap_or_default(),
+      screenshot: self
+        .screenshots_dir
+        .as_ref()
+        .filter(|_| capture == SessionCapture::SummaryWithScreenshot)
+        .and_then(|dir| self.last_screenshot(dir)),
+    }
+  }
+}
+
+fn history_path(app_handle: &tauri::AppHandle) -> Option<PathBuf> {
+  app_handle
+    .state::<PathResolver>()
+    .launcher_file("session-history.json")
+}
+
+fn load(path: &Path) -> Vec<SessionSummary> {
+  read_json(path).unwrap_or_default()
+}
+
+/// Keeps the summary and lets the frontend know with a `sessionSummary` event
+pub fn record(app_handle: &tauri::AppHandle, summary: SessionSummary) {
+  if let Some(path) = history_path(app_handle) {
+    let result = update_json(&path, |history: &mut Vec<SessionSummary>| {
+      history.push(summary.clone());
+      if history.len() > MAX_SESSIONS {
+        history.drain(..history.len() - MAX_SESSIONS);
+      }
+    });
+    if let Err(err) = result {
+      log::warn!("Unable to record session summary: {}", err);
+    }
+  }
+  if let Err(err) = app_handle.emit_all("sessionSummary", summary) {
+    log::warn!("Unable to emit session summary: {}", err);
+  }
+}
+
+/// Past sessions, most recent first, of one game or all of them
+pub fn history(app_handle: &tauri::AppHandle, game_name: Option<&str>) -> Vec<SessionSummary> {
+  let mut history = match history_path(app_handle) {
+    Some(path) => load(&path),
+    None => vec![],
+  };
+  if let Some(game_name) = game_name {
+    history.retain(|summary| summary.game_name == game_name);
+  }
+  history.reverse();
+  history
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn jak1_header(power_cells: f32, orbs: f32, scout_flies: f32, completion: f32) -> Vec<u8> {
+    let mut header = vec![0u8; JAK1_HEADER_SIZE];
+    for (offset, value) in [
+      (JAK1_POWER_CELLS_OFFSET, power_cells),
+      (JAK1_ORBS_OFFSET, orbs),
+      (JAK1_SCOUT_FLIES_OFFSET, scout_flies),
+      (JAK1_COMPLETION_OFFSET, completion),
+    ] {
+      header[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+    header
+  }
+
+  #[test]
+  fn reads_what_a_jak1_save_collected() {
+    let before = parse_jak1_progress(&jak1_header(20.0, 600.0, 14.0, 21.5)).unwrap();
+    let after = parse_jak1_progress(&jak1_header(23.0, 655.0, 17.0, 25.0)).unwrap();
+    assert_eq!(
+      after.since(&before),
+      SaveProgress {
+        power_cells: 3,
+        precursor_orbs: 55,
+        scout_flies: 3,
+        completion_percent: 3.5,
+      }
+    );
+  }
+
+  #[test]
+  fn ignores_saves_it_cant_make_sense_of() {
+    assert_eq!(
+      parse_jak1_progress(&jak1_header(500.0, 0.0, 0.0, 0.0)),
+      None
+    );
+    assert_eq!(
+      parse_jak1_progress(&jak1_header(1.0, 1.0, 1.0, f32::NAN)),
+      None
+    );
+    assert_eq!(parse_jak1_progress(&[0u8; 8]), None);
+  }
+}
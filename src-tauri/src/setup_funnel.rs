@@ -0,0 +1,224 @@
+// Where in the setup flow (install directory, tooling download, requirements, ISO) things went
+// wrong on this machine, for support threads
+//
+// Kept in `setup-funnel.json` in the config directory and never sent anywhere, it's only ever
+// read back by `get_setup_funnel_report` for the user to share themselves.  Every attempt at a
+// step is counted when it starts, so one that never reports back (the launcher was closed, or the
+// step was cancelled) shows up as abandoned rather than disappearing.
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use crate::{
+  paths::PathResolver,
+  util::file::{read_json, update_json},
+};
+
+/// The setup steps, in the order the flow goes through them
+#[derive(
+  Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, specta::Type,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum SetupStep {
+  InstallDir,
+  Download,
+  Requirements,
+  Iso,
+}
+
+const STEPS: [SetupStep; 4] = [
+  SetupStep::InstallDir,
+  SetupStep::Download,
+  SetupStep::Requirements,
+  SetupStep::Iso,
+];
+
+impl SetupStep {
+  fn name(&self) -> &'static str {
+    match self {
+      SetupStep::InstallDir => "install directory",
+      SetupStep::Download => "tooling download",
+      SetupStep::Requirements => "requirements",
+      SetupStep::Iso => "ISO extraction",
+    }
+  }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SetupStepRecord {
+  attempts: u32,
+  successes: u32,
+  failures: u32,
+  last_attempt_at: Option<String>,
+  last_failure: Option<String>,
+}
+
+impl SetupStepRecord {
+  fn abandoned(&self) -> u32 {
+    self
+      .attempts
+      .saturating_sub(self.successes.saturating_add(self.failures))
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SetupStepReport {
+  pub step: SetupStep,
+  pub attempts: u32,
+  pub successes: u32,
+  pub failures: u32,
+  pub abandoned: u32,
+  pub last_attempt_at: Option<String>,
+  pub last_failure: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SetupFunnelReport {
+  pub steps: Vec<SetupStepReport>,
+  /// The first step that hasn't succeeded yet, `None` once they all have
+  pub stopped_at: Option<SetupStep>,
+  /// The whole report in a few lines, to paste as is
+  pub summary: String,
+}
+
+type FunnelLog = BTreeMap<SetupStep, SetupStepRecord>;
+
+fn log_path(app_handle: &tauri::AppHandle) -> Option<PathBuf> {
+  app_handle
+    .state::<PathResolver>()
+    .launcher_file("setup-funnel.json")
+}
+
+fn load(app_handle: &tauri::AppHandle) -> FunnelLog {
+  log_path(app_handle)
+    .and_then(|path| read_json(&path))
+    .unwrap_or_default()
+}
+
+fn update(
+  app_handle: &tauri::AppHandle,
+  step: SetupStep,
+  apply: impl FnOnce(&mut SetupStepRecord),
+) {
+  if let Some(path) = log_path(app_handle) {
+    if let Err(err) = update_json(&path, |log: &mut FunnelLog| {
+      apply(log.entry(step).or_default())
+    }) {
+      log::warn!("Unable to record setup step: {}", err);
+    }
+  }
+}
+
+/// An attempt at a setup step, counted as soon as it starts.  One that's dropped without an
+/// outcome counts as failed, unless it's marked as abandoned
+pub struct SetupAttempt {
+  app_handle: tauri::AppHandle,
+  step: SetupStep,
+  finished: bool,
+}
+
+impl SetupAttempt {
+  pub fn start(app_handle: &tauri::AppHandle, step: SetupStep) -> Self {
+    update(app_handle, step, |record| {
+      record.attempts += 1;
+      record.last_attempt_at = Some(chrono::Local::now().to_rfc3339());
+    });
+    Self {
+      app_handle: app_handle.clone(),
+      step,
+      finished: false,
+    }
+  }
+
+  pub fn succeeded(mut self) {
+    self.finish(true, None);
+  }
+
+  pub fn failed(mut self, reason: impl Into<String>) {
+    self.finish(false, Some(reason.into()));
+  }
+
+  /// The user backed out (ie. cancelled), which isn't a failure of the step
+  pub fn abandoned(mut self) {
+    self.finished = true;
+  }
+
+  fn finish(&mut self, succeeded: bool, reason: Option<String>) {
+    self.finished = true;
+    update(&self.app_handle, self.step, |record| {
+      if succeeded {
+        record.successes += 1;
+      } else {
+        record.failures += 1;
+        record.last_failure = reason;
+      }
+    });
+  }
+}
+
+impl Drop for SetupAttempt {
+  fn drop(&mut self) {
+    if !self.finished {
+      self.finish(false, None);
+    }
+  }
+}
+
+fn describe(step: SetupStep, record: &SetupStepRecord) -> String {
+  if record.attempts == 0 {
+    return format!("{}: not reached", step.name());
+  }
+  let mut line = format!(
+    "{}: {} attempt(s), {} succeeded, {} failed, {} abandoned",
+    step.name(),
+    record.attempts,
+    record.successes,
+    record.failures,
+    record.abandoned()
+  );
+  if let Some(reason) = &record.last_failure {
+    line.push_str(&format!(" (last failure: {})", reason));
+  }
+  line
+}
+
+pub fn report(app_handle: &tauri::AppHandle) -> SetupFunnelReport {
+  let log = load(app_handle);
+  let mut stopped_at = None;
+  let mut lines = vec![];
+  let mut steps = vec![];
+  for step in STEPS {
+    let record = log.get(&step).cloned().unwrap_or_default();
+    if stopped_at.is_none() && record.successes == 0 {
+      stopped_at = Some(step);
+    }
+    lines.push(describe(step, &record));
+    let abandoned = record.abandoned();
+    steps.push(SetupStepReport {
+      step,
+      attempts: record.attempts,
+      successes: record.successes,
+      failures: record.failures,
+      abandoned,
+      last_attempt_at: record.last_attempt_at,
+      last_failure: record.last_failure,
+    });
+  }
+  lines.insert(
+    0,
+    match stopped_at {
+      Some(step) => format!("Setup stopped at: {}", step.name()),
+      None => "Setup completed every step".to_string(),
+    },
+  );
+  SetupFunnelReport {
+    steps,
+    stopped_at,
+    summary: lines.join("\n"),
+  }
+}
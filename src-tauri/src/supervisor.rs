@@ -0,0 +1,179 @@
+// Keeps the backend's long running services (the remote control API, local build watchers, the
+// remote config fetch) running
+//
+// A service is a future that's expected to run until it's stopped.  If it returns an error or
+// panics it's restarted, waiting longer after every failure in a row so a service that can't
+// start doesn't spin.  One that keeps failing is given up on after `MAX_CONSECUTIVE_FAILURES`.
+// A service that returns `Ok` was stopped on purpose and stays stopped.
+//
+// Every service's state, and how often it had to be restarted, is kept for `get_service_status`.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+const MAX_CONSECUTIVE_FAILURES: u32 = 8;
+// Running for this long means the service was healthy, and the next failure starts over
+const HEALTHY_RUN: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ServiceState {
+  Running,
+  /// Waiting to be restarted after it failed
+  Restarting,
+  /// Failed too many times in a row, and was given up on
+  Failed,
+  Stopped,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceStatus {
+  pub name: String,
+  pub state: ServiceState,
+  pub started_at: String,
+  pub restarts: u32,
+  pub last_error: Option<String>,
+  pub last_restart_at: Option<String>,
+}
+
+// Keyed by name, along with which run of `supervise` the status belongs to
+type Services = Arc<Mutex<HashMap<String, (u64, ServiceStatus)>>>;
+
+#[derive(Default)]
+pub struct Supervisor {
+  services: Services,
+  next_generation: AtomicU64,
+}
+
+impl Supervisor {
+  pub fn status(&self) -> Vec<ServiceStatus> {
+    let mut services: Vec<ServiceStatus> = match self.services.lock() {
+      Ok(services) => services
+        .values()
+        .map(|(_, status)| status.clone())
+        .collect(),
+      Err(_) => vec![],
+    };
+    services.sort_by(|a, b| a.name.cmp(&b.name));
+    services
+  }
+}
+
+fn is_current(services: &Services, name: &str, generation: u64) -> bool {
+  services.lock().map_or(false, |services| {
+    services
+      .get(name)
+      .map_or(false, |(current, _)| *current == generation)
+  })
+}
+
+// A service that was replaced under the same name mustn't update its replacement's status
+fn update(
+  services: &Services,
+  name: &str,
+  generation: u64,
+  apply: impl FnOnce(&mut ServiceStatus),
+) {
+  if let Ok(mut services) = services.lock() {
+    match services.get_mut(name) {
+      Some((current, status)) if *current == generation => apply(status),
+      _ => {}
+    }
+  }
+}
+
+/// Runs the service `make_service` creates under supervision, creating it again whenever it fails.
+/// A service that's already supervised under `name` is replaced in the status list, stopping the
+/// previous one is up to the caller
+pub fn supervise<F, Fut>(app_handle: &tauri::AppHandle, name: String, make_service: F)
+where
+  F: Fn() -> Fut + Send + 'static,
+  Fut: Future<Output = Result<(), String>> + Send + 'static,
+{
+  let supervisor = app_handle.state::<Supervisor>();
+  let services = supervisor.services.clone();
+  let generation = supervisor.next_generation.fetch_add(1, Ordering::SeqCst);
+  if let Ok(mut services) = services.lock() {
+    services.insert(
+      name.clone(),
+      (
+        generation,
+        ServiceStatus {
+          name: name.clone(),
+          state: ServiceState::Running,
+          started_at: chrono::Local::now().to_rfc3339(),
+          restarts: 0,
+          last_error: None,
+          last_restart_at: None,
+        },
+      ),
+    );
+  }
+  tauri::async_runtime::spawn(async move {
+    let mut backoff = INITIAL_BACKOFF;
+    let mut consecutive_failures = 0;
+    loop {
+      let started = Instant::now();
+      // Spawned on its own so a panic ends up here instead of taking the supervisor with it
+      let error = match tauri::async_runtime::spawn(make_service()).await {
+        Ok(Ok(())) => {
+          log::info!("Service '{}' stopped", name);
+          update(&services, &name, generation, |status| {
+            status.state = ServiceState::Stopped
+          });
+          return;
+        }
+        Ok(Err(err)) => err,
+        Err(err) => format!("panicked: {}", err),
+      };
+      if started.elapsed() >= HEALTHY_RUN {
+        backoff = INITIAL_BACKOFF;
+        consecutive_failures = 0;
+      }
+      consecutive_failures += 1;
+      if consecutive_failures > MAX_CONSECUTIVE_FAILURES {
+        log::error!(
+          "Service '{}' failed {} times in a row, giving up: {}",
+          name,
+          consecutive_failures,
+          error
+        );
+        update(&services, &name, generation, |status| {
+          status.state = ServiceState::Failed;
+          status.last_error = Some(error);
+        });
+        return;
+      }
+      log::warn!(
+        "Service '{}' failed, restarting in {}s: {}",
+        name,
+        backoff.as_secs(),
+        error
+      );
+      update(&services, &name, generation, |status| {
+        status.state = ServiceState::Restarting;
+        status.last_error = Some(error);
+      });
+      tokio::time::sleep(backoff).await;
+      backoff = (backoff * 2).min(MAX_BACKOFF);
+      if !is_current(&services, &name, generation) {
+        log::info!("Service '{}' was replaced, not restarting it", name);
+        return;
+      }
+      update(&services, &name, generation, |status| {
+        status.state = ServiceState::Running;
+        status.restarts += 1;
+        status.last_restart_at = Some(chrono::Local::now().to_rfc3339());
+      });
+    }
+  });
+}
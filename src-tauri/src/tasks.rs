@@ -0,0 +1,394 @@
+// Tracks long running backend work (downloads, install steps) independently of the frontend
+//
+// The frontend drives these tasks by awaiting a command, if the webview reloads or the window
+// is reopened that promise is gone but the work is not. Every tracked task gets a stable id and
+// its state is kept here (and on disk), so a reconnecting frontend can query it with
+// `get_task_state` and pick the progress events back up.
+//
+// While a task is running a `taskHeartbeat` event is emitted periodically, and `taskUpdated` is
+// emitted whenever a task starts or finishes.  Tasks that can estimate how long they'll take
+// include that as `estimatedFinishAt`.  Problems that didn't stop a task are collected in its
+// `warnings`, which are part of the `taskUpdated` event when it finishes.
+//
+// Starting a task whose id is already running doesn't start it twice, `start_or_attach` hands
+// back the in-flight task instead so the caller can wait for its outcome.  The ids are built from
+// the resource being worked on (ie. `download-<folder>-<version>`), which keeps a double-click from
+// racing two downloads or installs on the same folders.
+//
+// Running tasks can be cancelled, this is cooperative - the task checks its `CancellationToken`
+// (or waits on its child process through it) and is responsible for cleaning up after itself.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::{Child, ExitStatus};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use crate::{
+  util::file::read_json,
+  warnings::{Warning, WarningCode},
+};
+
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(2);
+const CANCELLATION_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+  pub fn cancel(&self) {
+    self.0.store(true, Ordering::SeqCst);
+  }
+
+  pub fn is_cancelled(&self) -> bool {
+    self.0.load(Ordering::SeqCst)
+  }
+
+  /// Resolves once the token is cancelled, for racing against other futures
+  pub async fn cancelled(&self) {
+    while !self.is_cancelled() {
+      tokio::time::sleep(CANCELLATION_POLL_INTERVAL).await;
+    }
+  }
+
+  /// Waits for `child` to exit, killing it if the token is cancelled first.  Returns `None` if
+  /// the child was killed
+  pub fn wait_for_child(&self, child: &mut Child) -> std::io::Result<Option<ExitStatus>> {
+    loop {
+      if let Some(status) = child.try_wait()? {
+        return Ok(Some(status));
+      }
+      if self.is_cancelled() {
+        log::info!("cancelling child process {}", child.id());
+        child.kill()?;
+        child.wait()?;
+        return Ok(None);
+      }
+      std::thread::sleep(CANCELLATION_POLL_INTERVAL);
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum TaskStatus {
+  Running,
+  Succeeded,
+  Failed,
+  Cancelled,
+  /// The launcher exited while the task was still running
+  Interrupted,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskState {
+  pub id: String,
+  pub description: String,
+  pub status: TaskStatus,
+  pub started_at: String,
+  pub last_heartbeat: String,
+  pub finished_at: Option<String>,
+  pub message: Option<String>,
+  /// When the task is expected to be done, if there's anything to base an estimate on
+  #[serde(default)]
+  pub estimated_finish_at: Option<String>,
+  /// Problems that didn't stop the task, see `warnings`
+  #[serde(default)]
+  pub warnings: Vec<Warning>,
+}
+
+/// A task that was already running when it was asked to start again
+pub struct InFlightTask {
+  id: String,
+  tasks: Arc<Mutex<HashMap<String, TaskState>>>,
+}
+
+impl InFlightTask {
+  /// Resolves with the task's final state once it's no longer running
+  pub async fn finished(self) -> TaskState {
+    loop {
+      let state = match self.tasks.lock() {
+        Ok(tasks) => tasks.get(&self.id).cloned(),
+        Err(_) => None,
+      };
+      match state {
+        Some(state) if state.status == TaskStatus::Running => {}
+        Some(state) => return state,
+        // Shouldn't happen, tasks are never forgotten while running
+        None => {
+          let mut state = new_task_state(&self.id, "");
+          state.status = TaskStatus::Interrupted;
+          return state;
+        }
+      }
+      tokio::time::sleep(CANCELLATION_POLL_INTERVAL).await;
+    }
+  }
+}
+
+pub struct TaskManager {
+  tasks: Arc<Mutex<HashMap<String, TaskState>>>,
+  cancellation_tokens: Arc<Mutex<HashMap<String, CancellationToken>>>,
+  state_path: Option<PathBuf>,
+}
+
+impl TaskManager {
+  /// Loads the task state persisted by the previous run, anything that was still running at the
+  /// time is marked as interrupted
+  pub fn load(config_dir: Option<PathBuf>) -> Self {
+    let state_path = config_dir.map(|dir| dir.join("task-state.json"));
+    let mut tasks: HashMap<String, TaskState> = match &state_path {
+      Some(path) => read_json(path).unwrap_or_default(),
+      None => HashMap::new(),
+    };
+    for task in tasks.values_mut() {
+      if task.status == TaskStatus::Running {
+        task.status = TaskStatus::Interrupted;
+      }
+    }
+    TaskManager {
+      tasks: Arc::new(Mutex::new(tasks)),
+      cancellation_tokens: Arc::new(Mutex::new(HashMap::new())),
+      state_path,
+    }
+  }
+
+  /// Requests that a running task stops, returns false if there is no such task running
+  pub fn cancel(&self, id: &str) -> bool {
+    match self
+      .cancellation_tokens
+      .lock()
+      .ok()
+      .and_then(|tokens| tokens.get(id).cloned())
+    {
+      Some(token) => {
+        log::info!("cancelling task {}", id);
+        token.cancel();
+        true
+      }
+      None => false,
+    }
+  }
+
+  pub fn get(&self, id: &str) -> Option<TaskState> {
+    self.tasks.lock().ok()?.get(id).cloned()
+  }
+
+  pub fn all(&self) -> Vec<TaskState> {
+    match self.tasks.lock() {
+      Ok(tasks) => tasks.values().cloned().collect(),
+      Err(_) => vec![],
+    }
+  }
+
+  pub fn running(&self) -> Vec<TaskState> {
+    match self.tasks.lock() {
+      Ok(tasks) => tasks
+        .values()
+        .filter(|task| task.status == TaskStatus::Running)
+        .cloned()
+        .collect(),
+      Err(_) => vec![],
+    }
+  }
+
+  /// Starts tracking a task, replacing the finished state of any previous one with the same id.
+  /// If a task with that id is still running it's left alone and returned as an `InFlightTask`
+  /// to wait on instead.  A started task is considered failed unless `TaskHandle::finish` is
+  /// called before the handle is dropped.
+  pub fn start_or_attach(
+    &self,
+    app_handle: &tauri::AppHandle,
+    id: String,
+    description: &str,
+  ) -> Result<TaskHandle, InFlightTask> {
+    let state = new_task_state(&id, description);
+    if let Ok(mut tasks) = self.tasks.lock() {
+      if tasks
+        .get(&id)
+        .map_or(false, |task| task.status == TaskStatus::Running)
+      {
+        log::info!("task {} is already running, attaching to it", id);
+        return Err(InFlightTask {
+          id,
+          tasks: self.tasks.clone(),
+        });
+      }
+      tasks.insert(id.clone(), state.clone());
+    }
+    Ok(self.track(app_handle, id, state))
+  }
+
+  fn track(&self, app_handle: &tauri::AppHandle, id: String, state: TaskState) -> TaskHandle {
+    let cancellation_token = CancellationToken::default();
+    if let Ok(mut tokens) = self.cancellation_tokens.lock() {
+      tokens.insert(id.clone(), cancellation_token.clone());
+    }
+    persist(&self.tasks, &self.state_path);
+    emit(app_handle, "taskUpdated", &state);
+
+    let (stop, stopped) = mpsc::channel::<()>();
+    let heartbeat_tasks = self.tasks.clone();
+    let heartbeat_app_handle = app_handle.clone();
+    let heartbeat_id = id.clone();
+    let spawned = std::thread::Builder::new()
+      .name(format!("task-heartbeat-{}", id))
+      .spawn(move || {
+        while let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(HEARTBEAT_INTERVAL) {
+          let state = match heartbeat_tasks.lock() {
+            Ok(mut tasks) => match tasks.get_mut(&heartbeat_id) {
+              Some(task) => {
+                task.last_heartbeat = chrono::Local::now().to_rfc3339();
+                task.clone()
+              }
+              None => break,
+            },
+            Err(_) => break,
+          };
+          emit(&heartbeat_app_handle, "taskHeartbeat", &state);
+        }
+      });
+    if let Err(err) = spawned {
+      log::warn!("Unable to start heartbeat for task {}: {}", id, err);
+    }
+
+    TaskHandle {
+      id,
+      tasks: self.tasks.clone(),
+      cancellation_tokens: self.cancellation_tokens.clone(),
+      cancellation_token,
+      state_path: self.state_path.clone(),
+      app_handle: app_handle.clone(),
+      stop: Some(stop),
+      outcome: None,
+    }
+  }
+}
+
+pub struct TaskHandle {
+  id: String,
+  tasks: Arc<Mutex<HashMap<String, TaskState>>>,
+  cancellation_tokens: Arc<Mutex<HashMap<String, CancellationToken>>>,
+  cancellation_token: CancellationToken,
+  state_path: Option<PathBuf>,
+  app_handle: tauri::AppHandle,
+  stop: Option<mpsc::Sender<()>>,
+  outcome: Option<(TaskStatus, Option<String>)>,
+}
+
+impl TaskHandle {
+  pub fn id(&self) -> &str {
+    &self.id
+  }
+
+  pub fn cancellation_token(&self) -> CancellationToken {
+    self.cancellation_token.clone()
+  }
+
+  /// Updates the task's estimated finish, it's sent along with the next heartbeat
+  pub fn set_remaining(&self, remaining: Duration) {
+    let remaining = match chrono::Duration::from_std(remaining) {
+      Ok(remaining) => remaining,
+      Err(_) => return,
+    };
+    if let Ok(mut tasks) = self.tasks.lock() {
+      if let Some(task) = tasks.get_mut(&self.id) {
+        task.estimated_finish_at = Some((chrono::Local::now() + remaining).to_rfc3339());
+      }
+    }
+  }
+
+  /// Records a problem that doesn't fail the task, it stays part of the task's state
+  pub fn warn(&self, code: WarningCode, message: String) {
+    log::warn!("task {}: {}", self.id, message);
+    if let Ok(mut tasks) = self.tasks.lock() {
+      if let Some(task) = tasks.get_mut(&self.id) {
+        task.warnings.push(Warning::new(code, message));
+      }
+    }
+  }
+
+  pub fn finish(&mut self, success: bool, message: Option<String>) {
+    let status = if success {
+      TaskStatus::Succeeded
+    } else {
+      TaskStatus::Failed
+    };
+    self.outcome = Some((status, message));
+  }
+}
+
+impl Drop for TaskHandle {
+  fn drop(&mut self) {
+    // Dropping the sender wakes the heartbeat thread up and ends it
+    self.stop.take();
+    if let Ok(mut tokens) = self.cancellation_tokens.lock() {
+      tokens.remove(&self.id);
+    }
+    let (status, message) = match self.outcome.take() {
+      _ if self.cancellation_token.is_cancelled() => (TaskStatus::Cancelled, None),
+      Some(outcome) => outcome,
+      // Commands finish their task with the error they return, so this is one that panicked
+      None => (TaskStatus::Failed, None),
+    };
+    let state = match self.tasks.lock() {
+      Ok(mut tasks) => match tasks.get_mut(&self.id) {
+        Some(task) => {
+          task.status = status;
+          task.message = message;
+          task.finished_at = Some(chrono::Local::now().to_rfc3339());
+          Some(task.clone())
+        }
+        None => None,
+      },
+      Err(_) => None,
+    };
+    persist(&self.tasks, &self.state_path);
+    if let Some(state) = state {
+      emit(&self.app_handle, "taskUpdated", &state);
+    }
+  }
+}
+
+fn new_task_state(id: &str, description: &str) -> TaskState {
+  let now = chrono::Local::now().to_rfc3339();
+  TaskState {
+    id: id.to_string(),
+    description: description.to_string(),
+    status: TaskStatus::Running,
+    started_at: now.clone(),
+    last_heartbeat: now,
+    finished_at: None,
+    message: None,
+    estimated_finish_at: None,
+    warnings: vec![],
+  }
+}
+
+fn persist(tasks: &Arc<Mutex<HashMap<String, TaskState>>>, state_path: &Option<PathBuf>) {
+  let path = match state_path {
+    Some(path) => path,
+    None => return,
+  };
+  let content = match tasks.lock() {
+    Ok(tasks) => serde_json::to_string_pretty(&*tasks),
+    Err(_) => return,
+  };
+  let result = content
+    .map_err(std::io::Error::from)
+    .and_then(|content| std::fs::write(path, content));
+  if let Err(err) = result {
+    log::warn!("Unable to persist task state: {}", err);
+  }
+}
+
+fn emit(app_handle: &tauri::AppHandle, event: &str, state: &TaskState) {
+  if let Err(err) = app_handle.emit_all(event, state) {
+    log::warn!("Unable to emit {} for task {}: {}", event, state.id, err);
+  }
+}
@@ -0,0 +1,108 @@
+// How fast past downloads and install steps went on this machine, to estimate how long the next
+// one will take
+//
+// Kept in `throughput.json` in the config directory as moving averages, so one unusually slow or
+// fast run only nudges the estimate.
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use crate::{
+  paths::PathResolver,
+  util::file::{read_json, update_json},
+};
+
+// How much the latest measurement counts towards the average
+const SMOOTHING: f64 = 0.3;
+// Anything smaller finishes too fast to say anything about the throughput
+const MIN_MEASURED_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ThroughputLog {
+  download_bytes_per_sec: Option<f64>,
+  extraction_bytes_per_sec: Option<f64>,
+  /// Install step (ie. `compile-jak1`) -> seconds
+  #[serde(default)]
+  step_seconds: BTreeMap<String, f64>,
+}
+
+fn log_path(app_handle: &tauri::AppHandle) -> Option<PathBuf> {
+  app_handle
+    .state::<PathResolver>()
+    .launcher_file("throughput.json")
+}
+
+fn load(app_handle: &tauri::AppHandle) -> ThroughputLog {
+  log_path(app_handle)
+    .and_then(|path| read_json(&path))
+    .unwrap_or_default()
+}
+
+fn update(app_handle: &tauri::AppHandle, apply: impl FnOnce(&mut ThroughputLog)) {
+  if let Some(path) = log_path(app_handle) {
+    if let Err(err) = update_json(&path, apply) {
+      log::warn!("Unable to record throughput: {}", err);
+    }
+  }
+}
+
+fn average(previous: Option<f64>, latest: f64) -> f64 {
+  match previous {
+    Some(previous) => previous * (1.0 - SMOOTHING) + latest * SMOOTHING,
+    None => latest,
+  }
+}
+
+fn rate(bytes: u64, elapsed: Duration) -> Option<f64> {
+  if bytes < MIN_MEASURED_BYTES || elapsed.is_zero() {
+    return None;
+  }
+  Some(bytes as f64 / elapsed.as_secs_f64())
+}
+
+// The log is only written by us, but a hand-edited one mustn't make `Duration` panic
+fn duration(seconds: f64) -> Option<Duration> {
+  (seconds.is_finite() && (0.0..1e9).contains(&seconds)).then(|| Duration::from_secs_f64(seconds))
+}
+
+pub fn record_download(app_handle: &tauri::AppHandle, bytes: u64, elapsed: Duration) {
+  if let Some(rate) = rate(bytes, elapsed) {
+    update(app_handle, |log| {
+      log.download_bytes_per_sec = Some(average(log.download_bytes_per_sec, rate))
+    });
+  }
+}
+
+pub fn record_extraction(app_handle: &tauri::AppHandle, bytes: u64, elapsed: Duration) {
+  if let Some(rate) = rate(bytes, elapsed) {
+    update(app_handle, |log| {
+      log.extraction_bytes_per_sec = Some(average(log.extraction_bytes_per_sec, rate))
+    });
+  }
+}
+
+pub fn record_step(app_handle: &tauri::AppHandle, step: &str, elapsed: Duration) {
+  update(app_handle, |log| {
+    let seconds = average(log.step_seconds.get(step).copied(), elapsed.as_secs_f64());
+    log.step_seconds.insert(step.to_string(), seconds);
+  });
+}
+
+pub fn download_bytes_per_sec(app_handle: &tauri::AppHandle) -> Option<f64> {
+  load(app_handle).download_bytes_per_sec
+}
+
+/// How long extracting an archive of `bytes` is expected to take
+pub fn extraction_duration(app_handle: &tauri::AppHandle, bytes: u64) -> Option<Duration> {
+  let rate = load(app_handle).extraction_bytes_per_sec?;
+  duration(bytes as f64 / rate)
+}
+
+/// How long an install step is expected to take, based on how long it took before
+pub fn step_duration(app_handle: &tauri::AppHandle, step: &str) -> Option<Duration> {
+  duration(*load(app_handle).step_seconds.get(step)?)
+}
@@ -1,5 +1,16 @@
+pub mod archive;
+pub mod cloud_sync;
+pub mod content_download;
+pub mod crypto;
+pub mod dedup;
 pub mod file;
+pub mod github;
 pub mod network;
 pub mod os;
-pub mod tar;
+pub mod power;
+pub mod process;
+pub mod sandbox;
+pub mod signing;
+#[cfg(test)]
+pub mod test_dir;
 pub mod zip;
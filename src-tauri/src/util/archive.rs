@@ -0,0 +1,743 @@
+// Extraction of the archives the launcher deals with (releases, mods, texture packs)
+//
+// The format is detected from the first bytes of the file rather than its name, callers just
+// use `extract_archive` / `extract_and_delete_archive`.  Supporting a new format is a matter of
+// implementing `ArchiveExtractor` and adding it to `EXTRACTORS`.
+//
+// Formats that can be read front to back (the tarballs) can also be extracted while they're
+// still arriving with an `ArchiveStream`.  Zips and 7z archives can't, their index is at the end
+// of the file, so a stream of one is written out first and then extracted like any other.
+//
+// There's no 7z decoder to build in, those are extracted with 7-Zip if it's installed.
+//
+// Extracting a large archive onto a slow drive can take long enough to be interrupted, so
+// `extract_and_delete_archive` records every entry it finished next to the archive (see
+// `ExtractionProgress`), and so does an `ArchiveStream` given the archive's path.  Extracting the
+// same archive again skips what's already there, rather than writing it all over again.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Cursor, Read, Write};
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread::JoinHandle;
+
+use sha2::{Digest, Sha256};
+use tokio::sync::mpsc;
+
+use super::{
+  crypto::hex,
+  process::{find_on_path, tool_command},
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ArchiveError {
+  #[error(transparent)]
+  IO(#[from] std::io::Error),
+  #[error(transparent)]
+  Zip(#[from] zip_extract::ZipExtractError),
+  #[error("{0} archives are not supported")]
+  UnsupportedFormat(String),
+  #[error("'{0}' is not a recognized archive")]
+  UnknownFormat(String),
+  #[error("{0} archives can't be extracted while downloading")]
+  NotStreamable(String),
+  #[error("extraction stopped before the archive was complete")]
+  Interrupted,
+  #[error("{0} archives can only be extracted with 7-Zip installed")]
+  MissingTool(String),
+  #[error("7-Zip was unable to extract the archive: {0}")]
+  ToolFailed(String),
+}
+
+/// The entries of an archive that were completely extracted, kept in `<archive>.progress` with a
+/// line for each: its checksum (the CRC-32 of a zip entry's data, or the checksum of a tar entry's
+/// header), size and path.  An entry counts as done as long as the archive still has it with the
+/// same checksum and size, and the extracted file is still that size.  Entries are only recorded
+/// once their file was synced to disk, so one cut short by a power loss isn't taken as done
+pub struct ExtractionProgress {
+  done: HashMap<String, (u32, u64)>,
+  log: std::fs::File,
+}
+
+impl ExtractionProgress {
+  pub fn path(archive_path: &Path) -> PathBuf {
+    let mut name = archive_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".progress");
+    archive_path.with_file_name(name)
+  }
+
+  pub fn open(archive_path: &Path) -> std::io::Result<Self> {
+    let path = Self::path(archive_path);
+    let mut done = HashMap::new();
+    if let Ok(file) = std::fs::File::open(&path) {
+      // The last line may have been cut short, it then doesn't parse and is left out
+      for line in BufReader::new(file).lines().map_while(Result::ok) {
+        let mut fields = line.splitn(3, ' ');
+        let entry = (|| {
+          let checksum = u32::from_str_radix(fields.next()?, 16).ok()?;
+          let size = fields.next()?.parse().ok()?;
+          Some((fields.next()?.to_string(), (checksum, size)))
+        })();
+        if let Some((name, entry)) = entry {
+          done.insert(name, entry);
+        }
+      }
+    }
+    let log = std::fs::OpenOptions::new()
+      .create(true)
+      .append(true)
+      .open(&path)?;
+    Ok(Self { done, log })
+  }
+
+  /// Whether an earlier attempt at extracting the archive recorded any entries as done
+  pub fn is_started(archive_path: &Path) -> bool {
+    std::fs::metadata(Self::path(archive_path)).map_or(false, |metadata| metadata.len() > 0)
+  }
+
+  pub fn done_count(&self) -> usize {
+    self.done.len()
+  }
+
+  fn is_done(&self, name: &str, checksum: u32, size: u64, target: &Path) -> bool {
+    self.done.get(name) == Some(&(checksum, size))
+      && std::fs::metadata(target).map_or(false, |metadata| {
+        metadata.is_file() && metadata.len() == size
+      })
+  }
+
+  // Only once the entry was written out in full
+  fn record(&mut self, name: &str, checksum: u32, size: u64) -> std::io::Result<()> {
+    writeln!(self.log, "{:08x} {} {}", checksum, size, name)?;
+    self.done.insert(name.to_string(), (checksum, size));
+    Ok(())
+  }
+
+  pub fn remove(archive_path: &Path) {
+    let _ = std::fs::remove_file(Self::path(archive_path));
+  }
+}
+
+// Makes sure what was written to `path` is on disk before it's recorded as done
+fn sync_file(path: &Path) -> std::io::Result<()> {
+  std::fs::OpenOptions::new()
+    .write(true)
+    .open(path)?
+    .sync_all()
+}
+
+pub trait ArchiveExtractor: Sync {
+  fn name(&self) -> &'static str;
+  /// Whether the archive starting with `header` is in this format
+  fn matches(&self, header: &[u8]) -> bool;
+  fn extract(&self, archive_path: &Path, extract_dir: &Path) -> Result<(), ArchiveError>;
+  /// Like `extract`, skipping the entries `progress` has as done and recording those that aren't.
+  /// Formats that can't extract entries one by one extract everything
+  fn extract_resumable(
+    &self,
+    archive_path: &Path,
+    extract_dir: &Path,
+    _progress: &mut ExtractionProgress,
+  ) -> Result<(), ArchiveError> {
+    self.extract(archive_path, extract_dir)
+  }
+  /// Whether `extract_stream` can be used, otherwise the archive has to be on disk
+  fn can_stream(&self) -> bool {
+    false
+  }
+  /// Like `extract_stream`, skipping and recording entries like `extract_resumable`
+  fn extract_stream_resumable(
+    &self,
+    reader: &mut dyn Read,
+    extract_dir: &Path,
+    _progress: &mut ExtractionProgress,
+  ) -> Result<(), ArchiveError> {
+    self.extract_stream(reader, extract_dir)
+  }
+  /// Extracts the archive as it's read from `reader`, for formats that don't need to seek
+  fn extract_stream(
+    &self,
+    _reader: &mut dyn Read,
+    _extract_dir: &Path,
+  ) -> Result<(), ArchiveError> {
+    Err(ArchiveError::NotStreamable(self.name().to_string()))
+  }
+}
+
+struct ZipExtractor;
+
+impl ArchiveExtractor for ZipExtractor {
+  fn name(&self) -> &'static str {
+    "zip"
+  }
+
+  fn matches(&self, header: &[u8]) -> bool {
+    // Regular and empty archives
+    header.starts_with(b"PK\x03\x04") || header.starts_with(b"PK\x05\x06")
+  }
+
+  fn extract(&self, archive_path: &Path, extract_dir: &Path) -> Result<(), ArchiveError> {
+    let archive: Vec<u8> = std::fs::read(archive_path)?;
+    zip_extract::extract(Cursor::new(archive), extract_dir, true)?;
+    Ok(())
+  }
+
+  // The same as `zip_extract::extract` does (a single top level folder is stripped), one entry
+  // at a time
+  fn extract_resumable(
+    &self,
+    archive_path: &Path,
+    extract_dir: &Path,
+    progress: &mut ExtractionProgress,
+  ) -> Result<(), ArchiveError> {
+    let zip_error = |err| ArchiveError::Zip(zip_extract::ZipExtractError::Zip(err));
+    let mut archive =
+      zip::ZipArchive::new(std::fs::File::open(archive_path)?).map_err(zip_error)?;
+    let names: Vec<PathBuf> = (0..archive.len())
+      .map(|i| archive.by_index(i).map(|file| file.mangled_name()))
+      .collect::<Result<_, _>>()
+      .map_err(zip_error)?;
+    let toplevel: Option<PathBuf> = names
+      .first()
+      .map(|name| name.components().take(1).collect());
+    let strip = names.len() >= 2
+      && toplevel.as_ref().map_or(false, |toplevel| {
+        names.iter().all(|name| name.starts_with(toplevel))
+      });
+    std::fs::create_dir_all(extract_dir)?;
+    let mut skipped = 0;
+    for (i, name) in names.iter().enumerate() {
+      let relative = match &toplevel {
+        Some(toplevel) if strip => name.strip_prefix(toplevel).unwrap_or(name),
+        _ => name.as_path(),
+      };
+      if relative.as_os_str().is_empty() {
+        continue;
+      }
+      let outpath = extract_dir.join(relative);
+      let mut file = archive.by_index(i).map_err(zip_error)?;
+      if file.is_dir() {
+        std::fs::create_dir_all(&outpath)?;
+        continue;
+      }
+      let key = relative.to_string_lossy().replace('\\', "/");
+      if progress.is_done(&key, file.crc32(), file.size(), &outpath) {
+        skipped += 1;
+        continue;
+      }
+      if let Some(parent) = outpath.parent() {
+        std::fs::create_dir_all(parent)?;
+      }
+      let mut outfile = std::fs::File::create(&outpath)?;
+      std::io::copy(&mut file, &mut outfile)?;
+      outfile.sync_all()?;
+      #[cfg(unix)]
+      if let Some(mode) = file.unix_mode() {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&outpath, std::fs::Permissions::from_mode(mode))?;
+      }
+      progress.record(&key, file.crc32(), file.size())?;
+    }
+    if skipped > 0 {
+      log::info!(
+        "skipped {} entries extracted by an earlier attempt",
+        skipped
+      );
+    }
+    Ok(())
+  }
+}
+
+// Like `tar::Archive::unpack`, one entry at a time
+fn unpack_tar_resumable<R: Read>(
+  mut archive: tar::Archive<R>,
+  extract_dir: &Path,
+  progress: &mut ExtractionProgress,
+) -> Result<(), ArchiveError> {
+  std::fs::create_dir_all(extract_dir)?;
+  let mut skipped = 0;
+  for entry in archive.entries()? {
+    let mut entry = entry?;
+    let relative = entry.path()?.into_owned();
+    // Regular files are the only ones worth skipping, `unpack_in` refuses unsafe paths
+    let trackable = entry.header().entry_type().is_file()
+      && relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+    if !trackable {
+      entry.unpack_in(extract_dir)?;
+      continue;
+    }
+    let key = relative.to_string_lossy().replace('\\', "/");
+    let checksum = entry.header().cksum()?;
+    let size = entry.size();
+    if progress.is_done(&key, checksum, size, &extract_dir.join(&relative)) {
+      skipped += 1;
+      continue;
+    }
+    entry.unpack_in(extract_dir)?;
+    sync_file(&extract_dir.join(&relative))?;
+    progress.record(&key, checksum, size)?;
+  }
+  if skipped > 0 {
+    log::info!(
+      "skipped {} entries extracted by an earlier attempt",
+      skipped
+    );
+  }
+  Ok(())
+}
+
+// Gzip on its own isn't an archive, anything gzipped is assumed to be a tarball
+struct TarGzExtractor;
+
+impl ArchiveExtractor for TarGzExtractor {
+  fn name(&self) -> &'static str {
+    "tar.gz"
+  }
+
+  fn matches(&self, header: &[u8]) -> bool {
+    header.starts_with(&[0x1f, 0x8b])
+  }
+
+  fn extract(&self, archive_path: &Path, extract_dir: &Path) -> Result<(), ArchiveError> {
+    let mut tar_gz = std::fs::File::open(archive_path)?;
+    self.extract_stream(&mut tar_gz, extract_dir)
+  }
+
+  fn extract_resumable(
+    &self,
+    archive_path: &Path,
+    extract_dir: &Path,
+    progress: &mut ExtractionProgress,
+  ) -> Result<(), ArchiveError> {
+    let tar = flate2::read::GzDecoder::new(std::fs::File::open(archive_path)?);
+    unpack_tar_resumable(tar::Archive::new(tar), extract_dir, progress)
+  }
+
+  fn can_stream(&self) -> bool {
+    true
+  }
+
+  fn extract_stream_resumable(
+    &self,
+    reader: &mut dyn Read,
+    extract_dir: &Path,
+    progress: &mut ExtractionProgress,
+  ) -> Result<(), ArchiveError> {
+    let tar = flate2::read::GzDecoder::new(reader);
+    unpack_tar_resumable(tar::Archive::new(tar), extract_dir, progress)
+  }
+
+  fn extract_stream(&self, reader: &mut dyn Read, extract_dir: &Path) -> Result<(), ArchiveError> {
+    let tar = flate2::read::GzDecoder::new(reader);
+    tar::Archive::new(tar).unpack(extract_dir)?;
+    Ok(())
+  }
+}
+
+// Same as for gzip, zstd is only used to compress tarballs
+struct TarZstExtractor;
+
+impl ArchiveExtractor for TarZstExtractor {
+  fn name(&self) -> &'static str {
+    "tar.zst"
+  }
+
+  fn matches(&self, header: &[u8]) -> bool {
+    header.starts_with(&[0x28, 0xb5, 0x2f, 0xfd])
+  }
+
+  fn extract(&self, archive_path: &Path, extract_dir: &Path) -> Result<(), ArchiveError> {
+    let mut tar_zst = std::fs::File::open(archive_path)?;
+    self.extract_stream(&mut tar_zst, extract_dir)
+  }
+
+  fn extract_resumable(
+    &self,
+    archive_path: &Path,
+    extract_dir: &Path,
+    progress: &mut ExtractionProgress,
+  ) -> Result<(), ArchiveError> {
+    let tar = zstd::stream::read::Decoder::new(std::fs::File::open(archive_path)?)?;
+    unpack_tar_resumable(tar::Archive::new(tar), extract_dir, progress)
+  }
+
+  fn can_stream(&self) -> bool {
+    true
+  }
+
+  fn extract_stream_resumable(
+    &self,
+    reader: &mut dyn Read,
+    extract_dir: &Path,
+    progress: &mut ExtractionProgress,
+  ) -> Result<(), ArchiveError> {
+    let tar = zstd::stream::read::Decoder::new(reader)?;
+    unpack_tar_resumable(tar::Archive::new(tar), extract_dir, progress)
+  }
+
+  fn extract_stream(&self, reader: &mut dyn Read, extract_dir: &Path) -> Result<(), ArchiveError> {
+    let tar = zstd::stream::read::Decoder::new(reader)?;
+    tar::Archive::new(tar).unpack(extract_dir)?;
+    Ok(())
+  }
+}
+
+struct TarExtractor;
+
+impl ArchiveExtractor for TarExtractor {
+  fn name(&self) -> &'static str {
+    "tar"
+  }
+
+  fn matches(&self, header: &[u8]) -> bool {
+    header.len() >= 262 && &header[257..262] == b"ustar"
+  }
+
+  fn extract(&self, archive_path: &Path, extract_dir: &Path) -> Result<(), ArchiveError> {
+    let mut tar = std::fs::File::open(archive_path)?;
+    self.extract_stream(&mut tar, extract_dir)
+  }
+
+  fn extract_resumable(
+    &self,
+    archive_path: &Path,
+    extract_dir: &Path,
+    progress: &mut ExtractionProgress,
+  ) -> Result<(), ArchiveError> {
+    let tar = std::fs::File::open(archive_path)?;
+    unpack_tar_resumable(tar::Archive::new(tar), extract_dir, progress)
+  }
+
+  fn can_stream(&self) -> bool {
+    true
+  }
+
+  fn extract_stream_resumable(
+    &self,
+    reader: &mut dyn Read,
+    extract_dir: &Path,
+    progress: &mut ExtractionProgress,
+  ) -> Result<(), ArchiveError> {
+    unpack_tar_resumable(tar::Archive::new(reader), extract_dir, progress)
+  }
+
+  fn extract_stream(&self, reader: &mut dyn Read, extract_dir: &Path) -> Result<(), ArchiveError> {
+    tar::Archive::new(reader).unpack(extract_dir)?;
+    Ok(())
+  }
+}
+
+// Where 7-Zip may be if it's not on the path
+#[cfg(target_os = "windows")]
+const SEVEN_ZIP_INSTALL_PATHS: &[&str] = &[
+  "C:\\Program Files\\7-Zip\\7z.exe",
+  "C:\\Program Files (x86)\\7-Zip\\7z.exe",
+];
+#[cfg(not(target_os = "windows"))]
+const SEVEN_ZIP_INSTALL_PATHS: &[&str] = &[];
+
+fn seven_zip_command(program: &str, cwd: &Path) -> Command {
+  // Found on the launcher's PATH, the curated one wouldn't have it
+  let program = find_on_path(program).unwrap_or_else(|| PathBuf::from(program));
+  let mut command = tool_command(program, cwd, None);
+  command.stdin(Stdio::null());
+  #[cfg(target_os = "windows")]
+  command.creation_flags(0x08000000);
+  command
+}
+
+// The full 7-Zip is `7z`, the standalone builds are `7zz` (current) and `7za` (p7zip)
+fn find_seven_zip() -> Option<&'static str> {
+  ["7z", "7zz", "7za"]
+    .iter()
+    .chain(SEVEN_ZIP_INSTALL_PATHS)
+    .find(|program| {
+      seven_zip_command(program, &std::env::temp_dir())
+        .output()
+        .is_ok()
+    })
+    .copied()
+}
+
+struct SevenZipExtractor;
+
+impl ArchiveExtractor for SevenZipExtractor {
+  fn name(&self) -> &'static str {
+    "7z"
+  }
+
+  fn matches(&self, header: &[u8]) -> bool {
+    header.starts_with(b"7z\xbc\xaf\x27\x1c")
+  }
+
+  fn extract(&self, archive_path: &Path, extract_dir: &Path) -> Result<(), ArchiveError> {
+    let program =
+      find_seven_zip().ok_or_else(|| ArchiveError::MissingTool(self.name().to_string()))?;
+    let output = seven_zip_command(program, extract_dir)
+      .arg("x")
+      .arg("-y")
+      .arg(format!("-o{}", extract_dir.display()))
+      .arg(archive_path)
+      .output()?;
+    if !output.status.success() {
+      return Err(ArchiveError::ToolFailed(
+        String::from_utf8_lossy(&output.stderr).trim().to_string(),
+      ));
+    }
+    Ok(())
+  }
+}
+
+static EXTRACTORS: &[&dyn ArchiveExtractor] = &[
+  &ZipExtractor,
+  &TarGzExtractor,
+  &TarZstExtractor,
+  &TarExtractor,
+  &SevenZipExtractor,
+];
+
+// Formats that are recognized, so the error is clearer, but can't be extracted (yet)
+const UNSUPPORTED_FORMATS: &[(&str, &[u8])] = &[("xz", b"\xfd7zXZ\x00"), ("rar", b"Rar!\x1a\x07")];
+
+// Enough to cover the tar header's magic
+const HEADER_LEN: u64 = 512;
+
+pub fn detect_extractor(
+  archive_path: &Path,
+) -> Result<&'static dyn ArchiveExtractor, ArchiveError> {
+  let mut header = Vec::new();
+  std::fs::File::open(archive_path)?
+    .take(HEADER_LEN)
+    .read_to_end(&mut header)?;
+  extractor_for_header(&header, &archive_path.display().to_string())
+}
+
+fn extractor_for_header(
+  header: &[u8],
+  archive_name: &str,
+) -> Result<&'static dyn ArchiveExtractor, ArchiveError> {
+  if let Some(extractor) = EXTRACTORS
+    .iter()
+    .find(|extractor| extractor.matches(header))
+  {
+    return Ok(*extractor);
+  }
+  match UNSUPPORTED_FORMATS
+    .iter()
+    .find(|(_, magic)| header.starts_with(magic))
+  {
+    Some((name, _)) => Err(ArchiveError::UnsupportedFormat(name.to_string())),
+    None => Err(ArchiveError::UnknownFormat(archive_name.to_string())),
+  }
+}
+
+pub fn extract_archive(archive_path: &Path, extract_dir: &Path) -> Result<(), ArchiveError> {
+  let extractor = detect_extractor(archive_path)?;
+  log::info!(
+    "extracting {} ({})",
+    archive_path.display(),
+    extractor.name()
+  );
+  extractor.extract(archive_path, extract_dir)
+}
+
+/// Extracts the archive picking up where an earlier attempt to extract it to `extract_dir`
+/// stopped, then deletes it along with the record of its progress
+pub fn extract_and_delete_archive(
+  archive_path: &Path,
+  extract_dir: &Path,
+) -> Result<(), ArchiveError> {
+  let extractor = detect_extractor(archive_path)?;
+  let mut progress = ExtractionProgress::open(archive_path)?;
+  log::info!(
+    "extracting {} ({}, {} entries already done)",
+    archive_path.display(),
+    extractor.name(),
+    progress.done_count()
+  );
+  extractor.extract_resumable(archive_path, extract_dir, &mut progress)?;
+  drop(progress);
+  log::info!("deleting {}", archive_path.display());
+  std::fs::remove_file(archive_path)?;
+  ExtractionProgress::remove(archive_path);
+  Ok(())
+}
+
+// How many chunks can be waiting for the extractor before the download is held up
+const STREAM_BUFFER_CHUNKS: usize = 16;
+
+// Hands the chunks sent to an `ArchiveStream` to the extractor, hashing them on the way
+struct ChunkReader {
+  receiver: mpsc::Receiver<Vec<u8>>,
+  chunk: Cursor<Vec<u8>>,
+  hasher: Sha256,
+}
+
+impl Read for ChunkReader {
+  fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    loop {
+      let read = self.chunk.read(buf)?;
+      if read > 0 || buf.is_empty() {
+        return Ok(read);
+      }
+      match self.receiver.blocking_recv() {
+        Some(chunk) => {
+          self.hasher.update(&chunk);
+          self.chunk = Cursor::new(chunk);
+        }
+        // The sender is gone, that's the end of the archive
+        None => return Ok(0),
+      }
+    }
+  }
+}
+
+fn extract_chunks(
+  mut reader: ChunkReader,
+  extract_dir: &Path,
+  archive_path: Option<&Path>,
+) -> Result<String, ArchiveError> {
+  let mut header = Vec::new();
+  (&mut reader).take(HEADER_LEN).read_to_end(&mut header)?;
+  let extractor = extractor_for_header(&header, "download")?;
+  log::info!(
+    "extracting download into {} ({})",
+    extract_dir.display(),
+    extractor.name()
+  );
+  if extractor.can_stream() {
+    let mut archive = Cursor::new(header).chain(&mut reader);
+    match archive_path {
+      Some(archive_path) => {
+        let mut progress = ExtractionProgress::open(archive_path)?;
+        extractor.extract_stream_resumable(&mut archive, extract_dir, &mut progress)?;
+        drop(progress);
+        ExtractionProgress::remove(archive_path);
+      }
+      None => extractor.extract_stream(&mut archive, extract_dir)?,
+    }
+    // Whatever follows the end of the archive (padding, mostly) still counts towards the digest
+    std::io::copy(&mut reader, &mut std::io::sink())?;
+  } else {
+    // Kept next to the folder it's extracted into, and removed the same as any other archive
+    let archive_path = PathBuf::from(format!("{}.{}", extract_dir.display(), extractor.name()));
+    let written = std::fs::File::create(&archive_path).and_then(|mut file| {
+      file.write_all(&header)?;
+      std::io::copy(&mut reader, &mut file)?;
+      file.flush()
+    });
+    if let Err(err) = written {
+      let _ = std::fs::remove_file(&archive_path);
+      return Err(err.into());
+    }
+    let extracted = extractor.extract(&archive_path, extract_dir);
+    log::info!("deleting {}", archive_path.display());
+    std::fs::remove_file(&archive_path)?;
+    extracted?;
+  }
+  Ok(hex(&reader.hasher.finalize()))
+}
+
+/// Extracts an archive into a directory as its chunks are sent, on a thread of its own so the
+/// sender (ie. a download) only waits when the extractor falls behind.  The format is detected
+/// from the first chunks like with `extract_archive`
+///
+/// Given where the archive is also being written, what's extracted is recorded next to it like
+/// with `extract_and_delete_archive`, so sending it again after an interruption skips that
+pub struct ArchiveStream {
+  sender: Option<mpsc::Sender<Vec<u8>>>,
+  extraction: Option<JoinHandle<Result<String, ArchiveError>>>,
+}
+
+impl ArchiveStream {
+  pub fn start(extract_dir: &Path, archive_path: Option<&Path>) -> Result<Self, ArchiveError> {
+    let (sender, receiver) = mpsc::channel(STREAM_BUFFER_CHUNKS);
+    let extract_dir: PathBuf = extract_dir.to_path_buf();
+    let archive_path: Option<PathBuf> = archive_path.map(Path::to_path_buf);
+    let extraction = std::thread::Builder::new()
+      .name("archive-stream".to_string())
+      .spawn(move || {
+        let reader = ChunkReader {
+          receiver,
+          chunk: Cursor::new(vec![]),
+          hasher: Sha256::new(),
+        };
+        extract_chunks(reader, &extract_dir, archive_path.as_deref())
+      })?;
+    Ok(Self {
+      sender: Some(sender),
+      extraction: Some(extraction),
+    })
+  }
+
+  /// A handle for sending the archive's chunks, in order.  Sending fails once the extraction
+  /// has stopped, `finish` then says why
+  pub fn sender(&self) -> mpsc::Sender<Vec<u8>> {
+    self
+      .sender
+      .clone()
+      .expect("the sender is only taken by finish")
+  }
+
+  /// Waits for the extraction of what was sent to be done, returning the archive's SHA-256.  The
+  /// archive is whatever was sent, so this has to be called only once all of it has been (and
+  /// every handle from `sender` dropped)
+  pub fn finish(mut self) -> Result<String, ArchiveError> {
+    self.sender.take();
+    match self.extraction.take().map(|extraction| extraction.join()) {
+      Some(Ok(result)) => result,
+      _ => Err(ArchiveError::Interrupted),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn archives_are_recognized_by_their_header() {
+    let mut tar = vec![0; HEADER_LEN as usize];
+    tar[257..262].copy_from_slice(b"ustar");
+    let cases: [(&[u8], &str); 6] = [
+      (b"PK\x03\x04\x14\x00", "zip"),
+      (b"PK\x05\x06\x00\x00", "zip"),
+      (&[0x1f, 0x8b, 0x08, 0x00], "tar.gz"),
+      (&[0x28, 0xb5, 0x2f, 0xfd, 0x00], "tar.zst"),
+      (&tar, "tar"),
+      (b"7z\xbc\xaf\x27\x1c\x00\x04", "7z"),
+    ];
+    for (header, format) in cases {
+      assert_eq!(extractor_for_header(header, "test").unwrap().name(), format);
+    }
+  }
+
+  #[test]
+  fn unsupported_formats_are_told_apart_from_unknown_ones() {
+    let unsupported: [(&[u8], &str); 2] = [
+      (b"\xfd7zXZ\x00\x00\x04", "xz"),
+      (b"Rar!\x1a\x07\x01\x00", "rar"),
+    ];
+    for (header, format) in unsupported {
+      match extractor_for_header(header, "test") {
+        Err(ArchiveError::UnsupportedFormat(name)) => assert_eq!(name, format),
+        other => panic!(
+          "{:?} for {}",
+          other.map(|extractor| extractor.name()),
+          format
+        ),
+      }
+    }
+    let unknown: [&[u8]; 5] = [b"", b"PK", b"\x1f", b"not an archive", &[0; 512]];
+    for header in unknown {
+      assert!(matches!(
+        extractor_for_header(header, "test.bin"),
+        Err(ArchiveError::UnknownFormat(name)) if name == "test.bin"
+      ));
+    }
+  }
+}
@@ -0,0 +1,130 @@
+// Spotting folders a cloud sync client (OneDrive, Dropbox, ...) is watching
+//
+// Sync clients open files as soon as they change to upload them, which keeps the compiler from
+// replacing its outputs mid-compile and can leave saves half written.  There's no API to ask
+// them, but each one leaves recognizable markers: OneDrive's folders are known from the
+// environment, Dropbox keeps a `.dropbox` file at the root of what it syncs, and Google Drive and
+// iCloud have fixed names for their roots.
+//
+// Only some clients can be told to leave a folder alone without the user going through its
+// settings, `exclude_from_sync` does what can be done for those.
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum CloudSyncClient {
+  OneDrive,
+  Dropbox,
+  GoogleDrive,
+  ICloud,
+}
+
+impl CloudSyncClient {
+  pub fn name(&self) -> &'static str {
+    match self {
+      CloudSyncClient::OneDrive => "OneDrive",
+      CloudSyncClient::Dropbox => "Dropbox",
+      CloudSyncClient::GoogleDrive => "Google Drive",
+      CloudSyncClient::ICloud => "iCloud",
+    }
+  }
+}
+
+fn onedrive_roots() -> Vec<PathBuf> {
+  ["OneDrive", "OneDriveConsumer", "OneDriveCommercial"]
+    .iter()
+    .filter_map(std::env::var_os)
+    .map(PathBuf::from)
+    .collect()
+}
+
+fn client_rooted_at(dir: &Path, onedrive_roots: &[PathBuf]) -> Option<CloudSyncClient> {
+  if onedrive_roots.iter().any(|root| root == dir) {
+    return Some(CloudSyncClient::OneDrive);
+  }
+  if dir.join(".dropbox").is_file() || dir.join(".dropbox.cache").is_dir() {
+    return Some(CloudSyncClient::Dropbox);
+  }
+  match dir.file_name().and_then(OsStr::to_str) {
+    Some("My Drive") | Some("Shared drives") => Some(CloudSyncClient::GoogleDrive),
+    Some("com~apple~CloudDocs") | Some("Mobile Documents") => Some(CloudSyncClient::ICloud),
+    _ => None,
+  }
+}
+
+/// The sync client watching `path`, along with the root of what it syncs, if there is one
+pub fn detect(path: &Path) -> Option<(CloudSyncClient, PathBuf)> {
+  let onedrive_roots = onedrive_roots();
+  path.ancestors().find_map(|dir| {
+    client_rooted_at(dir, &onedrive_roots).map(|client| (client, dir.to_path_buf()))
+  })
+}
+
+fn command_result(command: &mut Command) -> Result<(), std::io::Error> {
+  #[cfg(target_os = "windows")]
+  {
+    use std::os::windows::process::CommandExt;
+    command.creation_flags(0x08000000);
+  }
+  let output = command.output()?;
+  if !output.status.success() {
+    return Err(std::io::Error::new(
+      std::io::ErrorKind::Other,
+      String::from_utf8_lossy(&output.stderr).trim().to_string(),
+    ));
+  }
+  Ok(())
+}
+
+/// Asks `client` to stop syncing `dir`.  For Dropbox the folder is marked as ignored, OneDrive has
+/// no such thing, but pinning the folder keeps it from being made online-only underneath the game
+pub fn exclude_from_sync(client: CloudSyncClient, dir: &Path) -> Result<(), std::io::Error> {
+  match client {
+    CloudSyncClient::Dropbox => {
+      if cfg!(target_os = "windows") {
+        let script = format!(
+          "Set-Content -Path '{}' -Stream com.dropbox.ignored -Value 1",
+          dir.display().to_string().replace('\'', "''")
+        );
+        command_result(Command::new("powershell").args([
+          "-NoProfile",
+          "-NonInteractive",
+          "-Command",
+          &script,
+        ]))
+      } else if cfg!(target_os = "macos") {
+        command_result(
+          Command::new("xattr")
+            .args(["-w", "com.dropbox.ignored", "1"])
+            .arg(dir),
+        )
+      } else {
+        command_result(
+          Command::new("attr")
+            .args(["-s", "com.dropbox.ignored", "-V", "1"])
+            .arg(dir),
+        )
+      }
+    }
+    CloudSyncClient::OneDrive if cfg!(target_os = "windows") => {
+      // What's added to a pinned folder is pinned too, what's already in it has to be done
+      command_result(Command::new("attrib").args(["+P", "-U"]).arg(dir))?;
+      command_result(
+        Command::new("attrib")
+          .args(["+P", "-U", "/S", "/D"])
+          .arg(dir.join("*")),
+      )
+    }
+    _ => Err(std::io::Error::new(
+      std::io::ErrorKind::Unsupported,
+      format!(
+        "{} can only be told to stop syncing a folder from its own settings",
+        client.name()
+      ),
+    )),
+  }
+}
@@ -0,0 +1,143 @@
+// Downloading community content (texture packs, mods) from a URL the user pasted in
+//
+// Whatever is downloaded this way ends up in the game's data, so it's only fetched over HTTPS,
+// from hosts on the allow-list unless the user confirmed the host themselves, and up to a size
+// limit.  The host is pinned for the whole download: a redirect is only followed to an allowed
+// host (or the confirmed one), never to some other site the original host points at.
+use std::path::Path;
+
+use futures_util::StreamExt;
+use tokio::{fs::File, io::AsyncWriteExt};
+
+use crate::tasks::CancellationToken;
+
+/// Large HD texture packs come close to this, nothing legitimate is bigger
+pub const MAX_CONTENT_BYTES: u64 = 4 * 1024 * 1024 * 1024;
+const MAX_REDIRECTS: usize = 10;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ContentDownloadError {
+  #[error("'{0}' is not a valid URL")]
+  InvalidUrl(String),
+  #[error("content can only be downloaded over HTTPS")]
+  NotHttps,
+  #[error("{0} is not a known community host")]
+  UnknownHost(String),
+  #[error("the download was redirected to {0}, which isn't allowed")]
+  RedirectNotAllowed(String),
+  #[error("the download is larger than the {} MB limit", MAX_CONTENT_BYTES / 1024 / 1024)]
+  TooLarge,
+  #[error("download was cancelled")]
+  Cancelled,
+  #[error(transparent)]
+  IO(#[from] std::io::Error),
+  #[error(transparent)]
+  NetworkRequest(#[from] reqwest::Error),
+}
+
+/// Whether `host` is one of `allowed_hosts`, or a subdomain of one
+pub fn host_is_allowed(host: &str, allowed_hosts: &[String]) -> bool {
+  let host = host.trim_end_matches('.').to_lowercase();
+  allowed_hosts.iter().any(|allowed| {
+    let allowed = allowed.trim().trim_start_matches("*.").to_lowercase();
+    !allowed.is_empty() && (host == allowed || host.ends_with(&format!(".{}", allowed)))
+  })
+}
+
+/// Checks that `url` can be downloaded from, `confirmed_host` is whether the user agreed to
+/// download from its host even though it isn't on the allow-list
+pub fn check_content_url(
+  url: &str,
+  allowed_hosts: &[String],
+  confirmed_host: bool,
+) -> Result<reqwest::Url, ContentDownloadError> {
+  let parsed = reqwest::Url::parse(url.trim())
+    .map_err(|_| ContentDownloadError::InvalidUrl(url.to_string()))?;
+  if parsed.scheme() != "https" {
+    return Err(ContentDownloadError::NotHttps);
+  }
+  let host = match parsed.host_str() {
+    Some(host) => host.to_string(),
+    None => return Err(ContentDownloadError::InvalidUrl(url.to_string())),
+  };
+  if !confirmed_host && !host_is_allowed(&host, allowed_hosts) {
+    return Err(ContentDownloadError::UnknownHost(host));
+  }
+  Ok(parsed)
+}
+
+/// Downloads `url` (checked by `check_content_url`) to `destination`, returning its size.  Nothing
+/// is left behind if it's cancelled, too large or redirected somewhere it shouldn't be
+pub async fn download_content(
+  url: &reqwest::Url,
+  allowed_hosts: &[String],
+  destination: &Path,
+  cancellation_token: &CancellationToken,
+) -> Result<u64, ContentDownloadError> {
+  let mut pinned_hosts = allowed_hosts.to_vec();
+  if let Some(host) = url.host_str() {
+    pinned_hosts.push(host.to_string());
+  }
+  // Redirects that aren't allowed are handed back as is, rather than followed
+  let client = reqwest::Client::builder()
+    .redirect(reqwest::redirect::Policy::custom(move |attempt| {
+      let allowed = attempt.url().scheme() == "https"
+        && attempt
+          .url()
+          .host_str()
+          .map_or(false, |host| host_is_allowed(host, &pinned_hosts));
+      if !allowed || attempt.previous().len() >= MAX_REDIRECTS {
+        attempt.stop()
+      } else {
+        attempt.follow()
+      }
+    }))
+    .build()?;
+  let res = client.get(url.clone()).send().await?;
+  if res.status().is_redirection() {
+    let location = res
+      .headers()
+      .get(reqwest::header::LOCATION)
+      .and_then(|location| location.to_str().ok())
+      .unwrap_or("an unknown location")
+      .to_string();
+    return Err(ContentDownloadError::RedirectNotAllowed(location));
+  }
+  let res = res.error_for_status()?;
+  if res
+    .content_length()
+    .map_or(false, |len| len > MAX_CONTENT_BYTES)
+  {
+    return Err(ContentDownloadError::TooLarge);
+  }
+
+  let mut file = File::create(destination).await?;
+  let mut downloaded: u64 = 0;
+  let mut stream = res.bytes_stream();
+  let result = loop {
+    let chunk = match stream.next().await {
+      None => break Ok(downloaded),
+      Some(chunk) => chunk,
+    };
+    if cancellation_token.is_cancelled() {
+      break Err(ContentDownloadError::Cancelled);
+    }
+    let chunk = match chunk {
+      Ok(chunk) => chunk,
+      Err(err) => break Err(err.into()),
+    };
+    // The reported length can't be trusted, so the limit is enforced on what actually arrives
+    downloaded += chunk.len() as u64;
+    if downloaded > MAX_CONTENT_BYTES {
+      break Err(ContentDownloadError::TooLarge);
+    }
+    if let Err(err) = file.write_all(&chunk).await {
+      break Err(err.into());
+    }
+  };
+  drop(file);
+  if result.is_err() {
+    let _ = tokio::fs::remove_file(destination).await;
+  }
+  result
+}
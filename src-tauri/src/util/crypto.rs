@@ -0,0 +1,30 @@
+// Small helpers for handling secrets and hashes
+//
+// Comparing a secret with `==` stops at the first byte that differs, which tells whoever is
+// guessing how much of their guess was right from how long the answer took.
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+/// Whether `a` and `b` are the same, taking as long whatever they have in common
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+  if a.len() != b.len() {
+    return false;
+  }
+  a.iter().zip(b).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Lowercase hex, the way hashes and ids are written everywhere
+pub fn hex(bytes: &[u8]) -> String {
+  bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+  hex(&Sha256::digest(bytes))
+}
+
+/// `byte_count` random bytes as hex, for tokens and ids
+pub fn random_hex(byte_count: usize) -> String {
+  let mut bytes = vec![0u8; byte_count];
+  rand::thread_rng().fill_bytes(&mut bytes);
+  hex(&bytes)
+}
@@ -0,0 +1,152 @@
+// Replacing files that are the same in several tooling versions with hardlinks to one copy
+//
+// Consecutive releases share most of their binaries and assets.  Once a version is extracted its
+// files are compared against those of the versions already downloaded, one with the same
+// contents as a file on disk is replaced by a hardlink to it.  Files are only hashed when another
+// version has one of the same size, so most of a pass is spent listing directories.
+//
+// Hardlinks can't cross filesystems, version folders on another drive are left as they are.
+// Linked files share their contents, anything writing into a version's files has to replace them
+// rather than write over them.
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use same_file::Handle;
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use super::file::sha256_file;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DedupSavings {
+  /// Files that are hardlinks to a copy in another version
+  pub files: u64,
+  pub bytes: u64,
+}
+
+// The non-empty regular files under `dir`, symlinks aren't followed
+fn regular_files(dir: &Path, skipped: &[&str]) -> Vec<(PathBuf, std::fs::Metadata)> {
+  WalkDir::new(dir)
+    .into_iter()
+    .filter_map(|entry| entry.ok())
+    .filter(|entry| entry.file_type().is_file())
+    .filter(|entry| !skipped.contains(&entry.file_name().to_string_lossy().as_ref()))
+    .filter_map(|entry| Some((entry.path().to_path_buf(), entry.metadata().ok()?)))
+    .filter(|(_, metadata)| metadata.len() > 0)
+    .collect()
+}
+
+// Puts a hardlink to `original` in place of `duplicate`, the link is made next to it first so
+// `duplicate` is never missing
+fn replace_with_link(original: &Path, duplicate: &Path) -> std::io::Result<()> {
+  let temp = duplicate.with_file_name(format!(
+    ".{}.dedup",
+    duplicate.file_name().unwrap_or_default().to_string_lossy()
+  ));
+  let _ = std::fs::remove_file(&temp);
+  std::fs::hard_link(original, &temp)?;
+  std::fs::rename(&temp, duplicate).map_err(|err| {
+    let _ = std::fs::remove_file(&temp);
+    err
+  })
+}
+
+/// Replaces the files of `version_dir` that have a copy in one of `other_dirs` with hardlinks to
+/// it.  Files named in `skipped` are left alone
+pub fn deduplicate_version(
+  version_dir: &Path,
+  other_dirs: &[PathBuf],
+  skipped: &[&str],
+) -> DedupSavings {
+  // Indexed by which of `other_dirs` they're in, to give up on those that can't be linked to
+  let mut by_size: HashMap<u64, Vec<(usize, PathBuf)>> = HashMap::new();
+  for (index, dir) in other_dirs.iter().enumerate() {
+    for (path, metadata) in regular_files(dir, skipped) {
+      by_size
+        .entry(metadata.len())
+        .or_default()
+        .push((index, path));
+    }
+  }
+  let mut unlinkable = vec![false; other_dirs.len()];
+  let mut hashes: HashMap<PathBuf, Option<String>> = HashMap::new();
+  let mut savings = DedupSavings::default();
+  for (path, metadata) in regular_files(version_dir, skipped) {
+    let candidates = match by_size.get(&metadata.len()) {
+      Some(candidates) => candidates,
+      None => continue,
+    };
+    if candidates
+      .iter()
+      .any(|(_, candidate)| same_file::is_same_file(candidate, &path).unwrap_or(false))
+    {
+      continue;
+    }
+    let hash = match sha256_file(&path) {
+      Ok(hash) => hash,
+      Err(_) => continue,
+    };
+    for (index, candidate) in candidates {
+      if unlinkable[*index] {
+        continue;
+      }
+      let candidate_hash = hashes
+        .entry(candidate.clone())
+        .or_insert_with(|| sha256_file(candidate).ok());
+      if candidate_hash.as_deref() != Some(hash.as_str()) {
+        continue;
+      }
+      match replace_with_link(candidate, &path) {
+        Ok(()) => {
+          savings.files += 1;
+          savings.bytes += metadata.len();
+        }
+        Err(err) => {
+          // Most likely on another filesystem, the rest of its files won't link either
+          log::info!(
+            "Not deduplicating against {}: {}",
+            other_dirs[*index].display(),
+            err
+          );
+          unlinkable[*index] = true;
+          continue;
+        }
+      }
+      break;
+    }
+  }
+  savings
+}
+
+/// How much space is saved by files in `dirs` being hardlinks to each other
+pub fn measure_savings(dirs: &[PathBuf]) -> DedupSavings {
+  // Links to the same file share its size and modification time, so only files that have the
+  // same ones need to be opened to check
+  let mut groups: HashMap<(u64, Option<std::time::SystemTime>), Vec<PathBuf>> = HashMap::new();
+  for dir in dirs {
+    for (path, metadata) in regular_files(dir, &[]) {
+      groups
+        .entry((metadata.len(), metadata.modified().ok()))
+        .or_default()
+        .push(path);
+    }
+  }
+  let mut savings = DedupSavings::default();
+  for ((len, _), paths) in groups.into_iter().filter(|(_, paths)| paths.len() > 1) {
+    let mut seen: Vec<Handle> = vec![];
+    for path in paths {
+      let handle = match Handle::from_path(&path) {
+        Ok(handle) => handle,
+        Err(_) => continue,
+      };
+      if seen.contains(&handle) {
+        savings.files += 1;
+        savings.bytes += len;
+      } else {
+        seen.push(handle);
+      }
+    }
+  }
+  savings
+}
@@ -1,4 +1,21 @@
-use std::{io::BufRead, path::PathBuf};
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+use std::{
+  io::BufRead,
+  path::{Path, PathBuf},
+  time::{Duration, Instant},
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+
+use crate::tasks::CancellationToken;
+
+use super::crypto::hex;
+
+const DELETION_PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+const COPY_PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
 
 pub fn delete_dir(path: &PathBuf) -> Result<(), std::io::Error> {
   if path.exists() && path.is_dir() {
@@ -7,6 +24,312 @@ pub fn delete_dir(path: &PathBuf) -> Result<(), std::io::Error> {
   Ok(())
 }
 
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DeletionProgress {
+  pub files_removed: u64,
+  pub total_files: u64,
+  pub bytes_removed: u64,
+  pub total_bytes: u64,
+}
+
+/// Deletes `paths` a file at a time, reporting how far along it is to `on_progress` so that huge
+/// folders don't look stuck.  If `token` is cancelled it stops with `ErrorKind::Interrupted`,
+/// leaving whatever wasn't deleted yet in place
+pub fn delete_dirs_with_progress(
+  paths: &[PathBuf],
+  token: &CancellationToken,
+  mut on_progress: impl FnMut(&DeletionProgress),
+) -> Result<DeletionProgress, std::io::Error> {
+  let paths: Vec<&PathBuf> = paths.iter().filter(|path| path.is_dir()).collect();
+  // Counted up-front, so there's a total to report progress against.  Links aren't followed, and
+  // they're removed along with the directories at the end
+  let files: Vec<(PathBuf, u64)> = paths
+    .iter()
+    .flat_map(|path| {
+      WalkDir::new(path)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+    })
+    .filter(|entry| entry.file_type().is_file())
+    .map(|entry| {
+      let size = entry.metadata().map_or(0, |metadata| metadata.len());
+      (entry.into_path(), size)
+    })
+    .collect();
+  let mut progress = DeletionProgress {
+    total_files: files.len() as u64,
+    total_bytes: files.iter().map(|(_, size)| size).sum(),
+    ..Default::default()
+  };
+  on_progress(&progress);
+  let mut last_report = Instant::now();
+  for (file, size) in files {
+    if token.is_cancelled() {
+      return Err(std::io::Error::new(
+        std::io::ErrorKind::Interrupted,
+        "deletion was cancelled",
+      ));
+    }
+    std::fs::remove_file(&file)?;
+    progress.files_removed += 1;
+    progress.bytes_removed += size;
+    if last_report.elapsed() >= DELETION_PROGRESS_INTERVAL {
+      on_progress(&progress);
+      last_report = Instant::now();
+    }
+  }
+  for path in paths {
+    std::fs::remove_dir_all(path)?;
+  }
+  on_progress(&progress);
+  Ok(progress)
+}
+
+pub fn sha256_file(path: &Path) -> std::io::Result<String> {
+  let mut file = std::fs::File::open(path)?;
+  let mut hasher = Sha256::new();
+  std::io::copy(&mut file, &mut hasher)?;
+  Ok(hex(&hasher.finalize()))
+}
+
+/// Reads one of the JSON files the launcher keeps for itself, `None` if it's missing or can't be
+/// read - callers fall back to starting over
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Option<T> {
+  std::fs::read_to_string(path)
+    .ok()
+    .and_then(|content| serde_json::from_str(&content).ok())
+}
+
+pub fn write_json<T: Serialize>(path: &Path, value: &T) -> std::io::Result<()> {
+  let content = serde_json::to_string_pretty(value).map_err(std::io::Error::from)?;
+  std::fs::write(path, content)
+}
+
+/// Reads the JSON file (or the default, if there's nothing usable yet), applies `apply` and
+/// writes it back
+pub fn update_json<T: Default + Serialize + DeserializeOwned>(
+  path: &Path,
+  apply: impl FnOnce(&mut T),
+) -> std::io::Result<()> {
+  let mut value = read_json(path).unwrap_or_default();
+  apply(&mut value);
+  write_json(path, &value)
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyProgress {
+  pub files_copied: u64,
+  pub total_files: u64,
+  pub bytes_copied: u64,
+  pub total_bytes: u64,
+}
+
+/// How each copied file is checked against the original once it's written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyVerification {
+  Size,
+  /// Reads both files back in full, for data that's worth the wait (ie. saves)
+  Hash,
+}
+
+fn verify_copy(
+  src: &Path,
+  dst: &Path,
+  size: u64,
+  verification: CopyVerification,
+) -> Result<(), std::io::Error> {
+  let matches = std::fs::metadata(dst)?.len() == size
+    && (verification == CopyVerification::Size || sha256_file(src)? == sha256_file(dst)?);
+  if !matches {
+    return Err(std::io::Error::new(
+      std::io::ErrorKind::InvalidData,
+      format!(
+        "'{}' doesn't match '{}' after copying it",
+        dst.display(),
+        src.display()
+      ),
+    ));
+  }
+  Ok(())
+}
+
+fn copy_link(src: &Path, dst: &Path) -> Result<(), std::io::Error> {
+  let target = std::fs::read_link(src)?;
+  if src.is_dir() {
+    return replace_dir_link(&dst.to_path_buf(), &target);
+  }
+  #[cfg(unix)]
+  std::os::unix::fs::symlink(&target, dst)?;
+  #[cfg(windows)]
+  log::warn!(
+    "Not copying the link '{}', file links can't be made without elevation",
+    src.display()
+  );
+  Ok(())
+}
+
+/// Copies what's in `src` into `dst` a file at a time, which works across drives, reporting how
+/// far along it is to `on_progress`.  Files keep their permissions and modification times, links
+/// are copied as links rather than followed, and every file is checked against the original by
+/// `verification`.  If `token` is cancelled it stops with `ErrorKind::Interrupted`, leaving
+/// whatever was copied so far in place.  `src` and `dst` can't be inside one another.
+///
+/// Version folder moves, shared version adoption and snapshot and backup restores all go
+/// through this
+pub fn copy_dir_with_progress(
+  src: &Path,
+  dst: &Path,
+  verification: CopyVerification,
+  token: &CancellationToken,
+  mut on_progress: impl FnMut(&CopyProgress),
+) -> Result<CopyProgress, std::io::Error> {
+  refuse_overlapping(src, dst)?;
+  let entries: Vec<walkdir::DirEntry> = WalkDir::new(src)
+    .into_iter()
+    .collect::<Result<_, _>>()
+    .map_err(std::io::Error::from)?;
+  let mut progress = CopyProgress::default();
+  for entry in entries.iter().filter(|entry| entry.file_type().is_file()) {
+    progress.total_files += 1;
+    progress.total_bytes += entry.metadata().map_or(0, |metadata| metadata.len());
+  }
+  on_progress(&progress);
+  let mut last_report = Instant::now();
+  for entry in entries {
+    if token.is_cancelled() {
+      return Err(std::io::Error::new(
+        std::io::ErrorKind::Interrupted,
+        "copy was cancelled",
+      ));
+    }
+    let relative = entry
+      .path()
+      .strip_prefix(src)
+      .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+    let target = dst.join(relative);
+    let file_type = entry.file_type();
+    if file_type.is_dir() {
+      std::fs::create_dir_all(&target)?;
+      continue;
+    }
+    if file_type.is_symlink() {
+      copy_link(entry.path(), &target)?;
+      continue;
+    }
+    let metadata = entry.metadata().map_err(std::io::Error::from)?;
+    // Copying also carries the permissions over
+    std::fs::copy(entry.path(), &target)?;
+    filetime::set_file_mtime(
+      &target,
+      filetime::FileTime::from_last_modification_time(&metadata),
+    )?;
+    verify_copy(entry.path(), &target, metadata.len(), verification)?;
+    progress.files_copied += 1;
+    progress.bytes_copied += metadata.len();
+    if last_report.elapsed() >= COPY_PROGRESS_INTERVAL {
+      on_progress(&progress);
+      last_report = Instant::now();
+    }
+  }
+  on_progress(&progress);
+  Ok(progress)
+}
+
+// Resolves links and `..` for the part of `path` that exists, the rest is kept as it is
+fn resolve_path(path: &Path) -> PathBuf {
+  let mut existing = path;
+  let mut rest = vec![];
+  while let Some(parent) = existing.parent() {
+    if existing.exists() {
+      break;
+    }
+    if let Some(name) = existing.file_name() {
+      rest.push(name);
+    }
+    existing = parent;
+  }
+  let mut resolved = existing
+    .canonicalize()
+    .unwrap_or_else(|_| existing.to_path_buf());
+  resolved.extend(rest.iter().rev());
+  resolved
+}
+
+/// Whether `path` is `dir` or lies inside of it
+pub fn is_within(path: &Path, dir: &Path) -> bool {
+  resolve_path(path).starts_with(resolve_path(dir))
+}
+
+/// Paths the way the frontend shows them
+pub fn display_paths(paths: &[PathBuf]) -> Vec<String> {
+  paths
+    .iter()
+    .map(|path| path.to_string_lossy().into_owned())
+    .collect()
+}
+
+/// Whether either path is the other or lies inside of it, copying or moving one into the other
+/// would never finish (or remove what was just copied)
+pub fn paths_overlap(a: &Path, b: &Path) -> bool {
+  let (a, b) = (resolve_path(a), resolve_path(b));
+  a.starts_with(&b) || b.starts_with(&a)
+}
+
+fn refuse_overlapping(src: &Path, dst: &Path) -> Result<(), std::io::Error> {
+  if paths_overlap(src, dst) {
+    return Err(std::io::Error::new(
+      std::io::ErrorKind::InvalidInput,
+      format!(
+        "'{}' and '{}' are inside one another",
+        src.display(),
+        dst.display()
+      ),
+    ));
+  }
+  Ok(())
+}
+
+/// Moves `src` to `dst`, which mustn't exist yet (or be an empty directory).  Within a drive it's
+/// a rename, otherwise it's copied over by `copy_dir_with_progress` and `src` is only removed
+/// once everything made it
+pub fn move_dir_with_progress(
+  src: &Path,
+  dst: &Path,
+  verification: CopyVerification,
+  token: &CancellationToken,
+  on_progress: impl FnMut(&CopyProgress),
+) -> Result<CopyProgress, std::io::Error> {
+  // Checked before anything is touched, the clean up below would remove `src` otherwise
+  refuse_overlapping(src, dst)?;
+  if let Some(parent) = dst.parent() {
+    std::fs::create_dir_all(parent)?;
+  }
+  let _ = std::fs::remove_dir(dst);
+  if std::fs::rename(src, dst).is_ok() {
+    return Ok(CopyProgress::default());
+  }
+  let result = copy_dir_with_progress(src, dst, verification, token, on_progress);
+  match result {
+    Ok(progress) => {
+      std::fs::remove_dir_all(src)?;
+      Ok(progress)
+    }
+    Err(err) => {
+      // Half a copy is no use to anyone, the original is still there
+      if let Err(cleanup_err) = std::fs::remove_dir_all(dst) {
+        log::warn!(
+          "Unable to clean up the partial copy at '{}': {}",
+          dst.display(),
+          cleanup_err
+        );
+      }
+      Err(err)
+    }
+  }
+}
+
 pub fn create_dir(path: &PathBuf) -> Result<(), std::io::Error> {
   if path.exists() {
     return Ok(());
@@ -22,6 +345,68 @@ pub fn delete_file(path: &PathBuf) -> Result<(), std::io::Error> {
   Ok(())
 }
 
+/// Removes `link` if it is a symlink (or junction on windows), the directory it points to is left
+/// alone.  Anything at `link` that isn't a link is an error, so real data is never deleted
+pub fn remove_dir_link(link: &PathBuf) -> Result<(), std::io::Error> {
+  match std::fs::symlink_metadata(link) {
+    Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+    Err(err) => Err(err),
+    Ok(metadata) if metadata.file_type().is_symlink() => {
+      if cfg!(windows) {
+        std::fs::remove_dir(link)
+      } else {
+        std::fs::remove_file(link)
+      }
+    }
+    Ok(_) => Err(std::io::Error::new(
+      std::io::ErrorKind::AlreadyExists,
+      format!("'{}' exists and is not a link", link.display()),
+    )),
+  }
+}
+
+/// Points the directory link at `link` to `target`, creating it if needed
+///
+/// The new link is made under a temporary name and renamed into place, so on unix anything
+/// following the link sees either the old or the new target.  Windows can't rename over a
+/// directory, so the old junction is removed just before the rename.
+pub fn replace_dir_link(link: &PathBuf, target: &PathBuf) -> Result<(), std::io::Error> {
+  let link_name = link
+    .file_name()
+    .map(|name| name.to_string_lossy().into_owned())
+    .unwrap_or_default();
+  let tmp_link = link.with_file_name(format!(".{}.tmp", link_name));
+  remove_dir_link(&tmp_link)?;
+
+  #[cfg(unix)]
+  std::os::unix::fs::symlink(target, &tmp_link)?;
+  #[cfg(windows)]
+  {
+    // Junctions, unlike symlinks, don't require developer mode or elevation
+    let mut command = std::process::Command::new("cmd");
+    command
+      .arg("/C")
+      .arg("mklink")
+      .arg("/J")
+      .arg(&tmp_link)
+      .arg(target)
+      .creation_flags(0x08000000);
+    let output = command.output()?;
+    if !output.status.success() {
+      return Err(std::io::Error::new(
+        std::io::ErrorKind::Other,
+        format!(
+          "mklink failed: {}",
+          String::from_utf8_lossy(&output.stderr).trim()
+        ),
+      ));
+    }
+    remove_dir_link(link)?;
+  }
+
+  std::fs::rename(&tmp_link, link)
+}
+
 pub fn overwrite_dir(src: &PathBuf, dst: &PathBuf) -> Result<(), fs_extra::error::Error> {
   if src.exists() {
     let mut options = fs_extra::dir::CopyOptions::new();
@@ -65,3 +450,14 @@ pub fn touch_file(path: &PathBuf) -> std::io::Result<()> {
     Err(e) => Err(e),
   }
 }
+
+/// Whether files can be created in `dir`, found out by creating one, as permissions alone don't
+/// account for ACLs or read-only mounts
+pub fn is_dir_writable(dir: &Path) -> bool {
+  let probe = dir.join(".write-test.tmp");
+  let writable = std::fs::write(&probe, b"").is_ok();
+  if writable {
+    let _ = std::fs::remove_file(&probe);
+  }
+  writable
+}
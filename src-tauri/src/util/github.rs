@@ -0,0 +1,201 @@
+// Requests to GitHub's REST API, for everything that looks up releases
+//
+// Anonymous requests are limited to 60 an hour per IP address, which a few launchers sharing a
+// connection (a LAN party, a school) use up quickly.  The limit GitHub reports with each response
+// is remembered for the whole launcher: requests wait for it to reset if that's soon, and
+// otherwise fail right away with `GithubError::RateLimited` rather than being sent only to be
+// rejected.  A personal access token set in the config raises the limit to 5000 an hour.
+//
+// Requests are made without access to any managed state, so like the message catalog the limit
+// is kept in globals.
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+
+use chrono::TimeZone;
+use reqwest::{header::HeaderMap, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+// The longest a request waits for the limit to reset before giving up
+const MAX_WAIT: Duration = Duration::from_secs(60);
+const MAX_ATTEMPTS: u32 = 3;
+// Secondary limits don't always say how long to wait, GitHub asks for at least a minute
+const SECONDARY_LIMIT_BACKOFF: Duration = Duration::from_secs(60);
+
+// `UNKNOWN` until GitHub has reported them, times are unix times in seconds
+const UNKNOWN: u64 = u64::MAX;
+static LIMITED_UNTIL: AtomicU64 = AtomicU64::new(0);
+static REMAINING: AtomicU64 = AtomicU64::new(UNKNOWN);
+static LIMIT: AtomicU64 = AtomicU64::new(UNKNOWN);
+static RESETS_AT: AtomicU64 = AtomicU64::new(UNKNOWN);
+
+#[derive(Debug, thiserror::Error)]
+pub enum GithubError {
+  #[error(transparent)]
+  NetworkRequest(#[from] reqwest::Error),
+  #[error("GitHub's rate limit was reached, try again in {} minutes", (.0 + 59) / 60)]
+  RateLimited(u64),
+  #[error("GitHub rejected the access token, check it in the settings")]
+  InvalidToken,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct GithubRateLimit {
+  /// `None` until a request to GitHub has been made
+  pub remaining: Option<u64>,
+  pub limit: Option<u64>,
+  pub resets_at: Option<String>,
+  /// Whether requests are being held back until the limit resets
+  pub limited: bool,
+}
+
+fn unix_now() -> u64 {
+  SystemTime::now()
+    .duration_since(SystemTime::UNIX_EPOCH)
+    .map(|duration| duration.as_secs())
+    .unwrap_or(0)
+}
+
+fn known(value: u64) -> Option<u64> {
+  (value != UNKNOWN).then(|| value)
+}
+
+/// What's known of the rate limit from the last response
+pub fn rate_limit() -> GithubRateLimit {
+  GithubRateLimit {
+    remaining: known(REMAINING.load(Ordering::SeqCst)),
+    limit: known(LIMIT.load(Ordering::SeqCst)),
+    resets_at: known(RESETS_AT.load(Ordering::SeqCst))
+      .and_then(|secs| chrono::Local.timestamp_opt(secs as i64, 0).single())
+      .map(|time| time.to_rfc3339()),
+    limited: LIMITED_UNTIL.load(Ordering::SeqCst) > unix_now(),
+  }
+}
+
+/// Forgets the limit, for when the token changes as the new one has its own
+pub fn reset_rate_limit() {
+  LIMITED_UNTIL.store(0, Ordering::SeqCst);
+  REMAINING.store(UNKNOWN, Ordering::SeqCst);
+  LIMIT.store(UNKNOWN, Ordering::SeqCst);
+  RESETS_AT.store(UNKNOWN, Ordering::SeqCst);
+}
+
+fn header_u64(headers: &HeaderMap, name: &str) -> Option<u64> {
+  headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+// Remembers the limit the response reports, and how long to wait before trying again if it was
+// rejected because of it
+fn record_rate_limit(status: StatusCode, headers: &HeaderMap, attempt: u32) -> Option<u64> {
+  let remaining = header_u64(headers, "x-ratelimit-remaining");
+  let reset = header_u64(headers, "x-ratelimit-reset");
+  if let Some(remaining) = remaining {
+    REMAINING.store(remaining, Ordering::SeqCst);
+  }
+  if let Some(limit) = header_u64(headers, "x-ratelimit-limit") {
+    LIMIT.store(limit, Ordering::SeqCst);
+  }
+  if let Some(reset) = reset {
+    RESETS_AT.store(reset, Ordering::SeqCst);
+  }
+  let now = unix_now();
+  let wait = match (remaining, reset) {
+    // Even if this one went through, the next one wouldn't
+    (Some(0), Some(reset)) => Some(reset.saturating_sub(now)),
+    _ => None,
+  };
+  let wait = if status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::FORBIDDEN {
+    match header_u64(headers, "retry-after").or(wait) {
+      Some(wait) => Some(wait),
+      // A 403 without any of the headers is a plain refusal, not a limit
+      None if status == StatusCode::TOO_MANY_REQUESTS => {
+        Some(SECONDARY_LIMIT_BACKOFF.as_secs() << (attempt - 1))
+      }
+      None => None,
+    }
+  } else {
+    wait
+  };
+  if let Some(wait) = wait {
+    LIMITED_UNTIL.fetch_max(now + wait, Ordering::SeqCst);
+  }
+  wait
+}
+
+// Holds the request until the limit resets, if it's soon enough
+async fn wait_for_rate_limit() -> Result<(), GithubError> {
+  let wait = LIMITED_UNTIL
+    .load(Ordering::SeqCst)
+    .saturating_sub(unix_now());
+  if wait == 0 {
+    return Ok(());
+  }
+  if wait > MAX_WAIT.as_secs() {
+    return Err(GithubError::RateLimited(wait));
+  }
+  log::info!("Waiting {}s for GitHub's rate limit to reset", wait);
+  tokio::time::sleep(Duration::from_secs(wait)).await;
+  Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct GithubClient {
+  client: reqwest::Client,
+  token: Option<String>,
+}
+
+impl GithubClient {
+  /// `token` is a personal access token to authenticate with, requests are anonymous without one
+  pub fn new(token: Option<String>) -> Result<Self, GithubError> {
+    // GitHub's API rejects requests without a user agent
+    let client = reqwest::Client::builder()
+      .user_agent("OpenGOAL-Launcher")
+      .build()?;
+    Ok(Self {
+      client,
+      token: token.filter(|token| !token.trim().is_empty()),
+    })
+  }
+
+  /// For what's downloaded from GitHub rather than asked of its API (ie. release assets), which
+  /// isn't rate limited
+  pub fn http(&self) -> &reqwest::Client {
+    &self.client
+  }
+
+  pub async fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T, GithubError> {
+    let mut attempt = 1;
+    loop {
+      wait_for_rate_limit().await?;
+      let mut request = self
+        .client
+        .get(url)
+        .header("Accept", "application/vnd.github+json");
+      if let Some(token) = &self.token {
+        request = request.bearer_auth(token.trim());
+      }
+      let res = request.send().await?;
+      if res.status() == StatusCode::UNAUTHORIZED && self.token.is_some() {
+        return Err(GithubError::InvalidToken);
+      }
+      let wait = record_rate_limit(res.status(), res.headers(), attempt);
+      let err = match res.error_for_status() {
+        Ok(res) => return Ok(res.json().await?),
+        Err(err) => err,
+      };
+      match wait {
+        Some(wait) if attempt < MAX_ATTEMPTS => {
+          log::warn!(
+            "GitHub rate limited {} (attempt {}), retrying in {}s",
+            url,
+            attempt,
+            wait
+          );
+          attempt += 1;
+        }
+        Some(wait) => return Err(GithubError::RateLimited(wait)),
+        None => return Err(err.into()),
+      }
+    }
+  }
+}
@@ -1,6 +1,78 @@
-use futures_util::StreamExt;
-use std::path::PathBuf;
-use tokio::{fs::File, io::AsyncWriteExt};
+use futures_util::{future::try_join_all, StreamExt};
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::{
+  fs::File,
+  io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt},
+};
+
+use crate::tasks::CancellationToken;
+
+/// Files smaller than this download quickly enough over one connection
+const MIN_CHUNKED_SIZE: u64 = 16 * 1024 * 1024;
+// How often the combined progress of a chunked download is reported
+const CHUNKED_PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+// A range that fails is picked up where it stopped, this many times before giving up
+const RANGE_ATTEMPTS: u32 = 3;
+const RANGE_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Holds a download between chunks while it's set, and keeps it under a bandwidth cap if it has
+/// one.  The connections of a chunked download share the flag, and so the cap
+#[derive(Debug, Clone, Default)]
+pub struct PauseFlag {
+  paused: Arc<AtomicBool>,
+  // Bytes per second, `0` for no cap
+  bandwidth_cap: Arc<AtomicU64>,
+  // When the bytes let through so far will have taken their share of the cap
+  next_chunk_at: Arc<Mutex<Option<Instant>>>,
+}
+
+impl PauseFlag {
+  pub fn set(&self, paused: bool) {
+    self.paused.store(paused, Ordering::SeqCst);
+  }
+
+  pub fn is_paused(&self) -> bool {
+    self.paused.load(Ordering::SeqCst)
+  }
+
+  pub fn set_bandwidth_cap(&self, bytes_per_sec: Option<u64>) {
+    self
+      .bandwidth_cap
+      .store(bytes_per_sec.unwrap_or(0), Ordering::SeqCst);
+  }
+
+  /// Waits for as long as `bytes` take at the bandwidth cap, on top of what the earlier chunks
+  /// are still waiting for
+  pub async fn throttle(&self, bytes: u64) {
+    let cap = self.bandwidth_cap.load(Ordering::SeqCst);
+    if cap == 0 {
+      return;
+    }
+    let until = match self.next_chunk_at.lock() {
+      Ok(mut next_chunk_at) => {
+        let now = Instant::now();
+        let start = next_chunk_at.filter(|at| *at > now).unwrap_or(now);
+        let until = start + Duration::from_secs_f64(bytes as f64 / cap as f64);
+        *next_chunk_at = Some(until);
+        until
+      }
+      Err(_) => return,
+    };
+    tokio::time::sleep_until(until.into()).await;
+  }
+
+  /// Resolves once the flag is cleared, or `cancellation_token` is cancelled
+  pub async fn wait_while_paused(&self, cancellation_token: &CancellationToken) {
+    while self.is_paused() && !cancellation_token.is_cancelled() {
+      tokio::time::sleep(PAUSE_POLL_INTERVAL).await;
+    }
+  }
+}
 
 #[derive(Debug, thiserror::Error)]
 pub enum NetworkError {
@@ -8,19 +80,421 @@ pub enum NetworkError {
   IO(#[from] std::io::Error),
   #[error(transparent)]
   NetworkRequest(#[from] reqwest::Error),
+  #[error("download was cancelled")]
+  Cancelled,
+  #[error("server ignored the range request for bytes {0}-{1}")]
+  RangeIgnored(u64, u64),
+  #[error("server sent {2} bytes for the range {0}-{1}")]
+  RangeIncomplete(u64, u64, u64),
+  #[error("the download's consumer stopped taking it")]
+  SinkClosed,
+}
+
+/// The size the server reports for `url`, if it reports one
+pub async fn content_length(url: &String) -> Result<Option<u64>, NetworkError> {
+  let client = reqwest::Client::new();
+  let res = client.head(url).send().await?;
+  Ok(res.content_length())
+}
+
+// What the server called the file when it was first requested (its ETag, or when it was last
+// modified), kept next to the partial file.  It's only resumed if the server still has the same
+// file, the rest of a newer one would corrupt it
+fn validator_path(destination: &Path) -> PathBuf {
+  let mut path = destination.as_os_str().to_owned();
+  path.push(".validator");
+  PathBuf::from(path)
+}
+
+fn response_validator(headers: &reqwest::header::HeaderMap) -> Option<String> {
+  let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+  // Weak ETags can't be used for ranges
+  header(reqwest::header::ETAG)
+    .filter(|etag| !etag.starts_with("W/"))
+    .or_else(|| header(reqwest::header::LAST_MODIFIED))
+    .map(|validator| validator.to_string())
+}
+
+async fn save_validator(destination: &Path, validator: Option<String>) -> Result<(), NetworkError> {
+  let path = validator_path(destination);
+  match validator {
+    Some(validator) => tokio::fs::write(path, validator).await?,
+    None if path.exists() => tokio::fs::remove_file(path).await?,
+    None => {}
+  }
+  Ok(())
+}
+
+/// Removes a (partially) downloaded file, along with what was kept to resume it
+pub fn remove_partial_download(destination: &Path) -> Result<(), std::io::Error> {
+  for path in [destination.to_path_buf(), validator_path(destination)] {
+    match std::fs::remove_file(&path) {
+      Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err),
+      _ => {}
+    }
+  }
+  Ok(())
+}
+
+enum Resumed {
+  // The file was all there already
+  Complete(u64),
+  // The response carries the file from this offset on
+  From(u64, reqwest::Response),
+}
+
+// Requests `url`, or only what `destination` is missing of it if an earlier attempt got part of
+// it and the server still has the same file.  A server that doesn't support ranges, or whose
+// file changed, sends all of it again, which then replaces the partial one
+async fn request_rest(
+  client: &reqwest::Client,
+  url: &String,
+  destination: &Path,
+) -> Result<Resumed, NetworkError> {
+  let partial_len = tokio::fs::metadata(destination)
+    .await
+    .map_or(0, |metadata| metadata.len());
+  let validator = tokio::fs::read_to_string(validator_path(destination))
+    .await
+    .ok();
+  if let (true, Some(validator)) = (partial_len > 0, validator) {
+    let res = client
+      .get(url)
+      .header(reqwest::header::RANGE, format!("bytes={}-", partial_len))
+      .header(reqwest::header::IF_RANGE, validator.trim())
+      .send()
+      .await?;
+    if res.status() == reqwest::StatusCode::PARTIAL_CONTENT {
+      log::info!("Resuming download of {} at {} bytes", url, partial_len);
+      return Ok(Resumed::From(partial_len, res));
+    }
+    let total = res
+      .headers()
+      .get(reqwest::header::CONTENT_RANGE)
+      .and_then(|value| value.to_str().ok())
+      .and_then(|value| value.rsplit('/').next())
+      .and_then(|total| total.parse::<u64>().ok());
+    if res.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE && total == Some(partial_len) {
+      return Ok(Resumed::Complete(partial_len));
+    }
+    if res.status().is_success() {
+      save_validator(destination, response_validator(res.headers())).await?;
+      return Ok(Resumed::From(0, res));
+    }
+  }
+  let res = client.get(url).send().await?.error_for_status()?;
+  save_validator(destination, response_validator(res.headers())).await?;
+  Ok(Resumed::From(0, res))
 }
 
-pub async fn download_file(url: &String, destination: &PathBuf) -> Result<(), NetworkError> {
+async fn open_partial(destination: &Path, offset: u64) -> Result<File, NetworkError> {
+  if offset == 0 {
+    return Ok(File::create(destination).await?);
+  }
+  Ok(
+    tokio::fs::OpenOptions::new()
+      .append(true)
+      .open(destination)
+      .await?,
+  )
+}
+
+/// Downloads `url`, calling `on_progress` with the bytes downloaded so far and the total size
+/// (if the server reports it) after every chunk.  The download stops at the next chunk once
+/// `cancellation_token` is cancelled.  While `pause` is set it's held before the next chunk
+///
+/// Whatever arrived is kept when the download stops early, and the next download to the same
+/// `destination` only requests the rest (see `request_rest`).  `remove_partial_download` is for
+/// when it's no longer wanted
+pub async fn download_file_with_progress(
+  url: &String,
+  destination: &Path,
+  cancellation_token: &CancellationToken,
+  pause: &PauseFlag,
+  mut on_progress: impl FnMut(u64, Option<u64>),
+) -> Result<(), NetworkError> {
   let client = reqwest::Client::new();
-  let req = client.get(url);
-  let res = req.send().await?;
+  let (res, mut downloaded) = match request_rest(&client, url, destination).await? {
+    Resumed::Complete(length) => {
+      on_progress(length, Some(length));
+      return Ok(());
+    }
+    Resumed::From(offset, res) => (res, offset),
+  };
+  let mut file = open_partial(destination, downloaded).await?;
+  let total = res.content_length().map(|length| length + downloaded);
+  let mut stream = res.bytes_stream();
 
-  let mut file = File::create(destination).await?;
+  while let Some(chunk) = stream.next().await {
+    pause.wait_while_paused(cancellation_token).await;
+    if cancellation_token.is_cancelled() {
+      file.flush().await?;
+      return Err(NetworkError::Cancelled);
+    }
+    let chunk = chunk?;
+    pause.throttle(chunk.len() as u64).await;
+    file.write_all(&chunk).await?;
+    downloaded += chunk.len() as u64;
+    on_progress(downloaded, total);
+  }
+  file.flush().await?;
+  Ok(())
+}
+
+/// Downloads `url` like `download_file_with_progress`, but also sends the chunks to `sink` as
+/// they arrive.  Sending waits for `sink` to make room, so a slow consumer holds up the download
+/// instead of it piling up in memory.
+///
+/// A download resumed from what's already in `destination` sends that to `sink` first, so the
+/// consumer always sees the whole file from the start
+pub async fn download_to_channel(
+  url: &String,
+  destination: &PathBuf,
+  sink: tokio::sync::mpsc::Sender<Vec<u8>>,
+  cancellation_token: &CancellationToken,
+  pause: &PauseFlag,
+  mut on_progress: impl FnMut(u64, Option<u64>),
+) -> Result<(), NetworkError> {
+  let client = reqwest::Client::new();
+  let (res, offset) = match request_rest(&client, url, destination).await? {
+    Resumed::Complete(length) => (None, length),
+    Resumed::From(offset, res) => (Some(res), offset),
+  };
+  let total = match &res {
+    Some(res) => res.content_length().map(|length| length + offset),
+    None => Some(offset),
+  };
+  let mut downloaded = 0;
+  if offset > 0 {
+    let mut existing = File::open(destination).await?.take(offset);
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+      let read = existing.read(&mut buffer).await?;
+      if read == 0 {
+        break;
+      }
+      if sink.send(buffer[..read].to_vec()).await.is_err() {
+        return Err(NetworkError::SinkClosed);
+      }
+      downloaded += read as u64;
+      on_progress(downloaded, total);
+    }
+  }
+  let res = match res {
+    Some(res) => res,
+    None => return Ok(()),
+  };
+  let mut file = open_partial(destination, offset).await?;
   let mut stream = res.bytes_stream();
+  while let Some(chunk) = stream.next().await {
+    pause.wait_while_paused(cancellation_token).await;
+    if cancellation_token.is_cancelled() {
+      file.flush().await?;
+      return Err(NetworkError::Cancelled);
+    }
+    let chunk = chunk?;
+    pause.throttle(chunk.len() as u64).await;
+    file.write_all(&chunk).await?;
+    downloaded += chunk.len() as u64;
+    if sink.send(chunk.to_vec()).await.is_err() {
+      return Err(NetworkError::SinkClosed);
+    }
+    on_progress(downloaded, total);
+  }
+  file.flush().await?;
+  Ok(())
+}
+
+// The size of `url` and its validator, if the server reports a size and lets it be requested in
+// parts
+async fn ranged_length(
+  client: &reqwest::Client,
+  url: &String,
+) -> Result<Option<(u64, Option<String>)>, NetworkError> {
+  let res = client.head(url).send().await?.error_for_status()?;
+  let accepts_ranges = res
+    .headers()
+    .get(reqwest::header::ACCEPT_RANGES)
+    .map_or(false, |value| value.as_bytes() == b"bytes");
+  let validator = response_validator(res.headers());
+  Ok(
+    res
+      .content_length()
+      .filter(|_| accepts_ranges)
+      .map(|length| (length, validator)),
+  )
+}
 
+// Fetches bytes `start` up to `end` of `url` into `file`, which is already at `start`.  Only
+// succeeds if exactly those bytes arrived, the file is pre-sized so a short read would otherwise
+// go unnoticed
+async fn fetch_range(
+  client: &reqwest::Client,
+  url: &String,
+  file: &mut File,
+  (start, end): (u64, u64),
+  cancellation_token: &CancellationToken,
+  pause: &PauseFlag,
+  downloaded: &AtomicU64,
+) -> Result<(), NetworkError> {
+  let res = client
+    .get(url)
+    .header(reqwest::header::RANGE, format!("bytes={}-{}", start, end))
+    .send()
+    .await?
+    .error_for_status()?;
+  if res.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+    return Err(NetworkError::RangeIgnored(start, end));
+  }
+  let expected = end - start + 1;
+  let mut stream = res.bytes_stream();
+  let mut received = 0;
   while let Some(chunk) = stream.next().await {
+    pause.wait_while_paused(cancellation_token).await;
+    if cancellation_token.is_cancelled() {
+      return Err(NetworkError::Cancelled);
+    }
     let chunk = chunk?;
+    received += chunk.len() as u64;
+    if received > expected {
+      return Err(NetworkError::RangeIncomplete(start, end, received));
+    }
+    pause.throttle(chunk.len() as u64).await;
     file.write_all(&chunk).await?;
+    downloaded.fetch_add(chunk.len() as u64, Ordering::Relaxed);
+  }
+  file.flush().await?;
+  if received != expected {
+    return Err(NetworkError::RangeIncomplete(start, end, received));
+  }
+  Ok(())
+}
+
+async fn download_range(
+  client: &reqwest::Client,
+  url: &String,
+  destination: &PathBuf,
+  (start, end): (u64, u64),
+  cancellation_token: &CancellationToken,
+  pause: &PauseFlag,
+  downloaded: &AtomicU64,
+) -> Result<(), NetworkError> {
+  let mut file = tokio::fs::OpenOptions::new()
+    .write(true)
+    .open(destination)
+    .await?;
+  file.seek(SeekFrom::Start(start)).await?;
+  let mut attempt = 1;
+  loop {
+    // Whatever made it into the file is kept, a retry only asks for the rest
+    let position = file.stream_position().await?;
+    if position > end {
+      return Ok(());
+    }
+    let result = fetch_range(
+      client,
+      url,
+      &mut file,
+      (position, end),
+      cancellation_token,
+      pause,
+      downloaded,
+    )
+    .await;
+    match result {
+      Ok(()) => return Ok(()),
+      // Retrying won't change the server's mind about ranges, or the user's about cancelling
+      Err(err @ (NetworkError::Cancelled | NetworkError::RangeIgnored(..))) => return Err(err),
+      Err(err) if attempt >= RANGE_ATTEMPTS => return Err(err),
+      Err(err) => {
+        log::warn!(
+          "Bytes {}-{} of {} failed ({}), retrying the rest",
+          start,
+          end,
+          url,
+          err
+        );
+        attempt += 1;
+        tokio::time::sleep(RANGE_RETRY_DELAY).await;
+      }
+    }
+  }
+}
+
+/// Downloads `url` like `download_file_with_progress`, but split into ranges that are fetched
+/// over `connections` connections at once.  Progress is reported for the file as a whole.
+///
+/// Small files, servers that don't accept range requests, and files partially downloaded by an
+/// earlier attempt (which are resumed) go over a single connection instead.  The file is sized
+/// up front for the ranges to be written into, so a failed chunked download can't be resumed and
+/// whatever was written is removed
+pub async fn download_file_chunked(
+  url: &String,
+  destination: &PathBuf,
+  connections: u32,
+  cancellation_token: &CancellationToken,
+  pause: &PauseFlag,
+  mut on_progress: impl FnMut(u64, Option<u64>),
+) -> Result<(), NetworkError> {
+  let client = reqwest::Client::new();
+  let total = match connections {
+    0 | 1 => None,
+    _ if destination.exists() => None,
+    _ => ranged_length(&client, url)
+      .await
+      .unwrap_or_else(|err| {
+        log::warn!("Unable to check whether {} can be chunked: {}", url, err);
+        None
+      })
+      .filter(|(total, _)| *total >= MIN_CHUNKED_SIZE),
+  };
+  let (total, validator) = match total {
+    Some(ranged) => ranged,
+    None => {
+      return download_file_with_progress(url, destination, cancellation_token, pause, on_progress)
+        .await
+    }
+  };
+
+  log::info!(
+    "Downloading {} ({} bytes) over {} connections",
+    url,
+    total,
+    connections
+  );
+  File::create(destination).await?.set_len(total).await?;
+  let chunk_size = (total + connections as u64 - 1) / connections as u64;
+  let ranges: Vec<(u64, u64)> = (0..connections as u64)
+    .map(|i| (i * chunk_size, ((i + 1) * chunk_size).min(total) - 1))
+    .filter(|(start, end)| start <= end)
+    .collect();
+  let downloaded = AtomicU64::new(0);
+  let chunks = try_join_all(ranges.into_iter().map(|range| {
+    download_range(
+      &client,
+      url,
+      destination,
+      range,
+      cancellation_token,
+      pause,
+      &downloaded,
+    )
+  }));
+  tokio::pin!(chunks);
+  let result = loop {
+    tokio::select! {
+      result = &mut chunks => break result,
+      _ = tokio::time::sleep(CHUNKED_PROGRESS_INTERVAL) => {
+        on_progress(downloaded.load(Ordering::Relaxed), Some(total));
+      }
+    }
+  };
+  if let Err(err) = result {
+    remove_partial_download(destination)?;
+    return Err(err);
   }
+  // Complete, but a later attempt may still find it and shouldn't download it again
+  save_validator(destination, validator).await?;
+  on_progress(total, Some(total));
   Ok(())
 }
@@ -1,4 +1,9 @@
-use std::process::Command;
+use std::process::{Child, Command};
+
+use crate::config::ProcessPriority;
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+use crate::util::file::{create_dir, delete_file};
 
 #[cfg(target_os = "windows")]
 const FILE_OPENING_PROGRAM: &str = "explorer";
@@ -11,3 +16,514 @@ pub fn open_dir_in_os(dir: String) -> Result<(), std::io::Error> {
   Command::new(FILE_OPENING_PROGRAM).arg(dir).spawn()?;
   Ok(())
 }
+
+/// The argument passed to the launcher when it is started by the OS on login, and should start minimized
+pub const START_MINIMIZED_ARG: &str = "--minimized";
+
+const AUTOSTART_NAME: &str = "OpenGOAL-Launcher";
+
+pub fn launcher_executable() -> Result<std::path::PathBuf, std::io::Error> {
+  // When running as an AppImage, the executable is inside a temporary mount
+  #[cfg(target_os = "linux")]
+  if let Ok(appimage) = std::env::var("APPIMAGE") {
+    return Ok(std::path::PathBuf::from(appimage));
+  }
+  std::env::current_exe()
+}
+
+#[cfg(target_os = "windows")]
+pub fn set_launch_on_startup(enabled: bool, minimized: bool) -> Result<(), std::io::Error> {
+  use std::os::windows::process::CommandExt;
+  let run_key = "HKCU\\Software\\Microsoft\\Windows\\CurrentVersion\\Run";
+  let mut command = Command::new("reg");
+  if enabled {
+    let mut value = format!("\"{}\"", launcher_executable()?.display());
+    if minimized {
+      value = format!("{} {}", value, START_MINIMIZED_ARG);
+    }
+    command.args([
+      "add",
+      run_key,
+      "/v",
+      AUTOSTART_NAME,
+      "/t",
+      "REG_SZ",
+      "/d",
+      &value,
+      "/f",
+    ]);
+  } else {
+    command.args(["delete", run_key, "/v", AUTOSTART_NAME, "/f"]);
+  }
+  command.creation_flags(0x08000000);
+  let output = command.output()?;
+  // Deleting a value that doesn't exist fails, which is fine
+  if !output.status.success() && enabled {
+    return Err(std::io::Error::new(
+      std::io::ErrorKind::Other,
+      String::from_utf8_lossy(&output.stderr).into_owned(),
+    ));
+  }
+  Ok(())
+}
+
+#[cfg(target_os = "macos")]
+pub fn set_launch_on_startup(enabled: bool, minimized: bool) -> Result<(), std::io::Error> {
+  let home_dir = directories::UserDirs::new()
+    .map(|dirs| dirs.home_dir().to_path_buf())
+    .ok_or(std::io::Error::new(
+      std::io::ErrorKind::NotFound,
+      "unable to determine home directory",
+    ))?;
+  let agents_dir = home_dir.join("Library").join("LaunchAgents");
+  let plist_path = agents_dir.join(format!("{}.plist", AUTOSTART_NAME));
+  if !enabled {
+    return delete_file(&plist_path);
+  }
+  let mut program_args = format!(
+    "    <string>{}</string>\n",
+    launcher_executable()?.display()
+  );
+  if minimized {
+    program_args.push_str(&format!("    <string>{}</string>\n", START_MINIMIZED_ARG));
+  }
+  let plist = format!(
+    r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+  <key>Label</key>
+  <string>{}</string>
+  <key>ProgramArguments</key>
+  <array>
+{}  </array>
+  <key>RunAtLoad</key>
+  <true/>
+</dict>
+</plist>
+"#,
+    AUTOSTART_NAME, program_args
+  );
+  create_dir(&agents_dir)?;
+  std::fs::write(plist_path, plist)
+}
+
+#[cfg(target_os = "linux")]
+pub fn set_launch_on_startup(enabled: bool, minimized: bool) -> Result<(), std::io::Error> {
+  let autostart_dir = match std::env::var("XDG_CONFIG_HOME") {
+    Ok(dir) if !dir.is_empty() => std::path::PathBuf::from(dir),
+    _ => directories::UserDirs::new()
+      .map(|dirs| dirs.home_dir().join(".config"))
+      .ok_or(std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        "unable to determine home directory",
+      ))?,
+  }
+  .join("autostart");
+  let desktop_path = autostart_dir.join("opengoal-launcher.desktop");
+  if !enabled {
+    return delete_file(&desktop_path);
+  }
+  let mut exec = format!("\"{}\"", launcher_executable()?.display());
+  if minimized {
+    exec = format!("{} {}", exec, START_MINIMIZED_ARG);
+  }
+  let desktop_entry = format!(
+    "[Desktop Entry]\nType=Application\nName={}\nExec={}\nX-GNOME-Autostart-enabled=true\n",
+    AUTOSTART_NAME, exec
+  );
+  create_dir(&autostart_dir)?;
+  std::fs::write(desktop_path, desktop_entry)
+}
+
+const SHORTCUT_NAME: &str = "OpenGOAL Launcher";
+
+fn desktop_dir() -> Result<std::path::PathBuf, std::io::Error> {
+  directories::UserDirs::new()
+    .and_then(|dirs| dirs.desktop_dir().map(|dir| dir.to_path_buf()))
+    .ok_or(std::io::Error::new(
+      std::io::ErrorKind::NotFound,
+      "unable to determine desktop directory",
+    ))
+}
+
+/// Puts a shortcut to the launcher on the desktop, unless there already is one
+#[cfg(target_os = "windows")]
+pub fn create_desktop_shortcut() -> Result<std::path::PathBuf, std::io::Error> {
+  use std::os::windows::process::CommandExt;
+  let shortcut_path = desktop_dir()?.join(format!("{}.lnk", SHORTCUT_NAME));
+  if shortcut_path.exists() {
+    return Ok(shortcut_path);
+  }
+  // Shortcuts are only writable through COM, which powershell conveniently exposes
+  let quote = |path: &std::path::Path| path.display().to_string().replace('\'', "''");
+  let script = format!(
+    "$s = (New-Object -ComObject WScript.Shell).CreateShortcut('{}'); \
+     $s.TargetPath = '{}'; $s.Save()",
+    quote(&shortcut_path),
+    quote(&launcher_executable()?)
+  );
+  let output = Command::new("powershell")
+    .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+    .creation_flags(0x08000000)
+    .output()?;
+  if !output.status.success() {
+    return Err(std::io::Error::new(
+      std::io::ErrorKind::Other,
+      String::from_utf8_lossy(&output.stderr).trim().to_string(),
+    ));
+  }
+  Ok(shortcut_path)
+}
+
+#[cfg(target_os = "macos")]
+pub fn create_desktop_shortcut() -> Result<std::path::PathBuf, std::io::Error> {
+  let shortcut_path = desktop_dir()?.join(SHORTCUT_NAME);
+  if shortcut_path.exists() {
+    return Ok(shortcut_path);
+  }
+  // The executable is in `<bundle>.app/Contents/MacOS`, link the bundle so it opens like an app
+  let executable = launcher_executable()?;
+  let bundle = executable
+    .ancestors()
+    .find(|dir| dir.extension().map_or(false, |ext| ext == "app"))
+    .unwrap_or(&executable);
+  std::os::unix::fs::symlink(bundle, &shortcut_path)?;
+  Ok(shortcut_path)
+}
+
+#[cfg(target_os = "linux")]
+pub fn create_desktop_shortcut() -> Result<std::path::PathBuf, std::io::Error> {
+  use std::os::unix::fs::PermissionsExt;
+  let shortcut_path = desktop_dir()?.join("opengoal-launcher.desktop");
+  if shortcut_path.exists() {
+    return Ok(shortcut_path);
+  }
+  let desktop_entry = format!(
+    "[Desktop Entry]\nType=Application\nName={}\nExec=\"{}\"\nTerminal=false\n",
+    SHORTCUT_NAME,
+    launcher_executable()?.display()
+  );
+  std::fs::write(&shortcut_path, desktop_entry)?;
+  // Desktop environments only run entries on the desktop that are executable
+  std::fs::set_permissions(&shortcut_path, std::fs::Permissions::from_mode(0o755))?;
+  Ok(shortcut_path)
+}
+
+/// Windows priority classes are chosen at process creation time, to be combined with the other creation flags
+#[cfg(target_os = "windows")]
+pub fn priority_creation_flags(priority: &ProcessPriority) -> u32 {
+  match priority {
+    ProcessPriority::Normal => 0x00000020, // NORMAL_PRIORITY_CLASS
+    ProcessPriority::AboveNormal => 0x00008000, // ABOVE_NORMAL_PRIORITY_CLASS
+    ProcessPriority::High => 0x00000080,   // HIGH_PRIORITY_CLASS
+  }
+}
+
+/// On unix the niceness is lowered after the fact, raising priority usually requires
+/// privileges so this may fail in which case the game just keeps running at normal priority
+#[cfg(unix)]
+pub fn set_process_priority(
+  child: &Child,
+  priority: &ProcessPriority,
+) -> Result<(), std::io::Error> {
+  let niceness = match priority {
+    ProcessPriority::Normal => return Ok(()),
+    ProcessPriority::AboveNormal => "-5",
+    ProcessPriority::High => "-10",
+  };
+  let output = Command::new("renice")
+    .args(["-n", niceness, "-p", &child.id().to_string()])
+    .output()?;
+  if !output.status.success() {
+    return Err(std::io::Error::new(
+      std::io::ErrorKind::PermissionDenied,
+      String::from_utf8_lossy(&output.stderr).into_owned(),
+    ));
+  }
+  Ok(())
+}
+
+#[cfg(target_os = "windows")]
+pub fn set_process_affinity(child: &Child, mask: u64) -> Result<(), std::io::Error> {
+  use std::os::windows::io::AsRawHandle;
+
+  #[link(name = "kernel32")]
+  extern "system" {
+    fn SetProcessAffinityMask(process: *mut std::ffi::c_void, mask: usize) -> i32;
+  }
+
+  let result = unsafe { SetProcessAffinityMask(child.as_raw_handle(), mask as usize) };
+  if result == 0 {
+    return Err(std::io::Error::last_os_error());
+  }
+  Ok(())
+}
+
+#[cfg(target_os = "linux")]
+pub fn set_process_affinity(child: &Child, mask: u64) -> Result<(), std::io::Error> {
+  let output = Command::new("taskset")
+    .args(["-p", &format!("{:x}", mask), &child.id().to_string()])
+    .output()?;
+  if !output.status.success() {
+    return Err(std::io::Error::new(
+      std::io::ErrorKind::Other,
+      String::from_utf8_lossy(&output.stderr).into_owned(),
+    ));
+  }
+  Ok(())
+}
+
+#[cfg(target_os = "macos")]
+pub fn set_process_affinity(_child: &Child, _mask: u64) -> Result<(), std::io::Error> {
+  Err(std::io::Error::new(
+    std::io::ErrorKind::Unsupported,
+    "macOS does not support setting CPU affinity",
+  ))
+}
+
+/// Lists the shared libraries the dynamic loader is unable to resolve for the given binary
+#[cfg(target_os = "linux")]
+pub fn find_missing_shared_libraries(
+  binary: &std::path::Path,
+) -> Result<Vec<String>, std::io::Error> {
+  let output = Command::new("ldd").arg(binary).output()?;
+  // ie. `libGL.so.1 => not found`
+  Ok(
+    String::from_utf8_lossy(&output.stdout)
+      .lines()
+      .filter(|line| line.contains("not found"))
+      .map(|line| line.split("=>").next().unwrap_or(line).trim().to_string())
+      .collect(),
+  )
+}
+
+#[cfg(target_os = "linux")]
+fn linux_distro_family() -> Option<&'static str> {
+  let os_release = std::fs::read_to_string("/etc/os-release").ok()?;
+  let ids: Vec<String> = os_release
+    .lines()
+    .filter(|line| line.starts_with("ID=") || line.starts_with("ID_LIKE="))
+    .flat_map(|line| {
+      line
+        .split_once('=')
+        .map(|(_, value)| value.trim_matches('"').to_string())
+    })
+    .flat_map(|value| {
+      value
+        .split_whitespace()
+        .map(|id| id.to_string())
+        .collect::<Vec<String>>()
+    })
+    .collect();
+  for id in ids {
+    match id.as_str() {
+      "debian" | "ubuntu" => return Some("debian"),
+      "fedora" | "rhel" => return Some("fedora"),
+      "arch" => return Some("arch"),
+      "opensuse" | "suse" => return Some("suse"),
+      _ => continue,
+    }
+  }
+  None
+}
+
+/// Suggests the distribution package that provides a commonly missing library
+#[cfg(target_os = "linux")]
+pub fn shared_library_package_hint(library: &str) -> Option<String> {
+  // (library prefix, debian, fedora, arch, suse)
+  let known_packages = [
+    (
+      "libGL.so",
+      "libgl1",
+      "mesa-libGL",
+      "libglvnd",
+      "Mesa-libGL1",
+    ),
+    ("libX11.so", "libx11-6", "libX11", "libx11", "libX11-6"),
+    (
+      "libXrandr.so",
+      "libxrandr2",
+      "libXrandr",
+      "libxrandr",
+      "libXrandr2",
+    ),
+    (
+      "libXinerama.so",
+      "libxinerama1",
+      "libXinerama",
+      "libxinerama",
+      "libXinerama1",
+    ),
+    (
+      "libXcursor.so",
+      "libxcursor1",
+      "libXcursor",
+      "libxcursor",
+      "libXcursor1",
+    ),
+    ("libXi.so", "libxi6", "libXi", "libxi", "libXi6"),
+    (
+      "libasound.so",
+      "libasound2",
+      "alsa-lib",
+      "alsa-lib",
+      "libasound2",
+    ),
+    (
+      "libpulse.so",
+      "libpulse0",
+      "pulseaudio-libs",
+      "libpulse",
+      "libpulse0",
+    ),
+    (
+      "libstdc++.so",
+      "libstdc++6",
+      "libstdc++",
+      "gcc-libs",
+      "libstdc++6",
+    ),
+    (
+      "libgcc_s.so",
+      "libgcc-s1",
+      "libgcc",
+      "gcc-libs",
+      "libgcc_s1",
+    ),
+  ];
+  let (_, debian, fedora, arch, suse) = known_packages
+    .iter()
+    .find(|(prefix, ..)| library.starts_with(prefix))?;
+  Some(match linux_distro_family() {
+    Some("debian") => format!("sudo apt install {}", debian),
+    Some("fedora") => format!("sudo dnf install {}", fedora),
+    Some("arch") => format!("sudo pacman -S {}", arch),
+    Some("suse") => format!("sudo zypper install {}", suse),
+    _ => format!(
+      "install the package providing {} (ie. '{}' on Debian/Ubuntu)",
+      library, debian
+    ),
+  })
+}
+
+/// Whether the active internet connection is metered (mobile data, tethering, or marked as such
+/// by the user).  `None` if it can't be determined
+#[cfg(target_os = "windows")]
+pub fn is_connection_metered() -> Option<bool> {
+  use std::os::windows::process::CommandExt;
+  // There's no win32 equivalent of the WinRT connection cost API, go through powershell
+  let script = "[void][Windows.Networking.Connectivity.NetworkInformation,Windows,ContentType=WindowsRuntime];\
+    $cost = [Windows.Networking.Connectivity.NetworkInformation]::GetInternetConnectionProfile().GetConnectionCost();\
+    Write-Output \"$($cost.NetworkCostType) $($cost.Roaming)\"";
+  let output = Command::new("powershell")
+    .args(["-NoProfile", "-NonInteractive", "-Command", script])
+    .creation_flags(0x08000000)
+    .output()
+    .ok()?;
+  let stdout = String::from_utf8_lossy(&output.stdout);
+  let mut parts = stdout.split_whitespace();
+  let cost_type = parts.next()?;
+  let roaming = parts.next() == Some("True");
+  match cost_type {
+    "Unrestricted" => Some(roaming),
+    "Fixed" | "Variable" => Some(true),
+    _ => None,
+  }
+}
+
+#[cfg(target_os = "linux")]
+pub fn is_connection_metered() -> Option<bool> {
+  // NetworkManager's `NMMetered` - 1 yes, 2 no, 3 guessed yes, 4 guessed no
+  let output = Command::new("busctl")
+    .args([
+      "get-property",
+      "org.freedesktop.NetworkManager",
+      "/org/freedesktop/NetworkManager",
+      "org.freedesktop.NetworkManager",
+      "Metered",
+    ])
+    .output()
+    .ok()?;
+  // ie. `u 4`
+  match String::from_utf8_lossy(&output.stdout)
+    .split_whitespace()
+    .nth(1)
+  {
+    Some("1") | Some("3") => Some(true),
+    Some("2") | Some("4") => Some(false),
+    _ => None,
+  }
+}
+
+#[cfg(target_os = "macos")]
+pub fn is_connection_metered() -> Option<bool> {
+  // Only exposed through the Network framework (`NWPath.isExpensive`)
+  None
+}
+
+/// Asks the process to exit on its own (SIGTERM / WM_CLOSE), rather than killing it outright
+#[cfg(unix)]
+pub fn request_process_exit(child: &Child) -> Result<(), std::io::Error> {
+  let output = Command::new("kill")
+    .args(["-TERM", &child.id().to_string()])
+    .output()?;
+  if !output.status.success() {
+    return Err(std::io::Error::new(
+      std::io::ErrorKind::Other,
+      String::from_utf8_lossy(&output.stderr).into_owned(),
+    ));
+  }
+  Ok(())
+}
+
+#[cfg(target_os = "windows")]
+pub fn request_process_exit(child: &Child) -> Result<(), std::io::Error> {
+  use std::os::windows::process::CommandExt;
+  // Without `/F` taskkill closes the window, giving the game a chance to shut down cleanly
+  let output = Command::new("taskkill")
+    .args(["/PID", &child.id().to_string()])
+    .creation_flags(0x08000000)
+    .output()?;
+  if !output.status.success() {
+    return Err(std::io::Error::new(
+      std::io::ErrorKind::Other,
+      String::from_utf8_lossy(&output.stderr).into_owned(),
+    ));
+  }
+  Ok(())
+}
+
+/// System components the game needs to play videos that are missing, without them cutscenes
+/// play as a black screen
+#[cfg(target_os = "windows")]
+pub fn missing_video_components() -> Vec<String> {
+  // "N" editions of Windows ship without Media Foundation unless the Media Feature Pack is added
+  let system_dir = std::env::var_os("SystemRoot")
+    .map(|root| std::path::PathBuf::from(root).join("System32"))
+    .unwrap_or_else(|| std::path::PathBuf::from("C:\\Windows\\System32"));
+  ["mfplat.dll", "mfreadwrite.dll"]
+    .iter()
+    .filter(|dll| !system_dir.join(dll).exists())
+    .map(|dll| format!("{} (Media Feature Pack)", dll))
+    .collect()
+}
+
+#[cfg(target_os = "linux")]
+pub fn missing_video_components() -> Vec<String> {
+  let libraries = match Command::new("ldconfig").arg("-p").output() {
+    Ok(output) => String::from_utf8_lossy(&output.stdout).into_owned(),
+    // Can't tell without the loader's cache, don't report anything rather than everything
+    Err(_) => return vec![],
+  };
+  ["libavcodec", "libavformat"]
+    .iter()
+    .filter(|library| !libraries.contains(&format!("{}.so", library)))
+    .map(|library| library.to_string())
+    .collect()
+}
+
+#[cfg(target_os = "macos")]
+pub fn missing_video_components() -> Vec<String> {
+  vec![]
+}
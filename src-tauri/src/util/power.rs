@@ -0,0 +1,113 @@
+// Keeps the machine from going to sleep during long running operations (downloads, installs)
+// so users don't come back to a half-extracted install
+//
+// Each platform has its own mechanism:
+// - linux - `systemd-inhibit` holding a lock for as long as a child process lives
+// - macos - `caffeinate` for as long as a child process lives
+// - windows - `SetThreadExecutionState` on a dedicated thread, as the state is per-thread
+
+#[cfg(target_os = "windows")]
+mod ffi {
+  pub const ES_CONTINUOUS: u32 = 0x80000000;
+  pub const ES_SYSTEM_REQUIRED: u32 = 0x00000001;
+
+  #[link(name = "kernel32")]
+  extern "system" {
+    pub fn SetThreadExecutionState(es_flags: u32) -> u32;
+  }
+}
+
+/// Prevents the system from sleeping until dropped
+pub struct SleepInhibitor {
+  #[cfg(unix)]
+  child: Option<std::process::Child>,
+  #[cfg(target_os = "windows")]
+  release: Option<std::sync::mpsc::Sender<()>>,
+}
+
+impl SleepInhibitor {
+  #[cfg(target_os = "linux")]
+  pub fn acquire(reason: &str) -> Self {
+    let child = std::process::Command::new("systemd-inhibit")
+      .args([
+        "--what=idle:sleep",
+        "--who=OpenGOAL-Launcher",
+        &format!("--why={}", reason),
+        "--mode=block",
+        "sleep",
+        "infinity",
+      ])
+      .stdout(std::process::Stdio::null())
+      .stderr(std::process::Stdio::null())
+      .spawn();
+    Self::from_child(reason, child)
+  }
+
+  #[cfg(target_os = "macos")]
+  pub fn acquire(reason: &str) -> Self {
+    // `-w` ties the assertion to the launcher, in case we never get the chance to release it
+    let child = std::process::Command::new("caffeinate")
+      .args(["-i", "-w", &std::process::id().to_string()])
+      .spawn();
+    Self::from_child(reason, child)
+  }
+
+  #[cfg(unix)]
+  fn from_child(reason: &str, child: Result<std::process::Child, std::io::Error>) -> Self {
+    match child {
+      Ok(child) => {
+        log::info!("Preventing sleep - {}", reason);
+        Self { child: Some(child) }
+      }
+      Err(err) => {
+        log::warn!("Unable to prevent the system from sleeping: {}", err);
+        Self { child: None }
+      }
+    }
+  }
+
+  #[cfg(target_os = "windows")]
+  pub fn acquire(reason: &str) -> Self {
+    let (release, released) = std::sync::mpsc::channel::<()>();
+    let spawned = std::thread::Builder::new()
+      .name("sleep-inhibitor".to_string())
+      .spawn(move || unsafe {
+        ffi::SetThreadExecutionState(ffi::ES_CONTINUOUS | ffi::ES_SYSTEM_REQUIRED);
+        // Returns once the sender has been dropped
+        let _ = released.recv();
+        ffi::SetThreadExecutionState(ffi::ES_CONTINUOUS);
+      });
+    match spawned {
+      Ok(_) => {
+        log::info!("Preventing sleep - {}", reason);
+        Self {
+          release: Some(release),
+        }
+      }
+      Err(err) => {
+        log::warn!("Unable to prevent the system from sleeping: {}", err);
+        Self { release: None }
+      }
+    }
+  }
+}
+
+impl Drop for SleepInhibitor {
+  #[cfg(unix)]
+  fn drop(&mut self) {
+    if let Some(child) = &mut self.child {
+      log::info!("Allowing sleep again");
+      if let Err(err) = child.kill() {
+        log::warn!("Unable to release sleep inhibitor: {}", err);
+      }
+      let _ = child.wait();
+    }
+  }
+
+  #[cfg(target_os = "windows")]
+  fn drop(&mut self) {
+    if self.release.take().is_some() {
+      log::info!("Allowing sleep again");
+    }
+  }
+}
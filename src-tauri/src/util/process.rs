@@ -0,0 +1,153 @@
+// Spawning the tooling (extractor, gk, goalc) and the programs it relies on
+//
+// Tools don't inherit the launcher's environment, whatever is on the user's PATH (MSYS, a Python
+// install, an old copy of the tooling) has nothing to do with them but can still be picked up
+// ahead of what they expect.  Instead they get a curated one: a minimal PATH with the tool's own
+// directory first, the variables the OS and the desktop session need, and the user's locale.
+// Anything else a tool needs has to be asked for in its `ToolEnvironment` in the config.
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::ToolEnvironment;
+
+/// The tools that can be given their own environment, and the launcher itself when it restarts
+pub const TOOLS: &[&str] = &["extractor", "gk", "goalc", "launcher"];
+
+#[cfg(target_os = "windows")]
+const PASSED_THROUGH: &[&str] = &[
+  "SYSTEMROOT",
+  "SYSTEMDRIVE",
+  "WINDIR",
+  "COMSPEC",
+  "PATHEXT",
+  "OS",
+  "TEMP",
+  "TMP",
+  "USERNAME",
+  "USERPROFILE",
+  "HOMEDRIVE",
+  "HOMEPATH",
+  "APPDATA",
+  "LOCALAPPDATA",
+  "PROGRAMDATA",
+  "PROGRAMFILES",
+  "PROGRAMFILES(X86)",
+  "COMPUTERNAME",
+  "NUMBER_OF_PROCESSORS",
+  "PROCESSOR_ARCHITECTURE",
+];
+
+#[cfg(not(target_os = "windows"))]
+const PASSED_THROUGH: &[&str] = &[
+  "HOME",
+  "USER",
+  "LOGNAME",
+  "TMPDIR",
+  "DISPLAY",
+  "WAYLAND_DISPLAY",
+  "XAUTHORITY",
+  "XDG_RUNTIME_DIR",
+  "XDG_SESSION_TYPE",
+  "XDG_CONFIG_HOME",
+  "XDG_DATA_HOME",
+  "XDG_DATA_DIRS",
+  "DBUS_SESSION_BUS_ADDRESS",
+];
+
+// The locale, and what the game needs to pick its video and audio drivers the user's way
+const PASSED_THROUGH_PREFIXES: &[&str] = &[
+  "LANG",
+  "LC_",
+  "SDL_",
+  "MESA_",
+  "__GL_",
+  "VK_",
+  "LIBGL_",
+  "PULSE_",
+  "PIPEWIRE_",
+];
+
+fn minimal_path() -> Vec<PathBuf> {
+  if cfg!(target_os = "windows") {
+    let system_root =
+      PathBuf::from(std::env::var_os("SYSTEMROOT").unwrap_or_else(|| "C:\\Windows".into()));
+    vec![
+      system_root.join("System32"),
+      system_root.clone(),
+      system_root.join("System32").join("Wbem"),
+    ]
+  } else {
+    ["/usr/local/bin", "/usr/bin", "/bin"]
+      .iter()
+      .map(PathBuf::from)
+      .collect()
+  }
+}
+
+fn is_passed_through(name: &str) -> bool {
+  let name = if cfg!(target_os = "windows") {
+    name.to_uppercase()
+  } else {
+    name.to_owned()
+  };
+  PASSED_THROUGH.contains(&name.as_str())
+    || PASSED_THROUGH_PREFIXES
+      .iter()
+      .any(|prefix| name.starts_with(prefix))
+}
+
+/// Looks `program` up on the launcher's own PATH, for programs the user installed (ie. 7-Zip)
+/// that the minimal PATH wouldn't find
+pub fn find_on_path(program: &str) -> Option<PathBuf> {
+  std::env::var_os("PATH").and_then(|paths| {
+    std::env::split_paths(&paths)
+      .map(|dir| dir.join(program))
+      .find(|path| path.is_file())
+  })
+}
+
+/// Builds the command that runs `program` in `cwd`, with the curated environment and the tool's
+/// overrides from the config on top
+pub fn tool_command(
+  program: impl AsRef<OsStr>,
+  cwd: &Path,
+  overrides: Option<&ToolEnvironment>,
+) -> Command {
+  let mut command = Command::new(program.as_ref());
+  command.current_dir(cwd).env_clear();
+  for (name, value) in std::env::vars_os() {
+    let passed_through = name.to_str().map_or(false, |name| {
+      is_passed_through(name)
+        || overrides.map_or(false, |overrides| {
+          overrides.inherit.iter().any(|inherited| inherited == name)
+        })
+    });
+    if passed_through {
+      command.env(name, value);
+    }
+  }
+  // The tooling expects some locale to be set, even when the user has none
+  #[cfg(not(target_os = "windows"))]
+  if std::env::var_os("LANG").is_none() && std::env::var_os("LC_ALL").is_none() {
+    command.env("LANG", "C.UTF-8");
+  }
+
+  let mut path = vec![];
+  if let Some(overrides) = overrides {
+    path.extend(overrides.path.iter().map(PathBuf::from));
+  }
+  if let Some(dir) = Path::new(program.as_ref()).parent() {
+    if !dir.as_os_str().is_empty() {
+      path.push(dir.to_path_buf());
+    }
+  }
+  path.extend(minimal_path());
+  if let Ok(path) = std::env::join_paths(path) {
+    command.env("PATH", path);
+  }
+  if let Some(overrides) = overrides {
+    command.envs(&overrides.vars);
+  }
+  command
+}
@@ -0,0 +1,113 @@
+// Runs untrusted programs with no network access and only the filesystem access they're granted
+//
+// This relies on what the OS provides: bubblewrap on linux and `sandbox-exec` on macOS.  Windows
+// has nothing comparable that can be driven from the command line, so sandboxing is reported as
+// unavailable there rather than running anything unconstrained.
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::process::{find_on_path, tool_command};
+
+/// What a sandboxed program may touch, everything else is off limits
+#[derive(Debug, Clone)]
+pub struct SandboxPolicy {
+  pub read_only: Vec<PathBuf>,
+  pub writable: Vec<PathBuf>,
+}
+
+impl SandboxPolicy {
+  /// A policy granting `writable`, along with reading what a shell script needs to run
+  pub fn for_scripts(writable: Vec<PathBuf>) -> Self {
+    let read_only = if cfg!(target_os = "macos") {
+      vec![
+        "/bin",
+        "/usr",
+        "/System",
+        "/Library",
+        "/private/etc",
+        "/dev",
+      ]
+    } else {
+      vec!["/bin", "/usr", "/lib", "/lib64", "/etc"]
+    };
+    Self {
+      read_only: read_only.into_iter().map(PathBuf::from).collect(),
+      writable,
+    }
+  }
+}
+
+/// The name of the sandbox that would be used, `None` if there is none on this system
+pub fn sandbox_name() -> Option<&'static str> {
+  if cfg!(target_os = "linux") && find_on_path("bwrap").is_some() {
+    Some("bubblewrap")
+  } else if cfg!(target_os = "macos") && PathBuf::from("/usr/bin/sandbox-exec").exists() {
+    Some("sandbox-exec")
+  } else {
+    None
+  }
+}
+
+// sandbox-exec profiles are s-expressions, paths are quoted strings in them
+fn quote_profile_path(path: &Path) -> String {
+  format!(
+    "\"{}\"",
+    path
+      .to_string_lossy()
+      .replace('\\', "\\\\")
+      .replace('"', "\\\"")
+  )
+}
+
+/// Builds the command that runs `program` with `args` inside the sandbox, starting in `cwd` with
+/// the curated environment tools get
+pub fn sandboxed_command(
+  policy: &SandboxPolicy,
+  program: &str,
+  args: &[String],
+  cwd: &Path,
+) -> Result<Command, std::io::Error> {
+  match sandbox_name() {
+    Some("bubblewrap") => {
+      let bwrap = find_on_path("bwrap").unwrap_or_else(|| PathBuf::from("bwrap"));
+      let mut command = tool_command(&bwrap, cwd, None);
+      command.args(["--unshare-all", "--die-with-parent", "--new-session"]);
+      command.args(["--dev", "/dev", "--proc", "/proc", "--tmpfs", "/tmp"]);
+      for path in &policy.read_only {
+        command.arg("--ro-bind-try").arg(path).arg(path);
+      }
+      for path in &policy.writable {
+        command.arg("--bind").arg(path).arg(path);
+      }
+      command.arg("--chdir").arg(cwd);
+      command.arg("--").arg(program).args(args);
+      Ok(command)
+    }
+    Some("sandbox-exec") => {
+      let mut profile =
+        String::from("(version 1)(deny default)(allow process-fork)(allow process-exec)");
+      profile.push_str("(allow sysctl-read)(allow file-read-metadata)");
+      for path in &policy.read_only {
+        profile.push_str(&format!(
+          "(allow file-read* (subpath {}))",
+          quote_profile_path(path)
+        ));
+      }
+      for path in &policy.writable {
+        profile.push_str(&format!(
+          "(allow file-read* file-write* (subpath {}))",
+          quote_profile_path(path)
+        ));
+      }
+      // Denied by default already, but spelled out as it's the point of the sandbox
+      profile.push_str("(deny network*)");
+      let mut command = tool_command("/usr/bin/sandbox-exec", cwd, None);
+      command.arg("-p").arg(profile).arg(program).args(args);
+      Ok(command)
+    }
+    _ => Err(std::io::Error::new(
+      std::io::ErrorKind::Unsupported,
+      "no sandbox is available on this system",
+    )),
+  }
+}
@@ -0,0 +1,62 @@
+// Checking that the tooling binaries extracted from a downloaded release are the project's own
+//
+// A release that publishes signatures has a minisign signature next to each binary, made with the
+// key launcher releases are signed with: `gk.minisig`, or `gk.sig` as `tauri signer sign` names
+// it.  On Windows a binary without one can instead carry an Authenticode signature.  Whether an
+// unsigned binary is refused is up to the caller, but a signature that's there has to be valid.
+use std::path::{Path, PathBuf};
+
+const SIGNATURE_EXTENSIONS: &[&str] = &["minisig", "sig"];
+
+fn signature_path(path: &Path) -> Option<PathBuf> {
+  SIGNATURE_EXTENSIONS
+    .iter()
+    .map(|ext| {
+      let mut signature_path = path.as_os_str().to_owned();
+      signature_path.push(format!(".{}", ext));
+      PathBuf::from(signature_path)
+    })
+    .find(|signature_path| signature_path.is_file())
+}
+
+#[cfg(target_os = "windows")]
+fn verify_authenticode(path: &Path) -> Result<bool, String> {
+  use std::os::windows::process::CommandExt;
+  let script = format!(
+    "(Get-AuthenticodeSignature -LiteralPath '{}').Status",
+    path.display().to_string().replace('\'', "''")
+  );
+  let output = std::process::Command::new("powershell")
+    .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+    .creation_flags(0x08000000)
+    .output()
+    .map_err(|err| format!("unable to check its signature: {}", err))?;
+  let status = String::from_utf8_lossy(&output.stdout).trim().to_string();
+  match status.as_str() {
+    "Valid" => Ok(true),
+    "NotSigned" => Ok(false),
+    "" => Err(format!(
+      "unable to check its signature: {}",
+      String::from_utf8_lossy(&output.stderr).trim()
+    )),
+    _ => Err(format!("its signature is not valid ({})", status)),
+  }
+}
+
+/// Whether the binary at `path` is signed, `Err` with why it can't be trusted if it has a
+/// signature that isn't valid
+pub fn verify_binary(path: &Path) -> Result<bool, String> {
+  let signature_path = match signature_path(path) {
+    Some(signature_path) => signature_path,
+    #[cfg(target_os = "windows")]
+    None => return verify_authenticode(path),
+    #[cfg(not(target_os = "windows"))]
+    None => return Ok(false),
+  };
+  let signature = std::fs::read_to_string(&signature_path)
+    .map_err(|err| format!("unable to read its signature: {}", err))?;
+  let content =
+    std::fs::read(path).map_err(|err| format!("unable to check its signature: {}", err))?;
+  crate::remote_config::verify_signature(&content, &signature).map_err(|err| err.to_string())?;
+  Ok(true)
+}
@@ -0,0 +1,42 @@
+// A folder of its own for a test to work in, for tests that need real files
+use std::path::{Path, PathBuf};
+
+/// Removed again (with everything in it) once it's dropped
+pub struct TestDir(PathBuf);
+
+impl TestDir {
+  /// `name` has to be unique across the tests, they run in parallel
+  pub fn new(name: &str) -> Self {
+    let dir =
+      std::env::temp_dir().join(format!("opengoal-launcher-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    Self(dir)
+  }
+
+  pub fn path(&self) -> &Path {
+    &self.0
+  }
+
+  /// Writes `content` to `name`, creating the folders it's in
+  pub fn file(&self, name: &str, content: &[u8]) -> PathBuf {
+    let path = self.0.join(name);
+    if let Some(parent) = path.parent() {
+      std::fs::create_dir_all(parent).unwrap();
+    }
+    std::fs::write(&path, content).unwrap();
+    path
+  }
+
+  pub fn dir(&self, name: &str) -> PathBuf {
+    let path = self.0.join(name);
+    std::fs::create_dir_all(&path).unwrap();
+    path
+  }
+}
+
+impl Drop for TestDir {
+  fn drop(&mut self) {
+    let _ = std::fs::remove_dir_all(&self.0);
+  }
+}
@@ -1,5 +1,3 @@
-use std::io::Cursor;
-use std::path::PathBuf;
 use std::{
   fs::File,
   io::{Read, Write},
@@ -89,13 +87,3 @@ pub fn append_file_to_zip(
 
   Ok(())
 }
-
-pub fn extract_and_delete_zip_file(
-  zip_path: &PathBuf,
-  extract_dir: &PathBuf,
-) -> Result<(), zip_extract::ZipExtractError> {
-  let archive: Vec<u8> = std::fs::read(zip_path)?;
-  zip_extract::extract(Cursor::new(archive), extract_dir, true)?;
-  std::fs::remove_file(zip_path)?;
-  Ok(())
-}
@@ -0,0 +1,46 @@
+// Non-fatal problems with an operation that otherwise succeeded
+//
+// These are reported alongside the result (on the task, or in the command's return value) so the
+// UI can show them rather than leaving them in the logs.  The codes are stable, the frontend can
+// base its notices and translations on them, the message is only meant for display.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum WarningCode {
+  /// A download couldn't be checked against a published digest
+  UnverifiedDownload,
+  /// A step failed at first, and only succeeded after being retried
+  StepRetried,
+  /// Extracted binaries weren't executable, and had to be made so
+  PermissionsFixed,
+  /// One of the configured post-install steps failed
+  PostInstallStepFailed,
+  /// The game was installed with the system requirements check bypassed
+  RequirementsBypassed,
+  /// A requirement probe crashed or hung, so whether the requirement is met is unknown
+  RequirementProbeFailed,
+  /// The extracted ISO data was meant to be removed after installing, but couldn't be
+  IsoDataNotPurged,
+  /// The tooling's binaries couldn't be verified as the project's, and were kept at the user's say
+  UnsignedBinaries,
+  /// One of the install tools logged a warning, the message is the line it logged
+  ToolWarning,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Warning {
+  pub code: WarningCode,
+  pub message: String,
+}
+
+impl Warning {
+  pub fn new(code: WarningCode, message: impl Into<String>) -> Self {
+    Self {
+      code,
+      message: message.into(),
+    }
+  }
+}
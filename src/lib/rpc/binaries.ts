@@ -1,6 +1,6 @@
 import { toastStore } from "$lib/stores/ToastStore";
 import { invoke } from "@tauri-apps/api/tauri";
-import { exceptionLog } from "./logging";
+import { describeError, exceptionLog } from "./logging";
 
 interface InstallationOutput {
   msg: string | null;
@@ -99,6 +99,20 @@ export async function runCompiler(
   }
 }
 
+export async function resumeInstall(
+  gameName: string
+): Promise<InstallationOutput> {
+  try {
+    return await invoke("resume_install", { gameName: gameName });
+  } catch (e) {
+    exceptionLog("Unable to resume the installation", e);
+    return {
+      msg: describeError(e),
+      success: false,
+    };
+  }
+}
+
 export async function launchGame(
   gameName: string,
   inDebugMode: boolean
@@ -114,6 +128,21 @@ export async function launchGame(
   }
 }
 
+export async function launchGameWithLimit(
+  gameName: string,
+  minutes: number
+): Promise<void> {
+  try {
+    return await invoke("launch_game_with_limit", {
+      gameName: gameName,
+      minutes: minutes,
+    });
+  } catch (e) {
+    exceptionLog("Unexpected error encountered when launching the game", e);
+    toastStore.makeToast("Unable to launch game", "error");
+  }
+}
+
 export async function openREPL(gameName: string): Promise<void> {
   try {
     return await invoke("open_repl", {
@@ -124,3 +153,41 @@ export async function openREPL(gameName: string): Promise<void> {
     toastStore.makeToast("Unable to open REPL", "error");
   }
 }
+
+export interface LaunchFailureReport {
+  timestamp: string;
+  commandLine: string[];
+  workingDirectory: string | null;
+  environment: Record<string, string>;
+  spawnError: string | null;
+  exitCode: number | null;
+  secondsUntilExit: number | null;
+  missingLibraries: string[];
+  loaderErrors: string[];
+  gameLogTail: string;
+}
+
+export async function getLaunchFailureReport(): Promise<LaunchFailureReport | null> {
+  try {
+    return await invoke("get_launch_failure_report", {});
+  } catch (e) {
+    exceptionLog("Unable to get launch failure report", e);
+    return null;
+  }
+}
+
+export interface MissingLibrary {
+  name: string;
+  packageHint: string | null;
+}
+
+export async function checkSharedLibraryRequirements(): Promise<
+  MissingLibrary[]
+> {
+  try {
+    return await invoke("check_shared_library_requirements", {});
+  } catch (e) {
+    exceptionLog("Unable to check shared library requirements", e);
+    return [];
+  }
+}
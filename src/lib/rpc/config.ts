@@ -1,6 +1,8 @@
 import { toastStore } from "$lib/stores/ToastStore";
 import { invoke } from "@tauri-apps/api/tauri";
-import { errorLog, exceptionLog } from "./logging";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import { describeError, errorLog, exceptionLog } from "./logging";
+import type { Warning } from "./tasks";
 import type { VersionFolders } from "./versions";
 import { locale } from "svelte-i18n";
 
@@ -62,6 +64,62 @@ export async function setInstallationDirectory(
   }
 }
 
+export async function getToolingDirectory(): Promise<string | null> {
+  try {
+    return await invoke("get_tooling_directory", {});
+  } catch (e) {
+    exceptionLog("Unable to fetch tooling directory", e);
+    return null;
+  }
+}
+
+export async function setToolingDirectory(
+  newDir: string
+): Promise<string | null> {
+  try {
+    const errMsg: string = await invoke("set_tooling_directory", {
+      newDir: newDir,
+    });
+    if (errMsg !== null) {
+      errorLog("Unable to set tooling directory");
+      toastStore.makeToast(errMsg, "error");
+    }
+    return errMsg;
+  } catch (e) {
+    exceptionLog("Unable to set tooling directory", e);
+    toastStore.makeToast("Invalid tooling directory", "error");
+    return "Unexpected error occurred";
+  }
+}
+
+export async function getGameDataDirectory(): Promise<string | null> {
+  try {
+    return await invoke("get_game_data_directory", {});
+  } catch (e) {
+    exceptionLog("Unable to fetch game data directory", e);
+    return null;
+  }
+}
+
+export async function setGameDataDirectory(
+  newDir: string
+): Promise<string | null> {
+  try {
+    const errMsg: string = await invoke("set_game_data_directory", {
+      newDir: newDir,
+    });
+    if (errMsg !== null) {
+      errorLog("Unable to set game data directory");
+      toastStore.makeToast(errMsg, "error");
+    }
+    return errMsg;
+  } catch (e) {
+    exceptionLog("Unable to set game data directory", e);
+    toastStore.makeToast("Invalid game data directory", "error");
+    return "Unexpected error occurred";
+  }
+}
+
 export async function isAVXRequirementMet(
   force: boolean
 ): Promise<boolean | undefined> {
@@ -73,6 +131,31 @@ export async function isAVXRequirementMet(
   }
 }
 
+export interface CpuCapabilities {
+  brand: string;
+  vendor: string;
+  sse: boolean;
+  sse2: boolean;
+  sse3: boolean;
+  ssse3: boolean;
+  sse41: boolean;
+  sse42: boolean;
+  avx: boolean;
+  avx2: boolean;
+  fma: boolean;
+  meetsAvxRequirement: boolean;
+  canRunAvxFreeBuilds: boolean;
+}
+
+export async function getCpuCapabilities(): Promise<CpuCapabilities | null> {
+  try {
+    return await invoke("get_cpu_capabilities", {});
+  } catch (e) {
+    exceptionLog("Unable to get CPU capabilities", e);
+    return null;
+  }
+}
+
 export async function isOpenGLRequirementMet(
   force: boolean
 ): Promise<boolean | undefined> {
@@ -88,11 +171,76 @@ export async function isOpenGLRequirementMet(
   }
 }
 
-export async function finalizeInstallation(gameName: string): Promise<void> {
+// The GPU probe crashed or hung, `isOpenGLRequirementMet` returns undefined when that happens
+export async function onRequirementProbeFailed(
+  callback: (warning: Warning) => void
+): Promise<UnlistenFn> {
+  return await listen<Warning>("requirementProbeFailed", (event) =>
+    callback(event.payload)
+  );
+}
+
+export interface InstallSummary {
+  gameName: string;
+  version: string;
+  versionFolder: string;
+  finishedAt: string;
+  durationSeconds: number | null;
+  diskUsageBytes: number;
+  warnings: Warning[];
+  performance: InstallPerformance | null;
+}
+
+export type Bottleneck = "cpu" | "disk" | "otherProcesses";
+
+export interface StagePerformance {
+  stage: string;
+  startedAt: string;
+  durationSeconds: number;
+  succeeded: boolean;
+  averageCoresUsed: number;
+  peakCoresUsed: number;
+  averageSystemCpuPercent: number;
+  bytesRead: number;
+  bytesWritten: number;
+  ioBytesPerSec: number;
+  bottleneck: Bottleneck | null;
+}
+
+export interface InstallPerformance {
+  stages: StagePerformance[];
+  totalSeconds: number;
+  bottleneck: Bottleneck | null;
+}
+
+export async function getInstallPerformance(
+  gameName: string
+): Promise<InstallPerformance | null> {
+  try {
+    return await invoke("get_install_performance", { gameName: gameName });
+  } catch (e) {
+    exceptionLog("Unable to get install performance", e);
+    return null;
+  }
+}
+
+export async function finalizeInstallation(
+  gameName: string
+): Promise<InstallSummary | null> {
   try {
     return await invoke("finalize_installation", { gameName: gameName });
   } catch (e) {
     exceptionLog("Unable to finalize installation", e);
+    return null;
+  }
+}
+
+export async function getInstallHistory(): Promise<InstallSummary[]> {
+  try {
+    return await invoke("get_install_history", {});
+  } catch (e) {
+    exceptionLog("Unable to get install history", e);
+    return [];
   }
 }
 
@@ -179,3 +327,807 @@ export async function getBypassRequirements(): Promise<boolean> {
     return false;
   }
 }
+
+export async function getLaunchOnStartup(): Promise<boolean> {
+  try {
+    return await invoke("get_launch_on_startup", {});
+  } catch (e) {
+    exceptionLog("Unable to get launch on startup setting", e);
+    return false;
+  }
+}
+
+export async function setLaunchOnStartup(
+  enabled: boolean,
+  minimized: boolean
+): Promise<boolean> {
+  try {
+    await invoke("set_launch_on_startup", {
+      enabled: enabled,
+      minimized: minimized,
+    });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to set launch on startup setting", e);
+    toastStore.makeToast("Unable to change launch on startup", "error");
+    return false;
+  }
+}
+
+export type ProcessPriority = "normal" | "aboveNormal" | "high";
+
+export interface GameProcessOptions {
+  priority: ProcessPriority | null;
+  cpuAffinity: number | null;
+}
+
+export async function getGameProcessOptions(): Promise<GameProcessOptions> {
+  try {
+    return await invoke("get_game_process_options", {});
+  } catch (e) {
+    exceptionLog("Unable to get game process options", e);
+    return { priority: null, cpuAffinity: null };
+  }
+}
+
+export async function setGameProcessOptions(
+  options: GameProcessOptions
+): Promise<boolean> {
+  try {
+    await invoke("set_game_process_options", { options: options });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to set game process options", e);
+    toastStore.makeToast("Unable to save game process options", "error");
+    return false;
+  }
+}
+
+export type DisplayMode = "windowed" | "fullscreen" | "borderless";
+
+export interface GameDisplayOptions {
+  displayMode: DisplayMode | null;
+  monitor: number | null;
+}
+
+export async function getGameDisplayOptions(): Promise<GameDisplayOptions> {
+  try {
+    return await invoke("get_game_display_options", {});
+  } catch (e) {
+    exceptionLog("Unable to get game display options", e);
+    return { displayMode: null, monitor: null };
+  }
+}
+
+export async function setGameDisplayOptions(
+  options: GameDisplayOptions
+): Promise<boolean> {
+  try {
+    await invoke("set_game_display_options", { options: options });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to set game display options", e);
+    toastStore.makeToast("Unable to save game display options", "error");
+    return false;
+  }
+}
+
+export interface GameVideoOptions {
+  playback: boolean | null;
+  hardwareDecoding: boolean | null;
+}
+
+export async function getGameVideoOptions(): Promise<GameVideoOptions> {
+  try {
+    return await invoke("get_game_video_options", {});
+  } catch (e) {
+    exceptionLog("Unable to get game video options", e);
+    return { playback: null, hardwareDecoding: null };
+  }
+}
+
+export async function setGameVideoOptions(
+  options: GameVideoOptions
+): Promise<boolean> {
+  try {
+    await invoke("set_game_video_options", { options: options });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to set game video options", e);
+    toastStore.makeToast("Unable to save game video options", "error");
+    return false;
+  }
+}
+
+export interface VideoPlaybackSupport {
+  hasVideoSettings: boolean | null;
+  missingComponents: string[];
+}
+
+export async function getVideoPlaybackSupport(
+  gameName: string
+): Promise<VideoPlaybackSupport | null> {
+  try {
+    return await invoke("get_video_playback_support", { gameName: gameName });
+  } catch (e) {
+    exceptionLog("Unable to check video playback support", e);
+    return null;
+  }
+}
+
+export interface RemoteConfig {
+  features: Record<string, boolean>;
+  disabledDownloadHosts: string[];
+  downloadUrlTemplates: Record<string, string>;
+  releaseRollouts: Record<string, number>;
+}
+
+export async function getRemoteConfig(): Promise<RemoteConfig> {
+  try {
+    return await invoke("get_remote_config", {});
+  } catch (e) {
+    exceptionLog("Unable to get remote config", e);
+    return {
+      features: {},
+      disabledDownloadHosts: [],
+      downloadUrlTemplates: {},
+      releaseRollouts: {},
+    };
+  }
+}
+
+export async function isFeatureEnabled(
+  feature: string,
+  defaultValue: boolean
+): Promise<boolean> {
+  try {
+    return await invoke("is_feature_enabled", {
+      feature: feature,
+      default: defaultValue,
+    });
+  } catch (e) {
+    exceptionLog("Unable to check if feature is enabled", e);
+    return defaultValue;
+  }
+}
+
+export interface RetentionPolicy {
+  launcherLogDays: number | null;
+  installLogDays: number | null;
+  crashReportDays: number | null;
+}
+
+export async function getRetentionPolicy(): Promise<RetentionPolicy> {
+  try {
+    return await invoke("get_retention_policy", {});
+  } catch (e) {
+    exceptionLog("Unable to get retention policy", e);
+    return {
+      launcherLogDays: null,
+      installLogDays: null,
+      crashReportDays: null,
+    };
+  }
+}
+
+export async function setRetentionPolicy(
+  policy: RetentionPolicy
+): Promise<boolean> {
+  try {
+    await invoke("set_retention_policy", { policy: policy });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to set retention policy", e);
+    toastStore.makeToast("Unable to save retention policy", "error");
+    return false;
+  }
+}
+
+export async function getMaintainActiveVersionLink(): Promise<boolean> {
+  try {
+    return await invoke("get_maintain_active_version_link", {});
+  } catch (e) {
+    exceptionLog("Unable to get active version link option", e);
+    return false;
+  }
+}
+
+export async function setMaintainActiveVersionLink(
+  enabled: boolean
+): Promise<boolean> {
+  try {
+    await invoke("set_maintain_active_version_link", { enabled: enabled });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to set active version link option", e);
+    toastStore.makeToast("Unable to save active version link option", "error");
+    return false;
+  }
+}
+
+export async function getAutoUpdateTooling(): Promise<boolean> {
+  try {
+    return await invoke("get_auto_update_tooling", {});
+  } catch (e) {
+    exceptionLog("Unable to get auto update option", e);
+    return false;
+  }
+}
+
+export async function setAutoUpdateTooling(enabled: boolean): Promise<boolean> {
+  try {
+    await invoke("set_auto_update_tooling", { enabled: enabled });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to set auto update option", e);
+    toastStore.makeToast("Unable to save auto update option", "error");
+    return false;
+  }
+}
+
+export async function getDedupeVersions(): Promise<boolean> {
+  try {
+    return await invoke("get_dedupe_versions", {});
+  } catch (e) {
+    exceptionLog("Unable to get deduplication option", e);
+    return false;
+  }
+}
+
+export async function setDedupeVersions(enabled: boolean): Promise<boolean> {
+  try {
+    await invoke("set_dedupe_versions", { enabled: enabled });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to set deduplication option", e);
+    toastStore.makeToast("Unable to save deduplication option", "error");
+    return false;
+  }
+}
+
+export async function hasGithubToken(): Promise<boolean> {
+  try {
+    return await invoke("has_github_token", {});
+  } catch (e) {
+    exceptionLog("Unable to check for a GitHub token", e);
+    return false;
+  }
+}
+
+export async function setGithubToken(token: string | null): Promise<boolean> {
+  try {
+    await invoke("set_github_token", { token: token });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to set GitHub token", e);
+    toastStore.makeToast("Unable to save GitHub token", "error");
+    return false;
+  }
+}
+
+export interface GithubRateLimit {
+  remaining: number | null;
+  limit: number | null;
+  resetsAt: string | null;
+  limited: boolean;
+}
+
+export async function getGithubRateLimit(): Promise<GithubRateLimit | null> {
+  try {
+    return await invoke("get_github_rate_limit", {});
+  } catch (e) {
+    exceptionLog("Unable to get GitHub rate limit", e);
+    return null;
+  }
+}
+
+export type QuietHoursBehavior = "pause" | "capBandwidth";
+
+export interface QuietHours {
+  start: string;
+  end: string;
+  behavior: QuietHoursBehavior;
+  bandwidthCapKbps: number | null;
+}
+
+export interface QuietHoursStatus {
+  active: boolean;
+  endsAt: string | null;
+  behavior: QuietHoursBehavior | null;
+}
+
+export async function getQuietHours(): Promise<QuietHours | null> {
+  try {
+    return await invoke("get_quiet_hours", {});
+  } catch (e) {
+    exceptionLog("Unable to get quiet hours", e);
+    return null;
+  }
+}
+
+export async function setQuietHours(
+  quietHours: QuietHours | null
+): Promise<boolean> {
+  try {
+    await invoke("set_quiet_hours", { quietHours: quietHours });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to set quiet hours", e);
+    toastStore.makeToast(describeError(e), "error");
+    return false;
+  }
+}
+
+export async function getQuietHoursStatus(): Promise<QuietHoursStatus | null> {
+  try {
+    return await invoke("get_quiet_hours_status", {});
+  } catch (e) {
+    exceptionLog("Unable to get quiet hours status", e);
+    return null;
+  }
+}
+
+export type MeteredBehavior = "ignore" | "confirm" | "pause";
+
+export async function getMeteredBehavior(): Promise<MeteredBehavior> {
+  try {
+    return await invoke("get_metered_behavior", {});
+  } catch (e) {
+    exceptionLog("Unable to get metered connection option", e);
+    return "confirm";
+  }
+}
+
+export async function setMeteredBehavior(
+  behavior: MeteredBehavior
+): Promise<boolean> {
+  try {
+    await invoke("set_metered_behavior", { behavior: behavior });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to set metered connection option", e);
+    toastStore.makeToast("Unable to save metered connection option", "error");
+    return false;
+  }
+}
+
+export async function getContentHosts(): Promise<string[]> {
+  try {
+    return await invoke("get_content_hosts", {});
+  } catch (e) {
+    exceptionLog("Unable to get content hosts", e);
+    return [];
+  }
+}
+
+export async function setContentHosts(hosts: string[]): Promise<boolean> {
+  try {
+    await invoke("set_content_hosts", { hosts: hosts });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to set content hosts", e);
+    toastStore.makeToast(describeError(e), "error");
+    return false;
+  }
+}
+
+export type ReleaseChannel = "stable" | "preRelease" | "nightly";
+
+export async function getReleaseChannel(): Promise<ReleaseChannel> {
+  try {
+    return await invoke("get_release_channel", {});
+  } catch (e) {
+    exceptionLog("Unable to get release channel", e);
+    return "stable";
+  }
+}
+
+export async function setReleaseChannel(
+  channel: ReleaseChannel
+): Promise<boolean> {
+  try {
+    await invoke("set_release_channel", { channel: channel });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to set release channel", e);
+    toastStore.makeToast("Unable to save release channel", "error");
+    return false;
+  }
+}
+
+export type SessionCapture = "off" | "summary" | "summaryWithScreenshot";
+
+export async function getSessionCapture(): Promise<SessionCapture> {
+  try {
+    return await invoke("get_session_capture", {});
+  } catch (e) {
+    exceptionLog("Unable to get session capture", e);
+    return "off";
+  }
+}
+
+export async function setSessionCapture(
+  capture: SessionCapture
+): Promise<boolean> {
+  try {
+    await invoke("set_session_capture", { capture: capture });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to set session capture", e);
+    toastStore.makeToast("Unable to save session capture", "error");
+    return false;
+  }
+}
+
+export interface SaveProgress {
+  powerCells: number;
+  precursorOrbs: number;
+  scoutFlies: number;
+  completionPercent: number;
+}
+
+export interface SaveChange {
+  file: string;
+  created: boolean;
+  bytes: number;
+  progress: SaveProgress | null;
+}
+
+export interface SessionSummary {
+  gameName: string;
+  startedAt: string;
+  endedAt: string;
+  secondsPlayed: number;
+  crashed: boolean;
+  exitCode: number | null;
+  savesWritten: SaveChange[];
+  screenshot: string | null;
+}
+
+export async function getSessionHistory(
+  gameName: string | null = null
+): Promise<SessionSummary[]> {
+  try {
+    return await invoke("get_session_history", { gameName: gameName });
+  } catch (e) {
+    exceptionLog("Unable to get session history", e);
+    return [];
+  }
+}
+
+export async function onSessionSummary(
+  callback: (summary: SessionSummary) => void
+): Promise<UnlistenFn> {
+  return await listen<SessionSummary>("sessionSummary", (event) =>
+    callback(event.payload)
+  );
+}
+
+export interface InstallRetryPolicy {
+  retries: number;
+  backoffSecs: number;
+}
+
+export async function getInstallRetryPolicy(): Promise<InstallRetryPolicy> {
+  try {
+    return await invoke("get_install_retry_policy", {});
+  } catch (e) {
+    exceptionLog("Unable to get install retry policy", e);
+    return { retries: 2, backoffSecs: 2 };
+  }
+}
+
+export async function setInstallRetryPolicy(
+  policy: InstallRetryPolicy
+): Promise<boolean> {
+  try {
+    await invoke("set_install_retry_policy", { policy: policy });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to set install retry policy", e);
+    toastStore.makeToast("Unable to save install retry policy", "error");
+    return false;
+  }
+}
+
+export async function getDownloadConnections(): Promise<number> {
+  try {
+    return await invoke("get_download_connections", {});
+  } catch (e) {
+    exceptionLog("Unable to get download connections", e);
+    return 4;
+  }
+}
+
+export async function setDownloadConnections(
+  connections: number
+): Promise<boolean> {
+  try {
+    await invoke("set_download_connections", { connections: connections });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to set download connections", e);
+    toastStore.makeToast(describeError(e), "error");
+    return false;
+  }
+}
+
+export async function getVersionsToKeep(): Promise<number> {
+  try {
+    return await invoke("get_versions_to_keep", {});
+  } catch (e) {
+    exceptionLog("Unable to get versions to keep", e);
+    return 3;
+  }
+}
+
+export async function setVersionsToKeep(count: number): Promise<boolean> {
+  try {
+    await invoke("set_versions_to_keep", { count: count });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to set versions to keep", e);
+    toastStore.makeToast("Unable to save versions to keep", "error");
+    return false;
+  }
+}
+
+export type Tool = "extractor" | "gk" | "goalc" | "launcher";
+
+export interface ToolEnvironment {
+  vars: Record<string, string>;
+  path: string[];
+  inherit: string[];
+}
+
+export async function getToolEnvironments(): Promise<
+  Partial<Record<Tool, ToolEnvironment>>
+> {
+  try {
+    return await invoke("get_tool_environments", {});
+  } catch (e) {
+    exceptionLog("Unable to get tool environments", e);
+    return {};
+  }
+}
+
+export async function setToolEnvironment(
+  tool: Tool,
+  environment: ToolEnvironment | null
+): Promise<boolean> {
+  try {
+    await invoke("set_tool_environment", {
+      tool: tool,
+      environment: environment,
+    });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to set tool environment", e);
+    toastStore.makeToast(describeError(e), "error");
+    return false;
+  }
+}
+
+export interface SyncConflict {
+  key: string;
+  localValue: string;
+  syncedValue: string;
+}
+
+export interface ConfigSyncStatus {
+  folder: string | null;
+  lastSyncedAt: string | null;
+  conflicts: SyncConflict[];
+}
+
+export async function getConfigSyncStatus(): Promise<ConfigSyncStatus | null> {
+  try {
+    return await invoke("get_config_sync_status", {});
+  } catch (e) {
+    exceptionLog("Unable to get settings sync status", e);
+    return null;
+  }
+}
+
+export async function setConfigSyncFolder(
+  folder: string | null
+): Promise<ConfigSyncStatus | null> {
+  try {
+    return await invoke("set_config_sync_folder", { folder: folder });
+  } catch (e) {
+    exceptionLog("Unable to set settings sync folder", e);
+    toastStore.makeToast(describeError(e), "error");
+    return null;
+  }
+}
+
+export type PostInstallStep =
+  | "applyDefaultSettings"
+  | { installSubtitlePack: { pack: string } }
+  | "createShortcut"
+  | "runVerification";
+
+export interface PostInstallStepUpdate {
+  gameName: string;
+  index: number;
+  total: number;
+  step: PostInstallStep;
+  status: "running" | "succeeded" | "failed";
+  message: string | null;
+}
+
+export async function getPostInstallSteps(): Promise<PostInstallStep[]> {
+  try {
+    return await invoke("get_post_install_steps", {});
+  } catch (e) {
+    exceptionLog("Unable to get post-install steps", e);
+    return ["applyDefaultSettings", "runVerification"];
+  }
+}
+
+export async function setPostInstallSteps(
+  steps: PostInstallStep[]
+): Promise<boolean> {
+  try {
+    await invoke("set_post_install_steps", { steps: steps });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to set post-install steps", e);
+    toastStore.makeToast("Unable to save post-install steps", "error");
+    return false;
+  }
+}
+
+export async function onPostInstallStepUpdated(
+  callback: (update: PostInstallStepUpdate) => void
+): Promise<UnlistenFn> {
+  return await listen<PostInstallStepUpdate>(
+    "postInstallStepUpdated",
+    (event) => callback(event.payload)
+  );
+}
+
+export type LaunchAction = "keepOpen" | "minimize" | "exit";
+export type GameExitAction = "nothing" | "reopenLauncher" | "showSummary";
+
+export interface GameLaunchActions {
+  launchAction: LaunchAction;
+  exitAction: GameExitAction;
+}
+
+export async function getGameLaunchActions(
+  gameName: string
+): Promise<GameLaunchActions> {
+  try {
+    return await invoke("get_game_launch_actions", { gameName: gameName });
+  } catch (e) {
+    exceptionLog("Unable to get game launch actions", e);
+    return { launchAction: "keepOpen", exitAction: "nothing" };
+  }
+}
+
+export async function setGameLaunchActions(
+  gameName: string,
+  actions: GameLaunchActions
+): Promise<boolean> {
+  try {
+    await invoke("set_game_launch_actions", {
+      gameName: gameName,
+      actions: actions,
+    });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to set game launch actions", e);
+    toastStore.makeToast("Unable to save game launch actions", "error");
+    return false;
+  }
+}
+
+export interface GameInstallInfo {
+  isInstalled: boolean;
+  version: string | null;
+  versionFolder: string | null;
+}
+
+export async function getGameInstallInfo(
+  gameName: string
+): Promise<GameInstallInfo | null> {
+  try {
+    return await invoke("get_game_install_info", { gameName: gameName });
+  } catch (e) {
+    exceptionLog("Unable to get game install info", e);
+    return null;
+  }
+}
+
+export interface InstallCheckpoint {
+  stage: "extracted" | "decompiled" | "compiled";
+  version: string | null;
+  versionFolder: VersionFolders;
+  reachedAt: string;
+}
+
+export async function getInstallCheckpoint(
+  gameName: string
+): Promise<InstallCheckpoint | null> {
+  try {
+    return await invoke("get_install_checkpoint", { gameName: gameName });
+  } catch (e) {
+    exceptionLog("Unable to get install checkpoint", e);
+    return null;
+  }
+}
+
+export async function getUnavailableInstallDirectories(): Promise<string[]> {
+  try {
+    return await invoke("get_unavailable_install_directories", {});
+  } catch (e) {
+    exceptionLog("Unable to check install directories", e);
+    return [];
+  }
+}
+
+// True once the install directories are reachable again, false on timeout
+export async function waitForInstallDir(
+  timeoutSeconds?: number
+): Promise<boolean> {
+  try {
+    return await invoke("wait_for_install_dir", {
+      timeoutSeconds: timeoutSeconds ?? null,
+    });
+  } catch (e) {
+    exceptionLog("Unable to wait for install directory", e);
+    return false;
+  }
+}
+
+export async function chooseNewInstallDir(
+  newDir: string
+): Promise<string | null> {
+  try {
+    return await invoke("choose_new_install_dir", { newDir: newDir });
+  } catch (e) {
+    exceptionLog("Unable to relocate install directory", e);
+    toastStore.makeToast(describeError(e), "error");
+    return null;
+  }
+}
+
+export interface FactoryResetPreview {
+  confirmToken: string;
+  launcherPaths: string[];
+  installPaths: string[];
+}
+
+export interface FactoryResetSummary {
+  removed: string[];
+  failed: string[];
+  freedBytes: number;
+}
+
+export async function prepareFactoryReset(): Promise<FactoryResetPreview | null> {
+  try {
+    return await invoke("prepare_factory_reset", {});
+  } catch (e) {
+    exceptionLog("Unable to prepare factory reset", e);
+    return null;
+  }
+}
+
+// The token comes from `prepareFactoryReset`, once the user has confirmed what
+// will be removed
+export async function factoryReset(
+  confirmToken: string,
+  includeInstallDir: boolean
+): Promise<FactoryResetSummary | null> {
+  try {
+    return await invoke("factory_reset", {
+      confirmToken: confirmToken,
+      includeInstallDir: includeInstallDir,
+    });
+  } catch (e) {
+    exceptionLog("Unable to perform factory reset", e);
+    toastStore.makeToast(describeError(e), "error");
+    return null;
+  }
+}
@@ -1,6 +1,8 @@
 import { toastStore } from "$lib/stores/ToastStore";
+import { confirm } from "@tauri-apps/api/dialog";
 import { invoke } from "@tauri-apps/api/tauri";
-import { exceptionLog } from "./logging";
+import { describeError, exceptionLog, isBackendError } from "./logging";
+import type { DeletionProgress } from "./tasks";
 
 export async function uninstallGame(gameName: string): Promise<void> {
   try {
@@ -24,3 +26,381 @@ export async function resetGameSettings(gameName: string): Promise<void> {
     toastStore.makeToast("Unable to reset game settings", "error");
   }
 }
+
+export async function hasIsoData(gameName: string): Promise<boolean> {
+  try {
+    return await invoke("has_iso_data", {
+      gameName: gameName,
+    });
+  } catch (e) {
+    exceptionLog("Unable to check for ISO data", e);
+    return false;
+  }
+}
+
+export async function purgeIsoData(
+  gameName: string
+): Promise<DeletionProgress | null> {
+  try {
+    return await invoke("purge_iso_data", {
+      gameName: gameName,
+    });
+  } catch (e) {
+    exceptionLog("Unable to remove ISO data", e);
+    toastStore.makeToast(describeError(e), "error");
+    return null;
+  }
+}
+
+export async function retainIsoData(
+  gameName: string,
+  retain: boolean
+): Promise<boolean> {
+  try {
+    await invoke("retain_iso_data", {
+      gameName: gameName,
+      retain: retain,
+    });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to change whether to keep ISO data", e);
+    toastStore.makeToast(describeError(e), "error");
+    return false;
+  }
+}
+
+export interface GameDataChange {
+  id: string;
+  description: string;
+  timestamp: string;
+  fileCount: number;
+}
+
+export async function getGameDataChanges(
+  gameName: string
+): Promise<GameDataChange[]> {
+  try {
+    return await invoke("get_game_data_changes", {
+      gameName: gameName,
+    });
+  } catch (e) {
+    exceptionLog("Unable to get game data changes", e);
+    return [];
+  }
+}
+
+export async function revertLastChange(
+  gameName: string
+): Promise<string | null> {
+  try {
+    return await invoke("revert_last_change", {
+      gameName: gameName,
+    });
+  } catch (e) {
+    exceptionLog("Unable to revert last change", e);
+    toastStore.makeToast("Unable to revert last change", "error");
+    return null;
+  }
+}
+
+export async function getPlaytimeToday(): Promise<Record<string, number>> {
+  try {
+    return await invoke("get_playtime_today", {});
+  } catch (e) {
+    exceptionLog("Unable to get today's playtime", e);
+    return {};
+  }
+}
+
+export interface DataHotfix {
+  id: string;
+  description: string;
+  versions: string[];
+  files: {
+    path: string;
+    url: string;
+    sha256: string;
+    previousSha256: string | null;
+  }[];
+}
+
+export async function checkDataHotfixes(
+  gameName: string
+): Promise<DataHotfix[]> {
+  try {
+    return await invoke("check_data_hotfixes", { gameName: gameName });
+  } catch (e) {
+    exceptionLog("Unable to check for game data hotfixes", e);
+    return [];
+  }
+}
+
+export async function applyDataHotfixes(gameName: string): Promise<string[]> {
+  try {
+    return await invoke("apply_data_hotfixes", { gameName: gameName });
+  } catch (e) {
+    exceptionLog("Unable to apply game data hotfixes", e);
+    toastStore.makeToast(describeError(e), "error");
+    return [];
+  }
+}
+
+export interface ModScriptPlan {
+  script: string;
+  modDir: string;
+  readOnly: string[];
+  writable: string[];
+  network: boolean;
+  sandbox: string | null;
+  scriptSha256: string;
+}
+
+export interface ModScriptOutput {
+  success: boolean;
+  exitCode: number | null;
+  logFile: string;
+}
+
+export async function previewModInstallScript(
+  gameName: string,
+  modName: string
+): Promise<ModScriptPlan | null> {
+  try {
+    return await invoke("preview_mod_install_script", {
+      gameName: gameName,
+      modName: modName,
+    });
+  } catch (e) {
+    exceptionLog("Unable to read mod install script", e);
+    toastStore.makeToast(describeError(e), "error");
+    return null;
+  }
+}
+
+export async function runModInstallScript(
+  gameName: string,
+  modName: string,
+  approvedSha256: string
+): Promise<ModScriptOutput | null> {
+  try {
+    return await invoke("run_mod_install_script", {
+      gameName: gameName,
+      modName: modName,
+      approvedSha256: approvedSha256,
+    });
+  } catch (e) {
+    exceptionLog("Unable to run mod install script", e);
+    toastStore.makeToast(describeError(e), "error");
+    return null;
+  }
+}
+
+// Mods from sites that aren't on the allow-list need the user's go-ahead
+export async function installModFromUrl(
+  gameName: string,
+  modName: string,
+  url: string,
+  allowUnknownHost: boolean = false
+): Promise<string | null> {
+  try {
+    return await invoke("install_mod_from_url", {
+      gameName: gameName,
+      modName: modName,
+      url: url,
+      allowUnknownHost: allowUnknownHost,
+    });
+  } catch (e) {
+    if (
+      !allowUnknownHost &&
+      isBackendError(e) &&
+      e.code === "untrustedHost" &&
+      (await confirm(`${e.detail}, download from it anyway?`, {
+        title: "OpenGOAL Launcher",
+      }))
+    ) {
+      return await installModFromUrl(gameName, modName, url, true);
+    }
+    exceptionLog("Unable to install mod", e);
+    toastStore.makeToast(describeError(e), "error");
+    return null;
+  }
+}
+
+export interface FeedEntry {
+  id: string;
+  kind: "news" | "mod";
+  title: string;
+  summary: string | null;
+  url: string;
+  imageUrl: string | null;
+  published: string | null;
+}
+
+export interface GameFeed {
+  gameName: string;
+  entries: FeedEntry[];
+  fetchedAt: string | null;
+  stale: boolean;
+}
+
+export async function getGameFeed(
+  gameName: string,
+  forceRefresh: boolean = false
+): Promise<GameFeed | null> {
+  try {
+    return await invoke("get_game_feed", {
+      gameName: gameName,
+      forceRefresh: forceRefresh,
+    });
+  } catch (e) {
+    exceptionLog("Unable to get game feed", e);
+    return null;
+  }
+}
+
+export type ArtworkKind = "grid" | "hero" | "logo";
+
+export interface ArtworkImage {
+  path: string;
+  custom: boolean;
+}
+
+export interface GameArtwork {
+  grid: ArtworkImage | null;
+  hero: ArtworkImage | null;
+  logo: ArtworkImage | null;
+}
+
+export async function getGameArtwork(
+  gameName: string,
+  modName: string | null = null
+): Promise<GameArtwork | null> {
+  try {
+    return await invoke("get_game_artwork", {
+      gameName: gameName,
+      modName: modName,
+    });
+  } catch (e) {
+    exceptionLog("Unable to get game artwork", e);
+    return null;
+  }
+}
+
+export async function setCustomArtwork(
+  gameName: string,
+  modName: string | null,
+  kind: ArtworkKind,
+  imagePath: string
+): Promise<boolean> {
+  try {
+    await invoke("set_custom_artwork", {
+      gameName: gameName,
+      modName: modName,
+      kind: kind,
+      imagePath: imagePath,
+    });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to set custom artwork", e);
+    toastStore.makeToast(describeError(e), "error");
+    return false;
+  }
+}
+
+export async function clearCustomArtwork(
+  gameName: string,
+  modName: string | null,
+  kind: ArtworkKind
+): Promise<boolean> {
+  try {
+    await invoke("clear_custom_artwork", {
+      gameName: gameName,
+      modName: modName,
+      kind: kind,
+    });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to clear custom artwork", e);
+    toastStore.makeToast(describeError(e), "error");
+    return false;
+  }
+}
+
+export interface ForeignInstall {
+  kind: "pcsx2" | "openGoal";
+  path: string;
+  saveGames: string[];
+  textureDirs: string[];
+  memoryCards: string[];
+}
+
+export interface ImportedSaves {
+  imported: number;
+  skipped: string[];
+}
+
+export async function detectForeignInstalls(
+  extraPaths: string[] = []
+): Promise<ForeignInstall[]> {
+  try {
+    return await invoke("detect_foreign_installs", { extraPaths: extraPaths });
+  } catch (e) {
+    exceptionLog("Unable to detect other installs", e);
+    return [];
+  }
+}
+
+export async function importForeignSaves(
+  sourcePath: string,
+  gameName: string
+): Promise<ImportedSaves | null> {
+  try {
+    return await invoke("import_foreign_saves", {
+      sourcePath: sourcePath,
+      gameName: gameName,
+    });
+  } catch (e) {
+    exceptionLog("Unable to import saves", e);
+    toastStore.makeToast(describeError(e), "error");
+    return null;
+  }
+}
+
+export async function importForeignTexturePack(
+  textureDir: string,
+  gameName: string,
+  packName: string
+): Promise<string | null> {
+  try {
+    return await invoke("import_foreign_texture_pack", {
+      textureDir: textureDir,
+      gameName: gameName,
+      packName: packName,
+    });
+  } catch (e) {
+    exceptionLog("Unable to import texture pack", e);
+    toastStore.makeToast(describeError(e), "error");
+    return null;
+  }
+}
+
+export interface DetectedInstall {
+  path: string;
+  games: string[];
+  versions: string[];
+  current: boolean;
+  writable: boolean;
+}
+
+// Looks for install directories on an external drive, which can be picked as the install directory
+export async function scanDriveForInstalls(
+  path: string
+): Promise<DetectedInstall[]> {
+  try {
+    return await invoke("scan_drive_for_installs", { path: path });
+  } catch (e) {
+    exceptionLog("Unable to scan drive for installs", e);
+    toastStore.makeToast(describeError(e), "error");
+    return [];
+  }
+}
@@ -0,0 +1,81 @@
+import { toastStore } from "$lib/stores/ToastStore";
+import { invoke } from "@tauri-apps/api/tauri";
+import { describeError, exceptionLog } from "./logging";
+
+export interface KioskStatus {
+  enabled: boolean;
+  locked: boolean;
+  dailyLimitMinutes: number | null;
+  playedTodaySeconds: number;
+}
+
+export async function getKioskStatus(): Promise<KioskStatus> {
+  try {
+    return await invoke("get_kiosk_status", {});
+  } catch (e) {
+    exceptionLog("Unable to get locked mode status", e);
+    return {
+      enabled: false,
+      locked: false,
+      dailyLimitMinutes: null,
+      playedTodaySeconds: 0,
+    };
+  }
+}
+
+export async function setKioskDailyLimit(
+  minutes: number | null,
+): Promise<boolean> {
+  try {
+    await invoke("set_kiosk_daily_limit", { minutes: minutes });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to set the daily playtime limit", e);
+    toastStore.makeToast(describeError(e), "error");
+    return false;
+  }
+}
+
+export async function enableKioskMode(pin: string): Promise<boolean> {
+  try {
+    await invoke("enable_kiosk_mode", { pin: pin });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to enable locked mode", e);
+    toastStore.makeToast(describeError(e), "error");
+    return false;
+  }
+}
+
+export async function disableKioskMode(pin: string): Promise<boolean> {
+  try {
+    await invoke("disable_kiosk_mode", { pin: pin });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to disable locked mode", e);
+    toastStore.makeToast(describeError(e), "error");
+    return false;
+  }
+}
+
+export async function lockKiosk(): Promise<boolean> {
+  try {
+    await invoke("lock_kiosk", {});
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to lock the launcher", e);
+    toastStore.makeToast(describeError(e), "error");
+    return false;
+  }
+}
+
+export async function unlockKiosk(pin: string): Promise<boolean> {
+  try {
+    await invoke("unlock_kiosk", { pin: pin });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to unlock the launcher", e);
+    toastStore.makeToast(describeError(e), "error");
+    return false;
+  }
+}
@@ -40,11 +40,38 @@ export async function errorLog(log: String): Promise<void> {
   genericLog("error", log);
 }
 
+// Errors returned from backend commands, `message` is localized while `detail`
+// is always the original english error
+export interface BackendError {
+  code: string;
+  message: string;
+  detail: string;
+}
+
+export function isBackendError(error: any): error is BackendError {
+  return (
+    error !== null &&
+    typeof error === "object" &&
+    typeof error.code === "string" &&
+    typeof error.detail === "string"
+  );
+}
+
+// A user presentable description of an error thrown by `invoke`
+export function describeError(error: any): string {
+  if (isBackendError(error)) {
+    return `${error.message}: ${error.detail}`;
+  }
+  return String(error);
+}
+
 export async function exceptionLog(log: String, error: any): Promise<void> {
   if (error instanceof Error) {
     errorLog(
       `${log} | Exception: ${error.name}:${error.message}, Stack: ${error.stack}, Cause: ${error.cause}`
     );
+  } else if (isBackendError(error)) {
+    errorLog(`${log} | Error (${error.code}): ${error.detail}`);
   } else {
     errorLog(error);
   }
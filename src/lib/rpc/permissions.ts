@@ -0,0 +1,37 @@
+import { toastStore } from "$lib/stores/ToastStore";
+import { invoke } from "@tauri-apps/api/tauri";
+import { describeError, exceptionLog } from "./logging";
+
+export type PermissionKind =
+  | "telemetry"
+  | "bundleUploads"
+  | "remoteConfig"
+  | "remoteApi";
+
+export interface PermissionStatus {
+  kind: PermissionKind;
+  granted: boolean;
+  changedAt: string | null;
+}
+
+export async function getPermissions(): Promise<PermissionStatus[]> {
+  try {
+    return await invoke("get_permissions", {});
+  } catch (e) {
+    exceptionLog("Unable to get permissions", e);
+    return [];
+  }
+}
+
+export async function setPermission(
+  kind: PermissionKind,
+  granted: boolean
+): Promise<PermissionStatus | null> {
+  try {
+    return await invoke("set_permission", { kind: kind, granted: granted });
+  } catch (e) {
+    exceptionLog("Unable to set permission", e);
+    toastStore.makeToast(describeError(e), "error");
+    return null;
+  }
+}
@@ -0,0 +1,44 @@
+import { toastStore } from "$lib/stores/ToastStore";
+import { invoke } from "@tauri-apps/api/tauri";
+import { describeError, exceptionLog } from "./logging";
+
+export interface RemoteApiSettings {
+  enabled: boolean;
+  port: number;
+  token: string | null;
+}
+
+export async function getRemoteApiSettings(): Promise<RemoteApiSettings | null> {
+  try {
+    return await invoke("get_remote_api_settings", {});
+  } catch (e) {
+    exceptionLog("Unable to get remote control API settings", e);
+    return null;
+  }
+}
+
+export async function setRemoteApiEnabled(
+  enabled: boolean,
+  port: number | null = null
+): Promise<RemoteApiSettings | null> {
+  try {
+    return await invoke("set_remote_api_enabled", {
+      enabled: enabled,
+      port: port,
+    });
+  } catch (e) {
+    exceptionLog("Unable to set remote control API options", e);
+    toastStore.makeToast(describeError(e), "error");
+    return null;
+  }
+}
+
+export async function regenerateRemoteApiToken(): Promise<RemoteApiSettings | null> {
+  try {
+    return await invoke("regenerate_remote_api_token", {});
+  } catch (e) {
+    exceptionLog("Unable to regenerate remote control API token", e);
+    toastStore.makeToast(describeError(e), "error");
+    return null;
+  }
+}
@@ -0,0 +1,33 @@
+import { toastStore } from "$lib/stores/ToastStore";
+import { invoke } from "@tauri-apps/api/tauri";
+import { describeError, exceptionLog } from "./logging";
+
+export async function isSafeMode(): Promise<boolean> {
+  try {
+    return await invoke("is_safe_mode", {});
+  } catch (e) {
+    exceptionLog("Unable to check for safe mode", e);
+    return false;
+  }
+}
+
+// Resolves to the path of the backup that was kept, or null if there was no
+// settings file to discard
+export async function discardSettingsFile(): Promise<string | null> {
+  try {
+    return await invoke("discard_settings_file", {});
+  } catch (e) {
+    exceptionLog("Unable to discard settings file", e);
+    toastStore.makeToast(describeError(e), "error");
+    return null;
+  }
+}
+
+export async function restartLauncher(safeMode: boolean): Promise<void> {
+  try {
+    await invoke("restart_launcher", { safeMode: safeMode });
+  } catch (e) {
+    exceptionLog("Unable to restart the launcher", e);
+    toastStore.makeToast(describeError(e), "error");
+  }
+}
@@ -0,0 +1,70 @@
+import { toastStore } from "$lib/stores/ToastStore";
+import { invoke } from "@tauri-apps/api/tauri";
+import { describeError, exceptionLog } from "./logging";
+
+export interface SaveSnapshot {
+  id: string;
+  gameName: string;
+  label: string;
+  createdAt: string;
+  checkpoint: string | null;
+}
+
+export async function createSnapshot(
+  gameName: string,
+  label: string | null = null
+): Promise<SaveSnapshot | null> {
+  try {
+    return await invoke("create_snapshot", {
+      gameName: gameName,
+      label: label,
+    });
+  } catch (e) {
+    exceptionLog("Unable to take save snapshot", e);
+    toastStore.makeToast(describeError(e), "error");
+    return null;
+  }
+}
+
+export async function listSnapshots(gameName: string): Promise<SaveSnapshot[]> {
+  try {
+    return await invoke("list_snapshots", { gameName: gameName });
+  } catch (e) {
+    exceptionLog("Unable to list save snapshots", e);
+    return [];
+  }
+}
+
+export async function restoreSnapshot(
+  gameName: string,
+  id: string
+): Promise<boolean> {
+  try {
+    await invoke("restore_snapshot", { gameName: gameName, id: id });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to restore save snapshot", e);
+    toastStore.makeToast(describeError(e), "error");
+    return false;
+  }
+}
+
+export async function getSnapshotHotkey(): Promise<string> {
+  try {
+    return await invoke("get_snapshot_hotkey", {});
+  } catch (e) {
+    exceptionLog("Unable to get snapshot hotkey", e);
+    return "";
+  }
+}
+
+export async function setSnapshotHotkey(hotkey: string): Promise<boolean> {
+  try {
+    await invoke("set_snapshot_hotkey", { hotkey: hotkey });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to set snapshot hotkey", e);
+    toastStore.makeToast("Unable to save snapshot hotkey", "error");
+    return false;
+  }
+}
@@ -1,7 +1,7 @@
 import { toastStore } from "$lib/stores/ToastStore";
-import { saveFilePrompt } from "$lib/utils/file";
+import { filePrompt, saveFilePrompt } from "$lib/utils/file";
 import { invoke } from "@tauri-apps/api/tauri";
-import { exceptionLog } from "./logging";
+import { describeError, exceptionLog } from "./logging";
 
 export async function generateSupportPackage(): Promise<boolean> {
   try {
@@ -16,3 +16,190 @@ export async function generateSupportPackage(): Promise<boolean> {
     toastStore.makeToast("Unable to create support package", "error");
   }
 }
+
+export interface SelfTestResult {
+  subsystem: string;
+  passed: boolean;
+  msg: string | null;
+}
+
+export async function runSelfTest(): Promise<SelfTestResult[]> {
+  try {
+    return await invoke("run_self_test", {});
+  } catch (e) {
+    exceptionLog("Unable to run self-test", e);
+    toastStore.makeToast("Unable to run self-test", "error");
+    return [];
+  }
+}
+
+export interface CloudSyncedFolder {
+  location: "toolingDirectory" | "gameDataDirectory" | "saves";
+  path: string;
+  client: "oneDrive" | "dropbox" | "googleDrive" | "iCloud";
+  syncRoot: string;
+}
+
+export async function getCloudSyncedFolders(): Promise<CloudSyncedFolder[]> {
+  try {
+    return await invoke("get_cloud_synced_folders", {});
+  } catch (e) {
+    exceptionLog("Unable to check for cloud synced folders", e);
+    return [];
+  }
+}
+
+export async function excludeFromCloudSync(path: string): Promise<boolean> {
+  try {
+    await invoke("exclude_from_cloud_sync", { path: path });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to exclude folder from cloud sync", e);
+    toastStore.makeToast(describeError(e), "error");
+    return false;
+  }
+}
+
+export interface DataUsageEntry {
+  category: string;
+  path: string | null;
+  sizeBytes: number;
+  fileCount: number;
+}
+
+export async function getDataUsageBreakdown(): Promise<DataUsageEntry[]> {
+  try {
+    return await invoke("get_data_usage_breakdown", {});
+  } catch (e) {
+    exceptionLog("Unable to get data usage breakdown", e);
+    return [];
+  }
+}
+
+// Must match `API_VERSION` in `src-tauri/src/commands/support.rs`
+export const API_VERSION = 2;
+
+export async function getApiVersion(): Promise<number | null> {
+  try {
+    return await invoke("get_api_version", {});
+  } catch (e) {
+    exceptionLog("Unable to get backend API version", e);
+    return null;
+  }
+}
+
+export interface CacheUsage {
+  name: string;
+  entries: number;
+  approximateBytes: number;
+}
+
+export interface MemoryUsage {
+  allocator: string;
+  residentBytes: number;
+  virtualBytes: number;
+  caches: CacheUsage[];
+}
+
+export async function getMemoryUsage(): Promise<MemoryUsage | null> {
+  try {
+    return await invoke("get_memory_usage", {});
+  } catch (e) {
+    exceptionLog("Unable to get memory usage", e);
+    return null;
+  }
+}
+
+export interface ServiceStatus {
+  name: string;
+  state: "running" | "restarting" | "failed" | "stopped";
+  startedAt: string;
+  restarts: number;
+  lastError: string | null;
+  lastRestartAt: string | null;
+}
+
+export async function getServiceStatus(): Promise<ServiceStatus[]> {
+  try {
+    return await invoke("get_service_status", {});
+  } catch (e) {
+    exceptionLog("Unable to get service status", e);
+    return [];
+  }
+}
+
+export type SetupStep = "installDir" | "download" | "requirements" | "iso";
+
+export interface SetupStepReport {
+  step: SetupStep;
+  attempts: number;
+  successes: number;
+  failures: number;
+  abandoned: number;
+  lastAttemptAt: string | null;
+  lastFailure: string | null;
+}
+
+export interface SetupFunnelReport {
+  steps: SetupStepReport[];
+  stoppedAt: SetupStep | null;
+  summary: string;
+}
+
+export async function getSetupFunnelReport(): Promise<
+  SetupFunnelReport | null
+> {
+  try {
+    return await invoke("get_setup_funnel_report", {});
+  } catch (e) {
+    exceptionLog("Unable to get setup funnel report", e);
+    return null;
+  }
+}
+
+export interface BackupManifest {
+  schemaVersion: number;
+  createdAt: string;
+  launcherVersion: string;
+  files: number;
+  bytes: number;
+}
+
+export interface BackupRestore {
+  manifest: BackupManifest;
+  filesRestored: number;
+  skipped: string[];
+}
+
+// Backs up the settings, saves and mod metadata, for moving to a new machine
+export async function createFullBackup(): Promise<BackupManifest | null> {
+  try {
+    const dest = await saveFilePrompt(
+      "ZIP",
+      ["zip"],
+      "opengoal-launcher-backup.zip"
+    );
+    if (dest === null) {
+      return null;
+    }
+    return await invoke("create_full_backup", { dest: dest });
+  } catch (e) {
+    exceptionLog("Unable to create backup", e);
+    toastStore.makeToast(describeError(e), "error");
+    return null;
+  }
+}
+
+export async function restoreFullBackup(): Promise<BackupRestore | null> {
+  try {
+    const path = await filePrompt(["zip"], "ZIP", "Select a launcher backup");
+    if (path === null) {
+      return null;
+    }
+    return await invoke("restore_full_backup", { path: path });
+  } catch (e) {
+    exceptionLog("Unable to restore backup", e);
+    toastStore.makeToast(describeError(e), "error");
+    return null;
+  }
+}
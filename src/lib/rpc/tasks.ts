@@ -0,0 +1,134 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import { exceptionLog } from "./logging";
+
+export type TaskStatus =
+  | "running"
+  | "succeeded"
+  | "failed"
+  | "cancelled"
+  | "interrupted";
+
+export type WarningCode =
+  | "unverifiedDownload"
+  | "stepRetried"
+  | "permissionsFixed"
+  | "postInstallStepFailed"
+  | "requirementsBypassed"
+  | "requirementProbeFailed"
+  | "isoDataNotPurged"
+  | "unsignedBinaries"
+  | "toolWarning";
+
+export interface Warning {
+  code: WarningCode;
+  message: string;
+}
+
+export interface TaskState {
+  id: string;
+  description: string;
+  status: TaskStatus;
+  startedAt: string;
+  lastHeartbeat: string;
+  finishedAt: string | null;
+  message: string | null;
+  estimatedFinishAt: string | null;
+  warnings: Warning[];
+}
+
+export async function getTaskState(id: string): Promise<TaskState | null> {
+  try {
+    return await invoke("get_task_state", { id: id });
+  } catch (e) {
+    exceptionLog("Unable to get task state", e);
+    return null;
+  }
+}
+
+export async function getRunningTasks(): Promise<TaskState[]> {
+  try {
+    return await invoke("get_running_tasks", {});
+  } catch (e) {
+    exceptionLog("Unable to get running tasks", e);
+    return [];
+  }
+}
+
+export async function cancelTask(id: string): Promise<boolean> {
+  try {
+    return await invoke("cancel_task", { id: id });
+  } catch (e) {
+    exceptionLog("Unable to cancel task", e);
+    return false;
+  }
+}
+
+export async function cancelRunningTasks(): Promise<void> {
+  for (const task of await getRunningTasks()) {
+    await cancelTask(task.id);
+  }
+}
+
+// Subscribes to heartbeats and status changes for a single task, useful for picking a task
+// back up after the frontend has been reloaded
+export async function subscribeToTask(
+  id: string,
+  callback: (state: TaskState) => void
+): Promise<UnlistenFn> {
+  const handler = (event: { payload: TaskState }) => {
+    if (event.payload.id === id) {
+      callback(event.payload);
+    }
+  };
+  const unlistenHeartbeat = await listen<TaskState>("taskHeartbeat", handler);
+  const unlistenUpdated = await listen<TaskState>("taskUpdated", handler);
+  return () => {
+    unlistenHeartbeat();
+    unlistenUpdated();
+  };
+}
+
+export interface DeletionProgress {
+  filesRemoved: number;
+  totalFiles: number;
+  bytesRemoved: number;
+  totalBytes: number;
+}
+
+export interface DeletionProgressUpdate {
+  taskId: string;
+  progress: DeletionProgress;
+}
+
+// Removing a version (`remove-<folder>-<version>`) or uninstalling a game (`uninstall-<game>`)
+export async function onDeletionProgress(
+  callback: (update: DeletionProgressUpdate) => void
+): Promise<UnlistenFn> {
+  return await listen<DeletionProgressUpdate>(
+    "deletionProgress",
+    (event) => callback(event.payload)
+  );
+}
+
+export interface CopyProgress {
+  filesCopied: number;
+  totalFiles: number;
+  bytesCopied: number;
+  totalBytes: number;
+}
+
+export interface CopyProgressUpdate {
+  taskId: string;
+  progress: CopyProgress;
+}
+
+// Moving a version folder (`migrate-versions-<folder>`), adopting versions into the shared
+// location (`adopt-shared-versions`) or restoring a save snapshot (`restore-snapshot-<game>`)
+export async function onCopyProgress(
+  callback: (update: CopyProgressUpdate) => void
+): Promise<UnlistenFn> {
+  return await listen<CopyProgressUpdate>("copyProgress", (event) =>
+    callback(event.payload)
+  );
+}
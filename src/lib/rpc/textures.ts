@@ -0,0 +1,99 @@
+import { toastStore } from "$lib/stores/ToastStore";
+import { confirm } from "@tauri-apps/api/dialog";
+import { invoke } from "@tauri-apps/api/tauri";
+import { describeError, exceptionLog, isBackendError } from "./logging";
+
+export interface TexturePackInfo {
+  name: string;
+  author: string;
+  description: string;
+  version: string;
+}
+
+export interface AppliedTexturePacks {
+  linkedFiles: number;
+  conflicts: string[];
+}
+
+export async function installTexturePack(
+  gameName: string,
+  archivePath: string
+): Promise<string | undefined> {
+  try {
+    return await invoke("install_texture_pack", {
+      gameName: gameName,
+      archivePath: archivePath,
+    });
+  } catch (e) {
+    exceptionLog("Unable to install texture pack", e);
+    toastStore.makeToast("Unable to install texture pack", "error");
+    return undefined;
+  }
+}
+
+// Packs from sites that aren't on the allow-list need the user's go-ahead
+export async function installTexturePackFromUrl(
+  gameName: string,
+  url: string,
+  allowUnknownHost: boolean = false
+): Promise<string | undefined> {
+  try {
+    return await invoke("install_texture_pack_from_url", {
+      gameName: gameName,
+      url: url,
+      allowUnknownHost: allowUnknownHost,
+    });
+  } catch (e) {
+    if (
+      !allowUnknownHost &&
+      isBackendError(e) &&
+      e.code === "untrustedHost" &&
+      (await confirm(`${e.detail}, download from it anyway?`, {
+        title: "OpenGOAL Launcher",
+      }))
+    ) {
+      return await installTexturePackFromUrl(gameName, url, true);
+    }
+    exceptionLog("Unable to install texture pack", e);
+    toastStore.makeToast(describeError(e), "error");
+    return undefined;
+  }
+}
+
+export async function listTexturePacks(
+  gameName: string
+): Promise<TexturePackInfo[]> {
+  try {
+    return await invoke("list_texture_packs", { gameName: gameName });
+  } catch (e) {
+    exceptionLog("Unable to list texture packs", e);
+    return [];
+  }
+}
+
+export async function getAppliedTexturePacks(
+  gameName: string
+): Promise<string[]> {
+  try {
+    return await invoke("get_applied_texture_packs", { gameName: gameName });
+  } catch (e) {
+    exceptionLog("Unable to get applied texture packs", e);
+    return [];
+  }
+}
+
+export async function applyTexturePacks(
+  gameName: string,
+  packs: string[]
+): Promise<AppliedTexturePacks | undefined> {
+  try {
+    return await invoke("apply_texture_packs", {
+      gameName: gameName,
+      packs: packs,
+    });
+  } catch (e) {
+    exceptionLog("Unable to apply texture packs", e);
+    toastStore.makeToast("Unable to apply texture packs", "error");
+    return undefined;
+  }
+}
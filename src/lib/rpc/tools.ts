@@ -0,0 +1,53 @@
+import { toastStore } from "$lib/stores/ToastStore";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import { invoke } from "@tauri-apps/api/tauri";
+import { describeError, exceptionLog } from "./logging";
+
+export interface AvailableTool {
+  name: string;
+  description: string;
+  path: string;
+}
+
+export interface ToolOutputLine {
+  taskId: string;
+  stream: "stdout" | "stderr";
+  line: string;
+}
+
+export interface ToolRunOutput {
+  exitCode: number | null;
+  success: boolean;
+  cancelled: boolean;
+}
+
+export async function listAvailableTools(): Promise<AvailableTool[]> {
+  try {
+    return await invoke("list_available_tools", {});
+  } catch (e) {
+    exceptionLog("Unable to list available tools", e);
+    return [];
+  }
+}
+
+// Runs as the `tool-<name>` task, cancelling that stops the tool
+export async function runTool(
+  name: string,
+  args: string[]
+): Promise<ToolRunOutput | null> {
+  try {
+    return await invoke("run_tool", { name: name, args: args });
+  } catch (e) {
+    exceptionLog("Unable to run tool", e);
+    toastStore.makeToast(describeError(e), "error");
+    return null;
+  }
+}
+
+export async function onToolOutput(
+  callback: (line: ToolOutputLine) => void
+): Promise<UnlistenFn> {
+  return await listen<ToolOutputLine>("toolOutput", (event) =>
+    callback(event.payload)
+  );
+}
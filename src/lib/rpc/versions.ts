@@ -1,12 +1,37 @@
 import { toastStore } from "$lib/stores/ToastStore";
+import { confirm } from "@tauri-apps/api/dialog";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
 import { invoke } from "@tauri-apps/api/tauri";
-import { exceptionLog } from "./logging";
+import type { ReleaseChannel } from "./config";
+import { describeError, exceptionLog, isBackendError } from "./logging";
 
-export type VersionFolders = null | "official" | "unofficial" | "devel";
+export type VersionFolders =
+  | null
+  | "official"
+  | "unofficial"
+  | "devel"
+  | "local";
 
+export interface VersionOrderingKey {
+  major: number;
+  minor: number;
+  patch: number;
+  preRelease: string | null;
+}
+
+export interface DownloadedVersion {
+  version: string;
+  orderingKey: VersionOrderingKey | null;
+  downloadedAt: string | null;
+  bytes: number;
+  isActive: boolean;
+  channel: ReleaseChannel | null;
+}
+
+// Newest first
 export async function listDownloadedVersions(
   folder: VersionFolders
-): Promise<string[]> {
+): Promise<DownloadedVersion[]> {
   try {
     return await invoke("list_downloaded_versions", { versionFolder: folder });
   } catch (e) {
@@ -15,19 +40,386 @@ export async function listDownloadedVersions(
   }
 }
 
+export interface ReleaseCompatibility {
+  checked: boolean;
+  compatible: boolean;
+  launcherVersion: string;
+  minLauncherVersion: string | null;
+  breakingChanges: string[];
+}
+
+export async function checkReleaseCompatibility(
+  versionFolder: string,
+  version: String
+): Promise<ReleaseCompatibility | null> {
+  try {
+    return await invoke("check_release_compatibility", {
+      versionFolder: versionFolder,
+      version: version,
+    });
+  } catch (e) {
+    exceptionLog("Unable to check release compatibility", e);
+    return null;
+  }
+}
+
+// Warns about releases that need a newer launcher or have breaking changes,
+// resolves to whether the download should go ahead
+async function confirmReleaseCompatibility(
+  versionFolder: string,
+  version: String
+): Promise<boolean> {
+  const compatibility = await checkReleaseCompatibility(versionFolder, version);
+  if (compatibility === null) {
+    return true;
+  }
+  let warnings: string[] = [];
+  if (!compatibility.compatible) {
+    warnings.push(
+      `${version} requires launcher ${compatibility.minLauncherVersion} or ` +
+        `newer, you have ${compatibility.launcherVersion}. It likely won't launch.`
+    );
+  }
+  for (const change of compatibility.breakingChanges) {
+    warnings.push(`Breaking change: ${change}`);
+  }
+  if (warnings.length === 0) {
+    return true;
+  }
+  return await confirm(`${warnings.join("\n")}\n\nDownload anyway?`, {
+    title: "OpenGOAL Launcher",
+  });
+}
+
+export async function cancelDownload(jobId: string): Promise<boolean> {
+  try {
+    return await invoke("cancel_download", { jobId: jobId });
+  } catch (e) {
+    exceptionLog("Unable to cancel download", e);
+    return false;
+  }
+}
+
+export interface VersionDownloadProgress {
+  jobId: string;
+  versionFolder: VersionFolders;
+  version: string;
+  stage:
+    | "downloading"
+    | "downloadingAndExtracting"
+    | "extracting"
+    | "extracted";
+  downloadedBytes: number;
+  totalBytes: number | null;
+  percent: number | null;
+}
+
+export async function onVersionDownloadProgress(
+  callback: (progress: VersionDownloadProgress) => void
+): Promise<UnlistenFn> {
+  return await listen<VersionDownloadProgress>(
+    "versionDownloadProgress",
+    (event) => callback(event.payload)
+  );
+}
+
+export interface ReleaseAsset {
+  name: string;
+  downloadUrl: string;
+}
+
+export interface CachedRelease {
+  tag: string;
+  publishedAt: string | null;
+  githubLink: string | null;
+  assets: ReleaseAsset[];
+  channel: ReleaseChannel;
+}
+
+export interface ReleaseCache {
+  releases: CachedRelease[];
+  cachedAt: string;
+}
+
+export async function cacheReleases(releases: CachedRelease[]): Promise<void> {
+  try {
+    await invoke("cache_releases", { releases: releases });
+  } catch (e) {
+    exceptionLog("Unable to cache releases", e);
+  }
+}
+
+export async function getCachedReleases(): Promise<ReleaseCache | null> {
+  try {
+    return await invoke("get_cached_releases", {});
+  } catch (e) {
+    exceptionLog("Unable to get cached releases", e);
+    return null;
+  }
+}
+
+export interface ReleaseChangelog {
+  tag: string;
+  name: string | null;
+  // Markdown, as written on GitHub
+  body: string;
+  publishedAt: string | null;
+  githubLink: string | null;
+  cachedAt: string;
+}
+
+// `null` if the version's source isn't on GitHub, or the changelog couldn't be retrieved.
+// `refresh` skips the cached changelog even if it's recent
+export async function getReleaseChangelog(
+  versionFolder: string,
+  version: string,
+  refresh: boolean = false
+): Promise<ReleaseChangelog | null> {
+  try {
+    return await invoke("get_release_changelog", {
+      versionFolder,
+      version,
+      refresh,
+    });
+  } catch (e) {
+    exceptionLog("Unable to get release changelog", e);
+    toastStore.makeToast(describeError(e), "error");
+    return null;
+  }
+}
+
+// Official releases on the configured channel (or `channel`), falling back to the cached listing
+// when GitHub can't be reached
+export async function listChannelReleases(
+  channel: ReleaseChannel | undefined = undefined
+): Promise<CachedRelease[]> {
+  try {
+    return await invoke("list_channel_releases", { channel: channel ?? null });
+  } catch (e) {
+    exceptionLog("Unable to list releases", e);
+    return [];
+  }
+}
+
+export interface NewToolingVersion {
+  versionFolder: VersionFolders;
+  tag: string;
+  publishedAt: string | null;
+  changelogUrl: string;
+  assetUrl: string | null;
+}
+
+export async function checkForNewToolingVersion(): Promise<
+  NewToolingVersion | null
+> {
+  try {
+    return await invoke("check_for_new_tooling_version", {});
+  } catch (e) {
+    exceptionLog("Unable to check for a new tooling version", e);
+    return null;
+  }
+}
+
+export async function onNewVersionAvailable(
+  callback: (version: NewToolingVersion) => void
+): Promise<UnlistenFn> {
+  return await listen<NewToolingVersion>("newVersionAvailable", (event) =>
+    callback(event.payload)
+  );
+}
+
+export interface ToolingUpdateProgress {
+  stage: "checking" | "downloading" | "verifying" | "switched" | "upToDate";
+  version: string | null;
+}
+
+export async function updateToolingToLatest(): Promise<
+  NewToolingVersion | null
+> {
+  try {
+    return await invoke("update_tooling_to_latest", {});
+  } catch (e) {
+    exceptionLog("Unable to update tooling to the latest version", e);
+    toastStore.makeToast(describeError(e), "error");
+    return null;
+  }
+}
+
+export async function onToolingUpdateProgress(
+  callback: (progress: ToolingUpdateProgress) => void
+): Promise<UnlistenFn> {
+  return await listen<ToolingUpdateProgress>(
+    "toolingUpdateProgress",
+    (event) => callback(event.payload)
+  );
+}
+
+export interface DownloadJob {
+  id: string;
+  versionFolder: VersionFolders;
+  version: string;
+  state: "queued" | "downloading" | "paused" | "finished";
+  queuedAt: string;
+  downloadedBytes: number;
+  totalBytes: number | null;
+}
+
+export async function listDownloads(): Promise<DownloadJob[]> {
+  try {
+    return await invoke("list_downloads", {});
+  } catch (e) {
+    exceptionLog("Unable to list downloads", e);
+    return [];
+  }
+}
+
+export async function pauseDownload(jobId: string): Promise<boolean> {
+  try {
+    return await invoke("pause_download", { jobId: jobId });
+  } catch (e) {
+    exceptionLog("Unable to pause download", e);
+    toastStore.makeToast(describeError(e), "error");
+    return false;
+  }
+}
+
+export async function resumeDownload(jobId: string): Promise<boolean> {
+  try {
+    return await invoke("resume_download", { jobId: jobId });
+  } catch (e) {
+    exceptionLog("Unable to resume download", e);
+    toastStore.makeToast(describeError(e), "error");
+    return false;
+  }
+}
+
+export async function onDownloadJobUpdated(
+  callback: (job: DownloadJob) => void
+): Promise<UnlistenFn> {
+  return await listen<DownloadJob>("downloadJobUpdated", (event) =>
+    callback(event.payload)
+  );
+}
+
+// Binaries whose signatures don't check out are only kept if the user says so
+async function confirmUnsignedBinaries(e: unknown): Promise<boolean> {
+  return (
+    isBackendError(e) &&
+    e.code === "unsignedBinaries" &&
+    (await confirm(
+      `${e.detail}. They may not be the project's own builds, use them anyway?`,
+      { title: "OpenGOAL Launcher" }
+    ))
+  );
+}
+
 export async function downloadOfficialVersion(
   version: String,
-  url: String
+  url: String,
+  allowMetered: boolean = false,
+  expectedSha256: String | undefined = undefined,
+  allowUnsigned: boolean = false
 ): Promise<boolean> {
+  // Retries after the metered or signature prompts have already been through this
+  if (
+    !allowMetered &&
+    !allowUnsigned &&
+    !(await confirmReleaseCompatibility("official", version))
+  ) {
+    return false;
+  }
   try {
     await invoke("download_version", {
       version: version,
       versionFolder: "official",
       url: url,
+      allowMetered: allowMetered,
+      expectedSha256: expectedSha256,
+      allowUnsigned: allowUnsigned,
     });
   } catch (e) {
+    // Large downloads on a metered connection need the user's go-ahead
+    if (
+      !allowMetered &&
+      isBackendError(e) &&
+      e.code === "meteredConnection" &&
+      (await confirm(`${e.message}, download anyway?`, {
+        title: "OpenGOAL Launcher",
+      }))
+    ) {
+      return await downloadOfficialVersion(
+        version,
+        url,
+        true,
+        expectedSha256,
+        allowUnsigned
+      );
+    }
+    if (!allowUnsigned && (await confirmUnsignedBinaries(e))) {
+      return await downloadOfficialVersion(
+        version,
+        url,
+        allowMetered,
+        expectedSha256,
+        true
+      );
+    }
     exceptionLog("Unable to download official version", e);
-    toastStore.makeToast(e, "error");
+    toastStore.makeToast(describeError(e), "error");
+    return false;
+  }
+  return true;
+}
+
+// Downloads a version of any registered source, the backend works out where from
+export async function downloadSourceVersion(
+  versionFolder: string,
+  version: string,
+  allowMetered: boolean = false,
+  allowUnsigned: boolean = false
+): Promise<boolean> {
+  if (
+    !allowMetered &&
+    !allowUnsigned &&
+    !(await confirmReleaseCompatibility(versionFolder, version))
+  ) {
+    return false;
+  }
+  try {
+    await invoke("download_version", {
+      version: version,
+      versionFolder: versionFolder,
+      url: null,
+      allowMetered: allowMetered,
+      expectedSha256: null,
+      allowUnsigned: allowUnsigned,
+    });
+  } catch (e) {
+    if (
+      !allowMetered &&
+      isBackendError(e) &&
+      e.code === "meteredConnection" &&
+      (await confirm(`${e.message}, download anyway?`, {
+        title: "OpenGOAL Launcher",
+      }))
+    ) {
+      return await downloadSourceVersion(
+        versionFolder,
+        version,
+        true,
+        allowUnsigned
+      );
+    }
+    if (!allowUnsigned && (await confirmUnsignedBinaries(e))) {
+      return await downloadSourceVersion(
+        versionFolder,
+        version,
+        allowMetered,
+        true
+      );
+    }
+    exceptionLog("Unable to download version", e);
+    toastStore.makeToast(describeError(e), "error");
     return false;
   }
   return true;
@@ -50,6 +442,153 @@ export async function removeVersion(
   return true;
 }
 
+export interface VersionVerification {
+  versionFolder: string;
+  version: string;
+  intact: boolean;
+  hasManifest: boolean;
+  hashesChecked: boolean;
+  checkedFiles: number;
+  missing: string[];
+  modified: string[];
+}
+
+export async function verifyVersion(
+  versionFolder: string,
+  version: string,
+  checkHashes: boolean = false
+): Promise<VersionVerification | null> {
+  try {
+    return await invoke("verify_version", {
+      versionFolder: versionFolder,
+      version: version,
+      checkHashes: checkHashes,
+    });
+  } catch (e) {
+    exceptionLog("Unable to verify version", e);
+    toastStore.makeToast(describeError(e), "error");
+    return null;
+  }
+}
+
+// Re-downloads the version, but only puts back the files that are missing or modified
+export async function repairVersion(
+  versionFolder: string,
+  version: string,
+  checkHashes: boolean = false,
+  allowMetered: boolean = false,
+  allowUnsigned: boolean = false
+): Promise<VersionVerification | null> {
+  try {
+    return await invoke("repair_version", {
+      versionFolder: versionFolder,
+      version: version,
+      checkHashes: checkHashes,
+      allowMetered: allowMetered,
+      allowUnsigned: allowUnsigned,
+    });
+  } catch (e) {
+    if (
+      !allowMetered &&
+      isBackendError(e) &&
+      e.code === "meteredConnection" &&
+      (await confirm(`${e.message}, download anyway?`, {
+        title: "OpenGOAL Launcher",
+      }))
+    ) {
+      return await repairVersion(
+        versionFolder,
+        version,
+        checkHashes,
+        true,
+        allowUnsigned
+      );
+    }
+    if (!allowUnsigned && (await confirmUnsignedBinaries(e))) {
+      return await repairVersion(
+        versionFolder,
+        version,
+        checkHashes,
+        allowMetered,
+        true
+      );
+    }
+    exceptionLog("Unable to repair version", e);
+    toastStore.makeToast(describeError(e), "error");
+    return null;
+  }
+}
+
+export interface PrunedVersion {
+  versionFolder: string;
+  version: string;
+  bytesRemoved: number;
+}
+
+export interface PruneResult {
+  removed: PrunedVersion[];
+  bytesReclaimed: number;
+}
+
+export async function pruneVersions(
+  keep: number | null = null
+): Promise<PruneResult | null> {
+  try {
+    return await invoke("prune_versions", { keep: keep });
+  } catch (e) {
+    exceptionLog("Unable to prune versions", e);
+    toastStore.makeToast(describeError(e), "error");
+    return null;
+  }
+}
+
+export interface VersionDiskUsage {
+  versionFolder: string;
+  version: string;
+  bytes: number;
+  files: number;
+  measuredAt: string;
+}
+
+export async function getVersionDiskUsage(
+  versionFolder: string | null = null,
+  refresh: boolean = false
+): Promise<VersionDiskUsage[]> {
+  try {
+    return await invoke("get_version_disk_usage", {
+      versionFolder: versionFolder,
+      refresh: refresh,
+    });
+  } catch (e) {
+    exceptionLog("Unable to get version disk usage", e);
+    return [];
+  }
+}
+
+export interface DedupSavings {
+  files: number;
+  bytes: number;
+}
+
+export async function deduplicateVersions(): Promise<DedupSavings | null> {
+  try {
+    return await invoke("deduplicate_versions", {});
+  } catch (e) {
+    exceptionLog("Unable to deduplicate versions", e);
+    toastStore.makeToast(describeError(e), "error");
+    return null;
+  }
+}
+
+export async function getVersionDedupSavings(): Promise<DedupSavings | null> {
+  try {
+    return await invoke("get_version_dedup_savings", {});
+  } catch (e) {
+    exceptionLog("Unable to get version deduplication savings", e);
+    return null;
+  }
+}
+
 export async function openVersionFolder(folder: VersionFolders) {
   try {
     return await invoke("go_to_version_folder", { versionFolder: folder });
@@ -59,6 +598,85 @@ export async function openVersionFolder(folder: VersionFolders) {
   }
 }
 
+export interface SharedVersionsStatus {
+  enabled: boolean;
+  path: string | null;
+  exists: boolean;
+  writable: boolean;
+}
+
+export async function getSharedVersionsStatus(): Promise<SharedVersionsStatus | null> {
+  try {
+    return await invoke("get_shared_versions_status", {});
+  } catch (e) {
+    exceptionLog("Unable to get shared versions status", e);
+    return null;
+  }
+}
+
+// With `adoptExisting` this user's versions are moved over, for every user to have
+export async function setSharedVersionsEnabled(
+  enabled: boolean,
+  adoptExisting: boolean = false
+): Promise<SharedVersionsStatus | null> {
+  try {
+    return await invoke("set_shared_versions_enabled", {
+      enabled: enabled,
+      adoptExisting: adoptExisting,
+    });
+  } catch (e) {
+    exceptionLog("Unable to change shared versions setting", e);
+    toastStore.makeToast(describeError(e), "error");
+    return null;
+  }
+}
+
+export async function getVersionFolderDirs(): Promise<Record<
+  string,
+  string
+> | null> {
+  try {
+    return await invoke("get_version_folder_dirs", {});
+  } catch (e) {
+    exceptionLog("Unable to get version folder locations", e);
+    return null;
+  }
+}
+
+export async function setVersionFolderDir(
+  versionFolder: String,
+  dir: String | null
+): Promise<boolean> {
+  try {
+    await invoke("set_version_folder_dir", {
+      versionFolder: versionFolder,
+      dir: dir,
+    });
+  } catch (e) {
+    exceptionLog("Unable to set version folder location", e);
+    toastStore.makeToast(describeError(e), "error");
+    return false;
+  }
+  return true;
+}
+
+export async function migrateVersionFolder(
+  versionFolder: String,
+  dir: String | null
+): Promise<boolean> {
+  try {
+    await invoke("migrate_version_folder", {
+      versionFolder: versionFolder,
+      dir: dir,
+    });
+  } catch (e) {
+    exceptionLog("Unable to move version folder", e);
+    toastStore.makeToast(describeError(e), "error");
+    return false;
+  }
+  return true;
+}
+
 export async function getActiveVersion(): Promise<string | null> {
   try {
     return await invoke("get_active_tooling_version", {});
@@ -85,3 +703,237 @@ export async function ensureActiveVersionStillExists(): Promise<boolean> {
     return false;
   }
 }
+
+export interface BinaryBuildInfo {
+  rawOutput: string;
+  reportedVersion: string | null;
+  buildHash: string | null;
+  buildDate: string | null;
+}
+
+export interface ArchiveVerification {
+  sha256: string;
+  verified: boolean;
+}
+
+export interface VersionMetadata {
+  extractor: BinaryBuildInfo | null;
+  gk: BinaryBuildInfo | null;
+  localBuild: string | null;
+  archive: ArchiveVerification | null;
+  signed: boolean | null;
+}
+
+export async function getActiveToolingBuildInfo(): Promise<VersionMetadata | null> {
+  try {
+    return await invoke("get_active_tooling_build_info", {});
+  } catch (e) {
+    exceptionLog("Unable to get build info of the active version", e);
+    return null;
+  }
+}
+
+export type TrustLevel = "official" | "trusted" | "untrusted";
+
+export interface VersionSource {
+  folder: string;
+  displayName: string;
+  remote: string | null;
+  trustLevel: TrustLevel;
+  downloadUrlTemplate: string | null;
+}
+
+export async function listVersionSources(): Promise<VersionSource[]> {
+  try {
+    return await invoke("list_version_sources", {});
+  } catch (e) {
+    exceptionLog("Unable to list version sources", e);
+    return [];
+  }
+}
+
+export async function addVersionSource(
+  source: VersionSource
+): Promise<boolean> {
+  try {
+    await invoke("add_version_source", { source: source });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to add version source", e);
+    toastStore.makeToast(describeError(e), "error");
+    return false;
+  }
+}
+
+export async function updateVersionSource(
+  source: VersionSource
+): Promise<boolean> {
+  try {
+    await invoke("update_version_source", { source: source });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to update version source", e);
+    toastStore.makeToast(describeError(e), "error");
+    return false;
+  }
+}
+
+export async function removeVersionSource(
+  versionFolder: string
+): Promise<boolean> {
+  try {
+    await invoke("remove_version_source", { versionFolder: versionFolder });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to remove version source", e);
+    toastStore.makeToast(describeError(e), "error");
+    return false;
+  }
+}
+
+export async function getVersionDownloadUrl(
+  versionFolder: string,
+  version: string
+): Promise<string | null> {
+  try {
+    return await invoke("get_version_download_url", {
+      versionFolder: versionFolder,
+      version: version,
+    });
+  } catch (e) {
+    exceptionLog("Unable to get version download URL", e);
+    return null;
+  }
+}
+
+// Registers a jak-project build as a version in the "local" folder, linking its
+// data unless `copy` is set
+export async function registerLocalBuild(
+  path: string,
+  name: string,
+  copy: boolean = false
+): Promise<boolean> {
+  try {
+    await invoke("register_local_build", {
+      path: path,
+      name: name,
+      copy: copy,
+    });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to register local build", e);
+    toastStore.makeToast(describeError(e), "error");
+    return false;
+  }
+}
+
+export async function watchLocalBuild(
+  name: string,
+  autoRestart: boolean = false
+): Promise<boolean> {
+  try {
+    await invoke("watch_local_build", { name: name, autoRestart: autoRestart });
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to watch local build", e);
+    toastStore.makeToast(describeError(e), "error");
+    return false;
+  }
+}
+
+export async function unwatchLocalBuild(name: string): Promise<boolean> {
+  try {
+    return await invoke("unwatch_local_build", { name: name });
+  } catch (e) {
+    exceptionLog("Unable to stop watching local build", e);
+    return false;
+  }
+}
+
+export async function listWatchedLocalBuilds(): Promise<string[]> {
+  try {
+    return await invoke("list_watched_local_builds", {});
+  } catch (e) {
+    exceptionLog("Unable to list watched local builds", e);
+    return [];
+  }
+}
+
+export interface PreparedVersionSwitch {
+  versionFolder: VersionFolders;
+  version: string;
+  games: string[];
+}
+
+export async function prepareVersionSwitch(
+  versionFolder: VersionFolders,
+  version: string
+): Promise<PreparedVersionSwitch | null> {
+  try {
+    return await invoke("prepare_version_switch", {
+      versionFolder: versionFolder,
+      version: version,
+    });
+  } catch (e) {
+    exceptionLog("Unable to prepare version switch", e);
+    toastStore.makeToast(describeError(e), "error");
+    return null;
+  }
+}
+
+export async function commitVersionSwitch(): Promise<boolean> {
+  try {
+    await invoke("commit_version_switch", {});
+    return true;
+  } catch (e) {
+    exceptionLog("Unable to commit version switch", e);
+    toastStore.makeToast(describeError(e), "error");
+    return false;
+  }
+}
+
+export async function cancelVersionSwitch(): Promise<void> {
+  try {
+    await invoke("cancel_version_switch", {});
+  } catch (e) {
+    exceptionLog("Unable to cancel version switch", e);
+  }
+}
+
+export async function getPreparedVersionSwitch(): Promise<
+  PreparedVersionSwitch | null
+> {
+  try {
+    return await invoke("get_prepared_version_switch", {});
+  } catch (e) {
+    exceptionLog("Unable to get prepared version switch", e);
+    return null;
+  }
+}
+
+export interface VersionRollback {
+  versionFolder: string;
+  version: string;
+  rolledBackFrom: string | null;
+  reason: string | null;
+}
+
+// Switches back to the version that was active before the current one
+export async function rollbackActiveVersion(): Promise<VersionRollback | null> {
+  try {
+    return await invoke("rollback_active_version", {});
+  } catch (e) {
+    exceptionLog("Unable to roll back the active version", e);
+    toastStore.makeToast(describeError(e), "error");
+    return null;
+  }
+}
+
+// Emitted when the launcher rolls back by itself, `reason` says why
+export async function onActiveVersionRolledBack(
+  callback: (rollback: VersionRollback) => void
+): Promise<UnlistenFn> {
+  return await listen<VersionRollback>("activeVersionRolledBack", (event) =>
+    callback(event.payload)
+  );
+}
@@ -23,3 +23,23 @@ export async function openMainWindow(): Promise<boolean> {
   }
   return false;
 }
+
+export interface MonitorInfo {
+  index: number;
+  name: string | null;
+  width: number;
+  height: number;
+  x: number;
+  y: number;
+  scaleFactor: number;
+  isPrimary: boolean;
+}
+
+export async function getMonitors(): Promise<MonitorInfo[]> {
+  try {
+    return await invoke("get_monitors", {});
+  } catch (e) {
+    exceptionLog("Unable to list monitors", e);
+    return [];
+  }
+}
@@ -1,4 +1,10 @@
 import { platform } from "@tauri-apps/api/os";
+import type { ReleaseChannel } from "$lib/rpc/config";
+import {
+  getVersionDownloadUrl,
+  listChannelReleases,
+  type CachedRelease,
+} from "$lib/rpc/versions";
 
 export interface ReleaseInfo {
   releaseType: "official" | "unofficial" | "devel";
@@ -6,75 +12,70 @@ export interface ReleaseInfo {
   date: string | undefined;
   githubLink: string | undefined;
   downloadUrl: string | undefined;
+  channel?: ReleaseChannel;
   isDownloaded: boolean;
   pendingAction: boolean;
 }
 
-async function getDownloadLinkForCurrentPlatform(
-  release
+// Older releases named their assets differently than the backend's templates expect
+async function getLegacyDownloadLink(
+  release: CachedRelease
 ): Promise<string | undefined> {
   const platformName = await platform();
   for (const asset of release.assets) {
     if (platformName === "darwin" && asset.name.includes("opengoal-macos-v")) {
-      return asset.browser_download_url;
+      return asset.downloadUrl;
     } else if (
       platformName === "win32" &&
-      (asset.name.startsWith("opengoal-windows-v") ||
-        (asset.name.startsWith("opengoal-v") && asset.name.includes("windows")))
+      asset.name.startsWith("opengoal-v") &&
+      asset.name.includes("windows")
     ) {
-      return asset.browser_download_url;
+      return asset.downloadUrl;
     } else if (
       platformName === "linux" &&
-      (asset.name.startsWith("opengoal-linux-v") ||
-        (asset.name.startsWith("opengoal-v") && asset.name.includes("linux")))
+      asset.name.startsWith("opengoal-v") &&
+      asset.name.includes("linux")
     ) {
-      return asset.browser_download_url;
+      return asset.downloadUrl;
     }
   }
   return undefined;
 }
 
-export async function listOfficialReleases(): Promise<ReleaseInfo[]> {
-  let releases = [];
-  // TODO - handle rate limiting
-  // TODO - long term - handle pagination (more than 100 releases)
-  // TODO - even longer term - extract this out into an API we control (avoid github rate limiting) -- will be needed for unofficial releases as well anyway
-  const resp = await fetch(
-    "https://api.github.com/repos/open-goal/jak-project/releases?per_page=100"
-  );
-  // TODO - handle error
-  const githubReleases = await resp.json();
-
-  for (const release of githubReleases) {
-    releases.push({
-      releaseType: "official",
-      version: release.tag_name,
-      date: release.published_at,
-      githubLink: release.html_url,
-      downloadUrl: await getDownloadLinkForCurrentPlatform(release),
-      isDownloaded: false,
-      pendingAction: false,
-    });
+// The URL comes from the backend's template for official versions, so a change in how upstream
+// names its assets can be followed without a launcher release
+async function getDownloadLinkForCurrentPlatform(
+  release: CachedRelease
+): Promise<string | undefined> {
+  const url = await getVersionDownloadUrl("official", release.tag);
+  if (
+    url !== null &&
+    release.assets.some((asset) => asset.downloadUrl === url)
+  ) {
+    return url;
   }
-
-  return releases.sort((a, b) => b.date.localeCompare(a.date));
+  return await getLegacyDownloadLink(release);
 }
 
-export async function getLatestOfficialRelease(): Promise<ReleaseInfo> {
-  // TODO - handle rate limiting
-  // TODO - even longer term - extract this out into an API we control (avoid github rate limiting) -- will be needed for unofficial releases as well anyway
-  const resp = await fetch(
-    "https://api.github.com/repos/open-goal/jak-project/releases/latest"
-  );
-  // TODO - handle error
-  const githubRelease = await resp.json();
+async function toReleaseInfo(release: CachedRelease): Promise<ReleaseInfo> {
   return {
     releaseType: "official",
-    version: githubRelease.tag_name,
-    date: githubRelease.published_at,
-    githubLink: githubRelease.html_url,
-    downloadUrl: await getDownloadLinkForCurrentPlatform(githubRelease),
+    version: release.tag,
+    date: release.publishedAt ?? undefined,
+    githubLink: release.githubLink ?? undefined,
+    downloadUrl: await getDownloadLinkForCurrentPlatform(release),
+    channel: release.channel,
     isDownloaded: false,
     pendingAction: false,
   };
 }
+
+export async function listOfficialReleases(): Promise<ReleaseInfo[]> {
+  let releases = [];
+  // TODO - extract this out into an API we control (avoid github rate limiting) -- will be needed for unofficial releases as well anyway
+  for (const release of await listChannelReleases()) {
+    releases.push(await toReleaseInfo(release));
+  }
+
+  return releases.sort((a, b) => (b.date ?? "").localeCompare(a.date ?? ""));
+}